@@ -1,6 +1,8 @@
 use pyo3::prelude::*;
 use pattern_engine::run_replay as rust_run_replay;
 use pattern_engine::run_replay_publish as rust_run_replay_publish;
+use pattern_engine::run_replay_detect as rust_run_replay_detect;
+use pattern_engine::ReplaySpeed;
 
 /// Call the library run_replay function and return the processed row count.
 #[pyfunction]
@@ -13,19 +15,42 @@ fn run_replay(py: Python, ticks_csv: Option<String>) -> PyResult<i32> {
     })
 }
 
+/// Parse a replay speed (`max`, `realtime`, `10x`); unpaced when None.
+fn replay_speed(speed: Option<String>) -> PyResult<ReplaySpeed> {
+    match speed {
+        Some(s) => s.parse().map_err(|e| pyo3::exceptions::PyValueError::new_err(format!("{}", e))),
+        None => Ok(ReplaySpeed::default()),
+    }
+}
+
 #[pyfunction]
-fn run_replay_publish(py: Python, ticks_csv: Option<String>, redis_url: Option<String>) -> PyResult<i32> {
+fn run_replay_publish(py: Python, ticks_csv: Option<String>, redis_url: Option<String>, speed: Option<String>) -> PyResult<i32> {
+    let speed = replay_speed(speed)?;
     py.allow_threads(|| {
-        match rust_run_replay_publish(ticks_csv.as_deref(), redis_url.as_deref()) {
+        match rust_run_replay_publish(ticks_csv.as_deref(), redis_url.as_deref(), speed) {
             Ok(n) => Ok(n),
             Err(e) => Err(pyo3::exceptions::PyRuntimeError::new_err(format!("replay publish error: {}", e))),
         }
     })
 }
 
+/// Run the engine's detection pipeline over a ticks file, publishing the
+/// signals when a redis url is given, and return the signal count.
+#[pyfunction]
+fn run_replay_detect(py: Python, ticks_csv: Option<String>, redis_url: Option<String>, speed: Option<String>) -> PyResult<i32> {
+    let speed = replay_speed(speed)?;
+    py.allow_threads(|| {
+        match rust_run_replay_detect(ticks_csv.as_deref(), redis_url.as_deref(), speed) {
+            Ok(n) => Ok(n),
+            Err(e) => Err(pyo3::exceptions::PyRuntimeError::new_err(format!("replay detect error: {}", e))),
+        }
+    })
+}
+
 #[pymodule]
 fn pattern_engine_pyo3(_py: Python, m: &PyModule) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(run_replay, m)?)?;
     m.add_function(wrap_pyfunction!(run_replay_publish, m)?)?;
+    m.add_function(wrap_pyfunction!(run_replay_detect, m)?)?;
     Ok(())
 }
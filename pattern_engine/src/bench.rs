@@ -0,0 +1,191 @@
+//! Benchmark reporting and baseline comparison.
+//!
+//! The `bench` subcommand of the service binary measures detection latency and
+//! writes a [`BenchRun`] as JSON. A previous run can be loaded as a baseline and
+//! compared with per-metric regression thresholds so CI can fail on slowdowns.
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::Duration;
+
+/// Latency/throughput summary for one benchmark case
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BenchReport {
+    pub name: String,
+    pub iterations: u64,
+    pub mean_ns: f64,
+    pub p50_ns: f64,
+    pub p99_ns: f64,
+    pub max_ns: f64,
+    /// Iterations per second over the whole run
+    pub throughput_per_sec: f64,
+}
+
+impl BenchReport {
+    /// Build a report from per-iteration latencies (nanoseconds)
+    pub fn from_samples(name: &str, samples: &mut [u64], total: Duration) -> Self {
+        samples.sort_unstable();
+        let n = samples.len();
+        let pct = |q: f64| -> f64 {
+            if n == 0 {
+                0.0
+            } else {
+                samples[((n - 1) as f64 * q).round() as usize] as f64
+            }
+        };
+        let mean = if n == 0 { 0.0 } else { samples.iter().sum::<u64>() as f64 / n as f64 };
+        let secs = total.as_secs_f64();
+        Self {
+            name: name.to_string(),
+            iterations: n as u64,
+            mean_ns: mean,
+            p50_ns: pct(0.50),
+            p99_ns: pct(0.99),
+            max_ns: samples.last().copied().unwrap_or(0) as f64,
+            throughput_per_sec: if secs > 0.0 { n as f64 / secs } else { 0.0 },
+        }
+    }
+}
+
+/// A complete benchmark run as written to disk
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BenchRun {
+    /// Unix timestamp of the run
+    pub timestamp: f64,
+    pub reports: Vec<BenchReport>,
+}
+
+impl BenchRun {
+    pub fn load(path: &Path) -> Result<Self> {
+        let data = std::fs::read_to_string(path).map_err(|e| anyhow!("failed to read {}: {}", path.display(), e))?;
+        Ok(serde_json::from_str(&data)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let data = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, data).map_err(|e| anyhow!("failed to write {}: {}", path.display(), e))
+    }
+}
+
+/// Maximum allowed relative regression per metric (0.10 = 10% worse)
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegressionThresholds {
+    pub mean: f64,
+    pub p50: f64,
+    pub p99: f64,
+    pub throughput: f64,
+}
+
+impl Default for RegressionThresholds {
+    fn default() -> Self {
+        Self {
+            mean: 0.10,
+            p50: 0.10,
+            p99: 0.10,
+            throughput: 0.10,
+        }
+    }
+}
+
+/// A metric that regressed beyond its threshold
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Regression {
+    pub case: String,
+    pub metric: &'static str,
+    pub baseline: f64,
+    pub current: f64,
+    /// Relative change in the "worse" direction (0.15 = 15% worse)
+    pub change: f64,
+    pub threshold: f64,
+}
+
+/// Compare every case of `current` with the same-named case in `baseline`.
+/// Cases missing from the baseline are skipped.
+pub fn compare(current: &BenchRun, baseline: &BenchRun, thresholds: &RegressionThresholds) -> Vec<Regression> {
+    let mut out = Vec::new();
+    for cur in &current.reports {
+        let Some(base) = baseline.reports.iter().find(|b| b.name == cur.name) else {
+            continue;
+        };
+        // latency metrics: higher is worse
+        for (metric, b, c, th) in [
+            ("mean_ns", base.mean_ns, cur.mean_ns, thresholds.mean),
+            ("p50_ns", base.p50_ns, cur.p50_ns, thresholds.p50),
+            ("p99_ns", base.p99_ns, cur.p99_ns, thresholds.p99),
+        ] {
+            if b > 0.0 {
+                let change = (c - b) / b;
+                if change > th {
+                    out.push(Regression { case: cur.name.clone(), metric, baseline: b, current: c, change, threshold: th });
+                }
+            }
+        }
+        // throughput: lower is worse
+        let (b, c) = (base.throughput_per_sec, cur.throughput_per_sec);
+        if b > 0.0 {
+            let change = (b - c) / b;
+            if change > thresholds.throughput {
+                out.push(Regression {
+                    case: cur.name.clone(),
+                    metric: "throughput_per_sec",
+                    baseline: b,
+                    current: c,
+                    change,
+                    threshold: thresholds.throughput,
+                });
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(p99: f64, throughput: f64) -> BenchReport {
+        BenchReport {
+            name: "detect".to_string(),
+            iterations: 100,
+            mean_ns: 1000.0,
+            p50_ns: 900.0,
+            p99_ns: p99,
+            max_ns: p99 * 2.0,
+            throughput_per_sec: throughput,
+        }
+    }
+
+    #[test]
+    fn test_report_from_samples() {
+        let mut samples: Vec<u64> = (1..=100).collect();
+        let r = BenchReport::from_samples("x", &mut samples, Duration::from_secs(1));
+        assert_eq!(r.iterations, 100);
+        assert_eq!(r.p50_ns, 51.0);
+        assert_eq!(r.p99_ns, 99.0);
+        assert_eq!(r.max_ns, 100.0);
+        assert_eq!(r.throughput_per_sec, 100.0);
+    }
+
+    #[test]
+    fn test_compare_flags_regressions() {
+        let baseline = BenchRun { timestamp: 0.0, reports: vec![report(2000.0, 1e6)] };
+        let ok = BenchRun { timestamp: 1.0, reports: vec![report(2100.0, 0.95e6)] };
+        assert!(compare(&ok, &baseline, &RegressionThresholds::default()).is_empty());
+
+        let slow = BenchRun { timestamp: 1.0, reports: vec![report(2500.0, 0.5e6)] };
+        let regs = compare(&slow, &baseline, &RegressionThresholds::default());
+        let metrics: Vec<&str> = regs.iter().map(|r| r.metric).collect();
+        assert_eq!(metrics, vec!["p99_ns", "throughput_per_sec"]);
+        assert!((regs[0].change - 0.25).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_roundtrip_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bench.json");
+        let run = BenchRun { timestamp: 5.0, reports: vec![report(1.0, 2.0)] };
+        run.save(&path).unwrap();
+        assert_eq!(BenchRun::load(&path).unwrap(), run);
+    }
+}
@@ -0,0 +1,318 @@
+//! Market breadth across groups of symbols.
+//!
+//! Each symbol keeps a session VWAP and session open (daily UTC sessions).
+//! Every `sample_secs` a group is sampled: the share of its members trading
+//! above VWAP and the advance/decline balance against the session open. The
+//! share above VWAP is smoothed with an EMA, and a breadth thrust fires when
+//! the EMA climbs from at most `thrust_low` to at least `thrust_high` within
+//! `thrust_window` samples, the intraday analogue of a Zweig thrust. A
+//! breadth washout is the mirror image. Either re-arms once the EMA is back
+//! on the other side of its starting level.
+
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+
+use crate::incremental::{EMA, VWAP};
+use crate::publisher::Signal;
+use crate::signal_id;
+
+/// Pattern name of bullish breadth thrusts
+pub const BREADTH_THRUST: &str = "breadth_thrust";
+/// Pattern name of bearish breadth washouts
+pub const BREADTH_WASHOUT: &str = "breadth_washout";
+
+const SESSION_SECS: f64 = 86_400.0;
+
+/// A named set of symbols sampled together
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BreadthGroup {
+    pub name: String,
+    /// Members, or None for every symbol seen
+    pub members: Option<BTreeSet<String>>,
+}
+
+impl BreadthGroup {
+    /// Parse `NAME=SYM|SYM|...,NAME=*,...`; `*` tracks every symbol
+    pub fn parse_list(spec: &str) -> Result<Vec<BreadthGroup>> {
+        let mut groups: Vec<BreadthGroup> = Vec::new();
+        for item in spec.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            let (name, members) = item
+                .split_once('=')
+                .map(|(n, m)| (n.trim(), m.trim()))
+                .filter(|(n, m)| !n.is_empty() && !m.is_empty())
+                .ok_or_else(|| anyhow!("invalid breadth group '{}': expected NAME=SYM|SYM or NAME=*", item))?;
+            if groups.iter().any(|g| g.name == name) {
+                return Err(anyhow!("duplicate breadth group '{}'", name));
+            }
+            let members = (members != "*").then(|| {
+                members.split('|').map(str::trim).filter(|s| !s.is_empty()).map(str::to_string).collect()
+            });
+            groups.push(BreadthGroup { name: name.to_string(), members });
+        }
+        Ok(groups)
+    }
+
+    pub fn contains(&self, symbol: &str) -> bool {
+        self.members.as_ref().is_none_or(|m| m.contains(symbol))
+    }
+}
+
+/// Sampling and thrust thresholds for [`BreadthTracker`]
+#[derive(Debug, Clone)]
+pub struct BreadthConfig {
+    /// Seconds between samples of a group
+    pub sample_secs: f64,
+    /// EMA span over samples of the share above VWAP
+    pub ema_span: usize,
+    pub thrust_low: f64,
+    pub thrust_high: f64,
+    /// Samples within which the EMA has to cover the range
+    pub thrust_window: usize,
+    /// Members with a price this session needed before a group is sampled
+    pub min_members: usize,
+}
+
+impl Default for BreadthConfig {
+    fn default() -> Self {
+        Self { sample_secs: 60.0, ema_span: 10, thrust_low: 0.4, thrust_high: 0.615, thrust_window: 10, min_members: 5 }
+    }
+}
+
+/// Latest sample of a group
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct BreadthSnapshot {
+    /// Members with a price this session
+    pub members: usize,
+    pub pct_above_vwap: f64,
+    /// (advancing - declining) / members against the session open
+    pub advance_decline: f64,
+    /// Smoothed share above VWAP
+    pub ema: f64,
+    pub timestamp: f64,
+}
+
+#[derive(Debug, Clone)]
+struct SymbolBreadth {
+    session: i64,
+    open: f64,
+    price: f64,
+    vwap: VWAP,
+}
+
+#[derive(Debug, Clone)]
+struct GroupState {
+    bucket: Option<i64>,
+    ema: EMA,
+    /// Recent EMA values, newest last
+    recent: VecDeque<f64>,
+    thrust_armed: bool,
+    washout_armed: bool,
+    last: Option<BreadthSnapshot>,
+}
+
+/// Breadth of the configured groups
+#[derive(Debug, Clone, Default)]
+pub struct BreadthTracker {
+    config: BreadthConfig,
+    groups: Vec<(BreadthGroup, GroupState)>,
+    symbols: HashMap<String, SymbolBreadth>,
+}
+
+impl BreadthTracker {
+    pub fn new(groups: Vec<BreadthGroup>, config: BreadthConfig) -> Self {
+        let state = GroupState {
+            bucket: None,
+            ema: EMA::new(2.0 / (config.ema_span.max(1) as f64 + 1.0)),
+            recent: VecDeque::new(),
+            thrust_armed: true,
+            washout_armed: true,
+            last: None,
+        };
+        Self { groups: groups.into_iter().map(|g| (g, state.clone())).collect(), config, symbols: HashMap::new() }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    /// Record a trade and sample every group containing `symbol` that is due;
+    /// returns thrust and washout signals
+    pub fn update(&mut self, symbol: &str, price: f64, volume: f64, timestamp: f64) -> Vec<Signal> {
+        if price <= 0.0 || !self.groups.iter().any(|(g, _)| g.contains(symbol)) {
+            return Vec::new();
+        }
+        let session = (timestamp / SESSION_SECS).floor() as i64;
+        let entry = self.symbols.entry(symbol.to_string()).or_insert_with(|| SymbolBreadth {
+            session,
+            open: price,
+            price,
+            vwap: VWAP::new(),
+        });
+        if entry.session != session {
+            *entry = SymbolBreadth { session, open: price, price, vwap: VWAP::new() };
+        }
+        entry.price = price;
+        entry.vwap.update(price, volume);
+
+        let bucket = (timestamp / self.config.sample_secs.max(1e-3)).floor() as i64;
+        let mut signals = Vec::new();
+        for (group, state) in self.groups.iter_mut() {
+            if !group.contains(symbol) || state.bucket == Some(bucket) {
+                continue;
+            }
+            let Some((pct, ad, members)) = sample(group, &self.symbols, session) else {
+                continue;
+            };
+            if members < self.config.min_members {
+                continue;
+            }
+            state.bucket = Some(bucket);
+            let ema = state.ema.update(pct);
+            state.recent.push_back(ema);
+            if state.recent.len() > self.config.thrust_window.max(1) {
+                state.recent.pop_front();
+            }
+            let snapshot = BreadthSnapshot { members, pct_above_vwap: pct, advance_decline: ad, ema, timestamp };
+            state.last = Some(snapshot);
+
+            let (low, high) = (self.config.thrust_low, self.config.thrust_high);
+            let lowest = state.recent.iter().copied().fold(f64::INFINITY, f64::min);
+            let highest = state.recent.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+            if ema <= low {
+                state.thrust_armed = true;
+            }
+            if ema >= 1.0 - low {
+                state.washout_armed = true;
+            }
+            if state.thrust_armed && ema >= high && lowest <= low {
+                state.thrust_armed = false;
+                signals.push(breadth_signal(&group.name, BREADTH_THRUST, 0.8, &snapshot, lowest));
+            } else if state.washout_armed && ema <= 1.0 - high && highest >= 1.0 - low {
+                state.washout_armed = false;
+                signals.push(breadth_signal(&group.name, BREADTH_WASHOUT, -0.8, &snapshot, highest));
+            }
+        }
+        signals
+    }
+
+    /// Latest sample of each group, keyed by group name
+    pub fn snapshot(&self) -> BTreeMap<String, BreadthSnapshot> {
+        self.groups.iter().filter_map(|(g, s)| s.last.map(|snap| (g.name.clone(), snap))).collect()
+    }
+}
+
+/// Share above VWAP, advance/decline balance and member count for this session
+fn sample(group: &BreadthGroup, symbols: &HashMap<String, SymbolBreadth>, session: i64) -> Option<(f64, f64, usize)> {
+    let (mut members, mut above, mut advancing, mut declining) = (0usize, 0usize, 0usize, 0usize);
+    for (symbol, s) in symbols {
+        if s.session != session || !group.contains(symbol) {
+            continue;
+        }
+        members += 1;
+        if s.price > s.vwap.value() {
+            above += 1;
+        }
+        if s.price > s.open {
+            advancing += 1;
+        } else if s.price < s.open {
+            declining += 1;
+        }
+    }
+    (members > 0).then(|| {
+        let n = members as f64;
+        (above as f64 / n, (advancing as f64 - declining as f64) / n, members)
+    })
+}
+
+fn breadth_signal(group: &str, pattern: &str, score: f64, snapshot: &BreadthSnapshot, from: f64) -> Signal {
+    let details = serde_json::json!({
+        "group": group,
+        "members": snapshot.members,
+        "pct_above_vwap": snapshot.pct_above_vwap,
+        "advance_decline": snapshot.advance_decline,
+        "ema": snapshot.ema,
+        "from": from,
+    });
+    Signal {
+        id: signal_id::next_id(),
+        symbol: group.to_string(),
+        score,
+        pattern: pattern.to_string(),
+        timestamp: snapshot.timestamp,
+        meta: None,
+        pattern_meta: None,
+        status: None,
+        linked_id: None,
+        extra: HashMap::from([("breadth".to_string(), details)]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SYMBOLS: [&str; 5] = ["A", "B", "C", "D", "E"];
+
+    fn config() -> BreadthConfig {
+        BreadthConfig { sample_secs: 60.0, ema_span: 3, thrust_window: 6, min_members: 5, ..Default::default() }
+    }
+
+    /// One minute in which the first `up` symbols rally and the rest sell off
+    fn minute(tracker: &mut BreadthTracker, t: f64, up: usize, level: f64) -> Vec<Signal> {
+        let mut out = Vec::new();
+        for (i, s) in SYMBOLS.iter().enumerate() {
+            let price = if i < up { 100.0 + level } else { 100.0 - level };
+            out.extend(tracker.update(s, price, 10.0, t + i as f64));
+        }
+        out
+    }
+
+    #[test]
+    fn test_parse_groups() {
+        let groups = BreadthGroup::parse_list("tech=AAPL|MSFT, market=*").unwrap();
+        assert!(groups[0].contains("MSFT") && !groups[0].contains("XOM"));
+        assert!(groups[1].contains("XOM"));
+        assert!(BreadthGroup::parse_list("tech").is_err());
+        assert!(BreadthGroup::parse_list("a=*,a=X").is_err());
+    }
+
+    #[test]
+    fn test_thrust_fires_once() {
+        let mut tracker = BreadthTracker::new(BreadthGroup::parse_list("market=*").unwrap(), config());
+        let mut signals = Vec::new();
+        // weak tape, then everything rallies above VWAP
+        for m in 0..4 {
+            signals.extend(minute(&mut tracker, m as f64 * 60.0, 0, 1.0 + m as f64));
+        }
+        assert!(signals.is_empty());
+        assert!(tracker.snapshot()["market"].ema <= 0.4);
+        for m in 4..10 {
+            signals.extend(minute(&mut tracker, m as f64 * 60.0, 5, 10.0 + m as f64));
+        }
+        assert_eq!(signals.len(), 1);
+        assert_eq!((signals[0].symbol.as_str(), signals[0].pattern.as_str()), ("market", BREADTH_THRUST));
+        assert!(signals[0].extra["breadth"]["ema"].as_f64().unwrap() >= 0.615);
+        let snap = tracker.snapshot()["market"];
+        assert_eq!((snap.members, snap.advance_decline), (5, 1.0));
+    }
+
+    #[test]
+    fn test_washout_and_small_groups() {
+        let mut tracker = BreadthTracker::new(BreadthGroup::parse_list("market=*,pair=A|B").unwrap(), config());
+        let mut signals = Vec::new();
+        for m in 0..4 {
+            signals.extend(minute(&mut tracker, m as f64 * 60.0, 5, 1.0 + m as f64));
+        }
+        for m in 4..10 {
+            signals.extend(minute(&mut tracker, m as f64 * 60.0, 0, 10.0 + m as f64));
+        }
+        // the opening sample has every price at VWAP, so the rally itself thrusts first
+        let washouts: Vec<&Signal> = signals.iter().filter(|s| s.pattern == BREADTH_WASHOUT).collect();
+        assert_eq!(washouts.len(), 1);
+        assert!(washouts[0].score < 0.0);
+        assert_eq!(signals.last().unwrap().pattern, BREADTH_WASHOUT);
+        // two members never reach min_members
+        assert!(!tracker.snapshot().contains_key("pair"));
+    }
+}
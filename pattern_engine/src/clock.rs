@@ -0,0 +1,160 @@
+//! Timestamp source selection per feed.
+//!
+//! Ticks carry the exchange timestamp and, for live feeds, the local receive
+//! time. Each feed declares which of the two drives candle bucketing and
+//! cooldowns so replayed and live runs align on the same clock.
+//! [`EventClock`] carries that clock forward between ticks, so heartbeats for
+//! quiet symbols are timed and stamped on it rather than on wall-clock time.
+
+use crate::publisher::Tick;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// Which tick timestamp drives time-based logic
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimestampSource {
+    /// Exchange (event) timestamp carried in `Tick::timestamp`
+    #[default]
+    Exchange,
+    /// Local receive time in `Tick::received_at`, falling back to exchange time
+    Receive,
+}
+
+impl FromStr for TimestampSource {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "exchange" | "event" => Ok(TimestampSource::Exchange),
+            "receive" | "received" | "local" => Ok(TimestampSource::Receive),
+            other => Err(anyhow!("unknown timestamp source: {}", other)),
+        }
+    }
+}
+
+impl Tick {
+    /// Timestamp according to `source`
+    pub fn event_time(&self, source: TimestampSource) -> f64 {
+        match source {
+            TimestampSource::Exchange => self.timestamp,
+            TimestampSource::Receive => self.received_at.unwrap_or(self.timestamp),
+        }
+    }
+}
+
+/// Default timestamp source plus per-feed overrides
+#[derive(Debug, Clone, Default)]
+pub struct TimestampPolicy {
+    default: TimestampSource,
+    per_feed: HashMap<String, TimestampSource>,
+}
+
+impl TimestampPolicy {
+    pub fn new(default: TimestampSource) -> Self {
+        Self {
+            default,
+            per_feed: HashMap::new(),
+        }
+    }
+
+    /// Parse per-feed overrides from a `feed=source,feed=source` spec
+    pub fn with_feed_spec(mut self, spec: &str) -> Result<Self> {
+        for item in spec.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            let (feed, source) = item
+                .split_once('=')
+                .ok_or_else(|| anyhow!("expected feed=source, got '{}'", item))?;
+            self.per_feed.insert(feed.trim().to_string(), source.parse()?);
+        }
+        Ok(self)
+    }
+
+    /// Override the source for one feed
+    pub fn set_feed(&mut self, feed: &str, source: TimestampSource) {
+        self.per_feed.insert(feed.to_string(), source);
+    }
+
+    /// Source used for ticks from `feed` (or the default for untagged ticks)
+    pub fn source_for(&self, feed: Option<&str>) -> TimestampSource {
+        feed.and_then(|f| self.per_feed.get(f).copied()).unwrap_or(self.default)
+    }
+
+    /// Timestamp that should drive candle bucketing and cooldowns for `tick`
+    pub fn event_time(&self, tick: &Tick) -> f64 {
+        tick.event_time(self.source_for(tick.feed.as_deref()))
+    }
+}
+
+/// Current wall-clock time in Unix seconds
+pub fn wall_now() -> f64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64()
+}
+
+/// A feed's event clock, extrapolated from its last tick by the wall-clock
+/// time elapsed since that tick arrived
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct EventClock {
+    event: f64,
+    wall: f64,
+}
+
+impl EventClock {
+    /// Record a tick stamped `event` on the policy's clock, seen at `wall`
+    pub fn observe(&mut self, event: f64, wall: f64) {
+        self.event = event;
+        self.wall = wall;
+    }
+
+    /// Last observed event time
+    pub fn last(&self) -> f64 {
+        self.event
+    }
+
+    /// Event time now, `wall` being the current wall-clock time
+    pub fn now(&self, wall: f64) -> f64 {
+        self.event + (wall - self.wall).max(0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tick(feed: Option<&str>, received_at: Option<f64>) -> Tick {
+        Tick { received_at, feed: feed.map(str::to_string), ..Tick::test("AAPL", 100.0, 10.0) }
+    }
+
+    #[test]
+    fn test_policy_per_feed() {
+        let policy = TimestampPolicy::new(TimestampSource::Exchange)
+            .with_feed_spec("live=receive, replay=exchange")
+            .unwrap();
+        assert_eq!(policy.event_time(&tick(Some("live"), Some(10.25))), 10.25);
+        assert_eq!(policy.event_time(&tick(Some("replay"), Some(10.25))), 10.0);
+        assert_eq!(policy.event_time(&tick(None, Some(10.25))), 10.0);
+        // receive time missing falls back to exchange time
+        assert_eq!(policy.event_time(&tick(Some("live"), None)), 10.0);
+    }
+
+    #[test]
+    fn test_event_clock_extrapolates_from_last_tick() {
+        let mut clock = EventClock::default();
+        // exchange time lags the local clock by an hour
+        clock.observe(1_000.0, 4_600.0);
+        assert_eq!(clock.last(), 1_000.0);
+        assert_eq!(clock.now(4_630.0), 1_030.0);
+        // a wall clock stepping backwards never moves event time back
+        assert_eq!(clock.now(4_500.0), 1_000.0);
+    }
+
+    #[test]
+    fn test_bad_spec() {
+        assert!(TimestampPolicy::default().with_feed_spec("live").is_err());
+        assert!(TimestampPolicy::default().with_feed_spec("live=wallclock").is_err());
+    }
+}
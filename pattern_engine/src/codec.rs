@@ -0,0 +1,108 @@
+//! Optional zstd compression of large stream entry fields.
+//!
+//! Fields whose value is at least `min_bytes` long are zstd-compressed and
+//! base64-encoded, and a `codec` field names them: `zstd:data` for a JSON
+//! entry, `zstd:meta,pattern_meta` for a flat one. Entries without a `codec`
+//! field are read unchanged, so compressed and plain entries can share a
+//! stream and readers that decompress handle both.
+
+use anyhow::{anyhow, bail, Result};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use std::collections::HashMap;
+
+/// Name of the field listing compressed fields
+pub const CODEC_FIELD: &str = "codec";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Compression {
+    /// zstd level, 1 (fast) to 22
+    pub level: i32,
+    /// Fields shorter than this stay uncompressed
+    pub min_bytes: usize,
+}
+
+impl Default for Compression {
+    fn default() -> Self {
+        Self { level: 3, min_bytes: 512 }
+    }
+}
+
+impl Compression {
+    /// Compress the large values among `fields`, adding the `codec` field
+    /// when any were compressed
+    pub fn compress(&self, fields: Vec<(String, String)>) -> Result<Vec<(String, String)>> {
+        let mut compressed = Vec::new();
+        let mut out = Vec::with_capacity(fields.len() + 1);
+        for (name, value) in fields {
+            if value.len() < self.min_bytes {
+                out.push((name, value));
+                continue;
+            }
+            let packed = STANDARD.encode(zstd::encode_all(value.as_bytes(), self.level)?);
+            // base64 costs a third; keep values that barely compress as they are
+            if packed.len() >= value.len() {
+                out.push((name, value));
+                continue;
+            }
+            compressed.push(name.clone());
+            out.push((name, packed));
+        }
+        if !compressed.is_empty() {
+            out.push((CODEC_FIELD.to_string(), format!("zstd:{}", compressed.join(","))));
+        }
+        Ok(out)
+    }
+}
+
+/// Undo [`Compression::compress`]; entries without a `codec` field are
+/// returned as they are
+pub fn decompress(fields: &HashMap<String, String>) -> Result<HashMap<String, String>> {
+    let Some(codec) = fields.get(CODEC_FIELD) else {
+        return Ok(fields.clone());
+    };
+    let (algorithm, names) = codec.split_once(':').unwrap_or((codec.as_str(), ""));
+    if algorithm != "zstd" {
+        bail!("unsupported codec '{}'", codec);
+    }
+    let mut out = fields.clone();
+    out.remove(CODEC_FIELD);
+    for name in names.split(',').filter(|n| !n.is_empty()) {
+        let packed = out.get_mut(name).ok_or_else(|| anyhow!("codec names missing field {}", name))?;
+        let raw = zstd::decode_all(STANDARD.decode(packed.as_bytes())?.as_slice())?;
+        *packed = String::from_utf8(raw).map_err(|_| anyhow!("field {} is not UTF-8 after decompression", name))?;
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compresses_only_large_fields() {
+        let large = format!("[{}]", vec!["0.125"; 400].join(","));
+        let fields = vec![("id".to_string(), "AAPL_1".to_string()), ("meta".to_string(), large.clone())];
+        let packed = Compression::default().compress(fields.clone()).unwrap();
+        let map: HashMap<_, _> = packed.into_iter().collect();
+        assert_eq!(map["id"], "AAPL_1");
+        assert_eq!(map[CODEC_FIELD], "zstd:meta");
+        assert!(map["meta"].len() < large.len() / 4);
+
+        assert_eq!(decompress(&map).unwrap(), fields.into_iter().collect::<HashMap<_, _>>());
+    }
+
+    #[test]
+    fn test_plain_entries_pass_through() {
+        let fields = vec![("data".to_string(), "{}".to_string())];
+        assert_eq!(Compression::default().compress(fields.clone()).unwrap(), fields);
+        let map: HashMap<_, _> = fields.into_iter().collect();
+        assert_eq!(decompress(&map).unwrap(), map);
+
+        let mut bad = map.clone();
+        bad.insert(CODEC_FIELD.to_string(), "lz4:data".to_string());
+        assert!(decompress(&bad).is_err());
+        bad.insert(CODEC_FIELD.to_string(), "zstd:data".to_string());
+        assert!(decompress(&bad).is_err());
+    }
+}
@@ -0,0 +1,370 @@
+//! Consumer type generation from the signal JSON schema.
+//!
+//! The schema is derived from the Rust `Signal` definition (and everything it
+//! embeds) with schemars, then rendered as Python dataclasses or TypeScript
+//! interfaces so downstream consumers track the wire format.
+
+use crate::publisher::Signal;
+use anyhow::{anyhow, Result};
+use serde_json::{Map, Value};
+use std::fmt::Write;
+use std::str::FromStr;
+
+/// Output flavour for [`generate`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    /// Raw JSON schema
+    Schema,
+    Python,
+    TypeScript,
+}
+
+impl FromStr for Language {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "schema" | "json" | "jsonschema" => Ok(Language::Schema),
+            "python" | "py" => Ok(Language::Python),
+            "typescript" | "ts" => Ok(Language::TypeScript),
+            other => Err(anyhow!("unknown codegen language: {}", other)),
+        }
+    }
+}
+
+/// JSON schema for published signals
+pub fn signal_schema() -> Value {
+    serde_json::to_value(schemars::schema_for!(Signal)).expect("schema serializes")
+}
+
+/// Render consumer types for `lang`
+pub fn generate(lang: Language) -> Result<String> {
+    let schema = signal_schema();
+    match lang {
+        Language::Schema => Ok(serde_json::to_string_pretty(&schema)? + "\n"),
+        Language::Python => Ok(python(&collect_types(&schema)?)),
+        Language::TypeScript => Ok(typescript(&collect_types(&schema)?)),
+    }
+}
+
+/// Field type reduced to what the emitters need
+#[derive(Debug, Clone, PartialEq)]
+enum FieldType {
+    String,
+    Integer,
+    Number,
+    Boolean,
+    Ref(String),
+    /// Reference to a string enum type
+    Literal(String),
+    Array(Box<FieldType>),
+    /// String-keyed object with uniform values
+    Map(Box<FieldType>),
+    Any,
+}
+
+#[derive(Debug)]
+struct Field {
+    name: String,
+    ty: FieldType,
+    nullable: bool,
+    required: bool,
+    doc: Option<String>,
+}
+
+#[derive(Debug)]
+struct TypeDef {
+    name: String,
+    doc: Option<String>,
+    fields: Vec<Field>,
+    /// Allowed values when the type is a string enum
+    variants: Vec<String>,
+}
+
+/// Flatten the root schema and its definitions into object types, root last
+fn collect_types(schema: &Value) -> Result<Vec<TypeDef>> {
+    let mut types = Vec::new();
+    if let Some(defs) = schema.get("definitions").and_then(Value::as_object) {
+        for (name, def) in defs {
+            types.push(type_def(name, def)?);
+        }
+    }
+    let root = schema
+        .get("title")
+        .and_then(Value::as_str)
+        .ok_or_else(|| anyhow!("root schema has no title"))?;
+    types.push(type_def(root, schema)?);
+
+    // Point references to string enums at their literal type
+    let enums: Vec<String> = types.iter().filter(|t| !t.variants.is_empty()).map(|t| t.name.clone()).collect();
+    for field in types.iter_mut().flat_map(|t| t.fields.iter_mut()) {
+        retarget_enums(&mut field.ty, &enums);
+    }
+    Ok(types)
+}
+
+fn retarget_enums(ty: &mut FieldType, enums: &[String]) {
+    match ty {
+        FieldType::Ref(name) if enums.contains(name) => *ty = FieldType::Literal(name.clone()),
+        FieldType::Array(inner) | FieldType::Map(inner) => retarget_enums(inner, enums),
+        _ => {}
+    }
+}
+
+/// String values of a unit enum schema (`enum`, or `oneOf` single-value variants)
+fn enum_variants(schema: &Value) -> Vec<String> {
+    let values = |s: &Value| -> Vec<String> {
+        s.get("enum")
+            .and_then(Value::as_array)
+            .map(|e| e.iter().filter_map(Value::as_str).map(str::to_string).collect())
+            .unwrap_or_default()
+    };
+    match schema.get("oneOf").and_then(Value::as_array) {
+        Some(variants) => variants.iter().flat_map(values).collect(),
+        None => values(schema),
+    }
+}
+
+fn type_def(name: &str, schema: &Value) -> Result<TypeDef> {
+    let empty = Map::new();
+    let props = schema.get("properties").and_then(Value::as_object).unwrap_or(&empty);
+    let required: Vec<&str> = schema
+        .get("required")
+        .and_then(Value::as_array)
+        .map(|r| r.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+
+    let fields = props
+        .iter()
+        .map(|(field, prop)| {
+            let (ty, nullable) = field_type(prop)?;
+            Ok(Field {
+                name: field.clone(),
+                ty,
+                nullable,
+                required: required.contains(&field.as_str()),
+                doc: doc(prop),
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(TypeDef {
+        name: name.to_string(),
+        doc: doc(schema),
+        fields,
+        variants: enum_variants(schema),
+    })
+}
+
+fn doc(schema: &Value) -> Option<String> {
+    schema.get("description").and_then(Value::as_str).map(|d| d.replace('`', ""))
+}
+
+/// Resolve a property schema into a type and whether it accepts null
+fn field_type(prop: &Value) -> Result<(FieldType, bool)> {
+    if let Some(r) = prop.get("$ref").and_then(Value::as_str) {
+        let name = r.rsplit('/').next().unwrap_or(r);
+        return Ok((FieldType::Ref(name.to_string()), false));
+    }
+    // A documented reference is wrapped as `allOf: [{$ref}]`
+    if let Some([inner]) = prop.get("allOf").and_then(Value::as_array).map(Vec::as_slice) {
+        return field_type(inner);
+    }
+    if let Some(variants) = prop.get("anyOf").and_then(Value::as_array) {
+        let nullable = variants.iter().any(|v| v.get("type").and_then(Value::as_str) == Some("null"));
+        let inner = variants
+            .iter()
+            .find(|v| v.get("type").and_then(Value::as_str) != Some("null"))
+            .ok_or_else(|| anyhow!("anyOf without a non-null variant"))?;
+        let (ty, inner_nullable) = field_type(inner)?;
+        return Ok((ty, nullable || inner_nullable));
+    }
+
+    let (name, nullable) = match prop.get("type") {
+        Some(Value::String(t)) => (t.as_str(), false),
+        Some(Value::Array(ts)) => {
+            let mut names = ts.iter().filter_map(Value::as_str);
+            let nullable = ts.iter().any(|t| t.as_str() == Some("null"));
+            (names.find(|t| *t != "null").unwrap_or("null"), nullable)
+        }
+        _ => return Ok((FieldType::Any, true)),
+    };
+    let ty = match name {
+        "string" => FieldType::String,
+        "integer" => FieldType::Integer,
+        "number" => FieldType::Number,
+        "boolean" => FieldType::Boolean,
+        "array" => {
+            let items = prop.get("items").ok_or_else(|| anyhow!("array without items"))?;
+            FieldType::Array(Box::new(field_type(items)?.0))
+        }
+        "object" if prop.get("properties").is_none() => match prop.get("additionalProperties") {
+            Some(values @ Value::Object(m)) if !m.is_empty() => FieldType::Map(Box::new(field_type(values)?.0)),
+            _ => FieldType::Map(Box::new(FieldType::Any)),
+        },
+        _ => FieldType::Any,
+    };
+    Ok((ty, nullable))
+}
+
+const HEADER: &str = "Generated by `pattern_engine codegen` from the Rust Signal definition. Do not edit.";
+
+fn py_type(ty: &FieldType) -> String {
+    match ty {
+        FieldType::String => "str".to_string(),
+        FieldType::Integer => "int".to_string(),
+        FieldType::Number => "float".to_string(),
+        FieldType::Boolean => "bool".to_string(),
+        FieldType::Ref(name) | FieldType::Literal(name) => name.clone(),
+        FieldType::Array(inner) => format!("List[{}]", py_type(inner)),
+        FieldType::Map(inner) => format!("Dict[str, {}]", py_type(inner)),
+        FieldType::Any => "Any".to_string(),
+    }
+}
+
+/// Expression converting `raw` (a JSON-decoded value) into the field type
+fn py_convert(ty: &FieldType, raw: &str) -> String {
+    match ty {
+        FieldType::Ref(name) => format!("{}.from_dict({})", name, raw),
+        FieldType::Array(inner) if matches!(**inner, FieldType::Ref(_)) => {
+            format!("[{} for x in {}]", py_convert(inner, "x"), raw)
+        }
+        _ => raw.to_string(),
+    }
+}
+
+fn python(types: &[TypeDef]) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "\"\"\"{}\"\"\"\n", HEADER);
+    out.push_str("from __future__ import annotations\n\n");
+    out.push_str("from dataclasses import dataclass, field\n");
+    out.push_str("from typing import Any, Dict, List, Literal, Optional\n");
+
+    for t in types.iter().filter(|t| !t.variants.is_empty()) {
+        let values: Vec<String> = t.variants.iter().map(|v| format!("{:?}", v)).collect();
+        out.push('\n');
+        if let Some(d) = &t.doc {
+            let _ = writeln!(out, "# {}", d);
+        }
+        let _ = writeln!(out, "{} = Literal[{}]", t.name, values.join(", "));
+    }
+
+    for t in types.iter().filter(|t| t.variants.is_empty()) {
+        let _ = write!(out, "\n\n@dataclass\nclass {}:\n", t.name);
+        if let Some(d) = &t.doc {
+            let _ = writeln!(out, "    \"\"\"{}\"\"\"\n", d);
+        }
+        // Dataclasses need fields without defaults first
+        let (required, optional): (Vec<&Field>, Vec<&Field>) = t.fields.iter().partition(|f| f.required && !f.nullable);
+        for f in required.iter().chain(optional.iter()) {
+            if let Some(d) = &f.doc {
+                let _ = writeln!(out, "    # {}", d);
+            }
+            let ty = py_type(&f.ty);
+            let decl = if f.required && !f.nullable {
+                ty
+            } else if matches!(f.ty, FieldType::Array(_)) && !f.nullable {
+                format!("{} = field(default_factory=list)", ty)
+            } else if matches!(f.ty, FieldType::Map(_)) && !f.nullable {
+                format!("{} = field(default_factory=dict)", ty)
+            } else if f.ty == FieldType::Boolean && !f.nullable {
+                format!("{} = False", ty)
+            } else {
+                format!("Optional[{}] = None", ty)
+            };
+            let _ = writeln!(out, "    {}: {}", f.name, decl);
+        }
+
+        let _ = write!(out, "\n    @classmethod\n    def from_dict(cls, d: Dict[str, Any]) -> \"{}\":\n        return cls(\n", t.name);
+        for f in required.iter().chain(optional.iter()) {
+            let key = format!("d[{:?}]", f.name);
+            let value = if f.required && !f.nullable {
+                py_convert(&f.ty, &key)
+            } else if matches!(f.ty, FieldType::Array(_)) && !f.nullable {
+                py_convert(&f.ty, &format!("d.get({:?}) or []", f.name))
+            } else if matches!(f.ty, FieldType::Map(_)) && !f.nullable {
+                format!("d.get({:?}) or {{}}", f.name)
+            } else if f.ty == FieldType::Boolean && !f.nullable {
+                format!("d.get({:?}, False)", f.name)
+            } else if matches!(f.ty, FieldType::Ref(_)) {
+                format!("{} if d.get({:?}) is not None else None", py_convert(&f.ty, &key), f.name)
+            } else {
+                format!("d.get({:?})", f.name)
+            };
+            let _ = writeln!(out, "            {}={},", f.name, value);
+        }
+        out.push_str("        )\n");
+    }
+    out
+}
+
+fn ts_type(ty: &FieldType) -> String {
+    match ty {
+        FieldType::String => "string".to_string(),
+        FieldType::Integer | FieldType::Number => "number".to_string(),
+        FieldType::Boolean => "boolean".to_string(),
+        FieldType::Ref(name) | FieldType::Literal(name) => name.clone(),
+        FieldType::Array(inner) => format!("{}[]", ts_type(inner)),
+        FieldType::Map(inner) => format!("Record<string, {}>", ts_type(inner)),
+        FieldType::Any => "unknown".to_string(),
+    }
+}
+
+fn typescript(types: &[TypeDef]) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "// {}", HEADER);
+    for t in types {
+        out.push('\n');
+        if let Some(d) = &t.doc {
+            let _ = writeln!(out, "/** {} */", d);
+        }
+        if !t.variants.is_empty() {
+            let values: Vec<String> = t.variants.iter().map(|v| format!("{:?}", v)).collect();
+            let _ = writeln!(out, "export type {} = {};", t.name, values.join(" | "));
+            continue;
+        }
+        let _ = writeln!(out, "export interface {} {{", t.name);
+        for f in &t.fields {
+            if let Some(d) = &f.doc {
+                let _ = writeln!(out, "  /** {} */", d);
+            }
+            let optional = if f.required { "" } else { "?" };
+            let null = if f.nullable { " | null" } else { "" };
+            let _ = writeln!(out, "  {}{}: {}{};", f.name, optional, ts_type(&f.ty), null);
+        }
+        out.push_str("}\n");
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_python_dataclasses() {
+        let py = generate(Language::Python).unwrap();
+        assert!(py.contains("@dataclass\nclass Signal:"));
+        assert!(py.contains("    meta: Optional[SignalMeta] = None"));
+        assert!(py.contains("    attributions: List[FeatureAttribution] = field(default_factory=list)"));
+        assert!(py.contains("    heartbeat: bool = False"));
+        assert!(py.contains("    extra: Dict[str, Any] = field(default_factory=dict)"));
+        assert!(py.contains("SignalStatus = Literal[\"provisional\", \"confirmed\", \"cancelled\"]"));
+        assert!(py.contains("            status=d.get(\"status\"),"));
+        assert!(!py.contains("class PatternSource"));
+        assert!(py.contains("attributions=[FeatureAttribution.from_dict(x) for x in d.get(\"attributions\") or []],"));
+        // the root type comes after everything it references
+        assert!(py.find("class PatternMeta:").unwrap() < py.find("class Signal:").unwrap());
+    }
+
+    #[test]
+    fn test_typescript_interfaces() {
+        let ts = generate(Language::TypeScript).unwrap();
+        assert!(ts.contains("export interface Signal {"));
+        assert!(ts.contains("  score: number;"));
+        assert!(ts.contains("  pattern_meta?: PatternMeta | null;"));
+        assert!(ts.contains("  reversal_zone?: PriceZone | null;"));
+        assert!(ts.contains("  extra?: Record<string, unknown>;"));
+        assert!(ts.contains("export type PatternSource = \"seeded\" | \"config\" | \"ml\" | \"detector\";"));
+        assert!(ts.contains("  source?: PatternSource;"));
+    }
+}
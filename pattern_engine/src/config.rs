@@ -0,0 +1,220 @@
+//! Typed configuration values.
+//!
+//! Durations accept humantime strings (`30s`, `5m`, `1h30m`, `250ms`) and
+//! fractions accept percentages (`0.5%`); bare numbers keep their historical
+//! meaning (seconds and plain fractions) so existing configs still load.
+//! Every value is range checked, and errors name the setting, the raw value
+//! and what was expected.
+
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Deserializer};
+use std::fmt::Display;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Parse a humantime duration, or a bare number of seconds
+pub fn parse_duration(raw: &str) -> Result<Duration> {
+    let raw = raw.trim();
+    if let Ok(secs) = raw.parse::<f64>() {
+        return Duration::try_from_secs_f64(secs).map_err(|_| anyhow!("'{}' is not a non-negative duration", raw));
+    }
+    humantime::parse_duration(raw).map_err(|e| anyhow!("'{}' is not a duration like 30s, 5m or 250ms ({})", raw, e))
+}
+
+/// Parse a fraction, either plain (`0.005`) or as a percentage (`0.5%`)
+pub fn parse_fraction(raw: &str) -> Result<f64> {
+    let raw = raw.trim();
+    let (number, scale) = match raw.strip_suffix('%') {
+        Some(pct) => (pct.trim(), 0.01),
+        None => (raw, 1.0),
+    };
+    let value = number
+        .parse::<f64>()
+        .map_err(|_| anyhow!("'{}' is not a number or percentage like 0.005 or 0.5%", raw))?;
+    if !value.is_finite() {
+        bail!("'{}' is not a finite number", raw);
+    }
+    Ok(value * scale)
+}
+
+/// Reject `value` outside `range`, naming the setting
+pub fn check_range<T: PartialOrd + Display>(name: &str, value: T, range: &RangeInclusive<T>) -> Result<T> {
+    if !range.contains(&value) {
+        bail!("{}: {} is outside {}..={}", name, value, range.start(), range.end());
+    }
+    Ok(value)
+}
+
+/// Duration setting from `raw`, falling back to `default` when unset
+pub fn duration_value(name: &str, raw: Option<&str>, default: Duration, range: RangeInclusive<Duration>) -> Result<Duration> {
+    let Some(raw) = raw else {
+        return Ok(default);
+    };
+    let value = parse_duration(raw).map_err(|e| anyhow!("{}: {}", name, e))?;
+    if !range.contains(&value) {
+        bail!(
+            "{}: {} is outside {}..={}",
+            name,
+            humantime::format_duration(value),
+            humantime::format_duration(*range.start()),
+            humantime::format_duration(*range.end())
+        );
+    }
+    Ok(value)
+}
+
+/// Fraction setting from `raw`, falling back to `default` when unset
+pub fn fraction_value(name: &str, raw: Option<&str>, default: f64, range: RangeInclusive<f64>) -> Result<f64> {
+    let Some(raw) = raw else {
+        return Ok(default);
+    };
+    let value = parse_fraction(raw).map_err(|e| anyhow!("{}: {}", name, e))?;
+    check_range(name, value, &range)
+}
+
+/// Numeric setting from `raw`, falling back to `default` when unset
+pub fn number_value<T>(name: &str, raw: Option<&str>, default: T, range: RangeInclusive<T>) -> Result<T>
+where
+    T: FromStr + PartialOrd + Display,
+{
+    let Some(raw) = raw else {
+        return Ok(default);
+    };
+    let value = raw.trim().parse::<T>().map_err(|_| anyhow!("{}: '{}' is not a valid number", name, raw))?;
+    check_range(name, value, &range)
+}
+
+fn env_raw(key: &str) -> Option<String> {
+    std::env::var(key).ok().filter(|v| !v.trim().is_empty())
+}
+
+/// [`duration_value`] read from the environment
+pub fn env_duration(key: &str, default: Duration, range: RangeInclusive<Duration>) -> Result<Duration> {
+    duration_value(key, env_raw(key).as_deref(), default, range)
+}
+
+/// [`fraction_value`] read from the environment
+pub fn env_fraction(key: &str, default: f64, range: RangeInclusive<f64>) -> Result<f64> {
+    fraction_value(key, env_raw(key).as_deref(), default, range)
+}
+
+/// [`number_value`] read from the environment
+pub fn env_number<T>(key: &str, default: T, range: RangeInclusive<T>) -> Result<T>
+where
+    T: FromStr + PartialOrd + Display,
+{
+    number_value(key, env_raw(key).as_deref(), default, range)
+}
+
+/// Optional numeric setting from the environment (unset stays None)
+pub fn env_optional<T>(key: &str, range: RangeInclusive<T>) -> Result<Option<T>>
+where
+    T: FromStr + PartialOrd + Display + Copy,
+{
+    env_raw(key)
+        .map(|raw| number_value(key, Some(&raw), *range.start(), range))
+        .transpose()
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum NumberOrString {
+    Number(f64),
+    String(String),
+}
+
+/// Serde helper: seconds given as a number or a duration string
+pub fn deserialize_secs<'de, D: Deserializer<'de>>(d: D) -> std::result::Result<f64, D::Error> {
+    match NumberOrString::deserialize(d)? {
+        NumberOrString::Number(n) => Ok(n),
+        NumberOrString::String(s) => parse_duration(&s).map(|v| v.as_secs_f64()).map_err(serde::de::Error::custom),
+    }
+}
+
+/// Serde helper: optional form of [`deserialize_secs`]
+pub fn deserialize_opt_secs<'de, D: Deserializer<'de>>(d: D) -> std::result::Result<Option<f64>, D::Error> {
+    match Option::<NumberOrString>::deserialize(d)? {
+        None => Ok(None),
+        Some(NumberOrString::Number(n)) => Ok(Some(n)),
+        Some(NumberOrString::String(s)) => parse_duration(&s).map(|v| Some(v.as_secs_f64())).map_err(serde::de::Error::custom),
+    }
+}
+
+/// Serde helper: a fraction given as a number or a percentage string
+pub fn deserialize_fraction<'de, D: Deserializer<'de>>(d: D) -> std::result::Result<f64, D::Error> {
+    match NumberOrString::deserialize(d)? {
+        NumberOrString::Number(n) => Ok(n),
+        NumberOrString::String(s) => parse_fraction(&s).map_err(serde::de::Error::custom),
+    }
+}
+
+/// Serde helper: optional form of [`deserialize_fraction`]
+pub fn deserialize_opt_fraction<'de, D: Deserializer<'de>>(d: D) -> std::result::Result<Option<f64>, D::Error> {
+    match Option::<NumberOrString>::deserialize(d)? {
+        None => Ok(None),
+        Some(NumberOrString::Number(n)) => Ok(Some(n)),
+        Some(NumberOrString::String(s)) => parse_fraction(&s).map(Some).map_err(serde::de::Error::custom),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("30s").unwrap(), Duration::from_secs(30));
+        assert_eq!(parse_duration("30m").unwrap(), Duration::from_secs(1800));
+        assert_eq!(parse_duration("1h 30m").unwrap(), Duration::from_secs(5400));
+        assert_eq!(parse_duration("250ms").unwrap(), Duration::from_millis(250));
+        assert_eq!(parse_duration("2.5").unwrap(), Duration::from_millis(2500));
+        assert!(parse_duration("-1").is_err());
+        let err = parse_duration("30x").unwrap_err().to_string();
+        assert!(err.contains("'30x' is not a duration"), "{}", err);
+    }
+
+    #[test]
+    fn test_parse_fraction() {
+        assert_eq!(parse_fraction("0.005").unwrap(), 0.005);
+        assert!((parse_fraction("0.5%").unwrap() - 0.005).abs() < 1e-12);
+        assert!((parse_fraction(" 85 % ").unwrap() - 0.85).abs() < 1e-12);
+        assert!(parse_fraction("half").is_err());
+        assert!(parse_fraction("inf").is_err());
+    }
+
+    #[test]
+    fn test_values_use_default_and_check_range() {
+        let range = Duration::ZERO..=Duration::from_secs(3600);
+        assert_eq!(duration_value("COOLDOWN", None, Duration::from_secs(30), range.clone()).unwrap(), Duration::from_secs(30));
+        assert_eq!(duration_value("COOLDOWN", Some("5m"), Duration::ZERO, range.clone()).unwrap(), Duration::from_secs(300));
+        let err = duration_value("COOLDOWN", Some("2h"), Duration::ZERO, range).unwrap_err().to_string();
+        assert_eq!(err, "COOLDOWN: 2h is outside 0s..=1h");
+
+        assert_eq!(fraction_value("RATE", Some("50%"), 1.0, 0.0..=1.0).unwrap(), 0.5);
+        let err = fraction_value("RATE", Some("1.5"), 1.0, 0.0..=1.0).unwrap_err().to_string();
+        assert_eq!(err, "RATE: 1.5 is outside 0..=1");
+
+        assert_eq!(number_value("WORKERS", Some("4"), 2usize, 1..=64).unwrap(), 4);
+        let err = number_value("WORKERS", Some("four"), 2usize, 1..=64).unwrap_err().to_string();
+        assert_eq!(err, "WORKERS: 'four' is not a valid number");
+        assert!(number_value("WORKERS", Some("0"), 2usize, 1..=64).is_err());
+    }
+
+    #[test]
+    fn test_serde_helpers() {
+        #[derive(Deserialize)]
+        struct Cfg {
+            #[serde(deserialize_with = "deserialize_secs")]
+            cooldown: f64,
+            #[serde(default, deserialize_with = "deserialize_opt_fraction")]
+            deviation: Option<f64>,
+        }
+        let cfg: Cfg = serde_json::from_str(r#"{"cooldown": "2m", "deviation": "0.5%"}"#).unwrap();
+        assert_eq!(cfg.cooldown, 120.0);
+        assert!((cfg.deviation.unwrap() - 0.005).abs() < 1e-12);
+        let cfg: Cfg = serde_json::from_str(r#"{"cooldown": 30}"#).unwrap();
+        assert_eq!((cfg.cooldown, cfg.deviation), (30.0, None));
+        assert!(serde_json::from_str::<Cfg>(r#"{"cooldown": "soon"}"#).is_err());
+    }
+}
@@ -0,0 +1,105 @@
+//! Two-phase signal emission.
+//!
+//! Tick-level signals are published as `provisional` straight away and held
+//! here until the next candle close for their symbol. The close then decides
+//! whether the follow-up is `confirmed` (price held on the signal's side of
+//! the trigger price) or `cancelled`; the follow-up links back to the
+//! provisional ID.
+
+use crate::publisher::{Signal, SignalStatus};
+use crate::signal_id;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone)]
+struct Pending {
+    signal: Signal,
+    features: Vec<f64>,
+    trigger_price: f64,
+}
+
+/// Provisional signals awaiting their confirmation window
+#[derive(Debug, Default)]
+pub struct ConfirmationTracker {
+    /// Largest adverse move (fraction of the trigger price) still confirmed
+    max_adverse: f64,
+    pending: HashMap<String, Vec<Pending>>,
+}
+
+impl ConfirmationTracker {
+    pub fn new(max_adverse: f64) -> Self {
+        Self {
+            max_adverse: max_adverse.max(0.0),
+            pending: HashMap::new(),
+        }
+    }
+
+    /// Mark `signal` provisional and hold a copy (with its features) for resolution
+    pub fn track(&mut self, signal: &mut Signal, features: &[f64], trigger_price: f64) {
+        signal.status = Some(SignalStatus::Provisional);
+        self.pending.entry(signal.symbol.clone()).or_default().push(Pending {
+            signal: signal.clone(),
+            features: features.to_vec(),
+            trigger_price,
+        });
+    }
+
+    /// Number of provisional signals awaiting resolution
+    pub fn pending(&self) -> usize {
+        self.pending.values().map(Vec::len).sum()
+    }
+
+    /// Resolve provisional signals for `symbol` raised before `close_time`
+    /// against the candle close, returning the follow-up signals and their features
+    pub fn resolve(&mut self, symbol: &str, close: f64, close_time: f64) -> Vec<(Signal, Vec<f64>)> {
+        let Some(list) = self.pending.get_mut(symbol) else {
+            return Vec::new();
+        };
+        let (due, waiting): (Vec<Pending>, Vec<Pending>) = list.drain(..).partition(|p| p.signal.timestamp < close_time);
+        *list = waiting;
+
+        due.into_iter()
+            .map(|p| {
+                let direction = p.signal.score.signum();
+                let adverse = -direction * (close - p.trigger_price) / p.trigger_price;
+                let status = if adverse <= self.max_adverse { SignalStatus::Confirmed } else { SignalStatus::Cancelled };
+                let mut signal = p.signal;
+                signal.linked_id = Some(std::mem::replace(&mut signal.id, signal_id::next_id()));
+                signal.status = Some(status);
+                signal.timestamp = close_time;
+                (signal, p.features)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_confirm_and_cancel() {
+        let mut tracker = ConfirmationTracker::new(0.001);
+        let mut long = Signal::test("AAPL", "ema_crossover", 0.6, 10.0);
+        let mut short = Signal::test("AAPL", "ema_crossover", -0.6, 20.0);
+        tracker.track(&mut long, &[1.0], 100.0);
+        tracker.track(&mut short, &[2.0], 100.0);
+        assert_eq!(long.status, Some(SignalStatus::Provisional));
+        assert_eq!(tracker.pending(), 2);
+
+        // close at 100.05: long held, short moved 0.05% against (within tolerance)
+        let resolved = tracker.resolve("AAPL", 100.05, 60.0);
+        assert_eq!(resolved.len(), 2);
+        assert!(resolved.iter().all(|(s, _)| s.status == Some(SignalStatus::Confirmed)));
+        assert!(signal_id::timestamp_ms(&resolved[0].0.id).is_some());
+        assert_eq!(resolved[0].0.linked_id.as_deref(), Some("AAPL_10"));
+        assert_eq!(resolved[0].1, vec![1.0]);
+
+        let mut late = Signal::test("AAPL", "ema_crossover", 0.6, 70.0);
+        tracker.track(&mut late, &[], 100.0);
+        assert!(tracker.resolve("AAPL", 99.0, 60.0).is_empty());
+        let resolved = tracker.resolve("AAPL", 99.0, 120.0);
+        assert_eq!(resolved[0].0.status, Some(SignalStatus::Cancelled));
+        assert_eq!(resolved[0].0.linked_id.as_deref(), Some("AAPL_70"));
+        assert_eq!(tracker.pending(), 0);
+    }
+}
@@ -0,0 +1,287 @@
+//! Dead letters for signals a sink could not take.
+//!
+//! [`DeadLetterSink`] wraps a sink and retries a failed signal publish with
+//! exponential backoff. Once the attempts run out, the signal and the error
+//! are handed to the [`DeadLetterQueue`], which appends them to the
+//! dead-letter stream (`signals:dlq` by default) and, failing that or when
+//! configured, to a JSON-lines file. Every dead letter is counted, and so is
+//! every one that could not be written anywhere. Closed candles get the same
+//! retries but are not dead-lettered: a candle that still fails is dropped,
+//! counted as a failure of its sink and can be rebuilt from the ticks.
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::future::Future;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tracing::{error, warn};
+
+use crate::publisher::{Candle, Publisher, Signal, Tick};
+use crate::sink::Sink;
+
+/// A signal that exhausted its publish attempts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeadLetter {
+    pub signal: Signal,
+    /// Sink that refused it
+    pub sink: String,
+    /// Last error
+    pub error: String,
+    pub attempts: u32,
+    pub timestamp: f64,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct DeadLetterStats {
+    /// Written to the stream or file
+    pub dead_lettered: u64,
+    /// Could not be written anywhere
+    pub lost: u64,
+}
+
+/// Where dead letters go: the stream first, then the file
+#[derive(Default)]
+pub struct DeadLetterQueue {
+    stream: Option<Arc<Mutex<Publisher>>>,
+    file: Option<std::sync::Mutex<File>>,
+    dead_lettered: AtomicU64,
+    lost: AtomicU64,
+}
+
+impl DeadLetterQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append dead letters to the publisher's dead-letter stream
+    pub fn with_stream(mut self, publisher: Arc<Mutex<Publisher>>) -> Self {
+        self.stream = Some(publisher);
+        self
+    }
+
+    /// Append dead letters as JSON lines to `path`, or only when the stream
+    /// write fails if a stream is configured too
+    pub fn with_file(mut self, path: &Path) -> Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| anyhow!("failed to open {}: {}", path.display(), e))?;
+        self.file = Some(std::sync::Mutex::new(file));
+        Ok(self)
+    }
+
+    /// Store `letter`; false when it could not be written anywhere
+    pub async fn record(&self, letter: &DeadLetter) -> bool {
+        let mut stored = false;
+        if let Some(publisher) = &self.stream {
+            match publisher.lock().await.publish_dead_letter(letter).await {
+                Ok(_) => stored = true,
+                Err(e) => warn!("Failed to write dead letter for {} to stream: {}", letter.signal.id, e),
+            }
+        }
+        if let (false, Some(file)) = (stored, &self.file) {
+            match append_line(file, letter) {
+                Ok(()) => stored = true,
+                Err(e) => warn!("Failed to write dead letter for {} to file: {}", letter.signal.id, e),
+            }
+        }
+        if stored {
+            self.dead_lettered.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.lost.fetch_add(1, Ordering::Relaxed);
+            error!("Signal {} lost: {} failed after {} attempts ({})", letter.signal.id, letter.sink, letter.attempts, letter.error);
+        }
+        stored
+    }
+
+    pub fn stats(&self) -> DeadLetterStats {
+        DeadLetterStats {
+            dead_lettered: self.dead_lettered.load(Ordering::Relaxed),
+            lost: self.lost.load(Ordering::Relaxed),
+        }
+    }
+}
+
+fn append_line(file: &std::sync::Mutex<File>, letter: &DeadLetter) -> Result<()> {
+    let mut line = serde_json::to_vec(letter)?;
+    line.push(b'\n');
+    let mut file = file.lock().map_err(|_| anyhow!("dead-letter file lock poisoned"))?;
+    file.write_all(&line)?;
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    /// Publish attempts before dead-lettering, at least 1
+    pub attempts: u32,
+    /// Wait before the first retry, doubled for each one after
+    pub backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self { attempts: 3, backoff: Duration::from_millis(100) }
+    }
+}
+
+/// Retries failed signal publishes, then dead-letters them; candles are
+/// retried only and ticks pass straight through
+pub struct DeadLetterSink {
+    name: String,
+    inner: Arc<dyn Sink>,
+    policy: RetryPolicy,
+    queue: Arc<DeadLetterQueue>,
+}
+
+impl DeadLetterSink {
+    pub fn new(name: &str, inner: Arc<dyn Sink>, policy: RetryPolicy, queue: Arc<DeadLetterQueue>) -> Self {
+        Self { name: name.to_string(), inner, policy, queue }
+    }
+
+    /// Run `publish` until it succeeds or the attempts run out, backing off
+    /// between tries; returns the last error
+    async fn retry<F, Fut>(&self, mut publish: F) -> Result<()>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<()>>,
+    {
+        let attempts = self.policy.attempts.max(1);
+        let mut backoff = self.policy.backoff;
+        let mut attempt = 1;
+        loop {
+            match publish().await {
+                Ok(()) => return Ok(()),
+                Err(e) if attempt >= attempts => return Err(e),
+                Err(_) => {
+                    tokio::time::sleep(backoff).await;
+                    backoff *= 2;
+                    attempt += 1;
+                }
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Sink for DeadLetterSink {
+    async fn publish_signal(&self, signal: &Signal) -> Result<()> {
+        let Err(error) = self.retry(|| self.inner.publish_signal(signal)).await else {
+            return Ok(());
+        };
+        let attempts = self.policy.attempts.max(1);
+        let letter = DeadLetter {
+            signal: signal.clone(),
+            sink: self.name.clone(),
+            error: error.to_string(),
+            attempts,
+            timestamp: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs_f64(),
+        };
+        self.queue.record(&letter).await;
+        Err(error.context(format!("dead-lettered after {} attempts", attempts)))
+    }
+
+    async fn publish_tick(&self, tick: &Tick) -> Result<()> {
+        self.inner.publish_tick(tick).await
+    }
+
+    async fn publish_ticks(&self, ticks: &[Tick]) -> Result<()> {
+        self.inner.publish_ticks(ticks).await
+    }
+
+    async fn publish_candle(&self, candle: &Candle) -> Result<()> {
+        let attempts = self.policy.attempts.max(1);
+        self.retry(|| self.inner.publish_candle(candle))
+            .await
+            .map_err(|e| e.context(format!("candle dropped after {} attempts", attempts)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::BufRead;
+
+    /// Fails the first `failures` publishes
+    struct Flaky {
+        failures: AtomicU64,
+    }
+
+    impl Flaky {
+        fn attempt(&self) -> Result<()> {
+            let left = self.failures.load(Ordering::Relaxed);
+            if left > 0 {
+                self.failures.store(left - 1, Ordering::Relaxed);
+                anyhow::bail!("unavailable");
+            }
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl Sink for Flaky {
+        async fn publish_signal(&self, _signal: &Signal) -> Result<()> {
+            self.attempt()
+        }
+
+        async fn publish_candle(&self, _candle: &Candle) -> Result<()> {
+            self.attempt()
+        }
+    }
+
+    fn policy() -> RetryPolicy {
+        RetryPolicy { attempts: 3, backoff: Duration::from_millis(1) }
+    }
+
+    #[tokio::test]
+    async fn test_retries_before_dead_lettering() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("dlq.jsonl");
+        let queue = Arc::new(DeadLetterQueue::new().with_file(&path).unwrap());
+
+        let recovers = DeadLetterSink::new("grpc", Arc::new(Flaky { failures: AtomicU64::new(2) }), policy(), queue.clone());
+        recovers.publish_signal(&Signal::test("AAPL", "flag", 0.5, 1.0)).await.unwrap();
+        assert_eq!(queue.stats(), DeadLetterStats::default());
+
+        let down = DeadLetterSink::new("grpc", Arc::new(Flaky { failures: AtomicU64::new(10) }), policy(), queue.clone());
+        assert!(down.publish_signal(&Signal::test("AAPL", "flag", 0.5, 2.0)).await.is_err());
+        assert_eq!(queue.stats(), DeadLetterStats { dead_lettered: 1, lost: 0 });
+
+        let lines: Vec<String> = std::io::BufReader::new(File::open(&path).unwrap()).lines().map(Result::unwrap).collect();
+        assert_eq!(lines.len(), 1);
+        let letter: DeadLetter = serde_json::from_str(&lines[0]).unwrap();
+        assert_eq!((letter.signal.id.as_str(), letter.sink.as_str(), letter.attempts), ("AAPL_2", "grpc", 3));
+        assert_eq!(letter.error, "unavailable");
+    }
+
+    #[tokio::test]
+    async fn test_candles_are_retried_not_dead_lettered() {
+        let queue = Arc::new(DeadLetterQueue::new());
+        let candle = Candle { symbol: "AAPL".into(), interval: 60, start: 0, open: 1.0, high: 1.0, low: 1.0, close: 1.0, volume: 1.0 };
+        let recovers = DeadLetterSink::new("redis", Arc::new(Flaky { failures: AtomicU64::new(2) }), policy(), queue.clone());
+        recovers.publish_candle(&candle).await.unwrap();
+
+        let down = DeadLetterSink::new("redis", Arc::new(Flaky { failures: AtomicU64::new(10) }), policy(), queue.clone());
+        let err = down.publish_candle(&candle).await.unwrap_err();
+        assert!(err.to_string().contains("after 3 attempts"), "{}", err);
+        assert_eq!(queue.stats(), DeadLetterStats::default());
+    }
+
+    #[tokio::test]
+    async fn test_counts_letters_with_nowhere_to_go() {
+        let publisher = Arc::new(Mutex::new(Publisher::new("redis://127.0.0.1:1/").unwrap()));
+        let queue = Arc::new(DeadLetterQueue::new().with_stream(publisher));
+        let sink = DeadLetterSink::new("redis", Arc::new(Flaky { failures: AtomicU64::new(10) }), policy(), queue.clone());
+        assert!(sink.publish_signal(&Signal::test("AAPL", "flag", 0.5, 1.0)).await.is_err());
+        assert_eq!(queue.stats(), DeadLetterStats { dead_lettered: 0, lost: 1 });
+    }
+}
@@ -0,0 +1,103 @@
+//! Signal deduplication before publish.
+//!
+//! The tick path and the candle path can detect the same pattern on the same
+//! symbol within seconds of each other. [`SignalDeduper`] keys each signal by
+//! `(symbol, pattern, time_bucket)`, where the pattern drops any interval
+//! suffix (`double_top:60s` and `double_top` are the same pattern) and the
+//! bucket is the signal timestamp divided by the window; only the first
+//! signal per key is published. Signals are checked before confirmation
+//! tracking and enrichment, so a dropped repeat is never tracked, counted in
+//! pattern stats or exported for training.
+//!
+//! Confirmation follow-ups (signals linked to an earlier one, or
+//! confirming/cancelling it) always pass: each resolves exactly one
+//! provisional signal that was itself admitted, and dropping it would leave
+//! that published signal unresolved for subscribers.
+
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::publisher::{Signal, SignalStatus};
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct DedupStats {
+    pub window_secs: f64,
+    /// Signals dropped as duplicates
+    pub duplicates: u64,
+    /// Keys currently remembered
+    pub tracked: usize,
+}
+
+#[derive(Debug)]
+pub struct SignalDeduper {
+    window: f64,
+    /// (symbol, pattern) -> last bucket published
+    seen: HashMap<(String, String), i64>,
+    duplicates: u64,
+}
+
+impl SignalDeduper {
+    /// `window` in seconds; must be positive
+    pub fn new(window: f64) -> Self {
+        Self { window: window.max(f64::MIN_POSITIVE), seen: HashMap::new(), duplicates: 0 }
+    }
+
+    /// True when `signal` should be published; false for a duplicate
+    pub fn admit(&mut self, signal: &Signal) -> bool {
+        let follow_up = signal.linked_id.is_some()
+            || matches!(signal.status, Some(SignalStatus::Confirmed | SignalStatus::Cancelled));
+        if follow_up {
+            return true;
+        }
+        let bucket = (signal.timestamp / self.window).floor() as i64;
+        let pattern = signal.pattern.split(':').next().unwrap_or(&signal.pattern);
+        let key = (signal.symbol.clone(), pattern.to_string());
+        match self.seen.get(&key) {
+            Some(&last) if last == bucket => {
+                self.duplicates += 1;
+                false
+            }
+            _ => {
+                self.seen.insert(key, bucket);
+                // forget keys whose bucket has long passed
+                if self.seen.len() > 4096 {
+                    self.seen.retain(|_, b| *b >= bucket - 1);
+                }
+                true
+            }
+        }
+    }
+
+    pub fn stats(&self) -> DedupStats {
+        DedupStats { window_secs: self.window, duplicates: self.duplicates, tracked: self.seen.len() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_drops_same_pattern_in_bucket() {
+        let mut dedup = SignalDeduper::new(10.0);
+        assert!(dedup.admit(&Signal::test("AAPL", "double_top", 0.5, 100.0)));
+        // candle path sees the same pattern a few seconds later
+        assert!(!dedup.admit(&Signal::test("AAPL", "double_top:60s", 0.5, 104.0)));
+        assert!(dedup.admit(&Signal::test("MSFT", "double_top", 0.5, 104.0)));
+        assert!(dedup.admit(&Signal::test("AAPL", "flag", 0.5, 104.0)));
+        // next bucket
+        assert!(dedup.admit(&Signal::test("AAPL", "double_top", 0.5, 110.0)));
+        assert_eq!(dedup.stats(), DedupStats { window_secs: 10.0, duplicates: 1, tracked: 3 });
+    }
+
+    #[test]
+    fn test_follow_ups_always_pass() {
+        let mut dedup = SignalDeduper::new(10.0);
+        assert!(dedup.admit(&Signal::test("AAPL", "flag", 0.5, 100.0)));
+        let mut confirmed = Signal::test("AAPL", "flag", 0.5, 101.0);
+        confirmed.status = Some(SignalStatus::Confirmed);
+        confirmed.linked_id = Some("AAPL_100".to_string());
+        assert!(dedup.admit(&confirmed));
+        assert_eq!(dedup.stats().duplicates, 0);
+    }
+}
@@ -0,0 +1,322 @@
+//! Stress-mode degradation ladder.
+//!
+//! Tick processing latency and throughput are measured over fixed windows.
+//! Consecutive windows breaching the latency SLO or load limit step the
+//! engine one rung down the ladder; consecutive healthy windows step it back
+//! up. Each rung sheds strictly more work than the one before:
+//!
+//! 1. `no_enrichment`: skip ML inference and attribution for unknown patterns
+//! 2. `hot_tiers_only`: additionally skip tick-level detection for cold tiers
+//! 3. `conflate`: additionally merge each symbol's ticks over a short interval
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::publisher::Tick;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DegradationLevel {
+    #[default]
+    Normal,
+    NoEnrichment,
+    HotTiersOnly,
+    Conflate,
+}
+
+impl DegradationLevel {
+    fn up(self) -> Self {
+        match self {
+            Self::Normal => Self::NoEnrichment,
+            Self::NoEnrichment => Self::HotTiersOnly,
+            Self::HotTiersOnly | Self::Conflate => Self::Conflate,
+        }
+    }
+
+    fn down(self) -> Self {
+        match self {
+            Self::Normal | Self::NoEnrichment => Self::Normal,
+            Self::HotTiersOnly => Self::NoEnrichment,
+            Self::Conflate => Self::HotTiersOnly,
+        }
+    }
+}
+
+/// SLOs and hysteresis for moving along the ladder
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DegradationPolicy {
+    /// p99 tick processing latency (ms) above which a window breaches
+    pub latency_slo_ms: f64,
+    /// Ticks per second above which a window breaches (0 = no load limit)
+    pub max_ticks_per_sec: f64,
+    /// Consecutive breaching windows before stepping down a rung
+    pub escalate_after: u32,
+    /// Consecutive healthy windows before stepping back up a rung
+    pub recover_after: u32,
+    /// Symbols with a tier at or below this number count as hot
+    pub hot_tier: u32,
+    /// Per-symbol merge interval while conflating (seconds)
+    pub conflate_secs: f64,
+}
+
+impl Default for DegradationPolicy {
+    fn default() -> Self {
+        Self {
+            latency_slo_ms: 50.0,
+            max_ticks_per_sec: 0.0,
+            escalate_after: 2,
+            recover_after: 6,
+            hot_tier: 1,
+            conflate_secs: 1.0,
+        }
+    }
+}
+
+/// Emitted when the degradation level changes
+#[derive(Debug, Clone, Serialize)]
+pub struct DegradationEvent {
+    pub event: String,
+    pub from: DegradationLevel,
+    pub to: DegradationLevel,
+    pub ticks_per_sec: f64,
+    pub p99_latency_ms: f64,
+}
+
+/// Current ladder state for `/health` and metrics
+#[derive(Debug, Clone, Serialize)]
+pub struct DegradationStatus {
+    pub level: DegradationLevel,
+    pub ticks_per_sec: f64,
+    pub p99_latency_ms: f64,
+    pub transitions: u64,
+    pub conflated_ticks: u64,
+}
+
+#[derive(Debug, Default)]
+pub struct DegradationLadder {
+    policy: DegradationPolicy,
+    level: DegradationLevel,
+    /// Tick latencies (ms) in the current window
+    latencies: Vec<f64>,
+    breaches: u32,
+    healthy: u32,
+    last_ticks_per_sec: f64,
+    last_p99_ms: f64,
+    transitions: u64,
+    /// Merged tick per symbol and when that symbol last went through
+    pending: HashMap<String, (Option<Tick>, f64)>,
+    conflated: u64,
+}
+
+impl DegradationLadder {
+    pub fn new(policy: DegradationPolicy) -> Self {
+        Self { policy, ..Default::default() }
+    }
+
+    pub fn level(&self) -> DegradationLevel {
+        self.level
+    }
+
+    /// Whether ML inference should run for unknown patterns
+    pub fn enrichment_enabled(&self) -> bool {
+        self.level < DegradationLevel::NoEnrichment
+    }
+
+    /// Whether tick-level detection should run for a symbol of this tier
+    /// (untiered symbols count as cold)
+    pub fn tick_detection_enabled(&self, tier: Option<u32>) -> bool {
+        self.level < DegradationLevel::HotTiersOnly || tier.is_some_and(|t| t <= self.policy.hot_tier)
+    }
+
+    /// Record the processing time of one tick
+    pub fn record(&mut self, latency: Duration) {
+        self.latencies.push(latency.as_secs_f64() * 1000.0);
+    }
+
+    /// Close a window of `elapsed` length; returns an event when the level changes
+    pub fn evaluate(&mut self, elapsed: Duration) -> Option<DegradationEvent> {
+        let secs = elapsed.as_secs_f64();
+        self.last_ticks_per_sec = if secs > 0.0 { self.latencies.len() as f64 / secs } else { 0.0 };
+        self.last_p99_ms = percentile(&mut self.latencies, 0.99);
+        self.latencies.clear();
+
+        let p = &self.policy;
+        let breached = self.last_p99_ms > p.latency_slo_ms
+            || (p.max_ticks_per_sec > 0.0 && self.last_ticks_per_sec > p.max_ticks_per_sec);
+        let from = self.level;
+        if breached {
+            self.healthy = 0;
+            self.breaches += 1;
+            if self.breaches >= p.escalate_after.max(1) {
+                self.breaches = 0;
+                self.level = from.up();
+            }
+        } else {
+            self.breaches = 0;
+            self.healthy += 1;
+            if self.healthy >= p.recover_after.max(1) {
+                self.healthy = 0;
+                self.level = from.down();
+            }
+        }
+
+        if self.level == from {
+            return None;
+        }
+        self.transitions += 1;
+        Some(DegradationEvent {
+            event: if self.level > from { "degradation_raised" } else { "degradation_lowered" }.to_string(),
+            from,
+            to: self.level,
+            ticks_per_sec: self.last_ticks_per_sec,
+            p99_latency_ms: self.last_p99_ms,
+        })
+    }
+
+    /// Pass a tick through conflation. Outside the `conflate` level ticks pass
+    /// unchanged; otherwise each symbol yields at most one tick per interval,
+    /// carrying the latest price and the summed volume.
+    pub fn conflate(&mut self, tick: Tick, now: f64) -> Option<Tick> {
+        if self.level < DegradationLevel::Conflate {
+            return Some(tick);
+        }
+        let interval = self.policy.conflate_secs;
+        let (merged, last) = self.pending.entry(tick.symbol.clone()).or_insert((None, f64::NEG_INFINITY));
+        let merged_tick = match merged.take() {
+            Some(prev) => {
+                self.conflated += 1;
+                Tick { volume: prev.volume + tick.volume, ..tick }
+            }
+            None => tick,
+        };
+        if now - *last >= interval {
+            *last = now;
+            Some(merged_tick)
+        } else {
+            *merged = Some(merged_tick);
+            None
+        }
+    }
+
+    /// Ticks still held by conflation, once the ladder has left that level
+    pub fn drain_conflated(&mut self) -> Vec<Tick> {
+        if self.level >= DegradationLevel::Conflate {
+            return Vec::new();
+        }
+        self.pending.drain().filter_map(|(_, (tick, _))| tick).collect()
+    }
+
+    pub fn status(&self) -> DegradationStatus {
+        DegradationStatus {
+            level: self.level,
+            ticks_per_sec: self.last_ticks_per_sec,
+            p99_latency_ms: self.last_p99_ms,
+            transitions: self.transitions,
+            conflated_ticks: self.conflated,
+        }
+    }
+}
+
+fn percentile(values: &mut [f64], q: f64) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    let idx = ((values.len() as f64 * q).ceil() as usize).clamp(1, values.len()) - 1;
+    values[idx]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ladder() -> DegradationLadder {
+        DegradationLadder::new(DegradationPolicy {
+            latency_slo_ms: 10.0,
+            max_ticks_per_sec: 100.0,
+            escalate_after: 2,
+            recover_after: 3,
+            hot_tier: 1,
+            conflate_secs: 1.0,
+        })
+    }
+
+    fn window(l: &mut DegradationLadder, ticks: usize, latency_ms: u64) -> Option<DegradationEvent> {
+        for _ in 0..ticks {
+            l.record(Duration::from_millis(latency_ms));
+        }
+        l.evaluate(Duration::from_secs(1))
+    }
+
+    fn tick(volume: f64, price: f64) -> Tick {
+        Tick { volume, ..Tick::test("AAPL", price, 0.0) }
+    }
+
+    #[test]
+    fn test_escalates_one_rung_per_sustained_breach() {
+        let mut l = ladder();
+        assert!(window(&mut l, 10, 20).is_none());
+        let ev = window(&mut l, 10, 20).unwrap();
+        assert_eq!((ev.from, ev.to), (DegradationLevel::Normal, DegradationLevel::NoEnrichment));
+        assert!(!l.enrichment_enabled());
+        assert!(l.tick_detection_enabled(None));
+
+        // load alone also breaches
+        window(&mut l, 500, 1);
+        assert_eq!(window(&mut l, 500, 1).unwrap().to, DegradationLevel::HotTiersOnly);
+        assert!(l.tick_detection_enabled(Some(1)));
+        assert!(!l.tick_detection_enabled(Some(2)));
+        assert!(!l.tick_detection_enabled(None));
+
+        window(&mut l, 10, 20);
+        window(&mut l, 10, 20);
+        window(&mut l, 10, 20);
+        window(&mut l, 10, 20);
+        assert_eq!(l.level(), DegradationLevel::Conflate);
+        assert_eq!(l.status().transitions, 3);
+    }
+
+    #[test]
+    fn test_recovers_after_healthy_windows() {
+        let mut l = ladder();
+        window(&mut l, 10, 20);
+        window(&mut l, 10, 20);
+        assert!(window(&mut l, 10, 1).is_none());
+        // a breach resets the healthy streak
+        window(&mut l, 10, 20);
+        assert!(window(&mut l, 10, 1).is_none());
+        assert!(window(&mut l, 10, 1).is_none());
+        let ev = window(&mut l, 10, 1).unwrap();
+        assert_eq!(ev.to, DegradationLevel::Normal);
+        assert_eq!(ev.event, "degradation_lowered");
+    }
+
+    #[test]
+    fn test_conflation_merges_volume_per_interval() {
+        let mut l = ladder();
+        assert!(l.conflate(tick(1.0, 10.0), 0.0).is_some());
+        l.level = DegradationLevel::Conflate;
+        assert_eq!(l.conflate(tick(1.0, 10.0), 0.0).unwrap().volume, 1.0);
+        assert!(l.conflate(tick(2.0, 11.0), 0.3).is_none());
+        assert!(l.conflate(tick(3.0, 12.0), 0.6).is_none());
+        let out = l.conflate(tick(4.0, 13.0), 1.2).unwrap();
+        assert_eq!((out.volume, out.price), (9.0, 13.0));
+        assert_eq!(l.status().conflated_ticks, 2);
+
+        assert!(l.conflate(tick(5.0, 14.0), 1.5).is_none());
+        assert!(l.drain_conflated().is_empty());
+        l.level = DegradationLevel::HotTiersOnly;
+        let rest = l.drain_conflated();
+        assert_eq!(rest.len(), 1);
+        assert_eq!(rest[0].volume, 5.0);
+    }
+
+    #[test]
+    fn test_percentile() {
+        let mut v: Vec<f64> = (1..=100).map(f64::from).collect();
+        assert_eq!(percentile(&mut v, 0.99), 99.0);
+        assert_eq!(percentile(&mut [], 0.99), 0.0);
+        assert_eq!(percentile(&mut [5.0], 0.99), 5.0);
+    }
+}
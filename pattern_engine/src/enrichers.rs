@@ -0,0 +1,209 @@
+//! Per-signal metadata enrichers.
+//!
+//! Enrichers add free-form fields to [`Signal::extra`] just before a signal is
+//! published, keyed by enricher name. The set is chosen by configuration
+//! (`regime,data_quality,...`) so deployments can attach context without
+//! changing the signal schema.
+
+use anyhow::{anyhow, bail, Result};
+use serde_json::{json, Map, Value};
+
+use crate::publisher::Signal;
+
+/// Produces one `extra` entry for a signal
+pub trait SignalEnricher: Send + Sync {
+    /// Key the output is stored under
+    fn name(&self) -> &str;
+
+    /// Value for this signal, or None to leave it out
+    fn enrich(&self, signal: &Signal, features: &[f64], names: &[&str]) -> Option<Value>;
+}
+
+/// Value of the named feature, if present and finite
+fn feature(features: &[f64], names: &[&str], name: &str) -> Option<f64> {
+    names
+        .iter()
+        .position(|n| *n == name)
+        .and_then(|i| features.get(i).copied())
+        .filter(|v| v.is_finite())
+}
+
+/// Trend direction from the EMA spread plus the signal's volatility
+pub struct RegimeEnricher {
+    /// Minimum |ema_diff_pct| to call a trend
+    pub trend_threshold: f64,
+}
+
+impl Default for RegimeEnricher {
+    fn default() -> Self {
+        Self { trend_threshold: 0.001 }
+    }
+}
+
+impl SignalEnricher for RegimeEnricher {
+    fn name(&self) -> &str {
+        "regime"
+    }
+
+    fn enrich(&self, signal: &Signal, features: &[f64], names: &[&str]) -> Option<Value> {
+        let spread = feature(features, names, "ema_diff_pct")?;
+        let trend = trend(spread, self.trend_threshold);
+        let volatility = signal
+            .meta
+            .as_ref()
+            .map(|m| m.volatility)
+            .or_else(|| feature(features, names, "volatility"));
+        Some(json!({ "trend": trend, "ema_spread_pct": spread, "volatility": volatility }))
+    }
+}
+
+/// `up`, `down` or `range` for an EMA spread against a trend threshold
+pub fn trend(spread: f64, threshold: f64) -> &'static str {
+    if spread > threshold {
+        "up"
+    } else if spread < -threshold {
+        "down"
+    } else {
+        "range"
+    }
+}
+
+/// Which inputs were available when the signal was produced
+pub struct DataQualityEnricher;
+
+impl SignalEnricher for DataQualityEnricher {
+    fn name(&self) -> &str {
+        "data_quality"
+    }
+
+    fn enrich(&self, signal: &Signal, features: &[f64], _names: &[&str]) -> Option<Value> {
+        let missing = features.iter().filter(|v| !v.is_finite()).count();
+        let meta = signal.meta.as_ref();
+        Some(json!({
+            "heartbeat": meta.map(|m| m.heartbeat).unwrap_or(false),
+            "missing_features": missing,
+            "has_vwap": meta.map(|m| m.vwap.is_some()).unwrap_or(false),
+            "has_rsi": meta.map(|m| m.rsi.is_some()).unwrap_or(false),
+            "has_order_flow": meta.map(|m| m.cvd.is_some()).unwrap_or(false),
+        }))
+    }
+}
+
+/// The feature vector the signal was scored on, by name
+pub struct FeatureSnapshotEnricher;
+
+impl SignalEnricher for FeatureSnapshotEnricher {
+    fn name(&self) -> &str {
+        "features"
+    }
+
+    fn enrich(&self, _signal: &Signal, features: &[f64], names: &[&str]) -> Option<Value> {
+        let map: Map<String, Value> = names
+            .iter()
+            .zip(features)
+            .filter(|(_, v)| v.is_finite())
+            .map(|(n, v)| (n.to_string(), json!(v)))
+            .collect();
+        (!map.is_empty()).then_some(Value::Object(map))
+    }
+}
+
+/// Fixed deployment fields (`key=value,...`), e.g. venue or model build
+pub struct StaticEnricher {
+    fields: Map<String, Value>,
+}
+
+impl StaticEnricher {
+    pub fn from_spec(spec: &str) -> Result<Self> {
+        let mut fields = Map::new();
+        for item in spec.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            let (key, value) = item
+                .split_once('=')
+                .ok_or_else(|| anyhow!("static field '{}' is not key=value", item))?;
+            fields.insert(key.trim().to_string(), Value::String(value.trim().to_string()));
+        }
+        Ok(Self { fields })
+    }
+}
+
+impl SignalEnricher for StaticEnricher {
+    fn name(&self) -> &str {
+        "static"
+    }
+
+    fn enrich(&self, _signal: &Signal, _features: &[f64], _names: &[&str]) -> Option<Value> {
+        (!self.fields.is_empty()).then(|| Value::Object(self.fields.clone()))
+    }
+}
+
+/// Enrichers named in a comma-separated spec; `static_fields` configures `static`
+pub fn build_enrichers(spec: &str, static_fields: &str) -> Result<Vec<Box<dyn SignalEnricher>>> {
+    let mut enrichers: Vec<Box<dyn SignalEnricher>> = Vec::new();
+    for name in spec.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        let enricher: Box<dyn SignalEnricher> = match name {
+            "regime" => Box::<RegimeEnricher>::default(),
+            "data_quality" => Box::new(DataQualityEnricher),
+            "features" => Box::new(FeatureSnapshotEnricher),
+            "static" => Box::new(StaticEnricher::from_spec(static_fields)?),
+            other => bail!("unknown signal enricher '{}'", other),
+        };
+        enrichers.push(enricher);
+    }
+    Ok(enrichers)
+}
+
+/// Run every enricher and store its output in `signal.extra`
+pub fn apply(enrichers: &[Box<dyn SignalEnricher>], signal: &mut Signal, features: &[f64], names: &[&str]) {
+    for enricher in enrichers {
+        if let Some(value) = enricher.enrich(signal, features, names) {
+            signal.extra.insert(enricher.name().to_string(), value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NAMES: [&str; 3] = ["ema_diff_pct", "volatility", "momentum"];
+
+    #[test]
+    fn test_regime_classifies_trend() {
+        let (e, signal) = (RegimeEnricher::default(), Signal::test("AAPL", "ema_crossover:60s", 0.8, 1.0));
+        let up = e.enrich(&signal, &[0.01, 0.2, 1.0], &NAMES).unwrap();
+        assert_eq!(up["trend"], "up");
+        assert_eq!(up["volatility"], 0.2);
+        let flat = e.enrich(&signal, &[0.0001, 0.2, 1.0], &NAMES).unwrap();
+        assert_eq!(flat["trend"], "range");
+        assert!(e.enrich(&signal, &[1.0], &["momentum"]).is_none());
+    }
+
+    #[test]
+    fn test_feature_snapshot_skips_non_finite() {
+        let signal = Signal::test("AAPL", "ema_crossover:60s", 0.8, 1.0);
+        let v = FeatureSnapshotEnricher.enrich(&signal, &[0.5, f64::NAN, 2.0], &NAMES).unwrap();
+        assert_eq!(v, json!({ "ema_diff_pct": 0.5, "momentum": 2.0 }));
+        let q = DataQualityEnricher.enrich(&signal, &[0.5, f64::NAN, 2.0], &NAMES).unwrap();
+        assert_eq!(q["missing_features"], 1);
+    }
+
+    #[test]
+    fn test_build_and_apply_round_trips() {
+        let enrichers = build_enrichers("regime, static", "venue=XNAS,build=42").unwrap();
+        let mut s = Signal::test("AAPL", "ema_crossover:60s", 0.8, 1.0);
+        apply(&enrichers, &mut s, &[-0.02, 0.1, 0.0], &NAMES);
+        assert_eq!(s.extra["regime"]["trend"], "down");
+        assert_eq!(s.extra["static"]["venue"], "XNAS");
+
+        let back: Signal = serde_json::from_str(&serde_json::to_string(&s).unwrap()).unwrap();
+        assert_eq!(back.extra, s.extra);
+        assert!(!serde_json::to_string(&Signal::test("AAPL", "ema_crossover:60s", 0.8, 1.0)).unwrap().contains("extra"));
+    }
+
+    #[test]
+    fn test_rejects_unknown_and_malformed() {
+        assert!(build_enrichers("regime,bogus", "").is_err());
+        assert!(build_enrichers("static", "novalue").is_err());
+        assert!(build_enrichers("", "").unwrap().is_empty());
+    }
+}
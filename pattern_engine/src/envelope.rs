@@ -0,0 +1,124 @@
+//! Versioned envelope around every published stream entry.
+//!
+//! The publisher adds three fields next to the payload fields of each entry:
+//! `schema_version`, `producer` and `emitted_at` (unix seconds, taken when
+//! the entry was first handed to the publisher, so entries replayed from the
+//! write-ahead log keep their original time). Consumers that only read
+//! `data` are unaffected.
+//!
+//! Entries written before the envelope existed carry none of these fields
+//! and are read as version 0. [`open`] strips the envelope and refuses
+//! entries from a newer schema than this build understands; [`decode_data`]
+//! also brings older JSON payloads up to the current layout before
+//! deserializing them.
+
+use anyhow::{anyhow, bail, Result};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::codec;
+
+/// Version written by this build
+pub const SCHEMA_VERSION: u32 = 1;
+
+pub const SCHEMA_VERSION_FIELD: &str = "schema_version";
+pub const PRODUCER_FIELD: &str = "producer";
+pub const EMITTED_AT_FIELD: &str = "emitted_at";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Envelope {
+    /// 0 for entries written before the envelope existed
+    pub schema_version: u32,
+    pub producer: Option<String>,
+    pub emitted_at: Option<f64>,
+}
+
+impl Envelope {
+    /// Envelope for entries emitted now by `producer`
+    pub fn new(producer: &str) -> Self {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs_f64();
+        Self { schema_version: SCHEMA_VERSION, producer: Some(producer.to_string()), emitted_at: Some(now) }
+    }
+
+    /// Append the envelope fields to an entry's payload fields
+    pub fn seal(&self, fields: &mut Vec<(String, String)>) {
+        fields.push((SCHEMA_VERSION_FIELD.to_string(), self.schema_version.to_string()));
+        if let Some(producer) = &self.producer {
+            fields.push((PRODUCER_FIELD.to_string(), producer.clone()));
+        }
+        if let Some(emitted_at) = self.emitted_at {
+            fields.push((EMITTED_AT_FIELD.to_string(), emitted_at.to_string()));
+        }
+    }
+}
+
+/// Split an entry into its envelope and payload fields
+pub fn open(fields: &HashMap<String, String>) -> Result<(Envelope, HashMap<String, String>)> {
+    let mut payload = fields.clone();
+    let schema_version = match payload.remove(SCHEMA_VERSION_FIELD) {
+        Some(v) => v.trim().parse().map_err(|_| anyhow!("invalid {} '{}'", SCHEMA_VERSION_FIELD, v))?,
+        None => 0,
+    };
+    if schema_version > SCHEMA_VERSION {
+        bail!("entry has schema version {}, newer than the supported {}", schema_version, SCHEMA_VERSION);
+    }
+    let producer = payload.remove(PRODUCER_FIELD);
+    let emitted_at = payload.remove(EMITTED_AT_FIELD).and_then(|v| v.parse().ok());
+    Ok((Envelope { schema_version, producer, emitted_at }, payload))
+}
+
+/// Bring a JSON payload written under `version` up to the current layout.
+/// Version 0 payloads are laid out like version 1 ones; later layout changes
+/// add their conversion here.
+fn upgrade(_version: u32, payload: serde_json::Value) -> serde_json::Value {
+    payload
+}
+
+/// Payload of an entry holding JSON in a `data` field, with its envelope
+pub fn decode_data<T: DeserializeOwned>(fields: &HashMap<String, String>) -> Result<(Envelope, T)> {
+    let (envelope, payload) = open(fields)?;
+    let payload = codec::decompress(&payload)?;
+    let data = payload.get("data").ok_or_else(|| anyhow!("entry without a data field"))?;
+    let value = upgrade(envelope.schema_version, serde_json::from_str(data)?);
+    Ok((envelope, serde_json::from_value(value)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::publisher::Tick;
+
+    const TICK: &str = r#"{"symbol":"AAPL","price":101.5,"volume":20.0,"timestamp":1.0}"#;
+
+    #[test]
+    fn test_sealed_entries_round_trip() {
+        let envelope = Envelope { schema_version: SCHEMA_VERSION, producer: Some("engine-a".to_string()), emitted_at: Some(12.5) };
+        let mut fields = vec![("data".to_string(), TICK.to_string())];
+        envelope.seal(&mut fields);
+        let map: HashMap<_, _> = fields.into_iter().collect();
+        assert_eq!(map[SCHEMA_VERSION_FIELD], "1");
+
+        let (opened, tick) = decode_data::<Tick>(&map).unwrap();
+        assert_eq!(opened, envelope);
+        assert_eq!(tick.price, 101.5);
+        assert_eq!(open(&map).unwrap().1.keys().collect::<Vec<_>>(), vec!["data"]);
+    }
+
+    #[test]
+    fn test_reads_legacy_and_refuses_newer_entries() {
+        let legacy: HashMap<_, _> = [("data".to_string(), TICK.to_string())].into_iter().collect();
+        let (envelope, tick) = decode_data::<Tick>(&legacy).unwrap();
+        assert_eq!(envelope, Envelope { schema_version: 0, producer: None, emitted_at: None });
+        assert_eq!(tick.symbol, "AAPL");
+
+        let mut newer = legacy.clone();
+        newer.insert(SCHEMA_VERSION_FIELD.to_string(), (SCHEMA_VERSION + 1).to_string());
+        assert!(decode_data::<Tick>(&newer).unwrap_err().to_string().contains("newer"));
+        newer.insert(SCHEMA_VERSION_FIELD.to_string(), "v2".to_string());
+        assert!(open(&newer).is_err());
+    }
+}
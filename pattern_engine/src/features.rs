@@ -0,0 +1,162 @@
+//! Named feature vectors sent to the models.
+//!
+//! Every signal, whether raised on a tick or on a closed candle, is scored
+//! with the same [`SIGNAL_FEATURES`] layout, built by name with
+//! [`FeatureVector`] so a feature can't land in the wrong slot. Features a
+//! tick doesn't have (the candle open) are 0. Exogenous features are
+//! appended after these, and the full list is a [`FeatureSchema`] whose
+//! [`FeatureSchema::hash`] is checked against the `feature_schema` metadata
+//! of a model, so a model trained on another layout is refused at load.
+
+use crate::publisher::SignalMeta;
+use anyhow::{bail, Result};
+
+/// Features of every signal, in model input order
+pub const SIGNAL_FEATURES: [&str; 8] = [
+    "ema_diff", "ema_diff_pct", "vwap_deviation", "volume_ratio", "momentum", "momentum_from_open", "open_pct", "volatility",
+];
+
+/// Ordered feature names a model is trained on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeatureSchema {
+    names: Vec<String>,
+}
+
+impl FeatureSchema {
+    pub fn new<S: Into<String>>(names: impl IntoIterator<Item = S>) -> Result<Self> {
+        let names: Vec<String> = names.into_iter().map(Into::into).collect();
+        if names.is_empty() {
+            bail!("feature schema lists no features");
+        }
+        for (i, name) in names.iter().enumerate() {
+            if names[..i].contains(name) {
+                bail!("feature '{}' is listed twice", name);
+            }
+        }
+        Ok(Self { names })
+    }
+
+    /// [`SIGNAL_FEATURES`] followed by `extra` (e.g. exogenous features)
+    pub fn signal(extra: &[String]) -> Result<Self> {
+        Self::new(SIGNAL_FEATURES.iter().map(|n| n.to_string()).chain(extra.iter().cloned()))
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.names.iter().map(String::as_str).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// FNV-1a hash of the names in order, as 16 hex digits; stable across
+    /// builds so training pipelines can compute it too
+    pub fn hash(&self) -> String {
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        for byte in self.names.join("\n").bytes() {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+        format!("{:016x}", hash)
+    }
+}
+
+/// Builds one vector in the order of `names`, by feature name
+#[derive(Debug, Clone)]
+pub struct FeatureVector<'a> {
+    names: &'a [&'a str],
+    values: Vec<Option<f64>>,
+    unknown: Vec<String>,
+}
+
+impl<'a> FeatureVector<'a> {
+    pub fn new(names: &'a [&'a str]) -> Self {
+        Self { names, values: vec![None; names.len()], unknown: Vec::new() }
+    }
+
+    pub fn set(mut self, name: &str, value: f64) -> Self {
+        match self.names.iter().position(|n| *n == name) {
+            Some(i) => self.values[i] = Some(value),
+            None => self.unknown.push(name.to_string()),
+        }
+        self
+    }
+
+    /// The values in order; fails if a feature was not set or a name is not
+    /// in the layout
+    pub fn build(self) -> Result<Vec<f64>> {
+        if !self.unknown.is_empty() {
+            bail!("unknown features: {}", self.unknown.join(", "));
+        }
+        let missing: Vec<&str> = self.names.iter().zip(&self.values).filter(|(_, v)| v.is_none()).map(|(n, _)| *n).collect();
+        if !missing.is_empty() {
+            bail!("features not set: {}", missing.join(", "));
+        }
+        Ok(self.values.into_iter().flatten().collect())
+    }
+}
+
+/// [`SIGNAL_FEATURES`] for a signal at `price`; `open` is the candle open for
+/// signals raised on a closed candle
+pub fn signal_features(meta: Option<&SignalMeta>, price: f64, volume: f64, avg_volume: f64, open: Option<f64>) -> Vec<f64> {
+    let (ema_fast, ema_slow, vwap, volume, volatility) = match meta {
+        Some(m) => (m.ema_fast.unwrap_or(0.0), m.ema_slow.unwrap_or(0.0), m.vwap.unwrap_or(0.0), m.volume, m.volatility),
+        None => (0.0, 0.0, 0.0, volume, 0.0),
+    };
+    let ema_diff = ema_fast - ema_slow;
+    let ratio = |num: f64, den: f64| if den.abs() > f64::EPSILON { num / den } else { 0.0 };
+    FeatureVector::new(&SIGNAL_FEATURES)
+        .set("ema_diff", ema_diff)
+        .set("ema_diff_pct", ratio(ema_diff, ema_slow))
+        .set("vwap_deviation", ratio(price - vwap, vwap))
+        .set("volume_ratio", if avg_volume > 0.0 { volume / avg_volume } else { 1.0 })
+        .set("momentum", price - ema_slow)
+        .set("momentum_from_open", open.map_or(0.0, |o| price - o))
+        .set("open_pct", open.map_or(0.0, |o| ratio(price - o, o)))
+        .set("volatility", volatility)
+        .build()
+        .expect("every signal feature is set")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builds_in_layout_order() {
+        let names = ["a", "b", "c"];
+        let values = FeatureVector::new(&names).set("c", 3.0).set("a", 1.0).set("b", 2.0).build().unwrap();
+        assert_eq!(values, vec![1.0, 2.0, 3.0]);
+        let err = FeatureVector::new(&names).set("a", 1.0).set("z", 0.0).build().unwrap_err().to_string();
+        assert_eq!(err, "unknown features: z");
+        let err = FeatureVector::new(&names).set("b", 1.0).build().unwrap_err().to_string();
+        assert_eq!(err, "features not set: a, c");
+    }
+
+    #[test]
+    fn test_tick_and_candle_signals_share_layout() {
+        let tick = signal_features(None, 101.0, 50.0, 25.0, None);
+        let candle = signal_features(None, 101.0, 50.0, 25.0, Some(100.0));
+        assert_eq!(tick.len(), SIGNAL_FEATURES.len());
+        assert_eq!(candle.len(), SIGNAL_FEATURES.len());
+        assert_eq!((tick[3], tick[5], tick[6]), (2.0, 0.0, 0.0));
+        assert_eq!(candle[5], 1.0);
+        assert!((candle[6] - 0.01).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_schema_hash() {
+        let schema = FeatureSchema::signal(&[]).unwrap();
+        assert_eq!(schema.len(), 8);
+        assert_eq!(schema.hash(), FeatureSchema::signal(&[]).unwrap().hash());
+        let extended = FeatureSchema::signal(&["news_sentiment".to_string()]).unwrap();
+        assert_ne!(extended.hash(), schema.hash());
+        assert_eq!(FeatureSchema::new(["a"]).unwrap().hash(), "af63dc4c8601ec8c");
+        assert!(FeatureSchema::new(["a", "a"]).is_err());
+        assert!(FeatureSchema::signal(&["volatility".to_string()]).is_err());
+    }
+}
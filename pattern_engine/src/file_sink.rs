@@ -0,0 +1,271 @@
+//! Sink appending signals and ticks to local files.
+//!
+//! For air-gapped research and for capturing golden outputs in tests.
+//! Signals and ticks go to separate numbered files in one directory
+//! (`signals-000001.jsonl`, `ticks-000001.csv`, ...) as JSON lines or CSV
+//! with a header. A file is rotated once it reaches `max_bytes`; numbering
+//! continues after any files already in the directory, and with `max_files`
+//! set the oldest files of each kind are removed.
+
+use anyhow::{anyhow, bail, Result};
+use async_trait::async_trait;
+use serde::Serialize;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::publisher::{Signal, SignalStatus, Tick, TradeSide};
+use crate::sink::Sink;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileFormat {
+    Jsonl,
+    Csv,
+}
+
+impl FileFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            FileFormat::Jsonl => "jsonl",
+            FileFormat::Csv => "csv",
+        }
+    }
+}
+
+impl std::str::FromStr for FileFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "jsonl" | "json" => Ok(FileFormat::Jsonl),
+            "csv" => Ok(FileFormat::Csv),
+            other => bail!("unknown file format '{}' (expected jsonl or csv)", other),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FileSinkConfig {
+    pub dir: PathBuf,
+    pub format: FileFormat,
+    /// Rotate a file once it holds this many bytes
+    pub max_bytes: u64,
+    /// Files of each kind to keep; 0 keeps them all
+    pub max_files: usize,
+}
+
+impl FileSinkConfig {
+    pub fn new(dir: impl Into<PathBuf>, format: FileFormat) -> Self {
+        Self { dir: dir.into(), format, max_bytes: 64 << 20, max_files: 0 }
+    }
+}
+
+const SIGNAL_COLUMNS: &str = "id,symbol,score,pattern,timestamp,status,linked_id";
+const TICK_COLUMNS: &str = "symbol,price,volume,timestamp,side,feed";
+
+/// One rotating series of files, e.g. the signal files
+struct Series {
+    kind: &'static str,
+    header: &'static str,
+    file: Option<File>,
+    index: u64,
+    written: u64,
+}
+
+impl Series {
+    fn new(kind: &'static str, header: &'static str, dir: &Path, ext: &str) -> Result<Self> {
+        let index = existing(dir, kind, ext)?.last().map_or(0, |(n, _)| *n);
+        Ok(Self { kind, header, file: None, index, written: 0 })
+    }
+
+    fn write(&mut self, config: &FileSinkConfig, line: &[u8]) -> Result<()> {
+        if self.file.is_none() || self.written >= config.max_bytes {
+            self.rotate(config)?;
+        }
+        if let Some(file) = self.file.as_mut() {
+            file.write_all(line)?;
+            self.written += line.len() as u64;
+        }
+        Ok(())
+    }
+
+    fn rotate(&mut self, config: &FileSinkConfig) -> Result<()> {
+        let ext = config.format.extension();
+        self.index += 1;
+        let path = config.dir.join(format!("{}-{:06}.{}", self.kind, self.index, ext));
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| anyhow!("failed to open {}: {}", path.display(), e))?;
+        self.written = file.metadata()?.len();
+        if config.format == FileFormat::Csv && self.written == 0 {
+            let header = format!("{}\n", self.header);
+            file.write_all(header.as_bytes())?;
+            self.written = header.len() as u64;
+        }
+        self.file = Some(file);
+        if config.max_files > 0 {
+            let files = existing(&config.dir, self.kind, ext)?;
+            for (_, old) in files.iter().take(files.len().saturating_sub(config.max_files)) {
+                if let Err(e) = std::fs::remove_file(old) {
+                    tracing::warn!("Failed to remove rotated file {}: {}", old.display(), e);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Numbered `kind-N.ext` files in `dir`, oldest first
+fn existing(dir: &Path, kind: &str, ext: &str) -> Result<Vec<(u64, PathBuf)>> {
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        let Some(name) = path.file_name().and_then(|n| n.to_str()) else { continue };
+        let index = name
+            .strip_prefix(kind)
+            .and_then(|n| n.strip_prefix('-'))
+            .and_then(|n| n.strip_suffix(ext))
+            .and_then(|n| n.strip_suffix('.'))
+            .and_then(|n| n.parse::<u64>().ok());
+        if let Some(index) = index {
+            files.push((index, path));
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Quote a CSV field when it needs it
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn signal_row(signal: &Signal) -> String {
+    let fields = [
+        csv_field(&signal.id),
+        csv_field(&signal.symbol),
+        signal.score.to_string(),
+        csv_field(&signal.pattern),
+        signal.timestamp.to_string(),
+        signal.status.as_ref().map_or("", SignalStatus::as_str).to_string(),
+        csv_field(signal.linked_id.as_deref().unwrap_or_default()),
+    ];
+    format!("{}\n", fields.join(","))
+}
+
+fn tick_row(tick: &Tick) -> String {
+    let fields = [
+        csv_field(&tick.symbol),
+        tick.price.to_string(),
+        tick.volume.to_string(),
+        tick.timestamp.to_string(),
+        tick.side.as_ref().map_or("", TradeSide::as_str).to_string(),
+        csv_field(tick.feed.as_deref().unwrap_or_default()),
+    ];
+    format!("{}\n", fields.join(","))
+}
+
+pub struct FileSink {
+    config: FileSinkConfig,
+    signals: Mutex<Series>,
+    ticks: Mutex<Series>,
+}
+
+impl FileSink {
+    /// Create the directory if needed; files are opened on the first write
+    pub fn open(config: FileSinkConfig) -> Result<Self> {
+        std::fs::create_dir_all(&config.dir).map_err(|e| anyhow!("failed to create {}: {}", config.dir.display(), e))?;
+        let ext = config.format.extension();
+        let signals = Series::new("signals", SIGNAL_COLUMNS, &config.dir, ext)?;
+        let ticks = Series::new("ticks", TICK_COLUMNS, &config.dir, ext)?;
+        Ok(Self { config, signals: Mutex::new(signals), ticks: Mutex::new(ticks) })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.config.dir
+    }
+
+    fn append(&self, series: &Mutex<Series>, line: &[u8]) -> Result<()> {
+        let mut series = series.lock().map_err(|_| anyhow!("file sink lock poisoned"))?;
+        series.write(&self.config, line)
+    }
+
+    fn json_line<T: Serialize>(value: &T) -> Result<Vec<u8>> {
+        let mut line = serde_json::to_vec(value)?;
+        line.push(b'\n');
+        Ok(line)
+    }
+}
+
+#[async_trait]
+impl Sink for FileSink {
+    async fn publish_signal(&self, signal: &Signal) -> Result<()> {
+        let line = match self.config.format {
+            FileFormat::Jsonl => Self::json_line(signal)?,
+            FileFormat::Csv => signal_row(signal).into_bytes(),
+        };
+        self.append(&self.signals, &line)
+    }
+
+    async fn publish_tick(&self, tick: &Tick) -> Result<()> {
+        let line = match self.config.format {
+            FileFormat::Jsonl => Self::json_line(tick)?,
+            FileFormat::Csv => tick_row(tick).into_bytes(),
+        };
+        self.append(&self.ticks, &line)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::publisher::{SignalStatus, TradeSide};
+
+    #[tokio::test]
+    async fn test_writes_csv_with_header() {
+        let dir = tempfile::tempdir().unwrap();
+        let sink = FileSink::open(FileSinkConfig::new(dir.path(), FileFormat::Csv)).unwrap();
+        let mut confirmed = Signal { id: "b".to_string(), ..Signal::test("AAPL", "flag, bull", 0.5, 100.0) };
+        confirmed.status = Some(SignalStatus::Confirmed);
+        confirmed.linked_id = Some("a".to_string());
+        sink.publish_signal(&Signal { id: "a".to_string(), ..Signal::test("AAPL", "flag", 0.5, 100.0) }).await.unwrap();
+        sink.publish_signal(&confirmed).await.unwrap();
+        sink.publish_tick(&Tick { volume: 2.0, side: Some(TradeSide::Buy), ..Tick::test("AAPL", 1.5, 100.0) }).await.unwrap();
+
+        let signals = std::fs::read_to_string(dir.path().join("signals-000001.csv")).unwrap();
+        assert_eq!(
+            signals,
+            "id,symbol,score,pattern,timestamp,status,linked_id\n\
+             a,AAPL,0.5,flag,100,,\n\
+             b,AAPL,0.5,\"flag, bull\",100,confirmed,a\n"
+        );
+        let ticks = std::fs::read_to_string(dir.path().join("ticks-000001.csv")).unwrap();
+        assert_eq!(ticks, "symbol,price,volume,timestamp,side,feed\nAAPL,1.5,2,100,buy,\n");
+    }
+
+    #[tokio::test]
+    async fn test_rotates_and_prunes_jsonl() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = FileSinkConfig { max_bytes: 1, max_files: 2, ..FileSinkConfig::new(dir.path(), FileFormat::Jsonl) };
+        let sink = FileSink::open(config.clone()).unwrap();
+        for price in [1.0, 2.0, 3.0] {
+            sink.publish_tick(&Tick::test("AAPL", price, 100.0)).await.unwrap();
+        }
+        let names: Vec<_> = existing(dir.path(), "ticks", "jsonl").unwrap().into_iter().map(|(n, _)| n).collect();
+        assert_eq!(names, vec![2, 3]);
+        let last: Tick = serde_json::from_str(&std::fs::read_to_string(dir.path().join("ticks-000003.jsonl")).unwrap()).unwrap();
+        assert_eq!(last.price, 3.0);
+
+        // a restart carries on after the existing files
+        let sink = FileSink::open(config).unwrap();
+        sink.publish_tick(&Tick::test("AAPL", 4.0, 100.0)).await.unwrap();
+        assert!(dir.path().join("ticks-000004.jsonl").exists());
+    }
+}
@@ -0,0 +1,37 @@
+//! Test fixtures shared by the unit tests of every module.
+
+use crate::publisher::{Signal, Tick};
+
+impl Signal {
+    /// A bare signal with id `{symbol}_{timestamp}` and no metadata
+    pub(crate) fn test(symbol: &str, pattern: &str, score: f64, timestamp: f64) -> Self {
+        Signal {
+            id: format!("{}_{}", symbol, timestamp),
+            symbol: symbol.to_string(),
+            score,
+            pattern: pattern.to_string(),
+            timestamp,
+            meta: None,
+            pattern_meta: None,
+            status: None,
+            linked_id: None,
+            extra: Default::default(),
+        }
+    }
+}
+
+impl Tick {
+    /// A one-share trade with no side, receive time, feed or book
+    pub(crate) fn test(symbol: &str, price: f64, timestamp: f64) -> Self {
+        Tick {
+            symbol: symbol.to_string(),
+            price,
+            volume: 1.0,
+            timestamp,
+            side: None,
+            received_at: None,
+            feed: None,
+            book: None,
+        }
+    }
+}
@@ -0,0 +1,102 @@
+//! Per-deployment feature flags.
+//!
+//! Flags gate experimental subsystems without separate builds. Initial state
+//! comes from configuration (`name=on,name=off`), can be changed at runtime,
+//! and enabled flags are stamped on signal metadata so consumers can tell
+//! which experiments produced a signal.
+
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Harmonic (XABCD) pattern detection
+pub const HARMONIC_PATTERNS: &str = "harmonic_patterns";
+/// Triangle / wedge / flag continuation detection
+pub const CONTINUATION_PATTERNS: &str = "continuation_patterns";
+/// Wyckoff accumulation / distribution range detection
+pub const WYCKOFF_PATTERNS: &str = "wyckoff_patterns";
+/// Sampled feature attributions for inferred patterns
+pub const FEATURE_ATTRIBUTION: &str = "feature_attribution";
+/// Liquidity sweep / absorption / iceberg detection on aggressor-side trades
+pub const ORDER_FLOW_PATTERNS: &str = "order_flow_patterns";
+
+/// Flags known to the engine and their defaults
+pub const KNOWN_FLAGS: [(&str, bool); 5] = [
+    (HARMONIC_PATTERNS, true),
+    (CONTINUATION_PATTERNS, true),
+    (WYCKOFF_PATTERNS, true),
+    (FEATURE_ATTRIBUTION, true),
+    (ORDER_FLOW_PATTERNS, true),
+];
+
+/// Current flag states
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FeatureFlags {
+    flags: BTreeMap<String, bool>,
+}
+
+impl Default for FeatureFlags {
+    fn default() -> Self {
+        Self {
+            flags: KNOWN_FLAGS.iter().map(|(name, on)| (name.to_string(), *on)).collect(),
+        }
+    }
+}
+
+impl FeatureFlags {
+    /// Defaults overridden by a `name=on|off,...` spec (a bare name enables it)
+    pub fn from_spec(spec: &str) -> Result<Self> {
+        let mut flags = Self::default();
+        for item in spec.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            let (name, value) = match item.split_once('=') {
+                Some((name, value)) => (name.trim(), parse_state(value)?),
+                None => (item, true),
+            };
+            flags.set(name, value);
+        }
+        Ok(flags)
+    }
+
+    pub fn is_enabled(&self, name: &str) -> bool {
+        self.flags.get(name).copied().unwrap_or(false)
+    }
+
+    /// Set a flag, returning its previous state
+    pub fn set(&mut self, name: &str, enabled: bool) -> Option<bool> {
+        self.flags.insert(name.to_string(), enabled)
+    }
+
+    /// Names of enabled flags, sorted
+    pub fn enabled(&self) -> Vec<String> {
+        self.flags.iter().filter(|(_, on)| **on).map(|(name, _)| name.clone()).collect()
+    }
+
+    pub fn all(&self) -> &BTreeMap<String, bool> {
+        &self.flags
+    }
+}
+
+/// Parse an on/off value as accepted in specs and the admin API
+pub fn parse_state(value: &str) -> Result<bool> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "on" | "true" | "1" | "yes" | "enabled" => Ok(true),
+        "off" | "false" | "0" | "no" | "disabled" => Ok(false),
+        other => Err(anyhow!("invalid flag value: {}", other)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spec_overrides_defaults() {
+        let flags = FeatureFlags::from_spec("harmonic_patterns=off, canary_model").unwrap();
+        assert!(!flags.is_enabled(HARMONIC_PATTERNS));
+        assert!(flags.is_enabled(CONTINUATION_PATTERNS));
+        assert!(flags.is_enabled("canary_model"));
+        assert!(!flags.is_enabled("unknown"));
+        assert_eq!(flags.enabled(), vec!["canary_model", "continuation_patterns", "feature_attribution", "order_flow_patterns", "wyckoff_patterns"]);
+        assert!(FeatureFlags::from_spec("x=maybe").is_err());
+    }
+}
@@ -0,0 +1,314 @@
+//! gRPC signal sink.
+//!
+//! Streams published signals to a strategy engine over the bidirectional
+//! `pattern_engine.SignalSink/Stream` call described in `proto/signals.proto`,
+//! as an alternative (or addition) to the Redis signals stream. The message
+//! types are written out with `prost` derives so no `protoc` is needed.
+//!
+//! Signals are queued without blocking the tick path. A background task keeps
+//! the stream open, reconnecting with exponential backoff. Sent signals stay
+//! in flight until the server acknowledges their id and are re-sent, oldest
+//! first, after a reconnect. When the queue is full new signals are dropped
+//! and counted.
+
+use anyhow::{anyhow, bail, Result};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::codec::ProstCodec;
+use tonic::transport::Endpoint;
+use tracing::{info, warn};
+
+use crate::publisher::Signal;
+
+/// Messages of `proto/signals.proto`
+pub mod proto {
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Signal {
+        #[prost(string, tag = "1")]
+        pub id: String,
+        #[prost(string, tag = "2")]
+        pub symbol: String,
+        #[prost(double, tag = "3")]
+        pub score: f64,
+        #[prost(string, tag = "4")]
+        pub pattern: String,
+        #[prost(double, tag = "5")]
+        pub timestamp: f64,
+        /// Full signal as JSON
+        #[prost(string, tag = "6")]
+        pub json: String,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Ack {
+        #[prost(string, tag = "1")]
+        pub id: String,
+    }
+}
+
+const STREAM_PATH: &str = "/pattern_engine.SignalSink/Stream";
+
+impl TryFrom<&Signal> for proto::Signal {
+    type Error = serde_json::Error;
+
+    fn try_from(signal: &Signal) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: signal.id.clone(),
+            symbol: signal.symbol.clone(),
+            score: signal.score,
+            pattern: signal.pattern.clone(),
+            timestamp: signal.timestamp,
+            json: serde_json::to_string(signal)?,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GrpcSinkConfig {
+    /// e.g. `http://strategy-engine:50051`
+    pub endpoint: String,
+    /// Signals queued while disconnected or slow
+    pub queue: usize,
+    /// Signals sent but not yet acknowledged
+    pub max_in_flight: usize,
+    pub connect_timeout: Duration,
+    pub reconnect_min: Duration,
+    pub reconnect_max: Duration,
+}
+
+impl GrpcSinkConfig {
+    pub fn new(endpoint: &str) -> Self {
+        Self {
+            endpoint: endpoint.to_string(),
+            queue: 10_000,
+            max_in_flight: 1_000,
+            connect_timeout: Duration::from_secs(5),
+            reconnect_min: Duration::from_millis(250),
+            reconnect_max: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Counters for `/metrics`-style reporting
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct GrpcSinkStats {
+    pub connected: bool,
+    pub sent: u64,
+    pub acked: u64,
+    pub resent: u64,
+    pub dropped: u64,
+    pub reconnects: u64,
+}
+
+#[derive(Default)]
+struct Counters {
+    connected: AtomicBool,
+    sent: AtomicU64,
+    acked: AtomicU64,
+    resent: AtomicU64,
+    dropped: AtomicU64,
+    reconnects: AtomicU64,
+}
+
+/// Sent signals awaiting acknowledgement, oldest first
+#[derive(Debug, Default)]
+pub struct InFlight {
+    pending: VecDeque<proto::Signal>,
+}
+
+impl InFlight {
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    pub fn push(&mut self, signal: proto::Signal) {
+        self.pending.push_back(signal);
+    }
+
+    /// Drop the signal with `id`; false when it was not in flight
+    pub fn ack(&mut self, id: &str) -> bool {
+        match self.pending.iter().position(|s| s.id == id) {
+            Some(i) => {
+                self.pending.remove(i);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Signals to re-send on a new stream, oldest first
+    pub fn replay(&self) -> impl Iterator<Item = &proto::Signal> {
+        self.pending.iter()
+    }
+}
+
+/// Handle for queueing signals to the gRPC stream; dropping it stops the
+/// background task once the queue drains
+pub struct GrpcSink {
+    queue: mpsc::Sender<proto::Signal>,
+    counters: Arc<Counters>,
+    endpoint: String,
+}
+
+impl GrpcSink {
+    /// Validate the endpoint and start the streaming task
+    pub fn spawn(config: GrpcSinkConfig) -> Result<Self> {
+        let endpoint = Endpoint::from_shared(config.endpoint.clone())
+            .map_err(|e| anyhow!("invalid gRPC endpoint {}: {}", config.endpoint, e))?
+            .connect_timeout(config.connect_timeout);
+        let (queue, rx) = mpsc::channel(config.queue.max(1));
+        let counters = Arc::new(Counters::default());
+        tokio::spawn(run(endpoint, config.clone(), rx, counters.clone()));
+        Ok(Self { queue, counters, endpoint: config.endpoint })
+    }
+
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    /// Queue `signal` without waiting; false when it was dropped
+    pub fn send(&self, signal: &Signal) -> bool {
+        let queued = proto::Signal::try_from(signal).is_ok_and(|msg| self.queue.try_send(msg).is_ok());
+        if !queued {
+            self.counters.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+        queued
+    }
+
+    pub fn stats(&self) -> GrpcSinkStats {
+        let c = &self.counters;
+        GrpcSinkStats {
+            connected: c.connected.load(Ordering::Relaxed),
+            sent: c.sent.load(Ordering::Relaxed),
+            acked: c.acked.load(Ordering::Relaxed),
+            resent: c.resent.load(Ordering::Relaxed),
+            dropped: c.dropped.load(Ordering::Relaxed),
+            reconnects: c.reconnects.load(Ordering::Relaxed),
+        }
+    }
+}
+
+async fn run(endpoint: Endpoint, config: GrpcSinkConfig, mut queue: mpsc::Receiver<proto::Signal>, counters: Arc<Counters>) {
+    let mut in_flight = InFlight::default();
+    let mut backoff = config.reconnect_min;
+    loop {
+        let result = session(&endpoint, &config, &mut queue, &mut in_flight, &counters).await;
+        // a stream that got established starts the backoff over
+        if counters.connected.swap(false, Ordering::Relaxed) {
+            backoff = config.reconnect_min;
+        }
+        match result {
+            // queue closed and everything acknowledged
+            Ok(()) => return,
+            Err(e) => warn!("gRPC sink {} disconnected ({} in flight): {}", config.endpoint, in_flight.len(), e),
+        }
+        tokio::time::sleep(backoff).await;
+        backoff = (backoff * 2).min(config.reconnect_max);
+        counters.reconnects.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// One stream: re-send what is in flight, then forward the queue while
+/// collecting acks
+async fn session(
+    endpoint: &Endpoint,
+    config: &GrpcSinkConfig,
+    queue: &mut mpsc::Receiver<proto::Signal>,
+    in_flight: &mut InFlight,
+    counters: &Counters,
+) -> Result<()> {
+    let channel = endpoint.connect().await?;
+    let mut client = tonic::client::Grpc::new(channel);
+    client.ready().await.map_err(|e| anyhow!("service not ready: {}", e))?;
+
+    let (tx, rx) = mpsc::channel(config.max_in_flight.max(1) + in_flight.len());
+    for msg in in_flight.replay() {
+        tx.try_send(msg.clone()).map_err(|_| anyhow!("stream buffer full"))?;
+        counters.resent.fetch_add(1, Ordering::Relaxed);
+    }
+    let path = tonic::codegen::http::uri::PathAndQuery::from_static(STREAM_PATH);
+    let mut acks = client
+        .streaming(tonic::Request::new(ReceiverStream::new(rx)), path, ProstCodec::<proto::Signal, proto::Ack>::default())
+        .await?
+        .into_inner();
+    counters.connected.store(true, Ordering::Relaxed);
+    info!("gRPC sink connected to {}", config.endpoint);
+
+    let mut tx = Some(tx);
+    loop {
+        let room = in_flight.len() < config.max_in_flight;
+        tokio::select! {
+            msg = queue.recv(), if room && tx.is_some() => match msg {
+                Some(msg) => {
+                    in_flight.push(msg.clone());
+                    tx.as_ref().expect("checked above").send(msg).await.map_err(|_| anyhow!("request stream closed"))?;
+                    counters.sent.fetch_add(1, Ordering::Relaxed);
+                }
+                // engine shutting down: finish the request stream, keep reading acks
+                None => tx = None,
+            },
+            ack = acks.message() => match ack? {
+                Some(ack) => {
+                    if in_flight.ack(&ack.id) {
+                        counters.acked.fetch_add(1, Ordering::Relaxed);
+                    }
+                    if tx.is_none() && in_flight.is_empty() {
+                        return Ok(());
+                    }
+                }
+                None if tx.is_none() && in_flight.is_empty() => return Ok(()),
+                None => bail!("server closed the stream"),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prost::Message;
+
+    #[test]
+    fn test_proto_round_trip() {
+        let msg = proto::Signal::try_from(&Signal::test("AAPL", "double_top", 0.7, 1.5)).unwrap();
+        let decoded = proto::Signal::decode(msg.encode_to_vec().as_slice()).unwrap();
+        assert_eq!(decoded, msg);
+        assert_eq!((decoded.symbol.as_str(), decoded.score), ("AAPL", 0.7));
+        let full: Signal = serde_json::from_str(&decoded.json).unwrap();
+        assert_eq!(full.pattern, "double_top");
+    }
+
+    #[test]
+    fn test_in_flight_acks_and_replay_order() {
+        let mut in_flight = InFlight::default();
+        for ts in [1.0, 2.0, 3.0] {
+            in_flight.push(proto::Signal::try_from(&Signal::test("AAPL", "double_top", 0.7, ts)).unwrap());
+        }
+        assert!(in_flight.ack("AAPL_2"));
+        assert!(!in_flight.ack("AAPL_2"));
+        let ids: Vec<_> = in_flight.replay().map(|s| s.id.as_str()).collect();
+        assert_eq!(ids, vec!["AAPL_1", "AAPL_3"]);
+    }
+
+    #[tokio::test]
+    async fn test_queue_drops_when_full() {
+        assert!(GrpcSink::spawn(GrpcSinkConfig::new("not a uri")).is_err());
+        // nothing listens on port 1, so queued signals stay queued
+        let config = GrpcSinkConfig { queue: 2, ..GrpcSinkConfig::new("http://127.0.0.1:1") };
+        let sink = GrpcSink::spawn(config).unwrap();
+        let signal = Signal::test("AAPL", "double_top", 0.7, 0.0);
+        assert!(sink.send(&signal) && sink.send(&signal));
+        assert!(!sink.send(&signal));
+        let stats = sink.stats();
+        assert_eq!((stats.connected, stats.sent, stats.dropped), (false, 0, 1));
+    }
+}
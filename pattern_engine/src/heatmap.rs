@@ -0,0 +1,161 @@
+//! Portfolio heat map snapshots.
+//!
+//! Summarises detector activity across the universe into one compact message
+//! per interval: for every symbol the net polarity of its recent signals, its
+//! trend regime and where its volatility ranks against the other symbols. The
+//! strategy engine can allocate top-down from these snapshots without
+//! consuming every individual signal.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+use crate::enrichers;
+use crate::publisher::Signal;
+
+/// Indicator inputs for one symbol at snapshot time
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SymbolInputs {
+    /// (ema_fast - ema_slow) / ema_slow, when both EMAs are warm
+    pub ema_spread_pct: Option<f64>,
+    /// Price standard deviation relative to the last price
+    pub volatility: f64,
+}
+
+/// Heat for one symbol
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SymbolHeat {
+    /// Mean score of signals in the window, -1 (bearish) .. 1 (bullish)
+    pub polarity: f64,
+    /// Signals in the window
+    pub signals: u32,
+    /// `up`, `down` or `range`
+    pub regime: String,
+    /// Volatility percentile across the snapshot's symbols, 0..1
+    pub vol_pct: f64,
+}
+
+/// One heat map message
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HeatSnapshot {
+    pub timestamp: f64,
+    pub window_secs: f64,
+    pub symbols: BTreeMap<String, SymbolHeat>,
+}
+
+/// Recent signal scores per symbol
+#[derive(Debug, Clone)]
+pub struct HeatMap {
+    window_secs: f64,
+    trend_threshold: f64,
+    recent: HashMap<String, VecDeque<(f64, f64)>>,
+}
+
+impl HeatMap {
+    /// Polarity over the last `window_secs`; regimes use the regime enricher's threshold
+    pub fn new(window_secs: f64) -> Self {
+        Self {
+            window_secs,
+            trend_threshold: enrichers::RegimeEnricher::default().trend_threshold,
+            recent: HashMap::new(),
+        }
+    }
+
+    /// Remember a published signal's score (follow-ups of two-phase signals are skipped)
+    pub fn record_signal(&mut self, signal: &Signal) {
+        if signal.linked_id.is_some() {
+            return;
+        }
+        self.recent
+            .entry(signal.symbol.clone())
+            .or_default()
+            .push_back((signal.timestamp, signal.score));
+    }
+
+    /// Build a snapshot at `now` for the given symbols
+    pub fn snapshot(&mut self, now: f64, inputs: &HashMap<String, SymbolInputs>) -> HeatSnapshot {
+        let cutoff = now - self.window_secs;
+        for scores in self.recent.values_mut() {
+            while scores.front().is_some_and(|(ts, _)| *ts < cutoff) {
+                scores.pop_front();
+            }
+        }
+        self.recent.retain(|_, scores| !scores.is_empty());
+
+        let mut vols: Vec<f64> = inputs.values().map(|i| i.volatility).filter(|v| v.is_finite()).collect();
+        vols.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+
+        let symbols = inputs
+            .iter()
+            .map(|(symbol, input)| {
+                let scores = self.recent.get(symbol);
+                let n = scores.map_or(0, VecDeque::len);
+                let polarity = match scores {
+                    Some(s) if n > 0 => (s.iter().map(|(_, score)| score).sum::<f64>() / n as f64).clamp(-1.0, 1.0),
+                    _ => 0.0,
+                };
+                let regime = input.ema_spread_pct.map_or("range", |spread| enrichers::trend(spread, self.trend_threshold));
+                let heat = SymbolHeat {
+                    polarity,
+                    signals: n as u32,
+                    regime: regime.to_string(),
+                    vol_pct: percentile_rank(&vols, input.volatility),
+                };
+                (symbol.clone(), heat)
+            })
+            .collect();
+
+        HeatSnapshot { timestamp: now, window_secs: self.window_secs, symbols }
+    }
+}
+
+/// Fraction of `sorted` values strictly below `v`, with ties counted half
+fn percentile_rank(sorted: &[f64], v: f64) -> f64 {
+    if sorted.len() < 2 || !v.is_finite() {
+        return 0.5;
+    }
+    let below = sorted.partition_point(|x| *x < v);
+    let equal = sorted.partition_point(|x| *x <= v) - below;
+    // exclude the symbol itself from the comparison set
+    let others = (sorted.len() - 1) as f64;
+    ((below as f64 + (equal.saturating_sub(1)) as f64 / 2.0) / others).clamp(0.0, 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_polarity_regime_and_volatility() {
+        let mut map = HeatMap::new(60.0);
+        map.record_signal(&Signal::test("AAPL", "ema_crossover", -1.0, 0.0)); // expires
+        map.record_signal(&Signal::test("AAPL", "ema_crossover", 0.6, 50.0));
+        map.record_signal(&Signal::test("AAPL", "ema_crossover", 0.2, 90.0));
+        map.record_signal(&Signal::test("TSLA", "ema_crossover", -0.5, 95.0));
+
+        let inputs = HashMap::from([
+            ("AAPL".to_string(), SymbolInputs { ema_spread_pct: Some(0.01), volatility: 0.01 }),
+            ("TSLA".to_string(), SymbolInputs { ema_spread_pct: Some(-0.01), volatility: 0.05 }),
+            ("MSFT".to_string(), SymbolInputs { ema_spread_pct: None, volatility: 0.02 }),
+        ]);
+        let snap = map.snapshot(100.0, &inputs);
+        let aapl = &snap.symbols["AAPL"];
+        assert!((aapl.polarity - 0.4).abs() < 1e-12);
+        assert_eq!((aapl.signals, aapl.regime.as_str(), aapl.vol_pct), (2, "up", 0.0));
+        assert_eq!(snap.symbols["TSLA"].regime, "down");
+        assert_eq!(snap.symbols["TSLA"].vol_pct, 1.0);
+        let msft = &snap.symbols["MSFT"];
+        assert_eq!((msft.polarity, msft.signals, msft.regime.as_str(), msft.vol_pct), (0.0, 0, "range", 0.5));
+    }
+
+    #[test]
+    fn test_follow_ups_ignored_and_single_symbol() {
+        let mut map = HeatMap::new(60.0);
+        let mut follow_up = Signal::test("AAPL", "ema_crossover", 0.9, 10.0);
+        follow_up.linked_id = Some("AAPL_1".into());
+        map.record_signal(&follow_up);
+        let inputs = HashMap::from([("AAPL".to_string(), SymbolInputs::default())]);
+        let snap = map.snapshot(20.0, &inputs);
+        assert_eq!(snap.symbols["AAPL"].signals, 0);
+        assert_eq!(snap.symbols["AAPL"].vol_pct, 0.5);
+    }
+}
@@ -0,0 +1,374 @@
+//! In-process store of recent ticks, candles and signals.
+//!
+//! Each symbol (and candle interval) gets a ring buffer ordered by timestamp,
+//! so range queries are two binary searches. Retention is bounded by point
+//! count and age and can differ per universe tier, letting hot symbols keep
+//! more history than the long tail. This answers recent-history questions
+//! without an external database.
+
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::str::FromStr;
+
+use crate::config;
+use crate::patterns::candlestick::Candle;
+use crate::publisher::{Signal, Tick};
+
+/// Anything stored in a series
+pub trait Timestamped {
+    fn ts(&self) -> f64;
+}
+
+impl Timestamped for Tick {
+    fn ts(&self) -> f64 {
+        self.timestamp
+    }
+}
+
+impl Timestamped for Candle {
+    fn ts(&self) -> f64 {
+        self.start as f64
+    }
+}
+
+impl Timestamped for Signal {
+    fn ts(&self) -> f64 {
+        self.timestamp
+    }
+}
+
+/// How much history a series keeps
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Retention {
+    pub max_points: usize,
+    /// Points older than the newest by more than this are dropped
+    pub max_age_secs: f64,
+}
+
+impl Default for Retention {
+    fn default() -> Self {
+        Self { max_points: 10_000, max_age_secs: 3600.0 }
+    }
+}
+
+impl FromStr for Retention {
+    type Err = anyhow::Error;
+
+    /// `POINTS/AGE`, e.g. `50000/6h`
+    fn from_str(s: &str) -> Result<Self> {
+        let (points, age) = s
+            .split_once('/')
+            .ok_or_else(|| anyhow!("retention '{}' is not POINTS/AGE (e.g. 50000/6h)", s))?;
+        Ok(Self {
+            max_points: points
+                .trim()
+                .parse()
+                .map_err(|_| anyhow!("retention '{}': '{}' is not a point count", s, points))?,
+            max_age_secs: config::parse_duration(age)?.as_secs_f64(),
+        })
+    }
+}
+
+/// Retention by universe tier, with a default for untiered symbols
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct RetentionPolicy {
+    pub default: Retention,
+    pub tiers: BTreeMap<u32, Retention>,
+}
+
+impl RetentionPolicy {
+    /// `default=10000/1h,1=100000/1d,2=20000/6h` (any part may be omitted)
+    pub fn from_spec(spec: &str) -> Result<Self> {
+        let mut policy = Self::default();
+        for item in spec.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            let (key, value) = item
+                .split_once('=')
+                .ok_or_else(|| anyhow!("retention entry '{}' is not tier=POINTS/AGE", item))?;
+            let retention: Retention = value.parse()?;
+            match key.trim() {
+                "default" => policy.default = retention,
+                tier => {
+                    let tier = tier.parse().map_err(|_| anyhow!("retention entry '{}': bad tier '{}'", item, tier))?;
+                    policy.tiers.insert(tier, retention);
+                }
+            }
+        }
+        Ok(policy)
+    }
+
+    pub fn for_tier(&self, tier: Option<u32>) -> Retention {
+        tier.and_then(|t| self.tiers.get(&t).copied()).unwrap_or(self.default)
+    }
+}
+
+/// Timestamp-ordered ring buffer
+#[derive(Debug, Clone)]
+pub struct Series<T> {
+    points: VecDeque<T>,
+    retention: Retention,
+}
+
+impl<T: Timestamped + Clone> Series<T> {
+    pub fn new(retention: Retention) -> Self {
+        Self { points: VecDeque::new(), retention }
+    }
+
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// Add a point, keeping timestamp order (late points are inserted in place)
+    pub fn push(&mut self, point: T) {
+        let ts = point.ts();
+        if self.points.back().is_none_or(|last| last.ts() <= ts) {
+            self.points.push_back(point);
+        } else {
+            let idx = self.points.partition_point(|p| p.ts() <= ts);
+            self.points.insert(idx, point);
+        }
+        self.trim();
+    }
+
+    /// Replace the newest point if it has the same timestamp, else push
+    pub fn upsert_last(&mut self, point: T) {
+        match self.points.back_mut() {
+            Some(last) if last.ts() == point.ts() => *last = point,
+            _ => self.push(point),
+        }
+    }
+
+    pub fn set_retention(&mut self, retention: Retention) {
+        self.retention = retention;
+        self.trim();
+    }
+
+    fn trim(&mut self) {
+        while self.points.len() > self.retention.max_points {
+            self.points.pop_front();
+        }
+        if let Some(newest) = self.points.back().map(Timestamped::ts) {
+            let cutoff = newest - self.retention.max_age_secs;
+            let expired = self.points.partition_point(|p| p.ts() < cutoff);
+            self.points.drain(..expired);
+        }
+    }
+
+    /// Points with `from <= ts <= to`, newest `limit` of them, oldest first
+    pub fn range(&self, from: f64, to: f64, limit: usize) -> Vec<T> {
+        let start = self.points.partition_point(|p| p.ts() < from);
+        let end = self.points.partition_point(|p| p.ts() <= to);
+        let start = start.max(end.saturating_sub(limit));
+        self.points.range(start..end.max(start)).cloned().collect()
+    }
+}
+
+/// Range query over one series
+#[derive(Debug, Clone, Copy)]
+pub struct RangeQuery {
+    pub from: f64,
+    pub to: f64,
+    pub limit: usize,
+}
+
+impl Default for RangeQuery {
+    fn default() -> Self {
+        Self { from: f64::NEG_INFINITY, to: f64::INFINITY, limit: 1000 }
+    }
+}
+
+/// Point counts for metrics
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct HistoryStats {
+    pub symbols: usize,
+    pub ticks: usize,
+    pub candles: usize,
+    pub signals: usize,
+}
+
+#[derive(Debug, Default)]
+pub struct TimeSeriesStore {
+    policy: RetentionPolicy,
+    tiers: HashMap<String, Option<u32>>,
+    ticks: HashMap<String, Series<Tick>>,
+    candles: HashMap<(String, u64), Series<Candle>>,
+    signals: HashMap<String, Series<Signal>>,
+}
+
+impl TimeSeriesStore {
+    pub fn new(policy: RetentionPolicy) -> Self {
+        Self { policy, ..Default::default() }
+    }
+
+    fn retention(&self, symbol: &str) -> Retention {
+        self.policy.for_tier(self.tiers.get(symbol).copied().flatten())
+    }
+
+    /// Record a symbol's tier and re-apply its retention to existing series
+    pub fn set_tier(&mut self, symbol: &str, tier: Option<u32>) {
+        if self.tiers.insert(symbol.to_string(), tier) == Some(tier) {
+            return;
+        }
+        let retention = self.policy.for_tier(tier);
+        if let Some(s) = self.ticks.get_mut(symbol) {
+            s.set_retention(retention);
+        }
+        if let Some(s) = self.signals.get_mut(symbol) {
+            s.set_retention(retention);
+        }
+        for ((sym, _), s) in self.candles.iter_mut() {
+            if sym == symbol {
+                s.set_retention(retention);
+            }
+        }
+    }
+
+    pub fn record_tick(&mut self, tick: &Tick) {
+        let retention = self.retention(&tick.symbol);
+        self.ticks
+            .entry(tick.symbol.clone())
+            .or_insert_with(|| Series::new(retention))
+            .push(tick.clone());
+    }
+
+    /// Record a closed (or in-progress) candle; a candle with the same start replaces the previous one
+    pub fn record_candle(&mut self, symbol: &str, interval: u64, candle: &Candle) {
+        let retention = self.retention(symbol);
+        self.candles
+            .entry((symbol.to_string(), interval))
+            .or_insert_with(|| Series::new(retention))
+            .upsert_last(candle.clone());
+    }
+
+    pub fn record_signal(&mut self, signal: &Signal) {
+        let retention = self.retention(&signal.symbol);
+        self.signals
+            .entry(signal.symbol.clone())
+            .or_insert_with(|| Series::new(retention))
+            .push(signal.clone());
+    }
+
+    pub fn ticks(&self, symbol: &str, q: RangeQuery) -> Vec<Tick> {
+        self.ticks.get(symbol).map(|s| s.range(q.from, q.to, q.limit)).unwrap_or_default()
+    }
+
+    pub fn candles(&self, symbol: &str, interval: u64, q: RangeQuery) -> Vec<Candle> {
+        self.candles
+            .get(&(symbol.to_string(), interval))
+            .map(|s| s.range(q.from, q.to, q.limit))
+            .unwrap_or_default()
+    }
+
+    pub fn signals(&self, symbol: &str, q: RangeQuery) -> Vec<Signal> {
+        self.signals.get(symbol).map(|s| s.range(q.from, q.to, q.limit)).unwrap_or_default()
+    }
+
+    /// Load historical ticks, aggregating them into candles for `intervals`
+    pub fn backfill(&mut self, ticks: impl IntoIterator<Item = Tick>, intervals: &[u64]) -> usize {
+        let mut open: HashMap<(String, u64), Candle> = HashMap::new();
+        let mut count = 0;
+        for tick in ticks {
+            for &interval in intervals {
+                let start = (tick.timestamp as u64 / interval) * interval;
+                let key = (tick.symbol.clone(), interval);
+                match open.get_mut(&key) {
+                    Some(c) if c.start == start => {
+                        c.high = c.high.max(tick.price);
+                        c.low = c.low.min(tick.price);
+                        c.close = tick.price;
+                        c.volume += tick.volume;
+                    }
+                    _ => {
+                        if let Some(closed) = open.insert(key, Candle::from_trade(start, tick.price, tick.volume)) {
+                            self.record_candle(&tick.symbol, interval, &closed);
+                        }
+                    }
+                }
+            }
+            self.record_tick(&tick);
+            count += 1;
+        }
+        for ((symbol, interval), candle) in open {
+            self.record_candle(&symbol, interval, &candle);
+        }
+        count
+    }
+
+    pub fn stats(&self) -> HistoryStats {
+        HistoryStats {
+            symbols: self.ticks.len(),
+            ticks: self.ticks.values().map(Series::len).sum(),
+            candles: self.candles.values().map(Series::len).sum(),
+            signals: self.signals.values().map(Series::len).sum(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_series_range_and_order() {
+        let mut s = Series::new(Retention { max_points: 100, max_age_secs: 1e9 });
+        for ts in [1.0, 2.0, 4.0, 5.0] {
+            s.push(Tick::test("A", ts, ts));
+        }
+        s.push(Tick::test("A", 3.0, 3.0)); // late arrival
+        let all: Vec<f64> = s.range(f64::NEG_INFINITY, f64::INFINITY, 100).iter().map(|t| t.timestamp).collect();
+        assert_eq!(all, vec![1.0, 2.0, 3.0, 4.0, 5.0]);
+        let mid: Vec<f64> = s.range(2.0, 4.0, 100).iter().map(|t| t.timestamp).collect();
+        assert_eq!(mid, vec![2.0, 3.0, 4.0]);
+        let newest: Vec<f64> = s.range(0.0, 10.0, 2).iter().map(|t| t.timestamp).collect();
+        assert_eq!(newest, vec![4.0, 5.0]);
+        assert!(s.range(6.0, 9.0, 10).is_empty());
+        assert!(s.range(4.0, 2.0, 10).is_empty());
+    }
+
+    #[test]
+    fn test_retention_by_count_age_and_tier() {
+        let policy = RetentionPolicy::from_spec("default=3/1h, 1=100/10s").unwrap();
+        assert_eq!(policy.for_tier(None).max_points, 3);
+        assert_eq!(policy.for_tier(Some(2)).max_points, 3);
+        assert_eq!(policy.for_tier(Some(1)).max_age_secs, 10.0);
+
+        let mut store = TimeSeriesStore::new(policy);
+        for ts in 0..20 {
+            store.record_tick(&Tick::test("HOT", 1.0, ts as f64));
+            store.record_tick(&Tick::test("COLD", 1.0, ts as f64));
+        }
+        assert_eq!(store.ticks("COLD", RangeQuery::default()).len(), 3);
+        assert_eq!(store.ticks("HOT", RangeQuery::default()).len(), 3);
+
+        store.set_tier("HOT", Some(1));
+        for ts in 20..40 {
+            store.record_tick(&Tick::test("HOT", 1.0, ts as f64));
+        }
+        let hot = store.ticks("HOT", RangeQuery::default());
+        assert_eq!(hot.first().unwrap().timestamp, 29.0);
+        assert_eq!(hot.len(), 11);
+
+        assert!(RetentionPolicy::from_spec("1=lots/1h").is_err());
+        assert!(RetentionPolicy::from_spec("x=10/1h").is_err());
+    }
+
+    #[test]
+    fn test_backfill_builds_candles() {
+        let mut store = TimeSeriesStore::new(RetentionPolicy::default());
+        let ticks = vec![Tick::test("A", 10.0, 0.0), Tick::test("A", 12.0, 30.0), Tick::test("A", 9.0, 59.0), Tick::test("A", 11.0, 61.0)];
+        assert_eq!(store.backfill(ticks, &[60]), 4);
+        let candles = store.candles("A", 60, RangeQuery::default());
+        assert_eq!(candles.len(), 2);
+        assert_eq!((candles[0].open, candles[0].high, candles[0].low, candles[0].close), (10.0, 12.0, 9.0, 9.0));
+        assert_eq!(candles[1].start, 60);
+
+        // an in-progress candle is updated in place
+        store.record_candle("A", 60, &Candle::from_trade(60, 11.0, 5.0));
+        assert_eq!(store.candles("A", 60, RangeQuery::default()).len(), 2);
+        assert_eq!(store.stats().ticks, 4);
+    }
+}
@@ -4,6 +4,14 @@
 //! - EMA: Exponential Moving Average
 //! - VWAP: Volume Weighted Average Price
 //! - Welford: Online variance and standard deviation
+//! - VolumeDelta: Cumulative and rolling signed (aggressor-side) volume
+//! - BurstStats: Time-boxed tick rate, trade size and inter-arrival statistics
+//! - VWAPBands: Session VWAP with volume-weighted standard deviation bands
+
+use crate::publisher::TradeSide;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
 
 /// Exponential Moving Average calculator
 #[derive(Debug, Clone)]
@@ -82,6 +90,12 @@ impl VWAP {
     }
 }
 
+impl Default for VWAP {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// Welford's online algorithm for variance and standard deviation
 #[derive(Debug, Clone)]
 pub struct Welford {
@@ -134,6 +148,312 @@ impl Welford {
     }
 }
 
+impl Default for Welford {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Cumulative volume delta: aggressor buy volume minus sell volume.
+///
+/// Trades without side information are ignored for the totals but still
+/// advance the rolling window so stale entries expire on time.
+#[derive(Debug, Clone)]
+pub struct VolumeDelta {
+    cumulative: f64,
+    window_secs: f64,
+    window: VecDeque<(f64, f64)>, // (timestamp, signed volume)
+    window_sum: f64,
+    sided_count: u64,
+}
+
+impl VolumeDelta {
+    /// Create a new volume delta tracker with a rolling window in seconds
+    pub fn new(window_secs: f64) -> Self {
+        assert!(window_secs > 0.0, "Window must be positive");
+        Self {
+            cumulative: 0.0,
+            window_secs,
+            window: VecDeque::new(),
+            window_sum: 0.0,
+            sided_count: 0,
+        }
+    }
+
+    /// Update with a trade and return the current cumulative delta
+    pub fn update(&mut self, side: Option<TradeSide>, volume: f64, timestamp: f64) -> f64 {
+        if let Some(side) = side {
+            let signed = match side {
+                TradeSide::Buy => volume,
+                TradeSide::Sell => -volume,
+            };
+            self.cumulative += signed;
+            self.window.push_back((timestamp, signed));
+            self.window_sum += signed;
+            self.sided_count += 1;
+        }
+
+        while let Some(&(ts, signed)) = self.window.front() {
+            if timestamp - ts > self.window_secs {
+                self.window_sum -= signed;
+                self.window.pop_front();
+            } else {
+                break;
+            }
+        }
+        if self.window.is_empty() {
+            // avoid accumulating floating point drift once the window drains
+            self.window_sum = 0.0;
+        }
+
+        self.cumulative
+    }
+
+    /// Get cumulative delta since creation
+    pub fn cumulative(&self) -> f64 {
+        self.cumulative
+    }
+
+    /// Get delta over the rolling window
+    pub fn rolling(&self) -> f64 {
+        self.window_sum
+    }
+
+    /// Returns true once at least one trade with side information was seen
+    pub fn has_side_data(&self) -> bool {
+        self.sided_count > 0
+    }
+}
+
+/// Time-boxed burst statistics over a sliding window.
+///
+/// Tracks ticks per second, the largest single trade and the distribution of
+/// inter-arrival times for the last `window_secs` seconds.
+#[derive(Debug, Clone)]
+pub struct BurstStats {
+    window_secs: f64,
+    // (second bucket, tick count) in arrival order
+    buckets: VecDeque<(i64, u32)>,
+    // monotonic decreasing (timestamp, volume) for the sliding max
+    max_trades: VecDeque<(f64, f64)>,
+    // (timestamp, gap since previous tick)
+    gaps: VecDeque<(f64, f64)>,
+    last_timestamp: Option<f64>,
+}
+
+/// Point-in-time view of [`BurstStats`], suitable for features and metrics
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct BurstSnapshot {
+    /// Ticks observed in the most recent one-second bucket
+    pub ticks_per_sec: u32,
+    /// Maximum ticks in any one-second bucket within the window
+    pub max_ticks_per_sec: u32,
+    /// Mean ticks per second across the window
+    pub mean_ticks_per_sec: f64,
+    /// Largest single trade volume within the window
+    pub largest_trade: f64,
+    /// 1st percentile of inter-arrival times (seconds), if any gaps observed
+    pub fast_interarrival_secs: Option<f64>,
+}
+
+impl BurstStats {
+    /// Create a new burst tracker with the given window in seconds
+    pub fn new(window_secs: f64) -> Self {
+        assert!(window_secs >= 1.0, "Window must be at least one second");
+        Self {
+            window_secs,
+            buckets: VecDeque::new(),
+            max_trades: VecDeque::new(),
+            gaps: VecDeque::new(),
+            last_timestamp: None,
+        }
+    }
+
+    /// Record a trade
+    pub fn update(&mut self, volume: f64, timestamp: f64) {
+        let sec = timestamp.floor() as i64;
+        match self.buckets.back_mut() {
+            Some((s, count)) if *s == sec => *count += 1,
+            _ => self.buckets.push_back((sec, 1)),
+        }
+
+        while let Some(&(_, v)) = self.max_trades.back() {
+            if v <= volume {
+                self.max_trades.pop_back();
+            } else {
+                break;
+            }
+        }
+        self.max_trades.push_back((timestamp, volume));
+
+        if let Some(prev) = self.last_timestamp {
+            let gap = (timestamp - prev).max(0.0);
+            // gaps longer than the window say nothing about burstiness
+            if gap <= self.window_secs {
+                self.gaps.push_back((timestamp, gap));
+            }
+        }
+        self.last_timestamp = Some(timestamp);
+
+        self.evict(timestamp);
+    }
+
+    fn evict(&mut self, now: f64) {
+        let cutoff = now - self.window_secs;
+        while matches!(self.buckets.front(), Some(&(s, _)) if (s as f64) <= cutoff) {
+            self.buckets.pop_front();
+        }
+        while matches!(self.max_trades.front(), Some(&(ts, _)) if ts <= cutoff) {
+            self.max_trades.pop_front();
+        }
+        while matches!(self.gaps.front(), Some(&(ts, _)) if ts <= cutoff) {
+            self.gaps.pop_front();
+        }
+    }
+
+    /// Ticks in the one-second bucket holding `now`; 0 once that second has
+    /// passed without a tick (buckets are only evicted on update)
+    pub fn ticks_per_sec(&self, now: f64) -> u32 {
+        match self.buckets.back() {
+            Some(&(sec, count)) if sec == now.floor() as i64 => count,
+            _ => 0,
+        }
+    }
+
+    /// Maximum ticks per second over the window
+    pub fn max_ticks_per_sec(&self) -> u32 {
+        self.buckets.iter().map(|&(_, c)| c).max().unwrap_or(0)
+    }
+
+    /// Mean ticks per second over the window
+    pub fn mean_ticks_per_sec(&self) -> f64 {
+        let total: u32 = self.buckets.iter().map(|&(_, c)| c).sum();
+        total as f64 / self.window_secs
+    }
+
+    /// Largest single trade volume over the window
+    pub fn largest_trade(&self) -> f64 {
+        self.max_trades.front().map(|&(_, v)| v).unwrap_or(0.0)
+    }
+
+    /// Inter-arrival time at the given quantile (0..1) over the window
+    pub fn interarrival_quantile(&self, q: f64) -> Option<f64> {
+        if self.gaps.is_empty() {
+            return None;
+        }
+        let mut gaps: Vec<f64> = self.gaps.iter().map(|&(_, g)| g).collect();
+        gaps.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+        let idx = ((gaps.len() - 1) as f64 * q.clamp(0.0, 1.0)).round() as usize;
+        Some(gaps[idx])
+    }
+
+    /// Returns true when the second holding `now` runs at `factor` times the
+    /// window mean and at least `min_ticks` ticks
+    pub fn is_burst(&self, now: f64, factor: f64, min_ticks: u32) -> bool {
+        let current = self.ticks_per_sec(now);
+        current >= min_ticks && current as f64 >= factor * self.mean_ticks_per_sec()
+    }
+
+    /// Snapshot the statistics as of `now`
+    pub fn snapshot(&self, now: f64) -> BurstSnapshot {
+        BurstSnapshot {
+            ticks_per_sec: self.ticks_per_sec(now),
+            max_ticks_per_sec: self.max_ticks_per_sec(),
+            mean_ticks_per_sec: self.mean_ticks_per_sec(),
+            largest_trade: self.largest_trade(),
+            fast_interarrival_secs: self.interarrival_quantile(0.01),
+        }
+    }
+}
+
+/// Session VWAP with volume-weighted standard deviation bands.
+///
+/// The accumulators reset whenever `timestamp` enters a new session, where
+/// sessions are consecutive `session_secs` buckets aligned to `session_offset_secs`.
+#[derive(Debug, Clone)]
+pub struct VWAPBands {
+    session_secs: f64,
+    session_offset_secs: f64,
+    session: Option<i64>,
+    volume: f64,
+    pv: f64,
+    pv2: f64,
+}
+
+/// VWAP band levels at a point in time
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct VWAPBandLevels {
+    pub vwap: f64,
+    /// Volume-weighted standard deviation of price around VWAP
+    pub sigma: f64,
+    pub upper_1: f64,
+    pub lower_1: f64,
+    pub upper_2: f64,
+    pub lower_2: f64,
+}
+
+impl VWAPBandLevels {
+    /// Distance of `price` from VWAP in sigmas (0 when sigma is 0)
+    pub fn z_score(&self, price: f64) -> f64 {
+        if self.sigma > 0.0 {
+            (price - self.vwap) / self.sigma
+        } else {
+            0.0
+        }
+    }
+}
+
+impl VWAPBands {
+    /// Create bands resetting every `session_secs` seconds (86400 = daily, UTC)
+    pub fn new(session_secs: f64) -> Self {
+        Self::with_offset(session_secs, 0.0)
+    }
+
+    /// Create bands with sessions starting `offset_secs` after each bucket boundary
+    pub fn with_offset(session_secs: f64, offset_secs: f64) -> Self {
+        assert!(session_secs > 0.0, "Session length must be positive");
+        Self {
+            session_secs,
+            session_offset_secs: offset_secs,
+            session: None,
+            volume: 0.0,
+            pv: 0.0,
+            pv2: 0.0,
+        }
+    }
+
+    /// Update with a trade
+    pub fn update(&mut self, price: f64, volume: f64, timestamp: f64) {
+        let session = ((timestamp - self.session_offset_secs) / self.session_secs).floor() as i64;
+        if self.session != Some(session) {
+            self.session = Some(session);
+            self.volume = 0.0;
+            self.pv = 0.0;
+            self.pv2 = 0.0;
+        }
+        self.volume += volume;
+        self.pv += price * volume;
+        self.pv2 += price * price * volume;
+    }
+
+    /// Current band levels, if the session has any volume
+    pub fn levels(&self) -> Option<VWAPBandLevels> {
+        if self.volume <= 0.0 {
+            return None;
+        }
+        let vwap = self.pv / self.volume;
+        let sigma = (self.pv2 / self.volume - vwap * vwap).max(0.0).sqrt();
+        Some(VWAPBandLevels {
+            vwap,
+            sigma,
+            upper_1: vwap + sigma,
+            lower_1: vwap - sigma,
+            upper_2: vwap + 2.0 * sigma,
+            lower_2: vwap - 2.0 * sigma,
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -179,4 +499,83 @@ mod tests {
         assert_eq!(welford.variance(), 4.0); // Sample variance
         assert_eq!(welford.std(), 2.0);
     }
+
+    #[test]
+    fn test_volume_delta() {
+        let mut vd = VolumeDelta::new(10.0);
+
+        // Unsided trades do not count
+        assert_eq!(vd.update(None, 500.0, 0.0), 0.0);
+        assert!(!vd.has_side_data());
+
+        assert_eq!(vd.update(Some(TradeSide::Buy), 100.0, 1.0), 100.0);
+        assert_eq!(vd.update(Some(TradeSide::Sell), 40.0, 2.0), 60.0);
+        assert_eq!(vd.rolling(), 60.0);
+        assert!(vd.has_side_data());
+
+        // First trade falls out of the window, cumulative is unaffected
+        assert_eq!(vd.update(Some(TradeSide::Buy), 10.0, 11.5), 70.0);
+        assert_eq!(vd.rolling(), -30.0);
+
+        // Window drains completely
+        vd.update(None, 0.0, 30.0);
+        assert_eq!(vd.rolling(), 0.0);
+        assert_eq!(vd.cumulative(), 70.0);
+    }
+
+    #[test]
+    fn test_vwap_bands() {
+        let mut bands = VWAPBands::new(100.0);
+        assert!(bands.levels().is_none());
+
+        bands.update(99.0, 10.0, 0.0);
+        bands.update(101.0, 10.0, 1.0);
+        let lv = bands.levels().unwrap();
+        assert_eq!(lv.vwap, 100.0);
+        assert!((lv.sigma - 1.0).abs() < 1e-9);
+        assert!((lv.upper_2 - 102.0).abs() < 1e-9);
+        assert!((lv.z_score(98.0) + 2.0).abs() < 1e-9);
+
+        // New session resets the accumulators
+        bands.update(50.0, 1.0, 150.0);
+        let lv = bands.levels().unwrap();
+        assert_eq!(lv.vwap, 50.0);
+        assert_eq!(lv.sigma, 0.0);
+    }
+
+    #[test]
+    fn test_burst_stats() {
+        let mut bs = BurstStats::new(60.0);
+
+        // One tick per second for ten seconds
+        for i in 0..10 {
+            bs.update(100.0, i as f64);
+        }
+        assert_eq!(bs.ticks_per_sec(9.5), 1);
+        assert_eq!(bs.max_ticks_per_sec(), 1);
+        assert!(!bs.is_burst(9.5, 3.0, 5));
+
+        // Burst of ten ticks within second 10, with one large trade
+        for i in 0..10 {
+            let vol = if i == 3 { 5000.0 } else { 50.0 };
+            bs.update(vol, 10.0 + i as f64 * 0.05);
+        }
+        assert_eq!(bs.ticks_per_sec(10.5), 10);
+        assert_eq!(bs.max_ticks_per_sec(), 10);
+        assert_eq!(bs.largest_trade(), 5000.0);
+        assert!(bs.is_burst(10.5, 3.0, 5));
+        // a later read without new ticks no longer sees the burst
+        assert_eq!(bs.ticks_per_sec(70.0), 0);
+        assert!(!bs.is_burst(70.0, 3.0, 5));
+        assert_eq!(bs.snapshot(70.0).ticks_per_sec, 0);
+        let fast = bs.interarrival_quantile(0.01).unwrap();
+        assert!(fast < 0.06);
+
+        // Everything expires after the window passes
+        bs.update(10.0, 200.0);
+        let snap = bs.snapshot(200.0);
+        assert_eq!(snap.max_ticks_per_sec, 1);
+        assert_eq!(snap.largest_trade, 10.0);
+        assert_eq!(snap.fast_interarrival_secs, None);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,194 @@
+//! Where model scores come from.
+//!
+//! The pattern library scores unknown patterns through [`InferenceBackend`]:
+//! a local ONNX model run by ONNX Runtime ([`OnnxClient`]) or tract
+//! ([`TractClient`]), or a remote inference server
+//! ([`RemoteClient`](crate::remote_inference::RemoteClient)). Calls are
+//! blocking and are made from the inference worker threads.
+
+use crate::inference_metrics::ModelStats;
+use crate::labels::Classification;
+use crate::onnx_client::{OnnxClient, WarmupReport};
+use crate::tract_client::TractClient;
+use anyhow::{anyhow, Result};
+use std::str::FromStr;
+
+pub trait InferenceBackend: Send + Sync {
+    /// Score one feature row, in [-1, 1]
+    fn infer(&self, features: &[f64]) -> Result<f64>;
+
+    /// Score many rows; backends that can batch override this
+    fn infer_batch(&self, rows: &[Vec<f64>]) -> Result<Vec<f64>> {
+        rows.iter().map(|row| self.infer(row)).collect()
+    }
+
+    /// Top class for a classification model; None when the model outputs a
+    /// single score
+    fn classify(&self, _features: &[f64]) -> Result<Option<Classification>> {
+        Ok(None)
+    }
+
+    /// Features per row the model expects, if fixed and known
+    fn feature_count(&self) -> Option<usize> {
+        None
+    }
+
+    /// Model file or URL, for logs and the admin API
+    fn location(&self) -> String;
+
+    /// Backend name reported in the status heartbeat
+    fn kind(&self) -> &'static str;
+
+    /// Pick up a new version of the model
+    fn reload(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// [`InferenceBackend::reload`] if the model changed; returns whether it did
+    fn reload_if_changed(&self) -> Result<bool> {
+        Ok(false)
+    }
+
+    /// Calls answered locally because the backend failed
+    fn fallbacks(&self) -> u64 {
+        0
+    }
+
+    /// Calls, errors, throughput and latency, for backends that track them
+    fn stats(&self) -> Option<ModelStats> {
+        None
+    }
+
+    /// Warm-up timing from the last load, for backends that warm up
+    fn warmup(&self) -> Option<WarmupReport> {
+        None
+    }
+}
+
+impl InferenceBackend for OnnxClient {
+    fn infer(&self, features: &[f64]) -> Result<f64> {
+        OnnxClient::infer(self, features)
+    }
+
+    fn infer_batch(&self, rows: &[Vec<f64>]) -> Result<Vec<f64>> {
+        OnnxClient::infer_batch(self, rows)
+    }
+
+    fn classify(&self, features: &[f64]) -> Result<Option<Classification>> {
+        OnnxClient::classify(self, features)
+    }
+
+    fn feature_count(&self) -> Option<usize> {
+        OnnxClient::feature_count(self)
+    }
+
+    fn location(&self) -> String {
+        self.path().display().to_string()
+    }
+
+    fn kind(&self) -> &'static str {
+        // without the onnx feature the client scores with the built-in stub
+        if cfg!(feature = "onnx") { "onnx" } else { "stub" }
+    }
+
+    fn reload(&self) -> Result<()> {
+        OnnxClient::reload(self)
+    }
+
+    fn reload_if_changed(&self) -> Result<bool> {
+        OnnxClient::reload_if_changed(self)
+    }
+
+    fn stats(&self) -> Option<ModelStats> {
+        Some(OnnxClient::stats(self))
+    }
+
+    fn warmup(&self) -> Option<WarmupReport> {
+        OnnxClient::warmup(self)
+    }
+}
+
+impl InferenceBackend for TractClient {
+    fn infer(&self, features: &[f64]) -> Result<f64> {
+        TractClient::infer(self, features)
+    }
+
+    fn infer_batch(&self, rows: &[Vec<f64>]) -> Result<Vec<f64>> {
+        TractClient::infer_batch(self, rows)
+    }
+
+    fn classify(&self, features: &[f64]) -> Result<Option<Classification>> {
+        TractClient::classify(self, features)
+    }
+
+    fn feature_count(&self) -> Option<usize> {
+        TractClient::feature_count(self)
+    }
+
+    fn location(&self) -> String {
+        self.path().display().to_string()
+    }
+
+    fn kind(&self) -> &'static str {
+        "tract"
+    }
+
+    fn reload(&self) -> Result<()> {
+        TractClient::reload(self)
+    }
+
+    fn reload_if_changed(&self) -> Result<bool> {
+        TractClient::reload_if_changed(self)
+    }
+
+    fn stats(&self) -> Option<ModelStats> {
+        Some(TractClient::stats(self))
+    }
+
+    fn warmup(&self) -> Option<WarmupReport> {
+        TractClient::warmup(self)
+    }
+}
+
+/// Which backend serves the default model
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BackendKind {
+    /// ONNX model file loaded in-process
+    #[default]
+    Onnx,
+    /// ONNX model file run by tract (`tract` feature)
+    Tract,
+    /// Remote inference server
+    Remote,
+}
+
+impl FromStr for BackendKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "onnx" | "local" => Ok(BackendKind::Onnx),
+            "tract" => Ok(BackendKind::Tract),
+            "remote" => Ok(BackendKind::Remote),
+            other => Err(anyhow!("unknown inference backend: {} (expected onnx, tract or remote)", other)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn test_onnx_client_as_backend() {
+        let client = OnnxClient::new(Path::new("dummy.onnx")).unwrap();
+        let backend: &dyn InferenceBackend = &client;
+        assert_eq!(backend.location(), "dummy.onnx");
+        assert_eq!(backend.kind(), if cfg!(feature = "onnx") { "onnx" } else { "stub" });
+        assert_eq!(backend.infer(&[0.2, 0.4]).unwrap(), client.infer(&[0.2, 0.4]).unwrap());
+        assert_eq!("Remote".parse::<BackendKind>().unwrap(), BackendKind::Remote);
+        assert_eq!("tract".parse::<BackendKind>().unwrap(), BackendKind::Tract);
+        assert!("triton".parse::<BackendKind>().is_err());
+    }
+}
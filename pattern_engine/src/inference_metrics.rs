@@ -0,0 +1,92 @@
+//! Per-model inference telemetry.
+//!
+//! Each backend keeps an [`InferenceMetrics`]: calls (one per `infer`,
+//! `infer_batch` or `classify`), the rows they scored, failed calls and a
+//! latency histogram. Throughput is rows per second since the backend was
+//! created. Warm-up runs are not counted.
+
+use crate::publish_metrics::{LatencyHistogram, LatencySnapshot};
+use anyhow::Result;
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+#[derive(Debug)]
+pub struct InferenceMetrics {
+    calls: AtomicU64,
+    rows: AtomicU64,
+    errors: AtomicU64,
+    latency: LatencyHistogram,
+    since: Instant,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ModelStats {
+    pub calls: u64,
+    /// Feature rows scored
+    pub rows: u64,
+    /// Calls that failed (or, for remote backends, fell back to the stub)
+    pub errors: u64,
+    pub rows_per_sec: f64,
+    pub latency: LatencySnapshot,
+}
+
+impl Default for InferenceMetrics {
+    fn default() -> Self {
+        Self {
+            calls: AtomicU64::new(0),
+            rows: AtomicU64::new(0),
+            errors: AtomicU64::new(0),
+            latency: LatencyHistogram::default(),
+            since: Instant::now(),
+        }
+    }
+}
+
+impl InferenceMetrics {
+    pub fn record(&self, rows: usize, elapsed: Duration, ok: bool) {
+        self.calls.fetch_add(1, Ordering::Relaxed);
+        self.rows.fetch_add(rows as u64, Ordering::Relaxed);
+        if !ok {
+            self.errors.fetch_add(1, Ordering::Relaxed);
+        }
+        self.latency.record(elapsed);
+    }
+
+    /// Run `call` over `rows` rows and record it
+    pub fn measure<T>(&self, rows: usize, call: impl FnOnce() -> Result<T>) -> Result<T> {
+        let start = Instant::now();
+        let result = call();
+        self.record(rows, start.elapsed(), result.is_ok());
+        result
+    }
+
+    pub fn stats(&self) -> ModelStats {
+        let rows = self.rows.load(Ordering::Relaxed);
+        let secs = self.since.elapsed().as_secs_f64();
+        ModelStats {
+            calls: self.calls.load(Ordering::Relaxed),
+            rows,
+            errors: self.errors.load(Ordering::Relaxed),
+            rows_per_sec: if secs > 0.0 { rows as f64 / secs } else { 0.0 },
+            latency: self.latency.snapshot(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_measure_counts_rows_and_errors() {
+        let metrics = InferenceMetrics::default();
+        assert_eq!(metrics.measure(3, || Ok(1)).unwrap(), 1);
+        assert!(metrics.measure(1, || -> Result<()> { anyhow::bail!("no session") }).is_err());
+        metrics.record(2, Duration::from_millis(4), true);
+        let stats = metrics.stats();
+        assert_eq!((stats.calls, stats.rows, stats.errors), (3, 6, 1));
+        assert_eq!(stats.latency.count, 3);
+        assert!(stats.rows_per_sec > 0.0);
+    }
+}
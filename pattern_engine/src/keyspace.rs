@@ -0,0 +1,275 @@
+//! Redis keyspace health monitoring and auto-throttling.
+//!
+//! The engine shares its Redis instance with other services. A periodic
+//! sample of memory usage and stream lengths is mapped to a throttle level;
+//! higher levels tighten stream trimming and forward fewer ticks. Level
+//! changes are returned as ops events for publishing.
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use crate::publisher::{Candle, Signal, Tick};
+use crate::sink::Sink;
+
+/// Memory and stream length thresholds that raise the throttle level
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyspaceThresholds {
+    /// used_memory / maxmemory at which throttling starts
+    pub elevated_memory_ratio: f64,
+    /// used_memory / maxmemory at which throttling is at its strongest
+    pub critical_memory_ratio: f64,
+    /// Stream length that counts as elevated, regardless of memory
+    pub elevated_stream_len: u64,
+    /// Absolute used_memory limit (bytes) when Redis runs without maxmemory
+    pub memory_limit_bytes: Option<u64>,
+}
+
+impl Default for KeyspaceThresholds {
+    fn default() -> Self {
+        Self {
+            elevated_memory_ratio: 0.7,
+            critical_memory_ratio: 0.85,
+            elevated_stream_len: 1_000_000,
+            memory_limit_bytes: None,
+        }
+    }
+}
+
+/// Stream trimming and tick forwarding per throttle level
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThrottlePolicy {
+    /// Approximate MAXLEN for the engine's streams (None = untrimmed)
+    pub normal_maxlen: Option<usize>,
+    pub elevated_maxlen: usize,
+    pub critical_maxlen: usize,
+    /// Fraction of ticks forwarded to the ticks stream
+    pub elevated_tick_sample: f64,
+    pub critical_tick_sample: f64,
+}
+
+impl Default for ThrottlePolicy {
+    fn default() -> Self {
+        Self {
+            normal_maxlen: None,
+            elevated_maxlen: 100_000,
+            critical_maxlen: 10_000,
+            elevated_tick_sample: 0.5,
+            critical_tick_sample: 0.1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ThrottleLevel {
+    #[default]
+    Normal,
+    Elevated,
+    Critical,
+}
+
+/// One observation of the Redis keyspace
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct KeyspaceSample {
+    pub used_memory: u64,
+    /// Configured maxmemory (0 = unlimited)
+    pub maxmemory: u64,
+    pub stream_lengths: HashMap<String, u64>,
+    pub timestamp: f64,
+}
+
+/// Emitted when the throttle level changes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThrottleEvent {
+    pub event: String,
+    pub from: ThrottleLevel,
+    pub to: ThrottleLevel,
+    pub memory_ratio: Option<f64>,
+    pub maxlen: Option<usize>,
+    pub tick_sample_rate: f64,
+    pub sample: KeyspaceSample,
+}
+
+/// Current monitor state for the admin API
+#[derive(Debug, Clone, Serialize)]
+pub struct KeyspaceStatus {
+    pub level: ThrottleLevel,
+    pub maxlen: Option<usize>,
+    pub tick_sample_rate: f64,
+    pub last_sample: Option<KeyspaceSample>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct KeyspaceMonitor {
+    thresholds: KeyspaceThresholds,
+    policy: ThrottlePolicy,
+    level: ThrottleLevel,
+    last_sample: Option<KeyspaceSample>,
+}
+
+impl KeyspaceMonitor {
+    pub fn new(thresholds: KeyspaceThresholds, policy: ThrottlePolicy) -> Self {
+        Self { thresholds, policy, level: ThrottleLevel::Normal, last_sample: None }
+    }
+
+    pub fn level(&self) -> ThrottleLevel {
+        self.level
+    }
+
+    /// MAXLEN to apply at the current level
+    pub fn maxlen(&self) -> Option<usize> {
+        match self.level() {
+            ThrottleLevel::Normal => self.policy.normal_maxlen,
+            ThrottleLevel::Elevated => Some(self.policy.elevated_maxlen),
+            ThrottleLevel::Critical => Some(self.policy.critical_maxlen),
+        }
+    }
+
+    /// Fraction of ticks to forward at the current level
+    pub fn tick_sample_rate(&self) -> f64 {
+        match self.level() {
+            ThrottleLevel::Normal => 1.0,
+            ThrottleLevel::Elevated => self.policy.elevated_tick_sample,
+            ThrottleLevel::Critical => self.policy.critical_tick_sample,
+        }
+    }
+
+    fn memory_ratio(&self, sample: &KeyspaceSample) -> Option<f64> {
+        let limit = if sample.maxmemory > 0 { Some(sample.maxmemory) } else { self.thresholds.memory_limit_bytes };
+        limit.filter(|l| *l > 0).map(|l| sample.used_memory as f64 / l as f64)
+    }
+
+    /// Record a sample; returns an event when the throttle level changes
+    pub fn observe(&mut self, sample: KeyspaceSample) -> Option<ThrottleEvent> {
+        let ratio = self.memory_ratio(&sample);
+        let longest = sample.stream_lengths.values().copied().max().unwrap_or(0);
+        let t = &self.thresholds;
+        let level = match ratio {
+            Some(r) if r >= t.critical_memory_ratio => ThrottleLevel::Critical,
+            Some(r) if r >= t.elevated_memory_ratio => ThrottleLevel::Elevated,
+            _ if longest >= t.elevated_stream_len => ThrottleLevel::Elevated,
+            _ => ThrottleLevel::Normal,
+        };
+
+        let from = self.level();
+        self.level = level;
+        self.last_sample = Some(sample.clone());
+        if from == level {
+            return None;
+        }
+        Some(ThrottleEvent {
+            event: if level > from { "redis_throttle_raised" } else { "redis_throttle_lowered" }.to_string(),
+            from,
+            to: level,
+            memory_ratio: ratio,
+            maxlen: self.maxlen(),
+            tick_sample_rate: self.tick_sample_rate(),
+            sample,
+        })
+    }
+
+    pub fn status(&self) -> KeyspaceStatus {
+        KeyspaceStatus {
+            level: self.level(),
+            maxlen: self.maxlen(),
+            tick_sample_rate: self.tick_sample_rate(),
+            last_sample: self.last_sample.clone(),
+        }
+    }
+}
+
+/// Extract `used_memory` and `maxmemory` from `INFO memory` output
+pub fn parse_memory_info(info: &str) -> (u64, u64) {
+    let field = |name: &str| {
+        info.lines()
+            .find_map(|l| l.trim().strip_prefix(name)?.strip_prefix(':').map(str::to_string))
+            .and_then(|v| v.trim().parse().ok())
+            .unwrap_or(0)
+    };
+    (field("used_memory"), field("maxmemory"))
+}
+
+/// Forwards every signal but only the current level's share of ticks
+pub struct SampledTicks {
+    inner: Arc<dyn Sink>,
+    monitor: Arc<Mutex<KeyspaceMonitor>>,
+}
+
+impl SampledTicks {
+    pub fn new(inner: Arc<dyn Sink>, monitor: Arc<Mutex<KeyspaceMonitor>>) -> Self {
+        Self { inner, monitor }
+    }
+}
+
+#[async_trait]
+impl Sink for SampledTicks {
+    async fn publish_signal(&self, signal: &Signal) -> Result<()> {
+        self.inner.publish_signal(signal).await
+    }
+
+    async fn publish_tick(&self, tick: &Tick) -> Result<()> {
+        let rate = self.monitor.lock().await.tick_sample_rate();
+        if rate >= 1.0 || rand::random::<f64>() < rate {
+            self.inner.publish_tick(tick).await?;
+        }
+        Ok(())
+    }
+
+    /// Candles are few and are never sampled
+    async fn publish_candle(&self, candle: &Candle) -> Result<()> {
+        self.inner.publish_candle(candle).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(used: u64, max: u64, ticks: u64) -> KeyspaceSample {
+        KeyspaceSample {
+            used_memory: used,
+            maxmemory: max,
+            stream_lengths: [("ticks:global".to_string(), ticks)].into_iter().collect(),
+            timestamp: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_levels_and_events() {
+        let mut mon = KeyspaceMonitor::default();
+        assert!(mon.observe(sample(100, 1000, 10)).is_none());
+        assert_eq!(mon.tick_sample_rate(), 1.0);
+
+        let ev = mon.observe(sample(750, 1000, 10)).unwrap();
+        assert_eq!((ev.from, ev.to), (ThrottleLevel::Normal, ThrottleLevel::Elevated));
+        assert_eq!(ev.event, "redis_throttle_raised");
+        assert_eq!(mon.maxlen(), Some(100_000));
+        assert!(mon.observe(sample(760, 1000, 10)).is_none());
+
+        mon.observe(sample(900, 1000, 10)).unwrap();
+        assert_eq!(mon.level(), ThrottleLevel::Critical);
+        assert_eq!(mon.tick_sample_rate(), 0.1);
+
+        let ev = mon.observe(sample(100, 1000, 10)).unwrap();
+        assert_eq!(ev.event, "redis_throttle_lowered");
+        assert_eq!(mon.maxlen(), None);
+    }
+
+    #[test]
+    fn test_stream_length_and_unlimited_memory() {
+        let mut mon = KeyspaceMonitor::default();
+        // no maxmemory and no configured limit: only stream length counts
+        mon.observe(sample(u64::MAX / 2, 0, 2_000_000)).unwrap();
+        assert_eq!(mon.level(), ThrottleLevel::Elevated);
+    }
+
+    #[test]
+    fn test_parse_memory_info() {
+        let info = "# Memory\r\nused_memory:1048576\r\nused_memory_human:1.00M\r\nmaxmemory:4194304\r\n";
+        assert_eq!(parse_memory_info(info), (1_048_576, 4_194_304));
+    }
+}
@@ -0,0 +1,135 @@
+//! Class labels for classification models.
+//!
+//! A model whose output is a probability vector over pattern classes ships a
+//! label file next to it, `pattern_model.onnx` → `pattern_model.labels.txt`,
+//! with one pattern name per line in class-index order. Blank lines and
+//! `#` comments are skipped. The top class becomes the inferred pattern's
+//! name and its probability the confidence.
+
+use anyhow::{bail, Context, Result};
+use std::path::{Path, PathBuf};
+
+/// Top class of one classification
+#[derive(Debug, Clone, PartialEq)]
+pub struct Classification {
+    /// Pattern name from the label file
+    pub label: String,
+    pub class: usize,
+    /// Probability of the top class, 0..1
+    pub confidence: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClassLabels {
+    names: Vec<String>,
+}
+
+impl ClassLabels {
+    /// Parse and validate a label file
+    pub fn parse(text: &str) -> Result<Self> {
+        let names: Vec<String> = text
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty() && !l.starts_with('#'))
+            .map(str::to_string)
+            .collect();
+        if names.len() < 2 {
+            bail!("label file needs at least two classes, has {}", names.len());
+        }
+        for (i, name) in names.iter().enumerate() {
+            if names[..i].contains(name) {
+                bail!("label '{}' is listed twice", name);
+            }
+        }
+        Ok(Self { names })
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path).with_context(|| format!("reading labels {}", path.display()))?;
+        Self::parse(&text).with_context(|| format!("invalid labels {}", path.display()))
+    }
+
+    /// Where the labels for `model_path` live
+    pub fn sidecar_path(model_path: &Path) -> PathBuf {
+        model_path.with_extension("labels.txt")
+    }
+
+    /// The labels shipped next to `model_path`, if there are any
+    pub fn load_for_model(model_path: &Path) -> Result<Option<Self>> {
+        let path = Self::sidecar_path(model_path);
+        if !path.is_file() {
+            return Ok(None);
+        }
+        Self::load(&path).map(Some)
+    }
+
+    /// Number of classes
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// Pick the most probable class from one row of model output
+    pub fn top(&self, probabilities: &[f64]) -> Result<Classification> {
+        if probabilities.len() != self.names.len() {
+            bail!("model returned {} probabilities for {} classes", probabilities.len(), self.names.len());
+        }
+        if let Some(p) = probabilities.iter().find(|p| !p.is_finite()) {
+            bail!("model returned a non-finite probability {}", p);
+        }
+        let (class, &p) = probabilities
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(b.1))
+            .expect("at least two classes");
+        Ok(Classification { label: self.names[class].clone(), class, confidence: p.clamp(0.0, 1.0) })
+    }
+}
+
+/// Check a classifier's output shape `[batch, k]` has one value per label
+pub fn check_label_count(output_shape: &[i64], labels: &ClassLabels) -> Result<()> {
+    match output_shape {
+        [_, k] if *k < 0 || *k as usize == labels.len() => Ok(()),
+        _ => bail!("model output {:?} does not match its {} class labels", output_shape, labels.len()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_top_class() {
+        let labels = ClassLabels::parse("# classes\ndouble_top\n\nhead_and_shoulders\n cup_and_handle \n").unwrap();
+        assert_eq!(labels.names(), ["double_top", "head_and_shoulders", "cup_and_handle"]);
+        let top = labels.top(&[0.2, 0.7, 0.1]).unwrap();
+        assert_eq!(top, Classification { label: "head_and_shoulders".into(), class: 1, confidence: 0.7 });
+        assert!(labels.top(&[0.5, 0.5]).is_err());
+        assert!(labels.top(&[0.5, f64::NAN, 0.1]).is_err());
+
+        assert!(check_label_count(&[-1, 3], &labels).is_ok());
+        assert!(check_label_count(&[-1, -1], &labels).is_ok());
+        assert!(check_label_count(&[-1, 4], &labels).is_err());
+        assert!(check_label_count(&[-1], &labels).is_err());
+    }
+
+    #[test]
+    fn test_rejects_bad_label_files() {
+        assert!(ClassLabels::parse("only_one\n").is_err());
+        assert!(ClassLabels::parse("a\nb\na\n").is_err());
+
+        let dir = tempfile::tempdir().unwrap();
+        let model = dir.path().join("pattern_model.onnx");
+        assert_eq!(ClassLabels::sidecar_path(&model), dir.path().join("pattern_model.labels.txt"));
+        assert!(ClassLabels::load_for_model(&model).unwrap().is_none());
+        std::fs::write(ClassLabels::sidecar_path(&model), "a\nb\n").unwrap();
+        assert_eq!(ClassLabels::load_for_model(&model).unwrap().unwrap().len(), 2);
+    }
+}
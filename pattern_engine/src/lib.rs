@@ -5,21 +5,74 @@
 //!
 //! Key features:
 //! - Ultra-low latency signal detection (<1ms target)
-//! - Incremental mathematical functions (EMA, VWAP, Welford)
+//! - Incremental mathematical functions (EMA, VWAP, Welford, volume delta)
 //! - Redis Streams publishing
 //! - Optional ONNX model integration
 //! - Async tokio runtime
 
+pub mod bench;
+pub mod breadth;
+pub mod clock;
+pub mod codec;
+pub mod codegen;
+pub mod config;
+pub mod confirmation;
+pub mod control;
+pub mod dead_letter;
+pub mod dedup;
+pub mod degrade;
+pub mod enrichers;
+pub mod envelope;
+pub mod features;
+pub mod file_sink;
+#[cfg(test)]
+mod fixtures;
+pub mod flags;
+pub mod grpc;
+pub mod heatmap;
+pub mod history;
 pub mod incremental;
+pub mod inference_backend;
+pub mod inference_metrics;
+pub mod keyspace;
+pub mod labels;
+pub mod publish_metrics;
 pub mod publisher;
+pub mod pubsub;
 pub mod onnx_client;
+pub mod orders;
+pub mod pairs;
+pub mod parquet_sink;
 pub mod patterns;
+pub mod postgres_sink;
+pub mod ratelimit;
+pub mod remote_inference;
 pub mod replay;
+pub mod rules;
+pub mod scaler;
+pub mod signal_id;
+pub mod sink;
+pub mod status;
+pub mod subscriber;
+pub mod suppressed;
+pub mod supervisor;
+pub mod symbol_state;
+pub mod tracking;
+pub mod tract_client;
+pub mod training;
+pub mod universe;
+pub mod wal;
+pub mod websocket;
+pub mod zmq_sink;
 
 // Re-export commonly used types
-pub use incremental::{EMA, VWAP, Welford};
-pub use publisher::{Publisher, Signal, SignalMeta, Tick};
+pub use incremental::{BurstSnapshot, BurstStats, EMA, VWAP, VWAPBands, VolumeDelta, Welford};
+pub use publisher::{Publisher, Signal, SignalMeta, SignalStatus, Tick, TradeSide};
 pub use onnx_client::{OnnxClient, default_model_stub};
 pub use patterns::{PatternLibrary, PatternMeta};
+pub use patterns::pipeline::{DetectionPipeline, PatternDetector};
 pub use replay::run_replay;
-pub use replay::run_replay_publish;
\ No newline at end of file
+pub use replay::run_replay_publish;
+pub use replay::run_replay_detect;
+pub use replay::ReplaySpeed;
+pub use universe::{DetectionThresholds, Universe};
\ No newline at end of file
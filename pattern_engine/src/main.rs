@@ -5,216 +5,164 @@
 
 use anyhow::Result;
 use axum::{
-    extract::{State, Query},
-    response::Json,
-    routing::get,
+    extract::{ws::WebSocketUpgrade, Path, State, Query},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Json, Response},
+    routing::{get, post},
     Router,
 };
 use hyper::server::Server;
 use pattern_engine::{
-    incremental::{EMA, VWAP, Welford},
-    publisher::{Publisher, Signal, SignalMeta, Tick},
-    patterns::PatternLibrary,
+    bench::{self, BenchReport, BenchRun, RegressionThresholds},
+    clock::{wall_now, TimestampPolicy, TimestampSource},
+    codec::Compression,
+    codegen::{self, Language},
+    config::{duration_value, env_duration, env_fraction, env_number, env_optional},
+    confirmation::ConfirmationTracker,
+    enrichers::{self, SignalEnricher},
+    control::{IngestGate, PausePolicy, PauseStatus},
+    dead_letter::{DeadLetterQueue, DeadLetterSink, DeadLetterStats, RetryPolicy},
+    dedup::{DedupStats, SignalDeduper},
+    degrade::{DegradationLadder, DegradationLevel, DegradationPolicy, DegradationStatus},
+    flags::{self, FeatureFlags},
+    file_sink::{FileFormat, FileSink, FileSinkConfig},
+    grpc::{GrpcSink, GrpcSinkConfig, GrpcSinkStats},
+    heatmap::{HeatMap, SymbolInputs},
+    history::{HistoryStats, RangeQuery, RetentionPolicy, TimeSeriesStore},
+    pairs::{PairConfig, PairSpec, PairTracker, SpreadStats},
+    parquet_sink::{ParquetSink, ParquetSinkConfig, ParquetSinkStats},
+    postgres_sink::{PostgresSink, PostgresSinkConfig, PostgresSinkStats},
+    breadth::{BreadthConfig, BreadthGroup, BreadthSnapshot, BreadthTracker},
+    keyspace::{KeyspaceMonitor, KeyspaceStatus, KeyspaceThresholds, SampledTicks, ThrottlePolicy},
+    incremental::BurstSnapshot,
+    pubsub::{PubSubSink, RedisMode},
+    ratelimit::{LimiterStats, PublishLimiter, RateLimit},
+    publish_metrics::PublisherStats,
+    publisher::{Publisher, PublisherConfig, Signal, SignalStatus, StreamInfo, StreamLifecycle, TagRoute, Tick, TradeSide},
+    replay,
+    rules::{self, Rule},
+    suppressed::{SuppressedSignal, SuppressionConfig, SuppressionLogger, SuppressionReason},
+    symbol_state::{roll_candles, SymbolState, TickSignals, CANDLE_INTERVALS},
+    supervisor::{SubsystemStatus, Supervisor},
+    tracking::{self, ExperimentTracker, RunRecord},
+    tract_client::TractClient,
+    training::{TrainingExport, TrainingExportConfig, TrainingExportStats, TrainingFormat, TrainingRow},
+    patterns::cache::{InferenceCacheConfig, InferenceCacheStats},
+    inference_backend::BackendKind,
+    inference_metrics::ModelStats,
+    onnx_client::{OnnxClient, OnnxConfig, WarmupReport},
+    remote_inference::RemoteConfig,
+    patterns::ensemble::Ensemble,
+    patterns::exogenous::{ExogenousFeatures, FileProvider},
+    patterns::candlestick::Candle,
+    patterns::pool::{InferenceShed, InferencePool, ShedPolicy},
+    patterns::stats::{PatternStats, PatternSummary},
+    patterns::pipeline::{DetectionPipeline, RULE_FEATURE_NAMES},
+    features::{FeatureSchema, SIGNAL_FEATURES},
+    patterns::builder::DEFAULT_MODEL_PATH,
+    patterns::export::LibraryExport,
+    patterns::registry::{self, ModelRoute},
+    patterns::shadow::ShadowStats,
+    patterns::canary::CanaryStats,
+    patterns::{PatternLibrary, PatternMeta},
+    universe::{self, DetectionThresholds, EffectiveSymbol, ImportMode, Universe},
+    sink::{FanoutSink, Sink, SinkStats, TicksOnly},
+    status::{EngineStatus, ModelStatus, RateMeter},
+    subscriber::{Subscriber, SubscriberConfig},
+    wal::WriteAheadLog,
+    websocket::{self, SignalBroadcast, SymbolFilter},
+    zmq_sink::ZmqSink,
 };
 use serde::Serialize;
-use std::{collections::HashMap, env, sync::Arc, time::Duration};
+use std::{collections::{BTreeMap, HashMap}, env, sync::Arc, time::Duration};
 use std::time::Instant;
 use std::sync::atomic::{AtomicU64, Ordering};
 use tokio::sync::Mutex;
 use tower_http::cors::CorsLayer;
-use tracing::{error, info};
-
-/// Simple OHLC candle used for interval aggregation
-#[derive(Debug, Clone)]
-struct Candle {
-    start: u64,
-    open: f64,
-    high: f64,
-    low: f64,
-    close: f64,
-    volume: f64,
-}
-
-/// Per-symbol state for pattern detection
-#[derive(Debug)]
-struct SymbolState {
-    symbol: String,
-    ema_fast: EMA,
-    ema_slow: EMA,
-    vwap: VWAP,
-    welford: Welford,
-    last_signal_time: f64,
-    signal_cooldown: f64,
-    // Running average for volume and count for simple volume-based features
-    avg_volume: f64,
-    volume_count: u64,
-    // RSI (Wilder) state
-    prev_close: Option<f64>,
-    rsi_avg_gain: f64,
-    rsi_avg_loss: f64,
-    rsi_period: usize,
-    // ATR state
-    atr: f64,
-    atr_period: usize,
-}
-
-impl SymbolState {
-    fn new(symbol: String) -> Self {
-        Self {
-            symbol,
-            ema_fast: EMA::new(0.1), // 10-period equivalent
-            ema_slow: EMA::new(0.05), // 20-period equivalent
-            vwap: VWAP::new(),
-            welford: Welford::new(),
-            last_signal_time: 0.0,
-            signal_cooldown: 30.0, // 30 seconds between signals
-            avg_volume: 0.0,
-            volume_count: 0,
-            prev_close: None,
-            rsi_avg_gain: 0.0,
-            rsi_avg_loss: 0.0,
-            rsi_period: 14,
-            atr: 0.0,
-            atr_period: 14,
-        }
-    }
-
-    /// Update indicators and detect patterns
-    fn update_and_detect(&mut self, price: f64, volume: f64, timestamp: f64) -> Option<Signal> {
-        // Update all indicators
-        let ema_fast = self.ema_fast.update(price);
-        let ema_slow = self.ema_slow.update(price);
-        let vwap_price = self.vwap.update(price, volume);
-        self.welford.update(price);
-
-        // Update running average for volume
-        self.volume_count += 1;
-        let n = self.volume_count as f64;
-        if n == 1.0 {
-            self.avg_volume = volume;
-        } else {
-            self.avg_volume += (volume - self.avg_volume) / n;
-        }
-
-        // RSI and ATR updates
-        if let Some(prev) = self.prev_close {
-            let change = price - prev;
-            let gain = if change > 0.0 { change } else { 0.0 };
-            let loss = if change < 0.0 { -change } else { 0.0 };
-            if self.volume_count as usize <= self.rsi_period {
-                // initial average
-                self.rsi_avg_gain = (self.rsi_avg_gain * (self.volume_count as f64 - 1.0) + gain) / (self.volume_count as f64);
-                self.rsi_avg_loss = (self.rsi_avg_loss * (self.volume_count as f64 - 1.0) + loss) / (self.volume_count as f64);
-            } else {
-                // Wilder smoothing
-                self.rsi_avg_gain = (self.rsi_avg_gain * (self.rsi_period as f64 - 1.0) + gain) / (self.rsi_period as f64);
-                self.rsi_avg_loss = (self.rsi_avg_loss * (self.rsi_period as f64 - 1.0) + loss) / (self.rsi_period as f64);
-            }
-            // ATR (True Range)
-            let tr = (price - prev).abs();
-            if self.atr == 0.0 {
-                self.atr = tr;
-            } else {
-                self.atr = (self.atr * (self.atr_period as f64 - 1.0) + tr) / (self.atr_period as f64);
-            }
-        }
-        self.prev_close = Some(price);
-
-        // Pattern detection logic
-        let mut signal_score = 0.0;
-        let mut pattern_type = None;
-
-        // EMA Crossover Pattern
-        let ema_fast_val = ema_fast;
-        let ema_slow_val = ema_slow;
-        if ema_fast_val > 0.0 && ema_slow_val > 0.0 {
-            let ema_diff = (ema_fast_val - ema_slow_val) / ema_slow_val;
-            if ema_diff.abs() > 0.01 { // 1% difference threshold
-                signal_score += ema_diff * 2.0; // Amplify signal
-                pattern_type = Some("ema_crossover".to_string());
-            }
-        }
-
-        // VWAP Deviation Pattern
-        if vwap_price > 0.0 {
-            let vwap_diff = (price - vwap_price) / vwap_price;
-            if vwap_diff.abs() > 0.005 { // 0.5% deviation threshold
-                signal_score += vwap_diff * 1.5;
-                if pattern_type.is_none() {
-                    pattern_type = Some("vwap_deviation".to_string());
-                }
-            }
-        }
-
-        // Volume Spike Pattern (simplified)
-        if volume > 0.0 {
-            let avg_volume = 1000.0; // Placeholder - should be calculated
-            let volume_ratio = volume / avg_volume;
-            if volume_ratio > 2.0 { // 2x average volume
-                signal_score += if signal_score > 0.0 { 0.3 } else { -0.3 };
-                pattern_type = Some("volume_spike".to_string());
-            }
-        }
-
-        // Volatility Pattern
-        if self.welford.count() > 5 {
-            let volatility = self.welford.std();
-            let price_change = (price - ema_fast_val).abs() / price;
-            if price_change > volatility * 2.0 { // 2 standard deviations
-                signal_score += if signal_score > 0.0 { 0.4 } else { -0.4 };
-                pattern_type = Some("volatility_breakout".to_string());
-            }
-        }
-
-        // Normalize signal score to [-1, 1]
-        signal_score = signal_score.max(-1.0).min(1.0);
-
-        // Only generate signal if significant and not in cooldown
-        if signal_score.abs() > 0.3 && (timestamp - self.last_signal_time) > self.signal_cooldown {
-            self.last_signal_time = timestamp;
-
-            let signal = Signal {
-                id: format!("{}_{}", self.symbol, timestamp as i64),
-                symbol: self.symbol.clone(),
-                score: signal_score,
-                pattern: pattern_type.unwrap_or_else(|| "composite".to_string()),
-                timestamp,
-                meta: Some(SignalMeta {
-                    ema_fast: Some(ema_fast_val),
-                    ema_slow: Some(ema_slow_val),
-                    vwap: Some(vwap_price),
-                    volume,
-                    volatility: self.welford.std(),
-                    rsi: if self.rsi_avg_loss > 0.0 {
-                        let rs = self.rsi_avg_gain / self.rsi_avg_loss;
-                        Some(100.0 - (100.0 / (1.0 + rs)))
-                    } else {
-                        Some(100.0)
-                    },
-                    atr: Some(self.atr),
-                }),
-                pattern_meta: None,
-            };
+use tracing::{error, info, warn};
 
-            Some(signal)
-        } else {
-            None
-        }
-    }
-}
+/// Per-symbol telemetry counters: (inferred, known, total_latency_ns)
+type SymbolTelemetry = (u64, u64, u64);
 
 /// Application state
 #[derive(Clone)]
 struct AppState {
     publisher: Arc<Mutex<Publisher>>,
     symbol_states: Arc<Mutex<HashMap<String, SymbolState>>>,
-    pattern_lib: Arc<PatternLibrary>,
+    // Worker threads running model inference against the pattern library;
+    // replaced as a unit when the pattern library is reloaded
+    inference: Arc<Mutex<Arc<InferencePool>>>,
+    universe: Arc<Mutex<Universe>>,
     // Telemetry
+    ticks_processed: Arc<AtomicU64>,
     inferred_count: Arc<AtomicU64>,
     known_count: Arc<AtomicU64>,
     total_infer_latency_ns: Arc<AtomicU64>,
     // per-symbol telemetry: symbol -> (inferred, known, total_latency_ns)
-    per_symbol_metrics: Arc<Mutex<HashMap<String, (u64, u64, u64)>>>,
+    per_symbol_metrics: Arc<Mutex<HashMap<String, SymbolTelemetry>>>,
+    // Fraction of inferred signals that get feature attributions (0 disables)
+    attribution_sample_rate: f64,
+    // Suppressed-signal logging (None when disabled) and whether to publish to Redis
+    suppression: Option<Arc<Mutex<SuppressionLogger>>>,
+    suppressed_to_stream: bool,
+    // Every configured signal and tick destination (Redis, gRPC, ZeroMQ, WebSocket, files, databases)
+    sinks: Arc<FanoutSink>,
+    // Signals that exhausted their publish retries
+    dead_letters: Arc<DeadLetterQueue>,
+    grpc_sink: Option<Arc<GrpcSink>>,
+    // Live signals for WebSocket subscribers
+    broadcast: SignalBroadcast,
+    // Date/symbol partitioned Parquet files (None when not configured)
+    parquet: Option<Arc<ParquetSink>>,
+    // Batched inserts into PostgreSQL/TimescaleDB (None when not configured)
+    postgres: Option<Arc<PostgresSink>>,
+    // Model inputs with their realized forward returns (None when not configured)
+    training: Option<Arc<TrainingExport>>,
+    // Which tick timestamp each feed uses for time-based logic
+    timestamps: Arc<TimestampPolicy>,
+    // Pause/resume gate in front of tick processing
+    ingest: Arc<Mutex<IngestGate>>,
+    // Provisional tick-level signals awaiting confirmation (None = single-phase emission)
+    confirmation: Option<Arc<Mutex<ConfirmationTracker>>>,
+    // Drops repeats of a pattern within the dedup window (None = off)
+    dedup: Option<Arc<Mutex<SignalDeduper>>>,
+    // Runtime-adjustable feature flags gating experimental subsystems
+    flags: Arc<Mutex<FeatureFlags>>,
+    // Config-defined rules evaluated on every trade tick
+    rules: Arc<Vec<Rule>>,
+    // Weighting of tick-level detector contributions
+    ensemble: Arc<Ensemble>,
+    // Redis memory/stream health and the resulting throttle level
+    keyspace: Arc<Mutex<KeyspaceMonitor>>,
+    // Forward-return hit rates used to calibrate pattern confidence
+    pattern_stats: Arc<Mutex<PatternStats>>,
+    // Configured enrichers filling `Signal::extra`
+    enrichers: Arc<Vec<Box<dyn SignalEnricher>>>,
+    // Per-symbol outside features appended to the model input of unknown patterns
+    exogenous: Arc<ExogenousFeatures>,
+    // Load-shedding level driven by tick latency and throughput
+    degradation: Arc<Mutex<DegradationLadder>>,
+    // Closed candles go to the candles:{interval}s streams
+    publish_candles: bool,
+    // Recent ticks, candles and signals for the history endpoints
+    history: Arc<Mutex<TimeSeriesStore>>,
+    // Recent signal scores summarised into periodic heat map snapshots
+    heatmap: Arc<Mutex<HeatMap>>,
+    // Rolling spread fits for configured symbol pairs
+    pairs: Arc<Mutex<PairTracker>>,
+    // Share of each symbol group above VWAP, for breadth thrusts and washouts
+    breadth: Arc<Mutex<BreadthTracker>>,
+    // Restartable subsystems (feeds, publisher, aggregator, pattern library)
+    supervisor: Arc<Mutex<Supervisor>>,
+    // Bumped to make the feed loop drop its in-progress candles
+    aggregator_epoch: Arc<AtomicU64>,
+}
+
+impl AppState {
+    /// Current inference pool (and through it the pattern library)
+    async fn inference(&self) -> Arc<InferencePool> {
+        self.inference.lock().await.clone()
+    }
 }
 
 /// Health check response
@@ -225,6 +173,10 @@ struct HealthResponse {
     signals_stream: String,
     ticks_stream: String,
     timestamp: f64,
+    degradation: DegradationLevel,
+    /// Warm-up timing per model from its last load
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    model_warmup: BTreeMap<String, WarmupReport>,
 }
 
 #[derive(Serialize)]
@@ -240,8 +192,66 @@ struct MetricsResponse {
     known_count: u64,
     avg_infer_latency_ms: f64,
     per_symbol: std::collections::HashMap<String, PerSymbolMetrics>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    inference_cache: Option<InferenceCacheStats>,
+    inference_queue_depth: usize,
+    inference_shed: u64,
+    inference_fallbacks: u64,
+    degraded_inferences: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    shadow: Option<ShadowStats>,
+    /// Calls, errors, throughput and latency per model
+    models: BTreeMap<String, ModelStats>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    canary: Option<CanaryStats>,
+    degradation: DegradationStatus,
+    history: HistoryStats,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    grpc_sink: Option<GrpcSinkStats>,
+    websocket_connections: usize,
+    sinks: Vec<SinkStats>,
+    dead_letters: DeadLetterStats,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dedup: Option<DedupStats>,
+    /// Redis round trips, failures, retries and XADD latency
+    publisher: PublisherStats,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rate_limits: Option<LimiterStats>,
+    /// Entries waiting in the publisher's write-ahead log
+    #[serde(skip_serializing_if = "Option::is_none")]
+    wal_pending: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    parquet: Option<ParquetSinkStats>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    postgres: Option<PostgresSinkStats>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    training_export: Option<TrainingExportStats>,
+}
+
+#[derive(Serialize)]
+struct ImportResponse {
+    imported: usize,
+    total: usize,
+}
+
+#[derive(Serialize)]
+struct ExportResponse {
+    defaults: DetectionThresholds,
+    symbols: Vec<EffectiveSymbol>,
 }
 
+#[derive(Serialize)]
+struct BurstMetricsResponse {
+    per_symbol: HashMap<String, BurstSnapshot>,
+}
+
+/// Per-symbol, per-interval candles under construction
+type CandleBook = HashMap<String, BTreeMap<u64, Candle>>;
+
+/// Upper bounds for duration settings
+const HOUR: Duration = Duration::from_secs(3600);
+const DAY: Duration = Duration::from_secs(86_400);
+
 /// Generate mock tick data for testing
 async fn generate_mock_ticks(state: AppState) -> Result<()> {
     info!("Generating mock tick data for pattern detection");
@@ -255,13 +265,15 @@ async fn generate_mock_ticks(state: AppState) -> Result<()> {
         ("AMZN".to_string(), 3400.0),
     ].into_iter().collect();
 
+    let heartbeat_secs = heartbeat_cadence()?;
+
     let mut tick_count = 0u64;
-    // Candle maps: symbol -> interval -> current candle
-    use std::collections::BTreeMap;
-    let intervals = vec![60u64, 300u64]; // 60s and 5min
-    let mut candles: HashMap<String, BTreeMap<u64, Candle>> = HashMap::new();
+    let mut candles = CandleBook::new();
+    let mut aggregator_epoch = state.aggregator_epoch.load(Ordering::Relaxed);
 
     loop {
+        flush_held_ticks(&state, &mut candles, &mut aggregator_epoch).await;
+
         for symbol in &symbols {
             // Generate realistic price movement
             let base_price = *base_prices.get(symbol).unwrap_or(&100.0);
@@ -269,327 +281,2216 @@ async fn generate_mock_ticks(state: AppState) -> Result<()> {
             let new_price = base_price + price_change;
             base_prices.insert(symbol.to_string(), new_price);
 
-            let volume = (rand::random::<f64>() * 4900.0 + 100.0) as f64;
+            let volume = rand::random::<f64>() * 4900.0 + 100.0;
+            let side = if rand::random::<bool>() { TradeSide::Buy } else { TradeSide::Sell };
             let timestamp = std::time::SystemTime::now()
                 .duration_since(std::time::UNIX_EPOCH)?
                 .as_secs_f64();
 
-            // Update per-interval candles
-            for &intv in &intervals {
-                let start = (timestamp as u64 / intv) * intv;
-                let entry = candles.entry(symbol.to_string()).or_default();
-                let c = entry.entry(intv).or_insert_with(|| Candle {
-                    start,
-                    open: new_price,
-                    high: new_price,
-                    low: new_price,
-                    close: new_price,
-                    volume,
-                });
-
-                if c.start == start {
-                    // update candle
-                    c.high = c.high.max(new_price);
-                    c.low = c.low.min(new_price);
-                    c.close = new_price;
-                    c.volume += volume;
-                } else {
-                    // Candle closed - run detection on closed candle
-                    let closed = c.clone();
-                    // reset candle to new interval
-                    *c = Candle {
-                        start,
-                        open: new_price,
-                        high: new_price,
-                        low: new_price,
-                        close: new_price,
-                        volume,
-                    };
+            let tick = Tick {
+                symbol: symbol.to_string(),
+                price: new_price,
+                volume,
+                timestamp,
+                side: Some(side),
+                received_at: Some(timestamp),
+                feed: Some("mock".to_string()),
+                book: None,
+            };
+            if !ingest_tick(&state, &mut candles, tick, timestamp).await {
+                continue;
+            }
 
-                    // Run detection using closed.close as price and closed.volume
-                    let mut symbol_states = state.symbol_states.lock().await;
-                    let symbol_state = symbol_states
-                        .entry(symbol.to_string())
-                        .or_insert_with(|| SymbolState::new(symbol.to_string()));
-
-                    if let Some(mut sig) = symbol_state.update_and_detect(closed.close, closed.volume, closed.start as f64) {
-                        // suffix pattern with interval for context
-                        sig.pattern = format!("{}:{}s", sig.pattern, intv);
-
-                        // extract features from sig.meta similar to tick flow
-                        let (price_ema_fast, price_ema_slow, price_vwap, meta_volume, meta_volatility) = if let Some(ref m) = sig.meta {
-                            (
-                                m.ema_fast.unwrap_or(0.0),
-                                m.ema_slow.unwrap_or(0.0),
-                                m.vwap.unwrap_or(0.0),
-                                m.volume,
-                                m.volatility,
-                            )
-                        } else {
-                            (0.0, 0.0, 0.0, closed.volume, 0.0)
-                        };
-
-                        let ema_diff = price_ema_fast - price_ema_slow;
-                        let ema_diff_pct = if price_ema_slow.abs() > f64::EPSILON { ema_diff / price_ema_slow } else { 0.0 };
-                        let vwap_deviation = if price_vwap.abs() > f64::EPSILON { (closed.close - price_vwap) / price_vwap } else { 0.0 };
-                        let volume_ratio = if symbol_state.avg_volume > 0.0 { meta_volume / symbol_state.avg_volume } else { 1.0 };
-                        let momentum = closed.close - price_ema_slow;
-                        let momentum_from_open = closed.close - closed.open;
-                        let open_pct = if closed.open.abs() > f64::EPSILON { (closed.close - closed.open) / closed.open } else { 0.0 };
-                        let volatility = meta_volatility;
-
-                        let features = vec![ema_diff, ema_diff_pct, vwap_deviation, volume_ratio, momentum, momentum_from_open, open_pct, volatility];
-
-                        // Telemetry: measure inference and update known/inferred counters
-                        let start = Instant::now();
-                        let pattern_meta = match state.pattern_lib.lookup_or_infer(&sig.pattern, Some(&features)) {
-                            Ok(pm) => {
-                                // If the pattern is known, increment known_count, else inferred_count
-                                if state.pattern_lib.is_known(&sig.pattern) {
-                                    state.known_count.fetch_add(1, Ordering::Relaxed);
-                                } else {
-                                    state.inferred_count.fetch_add(1, Ordering::Relaxed);
-                                }
-                                Some(pm)
-                            }
-                            Err(e) => {
-                                error!("PatternLibrary inference error: {}", e);
-                                None
-                            }
-                        };
-                        let elapsed = start.elapsed();
-                        let ns = elapsed.as_nanos() as u64;
-                        state.total_infer_latency_ns.fetch_add(ns, Ordering::Relaxed);
-                        // update per-symbol metrics
-                        {
-                            let mut pm = state.per_symbol_metrics.lock().await;
-                            let entry = pm.entry(symbol.to_string()).or_insert((0u64, 0u64, 0u64));
-                            if state.pattern_lib.is_known(&sig.pattern) {
-                                entry.1 += 1; // known
-                            } else {
-                                entry.0 += 1; // inferred
-                            }
-                            entry.2 += ns; // add latency
-                        }
-
-                        sig.pattern_meta = pattern_meta;
-
-                        let publisher = state.publisher.lock().await;
-                        if let Err(e) = publisher.publish_signal(sig).await {
-                            error!("Failed to publish interval signal: {}", e);
-                        }
-                    }
-                }
+            tick_count += 1;
+            if tick_count.is_multiple_of(100) {
+                let active_symbols = state.symbol_states.lock().await.len();
+                info!("Processed {} ticks, {} symbols active", tick_count, active_symbols);
             }
+        }
 
-            // Update pattern detection (tick-level)
-            {
-                let mut symbol_states = state.symbol_states.lock().await;
-                let symbol_state = symbol_states
-                    .entry(symbol.to_string())
-                    .or_insert_with(|| SymbolState::new(symbol.to_string()));
-
-                let signal = symbol_state.update_and_detect(new_price, volume, timestamp);
-
-                // Publish tick data
-                let tick = Tick {
-                    symbol: symbol.to_string(),
-                    price: new_price,
-                    volume,
-                    timestamp,
-                };
+        // Heartbeats would advance frozen state, so skip them while paused
+        let paused = state.ingest.lock().await.is_paused();
+        if let (Some(cadence), false) = (heartbeat_secs, paused) {
+            run_heartbeats(&state, &mut candles, cadence).await?;
+        }
+
+        // Wait before next tick batch
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+}
 
-                let publisher = state.publisher.lock().await;
-                if let Err(e) = publisher.publish_tick(tick).await {
-                    error!("Failed to publish tick: {}", e);
+/// Consume ticks published by other services from a Redis stream consumer
+/// group, acknowledging each batch once it has been processed
+async fn consume_stream_ticks(state: AppState, subscriber: Subscriber<Tick>) -> Result<()> {
+    let config = subscriber.config();
+    info!("Consuming ticks from {} as {}/{}", config.stream, config.group, config.consumer);
+
+    let heartbeat_secs = heartbeat_cadence()?;
+    let mut candles = CandleBook::new();
+    let mut aggregator_epoch = state.aggregator_epoch.load(Ordering::Relaxed);
+    let mut last_claim = tokio::time::Instant::now();
+    // Entries abandoned by consumers that died come first
+    let mut batch = subscriber.claim_pending().await;
+
+    loop {
+        flush_held_ticks(&state, &mut candles, &mut aggregator_epoch).await;
+
+        match batch {
+            Ok(entries) => {
+                let mut ids = Vec::with_capacity(entries.len());
+                for entry in entries {
+                    let now = std::time::SystemTime::now()
+                        .duration_since(std::time::UNIX_EPOCH)?
+                        .as_secs_f64();
+                    let mut tick = entry.message;
+                    tick.received_at.get_or_insert(now);
+                    ingest_tick(&state, &mut candles, tick, now).await;
+                    ids.push(entry.id);
                 }
+                if let Err(e) = subscriber.ack(&ids).await {
+                    warn!("Failed to acknowledge {} ticks: {}", ids.len(), e);
+                }
+            }
+            Err(e) => {
+                warn!("Reading ticks from {} failed: {}", config.stream, e);
+                tokio::time::sleep(config.block).await;
+            }
+        }
 
-                // Publish signal if detected
-                if let Some(mut signal) = signal {
-                    // Extract features from signal.meta if available
-                    let (price_ema_fast, price_ema_slow, price_vwap, meta_volume, meta_volatility) = if let Some(ref m) = signal.meta {
-                        (
-                            m.ema_fast.unwrap_or(0.0),
-                            m.ema_slow.unwrap_or(0.0),
-                            m.vwap.unwrap_or(0.0),
-                            m.volume,
-                            m.volatility,
-                        )
-                    } else {
-                        (0.0, 0.0, 0.0, volume, 0.0)
-                    };
+        let paused = state.ingest.lock().await.is_paused();
+        if let (Some(cadence), false) = (heartbeat_secs, paused) {
+            run_heartbeats(&state, &mut candles, cadence).await?;
+        }
 
-                    // Derived features
-                    let ema_diff = price_ema_fast - price_ema_slow;
-                    let ema_diff_pct = if price_ema_slow.abs() > f64::EPSILON { ema_diff / price_ema_slow } else { 0.0 };
-                    let vwap_deviation = if price_vwap.abs() > f64::EPSILON { (new_price - price_vwap) / price_vwap } else { 0.0 };
-                    let volume_ratio = if symbol_state.avg_volume > 0.0 { meta_volume / symbol_state.avg_volume } else { 1.0 };
-                    let momentum = new_price - price_ema_slow; // simple momentum
-                    let volatility = meta_volatility;
-
-                    let features = vec![ema_diff, ema_diff_pct, vwap_deviation, volume_ratio, momentum, volatility];
-
-                    // Consult pattern library to enrich meta
-                    // Telemetry: measure inference and update known/inferred counters
-                    let start = Instant::now();
-                    let pattern_meta = match state.pattern_lib.lookup_or_infer(&signal.pattern, Some(&features)) {
-                        Ok(pm) => {
-                            if state.pattern_lib.is_known(&signal.pattern) {
-                                state.known_count.fetch_add(1, Ordering::Relaxed);
-                            } else {
-                                state.inferred_count.fetch_add(1, Ordering::Relaxed);
-                            }
-                            Some(pm)
-                        }
-                        Err(e) => {
-                            error!("PatternLibrary inference error: {}", e);
-                            None
-                        }
-                    };
+        batch = if last_claim.elapsed() >= config.claim_idle {
+            last_claim = tokio::time::Instant::now();
+            subscriber.claim_pending().await
+        } else {
+            subscriber.read().await
+        };
+    }
+}
 
-                    let elapsed = start.elapsed();
-                    let ns = elapsed.as_nanos() as u64;
-                    state.total_infer_latency_ns.fetch_add(ns, Ordering::Relaxed);
-                    // update per-symbol metrics for tick-level inference
-                    {
-                        let mut pm = state.per_symbol_metrics.lock().await;
-                        let entry = pm.entry(symbol.to_string()).or_insert((0u64, 0u64, 0u64));
-                        if state.pattern_lib.is_known(&signal.pattern) {
-                            entry.1 += 1;
-                        } else {
-                            entry.0 += 1;
-                        }
-                        entry.2 += ns;
-                    }
+/// Optional heartbeat cadence for quiet symbols (disabled when unset or 0)
+fn heartbeat_cadence() -> Result<Option<f64>> {
+    let heartbeat_secs = Some(env_duration("HEARTBEAT_SECS", Duration::ZERO, Duration::ZERO..=HOUR)?.as_secs_f64())
+        .filter(|v| *v > 0.0);
+    if let Some(cadence) = heartbeat_secs {
+        info!("Heartbeat evaluations enabled every {}s for quiet symbols", cadence);
+    }
+    Ok(heartbeat_secs)
+}
 
-                    signal.pattern_meta = pattern_meta;
+/// Process ticks held back by a pause or by conflation, and drop candles
+/// after an aggregator restart
+async fn flush_held_ticks(state: &AppState, candles: &mut CandleBook, aggregator_epoch: &mut u64) {
+    // The aggregator was restarted: start candles afresh
+    let epoch = state.aggregator_epoch.load(Ordering::Relaxed);
+    if epoch != *aggregator_epoch {
+        *aggregator_epoch = epoch;
+        candles.clear();
+        info!("Candle aggregator reset");
+    }
+    // Replay ticks buffered while the engine was paused
+    let replay = state.ingest.lock().await.drain();
+    if !replay.is_empty() {
+        info!("Replaying {} ticks buffered during pause", replay.len());
+        for tick in replay {
+            process_tick(state, candles, tick, false).await;
+        }
+    }
+    // Flush ticks merged by conflation once the ladder has stepped back
+    let conflated = state.degradation.lock().await.drain_conflated();
+    for tick in conflated {
+        process_tick(state, candles, tick, false).await;
+    }
+}
 
-                    if let Err(e) = publisher.publish_signal(signal).await {
-                        error!("Failed to publish signal: {}", e);
-                    }
+/// Run a live tick through the ingest gate and conflation; false when it was
+/// held back or merged instead of processed
+async fn ingest_tick(state: &AppState, candles: &mut CandleBook, tick: Tick, now: f64) -> bool {
+    let Some(tick) = state.ingest.lock().await.admit(tick) else {
+        return false;
+    };
+    let Some(tick) = state.degradation.lock().await.conflate(tick, now) else {
+        return false;
+    };
+    process_tick(state, candles, tick, false).await;
+    true
+}
+
+/// Synthesize a heartbeat evaluation for every symbol that has seen neither a
+/// trade nor a heartbeat within `cadence` seconds.
+async fn run_heartbeats(state: &AppState, candles: &mut CandleBook, cadence: f64) -> Result<()> {
+    // Quiet time is measured on the symbol's own event clock, so exchange
+    // timestamps that lag or lead the local clock neither fire nor starve heartbeats
+    let wall = wall_now();
+    let quiet: Vec<(String, f64, f64)> = {
+        let mut symbol_states = state.symbol_states.lock().await;
+        symbol_states
+            .iter_mut()
+            .filter_map(|(sym, st)| {
+                let price = st.pipeline.indicators().prev_close?;
+                let at = st.heartbeat_due(wall, cadence)?;
+                Some((sym.clone(), price, at))
+            })
+            .collect()
+    };
+
+    // Both timestamps carry the event clock so any timestamp source reads it
+    for (symbol, price, at) in quiet {
+        let tick = Tick {
+            symbol,
+            price,
+            volume: 0.0,
+            timestamp: at,
+            side: None,
+            received_at: Some(at),
+            feed: None,
+            book: None,
+        };
+        process_tick(state, candles, tick, true).await;
+    }
+    Ok(())
+}
+
+/// Run candle aggregation, detection and publishing for a single tick.
+///
+/// Heartbeat ticks close candles and evaluate detection, but never update
+/// price/volume statistics and are not published to the ticks stream.
+async fn process_tick(state: &AppState, candles: &mut CandleBook, tick: Tick, heartbeat: bool) {
+    let started = Instant::now();
+    let symbol = tick.symbol.clone();
+    let new_price = tick.price;
+    let volume = tick.volume;
+    // The feed's timestamp source drives candle bucketing and cooldowns
+    let timestamp = state.timestamps.event_time(&tick);
+    if !heartbeat {
+        state.ticks_processed.fetch_add(1, Ordering::Relaxed);
+        state.pattern_stats.lock().await.on_price(&symbol, new_price, timestamp);
+        if let Some(training) = &state.training {
+            write_training(training, training.on_price(&symbol, new_price, timestamp)).await;
+        }
+        state.history.lock().await.record_tick(&tick);
+    }
+    let flags = state.flags.lock().await.clone();
+
+    // Update per-interval candles; each closed one runs detection
+    let closed_candles = roll_candles(candles.entry(symbol.clone()).or_default(), new_price, volume, timestamp, heartbeat);
+    for (intv, closed) in closed_candles {
+        state.history.lock().await.record_candle(&symbol, intv, &closed);
+        // Candles go through the sinks like ticks; each sink logs and counts
+        // its own failures, and candles are retried but never dead-lettered
+        if state.publish_candles {
+            let candle = pattern_engine::publisher::Candle::from_bar(&symbol, intv, &closed);
+            let _ = state.sinks.publish_candle(&candle).await;
+        }
+
+        // Run detection using closed.close as price and closed.volume
+        let (interval_signals, vetoed) = {
+            let mut symbol_states = state.symbol_states.lock().await;
+            let symbol_state = symbol_state_entry(state, &mut symbol_states, &symbol).await;
+            symbol_state.on_candle(state.inference().await.library(), &closed, intv, &flags)
+        };
+
+        for v in vetoed {
+            record_suppressed(state, v).await;
+        }
+        for (sig, features) in interval_signals {
+            if admit_signal(state, &sig).await {
+                enrich_and_publish(state, sig, &features).await;
+            }
+        }
+
+        // The shortest interval's close ends the confirmation window
+        if intv == CANDLE_INTERVALS[0] {
+            if let Some(tracker) = state.confirmation.as_ref() {
+                let resolved = tracker.lock().await.resolve(&symbol, closed.close, (closed.start + intv) as f64);
+                // Follow-ups resolve provisionals that already passed dedup
+                for (sig, features) in resolved {
+                    enrich_and_publish(state, sig, &features).await;
                 }
             }
+        }
+    }
 
-            tick_count += 1;
-            if tick_count % 100 == 0 {
-                let active_symbols = state.symbol_states.lock().await.len();
-                info!("Processed {} ticks, {} symbols active", tick_count, active_symbols);
+    // Tick-level detection is shed for cold tiers under stress
+    let tick_detection = {
+        let ladder = state.degradation.lock().await;
+        ladder.level() < DegradationLevel::HotTiersOnly
+            || ladder.tick_detection_enabled(state.universe.lock().await.get(&symbol).and_then(|c| c.tier))
+    };
+
+    // Update pattern detection (tick-level)
+    let (detected, other_signals, suppressed, vetoed) = {
+        let mut symbol_states = state.symbol_states.lock().await;
+        let symbol_state = symbol_state_entry(state, &mut symbol_states, &symbol).await;
+
+        let signals = if heartbeat {
+            TickSignals { signal: symbol_state.on_heartbeat(&tick, timestamp), ..Default::default() }
+        } else if tick_detection {
+            symbol_state.on_tick(state.inference().await.library(), &state.rules, &tick, timestamp, &flags)
+        } else {
+            symbol_state.record_tick(volume, timestamp);
+            TickSignals::default()
+        };
+        let suppressed = symbol_state.pipeline.take_suppressed();
+        (signals.signal, signals.others, suppressed, signals.vetoed)
+    };
+    for s in suppressed.into_iter().chain(vetoed) {
+        record_suppressed(state, s).await;
+    }
+
+    // Every tick sink gets the trade (heartbeats are not market data); the
+    // Redis stream route samples ticks down when Redis is under pressure
+    if !heartbeat {
+        let _ = state.sinks.publish_tick(&tick).await;
+    }
+
+    // Publish signal if detected; duplicates are dropped before they are
+    // tracked for confirmation or counted in stats and training
+    if let Some((mut signal, features)) = detected {
+        if admit_signal(state, &signal).await {
+            if let Some(tracker) = state.confirmation.as_ref() {
+                tracker.lock().await.track(&mut signal, &features, new_price);
+            }
+            enrich_and_publish(state, signal, &features).await;
+        }
+    }
+    for (signal, features) in other_signals {
+        if admit_signal(state, &signal).await {
+            enrich_and_publish(state, signal, &features).await;
+        }
+    }
+    // Spread signals belong to the pair, not this symbol's indicators, so skip enrichment
+    if !heartbeat {
+        let pair_signals = state.pairs.lock().await.update(&symbol, new_price, timestamp);
+        // Breadth signals likewise belong to the symbol group
+        let breadth_signals = state.breadth.lock().await.update(&symbol, new_price, volume, timestamp);
+        for signal in pair_signals.into_iter().chain(breadth_signals) {
+            if admit_signal(state, &signal).await {
+                publish_signal(state, signal).await;
             }
         }
+    }
 
-        // Wait before next tick batch
-        tokio::time::sleep(Duration::from_secs(1)).await;
+    if !heartbeat {
+        state.degradation.lock().await.record(started.elapsed());
     }
 }
 
-/// Health check endpoint
-async fn health_check(State(state): State<AppState>) -> Json<HealthResponse> {
-    let active_symbols = state.symbol_states.lock().await.len();
-    let timestamp = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_secs_f64();
-
-    Json(HealthResponse {
-        status: "healthy".to_string(),
-        active_symbols,
-        signals_stream: "signals:global".to_string(),
-        ticks_stream: "ticks:global".to_string(),
-        timestamp,
-    })
+/// Get or create the state for `symbol`, seeding thresholds from the universe
+async fn symbol_state_entry<'a>(
+    state: &AppState,
+    symbol_states: &'a mut HashMap<String, SymbolState>,
+    symbol: &str,
+) -> &'a mut SymbolState {
+    if !symbol_states.contains_key(symbol) {
+        let (thresholds, tier) = {
+            let uni = state.universe.lock().await;
+            (uni.thresholds_for(symbol), uni.get(symbol).and_then(|c| c.tier))
+        };
+        state.history.lock().await.set_tier(symbol, tier);
+        let mut pipeline = DetectionPipeline::new(symbol, thresholds, state.inference().await.library().gates())
+            .with_ensemble(state.ensemble.clone());
+        pipeline.set_track_suppressed(state.suppression.is_some());
+        let symbol_state = SymbolState::new(pipeline);
+        symbol_states.insert(symbol.to_string(), symbol_state);
+    }
+    symbol_states.get_mut(symbol).expect("symbol state inserted above")
 }
 
-/// Metrics endpoint exposing telemetry counters
-async fn metrics(State(state): State<AppState>, Query(params): Query<HashMap<String, String>>) -> Json<MetricsResponse> {
-    let inferred = state.inferred_count.load(Ordering::Relaxed);
-    let known = state.known_count.load(Ordering::Relaxed);
-    let total_ns = state.total_infer_latency_ns.load(Ordering::Relaxed);
-    // Use inferred-only denominators for average latency
-    let avg_ms = if inferred > 0 {
-        (total_ns as f64 / (inferred as f64)) / 1_000_000.0
+
+/// Consult the pattern library to enrich a signal, record telemetry and publish it
+async fn enrich_and_publish(state: &AppState, mut signal: Signal, features: &[f64]) {
+    // Telemetry: measure inference and update known/inferred counters
+    let start = Instant::now();
+    let inference = state.inference().await;
+    let is_known = inference.library().is_known(&signal.pattern);
+    // Under stress, unknown patterns are published without ML inference
+    let ml_enrichment = state.degradation.lock().await.enrichment_enabled();
+    // Unknown patterns are inferred with the exogenous features appended
+    let exogenous = !is_known && ml_enrichment && !state.exogenous.is_empty();
+    let exogenous_names = if exogenous { state.exogenous.names() } else { Vec::new() };
+    let mut features = features.to_vec();
+    if exogenous {
+        state.exogenous.append(&signal.symbol, &mut features).await;
+    }
+    let names: Vec<&str> = SIGNAL_FEATURES.iter().copied().chain(exogenous_names.iter().map(String::as_str)).collect();
+    let lookup = if is_known || ml_enrichment {
+        Some(inference.lookup_or_infer_for(&signal.pattern, Some(&signal.symbol), Some(&features)).await)
     } else {
-        0.0
+        None
+    };
+    let pattern_meta = match lookup {
+        None => None,
+        Some(Ok(pm)) => {
+            if is_known {
+                state.known_count.fetch_add(1, Ordering::Relaxed);
+            } else {
+                state.inferred_count.fetch_add(1, Ordering::Relaxed);
+            }
+            Some(pm)
+        }
+        // Shed under a full queue: published without ML metadata, counted by the pool
+        Some(Err(e)) if e.is::<InferenceShed>() => None,
+        Some(Err(e)) => {
+            error!("PatternLibrary inference error: {}", e);
+            None
+        }
     };
 
-    // Build per-symbol metrics snapshot
-    let mut per_symbol_map = std::collections::HashMap::new();
-    let pm = state.per_symbol_metrics.lock().await;
-    if let Some(sym_filter) = params.get("symbol") {
-        if let Some((inf, kn, total)) = pm.get(sym_filter) {
-            let avg = if *inf > 0 { (*total as f64 / (*inf as f64)) / 1_000_000.0 } else { 0.0 };
-            per_symbol_map.insert(sym_filter.clone(), PerSymbolMetrics {
-                inferred: *inf,
-                known: *kn,
-                avg_latency_ms: avg,
-            });
+    let elapsed = start.elapsed();
+    let ns = elapsed.as_nanos() as u64;
+    state.total_infer_latency_ns.fetch_add(ns, Ordering::Relaxed);
+    // update per-symbol metrics
+    if is_known || ml_enrichment {
+        let mut pm = state.per_symbol_metrics.lock().await;
+        let entry = pm.entry(signal.symbol.clone()).or_insert((0u64, 0u64, 0u64));
+        if is_known {
+            entry.1 += 1; // known
+        } else {
+            entry.0 += 1; // inferred
         }
-    } else {
-        for (sym, (inf, kn, total)) in pm.iter() {
-            let avg = if *inf > 0 { (*total as f64 / (*inf as f64)) / 1_000_000.0 } else { 0.0 };
-            per_symbol_map.insert(sym.clone(), PerSymbolMetrics {
-                inferred: *inf,
-                known: *kn,
-                avg_latency_ms: avg,
-            });
+        entry.2 += ns; // add latency
+    }
+
+    // Keep structural levels supplied by the detector
+    let pattern_meta = match (pattern_meta, signal.pattern_meta.take()) {
+        (Some(mut pm), Some(levels)) => {
+            pm.neckline = levels.neckline.or(pm.neckline);
+            pm.target = levels.target.or(pm.target);
+            pm.reversal_zone = levels.reversal_zone.or(pm.reversal_zone);
+            Some(pm)
+        }
+        (pm, _) => pm,
+    };
+    signal.pattern_meta = pattern_meta;
+
+    // Log the model input with the uncalibrated output for retraining
+    // (follow-ups of two-phase signals are not re-logged)
+    if let (Some(training), Some(pm), false) = (&state.training, signal.pattern_meta.as_ref(), is_known) {
+        if matches!(signal.status, None | Some(SignalStatus::Provisional)) {
+            match TrainingRow::new(&signal, pm, &names, &features) {
+                Ok(row) => write_training(training, training.record(row)).await,
+                Err(e) => warn!("Skipping training row for {}: {}", signal.id, e),
+            }
         }
     }
+    enrichers::apply(&state.enrichers, &mut signal, &features, &names);
 
-    Json(MetricsResponse {
-        inferred_count: inferred,
-        known_count: known,
-        avg_infer_latency_ms: avg_ms,
-        per_symbol: per_symbol_map,
-    })
+    // Calibrate confidence from the pattern's observed hit rate and track this
+    // emission's forward return (follow-ups of two-phase signals are not re-counted)
+    {
+        let mut stats = state.pattern_stats.lock().await;
+        if let Some(pm) = signal.pattern_meta.as_mut() {
+            pm.confidence = stats.calibrate(&signal.pattern, pm.confidence);
+        }
+        if matches!(signal.status, None | Some(SignalStatus::Provisional)) {
+            stats.record(&signal.symbol, &signal.pattern, signal.score, signal.timestamp);
+        }
+    }
+
+    // Stamp the experiments that were active for this signal
+    let (flags_on, attribution_on) = {
+        let flags = state.flags.lock().await;
+        (flags.enabled(), flags.is_enabled(flags::FEATURE_ATTRIBUTION))
+    };
+    if let Some(meta) = signal.meta.as_mut() {
+        meta.flags = flags_on;
+    }
+
+    // Sampled feature attribution for inferred patterns. It runs on the
+    // inference pool but is awaited here, so the signal still goes out in
+    // order with its attributions attached
+    let sampled = !is_known
+        && ml_enrichment
+        && attribution_on
+        && signal.pattern_meta.is_some()
+        && state.attribution_sample_rate > 0.0
+        && rand::random::<f64>() < state.attribution_sample_rate;
+    if sampled {
+        let names: Vec<String> = names.iter().map(|n| n.to_string()).collect();
+        let attribute = move |lib: &PatternLibrary| {
+            let names: Vec<&str> = names.iter().map(String::as_str).collect();
+            lib.attribute(&features, &names)
+        };
+        match inference.run(attribute).await {
+            Ok(Ok(attributions)) => {
+                if let Some(pm) = signal.pattern_meta.as_mut() {
+                    pm.attributions = attributions;
+                }
+            }
+            Ok(Err(e)) => error!("Feature attribution failed for {}: {}", signal.id, e),
+            Err(e) => error!("Feature attribution task failed for {}: {}", signal.id, e),
+        }
+    }
+    publish_signal(state, signal).await;
 }
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    // Initialize tracing
-    tracing_subscriber::fmt()
-        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
-        .init();
+/// Record a suppressed candidate if it passes tier, sampling and rate limits
+async fn record_suppressed(state: &AppState, suppressed: SuppressedSignal) {
+    let Some(logger) = state.suppression.as_ref() else {
+        return;
+    };
+    {
+        let mut logger = logger.lock().await;
+        if !logger.admit(&suppressed, suppressed.signal.timestamp, rand::random::<f64>()) {
+            return;
+        }
+        if let Err(e) = logger.write(&suppressed) {
+            error!("Failed to write suppressed signal: {}", e);
+        }
+    }
+    if state.suppressed_to_stream {
+        let publisher = state.publisher.lock().await;
+        if let Err(e) = publisher.publish_suppressed(&suppressed).await {
+            error!("Failed to publish suppressed signal: {}", e);
+        }
+    }
+}
 
-    info!("Starting Rust Pattern Engine Service");
+/// False when dedup drops `signal` as a repeat; checked before a signal is
+/// tracked, enriched or counted
+async fn admit_signal(state: &AppState, signal: &Signal) -> bool {
+    let admitted = match &state.dedup {
+        Some(dedup) => dedup.lock().await.admit(signal),
+        None => true,
+    };
+    if !admitted {
+        let detail = "repeat within the dedup window".to_string();
+        record_suppressed(state, SuppressedSignal::new(SuppressionReason::Duplicate, detail, signal.clone())).await;
+    }
+    admitted
+}
 
-    // Environment configuration
-    let redis_url = env::var("REDIS_URL").unwrap_or_else(|_| "redis://redis:6379/0".to_string());
-    let host = env::var("HOST").unwrap_or_else(|_| "0.0.0.0".to_string());
-    let port = env::var("PORT")
-        .unwrap_or_else(|_| "8005".to_string())
-        .parse::<u16>()?;
+async fn publish_signal(state: &AppState, signal: Signal) {
+    state.history.lock().await.record_signal(&signal);
+    state.heatmap.lock().await.record_signal(&signal);
+    // Each sink logs and counts its own failures
+    let _ = state.sinks.publish_signal(&signal).await;
+}
 
-    // Initialize publisher
-    let publisher = Publisher::new(&redis_url)?;
-    let publisher = Arc::new(Mutex::new(publisher));
+/// Periodically sample the Redis keyspace, apply the throttle level to the
+/// publisher and raise an ops event whenever the level changes
+async fn monitor_keyspace(state: AppState, interval: Duration) {
+    loop {
+        tokio::time::sleep(interval).await;
+        let sample = match state.publisher.lock().await.keyspace_sample().await {
+            Ok(sample) => sample,
+            Err(e) => {
+                error!("Redis keyspace sample failed: {}", e);
+                continue;
+            }
+        };
 
-    // Initialize application state and pattern library
-    let symbol_states = Arc::new(Mutex::new(HashMap::new()));
-    // Model path can be provided via MODEL_PATH env var; default to `models/pattern_model.onnx`
-    let model_path_str = env::var("MODEL_PATH").unwrap_or_else(|_| "models/pattern_model.onnx".to_string());
-    let model_path = std::path::Path::new(&model_path_str);
-    let pattern_lib = Arc::new(PatternLibrary::new(model_path)?);
-    let app_state = AppState {
-        publisher: publisher.clone(),
-        symbol_states: symbol_states.clone(),
-        pattern_lib: pattern_lib.clone(),
-        inferred_count: Arc::new(AtomicU64::new(0)),
-        known_count: Arc::new(AtomicU64::new(0)),
-        total_infer_latency_ns: Arc::new(AtomicU64::new(0)),
-        per_symbol_metrics: Arc::new(Mutex::new(HashMap::new())),
-    };
+        let (event, maxlen) = {
+            let mut monitor = state.keyspace.lock().await;
+            (monitor.observe(sample), monitor.maxlen())
+        };
+        let Some(event) = event else {
+            continue;
+        };
 
-    // Start mock tick generation
-    let tick_state = app_state.clone();
-    tokio::spawn(async move {
-        if let Err(e) = generate_mock_ticks(tick_state).await {
-            error!("Tick generation failed: {}", e);
+        warn!(
+            "Redis throttle {:?} -> {:?} (memory ratio {:?}, maxlen {:?}, tick sample {})",
+            event.from, event.to, event.memory_ratio, event.maxlen, event.tick_sample_rate
+        );
+        let mut publisher = state.publisher.lock().await;
+        publisher.set_maxlen(maxlen);
+        if let Err(e) = publisher.publish_ops_event(&event).await {
+            error!("Failed to publish ops event: {}", e);
         }
-    });
+    }
+}
+
+/// Send entries held back by the publish rate limits as the limits refill,
+/// reporting signals that were deferred as suppressed
+async fn release_rate_limited(state: AppState, interval: Duration) {
+    loop {
+        tokio::time::sleep(interval).await;
+        let deferred = {
+            let publisher = state.publisher.lock().await;
+            if let Err(e) = publisher.publish_released().await {
+                warn!("Failed to publish rate-limited entries: {}", e);
+            }
+            match publisher.limiter() {
+                Some(limiter) => limiter.lock().unwrap_or_else(|e| e.into_inner()).take_deferred(),
+                None => Vec::new(),
+            }
+        };
+        for (stream, signal) in deferred {
+            let detail = format!("held back by the {} rate limit", stream);
+            record_suppressed(&state, SuppressedSignal::new(SuppressionReason::RateLimited, detail, signal)).await;
+        }
+    }
+}
+
+/// Write buffered Parquet partitions so quiet symbols still reach disk
+async fn flush_parquet(sink: Arc<ParquetSink>, interval: Duration) {
+    loop {
+        tokio::time::sleep(interval).await;
+        if let Err(e) = sink.flush() {
+            warn!("Failed to write Parquet files: {}", e);
+        }
+    }
+}
+
+/// Write finished training rows on the blocking pool, off the async workers
+async fn write_training(export: &Arc<TrainingExport>, rows: Vec<TrainingRow>) {
+    if rows.is_empty() {
+        return;
+    }
+    let writer = export.clone();
+    match tokio::task::spawn_blocking(move || writer.write(rows)).await {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => warn!("Failed to write training rows to {}: {}", export.dir().display(), e),
+        Err(e) => warn!("Training write task failed: {}", e),
+    }
+}
+
+/// Write buffered training partitions so quiet symbols still reach disk
+async fn flush_training(export: Arc<TrainingExport>, interval: Duration) {
+    loop {
+        tokio::time::sleep(interval).await;
+        let writer = export.clone();
+        match tokio::task::spawn_blocking(move || writer.flush()).await {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => warn!("Failed to write training files: {}", e),
+            Err(e) => warn!("Training flush task failed: {}", e),
+        }
+    }
+}
+
+/// Insert buffered PostgreSQL rows so quiet periods still reach the database
+async fn flush_postgres(sink: Arc<PostgresSink>, interval: Duration) {
+    loop {
+        tokio::time::sleep(interval).await;
+        if let Err(e) = sink.flush().await {
+            warn!("Failed to insert into PostgreSQL: {}", e);
+        }
+    }
+}
+
+/// Replay write-ahead log entries once Redis is reachable again, even when
+/// nothing new is being published
+async fn flush_wal(state: AppState, interval: Duration) {
+    loop {
+        tokio::time::sleep(interval).await;
+        let publisher = state.publisher.lock().await;
+        if let Err(e) = publisher.flush_wal().await {
+            warn!("Write-ahead log replay failed: {}", e);
+        }
+    }
+}
+
+/// Publish a universe-wide heat map snapshot every `interval`
+async fn publish_heatmap(state: AppState, interval: Duration) {
+    loop {
+        tokio::time::sleep(interval).await;
+        let inputs: HashMap<String, SymbolInputs> = {
+            let symbol_states = state.symbol_states.lock().await;
+            symbol_states
+                .iter()
+                .map(|(sym, st)| {
+                    let ind = st.pipeline.indicators();
+                    let ema_spread_pct = match (ind.ema_fast.value(), ind.ema_slow.value()) {
+                        (Some(fast), Some(slow)) if slow > 0.0 => Some((fast - slow) / slow),
+                        _ => None,
+                    };
+                    let volatility = match ind.prev_close {
+                        Some(price) if price > 0.0 => ind.welford.std() / price,
+                        _ => 0.0,
+                    };
+                    (sym.clone(), SymbolInputs { ema_spread_pct, volatility })
+                })
+                .collect()
+        };
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs_f64();
+        let snapshot = state.heatmap.lock().await.snapshot(now, &inputs);
+        if let Err(e) = state.publisher.lock().await.publish_heatmap(&snapshot).await {
+            error!("Failed to publish heat map: {}", e);
+        }
+    }
+}
+
+/// Swap in a retrained model whenever the model file is replaced
+async fn watch_model(state: AppState, interval: Duration) {
+    loop {
+        tokio::time::sleep(interval).await;
+        let library = state.inference.lock().await.library().clone();
+        let path = library.model_location();
+        match tokio::task::spawn_blocking(move || library.reload_model_if_changed()).await {
+            Ok(Ok(true)) => {
+                info!("Reloaded model {}", path);
+                let event = serde_json::json!({ "event": "model_reloaded", "path": path, "reason": "file_changed" });
+                if let Err(e) = state.publisher.lock().await.publish_ops_event(&event).await {
+                    error!("Failed to publish ops event: {}", e);
+                }
+            }
+            Ok(Ok(false)) => {}
+            Ok(Err(e)) => warn!("Keeping the current model; reload of {} failed: {:#}", path, e),
+            Err(e) => warn!("Model reload task failed: {}", e),
+        }
+    }
+}
+
+/// Append an engine heartbeat to the status stream every `interval`
+async fn publish_status(state: AppState, interval: Duration) {
+    let started = Instant::now();
+    let instance = env::var("HOSTNAME").map_or_else(|_| format!("pid-{}", std::process::id()), |host| format!("{}-{}", host, std::process::id()));
+    let mut tick_rate = RateMeter::default();
+    loop {
+        tokio::time::sleep(interval).await;
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs_f64();
+        let ticks_processed = state.ticks_processed.load(Ordering::Relaxed);
+        let inference = state.inference().await;
+        let status = EngineStatus {
+            engine: env!("CARGO_PKG_NAME").to_string(),
+            instance: instance.clone(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            timestamp: now,
+            uptime_secs: started.elapsed().as_secs_f64(),
+            active_symbols: state.symbol_states.lock().await.len(),
+            ticks_processed,
+            tick_rate: tick_rate.update(ticks_processed, now),
+            paused: state.ingest.lock().await.is_paused(),
+            degradation: state.degradation.lock().await.level(),
+            model: ModelStatus {
+                model_id: inference.library().model_id().to_string(),
+                backend: inference.library().backend_kind().to_string(),
+                queue_depth: inference.pending(),
+            },
+        };
+        if let Err(e) = state.publisher.lock().await.publish_status(&status).await {
+            warn!("Failed to publish status heartbeat: {}", e);
+        }
+    }
+}
+
+/// EXOGENOUS_FEATURE_NAMES when EXOGENOUS_FEATURES_FILE is set
+fn exogenous_feature_names() -> Option<Vec<String>> {
+    env::var("EXOGENOUS_FEATURES_FILE").ok()?;
+    let names = env::var("EXOGENOUS_FEATURE_NAMES").unwrap_or_else(|_| "news_sentiment,earnings_flag".to_string());
+    Some(names.split(',').map(str::trim).filter(|n| !n.is_empty()).map(str::to_string).collect())
+}
+
+/// Backend selected by INFERENCE_BACKEND
+fn inference_backend() -> Result<BackendKind> {
+    match env::var("INFERENCE_BACKEND") {
+        Ok(kind) => kind.parse(),
+        Err(_) => Ok(BackendKind::default()),
+    }
+}
+
+/// Build the pattern library from MODEL_PATH, PATTERN_DEFINITIONS and MODEL_ID
+fn load_pattern_library(cache_config: InferenceCacheConfig) -> Result<PatternLibrary> {
+    // Model path can be provided via MODEL_PATH env var; default to `models/pattern_model.onnx`
+    let model_path = env::var("MODEL_PATH").unwrap_or_else(|_| DEFAULT_MODEL_PATH.to_string());
+    // ONNX_PROVIDERS and friends select GPU execution and session options.
+    // Models must take the signal feature vector plus any exogenous features,
+    // and match its schema hash when they record one.
+    let schema = FeatureSchema::signal(&exogenous_feature_names().unwrap_or_default())?;
+    info!("Feature schema {} ({} features)", schema.hash(), schema.len());
+    let onnx = OnnxConfig::from_env()?.with_feature_schema(&schema);
+    if onnx.accelerated() {
+        info!("ONNX execution providers: {:?}", onnx.providers);
+    }
+    // MODELS (`name=path,...`) registers more models and MODEL_ROUTES
+    // (`pattern:<glob>=name,symbol:<glob>=name,...`) picks one per unknown pattern
+    let models = match env::var("MODELS") {
+        Ok(spec) => registry::parse_named_models(&spec)?,
+        Err(_) => Vec::new(),
+    };
+    let routes = match env::var("MODEL_ROUTES") {
+        Ok(spec) => ModelRoute::parse_list(&spec)?,
+        Err(_) => Vec::new(),
+    };
+    if !models.is_empty() {
+        info!("Models besides the default: {:?}; {} route(s)", models, routes.len());
+    }
+    // INFERENCE_BACKEND=remote serves the default model from an inference
+    // server (REMOTE_INFERENCE_URL and friends) instead of MODEL_PATH;
+    // INFERENCE_BACKEND=tract runs the model files with tract (tract feature)
+    let backend = inference_backend()?;
+    let remote = match backend {
+        BackendKind::Remote => Some(RemoteConfig::from_env()?),
+        BackendKind::Onnx | BackendKind::Tract => None,
+    };
+    if let Some(config) = &remote {
+        info!("Remote inference at {} ({:?}, timeout {:?})", config.url, config.protocol, config.timeout);
+    }
+    let tract = backend == BackendKind::Tract;
+    if tract {
+        info!("Running models with tract");
+    }
+    // SHADOW_MODEL names a MODELS entry that scores every unknown pattern next
+    // to the served model; SHADOW_IN_META=1 also attaches its score to signals
+    let shadow = env::var("SHADOW_MODEL").ok();
+    let shadow_in_meta = env::var("SHADOW_IN_META").is_ok_and(|v| matches!(v.to_ascii_lowercase().as_str(), "1" | "true" | "on"));
+    if let Some(name) = &shadow {
+        info!("Shadow model {} scores every unknown pattern", name);
+    }
+    // MODEL_CANARY (`[base=]canary:percent`, e.g. `v2:5`) sends that share of
+    // a model's unknown patterns to another MODELS entry
+    let canary = env::var("MODEL_CANARY").ok();
+    if let Some(spec) = &canary {
+        info!("Model canary {}", spec);
+    }
+    let mut builder = PatternLibrary::builder().model_path(model_path).onnx(onnx).cache(cache_config).model_routes(routes);
+    for (name, path) in &models {
+        builder = builder.model(name, path);
+    }
+    if let Some(config) = remote {
+        builder = builder.remote(config);
+    }
+    if tract {
+        builder = builder.tract();
+    }
+    if let Some(name) = &shadow {
+        builder = builder.shadow_model(name, shadow_in_meta);
+    }
+    if let Some(spec) = &canary {
+        builder = builder.canary(spec);
+    }
+    // PATTERN_LIBRARY imports a full library exported from /patterns/export and
+    // takes precedence over PATTERN_DEFINITIONS (YAML or JSON); the built-in
+    // set is used when neither is given
+    let library = env::var("PATTERN_LIBRARY").ok();
+    let definitions = env::var("PATTERN_DEFINITIONS").ok().filter(|_| library.is_none());
+    if let Some(path) = &library {
+        builder = builder.export(LibraryExport::load(std::path::Path::new(path))?);
+    } else if let Some(path) = &definitions {
+        builder = builder.definitions_file(path);
+    }
+    // MODEL_ID names the model in inferred-pattern provenance (defaults to the file name)
+    if let Ok(id) = env::var("MODEL_ID") {
+        builder = builder.model_id(&id);
+    }
+    let pattern_lib = builder.build()?;
+    if let Some(path) = library {
+        info!("Imported pattern library from {}", path);
+    } else if let Some(path) = definitions {
+        info!("Loaded pattern definitions from {}", path);
+    }
+    Ok(pattern_lib)
+}
+
+/// Respawn subsystem tasks that exited and report them as ops events
+async fn supervise(state: AppState, interval: Duration) {
+    loop {
+        tokio::time::sleep(interval).await;
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs_f64();
+        let revived = state.supervisor.lock().await.revive_stopped(now);
+        for name in revived {
+            warn!("Subsystem {} stopped; restarted by supervisor", name);
+            let event = serde_json::json!({ "event": "subsystem_restarted", "subsystem": name, "reason": "stopped" });
+            if let Err(e) = state.publisher.lock().await.publish_ops_event(&event).await {
+                error!("Failed to publish ops event: {}", e);
+            }
+        }
+    }
+}
+
+/// Close degradation windows and publish level changes as ops events
+async fn monitor_degradation(state: AppState, window: Duration) {
+    let mut last = Instant::now();
+    loop {
+        tokio::time::sleep(window).await;
+        let now = Instant::now();
+        let event = state.degradation.lock().await.evaluate(now.duration_since(last));
+        last = now;
+        let Some(event) = event else {
+            continue;
+        };
+
+        warn!(
+            "Degradation {:?} -> {:?} ({:.0} ticks/s, p99 {:.1}ms)",
+            event.from, event.to, event.ticks_per_sec, event.p99_latency_ms
+        );
+        if let Err(e) = state.publisher.lock().await.publish_ops_event(&event).await {
+            error!("Failed to publish ops event: {}", e);
+        }
+    }
+}
+
+/// Periodically save pattern hit-rate stats so calibration survives restarts
+async fn persist_pattern_stats(state: AppState, path: String, interval: Duration) {
+    loop {
+        tokio::time::sleep(interval).await;
+        let stats = state.pattern_stats.lock().await.clone();
+        if let Err(e) = stats.save(std::path::Path::new(&path)) {
+            error!("Failed to persist pattern stats: {}", e);
+        }
+    }
+}
+
+/// Redis keyspace health and current throttle settings
+async fn redis_health(State(state): State<AppState>) -> Json<KeyspaceStatus> {
+    Json(state.keyspace.lock().await.status())
+}
+
+/// Stream lengths and consumer-group lag for the signals and ticks streams
+async fn stream_health(State(state): State<AppState>) -> Result<Json<StreamInfo>, (StatusCode, String)> {
+    let publisher = state.publisher.lock().await;
+    publisher.get_stream_info().await.map(Json).map_err(|e| (StatusCode::SERVICE_UNAVAILABLE, e.to_string()))
+}
+
+/// Health check endpoint
+async fn health_check(State(state): State<AppState>) -> Json<HealthResponse> {
+    let active_symbols = state.symbol_states.lock().await.len();
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64();
+
+    Json(HealthResponse {
+        status: "healthy".to_string(),
+        active_symbols,
+        signals_stream: "signals:global".to_string(),
+        ticks_stream: "ticks:global".to_string(),
+        timestamp,
+        degradation: state.degradation.lock().await.level(),
+        model_warmup: state.inference.lock().await.library().models().warmups(),
+    })
+}
+
+/// WebSocket stream of live signals (`?symbols=AAPL,MSFT`, default all)
+async fn signals_ws(
+    State(state): State<AppState>,
+    Query(params): Query<HashMap<String, String>>,
+    ws: WebSocketUpgrade,
+) -> Response {
+    let filter = SymbolFilter::parse(params.get("symbols").map(String::as_str).unwrap_or_default());
+    info!("WebSocket subscriber connected ({})", filter);
+    let broadcast = state.broadcast.clone();
+    ws.on_upgrade(move |socket| websocket::serve_connection(socket, broadcast, filter))
+}
+
+/// Metrics endpoint exposing telemetry counters
+async fn metrics(State(state): State<AppState>, Query(params): Query<HashMap<String, String>>) -> Json<MetricsResponse> {
+    let inferred = state.inferred_count.load(Ordering::Relaxed);
+    let known = state.known_count.load(Ordering::Relaxed);
+    let inference = state.inference().await;
+    let total_ns = state.total_infer_latency_ns.load(Ordering::Relaxed);
+    // Use inferred-only denominators for average latency
+    let avg_ms = if inferred > 0 {
+        (total_ns as f64 / (inferred as f64)) / 1_000_000.0
+    } else {
+        0.0
+    };
+
+    // Build per-symbol metrics snapshot
+    let mut per_symbol_map = std::collections::HashMap::new();
+    let pm = state.per_symbol_metrics.lock().await;
+    if let Some(sym_filter) = params.get("symbol") {
+        if let Some((inf, kn, total)) = pm.get(sym_filter) {
+            let avg = if *inf > 0 { (*total as f64 / (*inf as f64)) / 1_000_000.0 } else { 0.0 };
+            per_symbol_map.insert(sym_filter.clone(), PerSymbolMetrics {
+                inferred: *inf,
+                known: *kn,
+                avg_latency_ms: avg,
+            });
+        }
+    } else {
+        for (sym, (inf, kn, total)) in pm.iter() {
+            let avg = if *inf > 0 { (*total as f64 / (*inf as f64)) / 1_000_000.0 } else { 0.0 };
+            per_symbol_map.insert(sym.clone(), PerSymbolMetrics {
+                inferred: *inf,
+                known: *kn,
+                avg_latency_ms: avg,
+            });
+        }
+    }
+
+    let (wal, limiter, publisher) = {
+        let publisher = state.publisher.lock().await;
+        (publisher.wal(), publisher.limiter(), publisher.metrics().stats())
+    };
+    let wal_pending = match wal {
+        Some(wal) => Some(wal.lock().await.pending()),
+        None => None,
+    };
+    Json(MetricsResponse {
+        inferred_count: inferred,
+        known_count: known,
+        avg_infer_latency_ms: avg_ms,
+        per_symbol: per_symbol_map,
+        inference_cache: inference.library().inference_cache_stats(),
+        inference_queue_depth: inference.pending(),
+        inference_shed: inference.shed(),
+        inference_fallbacks: inference.library().models().fallbacks(),
+        degraded_inferences: inference.degraded_inferences(),
+        shadow: inference.library().shadow_stats(),
+        models: inference.library().models().stats(),
+        canary: inference.library().canary_stats(),
+        degradation: state.degradation.lock().await.status(),
+        history: state.history.lock().await.stats(),
+        grpc_sink: state.grpc_sink.as_ref().map(|sink| sink.stats()),
+        websocket_connections: state.broadcast.connections(),
+        sinks: state.sinks.stats(),
+        dead_letters: state.dead_letters.stats(),
+        dedup: match &state.dedup {
+            Some(dedup) => Some(dedup.lock().await.stats()),
+            None => None,
+        },
+        publisher,
+        rate_limits: limiter.map(|l| l.lock().unwrap_or_else(|e| e.into_inner()).stats()),
+        wal_pending,
+        parquet: state.parquet.as_ref().map(|p| p.stats()),
+        postgres: state.postgres.as_ref().map(|p| p.stats()),
+        training_export: state.training.as_ref().map(|t| t.stats()),
+    })
+}
+
+/// Parse `from`, `to` (unix seconds) and `limit` history query parameters
+fn range_query(params: &HashMap<String, String>) -> Result<RangeQuery, (StatusCode, String)> {
+    let mut q = RangeQuery::default();
+    let bad = |k: &str, v: &str| (StatusCode::BAD_REQUEST, format!("invalid {}: {}", k, v));
+    if let Some(v) = params.get("from") {
+        q.from = v.parse().map_err(|_| bad("from", v))?;
+    }
+    if let Some(v) = params.get("to") {
+        q.to = v.parse().map_err(|_| bad("to", v))?;
+    }
+    if let Some(v) = params.get("limit") {
+        q.limit = v.parse().map_err(|_| bad("limit", v))?;
+    }
+    Ok(q)
+}
+
+/// Recent ticks for a symbol (`?from=&to=&limit=`)
+async fn history_ticks(
+    State(state): State<AppState>,
+    Path(symbol): Path<String>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Vec<Tick>>, (StatusCode, String)> {
+    let q = range_query(&params)?;
+    Ok(Json(state.history.lock().await.ticks(&symbol, q)))
+}
+
+/// Recent closed candles for a symbol (`?interval=60&from=&to=&limit=`)
+async fn history_candles(
+    State(state): State<AppState>,
+    Path(symbol): Path<String>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Vec<Candle>>, (StatusCode, String)> {
+    let q = range_query(&params)?;
+    let interval = match params.get("interval") {
+        Some(v) => v.parse().map_err(|_| (StatusCode::BAD_REQUEST, format!("invalid interval: {}", v)))?,
+        None => CANDLE_INTERVALS[0],
+    };
+    if !CANDLE_INTERVALS.contains(&interval) {
+        return Err((StatusCode::BAD_REQUEST, format!("interval must be one of {:?}", CANDLE_INTERVALS)));
+    }
+    Ok(Json(state.history.lock().await.candles(&symbol, interval, q)))
+}
+
+/// Recent published signals for a symbol (`?from=&to=&limit=`)
+async fn history_signals(
+    State(state): State<AppState>,
+    Path(symbol): Path<String>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Vec<Signal>>, (StatusCode, String)> {
+    let q = range_query(&params)?;
+    Ok(Json(state.history.lock().await.signals(&symbol, q)))
+}
+
+/// Burst statistics endpoint for capacity planning
+async fn burst_metrics(State(state): State<AppState>, Query(params): Query<HashMap<String, String>>) -> Json<BurstMetricsResponse> {
+    let symbol_states = state.symbol_states.lock().await;
+    let wall = wall_now();
+    // read each symbol's burst stats at its own event time
+    let per_symbol = symbol_states
+        .iter()
+        .filter(|(sym, _)| params.get("symbol").is_none_or(|f| f == *sym))
+        .map(|(sym, st)| (sym.clone(), st.pipeline.indicators().burst.snapshot(st.tick_clock.now(wall))))
+        .collect();
+
+    Json(BurstMetricsResponse { per_symbol })
+}
+
+/// Bulk import a symbol universe from JSON or CSV.
+///
+/// The format is taken from `?format=csv|json`, falling back to the
+/// Content-Type header. `?mode=replace` swaps the whole universe; the default
+/// merges entries into the existing one.
+async fn import_symbols(
+    State(state): State<AppState>,
+    Query(params): Query<HashMap<String, String>>,
+    headers: HeaderMap,
+    body: String,
+) -> Result<Json<ImportResponse>, (StatusCode, String)> {
+    let is_csv = match params.get("format") {
+        Some(f) => f.eq_ignore_ascii_case("csv"),
+        None => headers
+            .get(header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|ct| ct.contains("csv")),
+    };
+    let mode = match params.get("mode").map(|m| m.to_ascii_lowercase()) {
+        None => ImportMode::Merge,
+        Some(m) if m == "merge" => ImportMode::Merge,
+        Some(m) if m == "replace" => ImportMode::Replace,
+        Some(m) => return Err((StatusCode::BAD_REQUEST, format!("unknown import mode: {}", m))),
+    };
+
+    let parsed = if is_csv { universe::parse_csv(&body) } else { universe::parse_json(&body) };
+    let entries = parsed.map_err(|e| (StatusCode::BAD_REQUEST, format!("invalid universe: {}", e)))?;
+
+    let (imported, total, snapshot) = {
+        let mut uni = state.universe.lock().await;
+        let imported = uni.import(entries, mode);
+        (imported, uni.len(), uni.clone())
+    };
+
+    // Apply effective thresholds to already-active symbols
+    let mut symbol_states = state.symbol_states.lock().await;
+    let mut history = state.history.lock().await;
+    for (sym, st) in symbol_states.iter_mut() {
+        st.pipeline.set_thresholds(snapshot.thresholds_for(sym));
+        history.set_tier(sym, snapshot.get(sym).and_then(|c| c.tier));
+    }
+
+    info!("Imported {} symbols into universe ({} total)", imported, total);
+    Ok(Json(ImportResponse { imported, total }))
+}
+
+/// Export the effective universe (configured plus active symbols) as JSON or CSV
+async fn export_symbols(State(state): State<AppState>, Query(params): Query<HashMap<String, String>>) -> Response {
+    let active: Vec<String> = state.symbol_states.lock().await.keys().cloned().collect();
+    let uni = state.universe.lock().await;
+    let symbols = uni.export(active.iter().map(String::as_str));
+
+    if params.get("format").is_some_and(|f| f.eq_ignore_ascii_case("csv")) {
+        ([(header::CONTENT_TYPE, "text/csv")], universe::to_csv(&symbols)).into_response()
+    } else {
+        Json(ExportResponse {
+            defaults: uni.defaults().clone(),
+            symbols,
+        })
+        .into_response()
+    }
+}
+
+/// Status of every restartable subsystem
+async fn list_subsystems(State(state): State<AppState>) -> Json<Vec<SubsystemStatus>> {
+    Json(state.supervisor.lock().await.status())
+}
+
+/// Restart one subsystem (feeds, publisher, aggregator, pattern_lib) without
+/// touching indicator state
+async fn restart_subsystem(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<Json<SubsystemStatus>, (StatusCode, String)> {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64();
+    let result = {
+        let mut supervisor = state.supervisor.lock().await;
+        if !supervisor.contains(&name) {
+            return Err((StatusCode::NOT_FOUND, format!("unknown subsystem: {}", name)));
+        }
+        supervisor.restart(&name, now).await
+    };
+    let event = serde_json::json!({
+        "event": "subsystem_restarted",
+        "subsystem": name,
+        "reason": "admin",
+        "error": result.as_ref().err().map(|e| format!("{:#}", e)),
+    });
+    if let Err(e) = state.publisher.lock().await.publish_ops_event(&event).await {
+        error!("Failed to publish ops event: {}", e);
+    }
+    match result {
+        Ok(status) => {
+            info!("Restarted subsystem {}", name);
+            Ok(Json(status))
+        }
+        Err(e) => Err((StatusCode::INTERNAL_SERVER_ERROR, format!("restart of {} failed: {:#}", name, e))),
+    }
+}
+
+/// Stop consuming ticks and freeze indicator state.
+///
+/// `?policy=buffer|drop` overrides the configured handling of ticks that
+/// arrive while paused.
+async fn pause_engine(
+    State(state): State<AppState>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<PauseStatus>, (StatusCode, String)> {
+    let policy = params
+        .get("policy")
+        .map(|p| p.parse::<PausePolicy>())
+        .transpose()
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64();
+
+    let mut gate = state.ingest.lock().await;
+    if gate.pause(now, policy) {
+        info!("Engine paused ({:?} policy)", gate.status().policy);
+    }
+    Ok(Json(gate.status()))
+}
+
+/// Load the model file again and swap it in without restarting
+async fn reload_model(State(state): State<AppState>) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    let library = state.inference.lock().await.library().clone();
+    let path = library.model_location();
+    let model_id = library.model_id().to_string();
+    let result = tokio::task::spawn_blocking(move || library.reload_model())
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    if let Err(e) = result {
+        return Err((StatusCode::INTERNAL_SERVER_ERROR, format!("reload of {} failed: {:#}", path, e)));
+    }
+    info!("Reloaded model {}", path);
+    let event = serde_json::json!({ "event": "model_reloaded", "path": path, "reason": "admin" });
+    if let Err(e) = state.publisher.lock().await.publish_ops_event(&event).await {
+        error!("Failed to publish ops event: {}", e);
+    }
+    Ok(Json(serde_json::json!({ "model_id": model_id, "path": path })))
+}
+
+/// Change the share of traffic the model canary gets (`?percent=25`); a
+/// library rebuild goes back to MODEL_CANARY
+async fn set_canary_percent(
+    State(state): State<AppState>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<CanaryStats>, (StatusCode, String)> {
+    let percent: f64 = params
+        .get("percent")
+        .ok_or((StatusCode::BAD_REQUEST, "missing percent parameter".to_string()))?
+        .parse()
+        .map_err(|_| (StatusCode::BAD_REQUEST, "percent must be a number".to_string()))?;
+    let library = state.inference.lock().await.library().clone();
+    let canary = library.models().canary().ok_or((StatusCode::NOT_FOUND, "no model canary configured".to_string()))?;
+    let previous = canary.percent();
+    canary.set_percent(percent).map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    info!("Model canary {} now gets {}% of {} (was {}%)", canary.canary, percent, canary.base, previous);
+    Ok(Json(canary.stats()))
+}
+
+/// Resume tick consumption; buffered ticks are replayed before new ones
+async fn resume_engine(State(state): State<AppState>) -> Json<PauseStatus> {
+    let mut gate = state.ingest.lock().await;
+    let status = gate.status();
+    if gate.resume() {
+        info!("Engine resumed ({} buffered, {} dropped)", status.buffered, status.dropped);
+    }
+    Json(gate.status())
+}
+
+/// Current pause state
+async fn pause_status(State(state): State<AppState>) -> Json<PauseStatus> {
+    Json(state.ingest.lock().await.status())
+}
+
+#[derive(Serialize)]
+struct VersionResponse {
+    name: &'static str,
+    version: &'static str,
+    flags: std::collections::BTreeMap<String, bool>,
+}
+
+/// Build version and active feature flags
+async fn version(State(state): State<AppState>) -> Json<VersionResponse> {
+    Json(VersionResponse {
+        name: env!("CARGO_PKG_NAME"),
+        version: env!("CARGO_PKG_VERSION"),
+        flags: state.flags.lock().await.all().clone(),
+    })
+}
+
+/// Current feature flag states
+async fn list_flags(State(state): State<AppState>) -> Json<FeatureFlags> {
+    Json(state.flags.lock().await.clone())
+}
+
+/// Known patterns, optionally filtered with `?tags=bullish,type:reversal`
+async fn list_patterns(
+    State(state): State<AppState>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Vec<PatternMeta>>, (StatusCode, String)> {
+    let tags = params.get("tags").map(String::as_str).unwrap_or_default();
+    let terms: Vec<&str> = tags.split(',').collect();
+    let inference = state.inference().await;
+    let patterns = inference
+        .library()
+        .filter_by_tags(&terms)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    Ok(Json(patterns.into_iter().cloned().collect()))
+}
+
+/// Full library with outcome stats, importable through PATTERN_LIBRARY
+async fn export_patterns(State(state): State<AppState>) -> Json<LibraryExport> {
+    let export = state.inference().await.library().export();
+    Json(export.with_stats(&*state.pattern_stats.lock().await))
+}
+
+/// Occurrences, win rate and mean forward return per pattern and horizon
+async fn pattern_outcome_stats(State(state): State<AppState>) -> Json<BTreeMap<String, PatternSummary>> {
+    Json(state.pattern_stats.lock().await.summary())
+}
+
+/// Latest breadth sample for each configured group
+async fn breadth_snapshot(State(state): State<AppState>) -> Json<BTreeMap<String, BreadthSnapshot>> {
+    Json(state.breadth.lock().await.snapshot())
+}
+
+/// Current spread fit for each configured pair
+async fn pair_stats(State(state): State<AppState>) -> Json<BTreeMap<String, SpreadStats>> {
+    Json(state.pairs.lock().await.stats())
+}
+
+/// Enable or disable a flag at runtime with `?enabled=on|off`
+async fn set_flag(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<FeatureFlags>, (StatusCode, String)> {
+    let value = params
+        .get("enabled")
+        .ok_or((StatusCode::BAD_REQUEST, "missing enabled parameter".to_string()))?;
+    let enabled = flags::parse_state(value).map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+
+    let mut flags = state.flags.lock().await;
+    let previous = flags.set(&name, enabled);
+    info!("Feature flag {} set to {} (was {:?})", name, enabled, previous);
+    Ok(Json(flags.clone()))
+}
+
+/// `bench` subcommand: measure detection latency on synthetic ticks (and, with
+/// `--replay-file`, memory-mapped replay parsing), write a JSON report and
+/// optionally fail on regressions against a baseline. With `--track` (or
+/// `EXPERIMENT_TRACKER`) the run's parameters and metrics are logged to an
+/// experiment tracker.
+///
+/// Usage: `pattern_engine bench [--iterations N] [--output PATH] [--baseline PATH]
+/// [--max-regression FRAC] [--max-p99-regression FRAC] [--replay-file PATH]
+/// [--track jsonl:PATH|mlflow:URL[#ID]] [--experiment NAME]`
+async fn run_bench(args: &[String]) -> Result<()> {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    let mut iterations = 100_000usize;
+    let mut output: Option<String> = None;
+    let mut baseline: Option<String> = None;
+    let mut replay_file: Option<String> = None;
+    let mut thresholds = RegressionThresholds::default();
+    let mut tracker: Option<ExperimentTracker> = env::var("EXPERIMENT_TRACKER").ok().map(|s| s.parse()).transpose()?;
+    let mut experiment = "bench".to_string();
+
+    let mut it = args.iter();
+    while let Some(arg) = it.next() {
+        let mut value = || it.next().cloned().ok_or_else(|| anyhow::anyhow!("missing value for {}", arg));
+        match arg.as_str() {
+            "--iterations" => iterations = value()?.parse()?,
+            "--output" => output = Some(value()?),
+            "--baseline" => baseline = Some(value()?),
+            "--replay-file" => replay_file = Some(value()?),
+            "--max-regression" => {
+                let v: f64 = value()?.parse()?;
+                thresholds = RegressionThresholds { mean: v, p50: v, p99: v, throughput: v };
+            }
+            "--max-p99-regression" => thresholds.p99 = value()?.parse()?,
+            "--track" => tracker = Some(value()?.parse()?),
+            "--experiment" => experiment = value()?,
+            other => anyhow::bail!("unknown bench argument: {}", other),
+        }
+    }
+
+    // Deterministic synthetic random walk so runs are comparable
+    const SEED: u64 = 42;
+    let started_at = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH)?.as_secs_f64();
+    let symbols = ["AAPL", "GOOGL", "MSFT", "TSLA", "AMZN"];
+    let mut rng = StdRng::seed_from_u64(SEED);
+    let flags = FeatureFlags::default();
+    let mut states: Vec<DetectionPipeline> = symbols
+        .iter()
+        .map(|s| DetectionPipeline::new(s, DetectionThresholds::default(), Arc::default()))
+        .collect();
+    let mut prices = vec![100.0f64; symbols.len()];
+    let mut candles: Vec<Option<Candle>> = vec![None; symbols.len()];
+
+    let mut tick_samples = Vec::with_capacity(iterations);
+    let mut candle_samples = Vec::new();
+    let run_start = Instant::now();
+    for i in 0..iterations {
+        let idx = i % symbols.len();
+        prices[idx] *= 1.0 + (rng.gen::<f64>() - 0.5) * 0.004;
+        let price = prices[idx];
+        let volume = rng.gen::<f64>() * 4900.0 + 100.0;
+        let side = if rng.gen::<bool>() { TradeSide::Buy } else { TradeSide::Sell };
+        let timestamp = (i / symbols.len()) as f64;
+        let st = &mut states[idx];
+
+        let t0 = Instant::now();
+        st.record_arrival(volume, timestamp);
+        std::hint::black_box(st.update_and_detect(price, volume, timestamp, Some(side)));
+        tick_samples.push(t0.elapsed().as_nanos() as u64);
+
+        // 60 tick candles per symbol
+        let start = (timestamp as u64 / 60) * 60;
+        match &mut candles[idx] {
+            Some(c) if c.start == start => {
+                c.high = c.high.max(price);
+                c.low = c.low.min(price);
+                c.close = price;
+                c.volume += volume;
+            }
+            slot => {
+                if let Some(closed) = slot.replace(Candle::from_trade(start, price, volume)) {
+                    let t0 = Instant::now();
+                    std::hint::black_box(st.detect_on_candle(&closed, 60, &flags));
+                    candle_samples.push(t0.elapsed().as_nanos() as u64);
+                }
+            }
+        }
+    }
+    let total = run_start.elapsed();
+
+    let mut reports = vec![
+        BenchReport::from_samples("tick_detection", &mut tick_samples, total),
+        BenchReport::from_samples("candle_detection", &mut candle_samples, total),
+    ];
+
+    // Memory-mapped replay parsing: latency is per row (measured per batch),
+    // throughput is rows per second
+    if let Some(path) = &replay_file {
+        let mut row_samples = Vec::new();
+        let mut checksum = 0.0;
+        let mut t0 = Instant::now();
+        let replay_start = Instant::now();
+        let rows = replay::replay_mmap(path, 8192, |batch| {
+            checksum += batch.iter().map(|r| r.price).sum::<f64>();
+            row_samples.push(t0.elapsed().as_nanos() as u64 / batch.len() as u64);
+            t0 = Instant::now();
+        })?
+        .rows;
+        let elapsed = replay_start.elapsed();
+        std::hint::black_box(checksum);
+        let mut report = BenchReport::from_samples("replay_mmap", &mut row_samples, elapsed);
+        report.iterations = rows;
+        report.throughput_per_sec = rows as f64 / elapsed.as_secs_f64().max(f64::EPSILON);
+        eprintln!("replay_mmap: {} rows at {:.0} rows/sec", rows, report.throughput_per_sec);
+        reports.push(report);
+    }
+
+    let run = BenchRun {
+        timestamp: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs_f64(),
+        reports,
+    };
+
+    println!("{}", serde_json::to_string_pretty(&run)?);
+    if let Some(path) = output {
+        run.save(std::path::Path::new(&path))?;
+    }
+
+    if let Some(tracker) = tracker {
+        let mut record = RunRecord::new(&experiment, started_at);
+        record.ended_at = run.timestamp;
+        record.param("iterations", iterations).param("seed", SEED).param("symbols", symbols.len());
+        if let Some(path) = &replay_file {
+            record.param("replay_file", path);
+            record.dataset_hash = Some(tracking::dataset_hash(std::path::Path::new(path))?);
+        }
+        for r in &run.reports {
+            record
+                .metric(&format!("{}.mean_ns", r.name), r.mean_ns)
+                .metric(&format!("{}.p50_ns", r.name), r.p50_ns)
+                .metric(&format!("{}.p99_ns", r.name), r.p99_ns)
+                .metric(&format!("{}.throughput_per_sec", r.name), r.throughput_per_sec);
+        }
+        tracker.log_run(&record).await?;
+        eprintln!("tracked run {} ({:?})", record.run_id, tracker);
+    }
+
+    if let Some(path) = baseline {
+        let base = BenchRun::load(std::path::Path::new(&path))?;
+        let regressions = bench::compare(&run, &base, &thresholds);
+        for r in &regressions {
+            eprintln!(
+                "REGRESSION {} {}: {:.1} -> {:.1} ({:+.1}% > {:.1}% allowed)",
+                r.case, r.metric, r.baseline, r.current, r.change * 100.0, r.threshold * 100.0
+            );
+        }
+        if !regressions.is_empty() {
+            anyhow::bail!("{} benchmark regression(s) against {}", regressions.len(), path);
+        }
+    }
+
+    Ok(())
+}
+
+/// `codegen` subcommand: emit consumer types (Python dataclasses, TypeScript
+/// interfaces or the raw JSON schema) for published signals.
+fn run_codegen(args: &[String]) -> Result<()> {
+    let mut lang = Language::Python;
+    let mut output: Option<String> = None;
+
+    let mut it = args.iter();
+    while let Some(arg) = it.next() {
+        let mut value = || it.next().cloned().ok_or_else(|| anyhow::anyhow!("missing value for {}", arg));
+        match arg.as_str() {
+            "--lang" => lang = value()?.parse()?,
+            "--output" => output = Some(value()?),
+            other => anyhow::bail!("unknown codegen argument: {}", other),
+        }
+    }
+
+    let code = codegen::generate(lang)?;
+    match output {
+        Some(path) => {
+            std::fs::write(&path, code)?;
+            info!("Wrote {:?} consumer types to {}", lang, path);
+        }
+        None => print!("{}", code),
+    }
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    // Initialize tracing
+    tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .init();
+
+    let args: Vec<String> = env::args().collect();
+    match args.get(1).map(String::as_str) {
+        Some("bench") => return run_bench(&args[2..]).await,
+        Some("codegen") => return run_codegen(&args[2..]),
+        _ => {}
+    }
+
+    info!("Starting Rust Pattern Engine Service");
+
+    // Environment configuration
+    let redis_url = env::var("REDIS_URL").unwrap_or_else(|_| "redis://redis:6379/0".to_string());
+    let host = env::var("HOST").unwrap_or_else(|_| "0.0.0.0".to_string());
+    let port = env_number("PORT", 8005u16, 1..=u16::MAX)?;
+
+    // Initialize publisher
+    // SIGNAL_TAG_ROUTES=stream=tag+tag,... copies matching signals to extra streams;
+    // SIGNAL_ENCODING=flat writes one stream field per signal field instead of a JSON blob;
+    // SIGNAL_COMPRESSION=zstd compresses fields of at least SIGNAL_COMPRESSION_MIN_BYTES
+    // REDIS_USERNAME / REDIS_PASSWORD (ACL) and REDIS_TLS_* certificates for rediss:// URLs
+    let redis_config = PublisherConfig::from_env(&redis_url);
+    let mut publisher = Publisher::with_config(&redis_config)?;
+    publisher.set_tag_routes(TagRoute::parse_list(&env::var("SIGNAL_TAG_ROUTES").unwrap_or_default())?);
+    if let Ok(encoding) = env::var("SIGNAL_ENCODING") {
+        publisher.set_encoding(encoding.parse().map_err(|e| anyhow::anyhow!("SIGNAL_ENCODING: {}", e))?);
+    }
+    match env::var("SIGNAL_COMPRESSION").unwrap_or_default().to_ascii_lowercase().as_str() {
+        "" | "none" => {}
+        "zstd" => {
+            let defaults = Compression::default();
+            publisher.set_compression(Some(Compression {
+                level: env_number("SIGNAL_COMPRESSION_LEVEL", defaults.level, 1..=22)?,
+                min_bytes: env_number("SIGNAL_COMPRESSION_MIN_BYTES", defaults.min_bytes, 0..=usize::MAX)?,
+            }));
+        }
+        other => anyhow::bail!("SIGNAL_COMPRESSION: unknown codec '{}' (expected zstd or none)", other),
+    }
+    // PUBLISH_WAL_DIR buffers entries on disk while Redis is unreachable, up to
+    // PUBLISH_WAL_MAX_BYTES, replaying them in order every PUBLISH_WAL_FLUSH_SECS
+    if let Ok(dir) = env::var("PUBLISH_WAL_DIR") {
+        let max_bytes = env_number("PUBLISH_WAL_MAX_BYTES", 256u64 << 20, 1..=u64::MAX)?;
+        let wal = WriteAheadLog::open(std::path::Path::new(&dir), max_bytes)?;
+        info!("Write-ahead log at {} ({} entries waiting)", dir, wal.pending());
+        publisher.set_wal(Some(Arc::new(Mutex::new(wal))));
+    }
+    // PUBLISH_RATE_LIMITS=stream=rate[/burst],... caps entries per second per
+    // stream: excess ticks coalesce per symbol, excess signals queue
+    let rate_limits = RateLimit::parse_list(&env::var("PUBLISH_RATE_LIMITS").unwrap_or_default())?;
+    if !rate_limits.is_empty() {
+        info!("Publish rate limits: {:?}", rate_limits);
+        publisher.set_limiter(Some(Arc::new(std::sync::Mutex::new(PublishLimiter::new(rate_limits)))));
+    }
+    let rate_release = env_duration("PUBLISH_RATE_RELEASE", Duration::from_millis(100), Duration::from_millis(1)..=Duration::from_secs(60))?;
+    let wal_flush = env_duration("PUBLISH_WAL_FLUSH_SECS", Duration::from_secs(5), Duration::from_millis(100)..=HOUR)?;
+    // REDIS_SINK_MODE=streams|pubsub|both: Pub/Sub broadcasts live signals and ticks
+    // on PUBSUB_SIGNALS_CHANNEL:{symbol} / PUBSUB_TICKS_CHANNEL:{symbol} without storing them
+    let redis_mode = match env::var("REDIS_SINK_MODE") {
+        Ok(v) => v.parse::<RedisMode>()?,
+        Err(_) => RedisMode::Streams,
+    };
+    let pubsub = if redis_mode.pubsub() {
+        let signals_channel = env::var("PUBSUB_SIGNALS_CHANNEL").unwrap_or_else(|_| publisher.signals_stream().to_string());
+        let ticks_channel = env::var("PUBSUB_TICKS_CHANNEL").unwrap_or_else(|_| publisher.ticks_stream().to_string());
+        info!("Broadcasting on Pub/Sub channels {}:* and {}:*", signals_channel, ticks_channel);
+        Some(Arc::new(PubSubSink::new(&redis_config, &signals_channel, &ticks_channel, publisher.producer())?))
+    } else {
+        None
+    };
+    let publisher = Arc::new(Mutex::new(publisher));
+
+    // Initialize application state and pattern library
+    let symbol_states = Arc::new(Mutex::new(HashMap::new()));
+    // Inference cache for unknown patterns: INFERENCE_CACHE_SIZE (0 disables),
+    // INFERENCE_CACHE_TTL_SECS and INFERENCE_CACHE_QUANTUM (feature rounding step)
+    let cache_defaults = InferenceCacheConfig::default();
+    let cache_config = InferenceCacheConfig {
+        capacity: env_number("INFERENCE_CACHE_SIZE", cache_defaults.capacity, 0..=10_000_000)?,
+        ttl: env_duration("INFERENCE_CACHE_TTL_SECS", cache_defaults.ttl, Duration::ZERO..=DAY)?,
+        quantum: env_number("INFERENCE_CACHE_QUANTUM", cache_defaults.quantum, 0.0..=1.0)?,
+    };
+    let pattern_lib = Arc::new(load_pattern_library(cache_config)?);
+    for (name, warmup) in pattern_lib.models().warmups() {
+        info!("Model {} warmed up: {} runs, first {:.2}ms, last {:.2}ms", name, warmup.runs, warmup.first_ms, warmup.last_ms);
+    }
+    // Inference worker pool: INFERENCE_WORKERS threads, INFERENCE_QUEUE pending jobs
+    let inference_workers = env_number("INFERENCE_WORKERS", 2usize, 1..=256)?;
+    let inference_queue = env_number("INFERENCE_QUEUE", 1024usize, 1..=1_000_000)?;
+    // INFERENCE_SHED=block|reject|wait decides what a full queue does to the
+    // tick path; `wait` gives up after INFERENCE_SHED_WAIT_SECS
+    let shed_policy = match env::var("INFERENCE_SHED") {
+        Ok(v) => v.parse::<ShedPolicy>()?,
+        Err(_) => "wait".parse::<ShedPolicy>()?,
+    }
+    .with_wait(env_duration("INFERENCE_SHED_WAIT_SECS", Duration::from_millis(50), Duration::ZERO..=Duration::from_secs(10))?);
+    // INFERENCE_DEADLINE_SECS bounds each lookup; slower ones are answered by
+    // the stub model and counted as degraded (0 disables)
+    let inference_deadline = Some(env_duration("INFERENCE_DEADLINE_SECS", Duration::ZERO, Duration::ZERO..=Duration::from_secs(60))?)
+        .filter(|d| !d.is_zero());
+    let inference_pool = Arc::new(
+        InferencePool::new(pattern_lib, inference_workers, inference_queue)?
+            .with_shed_policy(shed_policy)
+            .with_deadline(inference_deadline),
+    );
+    // Signal destinations: SIGNAL_SINK=redis|grpc|both; gRPC streams to
+    // GRPC_SINK_ENDPOINT, queueing up to GRPC_SINK_QUEUE signals while disconnected
+    let signal_sink = env::var("SIGNAL_SINK").unwrap_or_else(|_| "redis".to_string()).to_ascii_lowercase();
+    let grpc_sink = match signal_sink.as_str() {
+        "redis" => None,
+        "grpc" | "both" => {
+            let endpoint = env::var("GRPC_SINK_ENDPOINT")
+                .map_err(|_| anyhow::anyhow!("SIGNAL_SINK={} requires GRPC_SINK_ENDPOINT", signal_sink))?;
+            let defaults = GrpcSinkConfig::new(&endpoint);
+            let config = GrpcSinkConfig {
+                queue: env_number("GRPC_SINK_QUEUE", defaults.queue, 1..=10_000_000)?,
+                max_in_flight: env_number("GRPC_SINK_MAX_IN_FLIGHT", defaults.max_in_flight, 1..=1_000_000)?,
+                ..defaults
+            };
+            info!("Streaming signals over gRPC to {}", endpoint);
+            Some(Arc::new(GrpcSink::spawn(config)?))
+        }
+        other => anyhow::bail!("SIGNAL_SINK: unknown sink '{}' (expected redis, grpc or both)", other),
+    };
+
+    // ZeroMQ PUB sink (zmq feature): ZMQ_PUB_ENDPOINT, e.g. tcp://127.0.0.1:5556
+    let zmq = match env::var("ZMQ_PUB_ENDPOINT") {
+        Ok(endpoint) => {
+            let sink = ZmqSink::bind(&endpoint, env_number("ZMQ_PUB_QUEUE", 10_000usize, 1..=10_000_000)?)?;
+            info!("Publishing ticks and signals on ZeroMQ {}", sink.endpoint());
+            Some(Arc::new(sink))
+        }
+        Err(_) => None,
+    };
+
+    // File sink: FILE_SINK_DIR enables it, FILE_SINK_FORMAT=jsonl|csv, files
+    // rotate at FILE_SINK_MAX_BYTES and FILE_SINK_MAX_FILES of each kind are kept
+    let file_sink = match env::var("FILE_SINK_DIR") {
+        Ok(dir) => {
+            let format = match env::var("FILE_SINK_FORMAT") {
+                Ok(v) => v.parse::<FileFormat>()?,
+                Err(_) => FileFormat::Jsonl,
+            };
+            let defaults = FileSinkConfig::new(dir, format);
+            let config = FileSinkConfig {
+                max_bytes: env_number("FILE_SINK_MAX_BYTES", defaults.max_bytes, 1..=u64::MAX)?,
+                max_files: env_number("FILE_SINK_MAX_FILES", defaults.max_files, 0..=1_000_000)?,
+                ..defaults
+            };
+            let sink = FileSink::open(config)?;
+            info!("Writing ticks and signals to {}", sink.dir().display());
+            Some(Arc::new(sink))
+        }
+        Err(_) => None,
+    };
+
+    // Parquet sink (parquet feature): PARQUET_SINK_DIR enables it; partitions are
+    // written at PARQUET_SINK_BATCH_ROWS rows and every PARQUET_SINK_FLUSH_SECS
+    let parquet = match env::var("PARQUET_SINK_DIR") {
+        Ok(dir) => {
+            let defaults = ParquetSinkConfig::new(dir);
+            let config = ParquetSinkConfig {
+                batch_rows: env_number("PARQUET_SINK_BATCH_ROWS", defaults.batch_rows, 1..=10_000_000)?,
+                ..defaults
+            };
+            let sink = ParquetSink::open(config)?;
+            info!("Writing Parquet files to {}", sink.dir().display());
+            Some(Arc::new(sink))
+        }
+        Err(_) => None,
+    };
+    let parquet_flush = env_duration("PARQUET_SINK_FLUSH_SECS", Duration::from_secs(60), Duration::from_secs(1)..=DAY)?;
+
+    // PostgreSQL/TimescaleDB sink (postgres feature): POSTGRES_SINK_URL enables it;
+    // rows go to POSTGRES_SIGNALS_TABLE / POSTGRES_TICKS_TABLE in batches of
+    // POSTGRES_SINK_BATCH_ROWS and every POSTGRES_SINK_FLUSH_SECS
+    let postgres = match env::var("POSTGRES_SINK_URL") {
+        Ok(url) => {
+            let defaults = PostgresSinkConfig::new(&url);
+            let config = PostgresSinkConfig {
+                signals_table: env::var("POSTGRES_SIGNALS_TABLE").unwrap_or(defaults.signals_table.clone()),
+                ticks_table: env::var("POSTGRES_TICKS_TABLE").unwrap_or(defaults.ticks_table.clone()),
+                batch_rows: env_number("POSTGRES_SINK_BATCH_ROWS", defaults.batch_rows, 1..=100_000)?,
+                ..defaults
+            };
+            let sink = PostgresSink::connect(config).await?;
+            let (signals, ticks) = sink.tables();
+            info!("Inserting signals into {} and ticks into {}", signals, ticks);
+            Some(Arc::new(sink))
+        }
+        Err(_) => None,
+    };
+    let postgres_flush = env_duration("POSTGRES_SINK_FLUSH_SECS", Duration::from_secs(5), Duration::from_secs(1)..=HOUR)?;
+
+    // Training-data export: TRAINING_EXPORT_DIR enables it; every model input of an
+    // inferred pattern is written as TRAINING_EXPORT_FORMAT (jsonl or parquet) once
+    // TRAINING_EXPORT_HORIZON_SECS have passed, with the forward return over it;
+    // Parquet partitions are written every TRAINING_EXPORT_FLUSH_SECS
+    let training = match env::var("TRAINING_EXPORT_DIR") {
+        Ok(dir) => {
+            let format: TrainingFormat = env::var("TRAINING_EXPORT_FORMAT").as_deref().unwrap_or("jsonl").parse()?;
+            let defaults = TrainingExportConfig::new(dir, format);
+            let config = TrainingExportConfig {
+                horizon: env_duration("TRAINING_EXPORT_HORIZON_SECS", defaults.horizon, Duration::from_secs(1)..=DAY)?,
+                batch_rows: env_number("TRAINING_EXPORT_BATCH_ROWS", defaults.batch_rows, 1..=10_000_000)?,
+                max_pending: env_number("TRAINING_EXPORT_MAX_PENDING", defaults.max_pending, 1..=10_000_000)?,
+                ..defaults
+            };
+            let horizon = config.horizon;
+            let export = TrainingExport::open(config)?;
+            info!("Exporting training rows to {} ({:?} forward returns)", export.dir().display(), horizon);
+            Some(Arc::new(export))
+        }
+        Err(_) => None,
+    };
+    let training_flush = env_duration("TRAINING_EXPORT_FLUSH_SECS", Duration::from_secs(60), Duration::from_secs(1)..=DAY)?;
+
+    // Live ticks from another service: TICKS_SUBSCRIBE_STREAM replaces the mock
+    // feed with a consumer group (TICKS_SUBSCRIBE_GROUP / TICKS_SUBSCRIBE_CONSUMER)
+    let tick_subscriber = match env::var("TICKS_SUBSCRIBE_STREAM") {
+        Ok(stream) => {
+            let group = env::var("TICKS_SUBSCRIBE_GROUP").unwrap_or_else(|_| "pattern_engine".to_string());
+            let consumer = env::var("TICKS_SUBSCRIBE_CONSUMER").unwrap_or_else(|_| format!("pattern_engine-{}", std::process::id()));
+            let defaults = SubscriberConfig::new(&stream, &group, &consumer);
+            let config = SubscriberConfig {
+                batch: env_number("TICKS_SUBSCRIBE_BATCH", defaults.batch, 1..=100_000)?,
+                claim_idle: env_duration("TICKS_SUBSCRIBE_CLAIM_IDLE_SECS", defaults.claim_idle, Duration::from_secs(1)..=DAY)?,
+                ..defaults
+            };
+            Some(Subscriber::<Tick>::new(&redis_config, config)?)
+        }
+        Err(_) => None,
+    };
+
+    // Suppressed-signal logging: SUPPRESSED_SINK=stream|file|both enables it
+    let suppressed_sink = env::var("SUPPRESSED_SINK").unwrap_or_default().to_ascii_lowercase();
+    let suppression = match suppressed_sink.as_str() {
+        "stream" | "file" | "both" => {
+            let defaults = SuppressionConfig::default();
+            let config = SuppressionConfig {
+                max_tier: env_number("SUPPRESSED_MAX_TIER", defaults.max_tier, 1..=2)?,
+                sample_rate: env_fraction("SUPPRESSED_SAMPLE_RATE", defaults.sample_rate, 0.0..=1.0)?,
+                max_per_sec: env_number("SUPPRESSED_MAX_PER_SEC", defaults.max_per_sec, 0.0..=1_000_000.0)?,
+            };
+            let mut logger = SuppressionLogger::new(config);
+            if suppressed_sink != "stream" {
+                let path = env::var("SUPPRESSED_FILE").unwrap_or_else(|_| "suppressed_signals.jsonl".to_string());
+                logger = logger.with_file(std::path::Path::new(&path))?;
+            }
+            info!("Suppressed signal logging enabled ({})", suppressed_sink);
+            Some(Arc::new(Mutex::new(logger)))
+        }
+        _ => None,
+    };
+
+    // Timestamp source: TIMESTAMP_SOURCE sets the default, FEED_TIMESTAMP_SOURCES
+    // (feed=exchange|receive,...) overrides it per feed
+    let default_source = match env::var("TIMESTAMP_SOURCE") {
+        Ok(v) => v.parse::<TimestampSource>()?,
+        Err(_) => TimestampSource::Exchange,
+    };
+    let timestamps = TimestampPolicy::new(default_source)
+        .with_feed_spec(&env::var("FEED_TIMESTAMP_SOURCES").unwrap_or_default())?;
+
+    // Pause behaviour: PAUSE_POLICY=buffer|drop, PAUSE_BUFFER_CAPACITY bounds the buffer
+    let pause_policy = match env::var("PAUSE_POLICY") {
+        Ok(v) => v.parse::<PausePolicy>()?,
+        Err(_) => PausePolicy::Buffer,
+    };
+    let pause_capacity = env_number("PAUSE_BUFFER_CAPACITY", 10_000usize, 0..=10_000_000)?;
+
+    // Two-phase emission: EMISSION_MODE=two_phase publishes tick-level signals as
+    // provisional and follows up with confirmed/cancelled at the next 60s close
+    // SIGNAL_DEDUP_WINDOW publishes a pattern at most once per symbol and window
+    let dedup_window = env_duration("SIGNAL_DEDUP_WINDOW", Duration::ZERO, Duration::ZERO..=HOUR)?;
+    let dedup = (!dedup_window.is_zero()).then(|| Arc::new(Mutex::new(SignalDeduper::new(dedup_window.as_secs_f64()))));
+    let confirmation = match env::var("EMISSION_MODE").unwrap_or_default().to_ascii_lowercase().as_str() {
+        "two_phase" | "two-phase" => {
+            let max_adverse = env_fraction("CONFIRM_MAX_ADVERSE", 0.0, 0.0..=1.0)?;
+            info!("Two-phase signal emission enabled (max adverse move {})", max_adverse);
+            Some(Arc::new(Mutex::new(ConfirmationTracker::new(max_adverse))))
+        }
+        _ => None,
+    };
+
+    // Signal rules (YAML/JSON) from SIGNAL_RULES, compiled against the rule feature set
+    let signal_rules = match env::var("SIGNAL_RULES") {
+        Ok(path) => {
+            let loaded = rules::load_rules(std::path::Path::new(&path), &RULE_FEATURE_NAMES)?;
+            info!("Loaded {} signal rules from {}", loaded.len(), path);
+            loaded
+        }
+        Err(_) => Vec::new(),
+    };
+
+    // Ensemble: ENSEMBLE_WEIGHTS=pattern=weight,... rescales detector contributions;
+    // ENSEMBLE_MODEL scores the weighted components with a model instead, run
+    // like the named pattern models: with tract under INFERENCE_BACKEND=tract,
+    // otherwise with ONNX Runtime
+    let mut ensemble = Ensemble::from_spec(&env::var("ENSEMBLE_WEIGHTS").unwrap_or_default())?;
+    if let Ok(path) = env::var("ENSEMBLE_MODEL") {
+        let model_path = std::path::Path::new(&path);
+        let onnx = OnnxConfig::from_env()?;
+        ensemble = match inference_backend()? {
+            BackendKind::Tract => ensemble.with_model(TractClient::with_config(model_path, &onnx)?),
+            BackendKind::Onnx | BackendKind::Remote => ensemble.with_model(OnnxClient::with_config(model_path, &onnx)?),
+        };
+        info!("Scoring ensemble components with model {}", path);
+    }
+
+    // Per-signal extra metadata from SIGNAL_ENRICHERS (static fields from SIGNAL_EXTRA_STATIC)
+    let signal_enrichers = enrichers::build_enrichers(
+        &env::var("SIGNAL_ENRICHERS").unwrap_or_default(),
+        &env::var("SIGNAL_EXTRA_STATIC").unwrap_or_default(),
+    )?;
+    if !signal_enrichers.is_empty() {
+        let names: Vec<&str> = signal_enrichers.iter().map(|e| e.name()).collect();
+        info!("Signal enrichers: {}", names.join(", "));
+    }
+
+    // Exogenous features for inference: EXOGENOUS_FEATURES_FILE maps symbols to
+    // values for the EXOGENOUS_FEATURE_NAMES, cached for EXOGENOUS_TTL_SECS
+    let mut exogenous = ExogenousFeatures::new(
+        env_duration("EXOGENOUS_TTL_SECS", Duration::from_secs(60), Duration::ZERO..=DAY)?,
+        env_duration("EXOGENOUS_TIMEOUT", Duration::from_millis(250), Duration::from_millis(1)..=Duration::from_secs(30))?,
+    );
+    if let (Ok(path), Some(names)) = (env::var("EXOGENOUS_FEATURES_FILE"), exogenous_feature_names()) {
+        let names: Vec<&str> = names.iter().map(String::as_str).collect();
+        exogenous = exogenous.with_provider(Arc::new(FileProvider::new(&path, &names)));
+        info!("Exogenous features {} from {}", names.join(", "), path);
+    }
+
+    // Redis keyspace monitoring: REDIS_HEALTH_INTERVAL_SECS (0 disables), memory
+    // ratio thresholds, optional absolute limit and the untrimmed-level STREAM_MAXLEN
+    let keyspace_defaults = KeyspaceThresholds::default();
+    let keyspace_thresholds = KeyspaceThresholds {
+        elevated_memory_ratio: env_fraction("REDIS_ELEVATED_MEMORY_RATIO", keyspace_defaults.elevated_memory_ratio, 0.0..=1.0)?,
+        critical_memory_ratio: env_fraction("REDIS_CRITICAL_MEMORY_RATIO", keyspace_defaults.critical_memory_ratio, 0.0..=1.0)?,
+        elevated_stream_len: env_number("REDIS_ELEVATED_STREAM_LEN", keyspace_defaults.elevated_stream_len, 1..=u64::MAX)?,
+        memory_limit_bytes: env_optional("REDIS_MEMORY_LIMIT_BYTES", 1..=u64::MAX)?,
+    };
+    if keyspace_thresholds.critical_memory_ratio < keyspace_thresholds.elevated_memory_ratio {
+        anyhow::bail!(
+            "REDIS_CRITICAL_MEMORY_RATIO ({}) must not be below REDIS_ELEVATED_MEMORY_RATIO ({})",
+            keyspace_thresholds.critical_memory_ratio,
+            keyspace_thresholds.elevated_memory_ratio
+        );
+    }
+    let throttle_policy = ThrottlePolicy {
+        normal_maxlen: env_optional("STREAM_MAXLEN", 1..=usize::MAX)?,
+        ..ThrottlePolicy::default()
+    };
+    // SIGNALS_STREAM_MAXLEN / TICKS_STREAM_MAXLEN cap those streams even when
+    // STREAM_MAXLEN is unset; the tighter limit wins
+    {
+        let mut publisher = publisher.lock().await;
+        publisher.set_maxlen(throttle_policy.normal_maxlen);
+        let (signals, ticks) = (publisher.signals_stream().to_string(), publisher.ticks_stream().to_string());
+        publisher.set_stream_maxlen(&signals, env_optional("SIGNALS_STREAM_MAXLEN", 1..=usize::MAX)?);
+        publisher.set_stream_maxlen(&ticks, env_optional("TICKS_STREAM_MAXLEN", 1..=usize::MAX)?);
+    }
+    // STREAM_LIFECYCLE: auto lets XADD create streams; ensure creates them
+    // (trimmed to their MAXLEN) and the STREAM_GROUPS consumer groups at
+    // startup; external leaves creation to another component. Both of the
+    // latter append with NOMKSTREAM
+    let stream_lifecycle = match env::var("STREAM_LIFECYCLE") {
+        Ok(v) => v.parse::<StreamLifecycle>()?,
+        Err(_) => StreamLifecycle::Auto,
+    };
+    {
+        let mut publisher = publisher.lock().await;
+        publisher.set_nomkstream(stream_lifecycle.nomkstream());
+        if stream_lifecycle == StreamLifecycle::Ensure {
+            let groups: Vec<String> = env::var("STREAM_GROUPS")
+                .unwrap_or_default()
+                .split(',')
+                .map(|g| g.trim().to_string())
+                .filter(|g| !g.is_empty())
+                .collect();
+            // Redis may come up later; entries are buffered until then
+            match publisher.ensure_streams(&publisher.data_streams(), &groups).await {
+                Ok(created) => info!("Ensured streams ({} created) with groups {:?}", created.len(), groups),
+                Err(e) => warn!("Could not ensure streams: {}", e),
+            }
+        }
+    }
+    let keyspace_interval = env_duration("REDIS_HEALTH_INTERVAL_SECS", Duration::from_secs(15), Duration::ZERO..=HOUR)?.as_secs_f64();
+
+    // Degradation ladder: DEGRADE_WINDOW_SECS (0 disables), p99 latency SLO,
+    // optional tick-rate limit, hysteresis, hot tier cutoff and conflation interval
+    let degrade_defaults = DegradationPolicy::default();
+    let degradation_policy = DegradationPolicy {
+        latency_slo_ms: env_number("DEGRADE_LATENCY_SLO_MS", degrade_defaults.latency_slo_ms, 0.0..=60_000.0)?,
+        max_ticks_per_sec: env_number("DEGRADE_MAX_TICKS_PER_SEC", degrade_defaults.max_ticks_per_sec, 0.0..=1e9)?,
+        escalate_after: env_number("DEGRADE_ESCALATE_AFTER", degrade_defaults.escalate_after, 1..=1000)?,
+        recover_after: env_number("DEGRADE_RECOVER_AFTER", degrade_defaults.recover_after, 1..=1000)?,
+        hot_tier: env_number("DEGRADE_HOT_TIER", degrade_defaults.hot_tier, 0..=100)?,
+        conflate_secs: env_duration(
+            "DEGRADE_CONFLATE_SECS",
+            Duration::from_secs_f64(degrade_defaults.conflate_secs),
+            Duration::from_millis(1)..=Duration::from_secs(60),
+        )?
+        .as_secs_f64(),
+    };
+    let degrade_window = env_duration("DEGRADE_WINDOW_SECS", Duration::from_secs(5), Duration::ZERO..=HOUR)?.as_secs_f64();
+
+    // Confidence calibration: forward-return horizon and prior weight, persisted
+    // to PATTERN_STATS_FILE when set. PATTERN_STATS_HORIZONS=1m,1h adds
+    // horizons reported by /patterns/stats
+    let mut stats_horizons = Vec::new();
+    for raw in env::var("PATTERN_STATS_HORIZONS").unwrap_or_default().split(',').filter(|s| !s.trim().is_empty()) {
+        let horizon = duration_value("PATTERN_STATS_HORIZONS", Some(raw), Duration::ZERO, Duration::from_secs(1)..=DAY)?;
+        stats_horizons.push(horizon.as_secs_f64());
+    }
+    let mut pattern_stats = PatternStats::new(
+        env_duration("PATTERN_STATS_HORIZON_SECS", Duration::from_secs(300), Duration::from_secs(1)..=DAY)?.as_secs_f64(),
+        env_number("PATTERN_STATS_PRIOR_WEIGHT", 20.0, 0.0..=1_000_000.0)?,
+    )
+    .with_horizons(&stats_horizons);
+    let pattern_stats_file = env::var("PATTERN_STATS_FILE").ok();
+    if let Some(path) = pattern_stats_file.as_deref() {
+        let path = std::path::Path::new(path);
+        if path.exists() {
+            pattern_stats.load(path)?;
+            info!("Restored hit-rate stats for {} patterns from {}", pattern_stats.all().len(), path.display());
+        }
+    }
+    // An imported library carries the stats of the environment it came from
+    if pattern_stats.all().is_empty() {
+        if let Ok(path) = env::var("PATTERN_LIBRARY") {
+            pattern_stats.restore(LibraryExport::load(std::path::Path::new(&path))?.stats);
+        }
+    }
+
+    // Recent history: HISTORY_RETENTION=default=POINTS/AGE,TIER=POINTS/AGE,...
+    // bounds each series; HISTORY_BACKFILL preloads a CSV tick file at startup
+    let mut history = TimeSeriesStore::new(RetentionPolicy::from_spec(&env::var("HISTORY_RETENTION").unwrap_or_default())?);
+    if let Ok(path) = env::var("HISTORY_BACKFILL") {
+        let mut ticks = Vec::new();
+        let parsed = replay::replay_mmap(&path, 4096, |batch| ticks.extend(batch.iter().map(|r| r.to_tick())))?;
+        let loaded = history.backfill(ticks, &CANDLE_INTERVALS);
+        info!("Backfilled {} ticks of history from {} ({} malformed rows skipped)", loaded, path, parsed.skipped);
+    }
+
+    // Closed candles are published to CANDLES_STREAM_PREFIX:{interval}s on the
+    // Redis stream route unless PUBLISH_CANDLES=false
+    let publish_candles = env::var("PUBLISH_CANDLES").map_or(true, |v| !matches!(v.to_ascii_lowercase().as_str(), "0" | "false" | "off"));
+
+    // Check the model file for a replacement every MODEL_WATCH_SECS (0 disables)
+    let model_watch = env_duration("MODEL_WATCH_SECS", Duration::from_secs(10), Duration::ZERO..=HOUR)?;
+    // Status heartbeats to STATUS_STREAM every STATUS_HEARTBEAT_SECS (0 disables)
+    let status_interval = env_duration("STATUS_HEARTBEAT_SECS", Duration::from_secs(5), Duration::ZERO..=HOUR)?;
+
+    // Heat map: HEATMAP_INTERVAL_SECS between snapshots (0 disables) summarising
+    // signals from the last HEATMAP_WINDOW_SECS
+    let heatmap_interval = env_duration("HEATMAP_INTERVAL_SECS", Duration::from_secs(5), Duration::ZERO..=HOUR)?;
+    let heatmap_window = env_duration("HEATMAP_WINDOW_SECS", Duration::from_secs(300), Duration::from_secs(1)..=DAY)?;
+
+    // Pairs: PAIRS=LEFT/RIGHT,... fitted over PAIR_WINDOW samples, firing at |z| >= PAIR_ENTRY_Z
+    let pair_defaults = PairConfig::default();
+    let pair_config = PairConfig {
+        window: env_number("PAIR_WINDOW", pair_defaults.window, 10..=100_000)?,
+        entry_z: env_number("PAIR_ENTRY_Z", pair_defaults.entry_z, 0.5..=10.0)?,
+        ..pair_defaults
+    };
+    let pairs = PairSpec::parse_list(&env::var("PAIRS").unwrap_or_default())?;
+    if !pairs.is_empty() {
+        info!("Tracking {} symbol pairs", pairs.len());
+    }
+
+    // Market breadth: BREADTH_GROUPS=NAME=SYM|SYM,...,NAME=* sampled every
+    // BREADTH_SAMPLE_SECS once BREADTH_MIN_MEMBERS members have traded
+    let breadth_defaults = BreadthConfig::default();
+    let breadth_config = BreadthConfig {
+        sample_secs: env_duration(
+            "BREADTH_SAMPLE_SECS",
+            Duration::from_secs_f64(breadth_defaults.sample_secs),
+            Duration::from_secs(1)..=HOUR,
+        )?
+        .as_secs_f64(),
+        min_members: env_number("BREADTH_MIN_MEMBERS", breadth_defaults.min_members, 1..=100_000)?,
+        ..breadth_defaults
+    };
+    let breadth_groups = BreadthGroup::parse_list(&env::var("BREADTH_GROUPS").unwrap_or_default())?;
+    if !breadth_groups.is_empty() {
+        info!("Tracking breadth for {} symbol groups", breadth_groups.len());
+    }
+
+    // Feature flags: FEATURE_FLAGS=name=on|off,... overrides the defaults
+    let feature_flags = FeatureFlags::from_spec(&env::var("FEATURE_FLAGS").unwrap_or_default())?;
+    info!("Feature flags enabled: {:?}", feature_flags.enabled());
+
+    // Failed signal publishes are retried DLQ_MAX_ATTEMPTS times, DLQ_RETRY_BACKOFF
+    // apart (doubling), then written to DLQ_STREAM, or DLQ_FILE when that fails
+    let mut dead_letters = DeadLetterQueue::new().with_stream(publisher.clone());
+    if let Ok(path) = env::var("DLQ_FILE") {
+        dead_letters = dead_letters.with_file(std::path::Path::new(&path))?;
+    }
+    let dead_letters = Arc::new(dead_letters);
+    let retry_defaults = RetryPolicy::default();
+    let retry = RetryPolicy {
+        attempts: env_number("DLQ_MAX_ATTEMPTS", retry_defaults.attempts, 1..=100)?,
+        backoff: env_duration("DLQ_RETRY_BACKOFF", retry_defaults.backoff, Duration::ZERO..=Duration::from_secs(60))?,
+    };
+    let retried = |name: &str, sink: Arc<dyn Sink>| -> Arc<dyn Sink> {
+        Arc::new(DeadLetterSink::new(name, sink, retry, dead_letters.clone()))
+    };
+
+    // Signals fan out to every configured sink,
+    let broadcast = SignalBroadcast::new(env_number("WS_SIGNAL_BUFFER", 1024usize, 1..=1_000_000)?);
+    // and ticks to every sink that carries market data; with SIGNAL_SINK=grpc
+    // the Redis routes still get ticks
+    let keyspace = Arc::new(Mutex::new(KeyspaceMonitor::new(keyspace_thresholds, throttle_policy)));
+    let redis_route = |sink: Arc<dyn Sink>| -> Arc<dyn Sink> {
+        if signal_sink == "grpc" {
+            Arc::new(TicksOnly(sink))
+        } else {
+            sink
+        }
+    };
+    let mut sinks = FanoutSink::new().with("websocket", Arc::new(broadcast.clone()));
+    if redis_mode.streams() {
+        let sampled = SampledTicks::new(retried("redis", publisher.clone()), keyspace.clone());
+        sinks = sinks.with("redis", redis_route(Arc::new(sampled)));
+    }
+    if let Some(sink) = &pubsub {
+        sinks = sinks.with("redis-pubsub", redis_route(sink.clone()));
+    }
+    if let Some(sink) = &grpc_sink {
+        sinks = sinks.with("grpc", retried("grpc", sink.clone()));
+    }
+    if let Some(sink) = &zmq {
+        sinks = sinks.with("zmq", retried("zmq", sink.clone()));
+    }
+    if let Some(sink) = &file_sink {
+        sinks = sinks.with("file", sink.clone());
+    }
+    if let Some(sink) = &parquet {
+        sinks = sinks.with("parquet", sink.clone());
+    }
+    if let Some(sink) = &postgres {
+        sinks = sinks.with("postgres", sink.clone());
+    }
+    info!("Signal sinks: {}", sinks.names().join(", "));
+
+    let app_state = AppState {
+        publisher: publisher.clone(),
+        symbol_states: symbol_states.clone(),
+        inference: Arc::new(Mutex::new(inference_pool)),
+        universe: Arc::new(Mutex::new(Universe::default())),
+        ticks_processed: Arc::new(AtomicU64::new(0)),
+        inferred_count: Arc::new(AtomicU64::new(0)),
+        known_count: Arc::new(AtomicU64::new(0)),
+        total_infer_latency_ns: Arc::new(AtomicU64::new(0)),
+        per_symbol_metrics: Arc::new(Mutex::new(HashMap::new())),
+        attribution_sample_rate: env_fraction("ATTRIBUTION_SAMPLE_RATE", 0.0, 0.0..=1.0)?,
+        suppression,
+        suppressed_to_stream: suppressed_sink == "stream" || suppressed_sink == "both",
+        sinks: Arc::new(sinks),
+        dead_letters,
+        grpc_sink,
+        parquet: parquet.clone(),
+        postgres: postgres.clone(),
+        training: training.clone(),
+        broadcast,
+        timestamps: Arc::new(timestamps),
+        ingest: Arc::new(Mutex::new(IngestGate::new(pause_policy, pause_capacity))),
+        confirmation,
+        dedup,
+        flags: Arc::new(Mutex::new(feature_flags)),
+        rules: Arc::new(signal_rules),
+        ensemble: Arc::new(ensemble),
+        keyspace,
+        pattern_stats: Arc::new(Mutex::new(pattern_stats)),
+        enrichers: Arc::new(signal_enrichers),
+        exogenous: Arc::new(exogenous),
+        degradation: Arc::new(Mutex::new(DegradationLadder::new(degradation_policy))),
+        publish_candles,
+        history: Arc::new(Mutex::new(history)),
+        heatmap: Arc::new(Mutex::new(HeatMap::new(heatmap_window.as_secs_f64()))),
+        pairs: Arc::new(Mutex::new(PairTracker::new(pairs, pair_config))),
+        breadth: Arc::new(Mutex::new(BreadthTracker::new(breadth_groups, breadth_config))),
+        supervisor: Arc::new(Mutex::new(Supervisor::new())),
+        aggregator_epoch: Arc::new(AtomicU64::new(0)),
+    };
+
+    // Restartable subsystems; the feed (mock tick generation) starts here
+    {
+        let mut supervisor = app_state.supervisor.lock().await;
+        let state = app_state.clone();
+        supervisor.add_task("feeds", move || {
+            let (state, tick_subscriber) = (state.clone(), tick_subscriber.clone());
+            tokio::spawn(async move {
+                let result = match tick_subscriber {
+                    Some(subscriber) => consume_stream_ticks(state, subscriber).await,
+                    None => generate_mock_ticks(state).await,
+                };
+                if let Err(e) = result {
+                    error!("Tick generation failed: {}", e);
+                }
+            })
+        });
+        let state = app_state.clone();
+        supervisor.add_resource("publisher", move || {
+            let (state, redis_config) = (state.clone(), redis_config.clone());
+            Box::pin(async move {
+                let mut fresh = Publisher::with_config(&redis_config)?;
+                fresh.set_maxlen(state.keyspace.lock().await.maxlen());
+                {
+                    let current = state.publisher.lock().await;
+                    fresh.set_tag_routes(current.tag_routes().to_vec());
+                    fresh.set_encoding(current.encoding());
+                    fresh.set_compression(current.compression());
+                    fresh.set_wal(current.wal());
+                    fresh.set_limiter(current.limiter());
+                    fresh.set_metrics(current.metrics());
+                    fresh.set_producer(current.producer());
+                    fresh.set_nomkstream(current.nomkstream());
+                    for (stream, maxlen) in current.stream_maxlens() {
+                        fresh.set_stream_maxlen(stream, Some(*maxlen));
+                    }
+                }
+                *state.publisher.lock().await = fresh;
+                Ok(())
+            })
+        });
+        let state = app_state.clone();
+        supervisor.add_resource("aggregator", move || {
+            state.aggregator_epoch.fetch_add(1, Ordering::Relaxed);
+            Box::pin(async { Ok(()) })
+        });
+        let state = app_state.clone();
+        supervisor.add_resource("pattern_lib", move || {
+            let state = state.clone();
+            Box::pin(async move {
+                let library = Arc::new(load_pattern_library(cache_config)?);
+                let gates = library.gates();
+                let pool = Arc::new(
+                    InferencePool::new(library, inference_workers, inference_queue)?
+                        .with_shed_policy(shed_policy)
+                        .with_deadline(inference_deadline),
+                );
+                *state.inference.lock().await = pool;
+                for st in state.symbol_states.lock().await.values_mut() {
+                    st.pipeline.set_gates(gates.clone());
+                }
+                Ok(())
+            })
+        });
+    }
+    tokio::spawn(supervise(app_state.clone(), Duration::from_secs(5)));
+
+    if let Some(path) = pattern_stats_file {
+        tokio::spawn(persist_pattern_stats(app_state.clone(), path, Duration::from_secs(60)));
+    }
+
+    if degrade_window > 0.0 {
+        tokio::spawn(monitor_degradation(app_state.clone(), Duration::from_secs_f64(degrade_window)));
+    }
+
+    if let Some(limiter) = app_state.publisher.lock().await.limiter() {
+        limiter.lock().unwrap_or_else(|e| e.into_inner()).set_track_deferred(app_state.suppression.is_some());
+        tokio::spawn(release_rate_limited(app_state.clone(), rate_release));
+    }
+
+    if app_state.publisher.lock().await.wal().is_some() {
+        tokio::spawn(flush_wal(app_state.clone(), wal_flush));
+    }
+
+    if let Some(sink) = parquet {
+        tokio::spawn(flush_parquet(sink, parquet_flush));
+    }
+    if let Some(export) = training {
+        tokio::spawn(flush_training(export, training_flush));
+    }
+    if let Some(sink) = postgres {
+        tokio::spawn(flush_postgres(sink, postgres_flush));
+    }
+
+    if !heatmap_interval.is_zero() {
+        tokio::spawn(publish_heatmap(app_state.clone(), heatmap_interval));
+    }
+
+    if !model_watch.is_zero() {
+        tokio::spawn(watch_model(app_state.clone(), model_watch));
+    }
+    if !status_interval.is_zero() {
+        tokio::spawn(publish_status(app_state.clone(), status_interval));
+    }
+
+    if keyspace_interval > 0.0 {
+        tokio::spawn(monitor_keyspace(app_state.clone(), Duration::from_secs_f64(keyspace_interval)));
+    }
 
     // Build Axum router
     let app = Router::new()
         .route("/health", get(health_check))
+        .route("/health/redis", get(redis_health))
+        .route("/health/streams", get(stream_health))
         .route("/metrics", get(metrics))
+        .route("/metrics/bursts", get(burst_metrics))
+        .route("/symbols/import", post(import_symbols))
+        .route("/symbols/export", get(export_symbols))
+        .route("/admin/pause", post(pause_engine))
+        .route("/admin/resume", post(resume_engine))
+        .route("/admin/status", get(pause_status))
+        .route("/admin/subsystems", get(list_subsystems))
+        .route("/admin/subsystems/:name/restart", post(restart_subsystem))
+        .route("/admin/model/reload", post(reload_model))
+        .route("/admin/model/canary", post(set_canary_percent))
+        .route("/flags", get(list_flags))
+        .route("/flags/:name", post(set_flag))
+        .route("/version", get(version))
+        .route("/history/:symbol/ticks", get(history_ticks))
+        .route("/history/:symbol/candles", get(history_candles))
+        .route("/history/:symbol/signals", get(history_signals))
+        .route("/pairs", get(pair_stats))
+        .route("/breadth", get(breadth_snapshot))
+        .route("/ws/signals", get(signals_ws))
+        .route("/patterns", get(list_patterns))
+        .route("/patterns/stats", get(pattern_outcome_stats))
+        .route("/patterns/export", get(export_patterns))
         .layer(CorsLayer::permissive())
         .with_state(app_state);
 
@@ -1,53 +1,832 @@
 //! Optional ONNX client for ML inference.
 //!
-//! This module provides an interface to ONNX Runtime for model inference.
-//! It's optional and can be disabled if ONNX support is not needed.
+//! With the `onnx` feature, [`OnnxClient`] runs a model through ONNX Runtime
+//! (loaded at run time from `ORT_DYLIB_PATH` or the system library path).
+//! The model must take a single tensor of features, either `[n]` or
+//! `[batch, n]`, and its first output must be a float tensor whose first
+//! element per row is the score. Inputs may be float32 or float64, or int8 or
+//! uint8 for quantized models; features are then quantized with the
+//! [`Quantization`] from the config or the model's `input_scale` and
+//! `input_zero_point` metadata. [`OnnxClient::infer_batch`] scores many
+//! rows in a single call when the batch dimension is dynamic. Without the
+//! feature a deterministic stub scores the feature mean instead.
+//! Shapes and dtypes are checked when the model loads, and a fixed input
+//! width must be one of [`OnnxConfig::feature_widths`], so a model trained on
+//! a different feature vector fails at startup (or its reload is refused)
+//! instead of scoring garbage. A model recording the [`FeatureSchema`] hash
+//! it was trained on in its `feature_schema` metadata must match
+//! [`OnnxConfig::feature_schema`].
+//!
+//! A [`FeatureScaler`] found next to the model (see [`crate::scaler`]) is
+//! loaded and reloaded with it and applied to every row before inference.
+//! Likewise [`ClassLabels`] (see [`crate::labels`]) mark a classification
+//! model, whose `[batch, k]` output is a probability per label;
+//! [`OnnxClient::classify`] returns its top class.
+//!
+//! [`OnnxConfig`] picks the execution providers (CUDA, TensorRT, CoreML,
+//! CPU; tried in order, falling back to the next when one is unavailable)
+//! and session options per deployment.
+//!
+//! [`OnnxClient::stats`] reports calls, errors, throughput and a latency
+//! histogram (see [`crate::inference_metrics`]).
+//!
+//! [`OnnxClient::reload`] swaps in a new model from the same path without
+//! restarting; [`OnnxClient::reload_if_changed`] does so only when the file
+//! was replaced. Deploy new models by renaming them into place. Each load
+//! runs [`OnnxConfig::warmup_runs`] all-zero inferences before the model
+//! serves, and [`OnnxClient::warmup`] reports how long they took.
 
 #[cfg(feature = "onnx")]
-use anyhow::Result;
+use anyhow::{anyhow, bail, Result};
+#[cfg(feature = "onnx")]
+use ort::execution_providers::{
+    CPUExecutionProvider, CUDAExecutionProvider, CoreMLExecutionProvider, ExecutionProviderDispatch,
+    TensorRTExecutionProvider,
+};
+#[cfg(feature = "onnx")]
+use ort::session::builder::GraphOptimizationLevel;
+#[cfg(feature = "onnx")]
+use ort::session::Session;
 #[cfg(feature = "onnx")]
-use std::path::Path;
+use ort::tensor::TensorElementType;
+#[cfg(feature = "onnx")]
+use ort::value::{Tensor, ValueType};
+#[cfg(feature = "onnx")]
+use crate::labels::check_label_count;
+use crate::inference_metrics::{InferenceMetrics, ModelStats};
+use crate::labels::{ClassLabels, Classification};
+use crate::features::FeatureSchema;
+use crate::scaler::FeatureScaler;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Instant, SystemTime};
+
+use crate::config::{env_number, env_optional};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExecutionProvider {
+    Cpu,
+    Cuda,
+    TensorRt,
+    CoreMl,
+}
+
+impl std::str::FromStr for ExecutionProvider {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "cpu" => Ok(ExecutionProvider::Cpu),
+            "cuda" => Ok(ExecutionProvider::Cuda),
+            "tensorrt" | "trt" => Ok(ExecutionProvider::TensorRt),
+            "coreml" => Ok(ExecutionProvider::CoreMl),
+            other => anyhow::bail!("unknown execution provider '{}' (expected cpu, cuda, tensorrt or coreml)", other),
+        }
+    }
+}
+
+/// Graph optimizations applied when a session is created
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OptimizationLevel {
+    Disable,
+    Basic,
+    Extended,
+    #[default]
+    All,
+}
+
+impl std::str::FromStr for OptimizationLevel {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "disable" | "none" | "0" => Ok(OptimizationLevel::Disable),
+            "basic" | "1" => Ok(OptimizationLevel::Basic),
+            "extended" | "2" => Ok(OptimizationLevel::Extended),
+            "all" | "3" => Ok(OptimizationLevel::All),
+            other => anyhow::bail!("unknown graph optimization level '{}' (expected disable, basic, extended or all)", other),
+        }
+    }
+}
+
+/// Execution providers and session options for ONNX Runtime
+#[derive(Debug, Clone, PartialEq)]
+pub struct OnnxConfig {
+    /// Tried in order; ONNX Runtime falls back to the next one that loads
+    pub providers: Vec<ExecutionProvider>,
+    /// GPU used by CUDA and TensorRT
+    pub device_id: i32,
+    /// Threads used within an operator (None = ONNX Runtime default)
+    pub intra_threads: Option<usize>,
+    /// Threads running independent operators in parallel
+    pub inter_threads: Option<usize>,
+    pub optimization: OptimizationLevel,
+    /// Feature counts the engine sends; a model declaring any other fixed
+    /// input width is refused at load (empty accepts any width)
+    pub feature_widths: Vec<usize>,
+    /// [`FeatureSchema::hash`] of the vector the engine sends; a model whose
+    /// `feature_schema` metadata differs is refused at load
+    pub feature_schema: Option<String>,
+    /// Quantization of int8/uint8 inputs; overrides the model's metadata
+    pub input_quantization: Option<Quantization>,
+    /// All-zero inferences run per feature width after each load, so the
+    /// first live signal doesn't pay for lazy allocation (0 disables)
+    pub warmup_runs: usize,
+}
+
+impl Default for OnnxConfig {
+    fn default() -> Self {
+        Self {
+            providers: vec![ExecutionProvider::Cpu],
+            device_id: 0,
+            intra_threads: None,
+            inter_threads: None,
+            optimization: OptimizationLevel::default(),
+            feature_widths: Vec::new(),
+            feature_schema: None,
+            input_quantization: None,
+            warmup_runs: 3,
+        }
+    }
+}
+
+impl OnnxConfig {
+    /// Read ONNX_PROVIDERS (e.g. `tensorrt,cuda,cpu`), ONNX_DEVICE_ID,
+    /// ONNX_INTRA_THREADS, ONNX_INTER_THREADS, ONNX_OPTIMIZATION,
+    /// ONNX_WARMUP_RUNS and, for quantized inputs, ONNX_INPUT_SCALE and
+    /// ONNX_INPUT_ZERO_POINT
+    pub fn from_env() -> anyhow::Result<Self> {
+        let defaults = Self::default();
+        let providers = match std::env::var("ONNX_PROVIDERS") {
+            Ok(list) => parse_providers(&list)?,
+            Err(_) => defaults.providers,
+        };
+        let optimization = match std::env::var("ONNX_OPTIMIZATION") {
+            Ok(level) => level.parse()?,
+            Err(_) => defaults.optimization,
+        };
+        Ok(Self {
+            providers,
+            device_id: env_number("ONNX_DEVICE_ID", defaults.device_id, 0..=64)?,
+            intra_threads: env_optional("ONNX_INTRA_THREADS", 1..=1024)?,
+            inter_threads: env_optional("ONNX_INTER_THREADS", 1..=1024)?,
+            optimization,
+            feature_widths: defaults.feature_widths,
+            feature_schema: defaults.feature_schema,
+            input_quantization: match env_optional("ONNX_INPUT_SCALE", f64::MIN_POSITIVE..=f64::MAX)? {
+                Some(scale) => Some(Quantization::new(scale, env_number("ONNX_INPUT_ZERO_POINT", 0, -128..=255)?)?),
+                None => None,
+            },
+            warmup_runs: env_number("ONNX_WARMUP_RUNS", defaults.warmup_runs, 0..=1000)?,
+        })
+    }
+
+    /// Only load models taking one of `widths` features (or a dynamic width)
+    pub fn with_feature_widths(mut self, widths: impl IntoIterator<Item = usize>) -> Self {
+        self.feature_widths = widths.into_iter().collect();
+        self.feature_widths.sort_unstable();
+        self.feature_widths.dedup();
+        self
+    }
+
+    /// Only load models taking `schema`'s features: its width, and its hash
+    /// when the model records one
+    pub fn with_feature_schema(self, schema: &FeatureSchema) -> Self {
+        Self { feature_schema: Some(schema.hash()), ..self.with_feature_widths([schema.len()]) }
+    }
+
+    /// Whether any provider other than the CPU is requested
+    pub fn accelerated(&self) -> bool {
+        self.providers.iter().any(|p| *p != ExecutionProvider::Cpu)
+    }
+}
+
+/// Parse a comma-separated provider list
+pub fn parse_providers(list: &str) -> anyhow::Result<Vec<ExecutionProvider>> {
+    let providers = list
+        .split(',')
+        .filter(|p| !p.trim().is_empty())
+        .map(str::parse)
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    if providers.is_empty() {
+        anyhow::bail!("no execution providers given");
+    }
+    Ok(providers)
+}
+
+/// Features per row expected by a model input of `shape`: the last
+/// dimension, or None when it is dynamic. Inputs must be `[n]` or `[batch, n]`.
+pub fn feature_width(shape: &[i64]) -> anyhow::Result<Option<usize>> {
+    match shape {
+        [n] | [_, n] => Ok((*n >= 0).then_some(*n as usize)),
+        _ => anyhow::bail!("model input must have shape [n] or [batch, n], got {:?}", shape),
+    }
+}
+
+/// Affine quantization of features for int8/uint8 model inputs:
+/// `q = round(x / scale) + zero_point`, saturated to the type's range
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quantization {
+    pub scale: f64,
+    pub zero_point: i32,
+}
+
+impl Quantization {
+    pub fn new(scale: f64, zero_point: i32) -> anyhow::Result<Self> {
+        if !(scale.is_finite() && scale > 0.0) {
+            anyhow::bail!("quantization scale must be positive, got {}", scale);
+        }
+        Ok(Self { scale, zero_point })
+    }
+
+    /// From the model's `input_scale` and `input_zero_point` metadata values
+    /// (zero point 0 when absent); None without a scale
+    pub fn from_metadata(scale: Option<&str>, zero_point: Option<&str>) -> anyhow::Result<Option<Self>> {
+        let Some(scale) = scale else {
+            return Ok(None);
+        };
+        let scale = scale.trim().parse().map_err(|_| anyhow::anyhow!("bad input_scale metadata '{}'", scale))?;
+        let zero_point = match zero_point {
+            Some(z) => z.trim().parse().map_err(|_| anyhow::anyhow!("bad input_zero_point metadata '{}'", z))?,
+            None => 0,
+        };
+        Self::new(scale, zero_point).map(Some)
+    }
+
+    fn quantize(&self, value: f64, min: i64, max: i64) -> i64 {
+        ((value / self.scale).round() as i64).saturating_add(self.zero_point as i64).clamp(min, max)
+    }
+
+    pub fn to_i8(&self, value: f64) -> i8 {
+        self.quantize(value, i8::MIN as i64, i8::MAX as i64) as i8
+    }
+
+    pub fn to_u8(&self, value: f64) -> u8 {
+        self.quantize(value, 0, u8::MAX as i64) as u8
+    }
+}
+
+/// Element type features are converted to for the model input
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InputType {
+    F32,
+    F64,
+    I8(Quantization),
+    U8(Quantization),
+}
+
+/// Check a model's declared input width against the feature counts the engine
+/// sends (`accepted`; empty accepts any). A dynamic width always passes.
+pub fn check_feature_width(width: Option<usize>, accepted: &[usize]) -> anyhow::Result<()> {
+    match width {
+        Some(width) if !accepted.is_empty() && !accepted.contains(&width) => anyhow::bail!(
+            "model input takes {} features but the engine sends {}",
+            width,
+            accepted.iter().map(|w| w.to_string()).collect::<Vec<_>>().join(" or ")
+        ),
+        _ => Ok(()),
+    }
+}
+
+/// Check the `feature_schema` hash a model was trained with against the one
+/// the engine sends; either being unknown passes
+pub fn check_feature_schema(model: Option<&str>, expected: Option<&str>) -> anyhow::Result<()> {
+    match (model, expected) {
+        (Some(model), Some(expected)) if model.trim() != expected => {
+            anyhow::bail!("model was trained on feature schema {} but the engine sends {}", model.trim(), expected)
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Check a model output of `shape` yields a score per row: `[n]` or
+/// `[batch, k]` with at least one value
+pub fn check_output_shape(shape: &[i64]) -> anyhow::Result<()> {
+    match shape {
+        [n] | [_, n] if *n != 0 => Ok(()),
+        _ => anyhow::bail!("model output must have shape [batch] or [batch, k] with k > 0, got {:?}", shape),
+    }
+}
+
+/// Modification time and size of a model file, to notice when it is replaced
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileStamp {
+    modified: Option<SystemTime>,
+    len: u64,
+}
+
+impl FileStamp {
+    /// None if the file cannot be read
+    pub fn of(path: &Path) -> Option<Self> {
+        let meta = std::fs::metadata(path).ok()?;
+        Some(Self { modified: meta.modified().ok(), len: meta.len() })
+    }
+}
+
+/// Timing of the warm-up inferences run when a model was (re)loaded
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WarmupReport {
+    /// Inferences run, over all widths
+    pub runs: usize,
+    /// Feature counts warmed up
+    pub widths: Vec<usize>,
+    /// The first, cold inference
+    pub first_ms: f64,
+    /// The last inference, roughly what a live signal will see
+    pub last_ms: f64,
+    pub total_ms: f64,
+}
+
+/// Run `runs` all-zero inferences through `infer` for each of `widths`. None
+/// when there is nothing to run; any failure fails the warm-up.
+pub fn warm_up(
+    runs: usize,
+    widths: &[usize],
+    mut infer: impl FnMut(&[f64]) -> anyhow::Result<f64>,
+) -> anyhow::Result<Option<WarmupReport>> {
+    if runs == 0 || widths.is_empty() {
+        return Ok(None);
+    }
+    let mut timings = Vec::with_capacity(runs * widths.len());
+    for &width in widths {
+        let row = vec![0.0; width];
+        for _ in 0..runs {
+            let start = Instant::now();
+            infer(&row).map_err(|e| anyhow::anyhow!("warm-up inference with {} features failed: {:#}", width, e))?;
+            timings.push(start.elapsed().as_secs_f64() * 1000.0);
+        }
+    }
+    Ok(Some(WarmupReport {
+        runs: timings.len(),
+        widths: widths.to_vec(),
+        first_ms: timings[0],
+        last_ms: timings[timings.len() - 1],
+        total_ms: timings.iter().sum(),
+    }))
+}
+
+/// Widths to warm up: the model's own when fixed, else every accepted one
+pub(crate) fn warmup_widths(fixed: Option<usize>, accepted: &[usize]) -> Vec<usize> {
+    fixed.map_or_else(|| accepted.to_vec(), |w| vec![w])
+}
+
+/// `rows` scaled by the model's `scaler`, checked to all be `width` features
+/// wide (or as wide as the first row when the model takes any width)
+pub fn model_rows(rows: &[&[f64]], scaler: Option<&FeatureScaler>, width: Option<usize>) -> anyhow::Result<Vec<Vec<f64>>> {
+    let rows: Vec<Vec<f64>> = match scaler {
+        Some(scaler) => rows.iter().map(|r| scaler.apply(r)).collect::<anyhow::Result<_>>()?,
+        None => rows.iter().map(|r| r.to_vec()).collect(),
+    };
+    if let Some(expected) = width.or_else(|| rows.first().map(Vec::len)) {
+        if let Some(row) = rows.iter().find(|r| r.len() != expected) {
+            anyhow::bail!("model expects {} features, got {}", expected, row.len());
+        }
+    }
+    Ok(rows)
+}
+
+/// Values per row in a flattened `[batch]` or `[batch, k]` output of
+/// `values` values for `rows` rows
+pub fn output_stride(values: usize, rows: usize) -> anyhow::Result<usize> {
+    if values == 0 || !values.is_multiple_of(rows) {
+        anyhow::bail!("model returned {} values for {} rows", values, rows);
+    }
+    Ok(values / rows)
+}
+
+/// Each row's score, the first of its `stride` output values, clamped to
+/// [-1, 1]; a non-finite score is an error
+pub fn row_scores(values: &[f64], stride: usize) -> anyhow::Result<Vec<f64>> {
+    values
+        .iter()
+        .step_by(stride.max(1))
+        .map(|&score| {
+            if !score.is_finite() {
+                anyhow::bail!("model returned a non-finite score {}", score);
+            }
+            Ok(score.clamp(-1.0, 1.0))
+        })
+        .collect()
+}
+
+/// A local model file with its load stamp, warm-up report and call metrics,
+/// kept the same way by [`OnnxClient`] and
+/// [`TractClient`](crate::tract_client::TractClient)
+pub struct ModelFile {
+    path: PathBuf,
+    /// File the current model was loaded from
+    stamp: Mutex<Option<FileStamp>>,
+    warmup: Mutex<Option<WarmupReport>>,
+    metrics: InferenceMetrics,
+}
+
+impl ModelFile {
+    /// `path` as it was when loading began (`stamp`), so a file replaced
+    /// during the load is picked up again
+    pub fn new(path: &Path, stamp: Option<FileStamp>, warmup: Option<WarmupReport>) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            stamp: Mutex::new(stamp),
+            warmup: Mutex::new(warmup),
+            metrics: InferenceMetrics::default(),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn metrics(&self) -> &InferenceMetrics {
+        &self.metrics
+    }
+
+    /// Calls, errors, throughput and latency since the client was created
+    pub fn stats(&self) -> ModelStats {
+        self.metrics.stats()
+    }
+
+    /// Warm-up timing from the last (re)load, if one ran
+    pub fn warmup(&self) -> Option<WarmupReport> {
+        self.warmup.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Note a successful (re)load of the file as it was at `stamp`
+    pub fn loaded(&self, stamp: Option<FileStamp>, warmup: Option<WarmupReport>) {
+        *self.stamp.lock().unwrap_or_else(|e| e.into_inner()) = stamp;
+        *self.warmup.lock().unwrap_or_else(|e| e.into_inner()) = warmup;
+    }
+
+    /// Run `reload` when the file was replaced since it was last loaded.
+    /// A file that fails to load is not retried until it changes again, so a
+    /// model copied into place in several writes is picked up once complete.
+    pub fn reload_if_changed(&self, reload: impl FnOnce() -> anyhow::Result<()>) -> anyhow::Result<bool> {
+        let current = FileStamp::of(&self.path);
+        {
+            let mut stamp = self.stamp.lock().unwrap_or_else(|e| e.into_inner());
+            if current.is_none() || *stamp == current {
+                return Ok(false);
+            }
+            *stamp = current;
+        }
+        reload().map(|()| true)
+    }
+}
+
+/// A loaded session and what its input looks like
+#[cfg(feature = "onnx")]
+struct Model {
+    session: Session,
+    input_rank: usize,
+    /// Input is `[batch, n]` with a dynamic batch size
+    batched: bool,
+    input_type: InputType,
+    /// Fixed feature count, if the model declares one
+    width: Option<usize>,
+    /// Normalization applied to raw features before the session runs
+    scaler: Option<FeatureScaler>,
+    /// Class per output value, for classification models
+    labels: Option<ClassLabels>,
+}
 
 #[cfg(feature = "onnx")]
 pub struct OnnxClient {
-    // ONNX runtime session would go here
-    // For now, this is a placeholder
+    // `Session::run` needs exclusive access; reloads swap the whole model
+    model: Mutex<Model>,
+    config: OnnxConfig,
+    file: ModelFile,
+}
+
+#[cfg(feature = "onnx")]
+fn float_tensor(value_type: &ValueType, what: &str) -> Result<(TensorElementType, Vec<i64>)> {
+    match value_type {
+        ValueType::Tensor { ty: ty @ (TensorElementType::Float32 | TensorElementType::Float64), shape, .. } => {
+            Ok((*ty, shape.to_vec()))
+        }
+        other => bail!("{} must be a float32 or float64 tensor, got {:?}", what, other),
+    }
+}
+
+#[cfg(feature = "onnx")]
+impl Model {
+    /// Load the model at `path` and check its inputs and outputs
+    fn load(path: &Path, config: &OnnxConfig) -> Result<Self> {
+        if !path.is_file() {
+            bail!("model file {} not found", path.display());
+        }
+        let providers: Vec<ExecutionProviderDispatch> = config
+            .providers
+            .iter()
+            .map(|p| match p {
+                ExecutionProvider::Cpu => CPUExecutionProvider::default().build(),
+                ExecutionProvider::Cuda => CUDAExecutionProvider::default().with_device_id(config.device_id).build(),
+                ExecutionProvider::TensorRt => TensorRTExecutionProvider::default().with_device_id(config.device_id).build(),
+                ExecutionProvider::CoreMl => CoreMLExecutionProvider::default().build(),
+            })
+            .collect();
+        let optimization = match config.optimization {
+            OptimizationLevel::Disable => GraphOptimizationLevel::Disable,
+            OptimizationLevel::Basic => GraphOptimizationLevel::Level1,
+            OptimizationLevel::Extended => GraphOptimizationLevel::Level2,
+            OptimizationLevel::All => GraphOptimizationLevel::Level3,
+        };
+        let mut builder = Session::builder()?.with_execution_providers(providers)?.with_optimization_level(optimization)?;
+        if let Some(threads) = config.intra_threads {
+            builder = builder.with_intra_threads(threads)?;
+        }
+        if let Some(threads) = config.inter_threads {
+            builder = builder.with_parallel_execution(true)?.with_inter_threads(threads)?;
+        }
+        let session = builder
+            .commit_from_file(path)
+            .map_err(|e| anyhow!("failed to load model {}: {}", path.display(), e))?;
+        let [input] = session.inputs.as_slice() else {
+            let names: Vec<_> = session.inputs.iter().map(|i| i.name.as_str()).collect();
+            bail!("model {} must take a single feature input, has {:?}", path.display(), names);
+        };
+        let ValueType::Tensor { ty, shape, .. } = &input.input_type else {
+            bail!("model input '{}' must be a tensor, got {:?}", input.name, input.input_type);
+        };
+        let shape = shape.to_vec();
+        let schema = session.metadata()?.custom("feature_schema")?;
+        check_feature_schema(schema.as_deref(), config.feature_schema.as_deref())
+            .map_err(|e| anyhow!("model {}: {}", path.display(), e))?;
+        let input_type = match ty {
+            TensorElementType::Float32 => InputType::F32,
+            TensorElementType::Float64 => InputType::F64,
+            TensorElementType::Int8 | TensorElementType::Uint8 => {
+                let quantization = match config.input_quantization {
+                    Some(q) => q,
+                    None => {
+                        let metadata = session.metadata()?;
+                        let scale = metadata.custom("input_scale")?;
+                        let zero_point = metadata.custom("input_zero_point")?;
+                        Quantization::from_metadata(scale.as_deref(), zero_point.as_deref())?.ok_or_else(|| {
+                            anyhow!(
+                                "model {} has a quantized input but no input_scale metadata; set ONNX_INPUT_SCALE",
+                                path.display()
+                            )
+                        })?
+                    }
+                };
+                if *ty == TensorElementType::Int8 {
+                    InputType::I8(quantization)
+                } else {
+                    InputType::U8(quantization)
+                }
+            }
+            other => bail!("model input '{}' must be float32, float64, int8 or uint8, got {:?}", input.name, other),
+        };
+        let width = feature_width(&shape)?;
+        check_feature_width(width, &config.feature_widths).map_err(|e| anyhow!("model {}: {}", path.display(), e))?;
+        let output = session.outputs.first().ok_or_else(|| anyhow!("model {} has no outputs", path.display()))?;
+        let (_, output_shape) = float_tensor(&output.output_type, &format!("model output '{}'", output.name))?;
+        check_output_shape(&output_shape).map_err(|e| anyhow!("model {}: {}", path.display(), e))?;
+        let labels = ClassLabels::load_for_model(path)?;
+        if let Some(labels) = &labels {
+            check_label_count(&output_shape, labels).map_err(|e| anyhow!("model {}: {}", path.display(), e))?;
+        }
+        let scaler = FeatureScaler::load_for_model(path)?;
+        if let Some(scaler) = &scaler {
+            if width.is_some_and(|w| w != scaler.len()) {
+                bail!("model {} takes {:?} features but its scaler lists {}", path.display(), width, scaler.len());
+            }
+            check_feature_width(Some(scaler.len()), &config.feature_widths)
+                .map_err(|e| anyhow!("scaler for model {}: {}", path.display(), e))?;
+        }
+        let batched = shape.len() == 2 && shape[0] < 0;
+        Ok(Self { session, input_rank: shape.len(), batched, input_type, width, scaler, labels })
+    }
+
+    /// Run the configured warm-up inferences
+    fn warm_up(&mut self, config: &OnnxConfig) -> Result<Option<WarmupReport>> {
+        let fixed = self.scaler.as_ref().map(FeatureScaler::len).or(self.width);
+        warm_up(config.warmup_runs, &warmup_widths(fixed, &config.feature_widths), |row| Ok(self.run(&[row])?[0]))
+    }
+
+    /// One score per row, each clamped to [-1, 1]; rows are scaled first
+    fn run(&mut self, rows: &[&[f64]]) -> Result<Vec<f64>> {
+        let (values, stride) = self.outputs(rows)?;
+        row_scores(&values, stride)
+    }
+
+    /// Top class for one row; None unless the model has labels
+    fn classify(&mut self, features: &[f64]) -> Result<Option<Classification>> {
+        if self.labels.is_none() {
+            return Ok(None);
+        }
+        let (values, _) = self.outputs(&[features])?;
+        self.labels.as_ref().map(|labels| labels.top(&values)).transpose()
+    }
+
+    /// Flattened first output for `rows` and the number of values per row;
+    /// rows are scaled first
+    fn outputs(&mut self, rows: &[&[f64]]) -> Result<(Vec<f64>, usize)> {
+        let rows = model_rows(rows, self.scaler.as_ref(), self.width)?;
+        let Some(width) = rows.first().map(Vec::len) else {
+            return Ok((Vec::new(), 0));
+        };
+        let shape = if self.input_rank == 2 { vec![rows.len() as i64, width as i64] } else { vec![width as i64] };
+        let values = rows.iter().flat_map(|r| r.iter().copied());
+        let outputs = match self.input_type {
+            InputType::F32 => {
+                let data: Vec<f32> = values.map(|f| f as f32).collect();
+                self.session.run(ort::inputs![Tensor::from_array((shape, data))?])?
+            }
+            InputType::F64 => self.session.run(ort::inputs![Tensor::from_array((shape, rows.concat()))?])?,
+            InputType::I8(q) => {
+                let data: Vec<i8> = values.map(|f| q.to_i8(f)).collect();
+                self.session.run(ort::inputs![Tensor::from_array((shape, data))?])?
+            }
+            InputType::U8(q) => {
+                let data: Vec<u8> = values.map(|f| q.to_u8(f)).collect();
+                self.session.run(ort::inputs![Tensor::from_array((shape, data))?])?
+            }
+        };
+        let output = &outputs[0];
+        let values: Vec<f64> = match output.try_extract_tensor::<f32>() {
+            Ok((_, values)) => values.iter().map(|&v| v as f64).collect(),
+            Err(_) => output.try_extract_tensor::<f64>()?.1.to_vec(),
+        };
+        // [batch] or [batch, k] output: the first value of each row is its score
+        let stride = output_stride(values.len(), rows.len())?;
+        Ok((values, stride))
+    }
 }
 
 #[cfg(feature = "onnx")]
 impl OnnxClient {
-    /// Create a new ONNX client with the given model path
-    pub fn new(_model_path: &Path) -> Result<Self> {
-        // TODO: Initialize ONNX runtime session
-        // This would load the model and create an inference session
-        Ok(Self {})
+    /// Load the model at `model_path` on the CPU
+    pub fn new(model_path: &Path) -> Result<Self> {
+        Self::with_config(model_path, &OnnxConfig::default())
+    }
+
+    /// Load the model at `model_path` and check its inputs and outputs
+    pub fn with_config(model_path: &Path, config: &OnnxConfig) -> Result<Self> {
+        let stamp = FileStamp::of(model_path);
+        let mut model = Model::load(model_path, config)?;
+        let warmup = model.warm_up(config).map_err(|e| anyhow!("model {}: {:#}", model_path.display(), e))?;
+        Ok(Self { model: Mutex::new(model), config: config.clone(), file: ModelFile::new(model_path, stamp, warmup) })
+    }
+
+    /// Load the model file again, warm it up and swap it in; inferences
+    /// already running finish on the old model. On error the old model stays.
+    pub fn reload(&self) -> Result<()> {
+        let path = self.file.path();
+        let stamp = FileStamp::of(path);
+        let mut model = Model::load(path, &self.config)?;
+        let warmup = model.warm_up(&self.config).map_err(|e| anyhow!("model {}: {:#}", path.display(), e))?;
+        *self.model.lock().unwrap_or_else(|e| e.into_inner()) = model;
+        self.file.loaded(stamp, warmup);
+        Ok(())
+    }
+
+    /// Features per inference the model expects, if fixed
+    pub fn feature_count(&self) -> Option<usize> {
+        self.model.lock().unwrap_or_else(|e| e.into_inner()).width
     }
 
-    /// Run inference on the given features
-    pub fn infer(&self, _features: &[f64]) -> Result<f64> {
-        // TODO: Run actual inference
-        // For now, return a simple stub result
-        Ok(0.0)
+    /// Score `features`, clamped to [-1, 1]
+    pub fn infer(&self, features: &[f64]) -> Result<f64> {
+        self.file.metrics().measure(1, || {
+            let mut model = self.model.lock().unwrap_or_else(|e| e.into_inner());
+            Ok(model.run(&[features])?[0])
+        })
+    }
+
+    /// Score every row in one model call when the model has a dynamic batch
+    /// dimension; other models are run once per row
+    pub fn infer_batch(&self, rows: &[Vec<f64>]) -> Result<Vec<f64>> {
+        let rows: Vec<&[f64]> = rows.iter().map(Vec::as_slice).collect();
+        self.file.metrics().measure(rows.len(), || {
+            let mut model = self.model.lock().unwrap_or_else(|e| e.into_inner());
+            if model.batched || rows.len() <= 1 {
+                return model.run(&rows);
+            }
+            rows.iter().map(|row| Ok(model.run(&[row])?[0])).collect()
+        })
+    }
+
+    /// Top class for `features` from a classification model (None for a
+    /// model without labels)
+    pub fn classify(&self, features: &[f64]) -> Result<Option<Classification>> {
+        let mut model = self.model.lock().unwrap_or_else(|e| e.into_inner());
+        if model.labels.is_none() {
+            return Ok(None);
+        }
+        self.file.metrics().measure(1, || model.classify(features))
     }
 }
 
 #[cfg(not(feature = "onnx"))]
 /// Stub implementation when ONNX feature is not enabled
-pub struct OnnxClient;
+pub struct OnnxClient {
+    scaler: Mutex<Option<FeatureScaler>>,
+    labels: Mutex<Option<ClassLabels>>,
+    config: OnnxConfig,
+    file: ModelFile,
+}
 
 #[cfg(not(feature = "onnx"))]
 impl OnnxClient {
     /// Create a new ONNX client (stub)
-    pub fn new(_model_path: &std::path::Path) -> anyhow::Result<Self> {
-        Ok(Self)
+    pub fn new(model_path: &Path) -> anyhow::Result<Self> {
+        Self::with_config(model_path, &OnnxConfig::default())
     }
 
-    /// Run inference (stub implementation)
+    /// Create a new ONNX client (stub; only the warm-up settings are used)
+    pub fn with_config(model_path: &Path, config: &OnnxConfig) -> anyhow::Result<Self> {
+        let stamp = FileStamp::of(model_path);
+        let client = Self {
+            scaler: Mutex::new(FeatureScaler::load_for_model(model_path)?),
+            labels: Mutex::new(ClassLabels::load_for_model(model_path)?),
+            config: config.clone(),
+            file: ModelFile::new(model_path, stamp, None),
+        };
+        client.file.loaded(stamp, client.warm_up()?);
+        Ok(client)
+    }
+
+    fn warm_up(&self) -> anyhow::Result<Option<WarmupReport>> {
+        let widths = warmup_widths(self.feature_count(), &self.config.feature_widths);
+        warm_up(self.config.warmup_runs, &widths, |row| self.score(row))
+    }
+
+    /// Reload the scaler and labels and warm up (stub); notes the model
+    /// file's current state
+    pub fn reload(&self) -> anyhow::Result<()> {
+        let path = self.file.path();
+        let stamp = FileStamp::of(path);
+        let scaler = FeatureScaler::load_for_model(path)?;
+        let labels = ClassLabels::load_for_model(path)?;
+        *self.scaler.lock().unwrap_or_else(|e| e.into_inner()) = scaler;
+        *self.labels.lock().unwrap_or_else(|e| e.into_inner()) = labels;
+        self.file.loaded(stamp, self.warm_up()?);
+        Ok(())
+    }
+
+    /// The scaler's feature count, otherwise any number is accepted
+    pub fn feature_count(&self) -> Option<usize> {
+        self.scaler.lock().unwrap_or_else(|e| e.into_inner()).as_ref().map(FeatureScaler::len)
+    }
+
+    fn score(&self, features: &[f64]) -> anyhow::Result<f64> {
+        match &*self.scaler.lock().unwrap_or_else(|e| e.into_inner()) {
+            Some(scaler) => Ok(default_model_stub(&scaler.apply(features)?)),
+            None => Ok(default_model_stub(features)),
+        }
+    }
+
+    /// Run inference on the scaled features (stub implementation)
     pub fn infer(&self, features: &[f64]) -> anyhow::Result<f64> {
-        // Simple deterministic stub based on feature sum
-        let sum: f64 = features.iter().sum();
-        let score = sum / (features.len() as f64 + 1e-9);
-        Ok(score.max(-1.0).min(1.0))
+        self.file.metrics().measure(1, || self.score(features))
+    }
+
+    /// Score every row (stub implementation)
+    pub fn infer_batch(&self, rows: &[Vec<f64>]) -> anyhow::Result<Vec<f64>> {
+        self.file.metrics().measure(rows.len(), || rows.iter().map(|row| self.score(row)).collect())
+    }
+
+    /// Top class when labels are present (stub: the softmax of the first
+    /// features, one per class, zero-padded)
+    pub fn classify(&self, features: &[f64]) -> anyhow::Result<Option<Classification>> {
+        let labels = self.labels.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(labels) = labels.as_ref() else {
+            return Ok(None);
+        };
+        self.file.metrics().measure(1, || {
+            let features = match &*self.scaler.lock().unwrap_or_else(|e| e.into_inner()) {
+                Some(scaler) => scaler.apply(features)?,
+                None => features.to_vec(),
+            };
+            let logits: Vec<f64> = (0..labels.len()).map(|i| features.get(i).copied().unwrap_or(0.0)).collect();
+            let max = logits.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+            let exp: Vec<f64> = logits.iter().map(|l| (l - max).exp()).collect();
+            let sum: f64 = exp.iter().sum();
+            labels.top(&exp.iter().map(|e| e / sum).collect::<Vec<_>>()).map(Some)
+        })
+    }
+}
+
+impl OnnxClient {
+    pub fn path(&self) -> &Path {
+        self.file.path()
+    }
+
+    /// Calls, errors, throughput and latency since the client was created
+    pub fn stats(&self) -> ModelStats {
+        self.file.stats()
+    }
+
+    /// Warm-up timing from the last (re)load, if one ran
+    pub fn warmup(&self) -> Option<WarmupReport> {
+        self.file.warmup()
+    }
+
+    /// Reload when the model file was replaced since it was last loaded (see
+    /// [`ModelFile::reload_if_changed`])
+    pub fn reload_if_changed(&self) -> anyhow::Result<bool> {
+        self.file.reload_if_changed(|| self.reload())
     }
 }
 
@@ -55,7 +834,7 @@ impl OnnxClient {
 pub fn default_model_stub(features: &[f64]) -> f64 {
     let sum: f64 = features.iter().sum();
     let score = sum / (features.len() as f64 + 1e-9);
-    score.max(-1.0).min(1.0)
+    score.clamp(-1.0, 1.0)
 }
 
 #[cfg(test)]
@@ -66,14 +845,192 @@ mod tests {
     fn test_default_model_stub() {
         let features = vec![1.0, 2.0, 3.0];
         let result = default_model_stub(&features);
-        assert!(result >= -1.0 && result <= 1.0);
+        assert!((-1.0..=1.0).contains(&result));
     }
 
+    #[test]
+    fn test_shared_output_mapping() {
+        let rows: [&[f64]; 2] = [&[1.0, 2.0], &[3.0, 4.0]];
+        assert_eq!(model_rows(&rows, None, Some(2)).unwrap(), vec![vec![1.0, 2.0], vec![3.0, 4.0]]);
+        let err = model_rows(&[&[1.0], &[1.0, 2.0]], None, None).unwrap_err();
+        assert_eq!(err.to_string(), "model expects 1 features, got 2");
+
+        // [2, 2] output: the first value of each row is its score
+        assert_eq!(output_stride(4, 2).unwrap(), 2);
+        assert!(output_stride(3, 2).is_err() && output_stride(0, 1).is_err());
+        assert_eq!(row_scores(&[1.5, 0.1, -0.25, 0.9], 2).unwrap(), vec![1.0, -0.25]);
+        assert!(row_scores(&[f64::NAN], 1).is_err());
+    }
+
+    #[cfg(not(feature = "onnx"))]
     #[test]
     fn test_onnx_client_stub() {
         let client = OnnxClient::new(std::path::Path::new("dummy.onnx")).unwrap();
         let features = vec![1.0, 2.0, 3.0];
         let result = client.infer(&features).unwrap();
-        assert!(result >= -1.0 && result <= 1.0);
+        assert!((-1.0..=1.0).contains(&result));
+
+        let rows = vec![vec![1.0, 2.0, 3.0], vec![-0.5, -0.5], vec![]];
+        let scores = client.infer_batch(&rows).unwrap();
+        assert_eq!(scores.len(), 3);
+        assert_eq!(scores[0], result);
+        assert_eq!(scores[1], client.infer(&rows[1]).unwrap());
+        assert!(client.infer_batch(&[]).unwrap().is_empty());
+        let stats = client.stats();
+        assert_eq!((stats.calls, stats.rows, stats.errors), (4, 5, 0));
+    }
+
+    #[cfg(feature = "onnx")]
+    #[test]
+    fn test_missing_model_file() {
+        let err = OnnxClient::new(std::path::Path::new("missing.onnx")).err().unwrap();
+        assert!(err.to_string().contains("not found"));
     }
-}
\ No newline at end of file
+
+    #[cfg(not(feature = "onnx"))]
+    #[test]
+    fn test_reloads_when_model_file_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("model.onnx");
+        let client = OnnxClient::new(&path).unwrap();
+        // no file yet: nothing to reload
+        assert!(!client.reload_if_changed().unwrap());
+
+        std::fs::write(&path, b"v1").unwrap();
+        assert!(client.reload_if_changed().unwrap());
+        assert!(!client.reload_if_changed().unwrap());
+
+        std::fs::write(&path, b"v2 retrained").unwrap();
+        assert!(client.reload_if_changed().unwrap());
+        assert_eq!(client.path(), path.as_path());
+    }
+
+    #[cfg(not(feature = "onnx"))]
+    #[test]
+    fn test_scaler_applied_before_inference() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("model.onnx");
+        let client = OnnxClient::new(&path).unwrap();
+        assert_eq!(client.infer(&[4.0, 6.0]).unwrap(), default_model_stub(&[4.0, 6.0]));
+
+        let spec = r#"{"features": [{"name": "a", "mean": 2.0, "std": 2.0}, {"name": "b", "min": 2.0, "max": 6.0}]}"#;
+        std::fs::write(FeatureScaler::sidecar_path(&path), spec).unwrap();
+        client.reload().unwrap();
+        assert_eq!(client.feature_count(), Some(2));
+        assert_eq!(client.infer(&[4.0, 6.0]).unwrap(), default_model_stub(&[1.0, 1.0]));
+        assert_eq!(client.infer_batch(&[vec![4.0, 6.0]]).unwrap(), vec![default_model_stub(&[1.0, 1.0])]);
+        assert!(client.infer(&[4.0]).is_err());
+
+        // a broken scaler fails the load, like a broken model
+        std::fs::write(FeatureScaler::sidecar_path(&path), "{}").unwrap();
+        assert!(OnnxClient::new(&path).is_err());
+    }
+
+    #[cfg(not(feature = "onnx"))]
+    #[test]
+    fn test_warm_up_on_load() {
+        let config = OnnxConfig::default().with_feature_widths([6, 8]);
+        let client = OnnxClient::with_config(std::path::Path::new("dummy.onnx"), &config).unwrap();
+        let report = client.warmup().unwrap();
+        assert_eq!((report.runs, report.widths.clone()), (6, vec![6, 8]));
+        assert!(report.total_ms >= report.first_ms);
+
+        let config = OnnxConfig { warmup_runs: 0, ..config };
+        assert!(OnnxClient::with_config(std::path::Path::new("dummy.onnx"), &config).unwrap().warmup().is_none());
+    }
+
+    #[test]
+    fn test_warm_up_runs_each_width() {
+        let mut seen = Vec::new();
+        let report = warm_up(2, &warmup_widths(None, &[3, 5]), |row| {
+            seen.push(row.len());
+            Ok(0.0)
+        })
+        .unwrap()
+        .unwrap();
+        assert_eq!(seen, vec![3, 3, 5, 5]);
+        assert_eq!(report.runs, 4);
+        assert_eq!(warmup_widths(Some(4), &[3, 5]), vec![4]);
+        assert!(warm_up(2, &[], |_| Ok(0.0)).unwrap().is_none());
+        let err = warm_up(1, &[2], |_| anyhow::bail!("bad shape")).unwrap_err().to_string();
+        assert_eq!(err, "warm-up inference with 2 features failed: bad shape");
+    }
+
+    #[cfg(not(feature = "onnx"))]
+    #[test]
+    fn test_classify_with_labels() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("model.onnx");
+        let client = OnnxClient::new(&path).unwrap();
+        assert!(client.classify(&[0.1, 2.0]).unwrap().is_none());
+
+        std::fs::write(ClassLabels::sidecar_path(&path), "double_top\nflag\nwedge\n").unwrap();
+        client.reload().unwrap();
+        let top = client.classify(&[0.1, 2.0]).unwrap().unwrap();
+        assert_eq!((top.label.as_str(), top.class), ("flag", 1));
+        assert!(top.confidence > 0.5 && top.confidence < 1.0);
+    }
+
+    #[test]
+    fn test_parse_config() {
+        let providers = parse_providers("TensorRT, cuda,cpu").unwrap();
+        assert_eq!(providers, vec![ExecutionProvider::TensorRt, ExecutionProvider::Cuda, ExecutionProvider::Cpu]);
+        assert!(parse_providers("gpu").is_err());
+        assert!(parse_providers(" , ").is_err());
+        assert_eq!("2".parse::<OptimizationLevel>().unwrap(), OptimizationLevel::Extended);
+        assert!("max".parse::<OptimizationLevel>().is_err());
+
+        let config = OnnxConfig::default();
+        assert!(!config.accelerated());
+        assert!(OnnxConfig { providers, ..config }.accelerated());
+    }
+
+    #[test]
+    fn test_feature_width_from_input_shape() {
+        assert_eq!(feature_width(&[-1, 12]).unwrap(), Some(12));
+        assert_eq!(feature_width(&[8]).unwrap(), Some(8));
+        assert_eq!(feature_width(&[1, -1]).unwrap(), None);
+        assert!(feature_width(&[1, 3, 4]).is_err());
+        assert!(feature_width(&[]).is_err());
+    }
+
+    #[test]
+    fn test_quantize_inputs() {
+        let q = Quantization::new(0.5, 10).unwrap();
+        assert_eq!(q.to_i8(1.0), 12);
+        assert_eq!(q.to_i8(-2.2), 6);
+        assert_eq!(q.to_i8(1e9), i8::MAX);
+        assert_eq!(q.to_u8(-100.0), 0);
+        assert_eq!(q.to_u8(f64::NAN), 10);
+        assert!(Quantization::new(0.0, 0).is_err());
+
+        assert_eq!(Quantization::from_metadata(Some("0.25"), None).unwrap(), Some(Quantization { scale: 0.25, zero_point: 0 }));
+        assert_eq!(Quantization::from_metadata(None, Some("3")).unwrap(), None);
+        assert!(Quantization::from_metadata(Some("wide"), None).is_err());
+        assert!(Quantization::from_metadata(Some("0.1"), Some("1.5")).is_err());
+    }
+
+    #[test]
+    fn test_schema_checks() {
+        let config = OnnxConfig::default().with_feature_widths([8, 6, 8]);
+        assert_eq!(config.feature_widths, vec![6, 8]);
+        assert!(check_feature_width(Some(8), &config.feature_widths).is_ok());
+        assert!(check_feature_width(None, &config.feature_widths).is_ok());
+        assert!(check_feature_width(Some(12), &[]).is_ok());
+        let err = check_feature_width(Some(12), &config.feature_widths).unwrap_err();
+        assert_eq!(err.to_string(), "model input takes 12 features but the engine sends 6 or 8");
+
+        let schema = FeatureSchema::new(["a", "b"]).unwrap();
+        let config = OnnxConfig::default().with_feature_schema(&schema);
+        assert_eq!(config.feature_widths, vec![2]);
+        assert!(check_feature_schema(Some(&schema.hash()), config.feature_schema.as_deref()).is_ok());
+        assert!(check_feature_schema(None, config.feature_schema.as_deref()).is_ok());
+        assert!(check_feature_schema(Some("0123456789abcdef"), config.feature_schema.as_deref()).is_err());
+        assert!(check_feature_schema(Some("0123456789abcdef"), None).is_ok());
+
+        assert!(check_output_shape(&[-1]).is_ok());
+        assert!(check_output_shape(&[-1, 3]).is_ok());
+        assert!(check_output_shape(&[-1, 0]).is_err());
+        assert!(check_output_shape(&[1, 2, 3]).is_err());
+    }
+}
@@ -0,0 +1,185 @@
+//! Order and execution-report messages.
+//!
+//! These let the crate carry the traffic between the strategy engine and an
+//! execution gateway as well: the strategy publishes an [`OrderIntent`] to
+//! the orders stream, and the gateway answers with [`ExecutionReport`]s on
+//! the executions stream as the order is acknowledged, filled, cancelled or
+//! rejected. Both are JSON in a `data` field, like ticks.
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::publisher::TradeSide;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderType {
+    Market,
+    Limit,
+    Stop,
+    StopLimit,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimeInForce {
+    #[default]
+    Day,
+    Gtc,
+    Ioc,
+    Fok,
+}
+
+/// An order the strategy wants placed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OrderIntent {
+    /// Client order ID, echoed back in execution reports
+    pub id: String,
+    pub symbol: String,
+    pub side: TradeSide,
+    pub quantity: f64,
+    pub order_type: OrderType,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit_price: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop_price: Option<f64>,
+    #[serde(default)]
+    pub time_in_force: TimeInForce,
+    /// Signal that prompted the order
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signal_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub strategy: Option<String>,
+    pub timestamp: f64,
+}
+
+impl OrderIntent {
+    /// Market order for `quantity` of `symbol`
+    pub fn market(id: &str, symbol: &str, side: TradeSide, quantity: f64, timestamp: f64) -> Self {
+        Self {
+            id: id.to_string(),
+            symbol: symbol.to_string(),
+            side,
+            quantity,
+            order_type: OrderType::Market,
+            limit_price: None,
+            stop_price: None,
+            time_in_force: TimeInForce::Day,
+            signal_id: None,
+            strategy: None,
+            timestamp,
+        }
+    }
+
+    /// Check the quantity and that the prices the order type needs are set
+    pub fn validate(&self) -> Result<()> {
+        if self.id.is_empty() || self.symbol.is_empty() {
+            bail!("order needs an id and a symbol");
+        }
+        if !(self.quantity.is_finite() && self.quantity > 0.0) {
+            bail!("order {}: quantity must be positive, got {}", self.id, self.quantity);
+        }
+        let needs_limit = matches!(self.order_type, OrderType::Limit | OrderType::StopLimit);
+        let needs_stop = matches!(self.order_type, OrderType::Stop | OrderType::StopLimit);
+        for (name, price, needed) in [("limit", self.limit_price, needs_limit), ("stop", self.stop_price, needs_stop)] {
+            match price {
+                None if needed => bail!("order {}: {:?} order needs a {} price", self.id, self.order_type, name),
+                Some(p) if !(p.is_finite() && p > 0.0) => bail!("order {}: invalid {} price {}", self.id, name, p),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecutionStatus {
+    /// Accepted by the venue
+    New,
+    PartiallyFilled,
+    Filled,
+    Cancelled,
+    Rejected,
+}
+
+impl ExecutionStatus {
+    /// No further reports follow
+    pub fn is_final(&self) -> bool {
+        matches!(self, ExecutionStatus::Filled | ExecutionStatus::Cancelled | ExecutionStatus::Rejected)
+    }
+}
+
+/// The gateway's account of what happened to an order
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExecutionReport {
+    /// [`OrderIntent::id`] of the order
+    pub order_id: String,
+    /// Unique per report
+    pub exec_id: String,
+    pub symbol: String,
+    pub side: TradeSide,
+    pub status: ExecutionStatus,
+    /// Filled so far
+    pub filled_quantity: f64,
+    /// Still working
+    pub leaves_quantity: f64,
+    /// Size and price of the fill this report announces
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_quantity: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_price: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub avg_price: Option<f64>,
+    /// Why the order was rejected or cancelled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    pub timestamp: f64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validates_order_prices() {
+        let market = OrderIntent::market("o1", "AAPL", TradeSide::Buy, 100.0, 1.0);
+        assert!(market.validate().is_ok());
+        assert!(OrderIntent { quantity: 0.0, ..market.clone() }.validate().is_err());
+
+        let limit = OrderIntent { order_type: OrderType::Limit, ..market.clone() };
+        assert!(limit.validate().unwrap_err().to_string().contains("limit price"));
+        assert!(OrderIntent { limit_price: Some(101.5), ..limit.clone() }.validate().is_ok());
+        assert!(OrderIntent { limit_price: Some(-1.0), ..limit }.validate().is_err());
+
+        let stop_limit = OrderIntent { order_type: OrderType::StopLimit, limit_price: Some(99.0), ..market };
+        assert!(stop_limit.validate().unwrap_err().to_string().contains("stop price"));
+    }
+
+    #[test]
+    fn test_wire_format() {
+        let json = r#"{"id":"o1","symbol":"AAPL","side":"sell","quantity":5.0,"order_type":"limit","limit_price":10.0,"timestamp":1.0}"#;
+        let order: OrderIntent = serde_json::from_str(json).unwrap();
+        assert_eq!((order.side, order.time_in_force), (TradeSide::Sell, TimeInForce::Day));
+
+        let report = ExecutionReport {
+            order_id: "o1".to_string(),
+            exec_id: "e1".to_string(),
+            symbol: "AAPL".to_string(),
+            side: TradeSide::Sell,
+            status: ExecutionStatus::PartiallyFilled,
+            filled_quantity: 2.0,
+            leaves_quantity: 3.0,
+            last_quantity: Some(2.0),
+            last_price: Some(10.0),
+            avg_price: Some(10.0),
+            reason: None,
+            timestamp: 2.0,
+        };
+        let json = serde_json::to_string(&report).unwrap();
+        assert!(json.contains(r#""status":"partially_filled""#));
+        assert_eq!(serde_json::from_str::<ExecutionReport>(&json).unwrap(), report);
+        assert!(!report.status.is_final());
+        assert!(ExecutionStatus::Rejected.is_final());
+    }
+}
@@ -0,0 +1,282 @@
+//! Pairs / spread divergence across two symbols.
+//!
+//! For each configured pair the tracker keeps a rolling window of log prices,
+//! fits the hedge ratio by least squares (`ln left = alpha + beta * ln right`)
+//! and measures the latest residual in standard deviations. A pair only
+//! signals while its spread looks stationary: the residual's lag-1
+//! autoregression must revert with a half-life inside `max_half_life`
+//! samples, a cheap stand-in for a cointegration test. A divergence fires once
+//! when |z| reaches `entry_z` and re-arms after the spread is back inside
+//! `exit_z`.
+
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+use crate::publisher::Signal;
+use crate::signal_id;
+
+/// Pattern name of spread divergence signals
+pub const SPREAD_DIVERGENCE: &str = "spread_divergence";
+
+/// Two symbols traded as a spread: long `left`, short `beta * right`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PairSpec {
+    pub left: String,
+    pub right: String,
+}
+
+impl PairSpec {
+    /// Name used as the signal symbol, e.g. `KO/PEP`
+    pub fn name(&self) -> String {
+        format!("{}/{}", self.left, self.right)
+    }
+
+    /// Parse `LEFT/RIGHT,LEFT/RIGHT,...`
+    pub fn parse_list(spec: &str) -> Result<Vec<PairSpec>> {
+        spec.split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(|item| {
+                let (left, right) = item
+                    .split_once('/')
+                    .map(|(l, r)| (l.trim(), r.trim()))
+                    .filter(|(l, r)| !l.is_empty() && !r.is_empty() && l != r)
+                    .ok_or_else(|| anyhow!("invalid pair '{}': expected LEFT/RIGHT with two different symbols", item))?;
+                Ok(PairSpec { left: left.to_string(), right: right.to_string() })
+            })
+            .collect()
+    }
+}
+
+/// Thresholds for [`PairTracker`]
+#[derive(Debug, Clone)]
+pub struct PairConfig {
+    /// Samples in the rolling fit
+    pub window: usize,
+    /// |z| at which a divergence fires
+    pub entry_z: f64,
+    /// |z| at or below which the pair re-arms
+    pub exit_z: f64,
+    /// Longest acceptable spread half-life, in samples
+    pub max_half_life: f64,
+}
+
+impl Default for PairConfig {
+    fn default() -> Self {
+        Self { window: 100, entry_z: 2.0, exit_z: 0.5, max_half_life: 50.0 }
+    }
+}
+
+/// Fitted spread state for one pair
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct SpreadStats {
+    /// Hedge ratio of `ln right` in the fit
+    pub beta: f64,
+    /// Latest residual (log spread)
+    pub spread: f64,
+    pub z: f64,
+    /// Samples for a deviation to halve; 0 when the residual flips sign every step
+    pub half_life: Option<f64>,
+    pub stable: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+struct PairState {
+    /// (ln right, ln left) samples
+    samples: VecDeque<(f64, f64)>,
+    diverged: bool,
+}
+
+/// Rolling spread fits for the configured pairs
+#[derive(Debug, Clone, Default)]
+pub struct PairTracker {
+    config: PairConfig,
+    pairs: Vec<(PairSpec, PairState)>,
+    last_price: HashMap<String, f64>,
+}
+
+impl PairTracker {
+    pub fn new(pairs: Vec<PairSpec>, config: PairConfig) -> Self {
+        Self {
+            config,
+            pairs: pairs.into_iter().map(|p| (p, PairState::default())).collect(),
+            last_price: HashMap::new(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pairs.is_empty()
+    }
+
+    /// Record a trade; every pair containing `symbol` takes a new sample and
+    /// may emit a divergence signal
+    pub fn update(&mut self, symbol: &str, price: f64, timestamp: f64) -> Vec<Signal> {
+        if price <= 0.0 || !self.pairs.iter().any(|(p, _)| p.left == symbol || p.right == symbol) {
+            return Vec::new();
+        }
+        self.last_price.insert(symbol.to_string(), price);
+        let mut signals = Vec::new();
+        for (pair, state) in self.pairs.iter_mut() {
+            if pair.left != symbol && pair.right != symbol {
+                continue;
+            }
+            let (Some(left), Some(right)) = (self.last_price.get(&pair.left), self.last_price.get(&pair.right)) else {
+                continue;
+            };
+            state.samples.push_back((right.ln(), left.ln()));
+            if state.samples.len() > self.config.window {
+                state.samples.pop_front();
+            }
+            let Some(stats) = fit(&state.samples, &self.config) else {
+                continue;
+            };
+            if state.diverged {
+                state.diverged = stats.z.abs() > self.config.exit_z;
+            } else if stats.stable && stats.z.abs() >= self.config.entry_z {
+                state.diverged = true;
+                signals.push(divergence_signal(pair, &stats, self.config.entry_z, timestamp));
+            }
+        }
+        signals
+    }
+
+    /// Current fit for each pair with a full window, keyed by pair name
+    pub fn stats(&self) -> BTreeMap<String, SpreadStats> {
+        self.pairs
+            .iter()
+            .filter_map(|(pair, state)| fit(&state.samples, &self.config).map(|s| (pair.name(), s)))
+            .collect()
+    }
+}
+
+/// Least-squares hedge ratio, residual z-score and AR(1) half-life over a full window
+fn fit(samples: &VecDeque<(f64, f64)>, config: &PairConfig) -> Option<SpreadStats> {
+    if samples.len() < config.window.max(3) {
+        return None;
+    }
+    let n = samples.len() as f64;
+    let mean_x = samples.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = samples.iter().map(|(_, y)| y).sum::<f64>() / n;
+    let var_x = samples.iter().map(|(x, _)| (x - mean_x).powi(2)).sum::<f64>();
+    let cov = samples.iter().map(|(x, y)| (x - mean_x) * (y - mean_y)).sum::<f64>();
+    // a flat right leg leaves only the level: the spread is the demeaned left leg
+    let beta = if var_x > 0.0 { cov / var_x } else { 0.0 };
+    let alpha = mean_y - beta * mean_x;
+    let residuals: Vec<f64> = samples.iter().map(|(x, y)| y - alpha - beta * x).collect();
+    let std = (residuals.iter().map(|e| e * e).sum::<f64>() / n).sqrt();
+    if std <= 0.0 {
+        return None;
+    }
+    let spread = *residuals.last()?;
+
+    let (num, den) = residuals
+        .windows(2)
+        .fold((0.0, 0.0), |(num, den), w| (num + w[1] * w[0], den + w[0] * w[0]));
+    let phi = if den > 0.0 { num / den } else { 1.0 };
+    let half_life = if phi <= 0.0 {
+        Some(0.0)
+    } else if phi < 1.0 {
+        Some(-std::f64::consts::LN_2 / phi.ln())
+    } else {
+        None
+    };
+    Some(SpreadStats {
+        beta,
+        spread,
+        z: spread / std,
+        half_life,
+        stable: half_life.is_some_and(|h| h <= config.max_half_life),
+    })
+}
+
+fn divergence_signal(pair: &PairSpec, stats: &SpreadStats, entry_z: f64, timestamp: f64) -> Signal {
+    let name = pair.name();
+    // positive score: spread is cheap, buy left / sell right
+    let score = (-stats.z / (2.0 * entry_z)).clamp(-1.0, 1.0);
+    let details = serde_json::json!({
+        "left": pair.left,
+        "right": pair.right,
+        "beta": stats.beta,
+        "spread": stats.spread,
+        "z": stats.z,
+        "half_life": stats.half_life,
+    });
+    Signal {
+        id: signal_id::next_id(),
+        symbol: name,
+        score,
+        pattern: SPREAD_DIVERGENCE.to_string(),
+        timestamp,
+        meta: None,
+        pattern_meta: None,
+        status: None,
+        linked_id: None,
+        extra: HashMap::from([("pair".to_string(), details)]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> PairConfig {
+        PairConfig { window: 40, entry_z: 2.0, exit_z: 0.5, max_half_life: 10.0 }
+    }
+
+    /// Right leg trends; left tracks it 1:1 with small alternating noise
+    fn feed(tracker: &mut PairTracker, steps: usize) -> Vec<Signal> {
+        let mut out = Vec::new();
+        for i in 0..steps {
+            let right = 50.0 * (1.0 + 0.002 * i as f64);
+            let noise = if i % 2 == 0 { 1.001 } else { 0.999 };
+            out.extend(tracker.update("PEP", right, i as f64));
+            out.extend(tracker.update("KO", 2.0 * right * noise, i as f64));
+        }
+        out
+    }
+
+    #[test]
+    fn test_parse_pairs() {
+        let pairs = PairSpec::parse_list("KO/PEP, XOM / CVX").unwrap();
+        assert_eq!(pairs[1], PairSpec { left: "XOM".into(), right: "CVX".into() });
+        assert_eq!(pairs[0].name(), "KO/PEP");
+        assert!(PairSpec::parse_list("KO").is_err());
+        assert!(PairSpec::parse_list("KO/KO").is_err());
+        assert!(PairSpec::parse_list("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_divergence_fires_once() {
+        let mut tracker = PairTracker::new(PairSpec::parse_list("KO/PEP").unwrap(), config());
+        assert!(feed(&mut tracker, 60).is_empty());
+        let stats = tracker.stats()["KO/PEP"];
+        assert!((stats.beta - 1.0).abs() < 0.1);
+        assert!(stats.stable);
+
+        // KO jumps 3% rich against PEP
+        let sig = tracker.update("KO", 2.0 * 50.0 * 1.12 * 1.03, 100.0);
+        assert_eq!(sig.len(), 1);
+        assert_eq!((sig[0].symbol.as_str(), sig[0].pattern.as_str()), ("KO/PEP", SPREAD_DIVERGENCE));
+        assert!(sig[0].score < 0.0);
+        assert!(sig[0].extra["pair"]["z"].as_f64().unwrap() >= 2.0);
+        // still wide: no repeat
+        assert!(tracker.update("PEP", 50.0 * 1.12, 101.0).is_empty());
+    }
+
+    #[test]
+    fn test_unrelated_symbols_and_trending_spread_ignored() {
+        let mut tracker = PairTracker::new(PairSpec::parse_list("KO/PEP").unwrap(), config());
+        assert!(tracker.update("AAPL", 100.0, 0.0).is_empty());
+        // left drifts away from right along a random-walk-like path: no reversion
+        let mut signals = Vec::new();
+        let mut drift = 0.0;
+        for i in 0..80 {
+            drift += if (i * 7) % 5 < 3 { 0.004 } else { -0.001 };
+            signals.extend(tracker.update("PEP", 50.0, i as f64));
+            signals.extend(tracker.update("KO", 100.0 * (1.0 + drift), i as f64));
+        }
+        assert!(signals.is_empty());
+        assert!(tracker.stats().values().all(|s| !s.stable));
+    }
+}
@@ -0,0 +1,357 @@
+//! Optional Parquet sink for research pipelines.
+//!
+//! Signals and ticks are buffered per partition and written as columnar
+//! files in a Hive-style layout that pandas, Polars and Arrow datasets read
+//! directly:
+//!
+//! ```text
+//! <dir>/signals/date=2024-03-01/symbol=AAPL/part-<millis>-<seq>.parquet
+//! <dir>/ticks/date=2024-03-01/symbol=AAPL/part-<millis>-<seq>.parquet
+//! ```
+//!
+//! The date is the UTC day of the entry's timestamp. A partition is written
+//! once it holds `batch_rows` entries, and every partition on
+//! [`ParquetSink::flush`]. Each write produces a new file, so files are
+//! complete once they appear.
+//!
+//! Requires the `parquet` feature; without it [`ParquetSink::open`] returns
+//! an error.
+
+use std::path::{Path, PathBuf};
+
+#[cfg(feature = "parquet")]
+use anyhow::{anyhow, Result};
+#[cfg(feature = "parquet")]
+use arrow_array::{ArrayRef, Float64Array, RecordBatch, StringArray};
+#[cfg(feature = "parquet")]
+use arrow_schema::{DataType, Field, Schema};
+#[cfg(feature = "parquet")]
+use async_trait::async_trait;
+#[cfg(feature = "parquet")]
+use parquet::arrow::ArrowWriter;
+#[cfg(feature = "parquet")]
+use parquet::basic::Compression;
+#[cfg(feature = "parquet")]
+use parquet::file::properties::WriterProperties;
+#[cfg(feature = "parquet")]
+use std::collections::HashMap;
+#[cfg(feature = "parquet")]
+use std::sync::atomic::{AtomicU64, Ordering};
+#[cfg(feature = "parquet")]
+use std::sync::{Arc, Mutex};
+
+#[cfg(feature = "parquet")]
+use crate::publisher::{Signal, SignalStatus, Tick, TradeSide};
+#[cfg(feature = "parquet")]
+use crate::sink::Sink;
+
+#[derive(Debug, Clone)]
+pub struct ParquetSinkConfig {
+    pub dir: PathBuf,
+    /// Rows buffered per partition before it is written
+    pub batch_rows: usize,
+}
+
+impl ParquetSinkConfig {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into(), batch_rows: 10_000 }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, serde::Serialize)]
+pub struct ParquetSinkStats {
+    pub files_written: u64,
+    pub rows_written: u64,
+    /// Rows waiting for their partition to be written
+    pub buffered: usize,
+    pub failed_writes: u64,
+}
+
+/// Directory holding `kind` entries for `symbol` on the UTC day of `timestamp`
+pub fn partition_dir(root: &Path, kind: &str, symbol: &str, timestamp: f64) -> PathBuf {
+    let date = chrono::DateTime::from_timestamp(timestamp.floor() as i64, 0)
+        .map(|t| t.format("%Y-%m-%d").to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+    // keep symbols like BTC/USD to a single path component
+    let symbol: String = symbol.chars().map(|c| if matches!(c, '/' | '\\') { '_' } else { c }).collect();
+    root.join(kind).join(format!("date={}", date)).join(format!("symbol={}", symbol))
+}
+
+#[cfg(feature = "parquet")]
+#[derive(Default)]
+struct Buffers {
+    signals: HashMap<PathBuf, Vec<Signal>>,
+    ticks: HashMap<PathBuf, Vec<Tick>>,
+}
+
+#[cfg(feature = "parquet")]
+pub struct ParquetSink {
+    config: ParquetSinkConfig,
+    buffers: Mutex<Buffers>,
+    seq: AtomicU64,
+    files_written: AtomicU64,
+    rows_written: AtomicU64,
+    failed_writes: AtomicU64,
+}
+
+#[cfg(feature = "parquet")]
+impl ParquetSink {
+    pub fn open(config: ParquetSinkConfig) -> Result<Self> {
+        std::fs::create_dir_all(&config.dir).map_err(|e| anyhow!("failed to create {}: {}", config.dir.display(), e))?;
+        Ok(Self {
+            config,
+            buffers: Mutex::new(Buffers::default()),
+            seq: AtomicU64::new(0),
+            files_written: AtomicU64::new(0),
+            rows_written: AtomicU64::new(0),
+            failed_writes: AtomicU64::new(0),
+        })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.config.dir
+    }
+
+    /// Write every buffered partition
+    pub fn flush(&self) -> Result<()> {
+        let Buffers { signals, ticks } = std::mem::take(&mut *self.lock()?);
+        let mut result = Ok(());
+        for (dir, rows) in signals {
+            if let Err(e) = self.write(&dir, signal_batch(&rows)?) {
+                result = Err(e);
+            }
+        }
+        for (dir, rows) in ticks {
+            if let Err(e) = self.write(&dir, tick_batch(&rows)?) {
+                result = Err(e);
+            }
+        }
+        result
+    }
+
+    pub fn stats(&self) -> ParquetSinkStats {
+        let buffered = self
+            .lock()
+            .map(|b| b.signals.values().map(Vec::len).sum::<usize>() + b.ticks.values().map(Vec::len).sum::<usize>())
+            .unwrap_or(0);
+        ParquetSinkStats {
+            files_written: self.files_written.load(Ordering::Relaxed),
+            rows_written: self.rows_written.load(Ordering::Relaxed),
+            buffered,
+            failed_writes: self.failed_writes.load(Ordering::Relaxed),
+        }
+    }
+
+    fn lock(&self) -> Result<std::sync::MutexGuard<'_, Buffers>> {
+        self.buffers.lock().map_err(|_| anyhow!("parquet sink lock poisoned"))
+    }
+
+    fn write(&self, dir: &Path, batch: RecordBatch) -> Result<()> {
+        let result = write_batch(dir, self.seq.fetch_add(1, Ordering::Relaxed), &batch);
+        match &result {
+            Ok(()) => {
+                self.files_written.fetch_add(1, Ordering::Relaxed);
+                self.rows_written.fetch_add(batch.num_rows() as u64, Ordering::Relaxed);
+            }
+            Err(_) => {
+                self.failed_writes.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        result
+    }
+}
+
+/// Write `batch` as a new `part-<millis>-<seq>.parquet` file in `dir`
+#[cfg(feature = "parquet")]
+pub fn write_batch(dir: &Path, seq: u64, batch: &RecordBatch) -> Result<()> {
+    std::fs::create_dir_all(dir).map_err(|e| anyhow!("failed to create {}: {}", dir.display(), e))?;
+    let millis = chrono::Utc::now().timestamp_millis();
+    let path = dir.join(format!("part-{}-{:06}.parquet", millis, seq));
+    // write under a temporary name so readers never see a partial file
+    let partial = path.with_extension("parquet.tmp");
+    let file = std::fs::File::create(&partial).map_err(|e| anyhow!("failed to create {}: {}", partial.display(), e))?;
+    let props = WriterProperties::builder().set_compression(Compression::SNAPPY).build();
+    let mut writer = ArrowWriter::try_new(file, batch.schema(), Some(props))?;
+    writer.write(batch)?;
+    writer.close()?;
+    std::fs::rename(&partial, &path)?;
+    Ok(())
+}
+
+#[cfg(feature = "parquet")]
+pub(crate) fn strings<'a>(values: impl Iterator<Item = Option<&'a str>>) -> ArrayRef {
+    Arc::new(values.collect::<StringArray>())
+}
+
+#[cfg(feature = "parquet")]
+pub(crate) fn floats(values: impl Iterator<Item = Option<f64>>) -> ArrayRef {
+    Arc::new(values.collect::<Float64Array>())
+}
+
+#[cfg(feature = "parquet")]
+fn signal_batch(rows: &[Signal]) -> Result<RecordBatch> {
+    let schema = Schema::new(vec![
+        Field::new("id", DataType::Utf8, false),
+        Field::new("symbol", DataType::Utf8, false),
+        Field::new("score", DataType::Float64, false),
+        Field::new("pattern", DataType::Utf8, false),
+        Field::new("timestamp", DataType::Float64, false),
+        Field::new("status", DataType::Utf8, true),
+        Field::new("linked_id", DataType::Utf8, true),
+        Field::new("meta", DataType::Utf8, true),
+    ]);
+    let meta = rows
+        .iter()
+        .map(|s| s.meta.as_ref().map(serde_json::to_string).transpose())
+        .collect::<serde_json::Result<Vec<_>>>()?;
+    let columns = vec![
+        strings(rows.iter().map(|s| Some(s.id.as_str()))),
+        strings(rows.iter().map(|s| Some(s.symbol.as_str()))),
+        floats(rows.iter().map(|s| Some(s.score))),
+        strings(rows.iter().map(|s| Some(s.pattern.as_str()))),
+        floats(rows.iter().map(|s| Some(s.timestamp))),
+        strings(rows.iter().map(|s| s.status.as_ref().map(SignalStatus::as_str))),
+        strings(rows.iter().map(|s| s.linked_id.as_deref())),
+        strings(meta.iter().map(Option::as_deref)),
+    ];
+    Ok(RecordBatch::try_new(Arc::new(schema), columns)?)
+}
+
+#[cfg(feature = "parquet")]
+fn tick_batch(rows: &[Tick]) -> Result<RecordBatch> {
+    let schema = Schema::new(vec![
+        Field::new("symbol", DataType::Utf8, false),
+        Field::new("price", DataType::Float64, false),
+        Field::new("volume", DataType::Float64, false),
+        Field::new("timestamp", DataType::Float64, false),
+        Field::new("side", DataType::Utf8, true),
+        Field::new("received_at", DataType::Float64, true),
+        Field::new("feed", DataType::Utf8, true),
+    ]);
+    let columns = vec![
+        strings(rows.iter().map(|t| Some(t.symbol.as_str()))),
+        floats(rows.iter().map(|t| Some(t.price))),
+        floats(rows.iter().map(|t| Some(t.volume))),
+        floats(rows.iter().map(|t| Some(t.timestamp))),
+        strings(rows.iter().map(|t| t.side.as_ref().map(TradeSide::as_str))),
+        floats(rows.iter().map(|t| t.received_at)),
+        strings(rows.iter().map(|t| t.feed.as_deref())),
+    ];
+    Ok(RecordBatch::try_new(Arc::new(schema), columns)?)
+}
+
+#[cfg(feature = "parquet")]
+#[async_trait]
+impl Sink for ParquetSink {
+    async fn publish_signal(&self, signal: &Signal) -> Result<()> {
+        let dir = partition_dir(&self.config.dir, "signals", &signal.symbol, signal.timestamp);
+        let full = {
+            let mut buffers = self.lock()?;
+            let rows = buffers.signals.entry(dir.clone()).or_default();
+            rows.push(signal.clone());
+            (rows.len() >= self.config.batch_rows).then(|| std::mem::take(rows))
+        };
+        match full {
+            Some(rows) => self.write(&dir, signal_batch(&rows)?),
+            None => Ok(()),
+        }
+    }
+
+    async fn publish_tick(&self, tick: &Tick) -> Result<()> {
+        let dir = partition_dir(&self.config.dir, "ticks", &tick.symbol, tick.timestamp);
+        let full = {
+            let mut buffers = self.lock()?;
+            let rows = buffers.ticks.entry(dir.clone()).or_default();
+            rows.push(tick.clone());
+            (rows.len() >= self.config.batch_rows).then(|| std::mem::take(rows))
+        };
+        match full {
+            Some(rows) => self.write(&dir, tick_batch(&rows)?),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(not(feature = "parquet"))]
+/// Stub when the `parquet` feature is not enabled
+pub struct ParquetSink;
+
+#[cfg(not(feature = "parquet"))]
+impl ParquetSink {
+    pub fn open(config: ParquetSinkConfig) -> anyhow::Result<Self> {
+        anyhow::bail!("cannot write parquet to {}: built without the parquet feature", config.dir.display())
+    }
+
+    pub fn dir(&self) -> &Path {
+        Path::new("")
+    }
+
+    pub fn flush(&self) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    pub fn stats(&self) -> ParquetSinkStats {
+        ParquetSinkStats::default()
+    }
+}
+
+#[cfg(not(feature = "parquet"))]
+#[async_trait::async_trait]
+impl crate::sink::Sink for ParquetSink {
+    async fn publish_signal(&self, _signal: &crate::publisher::Signal) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_partitions_by_utc_day_and_symbol() {
+        let root = Path::new("/data");
+        // 2024-03-01T23:59:59Z and a second later
+        assert_eq!(
+            partition_dir(root, "ticks", "AAPL", 1_709_337_599.5),
+            Path::new("/data/ticks/date=2024-03-01/symbol=AAPL")
+        );
+        assert_eq!(
+            partition_dir(root, "signals", "BTC/USD", 1_709_337_600.0),
+            Path::new("/data/signals/date=2024-03-02/symbol=BTC_USD")
+        );
+    }
+
+    #[cfg(not(feature = "parquet"))]
+    #[test]
+    fn test_stub_refuses_to_open() {
+        assert!(ParquetSink::open(ParquetSinkConfig::new("/tmp/parquet")).is_err());
+    }
+
+    #[cfg(feature = "parquet")]
+    #[tokio::test]
+    async fn test_writes_batches_per_partition() {
+        use parquet::file::reader::{FileReader, SerializedFileReader};
+
+        let dir = tempfile::tempdir().unwrap();
+        let config = ParquetSinkConfig { batch_rows: 2, ..ParquetSinkConfig::new(dir.path()) };
+        let sink = ParquetSink::open(config).unwrap();
+        let tick = |symbol: &str, price: f64| Tick::test(symbol, price, 1_709_337_599.0);
+        for price in [1.0, 2.0, 3.0] {
+            sink.publish_tick(&tick("AAPL", price)).await.unwrap();
+        }
+        sink.publish_tick(&tick("MSFT", 4.0)).await.unwrap();
+        assert_eq!(sink.stats(), ParquetSinkStats { files_written: 1, rows_written: 2, buffered: 2, failed_writes: 0 });
+
+        sink.flush().unwrap();
+        assert_eq!(sink.stats().buffered, 0);
+        let aapl = partition_dir(dir.path(), "ticks", "AAPL", 1_709_337_599.0);
+        let mut rows: Vec<i64> = std::fs::read_dir(&aapl)
+            .unwrap()
+            .map(|e| SerializedFileReader::new(std::fs::File::open(e.unwrap().path()).unwrap()).unwrap())
+            .map(|r| r.metadata().file_metadata().num_rows())
+            .collect();
+        rows.sort();
+        assert_eq!(rows, vec![1, 2]);
+        assert!(partition_dir(dir.path(), "ticks", "MSFT", 1_709_337_599.0).exists());
+    }
+}
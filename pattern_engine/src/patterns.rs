@@ -1,16 +1,59 @@
-use crate::onnx_client::default_model_stub;
-use crate::onnx_client::OnnxClient;
+pub mod antipattern;
+pub mod builder;
+pub mod cache;
+pub mod candlestick;
+pub mod canary;
+pub mod composite;
+pub mod continuation;
+pub mod definitions;
+pub mod ensemble;
+pub mod exogenous;
+pub mod export;
+pub mod gaps;
+pub mod harmonic;
+pub mod mean_reversion;
+pub mod orderflow;
+pub mod pipeline;
+pub mod pool;
+pub mod registry;
+pub mod shadow;
+pub mod stats;
+pub mod structure;
+pub mod taxonomy;
+pub mod wyckoff;
+pub mod zigzag;
+
+use crate::inference_backend::InferenceBackend;
+use crate::labels::Classification;
+use crate::onnx_client::{default_model_stub, OnnxClient, OnnxConfig};
+use crate::publisher::Signal;
+use crate::suppressed::{SuppressedSignal, SuppressionReason};
+use antipattern::{AntiPattern, AntiPatternDefinition};
+use builder::PatternLibraryBuilder;
+use canary::{CanarySplit, CanaryStats};
+use cache::{InferenceCache, InferenceCacheConfig, InferenceCacheStats};
+use composite::{CompositeDefinition, CompositePattern, CompositeState};
+use registry::{ModelRegistry, ModelRoute, DEFAULT_MODEL};
+use shadow::{ShadowScore, ShadowStats, ShadowTracker};
+use definitions::{PatternDefinition, PatternGate};
+use taxonomy::{PatternTaxonomy, TagFilter, Timeframe};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
 
 /// Extended metadata for a known or inferred pattern
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, JsonSchema)]
 pub struct PatternMeta {
     pub name: String,
     pub description: String,
     /// Tags for categorization (e.g., reversal, continuation)
     pub tags: Vec<String>,
+    /// Direction / type / timeframe classification derived from the tags
+    #[serde(default)]
+    pub taxonomy: PatternTaxonomy,
     /// Normalized strength 0..1 indicating how strong the pattern is
     pub strength: f64,
     /// polarity -1..1 indicating bearish (-1) to bullish (+1)
@@ -21,96 +64,559 @@ pub struct PatternMeta {
     pub confidence: f64,
     /// Optional feature vector used for ML inference (can be empty for known patterns)
     pub features: Vec<f64>,
+    /// Neckline level for structural patterns
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub neckline: Option<f64>,
+    /// Measured-move price target for structural patterns
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<f64>,
+    /// Potential reversal zone for harmonic patterns
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reversal_zone: Option<PriceZone>,
+    /// Perturbation-based feature attributions for ML-inferred patterns, strongest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attributions: Vec<FeatureAttribution>,
+    /// Where this classification came from
+    #[serde(default)]
+    pub source: PatternSource,
+    /// Version of the definition set that produced a seeded/config entry
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// Model that produced an ML-inferred entry
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model_id: Option<String>,
+    /// Shadow model's score for an ML-inferred entry, when recorded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shadow: Option<ShadowScore>,
+}
+
+/// Origin of a [`PatternMeta`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum PatternSource {
+    /// Built-in definitions compiled into the engine
+    Seeded,
+    /// Definitions loaded from a configured file
+    Config,
+    /// Synthesized from the ML model score
+    Ml,
+    /// Levels supplied directly by a detector, without library lookup
+    #[default]
+    Detector,
 }
 
+/// Inclusive price range
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct PriceZone {
+    pub low: f64,
+    pub high: f64,
+}
+
+impl PriceZone {
+    pub fn contains(&self, price: f64) -> bool {
+        price >= self.low && price <= self.high
+    }
+}
+
+/// Contribution of one input feature to the model score, measured by
+/// replacing the feature with zero and re-scoring
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct FeatureAttribution {
+    pub feature: String,
+    /// Original score minus the score with this feature zeroed
+    pub contribution: f64,
+}
+
+/// Model ID on patterns scored by [`default_model_stub`] as a fallback
+pub const STUB_MODEL_ID: &str = "stub";
+
 /// Pattern library which holds known pattern definitions and can consult ML for unknown patterns
 pub struct PatternLibrary {
     known: HashMap<String, PatternMeta>,
-    ml_client: OnnxClient,
+    /// Per-pattern detector thresholds from the definitions
+    thresholds: HashMap<String, BTreeMap<String, f64>>,
+    /// Per-pattern min score and cooldown overrides
+    gates: Arc<BTreeMap<String, PatternGate>>,
+    /// Patterns combined from other patterns, evaluated per symbol
+    composites: Vec<CompositePattern>,
+    /// Definitions the composites were compiled from, kept for export
+    composite_definitions: Vec<CompositeDefinition>,
+    /// Conditions that veto or down-weight other signals
+    anti_patterns: Vec<AntiPattern>,
+    anti_pattern_definitions: Vec<AntiPatternDefinition>,
+    /// Default model plus named ones, routed by pattern and symbol
+    models: ModelRegistry,
+    /// Identifies the default model in provenance of inferred patterns
+    model_id: String,
+    /// Recent inference scores for unknown patterns (None = always infer)
+    cache: Option<Mutex<InferenceCache>>,
+    /// Registered model that also scores every unknown pattern, for comparison
+    shadow: Option<ShadowTracker>,
+}
+
+/// Default model ID: the model file name
+fn model_file_id(model_path: &Path) -> String {
+    model_path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| model_path.display().to_string())
 }
 
 impl PatternLibrary {
-    /// Create a new pattern library with a given ONNX model path (stub if feature disabled)
+    /// Compose a library from a model, seeds, definitions file and cache settings
+    pub fn builder() -> PatternLibraryBuilder {
+        PatternLibraryBuilder::new()
+    }
+
+    /// Create a new pattern library with a given ONNX model path (stub if feature disabled),
+    /// seeded with the built-in pattern definitions
     pub fn new(model_path: &Path) -> anyhow::Result<Self> {
-        let ml_client = OnnxClient::new(model_path)?;
+        Self::builder().model_path(model_path).build()
+    }
 
-        // Seed with some canonical patterns
+    /// Create a pattern library seeded from a YAML or JSON definitions file
+    pub fn with_definitions_file(model_path: &Path, definitions_path: &Path) -> anyhow::Result<Self> {
+        Self::builder().model_path(model_path).definitions_file(definitions_path).build()
+    }
+
+    /// Create a pattern library from already validated definitions, recording
+    /// `source` as their provenance. The model ID defaults to the model file name.
+    pub fn from_definitions(model_path: &Path, defs: Vec<PatternDefinition>, source: PatternSource) -> anyhow::Result<Self> {
+        Self::from_definitions_with(model_path, &OnnxConfig::default(), defs, source)
+    }
+
+    /// [`PatternLibrary::from_definitions`] with the model loaded under `onnx`
+    pub fn from_definitions_with(
+        model_path: &Path,
+        onnx: &OnnxConfig,
+        defs: Vec<PatternDefinition>,
+        source: PatternSource,
+    ) -> anyhow::Result<Self> {
+        let client = OnnxClient::with_config(model_path, onnx)?;
+        Ok(Self::from_definitions_on(Box::new(client), &model_file_id(model_path), defs, source))
+    }
+
+    /// [`PatternLibrary::from_definitions`] scoring unknown patterns with
+    /// `backend`, identified as `model_id` in provenance
+    pub fn from_definitions_on(
+        backend: Box<dyn InferenceBackend>,
+        model_id: &str,
+        defs: Vec<PatternDefinition>,
+        source: PatternSource,
+    ) -> Self {
         let mut known = HashMap::new();
-        known.insert("double_top".to_string(), PatternMeta {
-            name: "double_top".to_string(),
-            description: "Two peaks at similar levels followed by a drop".to_string(),
-            tags: vec!["reversal".to_string(), "bearish".to_string()],
-            strength: 0.85,
-            polarity: -0.9,
-            action: "sell".to_string(),
-            confidence: 0.9,
-            features: vec![],
-        });
-        known.insert("double_bottom".to_string(), PatternMeta {
-            name: "double_bottom".to_string(),
-            description: "Two troughs at similar levels followed by a rise".to_string(),
-            tags: vec!["reversal".to_string(), "bullish".to_string()],
-            strength: 0.8,
-            polarity: 0.9,
-            action: "buy".to_string(),
-            confidence: 0.88,
-            features: vec![],
-        });
-        known.insert("head_and_shoulders".to_string(), PatternMeta {
-            name: "head_and_shoulders".to_string(),
-            description: "Classic reversal pattern with a higher peak between two lower peaks".to_string(),
-            tags: vec!["reversal".to_string(), "bearish".to_string()],
-            strength: 0.82,
-            polarity: -0.8,
-            action: "sell".to_string(),
-            confidence: 0.87,
-            features: vec![],
-        });
+        let mut thresholds = HashMap::new();
+        for def in defs {
+            let mut meta = def.to_meta();
+            meta.source = source;
+            known.insert(def.name.clone(), meta);
+            thresholds.insert(def.name, def.thresholds);
+        }
 
-        Ok(Self { known, ml_client })
+        Self {
+            known,
+            thresholds,
+            gates: Arc::default(),
+            composites: Vec::new(),
+            composite_definitions: Vec::new(),
+            anti_patterns: Vec::new(),
+            anti_pattern_definitions: Vec::new(),
+            models: ModelRegistry::new(backend),
+            model_id: model_id.to_string(),
+            cache: None,
+            shadow: None,
+        }
+    }
+
+    /// Replace the per-pattern emission gates
+    pub fn with_gates(mut self, gates: BTreeMap<String, PatternGate>) -> Self {
+        self.gates = Arc::new(gates);
+        self
+    }
+
+    /// Add composite patterns, registering each as a known pattern from `source`
+    pub fn with_composites(mut self, defs: &[CompositeDefinition], source: PatternSource) -> anyhow::Result<Self> {
+        for def in defs {
+            self.composites.push(def.compile()?);
+            self.composite_definitions.push(def.clone());
+            let mut meta = def.to_meta();
+            meta.source = source;
+            self.known.insert(def.name.clone(), meta);
+        }
+        Ok(self)
+    }
+
+    pub fn composites(&self) -> &[CompositePattern] {
+        &self.composites
+    }
+
+    /// Record a symbol's newly emitted signals in `state` and return the
+    /// composite signals they complete
+    pub fn evaluate_composites(&self, state: &mut CompositeState, emitted: &[Signal]) -> Vec<Signal> {
+        if self.composites.is_empty() && self.anti_patterns.is_empty() {
+            return Vec::new();
+        }
+        state.evaluate(&self.composites, emitted)
+    }
+
+    /// Add anti-patterns that veto or down-weight other signals
+    pub fn with_anti_patterns(mut self, defs: &[AntiPatternDefinition]) -> anyhow::Result<Self> {
+        for def in defs {
+            self.anti_patterns.push(def.compile()?);
+            self.anti_pattern_definitions.push(def.clone());
+        }
+        Ok(self)
+    }
+
+    pub fn anti_patterns(&self) -> &[AntiPattern] {
+        &self.anti_patterns
+    }
+
+    /// Apply the anti-patterns active in a symbol's `state` (after
+    /// [`Self::evaluate_composites`] has seen this round). Targeted signals
+    /// are scaled and record the anti-patterns in `extra`; vetoed signals are
+    /// returned separately.
+    pub fn screen_anti_patterns(&self, state: &CompositeState, signals: Vec<Signal>) -> (Vec<Signal>, Vec<SuppressedSignal>) {
+        if self.anti_patterns.is_empty() {
+            return (signals, Vec::new());
+        }
+        let mut kept = Vec::with_capacity(signals.len());
+        let mut vetoed = Vec::new();
+        for mut signal in signals {
+            let meta = self.known.get(base_name(&signal.pattern));
+            let active: Vec<&AntiPattern> = self
+                .anti_patterns
+                .iter()
+                .filter(|a| a.targets(&signal, meta) && a.is_active(state, &signal))
+                .collect();
+            if active.is_empty() {
+                kept.push(signal);
+                continue;
+            }
+            let names: Vec<&str> = active.iter().map(|a| a.name()).collect();
+            if let Some(veto) = active.iter().find(|a| a.scale == 0.0) {
+                let detail = format!("vetoed by anti-pattern {}", veto.name());
+                vetoed.push(SuppressedSignal::new(SuppressionReason::AntiPattern, detail, signal));
+                continue;
+            }
+            let scale: f64 = active.iter().map(|a| a.scale).product();
+            signal.score *= scale;
+            signal
+                .extra
+                .insert("anti_patterns".to_string(), serde_json::json!({ "names": names, "scale": scale }));
+            kept.push(signal);
+        }
+        (kept, vetoed)
+    }
+
+    /// Emission gate for a pattern (interval suffixes are ignored)
+    pub fn gate(&self, pattern_name: &str) -> Option<&PatternGate> {
+        self.gates.get(base_name(pattern_name))
+    }
+
+    /// Shared handle to all emission gates
+    pub fn gates(&self) -> Arc<BTreeMap<String, PatternGate>> {
+        self.gates.clone()
+    }
+
+    /// Override the model ID stamped on inferred patterns
+    pub fn with_model_id(mut self, model_id: &str) -> Self {
+        self.model_id = model_id.to_string();
+        self
+    }
+
+    pub fn model_id(&self) -> &str {
+        &self.model_id
+    }
+
+    /// File or URL the default model is served from
+    pub fn model_location(&self) -> String {
+        self.models.default_model().location()
+    }
+
+    /// Backend serving the default model, e.g. `onnx`, `tract` or `remote`
+    pub fn backend_kind(&self) -> &'static str {
+        self.models.default_model().kind()
+    }
+
+    /// Register another model under `name`; it is only consulted through
+    /// routes (see [`PatternLibrary::with_model_routes`])
+    pub fn with_model(mut self, name: &str, client: impl InferenceBackend + 'static) -> Self {
+        self.models.insert(name, Box::new(client));
+        self
+    }
+
+    /// Choose models by pattern and symbol; routes must name registered models
+    pub fn with_model_routes(mut self, routes: Vec<ModelRoute>) -> anyhow::Result<Self> {
+        self.models.set_routes(routes)?;
+        Ok(self)
+    }
+
+    /// Send a share of one model's unknown patterns to another registered
+    /// model; see [`canary`]
+    pub fn with_canary(mut self, split: CanarySplit) -> anyhow::Result<Self> {
+        self.models.set_canary(split)?;
+        Ok(self)
+    }
+
+    /// Traffic split so far, if a canary is set
+    pub fn canary_stats(&self) -> Option<CanaryStats> {
+        self.models.canary().map(CanarySplit::stats)
+    }
+
+    /// Score every unknown pattern with the registered model `name` as well,
+    /// tracking how far it diverges from the served score; `in_meta` also
+    /// attaches its score to the pattern
+    pub fn with_shadow_model(mut self, name: &str, in_meta: bool) -> anyhow::Result<Self> {
+        if self.models.get(name).is_none() {
+            anyhow::bail!("shadow model '{}' is not registered (known: {:?})", name, self.models.names());
+        }
+        self.shadow = Some(ShadowTracker::new(name, in_meta));
+        Ok(self)
+    }
+
+    /// Shadow comparison so far, if a shadow model is set
+    pub fn shadow_stats(&self) -> Option<ShadowStats> {
+        self.shadow.as_ref().map(ShadowTracker::stats)
+    }
+
+    pub fn models(&self) -> &ModelRegistry {
+        &self.models
+    }
+
+    /// Load every model file again and swap them in, dropping cached scores
+    pub fn reload_model(&self) -> anyhow::Result<()> {
+        let result = self.models.reload_all();
+        self.clear_inference_cache();
+        result
+    }
+
+    /// [`PatternLibrary::reload_model`] for models whose files were replaced
+    pub fn reload_model_if_changed(&self) -> anyhow::Result<bool> {
+        let reloaded = self.models.reload_changed()?;
+        if !reloaded.is_empty() {
+            self.clear_inference_cache();
+        }
+        Ok(!reloaded.is_empty())
+    }
+
+    fn clear_inference_cache(&self) {
+        if let Some(cache) = &self.cache {
+            cache.lock().unwrap_or_else(|e| e.into_inner()).clear();
+        }
+    }
+
+    /// Cache inference scores by pattern name and quantized features
+    pub fn with_inference_cache(mut self, config: InferenceCacheConfig) -> Self {
+        self.cache = InferenceCache::new(config).map(Mutex::new);
+        self
+    }
+
+    /// Cache counters, if caching is enabled
+    pub fn inference_cache_stats(&self) -> Option<InferenceCacheStats> {
+        self.cache.as_ref().map(|c| c.lock().unwrap_or_else(|e| e.into_inner()).stats())
+    }
+
+    /// Score from `model` for an unknown pattern, served from the cache when
+    /// fresh. Entries are keyed by model as well as pattern.
+    fn infer_score(&self, model: &str, client: &dyn InferenceBackend, pattern_name: &str, features: &[f64]) -> anyhow::Result<f64> {
+        let Some(cache) = &self.cache else {
+            return client.infer(features);
+        };
+        let key = format!("{}/{}", model, pattern_name);
+        let now = Instant::now();
+        if let Some(score) = cache.lock().unwrap_or_else(|e| e.into_inner()).get(&key, features, now) {
+            return Ok(score);
+        }
+        let score = client.infer(features)?;
+        cache.lock().unwrap_or_else(|e| e.into_inner()).insert(&key, features, score, now);
+        Ok(score)
     }
 
     /// Lookup a pattern by name. If unknown, consult the ML model using `features`.
     /// Returns a PatternMeta either from the known library or synthesized from ML score.
-    /// Lookup a pattern by name. If unknown, consult the ML model using `features`.
-    /// Returns a PatternMeta either from the known library or synthesized from ML score.
+    /// Interval-suffixed names (e.g. `double_top:300s`) resolve to their base pattern.
     pub fn lookup_or_infer(&self, pattern_name: &str, features: Option<&[f64]>) -> anyhow::Result<PatternMeta> {
-        if let Some(meta) = self.known.get(pattern_name) {
-            return Ok(meta.clone());
+        self.lookup_or_infer_for(pattern_name, None, features)
+    }
+
+    /// [`PatternLibrary::lookup_or_infer`] for a pattern seen on `symbol`,
+    /// which unknown patterns use to pick a model from the routes
+    pub fn lookup_or_infer_for(
+        &self,
+        pattern_name: &str,
+        symbol: Option<&str>,
+        features: Option<&[f64]>,
+    ) -> anyhow::Result<PatternMeta> {
+        if let Some(meta) = self.known.get(base_name(pattern_name)) {
+            let mut meta = meta.clone();
+            meta.taxonomy.timeframe = Timeframe::from_pattern(pattern_name);
+            return Ok(meta);
         }
 
         // Unknown pattern: use ML inference if features provided, otherwise use default stub
         let feat_vec = features.map(|f| f.to_vec()).unwrap_or_default();
+        let (model, client) = self.models.select(base_name(pattern_name), symbol);
+        let model_id = if model == DEFAULT_MODEL { self.model_id.clone() } else { model.to_string() };
+        if !feat_vec.is_empty() {
+            if let Some(class) = client.classify(&feat_vec)? {
+                return Ok(self.classified_meta(pattern_name, class, feat_vec, model_id));
+            }
+        }
         let score = if feat_vec.is_empty() {
             default_model_stub(&[])
         } else {
-            self.ml_client.infer(&feat_vec)?
+            self.infer_score(model, client, pattern_name, &feat_vec)?
         };
+        let shadow = if feat_vec.is_empty() { None } else { self.shadow_score(model, score, &feat_vec) };
+        let mut meta = Self::inferred_meta(pattern_name, feat_vec, score, model_id);
+        meta.shadow = shadow;
+        Ok(meta)
+    }
+
+    /// Score `features` with the shadow model and record the comparison with
+    /// `score` from `served_by`. Returns the score to attach, if enabled.
+    fn shadow_score(&self, served_by: &str, score: f64, features: &[f64]) -> Option<ShadowScore> {
+        let tracker = self.shadow.as_ref()?;
+        let name = tracker.model();
+        if name == served_by {
+            return None;
+        }
+        match self.models.get(&name)?.infer(features) {
+            Ok(shadow) => {
+                let recorded = tracker.record(score, shadow, action_for(score) == action_for(shadow));
+                tracker.in_meta.then_some(recorded)
+            }
+            Err(e) => {
+                tracing::debug!("Shadow model {} failed: {:#}", name, e);
+                tracker.record_error();
+                None
+            }
+        }
+    }
 
+    /// An unknown pattern scored by [`default_model_stub`] instead of a model,
+    /// for when inference is unavailable or too slow
+    pub fn stub_meta(pattern_name: &str, features: &[f64]) -> PatternMeta {
+        Self::inferred_meta(pattern_name, features.to_vec(), default_model_stub(features), STUB_MODEL_ID.to_string())
+    }
+
+    /// Meta for an unknown pattern from its model score
+    fn inferred_meta(pattern_name: &str, feat_vec: Vec<f64>, score: f64, model_id: String) -> PatternMeta {
         // Convert score into strength/confidence/action heuristics
         let strength = score.abs();
         let confidence = (strength * 0.9).min(1.0);
-        let action = if score > 0.2 { "buy" } else if score < -0.2 { "sell" } else { "hold" };
+        let action = action_for(score);
         let tags = if score > 0.0 { vec!["bullish".to_string()] } else { vec!["bearish".to_string()] };
 
-        Ok(PatternMeta {
+        let mut taxonomy = PatternTaxonomy::classify(&tags, score);
+        taxonomy.timeframe = Timeframe::from_pattern(pattern_name);
+        PatternMeta {
             name: pattern_name.to_string(),
             description: format!("Synthesized pattern inferred by ML with score {:.3}", score),
             tags,
+            taxonomy,
             strength,
             polarity: score,
             action: action.to_string(),
             confidence,
             features: feat_vec,
-        })
+            neckline: None,
+            target: None,
+            reversal_zone: None,
+            attributions: vec![],
+            source: PatternSource::Ml,
+            version: None,
+            model_id: Some(model_id),
+            shadow: None,
+        }
+    }
+
+    /// Meta for an unknown pattern a classification model assigned to a
+    /// class: the class's library entry when it has one, with the model's
+    /// probability as confidence
+    fn classified_meta(&self, pattern_name: &str, class: Classification, feat_vec: Vec<f64>, model_id: String) -> PatternMeta {
+        let mut meta = match self.known.get(&class.label) {
+            Some(known) => PatternMeta { features: feat_vec, ..known.clone() },
+            None => {
+                let mut meta = Self::inferred_meta(&class.label, feat_vec, 0.0, model_id.clone());
+                meta.tags = Vec::new();
+                meta.taxonomy = PatternTaxonomy::classify(&meta.tags, 0.0);
+                meta
+            }
+        };
+        meta.description = format!("Classified by ML as {} with probability {:.3}", class.label, class.confidence);
+        meta.name = class.label;
+        meta.confidence = class.confidence;
+        meta.taxonomy.timeframe = Timeframe::from_pattern(pattern_name);
+        meta.source = PatternSource::Ml;
+        meta.version = None;
+        meta.model_id = Some(model_id);
+        meta
+    }
+
+    /// Attribute the model score to individual features by perturbation.
+    /// Each feature is zeroed in turn and the change in score recorded; results
+    /// are sorted by absolute contribution. `names` label features by position,
+    /// falling back to `f{index}`.
+    pub fn attribute(&self, features: &[f64], names: &[&str]) -> anyhow::Result<Vec<FeatureAttribution>> {
+        let client = self.models.default_model();
+        let base = client.infer(features)?;
+        let mut perturbed = features.to_vec();
+        let mut out = Vec::with_capacity(features.len());
+        for i in 0..features.len() {
+            perturbed[i] = 0.0;
+            let score = client.infer(&perturbed)?;
+            perturbed[i] = features[i];
+            out.push(FeatureAttribution {
+                feature: names.get(i).map(|n| n.to_string()).unwrap_or_else(|| format!("f{}", i)),
+                contribution: base - score,
+            });
+        }
+        out.sort_by(|a, b| {
+            b.contribution
+                .abs()
+                .partial_cmp(&a.contribution.abs())
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        Ok(out)
+    }
+
+    /// Configured thresholds for a known pattern
+    pub fn thresholds(&self, pattern_name: &str) -> Option<&BTreeMap<String, f64>> {
+        self.thresholds.get(base_name(pattern_name))
+    }
+
+    /// Known patterns matching every tag term (see [`TagFilter`]), sorted by name
+    pub fn filter_by_tags(&self, tags: &[&str]) -> anyhow::Result<Vec<&PatternMeta>> {
+        let filter = TagFilter::new(tags)?;
+        let mut out: Vec<&PatternMeta> = self.known.values().filter(|m| filter.matches(m)).collect();
+        out.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(out)
     }
 
     /// Returns true if the pattern name is known in the seeded library
     pub fn is_known(&self, pattern_name: &str) -> bool {
-        self.known.contains_key(pattern_name)
+        self.known.contains_key(base_name(pattern_name))
     }
 }
 
+/// Action suggested by a model score
+fn action_for(score: f64) -> &'static str {
+    if score > 0.2 {
+        "buy"
+    } else if score < -0.2 {
+        "sell"
+    } else {
+        "hold"
+    }
+}
+
+/// Strip an interval suffix such as `:60s` from a pattern name
+fn base_name(pattern_name: &str) -> &str {
+    pattern_name.split(':').next().unwrap_or(pattern_name)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -123,6 +629,67 @@ mod tests {
         assert!(meta.polarity < 0.0);
         assert_eq!(meta.action, "sell");
         assert!(meta.confidence > 0.0);
+
+        assert_eq!(meta.source, PatternSource::Seeded);
+        assert_eq!(meta.version.as_deref(), Some("1"));
+        assert!(meta.model_id.is_none());
+
+        let suffixed = lib.lookup_or_infer("double_top:300s", None).unwrap();
+        assert_eq!(suffixed.name, "double_top");
+        assert!(lib.is_known("double_top:300s"));
+        assert_eq!(suffixed.taxonomy.timeframe, Some(taxonomy::Timeframe::Intraday));
+    }
+
+    #[test]
+    fn test_classification_model_names_pattern() {
+        let dir = tempfile::tempdir().unwrap();
+        let model = dir.path().join("classifier.onnx");
+        std::fs::write(crate::labels::ClassLabels::sidecar_path(&model), "double_top\nmystery_class\n").unwrap();
+        let lib = PatternLibrary::new(&model).unwrap();
+
+        let meta = lib.lookup_or_infer("unlabeled:60s", Some(&[3.0, 0.0])).unwrap();
+        assert_eq!(meta.name, "double_top");
+        assert_eq!(meta.action, "sell");
+        assert_eq!(meta.source, PatternSource::Ml);
+        assert_eq!(meta.model_id.as_deref(), Some("classifier.onnx"));
+        assert!((meta.confidence - 3f64.exp() / (3f64.exp() + 1.0)).abs() < 1e-12);
+        assert_eq!(meta.taxonomy.timeframe, Some(taxonomy::Timeframe::Intraday));
+        assert_eq!(meta.features, vec![3.0, 0.0]);
+
+        let meta = lib.lookup_or_infer("unlabeled", Some(&[0.0, 1.0])).unwrap();
+        assert_eq!((meta.name.as_str(), meta.action.as_str()), ("mystery_class", "hold"));
+        assert!(meta.tags.is_empty());
+    }
+
+    #[test]
+    fn test_screen_anti_patterns() {
+        let defs = definitions::parse_definition_set(
+            "patterns: []\nanti_patterns:\n  - {name: no_buys, when: double_top within 2 bars, targets: [bullish], scale: 0}\n  - {name: damp, when: double_top, patterns: [volume_spike], scale: 0.5}\n",
+        )
+        .unwrap();
+        let lib = PatternLibrary::new(Path::new("dummy.onnx")).unwrap().with_anti_patterns(&defs.anti_patterns).unwrap();
+        let signal = |pattern: &str, score: f64| Signal::test("AAPL", pattern, score, 0.0);
+        let mut state = CompositeState::default();
+        let batch = vec![signal("double_top:60s", -0.8), signal("ema_crossover", 0.5), signal("volume_spike", -0.6)];
+        lib.evaluate_composites(&mut state, &batch);
+        let (kept, vetoed) = lib.screen_anti_patterns(&state, batch);
+        assert_eq!(vetoed.len(), 1);
+        assert_eq!((vetoed[0].signal.pattern.as_str(), vetoed[0].reason), ("ema_crossover", SuppressionReason::AntiPattern));
+        assert_eq!(kept.len(), 2);
+        assert_eq!(kept[1].score, -0.3);
+        assert_eq!(kept[1].extra["anti_patterns"]["names"][0], "damp");
+        assert_eq!(kept[0].score, -0.8);
+    }
+
+    #[test]
+    fn test_filter_by_tags() {
+        let lib = PatternLibrary::new(std::path::Path::new("dummy.onnx")).unwrap();
+        let bearish = lib.filter_by_tags(&["bearish", "type:reversal"]).unwrap();
+        assert!(bearish.iter().any(|m| m.name == "double_top"));
+        assert!(bearish.iter().all(|m| m.taxonomy.direction == Some(taxonomy::Direction::Bearish)));
+        assert!(bearish.windows(2).all(|w| w[0].name <= w[1].name));
+        assert_eq!(lib.filter_by_tags(&[]).unwrap().len(), lib.known.len());
+        assert!(lib.filter_by_tags(&["size:large"]).is_err());
     }
 
     #[test]
@@ -134,5 +701,131 @@ mod tests {
         assert!(meta.polarity >= -1.0 && meta.polarity <= 1.0);
         assert_eq!(meta.features, features);
         assert!(meta.confidence >= 0.0 && meta.confidence <= 1.0);
+        assert_eq!(meta.source, PatternSource::Ml);
+        assert_eq!(meta.model_id.as_deref(), Some("dummy.onnx"));
+
+        let lib = lib.with_model_id("pattern_model@3");
+        let meta = lib.lookup_or_infer("mystery_pattern", Some(&features)).unwrap();
+        assert_eq!(meta.model_id.as_deref(), Some("pattern_model@3"));
+    }
+
+    #[test]
+    fn test_config_definitions_provenance() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("defs.yaml");
+        std::fs::write(
+            &path,
+            "version: \"2024-06\"\npatterns:\n  - {name: flag, strength: 0.6, polarity: 0.5, action: buy, confidence: 0.7}\n  - {name: pennant, version: \"7\", strength: 0.6, polarity: 0.5, action: buy, confidence: 0.7}\n",
+        )
+        .unwrap();
+        let lib = PatternLibrary::with_definitions_file(Path::new("dummy.onnx"), &path).unwrap();
+        let flag = lib.lookup_or_infer("flag", None).unwrap();
+        assert_eq!(flag.source, PatternSource::Config);
+        assert_eq!(flag.version.as_deref(), Some("2024-06"));
+        assert_eq!(lib.lookup_or_infer("pennant", None).unwrap().version.as_deref(), Some("7"));
+
+        let json = serde_json::to_value(&flag).unwrap();
+        assert_eq!(json["source"], "config");
+        assert!(json.get("model_id").is_none());
+        let legacy: PatternMeta = serde_json::from_str(r#"{"name":"x","description":"","tags":[],"strength":0,"polarity":0,"action":"hold","confidence":0,"features":[]}"#).unwrap();
+        assert_eq!(legacy.source, PatternSource::Detector);
+    }
+
+    #[test]
+    fn test_gates_from_definitions() {
+        let lib = PatternLibrary::new(Path::new("dummy.onnx")).unwrap();
+        assert_eq!(lib.gate("ema_crossover:60s").unwrap().cooldown_secs, Some(10.0));
+        assert!(lib.gate("double_top").is_none());
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("defs.yaml");
+        std::fs::write(&path, "patterns: []\ngates:\n  flag: {min_score: 0.5}\n").unwrap();
+        let lib = PatternLibrary::with_definitions_file(Path::new("dummy.onnx"), &path).unwrap();
+        assert_eq!(lib.gate("flag").unwrap().min_score, Some(0.5));
+        assert!(lib.gate("ema_crossover").is_none());
+        assert_eq!(lib.gates().len(), 1);
+    }
+
+    #[test]
+    fn test_attribute_ranks_features() {
+        let lib = PatternLibrary::new(std::path::Path::new("dummy.onnx")).unwrap();
+        let attrs = lib.attribute(&[0.1, -0.9, 0.3], &["a", "b"]).unwrap();
+        assert_eq!(attrs.len(), 3);
+        assert_eq!(attrs[0].feature, "b");
+        assert!(attrs[0].contribution < 0.0);
+        assert_eq!(attrs[2].feature, "a");
+        assert!(attrs.iter().any(|a| a.feature == "f2"));
+    }
+
+    #[test]
+    fn test_inference_cache_serves_repeats() {
+        let lib = PatternLibrary::new(std::path::Path::new("dummy.onnx"))
+            .unwrap()
+            .with_inference_cache(InferenceCacheConfig::default());
+        let first = lib.lookup_or_infer("mystery_pattern", Some(&[0.3, 0.2])).unwrap();
+        let second = lib.lookup_or_infer("mystery_pattern", Some(&[0.30001, 0.2])).unwrap();
+        assert_eq!(first.polarity, second.polarity);
+        assert_eq!(second.features, vec![0.30001, 0.2]);
+        lib.lookup_or_infer("double_top", None).unwrap();
+        let stats = lib.inference_cache_stats().unwrap();
+        assert_eq!((stats.hits, stats.misses, stats.entries), (1, 1, 1));
+        assert!(PatternLibrary::new(std::path::Path::new("dummy.onnx")).unwrap().inference_cache_stats().is_none());
+    }
+
+    #[test]
+    fn test_model_reload_drops_cached_scores() {
+        let lib = PatternLibrary::new(std::path::Path::new("dummy.onnx"))
+            .unwrap()
+            .with_inference_cache(InferenceCacheConfig::default());
+        lib.lookup_or_infer("mystery_pattern", Some(&[0.3, 0.2])).unwrap();
+        assert_eq!(lib.inference_cache_stats().unwrap().entries, 1);
+        // the file never appeared: nothing changed
+        assert!(!lib.reload_model_if_changed().unwrap());
+        assert_eq!(lib.inference_cache_stats().unwrap().entries, 1);
+        lib.reload_model().unwrap();
+        assert_eq!(lib.inference_cache_stats().unwrap().entries, 0);
+        assert_eq!(lib.model_location(), "dummy.onnx");
+    }
+
+    #[test]
+    fn test_unknown_patterns_route_to_named_models() {
+        let lib = PatternLibrary::new(Path::new("dummy.onnx"))
+            .unwrap()
+            .with_model("crypto", OnnxClient::new(Path::new("crypto.onnx")).unwrap())
+            .with_model_routes(ModelRoute::parse_list("symbol:BTC/*=crypto").unwrap())
+            .unwrap()
+            .with_inference_cache(InferenceCacheConfig::default());
+        let features = [0.3, 0.2];
+        let routed = lib.lookup_or_infer_for("mystery:60s", Some("BTC/USD"), Some(&features)).unwrap();
+        assert_eq!(routed.model_id.as_deref(), Some("crypto"));
+        let default = lib.lookup_or_infer_for("mystery:60s", Some("AAPL"), Some(&features)).unwrap();
+        assert_eq!(default.model_id.as_deref(), Some("dummy.onnx"));
+        // scores are cached per model
+        assert_eq!(lib.inference_cache_stats().unwrap().entries, 2);
+        assert_eq!(lib.lookup_or_infer_for("double_top", Some("BTC/USD"), None).unwrap().source, PatternSource::Seeded);
+
+        let unknown = ModelRoute::parse_list("pattern:iceberg=flow").unwrap();
+        assert!(PatternLibrary::new(Path::new("dummy.onnx")).unwrap().with_model_routes(unknown).is_err());
+    }
+
+    #[test]
+    fn test_shadow_model_scores_alongside() {
+        let lib = PatternLibrary::new(Path::new("dummy.onnx"))
+            .unwrap()
+            .with_model("candidate", OnnxClient::new(Path::new("candidate.onnx")).unwrap())
+            .with_shadow_model("candidate", true)
+            .unwrap();
+        let meta = lib.lookup_or_infer("mystery", Some(&[0.4, 0.2])).unwrap();
+        assert_eq!(meta.model_id.as_deref(), Some("dummy.onnx"));
+        let shadow = meta.shadow.unwrap();
+        assert_eq!((shadow.model.as_str(), shadow.score), ("candidate", meta.polarity));
+        // known patterns and feature-less lookups are not compared
+        lib.lookup_or_infer("double_top", None).unwrap();
+        assert!(lib.lookup_or_infer("mystery", None).unwrap().shadow.is_none());
+
+        let stats = lib.shadow_stats().unwrap();
+        assert_eq!((stats.compared, stats.errors, stats.action_disagreements), (1, 0, 0));
+        assert_eq!(stats.max_abs_divergence, 0.0);
+        assert!(PatternLibrary::new(Path::new("dummy.onnx")).unwrap().with_shadow_model("candidate", false).is_err());
     }
 }
@@ -0,0 +1,157 @@
+//! Anti-patterns that veto or down-weight other signals.
+//!
+//! An anti-pattern is a condition over the patterns recently seen for a
+//! symbol, written like a composite (`double_top OR head_and_shoulders within
+//! 5 bars`). While it holds, signals it targets have their score multiplied
+//! by `scale`; a scale of 0 vetoes them. Targets are tag filters (see
+//! [`TagFilter`]) and/or explicit pattern names. Tag filters see the
+//! signal's direction from its score sign, so `type:breakout+bullish`
+//! targets breakout buys only. A signal never counts towards an anti-pattern
+//! that targets it.
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+use super::composite::{CompositePattern, CompositeState};
+use super::taxonomy::{Direction, TagFilter, Timeframe};
+use super::PatternMeta;
+use crate::publisher::Signal;
+
+/// An anti-pattern as written in a definitions file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AntiPatternDefinition {
+    pub name: String,
+    /// Condition expression, e.g. `double_top OR head_and_shoulders within 5 bars`
+    pub when: String,
+    #[serde(default)]
+    pub description: String,
+    /// Tag filters such as `type:breakout+bullish`; a signal matching any is targeted
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub targets: Vec<String>,
+    /// Pattern names targeted regardless of tags
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub patterns: Vec<String>,
+    /// Score multiplier for targeted signals; 0 vetoes them
+    #[serde(default)]
+    pub scale: f64,
+}
+
+impl AntiPatternDefinition {
+    pub fn compile(&self) -> Result<AntiPattern> {
+        if self.targets.is_empty() && self.patterns.is_empty() {
+            bail!("needs at least one of targets or patterns");
+        }
+        if !(0.0..1.0).contains(&self.scale) {
+            bail!("scale {} outside 0..1", self.scale);
+        }
+        let targets = self.targets.iter().map(|t| TagFilter::parse(t)).collect::<Result<Vec<_>>>()?;
+        if targets.iter().any(TagFilter::is_empty) {
+            bail!("empty target filter");
+        }
+        Ok(AntiPattern {
+            condition: CompositePattern::parse(&self.name, &self.when)?,
+            targets,
+            patterns: self.patterns.clone(),
+            scale: self.scale,
+        })
+    }
+}
+
+/// A compiled anti-pattern
+#[derive(Debug, Clone, PartialEq)]
+pub struct AntiPattern {
+    /// Named after the anti-pattern
+    pub condition: CompositePattern,
+    pub targets: Vec<TagFilter>,
+    pub patterns: Vec<String>,
+    pub scale: f64,
+}
+
+impl AntiPattern {
+    pub fn name(&self) -> &str {
+        &self.condition.name
+    }
+
+    /// Whether `signal` is a target; `meta` is the library entry for its pattern
+    pub fn targets(&self, signal: &Signal, meta: Option<&PatternMeta>) -> bool {
+        let base = signal.pattern.split(':').next().unwrap_or(&signal.pattern);
+        if self.patterns.iter().any(|p| p == base) {
+            return true;
+        }
+        if self.targets.is_empty() {
+            return false;
+        }
+        let mut meta = meta.cloned().unwrap_or_else(|| PatternMeta { name: base.to_string(), ..Default::default() });
+        meta.taxonomy.direction = Some(if signal.score > 0.0 {
+            Direction::Bullish
+        } else if signal.score < 0.0 {
+            Direction::Bearish
+        } else {
+            Direction::Neutral
+        });
+        meta.taxonomy.timeframe = Timeframe::from_pattern(&signal.pattern);
+        self.targets.iter().any(|f| f.matches(&meta))
+    }
+
+    /// Whether the condition holds in `state`, ignoring `signal`'s own pattern
+    pub fn is_active(&self, state: &CompositeState, signal: &Signal) -> bool {
+        let base = signal.pattern.split(':').next().unwrap_or(&signal.pattern);
+        state.holds(&self.condition, base)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::patterns::taxonomy::PatternTaxonomy;
+
+    fn definition(targets: &[&str], patterns: &[&str], scale: f64) -> AntiPatternDefinition {
+        AntiPatternDefinition {
+            name: "into_resistance".to_string(),
+            when: "double_top OR resistance_cluster within 3 bars".to_string(),
+            description: String::new(),
+            targets: targets.iter().map(|t| t.to_string()).collect(),
+            patterns: patterns.iter().map(|p| p.to_string()).collect(),
+            scale,
+        }
+    }
+
+    #[test]
+    fn test_compile_errors() {
+        assert!(definition(&[], &[], 0.0).compile().is_err());
+        assert!(definition(&["bullish"], &[], 1.0).compile().is_err());
+        assert!(definition(&["size:big"], &[], 0.0).compile().is_err());
+        assert!(definition(&[" , "], &[], 0.0).compile().is_err());
+        let mut bad = definition(&["bullish"], &[], 0.5);
+        bad.when = "double_top AND".to_string();
+        assert!(bad.compile().is_err());
+    }
+
+    #[test]
+    fn test_targets_by_tags_and_name() {
+        let anti = definition(&["type:breakout+bullish"], &["volatility_breakout"], 0.0).compile().unwrap();
+        let tags = vec!["breakout".to_string()];
+        let breakout = PatternMeta { taxonomy: PatternTaxonomy::classify(&tags, 0.0), tags, ..Default::default() };
+        assert!(anti.targets(&Signal::test("AAPL", "flag_break:300s", 0.4, 0.0), Some(&breakout)));
+        assert!(!anti.targets(&Signal::test("AAPL", "flag_break:300s", -0.4, 0.0), Some(&breakout)));
+        assert!(anti.targets(&Signal::test("AAPL", "volatility_breakout", -0.4, 0.0), None));
+        assert!(!anti.targets(&Signal::test("AAPL", "ema_crossover", 0.4, 0.0), None));
+    }
+
+    #[test]
+    fn test_active_within_window() {
+        let anti = definition(&[], &["volatility_breakout"], 0.5).compile().unwrap();
+        let breakout = Signal::test("AAPL", "volatility_breakout", 0.6, 0.0);
+        let mut state = CompositeState::default();
+        assert!(!anti.is_active(&state, &breakout));
+        state.evaluate(&[], &[Signal::test("AAPL", "double_top:60s", -0.7, 0.0)]);
+        assert!(anti.is_active(&state, &breakout));
+        (0..3).for_each(|_| state.on_bar());
+        assert!(!anti.is_active(&state, &breakout));
+
+        // a target never triggers itself
+        let own = definition(&[], &["double_top"], 0.5).compile().unwrap();
+        state.evaluate(&[], &[Signal::test("AAPL", "double_top:60s", -0.7, 0.0)]);
+        assert!(!own.is_active(&state, &Signal::test("AAPL", "double_top:60s", -0.7, 0.0)));
+    }
+}
@@ -0,0 +1,371 @@
+//! Step-by-step construction of a [`PatternLibrary`].
+//!
+//! The built-in definitions are the base set unless a definitions file
+//! replaces them or they are switched off. Seeds added in code are layered on
+//! top and replace a base definition of the same name. The combined set is
+//! validated once, when the library is built. A [`LibraryExport`] can stand in
+//! for the definitions instead; the models are set up the same way either way.
+
+use super::cache::InferenceCacheConfig;
+use super::canary::CanarySplit;
+use super::definitions::{self, DefinitionSet, PatternDefinition};
+use super::export::LibraryExport;
+use super::registry::ModelRoute;
+use super::{model_file_id, PatternLibrary, PatternSource};
+use crate::inference_backend::InferenceBackend;
+use crate::onnx_client::{OnnxClient, OnnxConfig};
+use crate::remote_inference::{RemoteClient, RemoteConfig};
+use crate::tract_client::TractClient;
+use std::path::PathBuf;
+
+/// Model used when none is configured
+pub const DEFAULT_MODEL_PATH: &str = "models/pattern_model.onnx";
+
+/// Builder for [`PatternLibrary`]; see [`PatternLibrary::builder`]
+#[derive(Debug, Clone)]
+pub struct PatternLibraryBuilder {
+    model_path: PathBuf,
+    model_id: Option<String>,
+    definitions_file: Option<PathBuf>,
+    /// Exported library used in place of the definitions
+    export: Option<LibraryExport>,
+    builtin: bool,
+    seeds: Vec<PatternDefinition>,
+    cache: Option<InferenceCacheConfig>,
+    onnx: OnnxConfig,
+    /// Named models besides the default, loaded with the same config
+    models: Vec<(String, PathBuf)>,
+    model_routes: Vec<ModelRoute>,
+    /// Serve the default model from an inference server instead of `model_path`
+    remote: Option<RemoteConfig>,
+    /// Run model files with tract instead of ONNX Runtime
+    tract: bool,
+    /// Registered model scoring every unknown pattern for comparison, and
+    /// whether its score goes into the pattern meta
+    shadow: Option<(String, bool)>,
+    /// `[base=]canary:percent` split between registered models
+    canary: Option<String>,
+}
+
+impl Default for PatternLibraryBuilder {
+    fn default() -> Self {
+        Self {
+            model_path: PathBuf::from(DEFAULT_MODEL_PATH),
+            model_id: None,
+            definitions_file: None,
+            export: None,
+            builtin: true,
+            seeds: Vec::new(),
+            cache: None,
+            onnx: OnnxConfig::default(),
+            models: Vec::new(),
+            model_routes: Vec::new(),
+            remote: None,
+            tract: false,
+            shadow: None,
+            canary: None,
+        }
+    }
+}
+
+impl PatternLibraryBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// ONNX model consulted for unknown patterns
+    pub fn model_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.model_path = path.into();
+        self
+    }
+
+    /// Execution providers and session options for the model
+    pub fn onnx(mut self, config: OnnxConfig) -> Self {
+        self.onnx = config;
+        self
+    }
+
+    /// Score unknown patterns on a remote inference server; the model path
+    /// is then unused
+    pub fn remote(mut self, config: RemoteConfig) -> Self {
+        self.remote = Some(config);
+        self
+    }
+
+    /// Run the model files with tract instead of ONNX Runtime; see
+    /// [`crate::tract_client`]
+    pub fn tract(mut self) -> Self {
+        self.tract = true;
+        self
+    }
+
+    /// Another model, consulted for unknown patterns the routes send to `name`
+    pub fn model(mut self, name: &str, path: impl Into<PathBuf>) -> Self {
+        self.models.push((name.to_string(), path.into()));
+        self
+    }
+
+    /// Rules choosing a model by pattern and symbol; see [`super::registry`]
+    pub fn model_routes(mut self, routes: Vec<ModelRoute>) -> Self {
+        self.model_routes = routes;
+        self
+    }
+
+    /// Shadow-score unknown patterns with the registered model `name`; see
+    /// [`PatternLibrary::with_shadow_model`]
+    pub fn shadow_model(mut self, name: &str, in_meta: bool) -> Self {
+        self.shadow = Some((name.to_string(), in_meta));
+        self
+    }
+
+    /// Send a share of a model's traffic to another, written
+    /// `[base=]canary:percent`; see [`super::canary`]
+    pub fn canary(mut self, spec: &str) -> Self {
+        self.canary = Some(spec.to_string());
+        self
+    }
+
+    /// Model ID stamped on inferred patterns (defaults to the model file name)
+    pub fn model_id(mut self, model_id: &str) -> Self {
+        self.model_id = Some(model_id.to_string());
+        self
+    }
+
+    /// Load the base definitions, gates and composites from a YAML or JSON
+    /// file instead of the built-in set
+    pub fn definitions_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.definitions_file = Some(path.into());
+        self
+    }
+
+    /// Take the entries, gates, composites, anti-patterns and model ID from an
+    /// exported library instead of definitions; entries keep their exported
+    /// provenance. Cannot be combined with a definitions file or seeds.
+    pub fn export(mut self, export: LibraryExport) -> Self {
+        self.export = Some(export);
+        self
+    }
+
+    /// Start from an empty base set when no definitions file is given
+    pub fn without_builtin(mut self) -> Self {
+        self.builtin = false;
+        self
+    }
+
+    /// Add a definition on top of the base set
+    pub fn seed(mut self, def: PatternDefinition) -> Self {
+        self.seeds.push(def);
+        self
+    }
+
+    pub fn seeds(mut self, defs: impl IntoIterator<Item = PatternDefinition>) -> Self {
+        self.seeds.extend(defs);
+        self
+    }
+
+    /// Cache inference scores of unknown patterns
+    pub fn cache(mut self, config: InferenceCacheConfig) -> Self {
+        self.cache = Some(config);
+        self
+    }
+
+    /// Cache up to `capacity` inference scores with the default TTL and quantum
+    pub fn cache_size(self, capacity: usize) -> Self {
+        self.cache(InferenceCacheConfig { capacity, ..Default::default() })
+    }
+
+    pub fn build(self) -> anyhow::Result<PatternLibrary> {
+        let mut lib = match &self.export {
+            Some(export) => {
+                if self.definitions_file.is_some() || !self.seeds.is_empty() {
+                    anyhow::bail!("a library export cannot be combined with a definitions file or seeds");
+                }
+                let (backend, _) = self.default_model()?;
+                PatternLibrary::from_export_on(backend, export)?
+            }
+            None => self.definitions_library()?,
+        };
+        if let Some(id) = &self.model_id {
+            lib = lib.with_model_id(id);
+        }
+        for (name, path) in &self.models {
+            lib = match self.tract {
+                true => lib.with_model(name, TractClient::with_config(path, &self.onnx)?),
+                false => lib.with_model(name, OnnxClient::with_config(path, &self.onnx)?),
+            };
+        }
+        lib = lib.with_model_routes(self.model_routes)?;
+        if let Some((name, in_meta)) = &self.shadow {
+            lib = lib.with_shadow_model(name, *in_meta)?;
+        }
+        if let Some(spec) = &self.canary {
+            lib = lib.with_canary(CanarySplit::parse(spec)?)?;
+        }
+        if let Some(config) = self.cache {
+            lib = lib.with_inference_cache(config);
+        }
+        Ok(lib)
+    }
+
+    /// Library from the definitions file or built-in set plus the seeds
+    fn definitions_library(&self) -> anyhow::Result<PatternLibrary> {
+        let (mut set, source) = match &self.definitions_file {
+            Some(path) => (definitions::load_definition_set(path)?, PatternSource::Config),
+            None if self.builtin => {
+                (definitions::parse_definition_set(definitions::BUILTIN_DEFINITIONS)?, PatternSource::Seeded)
+            }
+            None => (DefinitionSet::default(), PatternSource::Seeded),
+        };
+        set.patterns.retain(|d| !self.seeds.iter().any(|s| s.name == d.name));
+        set.patterns.extend(self.seeds.iter().cloned());
+        definitions::validate(&set)?;
+
+        let (backend, model_id) = self.default_model()?;
+        let mut lib = PatternLibrary::from_definitions_on(backend, &model_id, set.patterns, source)
+            .with_gates(set.gates)
+            .with_composites(&set.composites, source)?
+            .with_anti_patterns(&set.anti_patterns)?;
+        for seed in &self.seeds {
+            if let Some(meta) = lib.known.get_mut(&seed.name) {
+                meta.source = PatternSource::Seeded;
+            }
+        }
+        Ok(lib)
+    }
+
+    /// Backend serving the default model, with the model ID it implies
+    fn default_model(&self) -> anyhow::Result<(Box<dyn InferenceBackend>, String)> {
+        Ok(match &self.remote {
+            Some(config) => {
+                let client = RemoteClient::new(config.clone())?;
+                let model_id = client.model_id();
+                (Box::new(client), model_id)
+            }
+            None if self.tract => {
+                (Box::new(TractClient::with_config(&self.model_path, &self.onnx)?), model_file_id(&self.model_path))
+            }
+            None => (Box::new(OnnxClient::with_config(&self.model_path, &self.onnx)?), model_file_id(&self.model_path)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seed(name: &str, polarity: f64) -> PatternDefinition {
+        PatternDefinition {
+            name: name.to_string(),
+            description: String::new(),
+            tags: vec!["custom".to_string()],
+            strength: 0.5,
+            polarity,
+            action: "hold".to_string(),
+            confidence: 0.5,
+            thresholds: Default::default(),
+            version: None,
+        }
+    }
+
+    #[test]
+    fn test_defaults_match_new() {
+        let built = PatternLibrary::builder().model_path("dummy.onnx").build().unwrap();
+        let lib = PatternLibrary::new(std::path::Path::new("dummy.onnx")).unwrap();
+        assert_eq!(built.known, lib.known);
+        assert_eq!(built.model_id(), "dummy.onnx");
+        assert!(built.inference_cache_stats().is_none());
+    }
+
+    #[test]
+    fn test_seeds_cache_and_model_id() {
+        let lib = PatternLibrary::builder()
+            .model_path("dummy.onnx")
+            .without_builtin()
+            .seeds([seed("flag", 0.3), seed("pennant", -0.2)])
+            .cache_size(16)
+            .model_id("v2")
+            .build()
+            .unwrap();
+        assert!(lib.is_known("flag") && lib.is_known("pennant"));
+        assert!(!lib.is_known("double_top"));
+        assert_eq!(lib.model_id(), "v2");
+        assert_eq!(lib.inference_cache_stats().unwrap().entries, 0);
+
+        // a seed replaces the built-in entry of the same name
+        let lib = PatternLibrary::builder().model_path("dummy.onnx").seed(seed("double_top", 0.4)).build().unwrap();
+        let meta = lib.lookup_or_infer("double_top", None).unwrap();
+        assert_eq!((meta.polarity, meta.source), (0.4, PatternSource::Seeded));
+        assert!(lib.is_known("head_and_shoulders"));
+
+        assert!(PatternLibrary::builder().seed(seed("bad", 2.0)).build().is_err());
+    }
+
+    #[test]
+    fn test_named_models_and_routes() {
+        let routes = ModelRoute::parse_list("pattern:harmonic_*=harmonic").unwrap();
+        let lib = PatternLibrary::builder()
+            .model_path("dummy.onnx")
+            .model("harmonic", "harmonic.onnx")
+            .model_routes(routes.clone())
+            .build()
+            .unwrap();
+        assert_eq!(lib.models().names(), vec!["default", "harmonic"]);
+        let meta = lib.lookup_or_infer("harmonic_bat", Some(&[0.1])).unwrap();
+        assert_eq!(meta.model_id.as_deref(), Some("harmonic"));
+        assert!(PatternLibrary::builder().model_routes(routes).build().is_err());
+    }
+
+    #[test]
+    fn test_canary_split() {
+        let lib = PatternLibrary::builder().model_path("dummy.onnx").model("v2", "v2.onnx").canary("v2:50").build().unwrap();
+        let served: Vec<_> = (0..4).map(|_| lib.lookup_or_infer("mystery", Some(&[0.1])).unwrap().model_id.unwrap()).collect();
+        assert_eq!(served, vec!["dummy.onnx", "v2", "dummy.onnx", "v2"]);
+        assert_eq!(lib.canary_stats().map(|s| (s.total, s.routed)), Some((4, 2)));
+        assert!(PatternLibrary::builder().model_path("dummy.onnx").canary("v2:50").build().is_err());
+    }
+
+    #[test]
+    fn test_remote_default_model() {
+        let lib = PatternLibrary::builder().remote(RemoteConfig::new("http://scorer:8080/predict")).build().unwrap();
+        assert_eq!(lib.model_location(), "http://scorer:8080/predict");
+        assert_eq!(lib.backend_kind(), "remote");
+        assert_eq!(lib.model_id(), "http://scorer:8080/predict");
+        assert!(PatternLibrary::builder().remote(RemoteConfig::new("scorer:8080")).build().is_err());
+    }
+
+    #[cfg(not(feature = "tract"))]
+    #[test]
+    fn test_tract_needs_feature() {
+        let err = PatternLibrary::builder().model_path("dummy.onnx").tract().build().err().unwrap();
+        assert!(err.to_string().contains("built without the tract feature"));
+    }
+
+    #[test]
+    fn test_definitions_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("defs.yaml");
+        std::fs::write(&path, "- {name: wedge, strength: 0.5, polarity: 0.2, action: buy, confidence: 0.6}\n").unwrap();
+        let lib = PatternLibrary::builder().model_path("dummy.onnx").definitions_file(&path).build().unwrap();
+        assert_eq!(lib.lookup_or_infer("wedge", None).unwrap().source, PatternSource::Config);
+        assert!(!lib.is_known("double_top"));
+        assert!(PatternLibrary::builder().definitions_file(dir.path().join("missing.yaml")).build().is_err());
+    }
+
+    #[test]
+    fn test_export_source() {
+        let built = PatternLibrary::builder().model_path("dummy.onnx").model_id("v3").seed(seed("wedge", 0.2));
+        let export = built.build().unwrap().export();
+        let lib = PatternLibrary::builder()
+            .model_path("other.onnx")
+            .model("harmonic", "harmonic.onnx")
+            .model_routes(ModelRoute::parse_list("pattern:harmonic_*=harmonic").unwrap())
+            .export(export.clone())
+            .build()
+            .unwrap();
+        assert_eq!(lib.export(), export);
+        assert_eq!(lib.model_id(), "v3");
+        assert_eq!(lib.lookup_or_infer("wedge", None).unwrap().source, PatternSource::Seeded);
+        assert_eq!(lib.models().names(), vec!["default", "harmonic"]);
+        assert!(PatternLibrary::builder().export(export).seed(seed("flag", 0.1)).build().is_err());
+    }
+}
@@ -0,0 +1,145 @@
+//! LRU cache for ML inference scores of unknown patterns.
+//!
+//! Features are quantized before keying so near-identical vectors produced on
+//! consecutive ticks share an entry. Entries expire after a TTL so a cached
+//! score never outlives the market state it was computed for by much.
+
+use lru::LruCache;
+use serde::Serialize;
+use std::num::NonZeroUsize;
+use std::time::{Duration, Instant};
+
+/// Size, lifetime and key resolution of the cache
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InferenceCacheConfig {
+    pub capacity: usize,
+    pub ttl: Duration,
+    /// Feature values are rounded to multiples of this before keying
+    pub quantum: f64,
+}
+
+impl Default for InferenceCacheConfig {
+    fn default() -> Self {
+        Self {
+            capacity: 1024,
+            ttl: Duration::from_secs(60),
+            quantum: 1e-4,
+        }
+    }
+}
+
+/// Hit/miss counters and current size
+#[derive(Debug, Clone, Copy, Default, Serialize, PartialEq)]
+pub struct InferenceCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+}
+
+type CacheKey = (String, Vec<i64>);
+
+pub struct InferenceCache {
+    entries: LruCache<CacheKey, (f64, Instant)>,
+    ttl: Duration,
+    quantum: f64,
+    hits: u64,
+    misses: u64,
+}
+
+impl InferenceCache {
+    /// None when the configured capacity is zero
+    pub fn new(config: InferenceCacheConfig) -> Option<Self> {
+        let capacity = NonZeroUsize::new(config.capacity)?;
+        Some(Self {
+            entries: LruCache::new(capacity),
+            ttl: config.ttl,
+            quantum: if config.quantum > 0.0 { config.quantum } else { f64::EPSILON },
+            hits: 0,
+            misses: 0,
+        })
+    }
+
+    fn key(&self, name: &str, features: &[f64]) -> CacheKey {
+        let quantized = features.iter().map(|f| (f / self.quantum).round() as i64).collect();
+        (name.to_string(), quantized)
+    }
+
+    /// Cached score for `name` at these features, if still fresh
+    pub fn get(&mut self, name: &str, features: &[f64], now: Instant) -> Option<f64> {
+        let key = self.key(name, features);
+        match self.entries.get(&key) {
+            Some(&(score, stored)) if now.duration_since(stored) < self.ttl => {
+                self.hits += 1;
+                Some(score)
+            }
+            Some(_) => {
+                self.entries.pop(&key);
+                self.misses += 1;
+                None
+            }
+            None => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+
+    pub fn insert(&mut self, name: &str, features: &[f64], score: f64, now: Instant) {
+        let key = self.key(name, features);
+        self.entries.put(key, (score, now));
+    }
+
+    /// Forget every score, e.g. after the model changed
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub fn stats(&self) -> InferenceCacheStats {
+        InferenceCacheStats {
+            hits: self.hits,
+            misses: self.misses,
+            entries: self.entries.len(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cache(capacity: usize) -> InferenceCache {
+        InferenceCache::new(InferenceCacheConfig {
+            capacity,
+            ttl: Duration::from_secs(10),
+            quantum: 0.01,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_quantized_hit_and_ttl() {
+        let mut c = cache(4);
+        let t0 = Instant::now();
+        assert_eq!(c.get("p", &[0.5, 1.0], t0), None);
+        c.insert("p", &[0.5, 1.0], 0.7, t0);
+        assert_eq!(c.get("p", &[0.501, 0.999], t0), Some(0.7));
+        assert_eq!(c.get("q", &[0.5, 1.0], t0), None);
+        assert_eq!(c.get("p", &[0.52, 1.0], t0), None);
+        assert_eq!(c.get("p", &[0.5, 1.0], t0 + Duration::from_secs(11)), None);
+        assert_eq!(c.stats(), InferenceCacheStats { hits: 1, misses: 4, entries: 0 });
+    }
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        let mut c = cache(2);
+        let t0 = Instant::now();
+        c.insert("a", &[1.0], 0.1, t0);
+        c.insert("b", &[1.0], 0.2, t0);
+        assert!(c.get("a", &[1.0], t0).is_some());
+        c.insert("c", &[1.0], 0.3, t0);
+        assert!(c.get("b", &[1.0], t0).is_none());
+        assert!(c.get("a", &[1.0], t0).is_some());
+        assert!(c.get("c", &[1.0], t0).is_some());
+        assert!(InferenceCache::new(InferenceCacheConfig { capacity: 0, ..Default::default() }).is_none());
+    }
+}
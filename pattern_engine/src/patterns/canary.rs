@@ -0,0 +1,142 @@
+//! Gradual rollout of a model version.
+//!
+//! A [`CanarySplit`] sends a percentage of the unknown patterns that would go
+//! to one registered model (the base, `default` unless named) to another,
+//! e.g. 5% to a retrained version. Calls are split evenly rather than at
+//! random: at 5% every twentieth call goes to the canary. Per-version
+//! latency and errors are in each model's telemetry; the split counts what
+//! was routed. Written `[base=]canary:percent`:
+//!
+//! ```text
+//! v2:5
+//! harmonic=harmonic_v2:25
+//! ```
+
+use super::registry::DEFAULT_MODEL;
+use anyhow::{anyhow, bail, Result};
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+#[derive(Debug)]
+pub struct CanarySplit {
+    pub base: String,
+    pub canary: String,
+    /// Percent of base traffic sent to the canary, as f64 bits
+    percent: AtomicU64,
+    /// Calls that resolved to the base model
+    seen: AtomicU64,
+    routed: AtomicU64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CanaryStats {
+    pub base: String,
+    pub canary: String,
+    pub percent: f64,
+    /// Calls for the base model, whichever version served them
+    pub total: u64,
+    /// Of those, calls served by the canary
+    pub routed: u64,
+}
+
+fn check_percent(percent: f64) -> Result<()> {
+    if !(0.0..=100.0).contains(&percent) {
+        bail!("canary percent must be within 0..=100, got {}", percent);
+    }
+    Ok(())
+}
+
+impl CanarySplit {
+    pub fn new(base: &str, canary: &str, percent: f64) -> Result<Self> {
+        check_percent(percent)?;
+        if base == canary {
+            bail!("canary model '{}' is also the base", canary);
+        }
+        Ok(Self {
+            base: base.to_string(),
+            canary: canary.to_string(),
+            percent: AtomicU64::new(percent.to_bits()),
+            seen: AtomicU64::new(0),
+            routed: AtomicU64::new(0),
+        })
+    }
+
+    /// Parse `[base=]canary:percent`
+    pub fn parse(spec: &str) -> Result<Self> {
+        let (models, percent) = spec
+            .trim()
+            .rsplit_once(':')
+            .ok_or_else(|| anyhow!("canary '{}' must be [base=]canary:percent", spec))?;
+        let percent: f64 = percent.trim().parse().map_err(|_| anyhow!("invalid canary percent '{}'", percent))?;
+        let (base, canary) = models.split_once('=').unwrap_or((DEFAULT_MODEL, models));
+        let (base, canary) = (base.trim(), canary.trim());
+        if base.is_empty() || canary.is_empty() {
+            bail!("canary '{}' must be [base=]canary:percent", spec);
+        }
+        Self::new(base, canary, percent)
+    }
+
+    pub fn percent(&self) -> f64 {
+        f64::from_bits(self.percent.load(Ordering::Relaxed))
+    }
+
+    /// Change the share sent to the canary; counts carry on
+    pub fn set_percent(&self, percent: f64) -> Result<()> {
+        check_percent(percent)?;
+        self.percent.store(percent.to_bits(), Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Whether the next call for the base model goes to the canary
+    pub fn take(&self) -> bool {
+        let n = self.seen.fetch_add(1, Ordering::Relaxed) as f64;
+        let share = self.percent() / 100.0;
+        let hit = ((n + 1.0) * share).floor() > (n * share).floor();
+        if hit {
+            self.routed.fetch_add(1, Ordering::Relaxed);
+        }
+        hit
+    }
+
+    pub fn stats(&self) -> CanaryStats {
+        CanaryStats {
+            base: self.base.clone(),
+            canary: self.canary.clone(),
+            percent: self.percent(),
+            total: self.seen.load(Ordering::Relaxed),
+            routed: self.routed.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_split() {
+        let split = CanarySplit::parse("v2:5").unwrap();
+        assert_eq!((split.base.as_str(), split.canary.as_str(), split.percent()), ("default", "v2", 5.0));
+        let split = CanarySplit::parse(" harmonic = harmonic_v2 : 25 ").unwrap();
+        assert_eq!((split.base.as_str(), split.canary.as_str(), split.percent()), ("harmonic", "harmonic_v2", 25.0));
+        for bad in ["v2", "v2:150", "v2:-1", "v2:some", "=v2:5", "v2=v2:5"] {
+            assert!(CanarySplit::parse(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_splits_evenly() {
+        let split = CanarySplit::new("default", "v2", 5.0).unwrap();
+        let hits: Vec<u64> = (0..100).filter(|_| split.take()).map(|_| split.stats().total).collect();
+        assert_eq!(hits, vec![20, 40, 60, 80, 100]);
+
+        split.set_percent(50.0).unwrap();
+        assert_eq!((0..10).filter(|_| split.take()).count(), 5);
+        assert!(split.set_percent(101.0).is_err());
+        let stats = split.stats();
+        assert_eq!((stats.total, stats.routed, stats.percent), (110, 10, 50.0));
+
+        split.set_percent(0.0).unwrap();
+        assert!(!(0..1000).any(|_| split.take()));
+    }
+}
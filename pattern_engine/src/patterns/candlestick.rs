@@ -0,0 +1,258 @@
+//! Candlestick pattern recognition on closed OHLC bars.
+//!
+//! Detectors are incremental: feed each closed [`Candle`] once and the
+//! detector reports any single, two or three bar patterns completed by it.
+
+use serde::{Deserialize, Serialize};
+
+/// Simple OHLC candle used for interval aggregation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Candle {
+    pub start: u64,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub volume: f64,
+}
+
+impl Candle {
+    /// Start a new candle from a single trade
+    pub fn from_trade(start: u64, price: f64, volume: f64) -> Self {
+        Self {
+            start,
+            open: price,
+            high: price,
+            low: price,
+            close: price,
+            volume,
+        }
+    }
+
+    /// Absolute size of the real body
+    pub fn body(&self) -> f64 {
+        (self.close - self.open).abs()
+    }
+
+    /// High to low range
+    pub fn range(&self) -> f64 {
+        self.high - self.low
+    }
+
+    /// Wick above the real body
+    pub fn upper_shadow(&self) -> f64 {
+        self.high - self.open.max(self.close)
+    }
+
+    /// Wick below the real body
+    pub fn lower_shadow(&self) -> f64 {
+        self.open.min(self.close) - self.low
+    }
+
+    pub fn is_bullish(&self) -> bool {
+        self.close > self.open
+    }
+
+    pub fn is_bearish(&self) -> bool {
+        self.close < self.open
+    }
+
+    /// Midpoint of the real body
+    pub fn body_mid(&self) -> f64 {
+        (self.open + self.close) / 2.0
+    }
+}
+
+/// Recognized candlestick patterns
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CandlePattern {
+    Doji,
+    Hammer,
+    ShootingStar,
+    BullishEngulfing,
+    BearishEngulfing,
+    MorningStar,
+    EveningStar,
+}
+
+impl CandlePattern {
+    /// Pattern name as used in signals and the pattern library
+    pub fn name(&self) -> &'static str {
+        match self {
+            CandlePattern::Doji => "doji",
+            CandlePattern::Hammer => "hammer",
+            CandlePattern::ShootingStar => "shooting_star",
+            CandlePattern::BullishEngulfing => "bullish_engulfing",
+            CandlePattern::BearishEngulfing => "bearish_engulfing",
+            CandlePattern::MorningStar => "morning_star",
+            CandlePattern::EveningStar => "evening_star",
+        }
+    }
+
+    /// polarity -1..1 indicating bearish (-1) to bullish (+1)
+    pub fn polarity(&self) -> f64 {
+        match self {
+            CandlePattern::Doji => 0.0,
+            CandlePattern::Hammer => 0.5,
+            CandlePattern::ShootingStar => -0.5,
+            CandlePattern::BullishEngulfing => 0.6,
+            CandlePattern::BearishEngulfing => -0.6,
+            CandlePattern::MorningStar => 0.7,
+            CandlePattern::EveningStar => -0.7,
+        }
+    }
+}
+
+/// Thresholds used by [`CandlestickDetector`]
+#[derive(Debug, Clone)]
+pub struct CandlestickConfig {
+    /// Body at most this fraction of the range counts as a doji
+    pub doji_body_ratio: f64,
+    /// Long shadow must be at least this multiple of the body (hammer / shooting star)
+    pub long_shadow_ratio: f64,
+    /// Opposite shadow at most this fraction of the range (hammer / shooting star)
+    pub short_shadow_ratio: f64,
+    /// First bar of a star must have a body of at least this fraction of its range
+    pub star_body_ratio: f64,
+    /// Middle bar of a star must have a body at most this fraction of the first body
+    pub star_small_body_ratio: f64,
+}
+
+impl Default for CandlestickConfig {
+    fn default() -> Self {
+        Self {
+            doji_body_ratio: 0.1,
+            long_shadow_ratio: 2.0,
+            short_shadow_ratio: 0.1,
+            star_body_ratio: 0.5,
+            star_small_body_ratio: 0.3,
+        }
+    }
+}
+
+/// Incremental candlestick detector keeping the last two closed candles
+#[derive(Debug, Clone, Default)]
+pub struct CandlestickDetector {
+    config: CandlestickConfig,
+    prev: Option<Candle>,
+    prev2: Option<Candle>,
+}
+
+impl CandlestickDetector {
+    pub fn new(config: CandlestickConfig) -> Self {
+        Self {
+            config,
+            prev: None,
+            prev2: None,
+        }
+    }
+
+    /// Feed a closed candle and return all patterns completed by it
+    pub fn update(&mut self, candle: &Candle) -> Vec<CandlePattern> {
+        let mut found = Vec::new();
+        let cfg = &self.config;
+        let range = candle.range();
+
+        if range > 0.0 {
+            let body = candle.body();
+            if body <= cfg.doji_body_ratio * range {
+                found.push(CandlePattern::Doji);
+            } else {
+                let small_shadow = cfg.short_shadow_ratio * range;
+                if candle.lower_shadow() >= cfg.long_shadow_ratio * body && candle.upper_shadow() <= small_shadow {
+                    found.push(CandlePattern::Hammer);
+                }
+                if candle.upper_shadow() >= cfg.long_shadow_ratio * body && candle.lower_shadow() <= small_shadow {
+                    found.push(CandlePattern::ShootingStar);
+                }
+            }
+        }
+
+        if let Some(prev) = &self.prev {
+            if prev.is_bearish()
+                && candle.is_bullish()
+                && candle.open <= prev.close
+                && candle.close >= prev.open
+                && candle.body() > prev.body()
+            {
+                found.push(CandlePattern::BullishEngulfing);
+            }
+            if prev.is_bullish()
+                && candle.is_bearish()
+                && candle.open >= prev.close
+                && candle.close <= prev.open
+                && candle.body() > prev.body()
+            {
+                found.push(CandlePattern::BearishEngulfing);
+            }
+
+            if let Some(first) = &self.prev2 {
+                let first_is_long = first.range() > 0.0 && first.body() >= cfg.star_body_ratio * first.range();
+                let middle_is_small = prev.body() <= cfg.star_small_body_ratio * first.body();
+                if first_is_long && middle_is_small {
+                    if first.is_bearish() && candle.is_bullish() && candle.close > first.body_mid() {
+                        found.push(CandlePattern::MorningStar);
+                    }
+                    if first.is_bullish() && candle.is_bearish() && candle.close < first.body_mid() {
+                        found.push(CandlePattern::EveningStar);
+                    }
+                }
+            }
+        }
+
+        self.prev2 = self.prev.take();
+        self.prev = Some(candle.clone());
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candle(open: f64, high: f64, low: f64, close: f64) -> Candle {
+        Candle { start: 0, open, high, low, close, volume: 1.0 }
+    }
+
+    #[test]
+    fn test_single_bar_patterns() {
+        let mut det = CandlestickDetector::default();
+        assert_eq!(det.update(&candle(100.0, 101.0, 99.0, 100.05)), vec![CandlePattern::Doji]);
+
+        let mut det = CandlestickDetector::default();
+        assert_eq!(det.update(&candle(100.0, 100.6, 97.0, 100.5)), vec![CandlePattern::Hammer]);
+
+        let mut det = CandlestickDetector::default();
+        assert_eq!(det.update(&candle(100.5, 103.5, 99.95, 100.0)), vec![CandlePattern::ShootingStar]);
+    }
+
+    #[test]
+    fn test_engulfing() {
+        let mut det = CandlestickDetector::default();
+        det.update(&candle(101.0, 101.2, 99.8, 100.0));
+        let found = det.update(&candle(99.8, 102.0, 99.7, 101.5));
+        assert!(found.contains(&CandlePattern::BullishEngulfing));
+
+        let mut det = CandlestickDetector::default();
+        det.update(&candle(100.0, 101.2, 99.8, 101.0));
+        let found = det.update(&candle(101.2, 101.3, 99.0, 99.5));
+        assert!(found.contains(&CandlePattern::BearishEngulfing));
+    }
+
+    #[test]
+    fn test_stars() {
+        let mut det = CandlestickDetector::default();
+        det.update(&candle(105.0, 105.2, 99.8, 100.0));
+        det.update(&candle(99.5, 99.9, 99.0, 99.6));
+        let found = det.update(&candle(100.0, 104.0, 99.9, 103.8));
+        assert!(found.contains(&CandlePattern::MorningStar));
+
+        let mut det = CandlestickDetector::default();
+        det.update(&candle(100.0, 105.2, 99.8, 105.0));
+        det.update(&candle(105.5, 106.0, 105.1, 105.4));
+        let found = det.update(&candle(105.0, 105.1, 101.0, 101.2));
+        assert!(found.contains(&CandlePattern::EveningStar));
+        assert_eq!(CandlePattern::EveningStar.name(), "evening_star");
+    }
+}
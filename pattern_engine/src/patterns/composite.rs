@@ -0,0 +1,427 @@
+//! Composite patterns built from other patterns.
+//!
+//! A composite fires when its constituents have been seen for the same symbol
+//! within a window of bars. The condition is either boolean
+//! (`volume_spike AND (vwap_deviation OR NOT ema_crossover)`) or weighted
+//! (`0.6*volume_spike + 0.4*vwap_deviation >= 0.5`), optionally followed by
+//! `within N bars`; without it constituents must share the current bar.
+//! Constituents match by base pattern name, so `double_top:300s` counts as
+//! `double_top`. A composite fires again only after a constituent is seen
+//! anew, and its signals are observed in turn so composites may nest.
+
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+
+use super::taxonomy::PatternTaxonomy;
+use super::PatternMeta;
+use crate::publisher::Signal;
+use crate::signal_id;
+
+/// Observations older than this many bars are dropped
+const MAX_WINDOW_BARS: u64 = 500;
+
+/// Boolean condition over pattern names
+#[derive(Debug, Clone, PartialEq)]
+pub enum Condition {
+    Pattern(String),
+    And(Vec<Condition>),
+    Or(Vec<Condition>),
+    Not(Box<Condition>),
+}
+
+impl Condition {
+    fn matches(&self, present: &BTreeSet<&str>) -> bool {
+        match self {
+            Condition::Pattern(name) => present.contains(name.as_str()),
+            Condition::And(all) => all.iter().all(|c| c.matches(present)),
+            Condition::Or(any) => any.iter().any(|c| c.matches(present)),
+            Condition::Not(inner) => !inner.matches(present),
+        }
+    }
+
+    /// Names whose presence counts towards the condition (not negated ones)
+    fn positive_names<'a>(&'a self, out: &mut Vec<&'a str>) {
+        match self {
+            Condition::Pattern(name) => out.push(name),
+            Condition::And(cs) | Condition::Or(cs) => cs.iter().for_each(|c| c.positive_names(out)),
+            Condition::Not(_) => {}
+        }
+    }
+}
+
+/// How constituents combine
+#[derive(Debug, Clone, PartialEq)]
+pub enum CompositeRule {
+    Boolean(Condition),
+    /// Sum of the weights of present patterns must reach `min_total`
+    Weighted { weights: BTreeMap<String, f64>, min_total: f64 },
+}
+
+/// A named composite pattern
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompositePattern {
+    pub name: String,
+    pub rule: CompositeRule,
+    /// Constituents must fall within this many bars (1 = the same bar)
+    pub within_bars: u32,
+}
+
+impl CompositePattern {
+    /// Parse `expression [within N bars]`
+    pub fn parse(name: &str, expression: &str) -> Result<Self> {
+        let (expr, within_bars) = split_window(expression)?;
+        let rule = if let Some((lhs, rhs)) = expr.split_once(">=") {
+            parse_weighted(lhs, rhs)?
+        } else {
+            let tokens = tokenize(expr)?;
+            let mut parser = Parser { tokens: &tokens, pos: 0 };
+            let condition = parser.or()?;
+            if let Some(extra) = tokens.get(parser.pos) {
+                bail!("unexpected '{}' in '{}'", extra, expr.trim());
+            }
+            CompositeRule::Boolean(condition)
+        };
+        Ok(Self { name: name.to_string(), rule, within_bars })
+    }
+
+    /// Patterns that contribute to a match
+    pub fn constituents(&self) -> Vec<&str> {
+        match &self.rule {
+            CompositeRule::Boolean(c) => {
+                let mut out = Vec::new();
+                c.positive_names(&mut out);
+                out.sort_unstable();
+                out.dedup();
+                out
+            }
+            CompositeRule::Weighted { weights, .. } => weights.keys().map(String::as_str).collect(),
+        }
+    }
+
+    fn matches(&self, present: &BTreeSet<&str>) -> bool {
+        match &self.rule {
+            CompositeRule::Boolean(c) => c.matches(present),
+            CompositeRule::Weighted { weights, min_total } => {
+                let total: f64 = weights.iter().filter(|(n, _)| present.contains(n.as_str())).map(|(_, w)| w).sum();
+                total >= *min_total
+            }
+        }
+    }
+}
+
+/// A composite as written in a definitions file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CompositeDefinition {
+    pub name: String,
+    /// Condition expression, e.g. `volume_spike AND vwap_deviation within 3 bars`
+    pub when: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default = "default_strength")]
+    pub strength: f64,
+    #[serde(default)]
+    pub polarity: f64,
+    #[serde(default = "default_action")]
+    pub action: String,
+    #[serde(default = "default_strength")]
+    pub confidence: f64,
+}
+
+fn default_strength() -> f64 {
+    0.6
+}
+
+fn default_action() -> String {
+    "hold".to_string()
+}
+
+impl CompositeDefinition {
+    pub fn compile(&self) -> Result<CompositePattern> {
+        CompositePattern::parse(&self.name, &self.when)
+    }
+
+    /// Library entry; always tagged `composite`
+    pub fn to_meta(&self) -> PatternMeta {
+        let mut tags = self.tags.clone();
+        if !tags.iter().any(|t| t == "composite") {
+            tags.push("composite".to_string());
+        }
+        PatternMeta {
+            name: self.name.clone(),
+            description: self.description.clone(),
+            taxonomy: PatternTaxonomy::classify(&tags, self.polarity),
+            tags,
+            strength: self.strength,
+            polarity: self.polarity,
+            action: self.action.clone(),
+            confidence: self.confidence,
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Observation {
+    pattern: String,
+    score: f64,
+    bar: u64,
+    seq: u64,
+}
+
+/// Recently seen patterns for one symbol
+#[derive(Debug, Clone, Default)]
+pub struct CompositeState {
+    bar: u64,
+    seq: u64,
+    recent: VecDeque<Observation>,
+    /// Observation sequence number at each composite's last firing
+    fired_at: HashMap<String, u64>,
+}
+
+impl CompositeState {
+    /// Advance to the next bar
+    pub fn on_bar(&mut self) {
+        self.bar += 1;
+        while self.recent.front().is_some_and(|o| self.bar - o.bar > MAX_WINDOW_BARS) {
+            self.recent.pop_front();
+        }
+    }
+
+    fn observe(&mut self, signal: &Signal) {
+        self.seq += 1;
+        self.recent.push_back(Observation {
+            pattern: base_name(&signal.pattern).to_string(),
+            score: signal.score,
+            bar: self.bar,
+            seq: self.seq,
+        });
+    }
+
+    /// Whether `condition` holds over its window, ignoring observations of `excluding`
+    pub fn holds(&self, condition: &CompositePattern, excluding: &str) -> bool {
+        let oldest_bar = self.bar.saturating_sub(condition.within_bars.saturating_sub(1) as u64);
+        let present: BTreeSet<&str> = self
+            .recent
+            .iter()
+            .filter(|o| o.bar >= oldest_bar)
+            .map(|o| o.pattern.as_str())
+            .filter(|p| *p != excluding)
+            .collect();
+        condition.matches(&present)
+    }
+
+    /// Record `emitted` and return a signal for every composite they complete
+    pub fn evaluate(&mut self, composites: &[CompositePattern], emitted: &[Signal]) -> Vec<Signal> {
+        let Some(last) = emitted.last() else {
+            return Vec::new();
+        };
+        emitted.iter().for_each(|s| self.observe(s));
+
+        let mut out = Vec::new();
+        for composite in composites {
+            let oldest_bar = self.bar.saturating_sub(composite.within_bars.saturating_sub(1) as u64);
+            let window: Vec<&Observation> = self.recent.iter().filter(|o| o.bar >= oldest_bar).collect();
+            let present: BTreeSet<&str> = window.iter().map(|o| o.pattern.as_str()).collect();
+            if !composite.matches(&present) {
+                continue;
+            }
+            // latest observation of each contributing constituent
+            let mut latest: BTreeMap<&str, &Observation> = BTreeMap::new();
+            for o in window.iter().filter(|o| composite.constituents().contains(&o.pattern.as_str())) {
+                latest.insert(&o.pattern, o);
+            }
+            let newest = latest.values().map(|o| o.seq).max().unwrap_or(0);
+            if newest <= self.fired_at.get(&composite.name).copied().unwrap_or(0) {
+                continue;
+            }
+            self.fired_at.insert(composite.name.clone(), newest);
+            let score = if latest.is_empty() {
+                0.0
+            } else {
+                latest.values().map(|o| o.score).sum::<f64>() / latest.len() as f64
+            };
+            let details = serde_json::json!({
+                "constituents": latest.keys().collect::<Vec<_>>(),
+                "within_bars": composite.within_bars,
+            });
+            out.push(Signal {
+                id: signal_id::next_id(),
+                symbol: last.symbol.clone(),
+                score: score.clamp(-1.0, 1.0),
+                pattern: composite.name.clone(),
+                timestamp: last.timestamp,
+                meta: last.meta.clone(),
+                pattern_meta: None,
+                status: None,
+                linked_id: None,
+                extra: HashMap::from([("composite".to_string(), details)]),
+            });
+        }
+        // composites of composites see this round's results next time
+        out.iter().for_each(|s| self.observe(s));
+        out
+    }
+}
+
+fn base_name(pattern: &str) -> &str {
+    pattern.split(':').next().unwrap_or(pattern)
+}
+
+/// Split a trailing `within N bar(s)` off an expression
+fn split_window(expression: &str) -> Result<(&str, u32)> {
+    let lower = expression.to_ascii_lowercase();
+    let Some(at) = lower.rfind(" within ") else {
+        return Ok((expression, 1));
+    };
+    let mut words = expression[at + 8..].split_whitespace();
+    let bars: u32 = words
+        .next()
+        .and_then(|n| n.parse().ok())
+        .filter(|n| *n >= 1)
+        .ok_or_else(|| anyhow!("'within' needs a positive bar count in '{}'", expression.trim()))?;
+    match (words.next().map(str::to_ascii_lowercase).as_deref(), words.next()) {
+        (Some("bar" | "bars"), None) => Ok((&expression[..at], bars)),
+        _ => bail!("expected 'within N bars' in '{}'", expression.trim()),
+    }
+}
+
+fn parse_weighted(lhs: &str, rhs: &str) -> Result<CompositeRule> {
+    let min_total: f64 = rhs.trim().parse().map_err(|_| anyhow!("invalid threshold '{}'", rhs.trim()))?;
+    let mut weights = BTreeMap::new();
+    for term in lhs.split('+').map(str::trim) {
+        let (weight, name) = match term.split_once('*') {
+            Some((w, n)) => (w.trim().parse::<f64>().map_err(|_| anyhow!("invalid weight in '{}'", term))?, n.trim()),
+            None => (1.0, term),
+        };
+        if !is_identifier(name) {
+            bail!("invalid pattern name '{}'", name);
+        }
+        *weights.entry(name.to_string()).or_insert(0.0) += weight;
+    }
+    Ok(CompositeRule::Weighted { weights, min_total })
+}
+
+fn is_identifier(s: &str) -> bool {
+    !s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn tokenize(expr: &str) -> Result<Vec<String>> {
+    let spaced = expr.replace('(', " ( ").replace(')', " ) ");
+    let tokens: Vec<String> = spaced.split_whitespace().map(str::to_string).collect();
+    if tokens.is_empty() {
+        bail!("empty composite expression");
+    }
+    Ok(tokens)
+}
+
+/// Recursive descent: or := and (OR and)*, and := unary (AND unary)*,
+/// unary := NOT unary | ( or ) | name
+struct Parser<'a> {
+    tokens: &'a [String],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        self.tokens.get(self.pos).is_some_and(|t| t.eq_ignore_ascii_case(keyword))
+    }
+
+    fn or(&mut self) -> Result<Condition> {
+        let mut terms = vec![self.and()?];
+        while self.peek_keyword("or") {
+            self.pos += 1;
+            terms.push(self.and()?);
+        }
+        Ok(if terms.len() == 1 { terms.remove(0) } else { Condition::Or(terms) })
+    }
+
+    fn and(&mut self) -> Result<Condition> {
+        let mut terms = vec![self.unary()?];
+        while self.peek_keyword("and") {
+            self.pos += 1;
+            terms.push(self.unary()?);
+        }
+        Ok(if terms.len() == 1 { terms.remove(0) } else { Condition::And(terms) })
+    }
+
+    fn unary(&mut self) -> Result<Condition> {
+        let token = self.tokens.get(self.pos).ok_or_else(|| anyhow!("expression ends early"))?;
+        self.pos += 1;
+        if token.eq_ignore_ascii_case("not") {
+            return Ok(Condition::Not(Box::new(self.unary()?)));
+        }
+        if token == "(" {
+            let inner = self.or()?;
+            if self.tokens.get(self.pos).map(String::as_str) != Some(")") {
+                bail!("missing ')'");
+            }
+            self.pos += 1;
+            return Ok(inner);
+        }
+        if ["and", "or", ")"].iter().any(|k| token.eq_ignore_ascii_case(k)) || !is_identifier(token) {
+            bail!("unexpected '{}'", token);
+        }
+        Ok(Condition::Pattern(token.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_boolean_and_weighted() {
+        let c = CompositePattern::parse("x", "volume_spike AND (vwap_deviation OR NOT ema_crossover) within 3 bars").unwrap();
+        assert_eq!(c.within_bars, 3);
+        assert_eq!(c.constituents(), vec!["volume_spike", "vwap_deviation"]);
+        let w = CompositePattern::parse("y", "0.6*volume_spike + 0.4 * vwap_deviation >= 0.5").unwrap();
+        assert_eq!(w.within_bars, 1);
+        assert!(matches!(w.rule, CompositeRule::Weighted { min_total, .. } if min_total == 0.5));
+
+        for bad in ["", "a AND", "a b", "(a OR b", "a within 0 bars", "a within 3 days", "x*a >= 1", "a-b"] {
+            assert!(CompositePattern::parse("z", bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_and_within_bars() {
+        let composites = vec![CompositePattern::parse("spike_dev", "volume_spike AND vwap_deviation within 3 bars").unwrap()];
+        let mut state = CompositeState::default();
+        assert!(state.evaluate(&composites, &[Signal::test("AAPL", "volume_spike", 0.4, 1.0)]).is_empty());
+        state.on_bar();
+        state.on_bar();
+        let out = state.evaluate(&composites, &[Signal::test("AAPL", "vwap_deviation:60s", 0.6, 2.0)]);
+        assert_eq!(out.len(), 1);
+        assert_eq!(out[0].pattern, "spike_dev");
+        assert!((out[0].score - 0.5).abs() < 1e-12);
+        assert_eq!(out[0].extra["composite"]["constituents"], serde_json::json!(["volume_spike", "vwap_deviation"]));
+
+        // nothing new: no repeat; a fresh constituent fires again
+        assert!(state.evaluate(&composites, &[Signal::test("AAPL", "ema_crossover", 0.2, 3.0)]).is_empty());
+        assert_eq!(state.evaluate(&composites, &[Signal::test("AAPL", "volume_spike", 0.2, 4.0)]).len(), 1);
+
+        // the spike has left the window
+        state.on_bar();
+        state.on_bar();
+        state.on_bar();
+        assert!(state.evaluate(&composites, &[Signal::test("AAPL", "vwap_deviation", 0.6, 5.0)]).is_empty());
+    }
+
+    #[test]
+    fn test_weighted_and_nested() {
+        let composites = vec![
+            CompositePattern::parse("momentum", "0.6*ema_crossover + 0.4*liquidity_burst + 0.4*volume_spike >= 0.9").unwrap(),
+            CompositePattern::parse("confirmed", "momentum AND double_top within 2 bars").unwrap(),
+        ];
+        let mut state = CompositeState::default();
+        assert!(state.evaluate(&composites, &[Signal::test("AAPL", "ema_crossover", 0.5, 1.0)]).is_empty());
+        let out = state.evaluate(&composites, &[Signal::test("AAPL", "volume_spike", 0.3, 2.0)]);
+        assert_eq!(out.iter().map(|s| s.pattern.as_str()).collect::<Vec<_>>(), ["momentum"]);
+        state.on_bar();
+        let out = state.evaluate(&composites, &[Signal::test("AAPL", "double_top:60s", -0.8, 3.0)]);
+        assert_eq!(out.iter().map(|s| s.pattern.as_str()).collect::<Vec<_>>(), ["confirmed"]);
+    }
+}
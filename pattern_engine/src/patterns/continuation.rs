@@ -0,0 +1,240 @@
+//! Continuation pattern detection: triangles, wedges and flags.
+//!
+//! The last four confirmed pivots define an upper trendline through the two
+//! swing highs and a lower trendline through the two swing lows. Converging
+//! lines form a triangle (opposite or flat slopes) or a wedge (same-direction
+//! slopes); a parallel channel sloping against a strong preceding move (the
+//! pole) forms a flag. A close outside either line is the breakout.
+
+use super::structure::{StructureMatch, Trendline};
+use super::zigzag::{Pivot, PivotKind};
+use std::collections::VecDeque;
+
+/// Thresholds for [`ContinuationDetector`]
+#[derive(Debug, Clone)]
+pub struct ContinuationConfig {
+    /// Relative change between two swing points still treated as flat
+    pub flat_tolerance: f64,
+    /// Minimum relative narrowing of the channel for triangles and wedges
+    pub min_contraction: f64,
+    /// Maximum relative change in channel width for flags
+    pub parallel_tolerance: f64,
+    /// Minimum pole height as a multiple of the flag channel width
+    pub min_pole_ratio: f64,
+}
+
+impl Default for ContinuationConfig {
+    fn default() -> Self {
+        Self {
+            flat_tolerance: 0.005,
+            min_contraction: 0.25,
+            parallel_tolerance: 0.2,
+            min_pole_ratio: 2.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Shape {
+    Triangle,
+    RisingWedge,
+    FallingWedge,
+    /// Flag after a move in the given direction (true = up)
+    Flag { bullish: bool, pole: f64 },
+}
+
+#[derive(Debug, Clone)]
+struct Channel {
+    shape: Shape,
+    upper: Trendline,
+    lower: Trendline,
+    pivots: Vec<Pivot>,
+    /// Channel width at its widest (first) pivot
+    height: f64,
+}
+
+impl Channel {
+    fn name(&self, up: bool) -> &'static str {
+        match self.shape {
+            Shape::Triangle => if up { "triangle_breakout_up" } else { "triangle_breakout_down" },
+            Shape::RisingWedge => if up { "rising_wedge_breakout_up" } else { "rising_wedge_breakout_down" },
+            Shape::FallingWedge => if up { "falling_wedge_breakout_up" } else { "falling_wedge_breakout_down" },
+            Shape::Flag { bullish: true, .. } => "bull_flag",
+            Shape::Flag { bullish: false, .. } => "bear_flag",
+        }
+    }
+}
+
+/// Triangle, wedge and flag detector
+#[derive(Debug, Clone, Default)]
+pub struct ContinuationDetector {
+    config: ContinuationConfig,
+    pending: Option<Channel>,
+}
+
+impl ContinuationDetector {
+    pub fn new(config: ContinuationConfig) -> Self {
+        Self { config, pending: None }
+    }
+
+    /// Re-evaluate after a new pivot was confirmed
+    pub fn on_pivot(&mut self, pivots: &VecDeque<Pivot>) {
+        let n = pivots.len();
+        if n < 4 {
+            return;
+        }
+        let p = [pivots[n - 4], pivots[n - 3], pivots[n - 2], pivots[n - 1]];
+        let (highs, lows): (Vec<Pivot>, Vec<Pivot>) = p.iter().partition(|x| x.kind == PivotKind::High);
+        if highs.len() != 2 || lows.len() != 2 {
+            return;
+        }
+        let cfg = &self.config;
+        let upper = Trendline::through(&highs[0], &highs[1]);
+        let lower = Trendline::through(&lows[0], &lows[1]);
+
+        let start = p[0].timestamp;
+        let end = p[3].timestamp;
+        let width_start = upper.at(start) - lower.at(start);
+        let width_end = upper.at(end) - lower.at(end);
+        if width_start <= 0.0 || width_end <= 0.0 {
+            return;
+        }
+
+        let direction = |a: &Pivot, b: &Pivot| {
+            let change = (b.price - a.price) / a.price;
+            if change.abs() <= cfg.flat_tolerance { 0 } else if change > 0.0 { 1 } else { -1 }
+        };
+        let (up_dir, low_dir) = (direction(&highs[0], &highs[1]), direction(&lows[0], &lows[1]));
+        let contraction = 1.0 - width_end / width_start;
+
+        let shape = if (width_end - width_start).abs() / width_start <= cfg.parallel_tolerance {
+            // Flag: parallel channel drifting against the pole
+            let pole = (n >= 5).then(|| pivots[n - 5]).and_then(|base| {
+                let pole = p[0].price - base.price;
+                (pole.abs() >= cfg.min_pole_ratio * width_start).then_some(pole)
+            });
+            match pole {
+                Some(pole) if pole > 0.0 && up_dir < 0 && low_dir < 0 => Some(Shape::Flag { bullish: true, pole }),
+                Some(pole) if pole < 0.0 && up_dir > 0 && low_dir > 0 => Some(Shape::Flag { bullish: false, pole }),
+                _ => None,
+            }
+        } else if contraction >= cfg.min_contraction {
+            match (up_dir, low_dir) {
+                (u, l) if u <= 0 && l >= 0 && (u, l) != (0, 0) => Some(Shape::Triangle),
+                (1, 1) => Some(Shape::RisingWedge),
+                (-1, -1) => Some(Shape::FallingWedge),
+                _ => None,
+            }
+        } else {
+            None
+        };
+
+        // A new candidate replaces any older one; otherwise keep waiting for the break
+        if let Some(shape) = shape {
+            self.pending = Some(Channel {
+                shape,
+                upper,
+                lower,
+                pivots: p.to_vec(),
+                height: width_start,
+            });
+        }
+    }
+
+    /// Check the pending channel against a closing price
+    pub fn on_close(&mut self, close: f64, timestamp: f64) -> Option<StructureMatch> {
+        let c = self.pending.as_ref()?;
+        let (upper, lower) = (c.upper.at(timestamp), c.lower.at(timestamp));
+        // Converging lines past their apex no longer describe a pattern
+        if upper <= lower {
+            self.pending = None;
+            return None;
+        }
+        let up = close > upper;
+        if !up && close >= lower {
+            return None;
+        }
+
+        let c = self.pending.take()?;
+        let (level, move_size) = match c.shape {
+            Shape::Flag { bullish, pole } => {
+                // A flag broken against its pole is a failed pattern
+                if bullish != up {
+                    return None;
+                }
+                (if up { upper } else { lower }, pole.abs())
+            }
+            _ => (if up { upper } else { lower }, c.height),
+        };
+        let (polarity, target) = if up { (1.0, level + move_size) } else { (-1.0, level - move_size) };
+        Some(StructureMatch {
+            name: c.name(up).to_string(),
+            polarity,
+            neckline: level,
+            target,
+            pivots: c.pivots,
+            timestamp,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::patterns::zigzag::ZigZag;
+
+    fn run(prices: &[f64]) -> Vec<StructureMatch> {
+        let mut zz = ZigZag::new(0.02, 16);
+        let mut det = ContinuationDetector::default();
+        let mut out = Vec::new();
+        for (i, p) in prices.iter().enumerate() {
+            if zz.update(*p, i as f64).is_some() {
+                det.on_pivot(zz.pivots());
+            }
+            out.extend(det.on_close(*p, i as f64));
+        }
+        out
+    }
+
+    #[test]
+    fn test_symmetrical_triangle_breakout_up() {
+        // Highs 110 -> 106, lows 90 -> 94, then a close through the upper line
+        let found = run(&[100.0, 110.0, 100.0, 90.0, 100.0, 106.0, 100.0, 94.0, 100.0, 106.0]);
+        assert_eq!(found.len(), 1);
+        let m = &found[0];
+        assert_eq!(m.name, "triangle_breakout_up");
+        assert!(m.polarity > 0.0);
+        // upper line falls 1 per bar: 110 at t=1 -> 102 at t=9
+        assert!((m.neckline - 102.0).abs() < 1e-9);
+        // channel is 22 wide at the first pivot (110 vs. 88 on the lower line)
+        assert!((m.target - 124.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_rising_wedge_breakdown() {
+        let found = run(&[100.0, 110.0, 100.0, 96.0, 104.0, 114.0, 108.0, 106.0, 109.0, 108.0]);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].name, "rising_wedge_breakout_down");
+        assert!(found[0].target < found[0].neckline);
+    }
+
+    #[test]
+    fn test_bull_flag() {
+        // Pole 80 -> 120, then a shallow down-sloping channel and a break higher
+        let found = run(&[
+            80.0, 90.0, 100.0, 110.0, 120.0, 116.0, 114.0, 117.0, 118.0, 114.0, 112.0, 115.0, 119.0,
+        ]);
+        assert_eq!(found.len(), 1);
+        let m = &found[0];
+        assert_eq!(m.name, "bull_flag");
+        assert!((m.target - (m.neckline + 40.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_flag_broken_against_pole_is_ignored() {
+        let found = run(&[
+            80.0, 90.0, 100.0, 110.0, 120.0, 116.0, 114.0, 117.0, 118.0, 114.0, 112.0, 108.0,
+        ]);
+        assert!(found.iter().all(|m| m.name != "bull_flag"));
+    }
+}
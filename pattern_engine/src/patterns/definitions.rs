@@ -0,0 +1,316 @@
+//! Pattern definitions loaded from YAML or JSON.
+//!
+//! A definitions file is either a list of entries or an object with a
+//! `patterns` list, optional per-pattern emission `gates`, optional
+//! `composites` built from other patterns and optional `anti_patterns` that
+//! veto or down-weight other signals. Every entry is
+//! validated before the library is built so a bad file fails startup with all
+//! problems listed at once.
+
+use super::antipattern::AntiPatternDefinition;
+use super::composite::CompositeDefinition;
+use super::taxonomy::PatternTaxonomy;
+use super::PatternMeta;
+use crate::config;
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+
+/// Built-in definitions used when no file is configured
+pub const BUILTIN_DEFINITIONS: &str = include_str!("../../config/patterns.yaml");
+
+/// Allowed values for `action`
+const ACTIONS: [&str; 3] = ["buy", "sell", "hold"];
+
+/// One pattern definition as written in the config file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PatternDefinition {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub strength: f64,
+    pub polarity: f64,
+    pub action: String,
+    pub confidence: f64,
+    /// Named numeric thresholds for the detector behind this pattern
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub thresholds: BTreeMap<String, f64>,
+    /// Definition version; inherits the file-level `version` when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+}
+
+impl PatternDefinition {
+    pub fn to_meta(&self) -> PatternMeta {
+        PatternMeta {
+            name: self.name.clone(),
+            description: self.description.clone(),
+            tags: self.tags.clone(),
+            taxonomy: PatternTaxonomy::classify(&self.tags, self.polarity),
+            strength: self.strength,
+            polarity: self.polarity,
+            action: self.action.clone(),
+            confidence: self.confidence,
+            version: self.version.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Per-pattern emission gate; unset fields fall back to the symbol's thresholds
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PatternGate {
+    /// Minimum |score| for the pattern to fire (number or percentage)
+    #[serde(default, deserialize_with = "config::deserialize_opt_fraction", skip_serializing_if = "Option::is_none")]
+    pub min_score: Option<f64>,
+    /// Minimum time between two signals of this pattern (seconds or duration string)
+    #[serde(default, alias = "cooldown", deserialize_with = "config::deserialize_opt_secs", skip_serializing_if = "Option::is_none")]
+    pub cooldown_secs: Option<f64>,
+}
+
+/// Everything a definitions file configures
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DefinitionSet {
+    pub patterns: Vec<PatternDefinition>,
+    /// Emission gates by pattern name; may name detector patterns that have no definition
+    pub gates: BTreeMap<String, PatternGate>,
+    /// Patterns combined from other patterns
+    pub composites: Vec<CompositeDefinition>,
+    /// Conditions that veto or down-weight other signals
+    pub anti_patterns: Vec<AntiPatternDefinition>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum DefinitionsFile {
+    List(Vec<PatternDefinition>),
+    Wrapped {
+        #[serde(default)]
+        version: Option<String>,
+        patterns: Vec<PatternDefinition>,
+        #[serde(default)]
+        gates: BTreeMap<String, PatternGate>,
+        #[serde(default)]
+        composites: Vec<CompositeDefinition>,
+        #[serde(default)]
+        anti_patterns: Vec<AntiPatternDefinition>,
+    },
+}
+
+/// Parse a definitions file from YAML (a superset of JSON) and validate it
+pub fn parse_definition_set(text: &str) -> Result<DefinitionSet> {
+    let set = match serde_yaml::from_str(text)? {
+        DefinitionsFile::List(patterns) => DefinitionSet { patterns, ..Default::default() },
+        DefinitionsFile::Wrapped { version, patterns, gates, composites, anti_patterns } => DefinitionSet {
+            patterns: patterns
+                .into_iter()
+                .map(|d| PatternDefinition { version: d.version.or_else(|| version.clone()), ..d })
+                .collect(),
+            gates,
+            composites,
+            anti_patterns,
+        },
+    };
+    validate(&set)?;
+    Ok(set)
+}
+
+/// Parse definitions from YAML (a superset of JSON) and validate them
+pub fn parse_definitions(text: &str) -> Result<Vec<PatternDefinition>> {
+    parse_definition_set(text).map(|set| set.patterns)
+}
+
+/// Read and validate a YAML or JSON definitions file
+pub fn load_definition_set(path: &Path) -> Result<DefinitionSet> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read pattern definitions {}", path.display()))?;
+    parse_definition_set(&text).with_context(|| format!("invalid pattern definitions in {}", path.display()))
+}
+
+/// Read and validate the pattern entries of a YAML or JSON definitions file
+pub fn load_definitions(path: &Path) -> Result<Vec<PatternDefinition>> {
+    load_definition_set(path).map(|set| set.patterns)
+}
+
+/// Check every definition, gate and composite, reporting all problems together
+pub fn validate(set: &DefinitionSet) -> Result<()> {
+    let defs = &set.patterns;
+    let mut errors = Vec::new();
+    for (name, gate) in &set.gates {
+        if let Some(v) = gate.min_score {
+            if let Err(e) = config::check_range("min_score", v, &(0.0..=1.0)) {
+                errors.push(format!("gate {}: {}", name, e));
+            }
+        }
+        if let Some(v) = gate.cooldown_secs {
+            if let Err(e) = config::check_range("cooldown_secs", v, &(0.0..=86_400.0)) {
+                errors.push(format!("gate {}: {}", name, e));
+            }
+        }
+    }
+    let mut seen = HashSet::new();
+    for (i, d) in defs.iter().enumerate() {
+        let label = if d.name.is_empty() { format!("entry {}", i) } else { d.name.clone() };
+        if d.name.trim().is_empty() {
+            errors.push(format!("{}: name must not be empty", label));
+        } else if d.name.contains(':') {
+            errors.push(format!("{}: name must not contain ':'", label));
+        } else if !seen.insert(d.name.as_str()) {
+            errors.push(format!("{}: duplicate name", label));
+        }
+        if !(0.0..=1.0).contains(&d.strength) {
+            errors.push(format!("{}: strength {} outside 0..1", label, d.strength));
+        }
+        if !(-1.0..=1.0).contains(&d.polarity) {
+            errors.push(format!("{}: polarity {} outside -1..1", label, d.polarity));
+        }
+        if !(0.0..=1.0).contains(&d.confidence) {
+            errors.push(format!("{}: confidence {} outside 0..1", label, d.confidence));
+        }
+        if !ACTIONS.contains(&d.action.as_str()) {
+            errors.push(format!("{}: action '{}' must be one of buy, sell, hold", label, d.action));
+        }
+        for (key, value) in &d.thresholds {
+            if !value.is_finite() {
+                errors.push(format!("{}: threshold {} is not a finite number", label, key));
+            }
+        }
+    }
+    for (i, c) in set.composites.iter().enumerate() {
+        let label = if c.name.is_empty() { format!("composite {}", i) } else { format!("composite {}", c.name) };
+        if c.name.trim().is_empty() {
+            errors.push(format!("{}: name must not be empty", label));
+        } else if c.name.contains(':') {
+            errors.push(format!("{}: name must not contain ':'", label));
+        } else if !seen.insert(c.name.as_str()) {
+            errors.push(format!("{}: duplicate name", label));
+        }
+        match c.compile() {
+            Ok(compiled) if compiled.constituents().contains(&c.name.as_str()) => {
+                errors.push(format!("{}: must not reference itself", label));
+            }
+            Ok(_) => {}
+            Err(e) => errors.push(format!("{}: {}", label, e)),
+        }
+        if !(0.0..=1.0).contains(&c.strength) || !(0.0..=1.0).contains(&c.confidence) {
+            errors.push(format!("{}: strength and confidence must be within 0..1", label));
+        }
+        if !(-1.0..=1.0).contains(&c.polarity) {
+            errors.push(format!("{}: polarity {} outside -1..1", label, c.polarity));
+        }
+        if !ACTIONS.contains(&c.action.as_str()) {
+            errors.push(format!("{}: action '{}' must be one of buy, sell, hold", label, c.action));
+        }
+    }
+    let mut anti_names = HashSet::new();
+    for (i, a) in set.anti_patterns.iter().enumerate() {
+        let label = if a.name.is_empty() { format!("anti-pattern {}", i) } else { format!("anti-pattern {}", a.name) };
+        if a.name.trim().is_empty() {
+            errors.push(format!("{}: name must not be empty", label));
+        } else if !anti_names.insert(a.name.as_str()) {
+            errors.push(format!("{}: duplicate name", label));
+        }
+        if let Err(e) = a.compile() {
+            errors.push(format!("{}: {}", label, e));
+        }
+    }
+    if !errors.is_empty() {
+        bail!("{} problem(s): {}", errors.len(), errors.join("; "));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_definitions_valid() {
+        let defs = parse_definitions(BUILTIN_DEFINITIONS).unwrap();
+        assert_eq!(defs.len(), 4);
+        assert_eq!(defs[0].thresholds["peak_tolerance"], 0.015);
+    }
+
+    #[test]
+    fn test_json_list() {
+        let defs = parse_definitions(
+            r#"[{"name": "flag", "tags": ["continuation"], "strength": 0.6, "polarity": 0.5, "action": "buy", "confidence": 0.7}]"#,
+        )
+        .unwrap();
+        assert_eq!(defs[0].to_meta().tags, vec!["continuation"]);
+    }
+
+    #[test]
+    fn test_validation_lists_all_errors() {
+        let err = parse_definitions(
+            "- {name: a, strength: 1.5, polarity: 0.0, action: buy, confidence: 0.5}\n\
+             - {name: a, strength: 0.5, polarity: 0.0, action: short, confidence: 0.5}\n",
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(err.starts_with("3 problem(s)"), "{}", err);
+        assert!(err.contains("strength 1.5"));
+        assert!(err.contains("duplicate name"));
+        assert!(err.contains("action 'short'"));
+    }
+
+    #[test]
+    fn test_gates() {
+        let set = parse_definition_set(BUILTIN_DEFINITIONS).unwrap();
+        assert_eq!(set.gates["volatility_breakout"].cooldown_secs, Some(60.0));
+
+        let set = parse_definition_set(
+            "patterns: []\ngates:\n  ema_crossover: {min_score: 25%, cooldown: 5s}\n  double_top: {cooldown_secs: 120}\n",
+        )
+        .unwrap();
+        assert_eq!(set.gates["ema_crossover"], PatternGate { min_score: Some(0.25), cooldown_secs: Some(5.0) });
+        assert_eq!(set.gates["double_top"].min_score, None);
+
+        let err = parse_definition_set("patterns: []\ngates:\n  x: {min_score: 2, cooldown: 2d}\n")
+            .unwrap_err()
+            .to_string();
+        assert!(err.starts_with("2 problem(s)"), "{}", err);
+        assert!(err.contains("gate x: cooldown_secs: 172800 is outside 0..=86400"), "{}", err);
+    }
+
+    #[test]
+    fn test_composites() {
+        let set = parse_definition_set(
+            "patterns: []\ncomposites:\n  - {name: spike_dev, when: volume_spike AND vwap_deviation within 3 bars, polarity: 0.5, action: buy}\n",
+        )
+        .unwrap();
+        let meta = set.composites[0].to_meta();
+        assert_eq!((meta.tags, meta.strength), (vec!["composite".to_string()], 0.6));
+        assert_eq!(set.composites[0].compile().unwrap().within_bars, 3);
+
+        let err = parse_definition_set(
+            "patterns: []\ncomposites:\n  - {name: a, when: a AND b}\n  - {name: a, when: b OR, action: short}\n",
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(err.starts_with("4 problem(s)"), "{}", err);
+        assert!(err.contains("composite a: must not reference itself"), "{}", err);
+    }
+
+    #[test]
+    fn test_anti_patterns() {
+        let set = parse_definition_set(
+            "patterns: []\nanti_patterns:\n  - name: into_resistance\n    when: double_top within 5 bars\n    targets: [type:breakout+bullish]\n    scale: 0.25\n",
+        )
+        .unwrap();
+        let anti = set.anti_patterns[0].compile().unwrap();
+        assert_eq!((anti.name(), anti.scale, anti.condition.within_bars), ("into_resistance", 0.25, 5));
+
+        let err = parse_definition_set(
+            "patterns: []\nanti_patterns:\n  - {name: a, when: double_top}\n  - {name: a, when: x, patterns: [y], scale: 2}\n",
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(err.starts_with("3 problem(s)"), "{}", err);
+        assert!(err.contains("anti-pattern a: needs at least one of targets or patterns"), "{}", err);
+        assert!(err.contains("scale 2 outside 0..1"), "{}", err);
+    }
+}
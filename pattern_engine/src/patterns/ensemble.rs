@@ -0,0 +1,147 @@
+//! Ensemble weighting of tick-level detector contributions.
+//!
+//! Detectors add raw contributions to a [`Candidate`]; the ensemble turns them
+//! into the final score. By default every component has weight 1, which is the
+//! plain sum. Configured weights rescale individual patterns, and an optional
+//! model maps the weighted component vector ([`ENSEMBLE_COMPONENTS`] order) to
+//! the score instead, falling back to the weighted sum if inference fails.
+
+use anyhow::{anyhow, Result};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Arc;
+
+use super::pipeline::Candidate;
+use crate::inference_backend::InferenceBackend;
+
+/// Model input order for the built-in tick-level components
+pub const ENSEMBLE_COMPONENTS: [&str; 7] = [
+    "ema_crossover",
+    "vwap_deviation",
+    "volume_spike",
+    "vwap_band_touch",
+    "vwap_band_revert",
+    "liquidity_burst",
+    "volatility_breakout",
+];
+
+/// Combines detector contributions into a score
+#[derive(Clone, Default)]
+pub struct Ensemble {
+    weights: BTreeMap<String, f64>,
+    model: Option<Arc<dyn InferenceBackend>>,
+}
+
+impl fmt::Debug for Ensemble {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Ensemble")
+            .field("weights", &self.weights)
+            .field("model", &self.model.is_some())
+            .finish()
+    }
+}
+
+impl Ensemble {
+    /// Weights from a `pattern=weight,...` spec; unlisted patterns weigh 1
+    pub fn from_spec(spec: &str) -> Result<Self> {
+        let mut weights = BTreeMap::new();
+        for item in spec.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            let (name, value) = item
+                .split_once('=')
+                .ok_or_else(|| anyhow!("invalid ensemble weight '{}': expected pattern=weight", item))?;
+            let weight: f64 = value
+                .trim()
+                .parse()
+                .map_err(|_| anyhow!("invalid ensemble weight for {}: {}", name.trim(), value.trim()))?;
+            if !weight.is_finite() || weight.abs() > 10.0 {
+                return Err(anyhow!("ensemble weight for {} must be within -10..=10", name.trim()));
+            }
+            weights.insert(name.trim().to_string(), weight);
+        }
+        Ok(Self { weights, model: None })
+    }
+
+    /// Score with `model` over the weighted component vector
+    pub fn with_model(mut self, model: impl InferenceBackend + 'static) -> Self {
+        self.model = Some(Arc::new(model));
+        self
+    }
+
+    pub fn weight(&self, pattern: &str) -> f64 {
+        self.weights.get(pattern).copied().unwrap_or(1.0)
+    }
+
+    pub fn weights(&self) -> &BTreeMap<String, f64> {
+        &self.weights
+    }
+
+    /// Final score (unclamped) and the weighted contribution of each component.
+    /// Score added to the candidate outside a named component keeps weight 1.
+    pub fn combine(&self, candidate: &Candidate) -> (f64, BTreeMap<String, f64>) {
+        let mut components = BTreeMap::new();
+        let mut unnamed = candidate.score;
+        for (name, raw) in &candidate.components {
+            unnamed -= raw;
+            *components.entry(name.clone()).or_insert(0.0) += raw * self.weight(name);
+        }
+        let weighted = components.values().sum::<f64>() + unnamed;
+        let score = match &self.model {
+            Some(model) if !components.is_empty() => {
+                let input: Vec<f64> = ENSEMBLE_COMPONENTS
+                    .iter()
+                    .map(|name| components.get(*name).copied().unwrap_or(0.0))
+                    .collect();
+                model.infer(&input).unwrap_or(weighted)
+            }
+            _ => weighted,
+        };
+        (score, components)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::onnx_client::OnnxClient;
+
+    fn candidate() -> Candidate {
+        let mut c = Candidate::default();
+        c.add("ema_crossover", 0.4);
+        c.contribute("vwap_deviation", 0.2);
+        c.add("volume_spike", 0.3);
+        c
+    }
+
+    #[test]
+    fn test_default_weights_sum_contributions() {
+        let (score, components) = Ensemble::default().combine(&candidate());
+        assert!((score - 0.9).abs() < 1e-12);
+        assert_eq!(components.len(), 3);
+        assert_eq!(components["vwap_deviation"], 0.2);
+    }
+
+    #[test]
+    fn test_configured_weights() {
+        let ensemble = Ensemble::from_spec("ema_crossover=2, volume_spike=0").unwrap();
+        let mut c = candidate();
+        // direct score from a custom detector keeps weight 1
+        c.score += 0.05;
+        let (score, components) = ensemble.combine(&c);
+        assert!((score - 1.05).abs() < 1e-12);
+        assert_eq!(components["ema_crossover"], 0.8);
+        assert_eq!(components["volume_spike"], 0.0);
+        assert!(Ensemble::from_spec("ema_crossover").is_err());
+        assert!(Ensemble::from_spec("ema_crossover=x").is_err());
+        assert!(Ensemble::from_spec("ema_crossover=50").is_err());
+    }
+
+    #[test]
+    fn test_model_scores_component_vector() {
+        let model = OnnxClient::new(std::path::Path::new("ensemble.onnx")).unwrap();
+        let ensemble = Ensemble::default().with_model(model);
+        let (score, _) = ensemble.combine(&candidate());
+        assert!((-1.0..=1.0).contains(&score));
+        // nothing fired: no model call
+        assert_eq!(ensemble.combine(&Candidate::default()).0, 0.0);
+    }
+}
@@ -0,0 +1,223 @@
+//! Exogenous per-symbol features for pattern inference.
+//!
+//! Providers supply values from outside the tick stream (news sentiment, an
+//! earnings flag, ...). [`ExogenousFeatures`] appends them, in provider order,
+//! to the feature vector handed to
+//! [`PatternLibrary::lookup_or_infer`](super::PatternLibrary::lookup_or_infer). Each provider declares fixed feature
+//! names so the vector layout stays stable; values it does not return are 0.
+//!
+//! Fetches are cached per symbol for a TTL and bounded by a timeout. A failed
+//! or slow fetch falls back to the last value (or zeros) so inference never
+//! waits on an outside service for long.
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use tracing::warn;
+
+/// Source of exogenous features for a symbol
+#[async_trait]
+pub trait ExogenousProvider: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// Names of the features this provider contributes, in vector order
+    fn feature_names(&self) -> &[String];
+
+    /// Current values by feature name; unknown names are ignored
+    async fn fetch(&self, symbol: &str) -> Result<BTreeMap<String, f64>>;
+}
+
+/// Per-symbol JSON file, re-read on every fetch:
+/// `{"AAPL": {"news_sentiment": 0.4, "earnings_flag": 1}}`
+pub struct FileProvider {
+    path: PathBuf,
+    names: Vec<String>,
+}
+
+impl FileProvider {
+    pub fn new(path: impl Into<PathBuf>, names: &[&str]) -> Self {
+        Self { path: path.into(), names: names.iter().map(|n| n.to_string()).collect() }
+    }
+}
+
+#[async_trait]
+impl ExogenousProvider for FileProvider {
+    fn name(&self) -> &str {
+        "file"
+    }
+
+    fn feature_names(&self) -> &[String] {
+        &self.names
+    }
+
+    async fn fetch(&self, symbol: &str) -> Result<BTreeMap<String, f64>> {
+        let json = tokio::fs::read_to_string(&self.path)
+            .await
+            .with_context(|| format!("failed to read exogenous features {}", self.path.display()))?;
+        let mut all: HashMap<String, BTreeMap<String, f64>> =
+            serde_json::from_str(&json).with_context(|| format!("invalid exogenous features in {}", self.path.display()))?;
+        Ok(all.remove(symbol).unwrap_or_default())
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Cached {
+    values: Vec<f64>,
+    fetched: Instant,
+}
+
+/// Registered providers with a per-symbol cache
+pub struct ExogenousFeatures {
+    providers: Vec<Arc<dyn ExogenousProvider>>,
+    ttl: Duration,
+    timeout: Duration,
+    /// (provider index, symbol) -> last values
+    cache: Mutex<HashMap<(usize, String), Cached>>,
+}
+
+impl Default for ExogenousFeatures {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(60), Duration::from_millis(250))
+    }
+}
+
+impl ExogenousFeatures {
+    pub fn new(ttl: Duration, timeout: Duration) -> Self {
+        Self { providers: Vec::new(), ttl, timeout, cache: Mutex::new(HashMap::new()) }
+    }
+
+    pub fn with_provider(mut self, provider: Arc<dyn ExogenousProvider>) -> Self {
+        self.providers.push(provider);
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.providers.is_empty()
+    }
+
+    /// Names of the appended features, in order
+    pub fn names(&self) -> Vec<String> {
+        self.providers.iter().flat_map(|p| p.feature_names().iter().cloned()).collect()
+    }
+
+    /// Current exogenous values for `symbol`, one per [`ExogenousFeatures::names`]
+    pub async fn features(&self, symbol: &str) -> Vec<f64> {
+        let mut out = Vec::new();
+        for (i, provider) in self.providers.iter().enumerate() {
+            out.extend(self.provider_values(i, provider.as_ref(), symbol).await);
+        }
+        out
+    }
+
+    /// Append the exogenous values for `symbol` to `features`
+    pub async fn append(&self, symbol: &str, features: &mut Vec<f64>) {
+        features.extend(self.features(symbol).await);
+    }
+
+    async fn provider_values(&self, index: usize, provider: &dyn ExogenousProvider, symbol: &str) -> Vec<f64> {
+        let key = (index, symbol.to_string());
+        let stale = {
+            let cache = self.cache.lock().await;
+            match cache.get(&key) {
+                Some(c) if c.fetched.elapsed() < self.ttl => return c.values.clone(),
+                other => other.map(|c| c.values.clone()),
+            }
+        };
+        let names = provider.feature_names();
+        let fetched = match tokio::time::timeout(self.timeout, provider.fetch(symbol)).await {
+            Ok(Ok(values)) => Some(names.iter().map(|n| values.get(n).copied().filter(|v| v.is_finite()).unwrap_or(0.0)).collect()),
+            Ok(Err(e)) => {
+                warn!("Exogenous provider {} failed for {}: {}", provider.name(), symbol, e);
+                None
+            }
+            Err(_) => {
+                warn!("Exogenous provider {} timed out for {}", provider.name(), symbol);
+                None
+            }
+        };
+        // Failures keep serving the last values until the next attempt
+        let values = fetched.or(stale).unwrap_or_else(|| vec![0.0; names.len()]);
+        self.cache.lock().await.insert(key, Cached { values: values.clone(), fetched: Instant::now() });
+        values
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::bail;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct Sentiment {
+        names: Vec<String>,
+        calls: AtomicUsize,
+        fail_after: usize,
+    }
+
+    #[async_trait]
+    impl ExogenousProvider for Sentiment {
+        fn name(&self) -> &str {
+            "sentiment"
+        }
+
+        fn feature_names(&self) -> &[String] {
+            &self.names
+        }
+
+        async fn fetch(&self, symbol: &str) -> Result<BTreeMap<String, f64>> {
+            let call = self.calls.fetch_add(1, Ordering::SeqCst);
+            if call >= self.fail_after {
+                bail!("feed down");
+            }
+            let score = if symbol == "AAPL" { 0.4 } else { -0.2 };
+            Ok(BTreeMap::from([("news_sentiment".to_string(), score), ("ignored".to_string(), 9.0)]))
+        }
+    }
+
+    fn sentiment(fail_after: usize) -> Arc<Sentiment> {
+        Arc::new(Sentiment {
+            names: vec!["news_sentiment".to_string(), "earnings_flag".to_string()],
+            calls: AtomicUsize::new(0),
+            fail_after,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_appends_in_declared_order_and_caches() {
+        let provider = sentiment(usize::MAX);
+        let exo = ExogenousFeatures::default().with_provider(provider.clone());
+        assert_eq!(exo.names(), vec!["news_sentiment", "earnings_flag"]);
+
+        let mut features = vec![1.0, 2.0];
+        exo.append("AAPL", &mut features).await;
+        assert_eq!(features, vec![1.0, 2.0, 0.4, 0.0]);
+        assert_eq!(exo.features("AAPL").await, vec![0.4, 0.0]);
+        assert_eq!(exo.features("TSLA").await, vec![-0.2, 0.0]);
+        assert_eq!(provider.calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_failures_fall_back_to_last_values() {
+        let provider = sentiment(1);
+        let exo = ExogenousFeatures::new(Duration::ZERO, Duration::from_secs(1)).with_provider(provider.clone());
+        assert_eq!(exo.features("AAPL").await, vec![0.4, 0.0]);
+        assert_eq!(exo.features("AAPL").await, vec![0.4, 0.0]);
+        assert_eq!(exo.features("MSFT").await, vec![0.0, 0.0]);
+        assert_eq!(provider.calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_file_provider() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("exo.json");
+        std::fs::write(&path, r#"{"AAPL": {"news_sentiment": 0.7, "earnings_flag": 1}}"#).unwrap();
+        let exo = ExogenousFeatures::default()
+            .with_provider(Arc::new(FileProvider::new(&path, &["earnings_flag", "news_sentiment"])));
+        assert_eq!(exo.features("AAPL").await, vec![1.0, 0.7]);
+        assert_eq!(exo.features("TSLA").await, vec![0.0, 0.0]);
+    }
+}
@@ -0,0 +1,217 @@
+//! JSON snapshot of a [`PatternLibrary`].
+//!
+//! The export holds every library entry as a full [`PatternMeta`] (keeping
+//! its provenance), plus detector thresholds, emission gates, composite and
+//! anti-pattern definitions and optionally the outcome statistics gathered for each
+//! pattern. Importing rebuilds an equivalent library around a local model, so
+//! a library tuned in one environment can be shipped to another or inspected
+//! from the Python tooling.
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use super::antipattern::AntiPatternDefinition;
+use super::composite::CompositeDefinition;
+use super::definitions::{self, DefinitionSet, PatternDefinition, PatternGate};
+use super::stats::{PatternOutcomes, PatternStats};
+use super::{PatternLibrary, PatternMeta, PatternSource};
+use crate::inference_backend::InferenceBackend;
+use crate::onnx_client::{OnnxClient, OnnxConfig};
+
+/// Version of the export layout; bumped on incompatible changes
+pub const EXPORT_FORMAT: u32 = 1;
+
+/// One library entry with its detector thresholds
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportedPattern {
+    #[serde(flatten)]
+    pub meta: PatternMeta,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub thresholds: BTreeMap<String, f64>,
+}
+
+/// Serializable snapshot of a library
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LibraryExport {
+    pub format: u32,
+    pub model_id: String,
+    /// Every entry sorted by name, composites included
+    pub patterns: Vec<ExportedPattern>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub gates: BTreeMap<String, PatternGate>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub composites: Vec<CompositeDefinition>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub anti_patterns: Vec<AntiPatternDefinition>,
+    /// Occurrence and forward-return counters by pattern name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub stats: BTreeMap<String, PatternOutcomes>,
+}
+
+impl LibraryExport {
+    /// Attach the counters gathered so far
+    pub fn with_stats(mut self, stats: &PatternStats) -> Self {
+        self.stats = stats.all().iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+        self
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self> {
+        let export: Self = serde_json::from_str(json).context("invalid pattern library export")?;
+        if export.format != EXPORT_FORMAT {
+            bail!("unsupported pattern library export format {} (expected {})", export.format, EXPORT_FORMAT);
+        }
+        Ok(export)
+    }
+
+    /// Read an export written by [`LibraryExport::to_json`]
+    pub fn load(path: &Path) -> Result<Self> {
+        let json = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read pattern library {}", path.display()))?;
+        Self::from_json(&json).with_context(|| format!("invalid pattern library in {}", path.display()))
+    }
+}
+
+impl PatternLibrary {
+    /// Snapshot of the library without statistics
+    pub fn export(&self) -> LibraryExport {
+        let mut patterns: Vec<ExportedPattern> = self
+            .known
+            .values()
+            .map(|meta| ExportedPattern {
+                meta: meta.clone(),
+                thresholds: self.thresholds.get(&meta.name).cloned().unwrap_or_default(),
+            })
+            .collect();
+        patterns.sort_by(|a, b| a.meta.name.cmp(&b.meta.name));
+        LibraryExport {
+            format: EXPORT_FORMAT,
+            model_id: self.model_id.clone(),
+            patterns,
+            gates: (*self.gates).clone(),
+            composites: self.composite_definitions.clone(),
+            anti_patterns: self.anti_pattern_definitions.clone(),
+            stats: BTreeMap::new(),
+        }
+    }
+
+    pub fn export_json(&self) -> Result<String> {
+        self.export().to_json()
+    }
+
+    /// Rebuild a library from an export around the model at `model_path`.
+    /// Entries keep their exported provenance; the export is validated like a
+    /// definitions file first.
+    pub fn from_export(model_path: &Path, export: &LibraryExport) -> Result<Self> {
+        Self::from_export_with(model_path, &OnnxConfig::default(), export)
+    }
+
+    /// [`PatternLibrary::from_export`] with the model loaded under `onnx`
+    pub fn from_export_with(model_path: &Path, onnx: &OnnxConfig, export: &LibraryExport) -> Result<Self> {
+        let client = OnnxClient::with_config(model_path, onnx)?;
+        Self::from_export_on(Box::new(client), export)
+    }
+
+    /// [`PatternLibrary::from_export`] scoring unknown patterns with `backend`
+    pub fn from_export_on(backend: Box<dyn InferenceBackend>, export: &LibraryExport) -> Result<Self> {
+        let composite_names: Vec<&str> = export.composites.iter().map(|c| c.name.as_str()).collect();
+        let set = DefinitionSet {
+            patterns: export
+                .patterns
+                .iter()
+                .filter(|p| !composite_names.contains(&p.meta.name.as_str()))
+                .map(|p| PatternDefinition {
+                    name: p.meta.name.clone(),
+                    description: p.meta.description.clone(),
+                    tags: p.meta.tags.clone(),
+                    strength: p.meta.strength,
+                    polarity: p.meta.polarity,
+                    action: p.meta.action.clone(),
+                    confidence: p.meta.confidence,
+                    thresholds: p.thresholds.clone(),
+                    version: p.meta.version.clone(),
+                })
+                .collect(),
+            gates: export.gates.clone(),
+            composites: export.composites.clone(),
+            anti_patterns: export.anti_patterns.clone(),
+        };
+        definitions::validate(&set)?;
+
+        let mut lib = PatternLibrary::from_definitions_on(backend, &export.model_id, set.patterns, PatternSource::Config)
+            .with_gates(set.gates)
+            .with_composites(&set.composites, PatternSource::Config)?
+            .with_anti_patterns(&set.anti_patterns)?;
+        for p in &export.patterns {
+            lib.known.insert(p.meta.name.clone(), p.meta.clone());
+        }
+        Ok(lib)
+    }
+
+    pub fn import_json(model_path: &Path, json: &str) -> Result<Self> {
+        Self::from_export(model_path, &LibraryExport::from_json(json)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn library() -> PatternLibrary {
+        PatternLibrary::new(Path::new("dummy.onnx")).unwrap().with_model_id("v3")
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let lib = library();
+        let json = lib.export_json().unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["format"], EXPORT_FORMAT);
+        assert!(value["patterns"].as_array().unwrap().iter().any(|p| p["name"] == "double_top" && p["source"] == "seeded"));
+
+        let imported = PatternLibrary::import_json(Path::new("other.onnx"), &json).unwrap();
+        assert_eq!(imported.known, lib.known);
+        assert_eq!(imported.thresholds, lib.thresholds);
+        assert_eq!(imported.gates, lib.gates);
+        assert_eq!(imported.composites(), lib.composites());
+        assert_eq!(imported.model_id(), "v3");
+        assert_eq!(imported.export(), lib.export());
+    }
+
+    #[test]
+    fn test_stats_and_provenance_survive() {
+        let mut stats = PatternStats::new(1.0, 10.0);
+        stats.on_price("AAPL", 100.0, 0.0);
+        stats.record("AAPL", "double_top", -0.8, 0.0);
+        stats.on_price("AAPL", 98.0, 2.0);
+
+        let mut lib = library();
+        let mut learned = lib.lookup_or_infer("mystery", Some(&[0.2, 0.1])).unwrap();
+        learned.source = PatternSource::Ml;
+        lib.known.insert(learned.name.clone(), learned.clone());
+
+        let json = lib.export().with_stats(&stats).to_json().unwrap();
+        let export = LibraryExport::from_json(&json).unwrap();
+        assert_eq!(export.stats["double_top"].horizons[&1].hits, 1);
+        let imported = PatternLibrary::from_export(Path::new("dummy.onnx"), &export).unwrap();
+        assert_eq!(imported.lookup_or_infer("mystery", None).unwrap().source, PatternSource::Ml);
+    }
+
+    #[test]
+    fn test_rejects_bad_exports() {
+        let mut export = library().export();
+        export.format = EXPORT_FORMAT + 1;
+        let json = export.to_json().unwrap();
+        assert!(PatternLibrary::import_json(Path::new("dummy.onnx"), &json).is_err());
+
+        let mut export = library().export();
+        export.patterns[0].meta.polarity = 3.0;
+        assert!(PatternLibrary::from_export(Path::new("dummy.onnx"), &export).is_err());
+        assert!(PatternLibrary::import_json(Path::new("dummy.onnx"), "{}").is_err());
+    }
+}
@@ -0,0 +1,154 @@
+//! Price gap detection on closed candles.
+//!
+//! A session gap is an open away from the previous session's close; an
+//! intrabar gap is a bar that trades entirely above the previous bar's high or
+//! below its low. Either is reported as a gap-fill candidate: the move back to
+//! the level that closes the gap (previous close or previous bar extreme).
+
+use super::candlestick::Candle;
+use serde::Serialize;
+
+/// Thresholds for [`GapDetector`]
+#[derive(Debug, Clone)]
+pub struct GapConfig {
+    /// Session length in seconds (86400 = daily, UTC), as for session VWAP
+    pub session_secs: f64,
+    /// Session start offset from each bucket boundary
+    pub session_offset_secs: f64,
+    /// Minimum open-vs-close gap between sessions, relative to the close
+    pub min_session_gap: f64,
+    /// Minimum gap between consecutive bars of a session, relative to the previous close
+    pub min_intrabar_gap: f64,
+}
+
+impl Default for GapConfig {
+    fn default() -> Self {
+        Self {
+            session_secs: 86_400.0,
+            session_offset_secs: 0.0,
+            min_session_gap: 0.002,
+            min_intrabar_gap: 0.001,
+        }
+    }
+}
+
+/// Where the gap opened
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GapKind {
+    Session,
+    Intrabar,
+}
+
+/// A gap reported by [`GapDetector`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct GapEvent {
+    pub kind: GapKind,
+    /// True for a gap up
+    pub up: bool,
+    /// Level that fills the gap
+    pub fill_level: f64,
+    /// Edge of the gap on the new bar's side (its open or extreme)
+    pub gap_edge: f64,
+    pub timestamp: f64,
+}
+
+impl GapEvent {
+    /// Absolute gap size
+    pub fn size(&self) -> f64 {
+        (self.gap_edge - self.fill_level).abs()
+    }
+
+    /// Gap size relative to the fill level
+    pub fn size_pct(&self) -> f64 {
+        if self.fill_level != 0.0 { self.size() / self.fill_level.abs() } else { 0.0 }
+    }
+
+    /// Direction of the expected fill: gaps up fill down and vice versa
+    pub fn polarity(&self) -> f64 {
+        if self.up { -1.0 } else { 1.0 }
+    }
+}
+
+/// Incremental gap detector over closed candles of one interval
+#[derive(Debug, Clone, Default)]
+pub struct GapDetector {
+    config: GapConfig,
+    prev: Option<Candle>,
+}
+
+impl GapDetector {
+    pub fn new(config: GapConfig) -> Self {
+        Self { config, prev: None }
+    }
+
+    fn session(&self, start: u64) -> i64 {
+        ((start as f64 - self.config.session_offset_secs) / self.config.session_secs).floor() as i64
+    }
+
+    /// Feed one closed candle; returns the gap it opened, if any
+    pub fn update(&mut self, candle: &Candle) -> Option<GapEvent> {
+        let prev = self.prev.replace(candle.clone())?;
+        let timestamp = candle.start as f64;
+        if prev.close <= 0.0 {
+            return None;
+        }
+        if self.session(candle.start) != self.session(prev.start) {
+            let gap = (candle.open - prev.close) / prev.close;
+            return (gap.abs() >= self.config.min_session_gap).then_some(GapEvent {
+                kind: GapKind::Session,
+                up: gap > 0.0,
+                fill_level: prev.close,
+                gap_edge: candle.open,
+                timestamp,
+            });
+        }
+        let min_size = self.config.min_intrabar_gap * prev.close;
+        let (up, fill_level, gap_edge) = if candle.low - prev.high >= min_size {
+            (true, prev.high, candle.low)
+        } else if prev.low - candle.high >= min_size {
+            (false, prev.low, candle.high)
+        } else {
+            return None;
+        };
+        Some(GapEvent { kind: GapKind::Intrabar, up, fill_level, gap_edge, timestamp })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bar(start: u64, open: f64, high: f64, low: f64, close: f64) -> Candle {
+        Candle { start, open, high, low, close, volume: 10.0 }
+    }
+
+    #[test]
+    fn test_session_gap_up() {
+        let mut det = GapDetector::default();
+        assert!(det.update(&bar(86_340, 100.0, 101.0, 99.5, 100.0)).is_none());
+        // next day opens 1% higher; the bar overlaps the previous one so only the session gap counts
+        let gap = det.update(&bar(86_400, 101.0, 101.5, 100.5, 101.2)).unwrap();
+        assert_eq!((gap.kind, gap.up, gap.fill_level, gap.gap_edge), (GapKind::Session, true, 100.0, 101.0));
+        assert!((gap.size_pct() - 0.01).abs() < 1e-12);
+        assert_eq!(gap.polarity(), -1.0);
+    }
+
+    #[test]
+    fn test_small_session_gap_ignored() {
+        let mut det = GapDetector::default();
+        det.update(&bar(86_340, 100.0, 101.0, 99.5, 100.0));
+        assert!(det.update(&bar(86_400, 100.1, 100.5, 99.0, 100.2)).is_none());
+    }
+
+    #[test]
+    fn test_intrabar_gap_down() {
+        let mut det = GapDetector::default();
+        det.update(&bar(0, 100.0, 100.5, 99.8, 100.0));
+        assert!(det.update(&bar(60, 100.0, 100.2, 99.9, 100.1)).is_none());
+        let gap = det.update(&bar(120, 99.5, 99.6, 99.0, 99.2)).unwrap();
+        assert_eq!((gap.kind, gap.up, gap.fill_level, gap.gap_edge), (GapKind::Intrabar, false, 99.9, 99.6));
+        assert!((gap.size() - 0.3).abs() < 1e-9);
+        assert_eq!(gap.polarity(), 1.0);
+    }
+}
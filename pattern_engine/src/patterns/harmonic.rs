@@ -0,0 +1,170 @@
+//! Harmonic pattern recognition (Gartley, Bat, Butterfly).
+//!
+//! Checks the Fibonacci ratio relationships between the last five alternating
+//! pivots X, A, B, C, D. A match is reported as soon as D is confirmed, with the
+//! potential reversal zone (PRZ) implied by the XA retracement.
+
+use super::zigzag::{Pivot, PivotKind};
+use super::PriceZone;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// Ratio ranges defining one harmonic pattern
+#[derive(Debug, Clone, Copy)]
+pub struct HarmonicSpec {
+    pub name: &'static str,
+    /// B retracement of XA
+    pub ab: (f64, f64),
+    /// C retracement of AB
+    pub bc: (f64, f64),
+    /// CD extension of BC
+    pub cd: (f64, f64),
+    /// D retracement (or extension) of XA
+    pub ad: (f64, f64),
+}
+
+/// Gartley, Bat and Butterfly ratio definitions
+pub const HARMONIC_SPECS: [HarmonicSpec; 3] = [
+    HarmonicSpec { name: "gartley", ab: (0.618, 0.618), bc: (0.382, 0.886), cd: (1.272, 1.618), ad: (0.786, 0.786) },
+    HarmonicSpec { name: "bat", ab: (0.382, 0.5), bc: (0.382, 0.886), cd: (1.618, 2.618), ad: (0.886, 0.886) },
+    HarmonicSpec { name: "butterfly", ab: (0.786, 0.786), bc: (0.382, 0.886), cd: (1.618, 2.24), ad: (1.27, 1.618) },
+];
+
+/// A detected harmonic pattern
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HarmonicMatch {
+    /// e.g. `bullish_gartley`
+    pub name: String,
+    /// polarity -1..1 indicating bearish (-1) to bullish (+1)
+    pub polarity: f64,
+    /// X, A, B, C, D pivots
+    pub pivots: Vec<Pivot>,
+    /// Potential reversal zone around D
+    pub reversal_zone: PriceZone,
+    /// First target: 61.8% retracement of AD
+    pub target: f64,
+    pub timestamp: f64,
+}
+
+/// Harmonic XABCD detector
+#[derive(Debug, Clone)]
+pub struct HarmonicDetector {
+    /// Relative tolerance applied to every ratio bound
+    tolerance: f64,
+    last_d_index: Option<u64>,
+}
+
+impl Default for HarmonicDetector {
+    fn default() -> Self {
+        Self::new(0.05)
+    }
+}
+
+impl HarmonicDetector {
+    pub fn new(tolerance: f64) -> Self {
+        Self {
+            tolerance,
+            last_d_index: None,
+        }
+    }
+
+    /// Evaluate the last five pivots after a new pivot was confirmed
+    pub fn on_pivot(&mut self, pivots: &VecDeque<Pivot>) -> Option<HarmonicMatch> {
+        let n = pivots.len();
+        if n < 5 {
+            return None;
+        }
+        let p = [pivots[n - 5], pivots[n - 4], pivots[n - 3], pivots[n - 2], pivots[n - 1]];
+        let [x, a, b, c, d] = p;
+        // each D is evaluated only once
+        if self.last_d_index == Some(d.index) {
+            return None;
+        }
+        self.last_d_index = Some(d.index);
+
+        let xa = (a.price - x.price).abs();
+        let ab = (b.price - a.price).abs();
+        let bc = (c.price - b.price).abs();
+        if xa <= 0.0 || ab <= 0.0 || bc <= 0.0 {
+            return None;
+        }
+        let r_ab = ab / xa;
+        let r_bc = bc / ab;
+        let r_cd = (d.price - c.price).abs() / bc;
+        let r_ad = (d.price - a.price).abs() / xa;
+
+        let spec = HARMONIC_SPECS.iter().find(|s| {
+            self.within(r_ab, s.ab) && self.within(r_bc, s.bc) && self.within(r_cd, s.cd) && self.within(r_ad, s.ad)
+        })?;
+
+        // D is a low for bullish patterns (buy the reversal), a high for bearish
+        let bullish = d.kind == PivotKind::Low;
+        let dir = if bullish { -1.0 } else { 1.0 };
+        let prz_a = a.price + dir * xa * spec.ad.0 * (1.0 - self.tolerance);
+        let prz_b = a.price + dir * xa * spec.ad.1 * (1.0 + self.tolerance);
+        let reversal_zone = PriceZone {
+            low: prz_a.min(prz_b),
+            high: prz_a.max(prz_b),
+        };
+        let target = d.price + 0.618 * (a.price - d.price);
+
+        Some(HarmonicMatch {
+            name: format!("{}_{}", if bullish { "bullish" } else { "bearish" }, spec.name),
+            polarity: if bullish { 1.0 } else { -1.0 },
+            pivots: p.to_vec(),
+            reversal_zone,
+            target,
+            timestamp: d.timestamp,
+        })
+    }
+
+    fn within(&self, r: f64, (lo, hi): (f64, f64)) -> bool {
+        r >= lo * (1.0 - self.tolerance) && r <= hi * (1.0 + self.tolerance)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pivots(points: &[(PivotKind, f64)]) -> VecDeque<Pivot> {
+        points
+            .iter()
+            .enumerate()
+            .map(|(i, (kind, price))| Pivot { kind: *kind, price: *price, timestamp: i as f64, index: i as u64 })
+            .collect()
+    }
+
+    #[test]
+    fn test_bullish_gartley() {
+        use PivotKind::*;
+        // XA = 100, B at 61.8%, C at 61.8% of AB, D at 78.6% of XA
+        let pv = pivots(&[(Low, 100.0), (High, 200.0), (Low, 138.2), (High, 176.4), (Low, 121.4)]);
+        let mut det = HarmonicDetector::default();
+        let m = det.on_pivot(&pv).unwrap();
+        assert_eq!(m.name, "bullish_gartley");
+        assert!(m.polarity > 0.0);
+        assert!(m.reversal_zone.contains(121.4));
+        assert!(m.target > 121.4);
+
+        // same D is not reported twice
+        assert!(det.on_pivot(&pv).is_none());
+    }
+
+    #[test]
+    fn test_bearish_bat() {
+        use PivotKind::*;
+        // XA = 100 down, B at 45%, C at 66.7% of AB, D at 88.6% of XA
+        let pv = pivots(&[(High, 200.0), (Low, 100.0), (High, 145.0), (Low, 115.0), (High, 188.6)]);
+        let m = HarmonicDetector::default().on_pivot(&pv).unwrap();
+        assert_eq!(m.name, "bearish_bat");
+        assert!(m.reversal_zone.contains(188.6));
+    }
+
+    #[test]
+    fn test_non_harmonic_rejected() {
+        use PivotKind::*;
+        let pv = pivots(&[(Low, 100.0), (High, 200.0), (Low, 190.0), (High, 195.0), (Low, 150.0)]);
+        assert!(HarmonicDetector::default().on_pivot(&pv).is_none());
+    }
+}
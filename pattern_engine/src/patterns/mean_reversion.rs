@@ -0,0 +1,175 @@
+//! Mean-reversion entries and exits on a rolling z-score.
+//!
+//! Unlike the composite tick detectors, which mostly follow the trend, this
+//! detector fades stretched prices: price more than `entry_z` standard
+//! deviations from its rolling mean opens a position against the move, and a
+//! return inside `exit_z` closes it. The rolling statistics exclude the
+//! current trade so a single spike is measured against the prior window.
+
+use std::collections::VecDeque;
+
+/// Bands for [`MeanReversionDetector`]
+#[derive(Debug, Clone)]
+pub struct MeanReversionConfig {
+    /// Trades in the rolling mean / standard deviation
+    pub window: usize,
+    /// |z| at or beyond which a position is opened
+    pub entry_z: f64,
+    /// |z| at or inside which an open position is closed
+    pub exit_z: f64,
+}
+
+impl Default for MeanReversionConfig {
+    fn default() -> Self {
+        Self { window: 50, entry_z: 2.0, exit_z: 0.5 }
+    }
+}
+
+/// An entry or exit reported by [`MeanReversionDetector`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MeanReversionEvent {
+    /// True when opening a position, false when closing one
+    pub entry: bool,
+    /// Side of the position: 1 long (price stretched below the mean), -1 short
+    pub side: f64,
+    pub z: f64,
+    pub mean: f64,
+    pub std: f64,
+}
+
+impl MeanReversionEvent {
+    /// Signal score: entries scale with the stretch (0.5 at the entry band,
+    /// 1 at twice it); exits are a weak move against the position
+    pub fn score(&self, entry_z: f64) -> f64 {
+        if self.entry {
+            (self.side * self.z.abs() / (2.0 * entry_z)).clamp(-1.0, 1.0)
+        } else {
+            -0.3 * self.side
+        }
+    }
+}
+
+/// Rolling z-score band state machine for one symbol
+#[derive(Debug, Clone, Default)]
+pub struct MeanReversionDetector {
+    config: MeanReversionConfig,
+    prices: VecDeque<f64>,
+    sum: f64,
+    sum_sq: f64,
+    /// Side of the open position, if any
+    position: Option<f64>,
+}
+
+impl MeanReversionDetector {
+    pub fn new(config: MeanReversionConfig) -> Self {
+        Self { config, ..Default::default() }
+    }
+
+    pub fn config(&self) -> &MeanReversionConfig {
+        &self.config
+    }
+
+    /// Side of the open position (1 long, -1 short), if any
+    pub fn position(&self) -> Option<f64> {
+        self.position
+    }
+
+    /// Feed one trade price; returns an entry or exit it triggers
+    pub fn update(&mut self, price: f64) -> Option<MeanReversionEvent> {
+        let event = self.evaluate(price);
+        self.prices.push_back(price);
+        self.sum += price;
+        self.sum_sq += price * price;
+        if self.prices.len() > self.config.window {
+            let old = self.prices.pop_front().unwrap_or_default();
+            self.sum -= old;
+            self.sum_sq -= old * old;
+        }
+        event
+    }
+
+    fn evaluate(&mut self, price: f64) -> Option<MeanReversionEvent> {
+        if self.prices.len() < self.config.window {
+            return None;
+        }
+        let n = self.prices.len() as f64;
+        let mean = self.sum / n;
+        let std = (self.sum_sq / n - mean * mean).max(0.0).sqrt();
+        if std <= 0.0 {
+            return None;
+        }
+        let z = (price - mean) / std;
+        match self.position {
+            None if z.abs() >= self.config.entry_z => {
+                let side = -z.signum();
+                self.position = Some(side);
+                Some(MeanReversionEvent { entry: true, side, z, mean, std })
+            }
+            Some(side) if z.abs() <= self.config.exit_z || z.signum() == side => {
+                // back near the mean, or overshot through it
+                self.position = None;
+                Some(MeanReversionEvent { entry: false, side, z, mean, std })
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detector() -> MeanReversionDetector {
+        let mut det = MeanReversionDetector::new(MeanReversionConfig { window: 10, entry_z: 2.0, exit_z: 0.5 });
+        // mean 100, std 1
+        for i in 0..10 {
+            assert!(det.update(if i % 2 == 0 { 99.0 } else { 101.0 }).is_none());
+        }
+        det
+    }
+
+    #[test]
+    fn test_short_entry_and_exit() {
+        let mut det = detector();
+        assert!(det.update(101.5).is_none());
+        let entry = det.update(104.0).unwrap();
+        assert!(entry.entry);
+        assert_eq!(entry.side, -1.0);
+        assert!(entry.z >= 2.0);
+        assert!(entry.score(2.0) <= -0.5);
+        assert_eq!(det.position(), Some(-1.0));
+
+        // still stretched: holds
+        assert!(det.update(103.0).is_none());
+        let mut exit = None;
+        for _ in 0..5 {
+            exit = exit.or(det.update(100.0));
+        }
+        let exit = exit.unwrap();
+        assert!(!exit.entry);
+        assert_eq!(exit.side, -1.0);
+        assert_eq!(exit.score(2.0), 0.3);
+        assert_eq!(det.position(), None);
+    }
+
+    #[test]
+    fn test_long_entry_needs_warm_window() {
+        let mut cold = MeanReversionDetector::default();
+        assert!(cold.update(50.0).is_none());
+
+        let mut det = detector();
+        let entry = det.update(96.0).unwrap();
+        assert_eq!((entry.entry, entry.side), (true, 1.0));
+        assert!((entry.z + 4.0).abs() < 1e-9);
+        assert_eq!(entry.score(2.0), 1.0);
+    }
+
+    #[test]
+    fn test_overshoot_through_mean_exits() {
+        let mut det = detector();
+        det.update(96.0).unwrap();
+        let exit = det.update(103.0).unwrap();
+        assert!(!exit.entry);
+        assert_eq!(exit.side, 1.0);
+    }
+}
@@ -0,0 +1,352 @@
+//! Order-flow patterns from aggressor-side trades and top of book.
+//!
+//! - **Liquidity sweep**: a run of same-side aggressor trades that walks
+//!   through at least `sweep_levels` prices within `sweep_secs`, with total
+//!   size well above the typical trade.
+//! - **Absorption**: heavy one-sided aggression over `absorption_secs` that
+//!   fails to move price; the passive side is soaking it up, so the signal
+//!   leans against the aggressor.
+//! - **Iceberg refill**: trades at the best bid or ask keep exceeding the
+//!   displayed size while the level stays in the book, i.e. hidden size keeps
+//!   refilling it. Needs [`BookTop`] on the trades.
+//!
+//! Sizes are measured against an EMA of trade size; nothing fires before
+//! `warmup_trades` trades. Events found during warm-up are held back for
+//! [`OrderFlowDetector::take_cold`] so they can be reported as suppressed.
+
+use std::collections::VecDeque;
+
+use crate::incremental::EMA;
+use crate::publisher::{BookTop, TradeSide};
+
+/// Thresholds for [`OrderFlowDetector`]
+#[derive(Debug, Clone)]
+pub struct OrderFlowConfig {
+    pub warmup_trades: usize,
+    pub sweep_secs: f64,
+    /// Distinct prices a sweep must trade through
+    pub sweep_levels: usize,
+    /// Sweep size in multiples of the average trade
+    pub sweep_size: f64,
+    pub absorption_secs: f64,
+    /// Net aggressor volume in multiples of the average trade
+    pub absorption_size: f64,
+    /// Largest price move (fraction) in the aggressor's favour
+    pub absorption_max_move: f64,
+    /// Volume traded at one level in multiples of its largest displayed size
+    pub iceberg_refills: f64,
+}
+
+impl Default for OrderFlowConfig {
+    fn default() -> Self {
+        Self {
+            warmup_trades: 20,
+            sweep_secs: 1.0,
+            sweep_levels: 3,
+            sweep_size: 5.0,
+            absorption_secs: 5.0,
+            absorption_size: 10.0,
+            absorption_max_move: 0.0005,
+            iceberg_refills: 3.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderFlowKind {
+    Sweep,
+    Absorption,
+    Iceberg,
+}
+
+impl OrderFlowKind {
+    pub fn pattern(&self) -> &'static str {
+        match self {
+            OrderFlowKind::Sweep => "liquidity_sweep",
+            OrderFlowKind::Absorption => "absorption",
+            OrderFlowKind::Iceberg => "iceberg",
+        }
+    }
+}
+
+/// A detected order-flow pattern
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderFlowEvent {
+    pub kind: OrderFlowKind,
+    /// Aggressor side of the trades involved
+    pub aggressor: TradeSide,
+    /// Volume involved
+    pub size: f64,
+    /// `size` in multiples of the average trade
+    pub relative_size: f64,
+    /// Last trade price
+    pub price: f64,
+    /// Sweeps: prices traded through; absorption: price move (fraction);
+    /// icebergs: largest displayed size at the level
+    pub detail: f64,
+}
+
+impl OrderFlowEvent {
+    /// Signed score: sweeps follow the aggressor, absorption and icebergs fade it
+    pub fn score(&self) -> f64 {
+        let with_aggressor = match self.aggressor {
+            TradeSide::Buy => 1.0,
+            TradeSide::Sell => -1.0,
+        };
+        match self.kind {
+            OrderFlowKind::Sweep => 0.6 * with_aggressor,
+            OrderFlowKind::Absorption | OrderFlowKind::Iceberg => -0.5 * with_aggressor,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Trade {
+    price: f64,
+    size: f64,
+    side: TradeSide,
+    timestamp: f64,
+}
+
+/// Hidden-size tracking at one side of the book
+#[derive(Debug, Clone, Copy)]
+struct Level {
+    /// Side that trades into the level (sell aggressors hit the bid)
+    aggressor: TradeSide,
+    price: f64,
+    traded: f64,
+    max_displayed: f64,
+    fired: bool,
+}
+
+/// Per-symbol order-flow state
+#[derive(Debug, Clone)]
+pub struct OrderFlowDetector {
+    config: OrderFlowConfig,
+    avg_size: EMA,
+    trades_seen: usize,
+    /// Current same-side run, for sweeps
+    run: Vec<Trade>,
+    run_fired: bool,
+    /// Trades within the absorption window
+    window: VecDeque<Trade>,
+    level: Option<Level>,
+    /// Events found before warm-up finished, since the last `take_cold`
+    cold: Vec<OrderFlowEvent>,
+}
+
+impl Default for OrderFlowDetector {
+    fn default() -> Self {
+        Self::new(OrderFlowConfig::default())
+    }
+}
+
+impl OrderFlowDetector {
+    pub fn new(config: OrderFlowConfig) -> Self {
+        Self {
+            config,
+            avg_size: EMA::new(0.05),
+            trades_seen: 0,
+            run: Vec::new(),
+            run_fired: false,
+            window: VecDeque::new(),
+            level: None,
+            cold: Vec::new(),
+        }
+    }
+
+    /// Feed a trade; trades without an aggressor side are ignored
+    pub fn update(&mut self, price: f64, size: f64, side: Option<TradeSide>, book: Option<BookTop>, timestamp: f64) -> Vec<OrderFlowEvent> {
+        let Some(side) = side.filter(|_| size > 0.0 && price > 0.0) else {
+            return Vec::new();
+        };
+        let trade = Trade { price, size, side, timestamp };
+        let avg = self.avg_size.value().unwrap_or(size);
+        let warm = self.trades_seen >= self.config.warmup_trades;
+
+        let mut events = Vec::new();
+        events.extend(self.sweep(trade, avg));
+        events.extend(self.absorption(trade, avg));
+        events.extend(self.iceberg(trade, avg, book));
+
+        self.avg_size.update(size);
+        self.trades_seen += 1;
+        if warm {
+            events
+        } else {
+            self.cold.extend(events);
+            Vec::new()
+        }
+    }
+
+    /// Drain the events held back during warm-up
+    pub fn take_cold(&mut self) -> Vec<OrderFlowEvent> {
+        std::mem::take(&mut self.cold)
+    }
+
+    fn sweep(&mut self, trade: Trade, avg: f64) -> Option<OrderFlowEvent> {
+        let continues = self.run.last().is_some_and(|last| {
+            let moving = match trade.side {
+                TradeSide::Buy => trade.price >= last.price,
+                TradeSide::Sell => trade.price <= last.price,
+            };
+            last.side == trade.side && moving && trade.timestamp - self.run[0].timestamp <= self.config.sweep_secs
+        });
+        if !continues {
+            self.run.clear();
+            self.run_fired = false;
+        }
+        self.run.push(trade);
+
+        let levels = 1 + self.run.windows(2).filter(|w| w[1].price != w[0].price).count();
+        let size: f64 = self.run.iter().map(|t| t.size).sum();
+        if self.run_fired || levels < self.config.sweep_levels || size < self.config.sweep_size * avg {
+            return None;
+        }
+        self.run_fired = true;
+        Some(OrderFlowEvent {
+            kind: OrderFlowKind::Sweep,
+            aggressor: trade.side,
+            size,
+            relative_size: size / avg,
+            price: trade.price,
+            detail: levels as f64,
+        })
+    }
+
+    fn absorption(&mut self, trade: Trade, avg: f64) -> Option<OrderFlowEvent> {
+        self.window.push_back(trade);
+        while self.window.front().is_some_and(|t| trade.timestamp - t.timestamp > self.config.absorption_secs) {
+            self.window.pop_front();
+        }
+        let (buys, sells) = self.window.iter().fold((0.0, 0.0), |(b, s), t| match t.side {
+            TradeSide::Buy => (b + t.size, s),
+            TradeSide::Sell => (b, s + t.size),
+        });
+        let net = buys - sells;
+        if net.abs() < self.config.absorption_size * avg || net.abs() < 0.7 * (buys + sells) {
+            return None;
+        }
+        let aggressor = if net > 0.0 { TradeSide::Buy } else { TradeSide::Sell };
+        let start = self.window.front()?.price;
+        let favourable = match aggressor {
+            TradeSide::Buy => (trade.price - start) / start,
+            TradeSide::Sell => (start - trade.price) / start,
+        };
+        if favourable > self.config.absorption_max_move {
+            return None;
+        }
+        // one event per window of flow
+        self.window.clear();
+        Some(OrderFlowEvent {
+            kind: OrderFlowKind::Absorption,
+            aggressor,
+            size: net.abs(),
+            relative_size: net.abs() / avg,
+            price: trade.price,
+            detail: favourable,
+        })
+    }
+
+    fn iceberg(&mut self, trade: Trade, avg: f64, book: Option<BookTop>) -> Option<OrderFlowEvent> {
+        let book = book?;
+        let (level_price, displayed) = match trade.side {
+            TradeSide::Sell => (book.bid, book.bid_size),
+            TradeSide::Buy => (book.ask, book.ask_size),
+        };
+        if trade.price != level_price {
+            return None;
+        }
+        let level = match self.level.as_mut() {
+            Some(l) if l.price == level_price && l.aggressor == trade.side => l,
+            _ => self.level.insert(Level { aggressor: trade.side, price: level_price, traded: 0.0, max_displayed: 0.0, fired: false }),
+        };
+        level.traded += trade.size;
+        level.max_displayed = level.max_displayed.max(displayed);
+        // the level is still quoted after trading well beyond anything displayed
+        if level.fired || displayed <= 0.0 || level.traded < self.config.iceberg_refills * level.max_displayed {
+            return None;
+        }
+        level.fired = true;
+        Some(OrderFlowEvent {
+            kind: OrderFlowKind::Iceberg,
+            aggressor: trade.side,
+            size: level.traded,
+            relative_size: level.traded / avg,
+            price: level_price,
+            detail: level.max_displayed,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn warm(det: &mut OrderFlowDetector, t0: f64) {
+        // alternating small trades at a flat price
+        for i in 0..20 {
+            let side = if i % 2 == 0 { TradeSide::Buy } else { TradeSide::Sell };
+            assert!(det.update(100.0, 10.0, Some(side), None, t0 + i as f64 * 10.0).is_empty());
+        }
+    }
+
+    #[test]
+    fn test_sweep() {
+        let mut det = OrderFlowDetector::default();
+        warm(&mut det, 0.0);
+        let mut events = Vec::new();
+        for (i, price) in [100.01, 100.02, 100.03, 100.05].iter().enumerate() {
+            events.extend(det.update(*price, 30.0, Some(TradeSide::Buy), None, 300.0 + i as f64 * 0.1));
+        }
+        assert_eq!(events.len(), 1);
+        let e = &events[0];
+        assert_eq!((e.kind, e.aggressor, e.detail), (OrderFlowKind::Sweep, TradeSide::Buy, 3.0));
+        assert!(e.relative_size >= 5.0 && e.score() > 0.0);
+        assert!(det.update(99.0, 5.0, None, None, 301.0).is_empty());
+    }
+
+    #[test]
+    fn test_absorption() {
+        let mut det = OrderFlowDetector::default();
+        warm(&mut det, 0.0);
+        let mut events = Vec::new();
+        // sellers hammer the same price, which holds
+        for i in 0..12 {
+            events.extend(det.update(100.0, 12.0, Some(TradeSide::Sell), None, 300.0 + i as f64 * 0.3));
+        }
+        let absorbed: Vec<_> = events.iter().filter(|e| e.kind == OrderFlowKind::Absorption).collect();
+        assert_eq!(absorbed.len(), 1);
+        assert_eq!(absorbed[0].aggressor, TradeSide::Sell);
+        assert!(absorbed[0].score() > 0.0);
+    }
+
+    #[test]
+    fn test_cold_events_are_held_back() {
+        // the sweep lands inside a longer warm-up
+        let mut det = OrderFlowDetector::new(OrderFlowConfig { warmup_trades: 30, ..Default::default() });
+        warm(&mut det, 0.0);
+        for (i, price) in [100.01, 100.02, 100.03, 100.05].iter().enumerate() {
+            assert!(det.update(*price, 30.0, Some(TradeSide::Buy), None, 300.0 + i as f64 * 0.1).is_empty());
+        }
+        let cold = det.take_cold();
+        assert_eq!(cold.len(), 1);
+        assert_eq!(cold[0].kind, OrderFlowKind::Sweep);
+        assert!(det.take_cold().is_empty());
+    }
+
+    #[test]
+    fn test_iceberg_needs_book() {
+        let mut det = OrderFlowDetector::default();
+        warm(&mut det, 0.0);
+        let book = BookTop { bid: 99.99, bid_size: 20.0, ask: 100.01, ask_size: 50.0 };
+        let mut events = Vec::new();
+        for i in 0..8 {
+            events.extend(det.update(99.99, 10.0, Some(TradeSide::Sell), Some(book), 300.0 + i as f64 * 2.0));
+        }
+        let icebergs: Vec<_> = events.iter().filter(|e| e.kind == OrderFlowKind::Iceberg).collect();
+        assert_eq!(icebergs.len(), 1);
+        assert_eq!((icebergs[0].size, icebergs[0].detail), (60.0, 20.0));
+        assert!(icebergs[0].score() > 0.0);
+    }
+}
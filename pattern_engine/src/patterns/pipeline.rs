@@ -0,0 +1,1001 @@
+//! Per-symbol detection pipeline.
+//!
+//! [`DetectionPipeline`] owns a symbol's incremental indicators and runs an
+//! ordered list of [`PatternDetector`]s over every trade. Each detector adds
+//! its contribution to a shared [`Candidate`] score; the combined score is then
+//! gated by the pattern's min score and cooldown. Bar-based recognisers
+//! (candlesticks, structures, harmonics) run on closed candles, and config
+//! rules on trades. The service, replay, backtests and bindings all drive the
+//! same pipeline so they see identical signals.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use super::candlestick::{Candle, CandlestickDetector};
+use super::continuation::ContinuationDetector;
+use super::definitions::PatternGate;
+use super::ensemble::Ensemble;
+use super::gaps::GapDetector;
+use super::harmonic::HarmonicDetector;
+use super::mean_reversion::MeanReversionDetector;
+use super::orderflow::{OrderFlowDetector, OrderFlowEvent, OrderFlowKind};
+use super::structure::{DoubleTopDetector, HeadShouldersDetector};
+use super::wyckoff::WyckoffDetector;
+use super::zigzag::ZigZag;
+use super::PatternMeta;
+use crate::flags::{self, FeatureFlags};
+use crate::incremental::{BurstStats, VWAPBands, VolumeDelta, EMA, VWAP, Welford};
+use crate::publisher::{Signal, SignalMeta, Tick, TradeSide};
+use crate::rules::Rule;
+use crate::signal_id;
+use crate::suppressed::{SuppressedSignal, SuppressionReason};
+use crate::universe::DetectionThresholds;
+
+/// Features available to config-defined rules, in [`DetectionPipeline::rule_features`] order
+pub const RULE_FEATURE_NAMES: [&str; 14] = [
+    "price", "volume", "ema_fast", "ema_slow", "vwap", "vwap_deviation", "volume_ratio", "rsi", "atr", "volatility",
+    "cvd", "cvd_window", "vwap_z", "ticks_per_sec",
+];
+
+/// Incremental indicators for one symbol
+#[derive(Debug)]
+pub struct Indicators {
+    pub ema_fast: EMA,
+    pub ema_slow: EMA,
+    pub vwap: VWAP,
+    pub welford: Welford,
+    /// Running average trade volume and the number of trades seen
+    pub avg_volume: f64,
+    pub volume_count: u64,
+    /// Last traded price
+    pub prev_close: Option<f64>,
+    // RSI (Wilder) state
+    rsi_avg_gain: f64,
+    rsi_avg_loss: f64,
+    rsi_period: usize,
+    // ATR state
+    atr: f64,
+    atr_period: usize,
+    /// Session VWAP bands
+    pub vwap_bands: VWAPBands,
+    /// Order-flow state (only populated when ticks carry a trade side)
+    pub volume_delta: VolumeDelta,
+    /// Tick-rate / trade-size burst statistics (fed by raw ticks only)
+    pub burst: BurstStats,
+}
+
+impl Default for Indicators {
+    fn default() -> Self {
+        Self {
+            ema_fast: EMA::new(0.1), // 10-period equivalent
+            ema_slow: EMA::new(0.05), // 20-period equivalent
+            vwap: VWAP::new(),
+            welford: Welford::new(),
+            avg_volume: 0.0,
+            volume_count: 0,
+            prev_close: None,
+            rsi_avg_gain: 0.0,
+            rsi_avg_loss: 0.0,
+            rsi_period: 14,
+            atr: 0.0,
+            atr_period: 14,
+            vwap_bands: VWAPBands::new(86_400.0), // daily (UTC) sessions
+            volume_delta: VolumeDelta::new(60.0), // 1 minute rolling delta
+            burst: BurstStats::new(60.0), // 1 minute burst window
+        }
+    }
+}
+
+impl Indicators {
+    /// Update all price/volume indicators with a trade
+    pub fn update(&mut self, price: f64, volume: f64, timestamp: f64, side: Option<TradeSide>) {
+        self.ema_fast.update(price);
+        self.ema_slow.update(price);
+        self.vwap.update(price, volume);
+        self.vwap_bands.update(price, volume, timestamp);
+        self.welford.update(price);
+        self.volume_delta.update(side, volume, timestamp);
+
+        // Update running average for volume
+        self.volume_count += 1;
+        let n = self.volume_count as f64;
+        if n == 1.0 {
+            self.avg_volume = volume;
+        } else {
+            self.avg_volume += (volume - self.avg_volume) / n;
+        }
+
+        // RSI and ATR updates
+        if let Some(prev) = self.prev_close {
+            let change = price - prev;
+            let gain = if change > 0.0 { change } else { 0.0 };
+            let loss = if change < 0.0 { -change } else { 0.0 };
+            if self.volume_count as usize <= self.rsi_period {
+                // initial average
+                self.rsi_avg_gain = (self.rsi_avg_gain * (self.volume_count as f64 - 1.0) + gain) / (self.volume_count as f64);
+                self.rsi_avg_loss = (self.rsi_avg_loss * (self.volume_count as f64 - 1.0) + loss) / (self.volume_count as f64);
+            } else {
+                // Wilder smoothing
+                self.rsi_avg_gain = (self.rsi_avg_gain * (self.rsi_period as f64 - 1.0) + gain) / (self.rsi_period as f64);
+                self.rsi_avg_loss = (self.rsi_avg_loss * (self.rsi_period as f64 - 1.0) + loss) / (self.rsi_period as f64);
+            }
+            // ATR (True Range)
+            let tr = (price - prev).abs();
+            if self.atr == 0.0 {
+                self.atr = tr;
+            } else {
+                self.atr = (self.atr * (self.atr_period as f64 - 1.0) + tr) / (self.atr_period as f64);
+            }
+        }
+        self.prev_close = Some(price);
+    }
+
+    pub fn rsi(&self) -> f64 {
+        if self.rsi_avg_loss > 0.0 {
+            let rs = self.rsi_avg_gain / self.rsi_avg_loss;
+            100.0 - (100.0 / (1.0 + rs))
+        } else {
+            100.0
+        }
+    }
+
+    pub fn atr(&self) -> f64 {
+        self.atr
+    }
+
+    /// Snapshot the indicator values as signal metadata; burst stats are
+    /// read as of `now`
+    pub fn meta(&self, volume: f64, now: f64) -> SignalMeta {
+        SignalMeta {
+            ema_fast: self.ema_fast.value(),
+            ema_slow: self.ema_slow.value(),
+            vwap: Some(self.vwap.value()),
+            volume,
+            volatility: self.welford.std(),
+            rsi: Some(self.rsi()),
+            atr: Some(self.atr),
+            cvd: self.volume_delta.has_side_data().then(|| self.volume_delta.cumulative()),
+            cvd_window: self.volume_delta.has_side_data().then(|| self.volume_delta.rolling()),
+            vwap_bands: self.vwap_bands.levels(),
+            burst: Some(self.burst.snapshot(now)),
+            heartbeat: false,
+            flags: vec![],
+            components: BTreeMap::new(),
+        }
+    }
+}
+
+/// Inputs to one detection pass
+#[derive(Debug, Clone, Copy)]
+pub struct TickContext<'a> {
+    pub price: f64,
+    /// Trade volume (0 for heartbeats)
+    pub volume: f64,
+    pub timestamp: f64,
+    pub indicators: &'a Indicators,
+    pub thresholds: &'a DetectionThresholds,
+}
+
+/// Score and pattern accumulated across the detectors of one pass
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Candidate {
+    /// Unweighted running total
+    pub score: f64,
+    pub pattern: Option<String>,
+    /// Raw contributions in evaluation order, weighted later by the [`Ensemble`]
+    pub components: Vec<(String, f64)>,
+}
+
+impl Candidate {
+    /// Add `score` and name the candidate after `pattern`
+    pub fn add(&mut self, pattern: &str, score: f64) {
+        self.contribute(pattern, score);
+        self.pattern = Some(pattern.to_string());
+    }
+
+    /// Add `score` as a `component` without renaming the candidate
+    pub fn contribute(&mut self, component: &str, score: f64) {
+        self.score += score;
+        self.components.push((component.to_string(), score));
+    }
+}
+
+/// A tick-level pattern recogniser. Detectors run in pipeline order and may
+/// read what earlier detectors contributed to the candidate.
+pub trait PatternDetector: Debug + Send {
+    fn name(&self) -> &str;
+
+    /// Add this detector's contribution, if any, to `candidate`
+    fn evaluate(&mut self, ctx: &TickContext<'_>, candidate: &mut Candidate);
+}
+
+/// Fast EMA diverging from the slow EMA
+#[derive(Debug, Default)]
+pub struct EmaCrossover;
+
+impl PatternDetector for EmaCrossover {
+    fn name(&self) -> &str {
+        "ema_crossover"
+    }
+
+    fn evaluate(&mut self, ctx: &TickContext<'_>, candidate: &mut Candidate) {
+        let fast = ctx.indicators.ema_fast.value().unwrap_or(ctx.price);
+        let slow = ctx.indicators.ema_slow.value().unwrap_or(ctx.price);
+        if fast > 0.0 && slow > 0.0 {
+            let ema_diff = (fast - slow) / slow;
+            if ema_diff.abs() > ctx.thresholds.ema_diff {
+                candidate.add(self.name(), ema_diff * 2.0); // Amplify signal
+            }
+        }
+    }
+}
+
+/// Price away from VWAP; only names the candidate if nothing else has
+#[derive(Debug, Default)]
+pub struct VwapDeviation;
+
+impl PatternDetector for VwapDeviation {
+    fn name(&self) -> &str {
+        "vwap_deviation"
+    }
+
+    fn evaluate(&mut self, ctx: &TickContext<'_>, candidate: &mut Candidate) {
+        let vwap = ctx.indicators.vwap.value();
+        if vwap > 0.0 {
+            let vwap_diff = (ctx.price - vwap) / vwap;
+            if vwap_diff.abs() > ctx.thresholds.vwap_deviation {
+                candidate.contribute(self.name(), vwap_diff * 1.5);
+                if candidate.pattern.is_none() {
+                    candidate.pattern = Some(self.name().to_string());
+                }
+            }
+        }
+    }
+}
+
+/// Trade volume well above normal, reinforcing the current direction
+#[derive(Debug, Default)]
+pub struct VolumeSpike;
+
+impl PatternDetector for VolumeSpike {
+    fn name(&self) -> &str {
+        "volume_spike"
+    }
+
+    fn evaluate(&mut self, ctx: &TickContext<'_>, candidate: &mut Candidate) {
+        if ctx.volume > 0.0 {
+            let avg_volume = 1000.0; // Placeholder - should be calculated
+            let volume_ratio = ctx.volume / avg_volume;
+            if volume_ratio > ctx.thresholds.volume_ratio {
+                let score = if candidate.score > 0.0 { 0.3 } else { -0.3 };
+                candidate.add(self.name(), score);
+            }
+        }
+    }
+}
+
+/// Position of price relative to the 2σ VWAP bands
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+enum BandZone {
+    #[default]
+    Inside,
+    AboveUpper,
+    BelowLower,
+}
+
+/// VWAP band patterns: touching a 2σ band, then reverting inside 1σ
+#[derive(Debug, Default)]
+pub struct VwapBand {
+    zone: BandZone,
+}
+
+impl PatternDetector for VwapBand {
+    fn name(&self) -> &str {
+        "vwap_band"
+    }
+
+    fn evaluate(&mut self, ctx: &TickContext<'_>, candidate: &mut Candidate) {
+        let Some(bands) = ctx.indicators.vwap_bands.levels().filter(|b| b.sigma > 0.0) else {
+            return;
+        };
+        let z = bands.z_score(ctx.price);
+        match self.zone {
+            BandZone::Inside if z.abs() >= 2.0 => {
+                // stretched: expect mean reversion
+                candidate.add("vwap_band_touch", if z > 0.0 { -0.35 } else { 0.35 });
+                self.zone = if z > 0.0 { BandZone::AboveUpper } else { BandZone::BelowLower };
+            }
+            BandZone::AboveUpper | BandZone::BelowLower if z.abs() <= 1.0 => {
+                candidate.add("vwap_band_revert", if self.zone == BandZone::AboveUpper { -0.3 } else { 0.3 });
+                self.zone = BandZone::Inside;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Tick rate well above the 1 minute mean
+#[derive(Debug, Default)]
+pub struct LiquidityBurst;
+
+impl PatternDetector for LiquidityBurst {
+    fn name(&self) -> &str {
+        "liquidity_burst"
+    }
+
+    fn evaluate(&mut self, ctx: &TickContext<'_>, candidate: &mut Candidate) {
+        if ctx.indicators.burst.is_burst(ctx.timestamp, 3.0, 5) {
+            let score = if ctx.price >= ctx.indicators.vwap.value() { 0.3 } else { -0.3 };
+            candidate.add(self.name(), score);
+        }
+    }
+}
+
+/// Price more than two standard deviations from the fast EMA
+#[derive(Debug, Default)]
+pub struct VolatilityBreakout;
+
+impl PatternDetector for VolatilityBreakout {
+    fn name(&self) -> &str {
+        "volatility_breakout"
+    }
+
+    fn evaluate(&mut self, ctx: &TickContext<'_>, candidate: &mut Candidate) {
+        let ind = ctx.indicators;
+        if ind.welford.count() > 5 {
+            let volatility = ind.welford.std();
+            let ema_fast = ind.ema_fast.value().unwrap_or(ctx.price);
+            let price_change = (ctx.price - ema_fast).abs() / ctx.price;
+            if price_change > volatility * 2.0 {
+                let score = if candidate.score > 0.0 { 0.4 } else { -0.4 };
+                candidate.add(self.name(), score);
+            }
+        }
+    }
+}
+
+/// The built-in tick-level detectors, in evaluation order
+pub fn default_detectors() -> Vec<Box<dyn PatternDetector>> {
+    vec![
+        Box::new(EmaCrossover),
+        Box::new(VwapDeviation),
+        Box::new(VolumeSpike),
+        Box::new(VwapBand::default()),
+        Box::new(LiquidityBurst),
+        Box::new(VolatilityBreakout),
+    ]
+}
+
+/// Bar-based detectors for one candle interval
+#[derive(Debug)]
+struct IntervalDetectors {
+    candlestick: CandlestickDetector,
+    zigzag: ZigZag,
+    double_top: DoubleTopDetector,
+    head_shoulders: HeadShouldersDetector,
+    continuation: ContinuationDetector,
+    harmonic: HarmonicDetector,
+    wyckoff: WyckoffDetector,
+    gaps: GapDetector,
+}
+
+impl IntervalDetectors {
+    fn new() -> Self {
+        Self {
+            candlestick: CandlestickDetector::default(),
+            zigzag: ZigZag::new(0.01, 32), // 1% swings
+            double_top: DoubleTopDetector::default(),
+            head_shoulders: HeadShouldersDetector::default(),
+            continuation: ContinuationDetector::default(),
+            harmonic: HarmonicDetector::default(),
+            wyckoff: WyckoffDetector::default(),
+            gaps: GapDetector::default(),
+        }
+    }
+}
+
+/// Indicators, detectors and emission gating for one symbol
+#[derive(Debug)]
+pub struct DetectionPipeline {
+    symbol: String,
+    indicators: Indicators,
+    detectors: Vec<Box<dyn PatternDetector>>,
+    /// Effective detection thresholds (defaults plus universe overrides)
+    thresholds: DetectionThresholds,
+    /// Per-pattern min score / cooldown overrides
+    gates: Arc<BTreeMap<String, PatternGate>>,
+    /// Weighting of detector contributions into the candidate score
+    ensemble: Arc<Ensemble>,
+    /// Last time each emitted pattern fired, for per-pattern cooldowns
+    last_fired: HashMap<String, f64>,
+    /// Candlestick and structural detectors per candle interval (seconds)
+    interval_detectors: HashMap<u64, IntervalDetectors>,
+    /// Last time each configured rule fired, for per-rule cooldowns
+    rule_last_fired: HashMap<String, f64>,
+    /// Rolling z-score fade, reported separately from the composite candidate
+    mean_reversion: MeanReversionDetector,
+    /// Sweeps, absorption and icebergs from aggressor-side trades
+    order_flow: OrderFlowDetector,
+    /// Rejected candidates since the last take, collected only when tracking is on
+    track_suppressed: bool,
+    suppressed: Vec<SuppressedSignal>,
+}
+
+impl DetectionPipeline {
+    /// Pipeline running the [`default_detectors`]
+    pub fn new(symbol: &str, thresholds: DetectionThresholds, gates: Arc<BTreeMap<String, PatternGate>>) -> Self {
+        Self {
+            symbol: symbol.to_string(),
+            indicators: Indicators::default(),
+            detectors: default_detectors(),
+            thresholds,
+            gates,
+            ensemble: Arc::default(),
+            last_fired: HashMap::new(),
+            interval_detectors: HashMap::new(),
+            rule_last_fired: HashMap::new(),
+            mean_reversion: MeanReversionDetector::default(),
+            order_flow: OrderFlowDetector::default(),
+            track_suppressed: false,
+            suppressed: Vec::new(),
+        }
+    }
+
+    /// Replace the tick-level detectors
+    pub fn with_detectors(mut self, detectors: Vec<Box<dyn PatternDetector>>) -> Self {
+        self.detectors = detectors;
+        self
+    }
+
+    /// Combine detector contributions with `ensemble` instead of a plain sum
+    pub fn with_ensemble(mut self, ensemble: Arc<Ensemble>) -> Self {
+        self.ensemble = ensemble;
+        self
+    }
+
+    /// Append a tick-level detector after the existing ones
+    pub fn push_detector(&mut self, detector: Box<dyn PatternDetector>) {
+        self.detectors.push(detector);
+    }
+
+    pub fn detector_names(&self) -> Vec<&str> {
+        self.detectors.iter().map(|d| d.name()).collect()
+    }
+
+    pub fn symbol(&self) -> &str {
+        &self.symbol
+    }
+
+    pub fn indicators(&self) -> &Indicators {
+        &self.indicators
+    }
+
+    pub fn thresholds(&self) -> &DetectionThresholds {
+        &self.thresholds
+    }
+
+    pub fn set_thresholds(&mut self, thresholds: DetectionThresholds) {
+        self.thresholds = thresholds;
+    }
+
+    /// Swap in new per-pattern gates (e.g. after a pattern library reload)
+    pub fn set_gates(&mut self, gates: Arc<BTreeMap<String, PatternGate>>) {
+        self.gates = gates;
+    }
+
+    /// Keep rejected candidates for [`Self::take_suppressed`]
+    pub fn set_track_suppressed(&mut self, track: bool) {
+        self.track_suppressed = track;
+    }
+
+    pub fn take_suppressed(&mut self) -> Vec<SuppressedSignal> {
+        std::mem::take(&mut self.suppressed)
+    }
+
+    /// Count a raw trade arrival in the burst statistics (not called for
+    /// candle closes or heartbeats)
+    pub fn record_arrival(&mut self, volume: f64, timestamp: f64) {
+        self.indicators.burst.update(volume, timestamp);
+    }
+
+    /// Snapshot the current indicator values as signal metadata
+    pub fn current_meta(&self, volume: f64, now: f64) -> SignalMeta {
+        self.indicators.meta(volume, now)
+    }
+
+    /// Update indicators and detect patterns
+    pub fn update_and_detect(&mut self, price: f64, volume: f64, timestamp: f64, side: Option<TradeSide>) -> Option<Signal> {
+        self.indicators.update(price, volume, timestamp, side);
+        self.detect(price, volume, timestamp)
+    }
+
+    /// Evaluate detection at the last traded price without touching price or
+    /// volume statistics. Used for heartbeats on quiet symbols so time-based
+    /// logic (cooldown expiry) still runs.
+    pub fn heartbeat(&mut self, timestamp: f64) -> Option<Signal> {
+        let price = self.indicators.prev_close?;
+        // expire stale order-flow entries only
+        self.indicators.volume_delta.update(None, 0.0, timestamp);
+        let mut signal = self.detect(price, 0.0, timestamp)?;
+        if let Some(meta) = signal.meta.as_mut() {
+            meta.heartbeat = true;
+        }
+        Some(signal)
+    }
+
+    /// Effective (min score, cooldown secs) for a pattern: its gate, else the symbol thresholds
+    pub fn gate_for(&self, pattern: &str) -> (f64, f64) {
+        let gate = self.gates.get(pattern);
+        (
+            gate.and_then(|g| g.min_score).unwrap_or(self.thresholds.min_score),
+            gate.and_then(|g| g.cooldown_secs).unwrap_or(self.thresholds.cooldown_secs),
+        )
+    }
+
+    /// Apply the gate of a signal's pattern before it is emitted: |score|
+    /// above the min score and the pattern out of cooldown. Gates are looked
+    /// up by base name (without the `:<interval>s` suffix); cooldowns are
+    /// kept per emitted pattern, so each interval cools down on its own.
+    fn admit(&mut self, signal: &Signal) -> bool {
+        let base = signal.pattern.split(':').next().unwrap_or_default();
+        let (min_score, cooldown_secs) = self.gate_for(base);
+        if signal.score.abs() <= min_score
+            || since_last_fired(&self.last_fired, &signal.pattern, signal.timestamp) <= cooldown_secs
+        {
+            return false;
+        }
+        self.last_fired.insert(signal.pattern.clone(), signal.timestamp);
+        true
+    }
+
+    /// Run the detectors against the current indicator state
+    fn detect(&mut self, price: f64, volume: f64, timestamp: f64) -> Option<Signal> {
+        let mut candidate = Candidate::default();
+        let ctx = TickContext { price, volume, timestamp, indicators: &self.indicators, thresholds: &self.thresholds };
+        for detector in self.detectors.iter_mut() {
+            detector.evaluate(&ctx, &mut candidate);
+        }
+
+        // Weight the contributions, then normalize the score to [-1, 1]
+        let (score, components) = self.ensemble.combine(&candidate);
+        let signal_score = score.clamp(-1.0, 1.0);
+        let pattern_type = candidate.pattern;
+
+        // Only generate signal if significant and the pattern is not in cooldown
+        let pattern = pattern_type.as_deref().unwrap_or_default();
+        let (min_score, cooldown_secs) = self.gate_for(pattern);
+        let significant = signal_score.abs() > min_score;
+        let since_last = since_last_fired(&self.last_fired, pattern, timestamp);
+        let cooled_down = since_last > cooldown_secs;
+        if significant && cooled_down {
+            self.last_fired.insert(pattern.to_string(), timestamp);
+            let mut signal = self.build_signal(signal_score, pattern_type, volume, timestamp);
+            if let Some(meta) = signal.meta.as_mut() {
+                meta.components = components;
+            }
+            return Some(signal);
+        }
+
+        // Record rejected candidates: cooldown, or near misses (at least half the threshold)
+        if self.track_suppressed && pattern_type.is_some() {
+            let rejection = if significant {
+                Some((
+                    SuppressionReason::Cooldown,
+                    format!("{:.1}s of {:.1}s cooldown elapsed", since_last, cooldown_secs),
+                ))
+            } else if signal_score.abs() >= min_score * 0.5 {
+                Some((
+                    SuppressionReason::BelowThreshold,
+                    format!("|score| {:.3} below {:.3}", signal_score.abs(), min_score),
+                ))
+            } else {
+                None
+            };
+            if let Some((reason, detail)) = rejection {
+                let mut candidate = self.build_signal(signal_score, pattern_type, volume, timestamp);
+                if let Some(meta) = candidate.meta.as_mut() {
+                    meta.components = components;
+                }
+                self.suppressed.push(SuppressedSignal::new(reason, detail, candidate));
+            }
+        }
+        None
+    }
+
+    /// Run candlestick and structural recognition on a closed candle of the given interval
+    pub fn detect_on_candle(&mut self, candle: &Candle, interval: u64, flags: &FeatureFlags) -> Vec<Signal> {
+        let continuation_on = flags.is_enabled(flags::CONTINUATION_PATTERNS);
+        let detectors = self.interval_detectors.entry(interval).or_insert_with(IntervalDetectors::new);
+        let patterns = detectors.candlestick.update(candle);
+        let mut harmonic = None;
+        if detectors.zigzag.update_candle(candle).is_some() {
+            detectors.double_top.on_pivot(detectors.zigzag.pivots());
+            detectors.head_shoulders.on_pivot(detectors.zigzag.pivots());
+            if continuation_on {
+                detectors.continuation.on_pivot(detectors.zigzag.pivots());
+            }
+            if flags.is_enabled(flags::HARMONIC_PATTERNS) {
+                harmonic = detectors.harmonic.on_pivot(detectors.zigzag.pivots());
+            }
+        }
+        let ts = candle.start as f64;
+        // burst stats are read at the close, not the start, of the candle
+        let closed_at = (candle.start + interval) as f64;
+        let structures = [
+            detectors.double_top.on_close(candle.close, ts),
+            detectors.head_shoulders.on_close(candle.close, ts),
+            continuation_on.then(|| detectors.continuation.on_close(candle.close, ts)).flatten(),
+        ];
+        let gap = detectors.gaps.update(candle);
+        let wyckoff = flags.is_enabled(flags::WYCKOFF_PATTERNS).then(|| detectors.wyckoff.update(candle)).flatten();
+
+        // Zero-polarity patterns (dojis) mark indecision, not a direction,
+        // so they never become signals
+        let mut signals: Vec<Signal> = patterns
+            .into_iter()
+            .filter(|p| p.polarity() != 0.0)
+            .map(|p| {
+                // Scale polarity by how decisive the bar was
+                let body_ratio = if candle.range() > 0.0 { candle.body() / candle.range() } else { 0.0 };
+                let score = (p.polarity() * (0.5 + 0.5 * body_ratio)).clamp(-1.0, 1.0);
+                Signal {
+                    id: signal_id::next_id(),
+                    symbol: self.symbol.clone(),
+                    score,
+                    pattern: format!("{}:{}s", p.name(), interval),
+                    timestamp: candle.start as f64,
+                    meta: Some(self.current_meta(candle.volume, closed_at)),
+                    pattern_meta: None,
+                    status: None,
+                    linked_id: None,
+                    extra: Default::default(),
+                }
+            })
+            .collect();
+
+        for m in structures.into_iter().flatten() {
+            signals.push(Signal {
+                id: signal_id::next_id(),
+                symbol: self.symbol.clone(),
+                score: (m.polarity * 0.8).clamp(-1.0, 1.0),
+                pattern: format!("{}:{}s", m.name, interval),
+                timestamp: m.timestamp,
+                meta: Some(self.current_meta(candle.volume, closed_at)),
+                // levels are carried over when the pattern library enriches the signal
+                pattern_meta: Some(PatternMeta {
+                    name: m.name.clone(),
+                    neckline: Some(m.neckline),
+                    target: Some(m.target),
+                    ..Default::default()
+                }),
+                status: None,
+                linked_id: None,
+                extra: Default::default(),
+            });
+        }
+
+        if let Some(h) = harmonic {
+            signals.push(Signal {
+                id: signal_id::next_id(),
+                symbol: self.symbol.clone(),
+                score: (h.polarity * 0.7).clamp(-1.0, 1.0),
+                pattern: format!("{}:{}s", h.name, interval),
+                timestamp: candle.start as f64,
+                meta: Some(self.current_meta(candle.volume, closed_at)),
+                pattern_meta: Some(PatternMeta {
+                    name: h.name.clone(),
+                    target: Some(h.target),
+                    reversal_zone: Some(h.reversal_zone),
+                    ..Default::default()
+                }),
+                status: None,
+                linked_id: None,
+                extra: Default::default(),
+            });
+        }
+
+        if let Some(w) = wyckoff {
+            let range = serde_json::json!({
+                "phase": w.phase,
+                "support": w.support,
+                "resistance": w.resistance,
+                "volume_ratio": w.volume_ratio,
+            });
+            signals.push(Signal {
+                id: signal_id::next_id(),
+                symbol: self.symbol.clone(),
+                score: (w.polarity * 0.8).clamp(-1.0, 1.0),
+                pattern: format!("{}:{}s", w.name, interval),
+                timestamp: w.timestamp,
+                meta: Some(self.current_meta(candle.volume, closed_at)),
+                pattern_meta: Some(PatternMeta {
+                    name: w.name.clone(),
+                    neckline: Some(w.breakout_level()),
+                    target: Some(w.target()),
+                    ..Default::default()
+                }),
+                status: None,
+                linked_id: None,
+                extra: HashMap::from([("wyckoff".to_string(), range)]),
+            });
+        }
+
+        if let Some(g) = gap {
+            let details = serde_json::json!({
+                "kind": g.kind,
+                "direction": if g.up { "up" } else { "down" },
+                "size": g.size(),
+                "size_pct": g.size_pct(),
+                "fill_level": g.fill_level,
+            });
+            signals.push(Signal {
+                id: signal_id::next_id(),
+                symbol: self.symbol.clone(),
+                score: (g.polarity() * 0.6).clamp(-1.0, 1.0),
+                pattern: format!("gap_fill:{}s", interval),
+                timestamp: g.timestamp,
+                meta: Some(self.current_meta(candle.volume, closed_at)),
+                pattern_meta: Some(PatternMeta {
+                    name: "gap_fill".to_string(),
+                    target: Some(g.fill_level),
+                    ..Default::default()
+                }),
+                status: None,
+                linked_id: None,
+                extra: HashMap::from([("gap".to_string(), details)]),
+            });
+        }
+
+        signals.retain(|signal| self.admit(signal));
+        signals
+    }
+
+    /// Feature values for rule evaluation, ordered as [`RULE_FEATURE_NAMES`]
+    pub fn rule_features(&self, price: f64, volume: f64, timestamp: f64) -> Vec<f64> {
+        let meta = self.current_meta(volume, timestamp);
+        let nan = f64::NAN;
+        let vwap = meta.vwap.unwrap_or(nan);
+        vec![
+            price,
+            volume,
+            meta.ema_fast.unwrap_or(nan),
+            meta.ema_slow.unwrap_or(nan),
+            vwap,
+            if vwap > 0.0 { (price - vwap) / vwap } else { nan },
+            if self.indicators.avg_volume > 0.0 { volume / self.indicators.avg_volume } else { 1.0 },
+            meta.rsi.unwrap_or(nan),
+            meta.atr.unwrap_or(nan),
+            meta.volatility,
+            meta.cvd.unwrap_or(nan),
+            meta.cvd_window.unwrap_or(nan),
+            meta.vwap_bands.map(|b| b.z_score(price)).unwrap_or(nan),
+            self.indicators.burst.ticks_per_sec(timestamp) as f64,
+        ]
+    }
+
+    /// Evaluate configured rules, honouring the rule's gate (or the symbol
+    /// thresholds) for min score and cooldown per rule
+    pub fn evaluate_rules(&mut self, rules: &[Rule], price: f64, volume: f64, timestamp: f64) -> Vec<Signal> {
+        if rules.is_empty() {
+            return Vec::new();
+        }
+        let values = self.rule_features(price, volume, timestamp);
+        let mut signals = Vec::new();
+        for rule in rules {
+            let (min_score, cooldown_secs) = self.gate_for(&rule.name);
+            if rule.score.abs() <= min_score
+                || since_last_fired(&self.rule_last_fired, &rule.name, timestamp) <= cooldown_secs
+                || !rule.condition.matches(&values)
+            {
+                continue;
+            }
+            self.rule_last_fired.insert(rule.name.clone(), timestamp);
+            signals.push(self.build_signal(rule.score, Some(rule.name.clone()), volume, timestamp));
+        }
+        signals
+    }
+
+    /// Feed a trade to the mean-reversion detector, returning a `mean_reversion`
+    /// entry or `mean_reversion_exit` signal when price crosses its bands
+    pub fn detect_mean_reversion(&mut self, price: f64, volume: f64, timestamp: f64) -> Option<Signal> {
+        let event = self.mean_reversion.update(price)?;
+        let pattern = if event.entry { "mean_reversion" } else { "mean_reversion_exit" };
+        let score = event.score(self.mean_reversion.config().entry_z);
+        let mut signal = self.build_signal(score, Some(pattern.to_string()), volume, timestamp);
+        let bands = serde_json::json!({
+            "z": event.z,
+            "mean": event.mean,
+            "std": event.std,
+            "side": if event.side > 0.0 { "long" } else { "short" },
+        });
+        signal.extra.insert("mean_reversion".to_string(), bands);
+        self.admit(&signal).then_some(signal)
+    }
+
+    /// Feed a trade to the order-flow detectors, returning a signal per
+    /// `liquidity_sweep`, `absorption` or `iceberg` found. Trades without an
+    /// aggressor side are ignored; icebergs also need the tick's book.
+    pub fn detect_order_flow(&mut self, tick: &Tick) -> Vec<Signal> {
+        let events = self.order_flow.update(tick.price, tick.volume, tick.side, tick.book, tick.timestamp);
+        let cold = self.order_flow.take_cold();
+        if self.track_suppressed {
+            for event in cold {
+                let signal = self.order_flow_signal(&event, tick);
+                self.suppressed.push(SuppressedSignal::new(SuppressionReason::WarmUp, "order flow warming up".to_string(), signal));
+            }
+        }
+        let mut signals = events.iter().map(|event| self.order_flow_signal(event, tick)).collect::<Vec<_>>();
+        signals.retain(|signal| self.admit(signal));
+        signals
+    }
+
+    fn order_flow_signal(&self, event: &OrderFlowEvent, tick: &Tick) -> Signal {
+        let pattern = event.kind.pattern();
+        let mut signal = self.build_signal(event.score(), Some(pattern.to_string()), tick.volume, tick.timestamp);
+        let detail = match event.kind {
+            OrderFlowKind::Sweep => "levels",
+            OrderFlowKind::Absorption => "move",
+            OrderFlowKind::Iceberg => "displayed",
+        };
+        let flow = serde_json::json!({
+            "aggressor": match event.aggressor { TradeSide::Buy => "buy", TradeSide::Sell => "sell" },
+            "size": event.size,
+            "relative_size": event.relative_size,
+            "price": event.price,
+            (detail): event.detail,
+        });
+        signal.extra.insert("order_flow".to_string(), flow);
+        signal
+    }
+
+    fn build_signal(&self, score: f64, pattern_type: Option<String>, volume: f64, timestamp: f64) -> Signal {
+        Signal {
+            id: signal_id::next_id(),
+            symbol: self.symbol.clone(),
+            score,
+            pattern: pattern_type.unwrap_or_else(|| "composite".to_string()),
+            timestamp,
+            meta: Some(self.current_meta(volume, timestamp)),
+            pattern_meta: None,
+            status: None,
+            linked_id: None,
+            extra: Default::default(),
+        }
+    }
+}
+
+/// Seconds since `key` last fired, infinite if it never has
+fn since_last_fired(fired: &HashMap<String, f64>, key: &str, timestamp: f64) -> f64 {
+    fired.get(key).map_or(f64::INFINITY, |last| timestamp - last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Fires on every trade above a fixed price
+    #[derive(Debug)]
+    struct AbovePrice(f64);
+
+    impl PatternDetector for AbovePrice {
+        fn name(&self) -> &str {
+            "above_price"
+        }
+
+        fn evaluate(&mut self, ctx: &TickContext<'_>, candidate: &mut Candidate) {
+            if ctx.price > self.0 {
+                candidate.add(self.name(), 0.9);
+            }
+        }
+    }
+
+    fn pipeline(gates: BTreeMap<String, PatternGate>) -> DetectionPipeline {
+        DetectionPipeline::new("AAPL", DetectionThresholds::default(), Arc::new(gates))
+            .with_detectors(vec![Box::new(AbovePrice(100.0))])
+    }
+
+    #[test]
+    fn test_custom_detector_and_cooldown() {
+        let mut p = pipeline(BTreeMap::new());
+        assert_eq!(p.detector_names(), vec!["above_price"]);
+        assert!(p.update_and_detect(99.0, 10.0, 100.0, None).is_none());
+        let sig = p.update_and_detect(101.0, 10.0, 101.0, None).unwrap();
+        assert_eq!((sig.pattern.as_str(), sig.score), ("above_price", 0.9));
+        // default 30s cooldown
+        assert!(p.update_and_detect(101.0, 10.0, 120.0, None).is_none());
+        assert!(p.update_and_detect(101.0, 10.0, 140.0, None).is_some());
+        assert_eq!(p.indicators().volume_count, 4);
+    }
+
+    #[test]
+    fn test_gates_override_thresholds() {
+        let gate = PatternGate { min_score: Some(0.95), cooldown_secs: None };
+        let mut p = pipeline(BTreeMap::from([("above_price".to_string(), gate)]));
+        p.set_track_suppressed(true);
+        assert!(p.update_and_detect(101.0, 10.0, 100.0, None).is_none());
+        let suppressed = p.take_suppressed().pop().unwrap();
+        assert_eq!(suppressed.reason, SuppressionReason::BelowThreshold);
+        assert_eq!(p.gate_for("above_price"), (0.95, 30.0));
+        assert_eq!(p.gate_for("other"), (0.3, 30.0));
+    }
+
+    #[test]
+    fn test_every_emission_is_gated() {
+        let gates = BTreeMap::from([
+            ("hammer".to_string(), PatternGate { min_score: Some(0.4), cooldown_secs: Some(120.0) }),
+            ("weak_rule".to_string(), PatternGate { min_score: Some(0.5), cooldown_secs: None }),
+        ]);
+        let mut p = pipeline(gates);
+        let mut candle = p.build_signal(0.45, Some("hammer:60s".to_string()), 10.0, 60.0);
+        assert!(p.admit(&candle));
+        // cooldown per emitted pattern; the gate is the base name's
+        candle.timestamp = 120.0;
+        assert!(!p.admit(&candle));
+        candle.pattern = "hammer:300s".to_string();
+        assert!(p.admit(&candle));
+        candle.score = 0.35;
+        candle.pattern = "hammer:900s".to_string();
+        assert!(!p.admit(&candle));
+
+        let rules = crate::rules::parse_rules(
+            "- {name: weak_rule, when: 'price > 0', score: 0.45}\n- {name: strong_rule, when: 'price > 0', score: 0.6}\n",
+            &RULE_FEATURE_NAMES,
+        )
+        .unwrap();
+        let fired = p.evaluate_rules(&rules, 101.0, 10.0, 10.0);
+        assert_eq!(fired.iter().map(|s| s.pattern.as_str()).collect::<Vec<_>>(), vec!["strong_rule"]);
+        assert!(p.evaluate_rules(&rules, 101.0, 10.0, 20.0).is_empty());
+    }
+
+    #[test]
+    fn test_doji_is_not_emitted() {
+        // even a gate that would pass any nonzero score lets no doji through
+        let gate = PatternGate { min_score: Some(0.0), cooldown_secs: Some(0.0) };
+        let mut p = pipeline(BTreeMap::from([("doji".to_string(), gate)]));
+        let doji = Candle { start: 60, open: 100.0, high: 101.0, low: 99.0, close: 100.05, volume: 1.0 };
+        assert!(p.detect_on_candle(&doji, 60, &FeatureFlags::default()).is_empty());
+    }
+
+    #[test]
+    fn test_order_flow_warm_up_is_suppressed() {
+        let mut p = pipeline(BTreeMap::new());
+        p.set_track_suppressed(true);
+        let trade = |price: f64, volume: f64, side: TradeSide, timestamp: f64| Tick {
+            side: Some(side),
+            volume,
+            ..Tick::test("AAPL", price, timestamp)
+        };
+        for i in 0..5 {
+            let side = if i % 2 == 0 { TradeSide::Buy } else { TradeSide::Sell };
+            assert!(p.detect_order_flow(&trade(100.0, 10.0, side, i as f64)).is_empty());
+        }
+        // a sweep inside the 20-trade warm-up is reported, not emitted
+        for (i, price) in [100.01, 100.02, 100.03, 100.05].iter().enumerate() {
+            assert!(p.detect_order_flow(&trade(*price, 30.0, TradeSide::Buy, 10.0 + i as f64 * 0.1)).is_empty());
+        }
+        let suppressed = p.take_suppressed();
+        assert_eq!(suppressed.len(), 1);
+        assert_eq!(suppressed[0].reason, SuppressionReason::WarmUp);
+        assert_eq!(suppressed[0].signal.pattern, "liquidity_sweep");
+    }
+
+    #[test]
+    fn test_heartbeat_reuses_last_price() {
+        let mut p = pipeline(BTreeMap::new());
+        assert!(p.heartbeat(0.0).is_none());
+        p.update_and_detect(101.0, 10.0, 100.0, None).unwrap();
+        let sig = p.heartbeat(200.0).unwrap();
+        assert!(sig.meta.unwrap().heartbeat);
+        assert_eq!(p.indicators().volume_count, 1);
+    }
+
+    #[test]
+    fn test_default_detectors_order() {
+        let p = DetectionPipeline::new("AAPL", DetectionThresholds::default(), Arc::default());
+        assert_eq!(
+            p.detector_names(),
+            vec!["ema_crossover", "vwap_deviation", "volume_spike", "vwap_band", "liquidity_burst", "volatility_breakout"]
+        );
+    }
+}
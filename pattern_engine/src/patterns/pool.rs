@@ -0,0 +1,363 @@
+//! Dedicated worker threads for pattern library inference.
+//!
+//! Model inference is CPU-bound and can be slow, so it runs on its own
+//! threads fed by a bounded channel rather than on the async runtime. Callers
+//! await the result over a oneshot. What happens when the queue is full is the
+//! [`ShedPolicy`]: wait for a slot (backpressure), or give up at once or after
+//! a short wait so a slow model cannot stall tick processing. Known patterns
+//! are answered inline since they need no model call.
+//!
+//! An optional deadline bounds each lookup, queueing included. A lookup that
+//! misses it is answered with [`PatternLibrary::stub_meta`] and counted as
+//! degraded; its job is skipped if no worker has picked it up yet, otherwise
+//! its result is discarded.
+
+use super::{PatternLibrary, PatternMeta};
+use anyhow::{anyhow, Result};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc::error::{SendTimeoutError, TrySendError};
+use tokio::sync::{mpsc, oneshot};
+use tracing::debug;
+
+type Job = Box<dyn FnOnce(&PatternLibrary) + Send>;
+
+struct PendingGuard(Arc<AtomicUsize>);
+
+impl Drop for PendingGuard {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// What a caller does when the inference queue is full
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShedPolicy {
+    /// Wait for a free slot
+    Block,
+    /// Fail immediately with [`InferenceShed`]
+    Reject,
+    /// Wait up to the given time, then fail with [`InferenceShed`]
+    Wait(Duration),
+}
+
+impl FromStr for ShedPolicy {
+    type Err = anyhow::Error;
+
+    /// `block`, `reject` or `wait` (with a 50ms wait; see [`ShedPolicy::with_wait`])
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "block" => Ok(ShedPolicy::Block),
+            "reject" => Ok(ShedPolicy::Reject),
+            "wait" => Ok(ShedPolicy::Wait(Duration::from_millis(50))),
+            other => Err(anyhow!("unknown shed policy: {} (expected block, reject or wait)", other)),
+        }
+    }
+}
+
+impl ShedPolicy {
+    /// Replace the wait of a [`ShedPolicy::Wait`]; other policies are unchanged
+    pub fn with_wait(self, wait: Duration) -> Self {
+        match self {
+            ShedPolicy::Wait(_) => ShedPolicy::Wait(wait),
+            other => other,
+        }
+    }
+}
+
+/// A job was not queued because the queue was full
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InferenceShed;
+
+impl std::fmt::Display for InferenceShed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("inference queue full; request shed")
+    }
+}
+
+impl std::error::Error for InferenceShed {}
+
+pub struct InferencePool {
+    library: Arc<PatternLibrary>,
+    jobs: mpsc::Sender<Job>,
+    /// Jobs queued or running
+    pending: Arc<AtomicUsize>,
+    shed_policy: ShedPolicy,
+    /// Jobs turned away by the shed policy
+    shed: AtomicU64,
+    /// Latency budget per lookup (None = wait for the model)
+    deadline: Option<Duration>,
+    /// Lookups answered by the stub after missing the deadline
+    degraded: AtomicU64,
+}
+
+impl InferencePool {
+    /// Start `workers` threads sharing a queue of `capacity` jobs
+    pub fn new(library: Arc<PatternLibrary>, workers: usize, capacity: usize) -> Result<Self> {
+        let (jobs, rx) = mpsc::channel::<Job>(capacity.max(1));
+        let rx = Arc::new(Mutex::new(rx));
+        for i in 0..workers.max(1) {
+            let rx = rx.clone();
+            let library = library.clone();
+            std::thread::Builder::new()
+                .name(format!("inference-{}", i))
+                .spawn(move || loop {
+                    // The lock is only held while waiting for the next job
+                    let job = rx.lock().unwrap_or_else(|e| e.into_inner()).blocking_recv();
+                    match job {
+                        Some(job) => job(&library),
+                        None => break,
+                    }
+                })?;
+        }
+        Ok(Self {
+            library,
+            jobs,
+            pending: Arc::new(AtomicUsize::new(0)),
+            shed_policy: ShedPolicy::Block,
+            shed: AtomicU64::new(0),
+            deadline: None,
+            degraded: AtomicU64::new(0),
+        })
+    }
+
+    /// Answer lookups slower than `deadline` with the stub model
+    pub fn with_deadline(mut self, deadline: Option<Duration>) -> Self {
+        self.deadline = deadline;
+        self
+    }
+
+    pub fn deadline(&self) -> Option<Duration> {
+        self.deadline
+    }
+
+    /// Lookups that missed the deadline and were answered by the stub
+    pub fn degraded_inferences(&self) -> u64 {
+        self.degraded.load(Ordering::Relaxed)
+    }
+
+    /// Behaviour when the queue is full (default [`ShedPolicy::Block`])
+    pub fn with_shed_policy(mut self, policy: ShedPolicy) -> Self {
+        self.shed_policy = policy;
+        self
+    }
+
+    pub fn shed_policy(&self) -> ShedPolicy {
+        self.shed_policy
+    }
+
+    /// Jobs turned away because the queue was full
+    pub fn shed(&self) -> u64 {
+        self.shed.load(Ordering::Relaxed)
+    }
+
+    pub fn library(&self) -> &Arc<PatternLibrary> {
+        &self.library
+    }
+
+    /// Jobs queued or running
+    pub fn pending(&self) -> usize {
+        self.pending.load(Ordering::Relaxed)
+    }
+
+    /// Run `f` against the library on a worker thread. Fails with
+    /// [`InferenceShed`] when the queue is full and the policy gives up.
+    pub async fn run<T, F>(&self, f: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&PatternLibrary) -> T + Send + 'static,
+    {
+        let (tx, rx) = oneshot::channel();
+        self.pending.fetch_add(1, Ordering::Relaxed);
+        // Released when the job finishes or is dropped unsent
+        let guard = PendingGuard(self.pending.clone());
+        let job: Job = Box::new(move |lib| {
+            // The caller gave up (deadline or drop) before a worker got here
+            if tx.is_closed() {
+                return;
+            }
+            let result = f(lib);
+            drop(guard);
+            let _ = tx.send(result);
+        });
+        let stopped = || anyhow!("inference workers have stopped");
+        match self.shed_policy {
+            ShedPolicy::Block => self.jobs.send(job).await.map_err(|_| stopped())?,
+            ShedPolicy::Reject => match self.jobs.try_send(job) {
+                Ok(()) => {}
+                Err(TrySendError::Full(_)) => return Err(self.shed_job()),
+                Err(TrySendError::Closed(_)) => return Err(stopped()),
+            },
+            ShedPolicy::Wait(wait) => match self.jobs.send_timeout(job, wait).await {
+                Ok(()) => {}
+                Err(SendTimeoutError::Timeout(_)) => return Err(self.shed_job()),
+                Err(SendTimeoutError::Closed(_)) => return Err(stopped()),
+            },
+        }
+        rx.await.map_err(|_| anyhow!("inference worker dropped the job"))
+    }
+
+    fn shed_job(&self) -> anyhow::Error {
+        self.shed.fetch_add(1, Ordering::Relaxed);
+        InferenceShed.into()
+    }
+
+    /// Async [`PatternLibrary::lookup_or_infer`]: known patterns resolve
+    /// immediately, unknown ones are inferred on a worker
+    pub async fn lookup_or_infer(&self, pattern_name: &str, features: Option<&[f64]>) -> Result<PatternMeta> {
+        self.lookup_or_infer_for(pattern_name, None, features).await
+    }
+
+    /// Async [`PatternLibrary::lookup_or_infer_for`]
+    pub async fn lookup_or_infer_for(
+        &self,
+        pattern_name: &str,
+        symbol: Option<&str>,
+        features: Option<&[f64]>,
+    ) -> Result<PatternMeta> {
+        if self.library.is_known(pattern_name) {
+            return self.library.lookup_or_infer(pattern_name, None);
+        }
+        let name = pattern_name.to_string();
+        let symbol = symbol.map(str::to_string);
+        let features = features.map(<[f64]>::to_vec);
+        let fallback = features.clone().unwrap_or_default();
+        let lookup = self.run(move |lib| lib.lookup_or_infer_for(&name, symbol.as_deref(), features.as_deref()));
+        let Some(deadline) = self.deadline else {
+            return lookup.await?;
+        };
+        match tokio::time::timeout(deadline, lookup).await {
+            Ok(result) => result?,
+            Err(_) => {
+                self.degraded.fetch_add(1, Ordering::Relaxed);
+                debug!("Inference for {} exceeded {:?}; using the stub model", pattern_name, deadline);
+                Ok(PatternLibrary::stub_meta(pattern_name, &fallback))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::onnx_client::default_model_stub;
+    use crate::patterns::STUB_MODEL_ID;
+    use std::path::Path;
+
+    fn pool(workers: usize) -> InferencePool {
+        let lib = Arc::new(PatternLibrary::new(Path::new("dummy.onnx")).unwrap());
+        InferencePool::new(lib, workers, 4).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_matches_sync_lookup() {
+        let pool = pool(2);
+        let features = [0.4, -0.1, 0.2];
+        let async_meta = pool.lookup_or_infer("mystery_pattern", Some(&features)).await.unwrap();
+        let sync_meta = pool.library().lookup_or_infer("mystery_pattern", Some(&features)).unwrap();
+        assert_eq!(async_meta, sync_meta);
+        assert_eq!(pool.lookup_or_infer("double_top:60s", None).await.unwrap().name, "double_top");
+        assert_eq!(pool.pending(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_runs_off_the_runtime_thread() {
+        let pool = pool(1);
+        let caller = std::thread::current().id();
+        let worker = pool.run(|_| std::thread::current().id()).await.unwrap();
+        assert_ne!(caller, worker);
+
+        // more concurrent jobs than queue slots all complete
+        let pool = Arc::new(pool);
+        let handles: Vec<_> = (0..16)
+            .map(|i| {
+                let pool = pool.clone();
+                tokio::spawn(async move { pool.run(move |_| i).await.unwrap() })
+            })
+            .collect();
+        for (i, handle) in handles.into_iter().enumerate() {
+            assert_eq!(handle.await.unwrap(), i);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_full_queue_sheds_by_policy() {
+        // one worker held busy and one job queued fill a one-slot queue
+        let busy = |policy| {
+            let lib = Arc::new(PatternLibrary::new(Path::new("dummy.onnx")).unwrap());
+            let pool = Arc::new(InferencePool::new(lib, 1, 1).unwrap().with_shed_policy(policy));
+            let (release, hold) = std::sync::mpsc::channel::<()>();
+            let (started_tx, started) = oneshot::channel();
+            let running = {
+                let pool = pool.clone();
+                tokio::spawn(async move {
+                    pool.run(move |_| {
+                        let _ = started_tx.send(());
+                        hold.recv().ok()
+                    })
+                    .await
+                })
+            };
+            (pool, release, started, running)
+        };
+
+        for policy in [ShedPolicy::Reject, ShedPolicy::Wait(Duration::from_millis(20))] {
+            let (pool, release, started, running) = busy(policy);
+            started.await.unwrap();
+            let queued = {
+                let pool = pool.clone();
+                tokio::spawn(async move { pool.run(|_| 1).await })
+            };
+            while pool.pending() < 2 {
+                tokio::task::yield_now().await;
+            }
+            let err = pool.lookup_or_infer("mystery", Some(&[0.1])).await.unwrap_err();
+            assert!(err.is::<InferenceShed>(), "{:?}: {}", policy, err);
+            assert_eq!(pool.shed(), 1);
+            // known patterns never touch the queue
+            assert!(pool.lookup_or_infer("double_top", None).await.is_ok());
+
+            release.send(()).unwrap();
+            assert!(running.await.unwrap().is_ok());
+            assert_eq!(queued.await.unwrap().unwrap(), 1);
+        }
+
+        assert_eq!("Reject".parse::<ShedPolicy>().unwrap(), ShedPolicy::Reject);
+        let wait = "wait".parse::<ShedPolicy>().unwrap().with_wait(Duration::from_secs(1));
+        assert_eq!(wait, ShedPolicy::Wait(Duration::from_secs(1)));
+        assert_eq!(ShedPolicy::Block.with_wait(Duration::from_secs(1)), ShedPolicy::Block);
+        assert!("drop".parse::<ShedPolicy>().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_deadline_falls_back_to_stub() {
+        let lib = Arc::new(PatternLibrary::new(Path::new("dummy.onnx")).unwrap());
+        let pool = Arc::new(InferencePool::new(lib, 1, 4).unwrap().with_deadline(Some(Duration::from_millis(20))));
+        // hold the only worker so the lookup waits in the queue past its deadline
+        let (release, hold) = std::sync::mpsc::channel::<()>();
+        let busy = {
+            let pool = pool.clone();
+            tokio::spawn(async move { pool.run(move |_| hold.recv().ok()).await })
+        };
+        while pool.pending() < 1 {
+            tokio::task::yield_now().await;
+        }
+        let features = [0.5, -0.1];
+        let meta = pool.lookup_or_infer("mystery", Some(&features)).await.unwrap();
+        assert_eq!(meta.model_id.as_deref(), Some(STUB_MODEL_ID));
+        assert_eq!(meta.polarity, default_model_stub(&features));
+        assert_eq!(pool.degraded_inferences(), 1);
+
+        // the abandoned job is skipped once the worker is free
+        release.send(()).unwrap();
+        busy.await.unwrap().unwrap();
+        while pool.pending() > 0 {
+            tokio::task::yield_now().await;
+        }
+        let meta = pool.lookup_or_infer("mystery", Some(&features)).await.unwrap();
+        assert_eq!(meta.model_id.as_deref(), Some("dummy.onnx"));
+        assert_eq!(pool.degraded_inferences(), 1);
+    }
+}
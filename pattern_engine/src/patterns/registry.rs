@@ -0,0 +1,289 @@
+//! Named models for inferring unknown patterns.
+//!
+//! The library always has a `default` model (the one at `MODEL_PATH`). More
+//! models can be registered under their own names, e.g. one per pattern
+//! family or asset class, and [`ModelRoute`]s decide which one scores a
+//! pattern seen on a symbol. Routes are tried in order and the first match
+//! wins; anything unmatched goes to the default model. A
+//! [`CanarySplit`] then diverts a share of one model's traffic to another.
+//!
+//! Routes are written `selector=model`, comma-separated, where the selector
+//! is `pattern:<glob>`, `symbol:<glob>` or both joined by `+`. A glob is an
+//! exact name or a prefix ending in `*`:
+//!
+//! ```text
+//! pattern:harmonic_*=harmonic,symbol:BTC/*=crypto,pattern:iceberg+symbol:ES*=futures_flow
+//! ```
+
+use anyhow::{anyhow, bail, Result};
+use std::collections::BTreeMap;
+
+use super::canary::CanarySplit;
+use crate::inference_backend::InferenceBackend;
+use crate::inference_metrics::ModelStats;
+use crate::onnx_client::WarmupReport;
+
+/// Name of the model loaded from the library's model path
+pub const DEFAULT_MODEL: &str = "default";
+
+/// `*` alone, a prefix ending in `*`, or an exact name
+fn glob_match(glob: &str, text: &str) -> bool {
+    match glob.strip_suffix('*') {
+        Some(prefix) => text.starts_with(prefix),
+        None => glob == text,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModelRoute {
+    pub pattern: Option<String>,
+    pub symbol: Option<String>,
+    pub model: String,
+}
+
+impl ModelRoute {
+    /// Whether `pattern` (without interval suffix) seen on `symbol` takes this
+    /// route; a symbol selector never matches when the symbol is unknown
+    pub fn matches(&self, pattern: &str, symbol: Option<&str>) -> bool {
+        let pattern_ok = self.pattern.as_deref().is_none_or(|g| glob_match(g, pattern));
+        let symbol_ok = match (&self.symbol, symbol) {
+            (None, _) => true,
+            (Some(g), Some(s)) => glob_match(g, s),
+            (Some(_), None) => false,
+        };
+        pattern_ok && symbol_ok
+    }
+
+    /// Parse `selector=model,...`; see the module docs
+    pub fn parse_list(spec: &str) -> Result<Vec<Self>> {
+        let mut routes = Vec::new();
+        for entry in spec.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let (selector, model) = entry
+                .rsplit_once('=')
+                .ok_or_else(|| anyhow!("model route '{}' must be selector=model", entry))?;
+            let mut route = ModelRoute { pattern: None, symbol: None, model: model.trim().to_string() };
+            for part in selector.split('+') {
+                match part.trim().split_once(':') {
+                    Some(("pattern", glob)) if !glob.trim().is_empty() => route.pattern = Some(glob.trim().to_string()),
+                    Some(("symbol", glob)) if !glob.trim().is_empty() => route.symbol = Some(glob.trim().to_string()),
+                    _ => bail!("model route '{}': selector must be pattern:<glob> and/or symbol:<glob>", entry),
+                }
+            }
+            if route.model.is_empty() {
+                bail!("model route '{}' names no model", entry);
+            }
+            routes.push(route);
+        }
+        Ok(routes)
+    }
+}
+
+/// `name=path` pairs, comma-separated, naming models to register
+pub fn parse_named_models(spec: &str) -> Result<Vec<(String, String)>> {
+    spec.split(',')
+        .map(str::trim)
+        .filter(|e| !e.is_empty())
+        .map(|entry| match entry.split_once('=') {
+            Some((name, path)) if !name.trim().is_empty() && !path.trim().is_empty() => {
+                Ok((name.trim().to_string(), path.trim().to_string()))
+            }
+            _ => Err(anyhow!("model '{}' must be name=path", entry)),
+        })
+        .collect()
+}
+
+/// The default model plus any named ones, and the routes between them
+pub struct ModelRegistry {
+    models: BTreeMap<String, Box<dyn InferenceBackend>>,
+    routes: Vec<ModelRoute>,
+    canary: Option<CanarySplit>,
+}
+
+impl ModelRegistry {
+    pub fn new(default: Box<dyn InferenceBackend>) -> Self {
+        Self { models: BTreeMap::from([(DEFAULT_MODEL.to_string(), default)]), routes: Vec::new(), canary: None }
+    }
+
+    /// Register `client` as `name`, replacing a model of the same name
+    pub fn insert(&mut self, name: &str, client: Box<dyn InferenceBackend>) {
+        self.models.insert(name.to_string(), client);
+    }
+
+    /// Replace the routes; every route must name a registered model
+    pub fn set_routes(&mut self, routes: Vec<ModelRoute>) -> Result<()> {
+        if let Some(route) = routes.iter().find(|r| !self.models.contains_key(&r.model)) {
+            bail!("model route targets unknown model '{}' (known: {:?})", route.model, self.names());
+        }
+        self.routes = routes;
+        Ok(())
+    }
+
+    /// Divert part of the base model's traffic; both models must be registered
+    pub fn set_canary(&mut self, split: CanarySplit) -> Result<()> {
+        for name in [&split.base, &split.canary] {
+            if !self.models.contains_key(name) {
+                bail!("canary names unknown model '{}' (known: {:?})", name, self.names());
+            }
+        }
+        self.canary = Some(split);
+        Ok(())
+    }
+
+    pub fn canary(&self) -> Option<&CanarySplit> {
+        self.canary.as_ref()
+    }
+
+    pub fn routes(&self) -> &[ModelRoute] {
+        &self.routes
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.models.keys().map(String::as_str).collect()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn InferenceBackend> {
+        self.models.get(name).map(Box::as_ref)
+    }
+
+    pub fn default_model(&self) -> &dyn InferenceBackend {
+        self.models[DEFAULT_MODEL].as_ref()
+    }
+
+    /// Name and model scoring `pattern` on `symbol`
+    pub fn select(&self, pattern: &str, symbol: Option<&str>) -> (&str, &dyn InferenceBackend) {
+        let mut name = self
+            .routes
+            .iter()
+            .find(|r| r.matches(pattern, symbol))
+            .map(|r| r.model.as_str())
+            .unwrap_or(DEFAULT_MODEL);
+        if let Some(canary) = self.canary.as_ref().filter(|c| c.base == name) {
+            if canary.take() {
+                name = &canary.canary;
+            }
+        }
+        let (name, client) = self.models.get_key_value(name).expect("routes name registered models");
+        (name, client.as_ref())
+    }
+
+    /// Model file or URL of `name`
+    pub fn location(&self, name: &str) -> Option<String> {
+        self.get(name).map(|m| m.location())
+    }
+
+    /// Calls all models answered locally because their backend failed
+    pub fn fallbacks(&self) -> u64 {
+        self.models.values().map(|m| m.fallbacks()).sum()
+    }
+
+    /// Inference telemetry of each model that tracks it
+    pub fn stats(&self) -> BTreeMap<String, ModelStats> {
+        self.models.iter().filter_map(|(name, m)| Some((name.clone(), m.stats()?))).collect()
+    }
+
+    /// Warm-up timing of each model that ran one
+    pub fn warmups(&self) -> BTreeMap<String, WarmupReport> {
+        self.models.iter().filter_map(|(name, m)| Some((name.clone(), m.warmup()?))).collect()
+    }
+
+    /// Reload every model; stops at the first failure
+    pub fn reload_all(&self) -> Result<()> {
+        for (name, client) in &self.models {
+            client.reload().map_err(|e| anyhow!("model {}: {:#}", name, e))?;
+        }
+        Ok(())
+    }
+
+    /// Reload models whose files were replaced, returning their names. Every
+    /// model is checked even if one fails to load.
+    pub fn reload_changed(&self) -> Result<Vec<String>> {
+        let mut reloaded = Vec::new();
+        let mut errors = Vec::new();
+        for (name, client) in &self.models {
+            match client.reload_if_changed() {
+                Ok(true) => reloaded.push(name.clone()),
+                Ok(false) => {}
+                Err(e) => errors.push(format!("model {}: {:#}", name, e)),
+            }
+        }
+        if !errors.is_empty() {
+            bail!("{}", errors.join("; "));
+        }
+        Ok(reloaded)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::onnx_client::OnnxClient;
+
+    fn client(name: &str) -> Box<dyn InferenceBackend> {
+        Box::new(OnnxClient::new(std::path::Path::new(name)).unwrap())
+    }
+
+    #[test]
+    fn test_parse_routes() {
+        let routes = ModelRoute::parse_list("pattern:harmonic_*=harmonic, symbol:BTC/*=crypto,pattern:iceberg+symbol:ES*=flow").unwrap();
+        assert_eq!(routes.len(), 3);
+        assert_eq!(routes[0], ModelRoute { pattern: Some("harmonic_*".into()), symbol: None, model: "harmonic".into() });
+        assert_eq!((routes[2].pattern.as_deref(), routes[2].symbol.as_deref()), (Some("iceberg"), Some("ES*")));
+        assert!(ModelRoute::parse_list("").unwrap().is_empty());
+        for bad in ["harmonic", "pattern:=x", "sector:tech=x", "pattern:a="] {
+            assert!(ModelRoute::parse_list(bad).is_err(), "{}", bad);
+        }
+
+        let models = parse_named_models("crypto=models/crypto.onnx, harmonic = h.onnx").unwrap();
+        assert_eq!(models[1], ("harmonic".to_string(), "h.onnx".to_string()));
+        assert!(parse_named_models("crypto").is_err());
+    }
+
+    #[test]
+    fn test_routes_select_models() {
+        let mut registry = ModelRegistry::new(client("default.onnx"));
+        registry.insert("harmonic", client("harmonic.onnx"));
+        registry.insert("crypto", client("crypto.onnx"));
+        let routes = ModelRoute::parse_list("pattern:harmonic_*=harmonic,symbol:BTC/*=crypto").unwrap();
+        registry.set_routes(routes).unwrap();
+
+        assert_eq!(registry.select("harmonic_gartley", Some("BTC/USD")).0, "harmonic");
+        assert_eq!(registry.select("mystery", Some("BTC/USD")).0, "crypto");
+        assert_eq!(registry.select("mystery", Some("AAPL")).0, DEFAULT_MODEL);
+        assert_eq!(registry.select("mystery", None).0, DEFAULT_MODEL);
+        registry.select("harmonic_bat", None).1.infer(&[0.5]).unwrap();
+        let stats = registry.stats();
+        assert_eq!((stats["harmonic"].calls, stats["default"].calls), (1, 0));
+        assert_eq!(registry.location("crypto").as_deref(), Some("crypto.onnx"));
+        assert_eq!(registry.names(), vec!["crypto", "default", "harmonic"]);
+
+        let unknown = ModelRoute::parse_list("symbol:ES*=futures").unwrap();
+        assert!(registry.set_routes(unknown).is_err());
+        assert_eq!(registry.routes().len(), 2);
+    }
+
+    #[test]
+    fn test_canary_diverts_share_of_base() {
+        let mut registry = ModelRegistry::new(client("default.onnx"));
+        registry.insert("v2", client("v2.onnx"));
+        registry.insert("harmonic", client("harmonic.onnx"));
+        registry.set_routes(ModelRoute::parse_list("pattern:harmonic_*=harmonic").unwrap()).unwrap();
+        assert!(registry.set_canary(CanarySplit::parse("v3:10").unwrap()).is_err());
+        registry.set_canary(CanarySplit::parse("v2:25").unwrap()).unwrap();
+
+        let served: Vec<&str> = (0..8).map(|_| registry.select("mystery", None).0).collect();
+        assert_eq!(served.iter().filter(|&&n| n == "v2").count(), 2);
+        assert_eq!(registry.select("harmonic_bat", None).0, "harmonic");
+        assert_eq!(registry.canary().unwrap().stats().total, 8);
+    }
+
+    #[test]
+    fn test_warmups_by_model() {
+        let config = crate::onnx_client::OnnxConfig::default().with_feature_widths([6]);
+        let warmed = OnnxClient::with_config(std::path::Path::new("harmonic.onnx"), &config).unwrap();
+        let mut registry = ModelRegistry::new(client("default.onnx"));
+        registry.insert("harmonic", Box::new(warmed));
+        let warmups = registry.warmups();
+        assert_eq!(warmups.keys().collect::<Vec<_>>(), vec!["harmonic"]);
+        assert_eq!(warmups["harmonic"].widths, vec![6]);
+    }
+}
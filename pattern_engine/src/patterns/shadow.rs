@@ -0,0 +1,100 @@
+//! Shadow evaluation of a candidate model.
+//!
+//! A shadow model scores every unknown pattern alongside the model that
+//! serves it. Its score never affects the signal; the two are compared and
+//! summarized in [`ShadowStats`] (and, if enabled, attached to the pattern as
+//! [`ShadowScore`]) so a retrained model can be checked against live traffic
+//! before it is promoted.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+
+/// Shadow model's opinion on one inferred pattern
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ShadowScore {
+    pub model: String,
+    pub score: f64,
+    /// Shadow score minus the served score
+    pub divergence: f64,
+}
+
+/// Running comparison of shadow and served scores
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ShadowStats {
+    pub model: String,
+    /// Inferences scored by both models
+    pub compared: u64,
+    /// Shadow inferences that failed
+    pub errors: u64,
+    pub mean_primary: f64,
+    pub mean_shadow: f64,
+    pub mean_abs_divergence: f64,
+    pub max_abs_divergence: f64,
+    /// Comparisons where the two scores map to different actions
+    pub action_disagreements: u64,
+}
+
+/// Accumulates [`ShadowStats`] for one shadow model
+#[derive(Debug)]
+pub struct ShadowTracker {
+    /// Attach a [`ShadowScore`] to inferred patterns
+    pub in_meta: bool,
+    stats: Mutex<ShadowStats>,
+}
+
+impl ShadowTracker {
+    pub fn new(model: &str, in_meta: bool) -> Self {
+        Self { in_meta, stats: Mutex::new(ShadowStats { model: model.to_string(), ..Default::default() }) }
+    }
+
+    pub fn model(&self) -> String {
+        self.stats.lock().unwrap_or_else(|e| e.into_inner()).model.clone()
+    }
+
+    /// Record a comparison; `agree` is whether both scores give the same action
+    pub fn record(&self, primary: f64, shadow: f64, agree: bool) -> ShadowScore {
+        let mut stats = self.stats.lock().unwrap_or_else(|e| e.into_inner());
+        stats.compared += 1;
+        let n = stats.compared as f64;
+        let divergence = shadow - primary;
+        stats.mean_primary += (primary - stats.mean_primary) / n;
+        stats.mean_shadow += (shadow - stats.mean_shadow) / n;
+        stats.mean_abs_divergence += (divergence.abs() - stats.mean_abs_divergence) / n;
+        stats.max_abs_divergence = stats.max_abs_divergence.max(divergence.abs());
+        if !agree {
+            stats.action_disagreements += 1;
+        }
+        ShadowScore { model: stats.model.clone(), score: shadow, divergence }
+    }
+
+    pub fn record_error(&self) {
+        self.stats.lock().unwrap_or_else(|e| e.into_inner()).errors += 1;
+    }
+
+    pub fn stats(&self) -> ShadowStats {
+        self.stats.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tracks_divergence() {
+        let tracker = ShadowTracker::new("v2", false);
+        let score = tracker.record(0.5, 0.3, true);
+        assert_eq!(score.model, "v2");
+        assert!((score.divergence + 0.2).abs() < 1e-12);
+        tracker.record(-0.1, 0.5, false);
+        tracker.record_error();
+
+        let stats = tracker.stats();
+        assert_eq!((stats.compared, stats.errors, stats.action_disagreements), (2, 1, 1));
+        assert!((stats.mean_primary - 0.2).abs() < 1e-12);
+        assert!((stats.mean_shadow - 0.4).abs() < 1e-12);
+        assert!((stats.mean_abs_divergence - 0.4).abs() < 1e-12);
+        assert!((stats.max_abs_divergence - 0.6).abs() < 1e-12);
+    }
+}
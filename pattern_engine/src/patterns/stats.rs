@@ -0,0 +1,396 @@
+//! Empirical hit-rate tracking for emitted patterns.
+//!
+//! Every emitted signal is remembered with the price at emission. Once the
+//! forward horizon has passed, the signed forward return decides whether the
+//! signal was a hit. Hit rates calibrate `PatternMeta.confidence`: the
+//! configured confidence acts as a prior worth `prior_weight` observations,
+//! so patterns move toward their empirical hit rate as samples accumulate.
+//! Additional horizons are tracked for reporting only.
+//!
+//! [`ForwardReturns`] holds the entries waiting for their horizon; the
+//! training export uses it for its rows as well.
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::path::Path;
+
+/// Outcome counters for one pattern at one horizon
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct HitStats {
+    pub samples: u64,
+    pub hits: u64,
+    /// Sum of signed forward returns (positive = moved in the signal's direction)
+    pub sum_return: f64,
+}
+
+impl HitStats {
+    pub fn hit_rate(&self) -> Option<f64> {
+        (self.samples > 0).then(|| self.hits as f64 / self.samples as f64)
+    }
+
+    pub fn mean_return(&self) -> Option<f64> {
+        (self.samples > 0).then(|| self.sum_return / self.samples as f64)
+    }
+}
+
+/// Emissions and per-horizon outcomes of one pattern
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PatternOutcomes {
+    /// Emitted signals, including those that could not be priced
+    pub occurrences: u64,
+    /// Resolved outcomes keyed by horizon in whole seconds
+    pub horizons: BTreeMap<u64, HitStats>,
+}
+
+/// Reported statistics for one horizon
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HorizonSummary {
+    pub samples: u64,
+    pub win_rate: Option<f64>,
+    pub mean_return: Option<f64>,
+}
+
+/// Reported statistics for one pattern, horizons keyed like `300s`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PatternSummary {
+    pub occurrences: u64,
+    pub horizons: BTreeMap<String, HorizonSummary>,
+}
+
+/// Entries waiting for a later price, per symbol in arrival order, along
+/// with each symbol's last traded price
+#[derive(Debug, Clone)]
+pub struct ForwardReturns<T> {
+    pending: HashMap<String, VecDeque<Waiting<T>>>,
+    last_price: HashMap<String, f64>,
+    len: usize,
+}
+
+#[derive(Debug, Clone)]
+struct Waiting<T> {
+    entry_time: f64,
+    done: bool,
+    item: T,
+}
+
+impl<T> Default for ForwardReturns<T> {
+    fn default() -> Self {
+        Self { pending: HashMap::new(), last_price: HashMap::new(), len: 0 }
+    }
+}
+
+impl<T> ForwardReturns<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn last_price(&self, symbol: &str) -> Option<f64> {
+        self.last_price.get(symbol).copied()
+    }
+
+    /// Entries still waiting
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Hold `item`, entered at `entry_time`, until it is resolved
+    pub fn push(&mut self, symbol: &str, entry_time: f64, item: T) {
+        self.pending.entry(symbol.to_string()).or_default().push_back(Waiting { entry_time, done: false, item });
+        self.len += 1;
+    }
+
+    /// Remove the oldest entry of the symbol with the most waiting
+    pub fn evict_busiest(&mut self) -> Option<T> {
+        let queue = self.pending.values_mut().max_by_key(|q| q.len())?;
+        let waiting = queue.pop_front()?;
+        self.len -= 1;
+        Some(waiting.item)
+    }
+
+    /// Feed a traded price. Entries at least `min_age` old are passed to
+    /// `resolve` with the price and their age, oldest first, until one is
+    /// too young; it returns true once an entry needs no later price. Those
+    /// are removed, oldest first, and returned.
+    pub fn on_price(
+        &mut self,
+        symbol: &str,
+        price: f64,
+        timestamp: f64,
+        min_age: f64,
+        mut resolve: impl FnMut(&mut T, f64, f64) -> bool,
+    ) -> Vec<T> {
+        self.last_price.insert(symbol.to_string(), price);
+        let Some(queue) = self.pending.get_mut(symbol) else {
+            return Vec::new();
+        };
+        for w in queue.iter_mut().filter(|w| !w.done) {
+            let age = timestamp - w.entry_time;
+            if age < min_age {
+                break;
+            }
+            w.done = resolve(&mut w.item, price, age);
+        }
+        let mut finished = Vec::new();
+        while queue.front().is_some_and(|w| w.done) {
+            finished.extend(queue.pop_front().map(|w| w.item));
+        }
+        self.len -= finished.len();
+        finished
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Outcome {
+    pattern: String,
+    direction: f64,
+    entry_price: f64,
+    /// Horizons already resolved, in ascending order
+    resolved: usize,
+}
+
+/// Tracks forward returns per pattern and calibrates confidence
+#[derive(Debug, Clone)]
+pub struct PatternStats {
+    /// Horizon whose hit rate calibrates confidence
+    horizon_secs: f64,
+    /// Every tracked horizon, ascending and including `horizon_secs`
+    horizons: Vec<f64>,
+    prior_weight: f64,
+    stats: HashMap<String, PatternOutcomes>,
+    pending: ForwardReturns<Outcome>,
+}
+
+impl PatternStats {
+    /// `horizon_secs` is the forward-return window, `prior_weight` the number
+    /// of observations the configured confidence is worth
+    pub fn new(horizon_secs: f64, prior_weight: f64) -> Self {
+        Self {
+            horizon_secs,
+            horizons: vec![horizon_secs],
+            prior_weight: prior_weight.max(0.0),
+            stats: HashMap::new(),
+            pending: ForwardReturns::new(),
+        }
+    }
+
+    /// Also report forward returns over `horizons` (seconds)
+    pub fn with_horizons(mut self, horizons: &[f64]) -> Self {
+        self.horizons.extend(horizons.iter().copied().filter(|h| *h > 0.0));
+        self.horizons.sort_by(f64::total_cmp);
+        self.horizons.dedup_by(|a, b| horizon_key(*a) == horizon_key(*b));
+        self
+    }
+
+    pub fn horizons(&self) -> &[f64] {
+        &self.horizons
+    }
+
+    /// Feed a traded price; resolves outcomes whose horizons have elapsed
+    pub fn on_price(&mut self, symbol: &str, price: f64, timestamp: f64) {
+        let (horizons, stats) = (&self.horizons, &mut self.stats);
+        self.pending.on_price(symbol, price, timestamp, horizons[0], |o, price, age| {
+            let ret = o.direction * (price - o.entry_price) / o.entry_price;
+            while let Some(&h) = horizons.get(o.resolved) {
+                if age < h {
+                    break;
+                }
+                let entry = stats.entry(o.pattern.clone()).or_default();
+                let s = entry.horizons.entry(horizon_key(h)).or_default();
+                s.samples += 1;
+                if ret > 0.0 {
+                    s.hits += 1;
+                }
+                s.sum_return += ret;
+                o.resolved += 1;
+            }
+            o.resolved >= horizons.len()
+        });
+    }
+
+    /// Remember an emitted signal at the symbol's last traded price.
+    /// Every call counts as an occurrence; neutral scores and symbols without
+    /// a price are not tracked for outcomes.
+    pub fn record(&mut self, symbol: &str, pattern: &str, score: f64, timestamp: f64) {
+        self.stats.entry(pattern.to_string()).or_default().occurrences += 1;
+        let Some(entry_price) = self.pending.last_price(symbol) else {
+            return;
+        };
+        if score == 0.0 || entry_price <= 0.0 {
+            return;
+        }
+        let outcome = Outcome { pattern: pattern.to_string(), direction: score.signum(), entry_price, resolved: 0 };
+        self.pending.push(symbol, timestamp, outcome);
+    }
+
+    /// Outcomes at the calibration horizon
+    pub fn get(&self, pattern: &str) -> Option<&HitStats> {
+        self.outcomes(pattern)?.horizons.get(&horizon_key(self.horizon_secs))
+    }
+
+    pub fn outcomes(&self, pattern: &str) -> Option<&PatternOutcomes> {
+        self.stats.get(pattern)
+    }
+
+    pub fn all(&self) -> &HashMap<String, PatternOutcomes> {
+        &self.stats
+    }
+
+    /// Occurrences, win rate and mean forward return per pattern and horizon
+    pub fn summary(&self) -> BTreeMap<String, PatternSummary> {
+        self.stats
+            .iter()
+            .map(|(pattern, o)| {
+                let horizons = o
+                    .horizons
+                    .iter()
+                    .map(|(h, s)| {
+                        let summary = HorizonSummary { samples: s.samples, win_rate: s.hit_rate(), mean_return: s.mean_return() };
+                        (format!("{}s", h), summary)
+                    })
+                    .collect();
+                (pattern.clone(), PatternSummary { occurrences: o.occurrences, horizons })
+            })
+            .collect()
+    }
+
+    /// Blend `prior` confidence with the observed hit rate
+    pub fn calibrate(&self, pattern: &str, prior: f64) -> f64 {
+        match self.get(pattern) {
+            Some(s) if s.samples > 0 => {
+                let w = self.prior_weight;
+                ((prior * w + s.hits as f64) / (w + s.samples as f64)).clamp(0.0, 1.0)
+            }
+            _ => prior,
+        }
+    }
+
+    /// Replace the resolved counters, e.g. with those of an imported library
+    pub fn restore(&mut self, stats: impl IntoIterator<Item = (String, PatternOutcomes)>) {
+        self.stats = stats.into_iter().collect();
+    }
+
+    /// Persist resolved counters (pending outcomes are not saved)
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let data = serde_json::to_string_pretty(&self.stats)?;
+        std::fs::write(path, data).map_err(|e| anyhow!("failed to write {}: {}", path.display(), e))
+    }
+
+    /// Restore counters saved by [`PatternStats::save`]. Files from before
+    /// multi-horizon tracking hold one [`HitStats`] per pattern and load into
+    /// the calibration horizon.
+    pub fn load(&mut self, path: &Path) -> Result<()> {
+        let data = std::fs::read_to_string(path).map_err(|e| anyhow!("failed to read {}: {}", path.display(), e))?;
+        self.stats = match serde_json::from_str(&data) {
+            Ok(stats) => stats,
+            Err(e) => {
+                let legacy: HashMap<String, HitStats> = serde_json::from_str(&data).map_err(|_| e)?;
+                let key = horizon_key(self.horizon_secs);
+                legacy
+                    .into_iter()
+                    .map(|(pattern, s)| {
+                        let outcomes = PatternOutcomes { occurrences: s.samples, horizons: BTreeMap::from([(key, s)]) };
+                        (pattern, outcomes)
+                    })
+                    .collect()
+            }
+        };
+        Ok(())
+    }
+}
+
+fn horizon_key(secs: f64) -> u64 {
+    secs.round() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_forward_returns_and_calibration() {
+        let mut stats = PatternStats::new(60.0, 10.0);
+        stats.record("AAPL", "ema_crossover", 0.5, 0.0); // no price yet: ignored
+        stats.on_price("AAPL", 100.0, 0.0);
+        stats.record("AAPL", "ema_crossover", 0.5, 0.0);
+        stats.record("AAPL", "double_top:60s", -0.8, 10.0);
+
+        stats.on_price("AAPL", 101.0, 30.0);
+        assert!(stats.get("ema_crossover").is_none());
+        stats.on_price("AAPL", 102.0, 70.0);
+        let s = stats.get("ema_crossover").unwrap();
+        assert_eq!((s.samples, s.hits), (1, 1));
+        assert!((s.mean_return().unwrap() - 0.02).abs() < 1e-12);
+        // double top resolved too: price rose, so a miss
+        assert_eq!(stats.get("double_top:60s").unwrap().hits, 0);
+
+        // prior 0.5 worth 10 samples, one hit -> 6/11
+        assert!((stats.calibrate("ema_crossover", 0.5) - 6.0 / 11.0).abs() < 1e-12);
+        assert_eq!(stats.calibrate("unknown", 0.7), 0.7);
+    }
+
+    #[test]
+    fn test_forward_returns_release_in_order() {
+        let mut pending = ForwardReturns::new();
+        pending.push("AAPL", 0.0, "a");
+        pending.push("AAPL", 5.0, "b");
+        pending.push("MSFT", 0.0, "c");
+        assert_eq!(pending.on_price("AAPL", 100.0, 8.0, 10.0, |_, _, _| true), Vec::<&str>::new());
+        assert_eq!(pending.last_price("AAPL"), Some(100.0));
+        // a resolved entry waits behind an older unresolved one
+        let finished = pending.on_price("AAPL", 101.0, 20.0, 10.0, |item, _, _| *item == "b");
+        assert!(finished.is_empty());
+        assert_eq!(pending.on_price("AAPL", 102.0, 21.0, 10.0, |_, _, _| true), vec!["a", "b"]);
+        assert_eq!(pending.evict_busiest(), Some("c"));
+        assert!(pending.is_empty());
+    }
+
+    #[test]
+    fn test_persistence() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("stats.json");
+        let mut stats = PatternStats::new(1.0, 5.0);
+        stats.on_price("AAPL", 100.0, 0.0);
+        stats.record("AAPL", "vwap_deviation", -0.4, 0.0);
+        stats.on_price("AAPL", 99.0, 2.0);
+        stats.save(&path).unwrap();
+
+        let mut restored = PatternStats::new(1.0, 5.0);
+        restored.load(&path).unwrap();
+        assert_eq!(restored.get("vwap_deviation"), stats.get("vwap_deviation"));
+
+        // single-horizon files load into the calibration horizon
+        std::fs::write(&path, r#"{"ema_crossover": {"samples": 4, "hits": 3, "sum_return": 0.02}}"#).unwrap();
+        restored.load(&path).unwrap();
+        assert_eq!(restored.get("ema_crossover").unwrap().hits, 3);
+        assert_eq!(restored.outcomes("ema_crossover").unwrap().occurrences, 4);
+    }
+
+    #[test]
+    fn test_multiple_horizons() {
+        let mut stats = PatternStats::new(60.0, 10.0).with_horizons(&[10.0, 60.0, 0.0]);
+        assert_eq!(stats.horizons(), &[10.0, 60.0]);
+        stats.record("AAPL", "volume_spike", 0.5, 0.0); // counted, not priced
+        stats.on_price("AAPL", 100.0, 0.0);
+        stats.record("AAPL", "volume_spike", 0.5, 0.0);
+        stats.record("AAPL", "volume_spike", 0.5, 5.0);
+
+        stats.on_price("AAPL", 99.0, 12.0);
+        let o = stats.outcomes("volume_spike").unwrap();
+        assert_eq!(o.occurrences, 3);
+        assert_eq!(o.horizons[&10].samples, 1);
+        assert!(stats.get("volume_spike").is_none());
+
+        stats.on_price("AAPL", 103.0, 70.0);
+        let summary = &stats.summary()["volume_spike"];
+        assert_eq!(summary.horizons["10s"].samples, 2);
+        assert_eq!(summary.horizons["10s"].win_rate, Some(0.5));
+        assert_eq!(summary.horizons["60s"].win_rate, Some(1.0));
+        assert!((summary.horizons["60s"].mean_return.unwrap() - 0.03).abs() < 1e-12);
+        assert!(stats.pending.is_empty());
+    }
+}
@@ -0,0 +1,378 @@
+//! Structural (multi-swing) chart pattern detection.
+//!
+//! Detectors here consume confirmed [`Pivot`]s from a [`ZigZag`](super::zigzag::ZigZag)
+//! and closing prices. A formation is reported once price breaks its neckline,
+//! together with the neckline level and the measured-move target.
+
+use super::zigzag::{Pivot, PivotKind};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// A confirmed structural pattern occurrence
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StructureMatch {
+    /// Pattern name as used in the pattern library (e.g. `double_top`)
+    pub name: String,
+    /// polarity -1..1 indicating bearish (-1) to bullish (+1)
+    pub polarity: f64,
+    /// Neckline level whose break confirmed the pattern
+    pub neckline: f64,
+    /// Measured-move price target
+    pub target: f64,
+    /// Pivots forming the pattern, oldest first
+    pub pivots: Vec<Pivot>,
+    /// Timestamp of the breakout
+    pub timestamp: f64,
+}
+
+/// Straight line through two pivots, in price per second
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Trendline {
+    pub anchor_price: f64,
+    pub anchor_time: f64,
+    pub slope: f64,
+}
+
+impl Trendline {
+    /// Line through `a` and `b`; flat at their mean when they share a timestamp
+    pub fn through(a: &Pivot, b: &Pivot) -> Self {
+        if (b.timestamp - a.timestamp).abs() < f64::EPSILON {
+            return Self {
+                anchor_price: (a.price + b.price) / 2.0,
+                anchor_time: b.timestamp,
+                slope: 0.0,
+            };
+        }
+        Self {
+            anchor_price: b.price,
+            anchor_time: b.timestamp,
+            slope: (b.price - a.price) / (b.timestamp - a.timestamp),
+        }
+    }
+
+    /// Price of the line at `timestamp`
+    pub fn at(&self, timestamp: f64) -> f64 {
+        self.anchor_price + self.slope * (timestamp - self.anchor_time)
+    }
+}
+
+/// Thresholds for [`DoubleTopDetector`]
+#[derive(Debug, Clone)]
+pub struct DoubleTopConfig {
+    /// Maximum relative difference between the two peaks (or troughs)
+    pub peak_tolerance: f64,
+    /// Minimum depth of the middle swing relative to the peaks
+    pub min_depth: f64,
+}
+
+impl Default for DoubleTopConfig {
+    fn default() -> Self {
+        Self {
+            peak_tolerance: 0.015,
+            min_depth: 0.02,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Formation {
+    bearish: bool,
+    pivots: [Pivot; 3],
+    neckline: f64,
+    /// Price beyond which the formation is invalidated
+    invalidation: f64,
+}
+
+/// Double top / double bottom detector
+#[derive(Debug, Clone, Default)]
+pub struct DoubleTopDetector {
+    config: DoubleTopConfig,
+    pending: Option<Formation>,
+}
+
+impl DoubleTopDetector {
+    pub fn new(config: DoubleTopConfig) -> Self {
+        Self { config, pending: None }
+    }
+
+    /// Re-evaluate after a new pivot was confirmed
+    pub fn on_pivot(&mut self, pivots: &VecDeque<Pivot>) {
+        let n = pivots.len();
+        if n < 3 {
+            return;
+        }
+        let (a, b, c) = (pivots[n - 3], pivots[n - 2], pivots[n - 1]);
+        let cfg = &self.config;
+
+        let formation = match (a.kind, b.kind, c.kind) {
+            (PivotKind::High, PivotKind::Low, PivotKind::High) => {
+                let peak = a.price.max(c.price);
+                let similar = (a.price - c.price).abs() / peak <= cfg.peak_tolerance;
+                let deep = b.price <= peak * (1.0 - cfg.min_depth);
+                (similar && deep).then_some(Formation {
+                    bearish: true,
+                    pivots: [a, b, c],
+                    neckline: b.price,
+                    invalidation: peak * (1.0 + cfg.peak_tolerance),
+                })
+            }
+            (PivotKind::Low, PivotKind::High, PivotKind::Low) => {
+                let trough = a.price.min(c.price);
+                let similar = (a.price - c.price).abs() / trough <= cfg.peak_tolerance;
+                let deep = b.price >= trough * (1.0 + cfg.min_depth);
+                (similar && deep).then_some(Formation {
+                    bearish: false,
+                    pivots: [a, b, c],
+                    neckline: b.price,
+                    invalidation: trough * (1.0 - cfg.peak_tolerance),
+                })
+            }
+            _ => None,
+        };
+
+        // A new candidate replaces any older one; otherwise keep waiting for the break
+        if formation.is_some() {
+            self.pending = formation;
+        }
+    }
+
+    /// Check the pending formation against a closing price
+    pub fn on_close(&mut self, close: f64, timestamp: f64) -> Option<StructureMatch> {
+        let f = self.pending.as_ref()?;
+        let (broken, invalid) = if f.bearish {
+            (close < f.neckline, close > f.invalidation)
+        } else {
+            (close > f.neckline, close < f.invalidation)
+        };
+
+        if invalid {
+            self.pending = None;
+            return None;
+        }
+        if !broken {
+            return None;
+        }
+
+        let f = self.pending.take()?;
+        let extreme = (f.pivots[0].price + f.pivots[2].price) / 2.0;
+        let height = (extreme - f.neckline).abs();
+        let (name, polarity, target) = if f.bearish {
+            ("double_top", -1.0, f.neckline - height)
+        } else {
+            ("double_bottom", 1.0, f.neckline + height)
+        };
+        Some(StructureMatch {
+            name: name.to_string(),
+            polarity,
+            neckline: f.neckline,
+            target,
+            pivots: f.pivots.to_vec(),
+            timestamp,
+        })
+    }
+}
+
+/// Thresholds for [`HeadShouldersDetector`]
+#[derive(Debug, Clone)]
+pub struct HeadShouldersConfig {
+    /// Maximum relative difference between the two shoulders
+    pub shoulder_tolerance: f64,
+    /// Minimum relative height of the head above the higher shoulder
+    pub min_head_excess: f64,
+}
+
+impl Default for HeadShouldersConfig {
+    fn default() -> Self {
+        Self {
+            shoulder_tolerance: 0.03,
+            min_head_excess: 0.01,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct ShouldersFormation {
+    bearish: bool,
+    pivots: [Pivot; 5],
+    invalidation: f64,
+}
+
+impl ShouldersFormation {
+    /// Neckline through the two reaction pivots, evaluated at `timestamp`
+    fn neckline_at(&self, timestamp: f64) -> f64 {
+        Trendline::through(&self.pivots[1], &self.pivots[3]).at(timestamp)
+    }
+}
+
+/// Head and shoulders / inverse head and shoulders detector
+#[derive(Debug, Clone, Default)]
+pub struct HeadShouldersDetector {
+    config: HeadShouldersConfig,
+    pending: Option<ShouldersFormation>,
+}
+
+impl HeadShouldersDetector {
+    pub fn new(config: HeadShouldersConfig) -> Self {
+        Self { config, pending: None }
+    }
+
+    /// Re-evaluate after a new pivot was confirmed
+    pub fn on_pivot(&mut self, pivots: &VecDeque<Pivot>) {
+        let n = pivots.len();
+        if n < 5 {
+            return;
+        }
+        let p = [pivots[n - 5], pivots[n - 4], pivots[n - 3], pivots[n - 2], pivots[n - 1]];
+        let cfg = &self.config;
+        let (ls, head, rs) = (p[0].price, p[2].price, p[4].price);
+
+        let formation = match p[0].kind {
+            PivotKind::High => {
+                let shoulder = ls.max(rs);
+                let similar = (ls - rs).abs() / shoulder <= cfg.shoulder_tolerance;
+                let head_ok = head >= shoulder * (1.0 + cfg.min_head_excess);
+                (similar && head_ok).then_some(ShouldersFormation {
+                    bearish: true,
+                    pivots: p,
+                    invalidation: head,
+                })
+            }
+            PivotKind::Low => {
+                let shoulder = ls.min(rs);
+                let similar = (ls - rs).abs() / shoulder <= cfg.shoulder_tolerance;
+                let head_ok = head <= shoulder * (1.0 - cfg.min_head_excess);
+                (similar && head_ok).then_some(ShouldersFormation {
+                    bearish: false,
+                    pivots: p,
+                    invalidation: head,
+                })
+            }
+        };
+
+        if formation.is_some() {
+            self.pending = formation;
+        }
+    }
+
+    /// Check the pending formation against a closing price
+    pub fn on_close(&mut self, close: f64, timestamp: f64) -> Option<StructureMatch> {
+        let f = self.pending.as_ref()?;
+        let neckline = f.neckline_at(timestamp);
+        let (broken, invalid) = if f.bearish {
+            (close < neckline, close > f.invalidation)
+        } else {
+            (close > neckline, close < f.invalidation)
+        };
+
+        if invalid {
+            self.pending = None;
+            return None;
+        }
+        if !broken {
+            return None;
+        }
+
+        let f = self.pending.take()?;
+        let head = f.pivots[2];
+        let height = (head.price - f.neckline_at(head.timestamp)).abs();
+        let (name, polarity, target) = if f.bearish {
+            ("head_and_shoulders", -1.0, neckline - height)
+        } else {
+            ("inverse_head_and_shoulders", 1.0, neckline + height)
+        };
+        Some(StructureMatch {
+            name: name.to_string(),
+            polarity,
+            neckline,
+            target,
+            pivots: f.pivots.to_vec(),
+            timestamp,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::patterns::zigzag::ZigZag;
+
+    fn run(prices: &[f64]) -> Vec<StructureMatch> {
+        let mut zz = ZigZag::new(0.03, 16);
+        let mut det = DoubleTopDetector::default();
+        let mut out = Vec::new();
+        for (i, p) in prices.iter().enumerate() {
+            if zz.update(*p, i as f64).is_some() {
+                det.on_pivot(zz.pivots());
+            }
+            out.extend(det.on_close(*p, i as f64));
+        }
+        out
+    }
+
+    #[test]
+    fn test_double_top() {
+        let found = run(&[100.0, 105.0, 110.0, 104.0, 100.0, 105.0, 109.8, 104.0, 99.0, 96.0]);
+        assert_eq!(found.len(), 1);
+        let m = &found[0];
+        assert_eq!(m.name, "double_top");
+        assert_eq!(m.neckline, 100.0);
+        assert!((m.target - 90.1).abs() < 1e-9);
+        assert!(m.polarity < 0.0);
+    }
+
+    #[test]
+    fn test_double_bottom() {
+        let found = run(&[110.0, 105.0, 100.0, 105.0, 110.0, 105.0, 100.2, 106.0, 111.0]);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].name, "double_bottom");
+        assert_eq!(found[0].neckline, 110.0);
+        assert!(found[0].target > 110.0);
+    }
+
+    fn run_hs(prices: &[f64]) -> Vec<StructureMatch> {
+        let mut zz = ZigZag::new(0.03, 16);
+        let mut det = HeadShouldersDetector::default();
+        let mut out = Vec::new();
+        for (i, p) in prices.iter().enumerate() {
+            if zz.update(*p, i as f64).is_some() {
+                det.on_pivot(zz.pivots());
+            }
+            out.extend(det.on_close(*p, i as f64));
+        }
+        out
+    }
+
+    #[test]
+    fn test_head_and_shoulders() {
+        // LS 110, neck 100, head 120, neck 100, RS 111, break below 100
+        let found = run_hs(&[
+            100.0, 105.0, 110.0, 105.0, 100.0, 110.0, 120.0, 110.0, 100.0, 105.0, 111.0, 105.0, 99.0,
+        ]);
+        assert_eq!(found.len(), 1);
+        let m = &found[0];
+        assert_eq!(m.name, "head_and_shoulders");
+        assert_eq!(m.pivots.len(), 5);
+        assert!((m.neckline - 100.0).abs() < 1e-9);
+        assert!((m.target - 80.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_inverse_head_and_shoulders_sloped_neckline() {
+        let found = run_hs(&[
+            110.0, 105.0, 100.0, 105.0, 110.0, 100.0, 90.0, 100.0, 112.0, 106.0, 101.0, 106.0, 115.0,
+        ]);
+        assert_eq!(found.len(), 1);
+        let m = &found[0];
+        assert_eq!(m.name, "inverse_head_and_shoulders");
+        assert!(m.polarity > 0.0);
+        // neckline rises from 110 (t=4) to 112 (t=8), so 115 at t=12 is above 114
+        assert!((m.neckline - 114.0).abs() < 1e-9);
+        assert!(m.target > m.neckline);
+    }
+
+    #[test]
+    fn test_unequal_peaks_ignored() {
+        let found = run(&[100.0, 110.0, 100.0, 120.0, 100.0, 95.0]);
+        assert!(found.iter().all(|m| m.name != "double_top"));
+    }
+}
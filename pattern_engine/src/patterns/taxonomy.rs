@@ -0,0 +1,214 @@
+//! Structured pattern tags.
+//!
+//! Free-form tags stay on [`PatternMeta::tags`]; the taxonomy classifies each
+//! pattern along three fixed axes so consumers can filter reliably:
+//! direction (from `bullish`/`bearish` tags, else the polarity sign), type
+//! (the first recognised type tag) and timeframe (from the interval suffix of
+//! the emitted pattern name, e.g. `double_top:300s`).
+
+use anyhow::{bail, Result};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::PatternMeta;
+
+/// Polarity below this magnitude is neutral when no direction tag is given
+const NEUTRAL_POLARITY: f64 = 0.1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    Bullish,
+    Bearish,
+    Neutral,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PatternType {
+    Reversal,
+    Continuation,
+    Breakout,
+    Momentum,
+    MeanReversion,
+    Volume,
+    Composite,
+}
+
+/// Scale a pattern was detected on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Timeframe {
+    /// Trade-by-trade detection (no interval suffix)
+    Tick,
+    /// Candles shorter than a day
+    Intraday,
+    /// Daily or longer candles
+    Daily,
+}
+
+const TYPES: [(&str, PatternType); 7] = [
+    ("reversal", PatternType::Reversal),
+    ("continuation", PatternType::Continuation),
+    ("breakout", PatternType::Breakout),
+    ("momentum", PatternType::Momentum),
+    ("mean_reversion", PatternType::MeanReversion),
+    ("volume", PatternType::Volume),
+    ("composite", PatternType::Composite),
+];
+
+impl Direction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Direction::Bullish => "bullish",
+            Direction::Bearish => "bearish",
+            Direction::Neutral => "neutral",
+        }
+    }
+}
+
+impl PatternType {
+    pub fn as_str(&self) -> &'static str {
+        TYPES.iter().find(|(_, t)| t == self).map_or("", |(name, _)| name)
+    }
+}
+
+impl Timeframe {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Timeframe::Tick => "tick",
+            Timeframe::Intraday => "intraday",
+            Timeframe::Daily => "daily",
+        }
+    }
+
+    /// Timeframe implied by an emitted pattern name's `:<secs>s` suffix
+    pub fn from_pattern(pattern_name: &str) -> Option<Self> {
+        match pattern_name.split_once(':') {
+            None => Some(Timeframe::Tick),
+            Some((_, suffix)) => {
+                let secs: u64 = suffix.strip_suffix('s')?.parse().ok()?;
+                Some(if secs >= 86_400 { Timeframe::Daily } else { Timeframe::Intraday })
+            }
+        }
+    }
+}
+
+/// Classification of a pattern along the fixed axes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct PatternTaxonomy {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub direction: Option<Direction>,
+    #[serde(default, rename = "type", skip_serializing_if = "Option::is_none")]
+    pub kind: Option<PatternType>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeframe: Option<Timeframe>,
+}
+
+impl PatternTaxonomy {
+    /// Classify from free-form tags and polarity; the timeframe is left unset
+    pub fn classify(tags: &[String], polarity: f64) -> Self {
+        let has = |tag: &str| tags.iter().any(|t| t.eq_ignore_ascii_case(tag));
+        let direction = if has("bullish") {
+            Direction::Bullish
+        } else if has("bearish") {
+            Direction::Bearish
+        } else if polarity >= NEUTRAL_POLARITY {
+            Direction::Bullish
+        } else if polarity <= -NEUTRAL_POLARITY {
+            Direction::Bearish
+        } else {
+            Direction::Neutral
+        };
+        let kind = TYPES.iter().find(|(name, _)| has(name)).map(|(_, t)| *t);
+        Self { direction: Some(direction), kind, timeframe: None }
+    }
+}
+
+/// Conjunction of tag terms. A term matches a free-form tag or a taxonomy
+/// value, either bare (`reversal`) or qualified by axis (`type:reversal`,
+/// `direction:bullish`, `timeframe:tick`).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TagFilter {
+    terms: Vec<String>,
+}
+
+impl TagFilter {
+    pub fn new<S: AsRef<str>>(terms: &[S]) -> Result<Self> {
+        let mut out = Vec::new();
+        for term in terms.iter().map(|t| t.as_ref().trim().to_ascii_lowercase()) {
+            if term.is_empty() {
+                continue;
+            }
+            if let Some((axis, _)) = term.split_once(':') {
+                if !["direction", "type", "timeframe"].contains(&axis) {
+                    bail!("unknown tag axis '{}' in '{}'", axis, term);
+                }
+            }
+            out.push(term);
+        }
+        Ok(Self { terms: out })
+    }
+
+    /// Parse a `+` or `,` separated term list
+    pub fn parse(spec: &str) -> Result<Self> {
+        Self::new(&spec.split(['+', ',']).collect::<Vec<_>>())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    pub fn matches(&self, meta: &PatternMeta) -> bool {
+        let tax = &meta.taxonomy;
+        let axes = [
+            ("direction", tax.direction.map(|d| d.as_str())),
+            ("type", tax.kind.map(|k| k.as_str())),
+            ("timeframe", tax.timeframe.map(|t| t.as_str())),
+        ];
+        self.terms.iter().all(|term| match term.split_once(':') {
+            Some((axis, value)) => axes.iter().any(|(a, v)| *a == axis && *v == Some(value)),
+            None => {
+                axes.iter().any(|(_, v)| *v == Some(term.as_str())) || meta.tags.iter().any(|t| t.eq_ignore_ascii_case(term))
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn meta(tags: &[&str], polarity: f64, pattern: &str) -> PatternMeta {
+        let tags: Vec<String> = tags.iter().map(|t| t.to_string()).collect();
+        let mut taxonomy = PatternTaxonomy::classify(&tags, polarity);
+        taxonomy.timeframe = Timeframe::from_pattern(pattern);
+        PatternMeta { name: pattern.into(), tags, polarity, taxonomy, ..Default::default() }
+    }
+
+    #[test]
+    fn test_classify() {
+        let t = PatternTaxonomy::classify(&["reversal".into(), "bearish".into()], 0.5);
+        assert_eq!((t.direction, t.kind), (Some(Direction::Bearish), Some(PatternType::Reversal)));
+        assert_eq!(PatternTaxonomy::classify(&[], 0.05).direction, Some(Direction::Neutral));
+        assert_eq!(PatternTaxonomy::classify(&["custom".into()], 0.4).kind, None);
+        assert_eq!(Timeframe::from_pattern("double_top:300s"), Some(Timeframe::Intraday));
+        assert_eq!(Timeframe::from_pattern("double_top:86400s"), Some(Timeframe::Daily));
+        assert_eq!(Timeframe::from_pattern("ema_crossover"), Some(Timeframe::Tick));
+        assert_eq!(Timeframe::from_pattern("x:soon"), None);
+        assert_eq!(serde_json::to_value(t).unwrap(), serde_json::json!({"direction": "bearish", "type": "reversal"}));
+    }
+
+    #[test]
+    fn test_filter() {
+        let top = meta(&["reversal", "bearish"], -0.9, "double_top:300s");
+        let cross = meta(&["trend"], 0.4, "ema_crossover");
+        let bullish = TagFilter::parse("bullish").unwrap();
+        assert!(!bullish.matches(&top) && bullish.matches(&cross));
+        assert!(TagFilter::parse("reversal+timeframe:intraday").unwrap().matches(&top));
+        assert!(!TagFilter::parse("type:reversal, tick").unwrap().matches(&top));
+        assert!(TagFilter::parse("TREND").unwrap().matches(&cross));
+        assert!(TagFilter::parse("").unwrap().matches(&cross));
+        assert!(TagFilter::parse("color:red").is_err());
+    }
+}
@@ -0,0 +1,376 @@
+//! Wyckoff accumulation / distribution range detection.
+//!
+//! A range opens on a volume climax: a wide, heavy bar making a new low of the
+//! lookback window (selling climax) or a new high (buying climax). The rally
+//! or reaction that follows sets the opposite edge of the range. Once the
+//! range has held for a few bars a spring (a dip under support that closes
+//! back inside) or an upthrust (the mirror image above resistance) tests it,
+//! and a close through the far edge marks the markup or markdown. Every phase
+//! transition is reported once.
+
+use super::candlestick::Candle;
+use serde::Serialize;
+use std::collections::VecDeque;
+
+/// Thresholds for [`WyckoffDetector`]
+#[derive(Debug, Clone)]
+pub struct WyckoffConfig {
+    /// Bars used for average volume / range and the new-extreme check
+    pub lookback: usize,
+    /// Minimum climax volume as a multiple of the average
+    pub climax_volume: f64,
+    /// Minimum climax bar range as a multiple of the average
+    pub climax_range: f64,
+    /// Bars after the climax before the trading range counts as established
+    pub min_range_bars: usize,
+    /// Bars after which an unresolved range is abandoned
+    pub max_range_bars: usize,
+    /// Deepest spring / upthrust as a fraction of the range height; closes
+    /// further outside the range break it
+    pub max_penetration: f64,
+}
+
+impl Default for WyckoffConfig {
+    fn default() -> Self {
+        Self {
+            lookback: 20,
+            climax_volume: 2.5,
+            climax_range: 1.5,
+            min_range_bars: 5,
+            max_range_bars: 200,
+            max_penetration: 0.3,
+        }
+    }
+}
+
+/// Wyckoff phase entered by an event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum WyckoffPhase {
+    /// Climax stops the prior trend
+    A,
+    /// Trading range established
+    B,
+    /// Spring or upthrust tests the range
+    C,
+    /// Markup or markdown out of the range
+    D,
+}
+
+/// A phase transition reported by [`WyckoffDetector`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct WyckoffEvent {
+    /// e.g. `wyckoff_spring`
+    pub name: String,
+    pub phase: WyckoffPhase,
+    /// Accumulation events are bullish, distribution events bearish
+    pub polarity: f64,
+    pub support: f64,
+    pub resistance: f64,
+    /// Bar volume relative to the lookback average
+    pub volume_ratio: f64,
+    pub timestamp: f64,
+}
+
+impl WyckoffEvent {
+    /// Level the range resolves through: resistance for accumulation, support for distribution
+    pub fn breakout_level(&self) -> f64 {
+        if self.polarity > 0.0 { self.resistance } else { self.support }
+    }
+
+    /// Measured move of one range height beyond the breakout level
+    pub fn target(&self) -> f64 {
+        self.breakout_level() + self.polarity.signum() * (self.resistance - self.support)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Range {
+    accumulation: bool,
+    phase: WyckoffPhase,
+    support: f64,
+    resistance: f64,
+    bars: usize,
+    /// Extreme of the spring / upthrust bar, which must hold afterwards
+    test_extreme: f64,
+}
+
+impl Range {
+    fn height(&self) -> f64 {
+        self.resistance - self.support
+    }
+}
+
+/// Incremental Wyckoff range tracker over closed candles
+#[derive(Debug, Clone, Default)]
+pub struct WyckoffDetector {
+    config: WyckoffConfig,
+    history: VecDeque<Candle>,
+    range: Option<Range>,
+}
+
+impl WyckoffDetector {
+    pub fn new(config: WyckoffConfig) -> Self {
+        Self { config, history: VecDeque::new(), range: None }
+    }
+
+    /// Current phase of an open range, if any
+    pub fn phase(&self) -> Option<WyckoffPhase> {
+        self.range.as_ref().map(|r| r.phase)
+    }
+
+    /// Feed one closed candle; returns the phase transition it completes, if any
+    pub fn update(&mut self, candle: &Candle) -> Option<WyckoffEvent> {
+        let n = self.history.len().max(1) as f64;
+        let avg_volume = self.history.iter().map(|c| c.volume).sum::<f64>() / n;
+        let avg_range = self.history.iter().map(Candle::range).sum::<f64>() / n;
+        let volume_ratio = if avg_volume > 0.0 { candle.volume / avg_volume } else { 1.0 };
+
+        let mut event = self.advance(candle);
+        if self.range.is_none() && event.is_none() {
+            event = self.detect_climax(candle, volume_ratio, avg_range);
+        }
+
+        self.history.push_back(candle.clone());
+        if self.history.len() > self.config.lookback {
+            self.history.pop_front();
+        }
+
+        let (name, phase) = event?;
+        let range = self.range.clone()?;
+        // markup / markdown resolve the range
+        if phase == WyckoffPhase::D {
+            self.range = None;
+        }
+        let strength = match phase {
+            WyckoffPhase::A => 0.5,
+            WyckoffPhase::B => 0.4,
+            WyckoffPhase::C => 0.8,
+            WyckoffPhase::D => 1.0,
+        };
+        Some(WyckoffEvent {
+            name: name.to_string(),
+            phase,
+            polarity: if range.accumulation { strength } else { -strength },
+            support: range.support,
+            resistance: range.resistance,
+            volume_ratio,
+            timestamp: candle.start as f64,
+        })
+    }
+
+    /// Move an open range through its phases
+    fn advance(&mut self, candle: &Candle) -> Option<(&'static str, WyckoffPhase)> {
+        let max_bars = self.config.max_range_bars;
+        let min_bars = self.config.min_range_bars;
+        let max_pen = self.config.max_penetration;
+        let range = self.range.as_mut()?;
+        range.bars += 1;
+        if range.bars > max_bars {
+            self.range = None;
+            return None;
+        }
+
+        // Mirror distribution into accumulation terms so `support` is always
+        // the climax edge and `resistance` the edge the range should break
+        let acc = range.accumulation;
+        let (low, high, close) = if acc {
+            (candle.low, candle.high, candle.close)
+        } else {
+            (-candle.high, -candle.low, -candle.close)
+        };
+        let (support, resistance) = if acc { (range.support, range.resistance) } else { (-range.resistance, -range.support) };
+        let pen_limit = support - max_pen * range.height();
+
+        let mut broken = false;
+        let mut event = None;
+        match range.phase {
+            WyckoffPhase::A => {
+                if close < support {
+                    broken = true;
+                } else {
+                    // the automatic rally / reaction sets the far edge
+                    if high > resistance {
+                        set_outer(range, high);
+                    }
+                    if range.bars >= min_bars {
+                        range.phase = WyckoffPhase::B;
+                        event = Some((if acc { "wyckoff_accumulation" } else { "wyckoff_distribution" }, WyckoffPhase::B));
+                    }
+                }
+            }
+            WyckoffPhase::B => {
+                if close > resistance {
+                    range.phase = WyckoffPhase::D;
+                    event = Some(breakout(acc));
+                } else if close < pen_limit {
+                    broken = true;
+                } else if low < support && close >= support && low >= pen_limit {
+                    range.phase = WyckoffPhase::C;
+                    range.test_extreme = if acc { low } else { -low };
+                    event = Some((if acc { "wyckoff_spring" } else { "wyckoff_upthrust" }, WyckoffPhase::C));
+                }
+            }
+            WyckoffPhase::C => {
+                let test_extreme = if acc { range.test_extreme } else { -range.test_extreme };
+                if close > resistance {
+                    range.phase = WyckoffPhase::D;
+                    event = Some(breakout(acc));
+                } else if close < test_extreme {
+                    broken = true;
+                }
+            }
+            WyckoffPhase::D => {}
+        }
+        if broken {
+            self.range = None;
+        }
+        event
+    }
+
+    /// Open a range on a selling or buying climax
+    fn detect_climax(&mut self, candle: &Candle, volume_ratio: f64, avg_range: f64) -> Option<(&'static str, WyckoffPhase)> {
+        if self.history.len() < self.config.lookback
+            || volume_ratio < self.config.climax_volume
+            || candle.range() < self.config.climax_range * avg_range
+        {
+            return None;
+        }
+        let lowest = self.history.iter().map(|c| c.low).fold(f64::INFINITY, f64::min);
+        let highest = self.history.iter().map(|c| c.high).fold(f64::NEG_INFINITY, f64::max);
+        let accumulation = if candle.close < candle.open && candle.low < lowest {
+            true
+        } else if candle.close > candle.open && candle.high > highest {
+            false
+        } else {
+            return None;
+        };
+        self.range = Some(Range {
+            accumulation,
+            phase: WyckoffPhase::A,
+            support: candle.low,
+            resistance: candle.high,
+            bars: 0,
+            test_extreme: if accumulation { candle.low } else { candle.high },
+        });
+        Some((if accumulation { "wyckoff_selling_climax" } else { "wyckoff_buying_climax" }, WyckoffPhase::A))
+    }
+}
+
+/// Widen the range edge away from the climax (`level` in mirrored terms)
+fn set_outer(range: &mut Range, level: f64) {
+    if range.accumulation {
+        range.resistance = level;
+    } else {
+        range.support = -level;
+    }
+}
+
+fn breakout(accumulation: bool) -> (&'static str, WyckoffPhase) {
+    (if accumulation { "wyckoff_markup" } else { "wyckoff_markdown" }, WyckoffPhase::D)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bar(i: usize, open: f64, high: f64, low: f64, close: f64, volume: f64) -> Candle {
+        Candle { start: i as u64 * 60, open, high, low, close, volume }
+    }
+
+    /// Twenty quiet bars drifting down from 120 to 101
+    fn downtrend() -> Vec<Candle> {
+        (0..20)
+            .map(|i| {
+                let c = 120.0 - i as f64;
+                bar(i, c + 0.5, c + 1.0, c - 0.5, c, 100.0)
+            })
+            .collect()
+    }
+
+    fn run(candles: &[Candle]) -> Vec<WyckoffEvent> {
+        let mut det = WyckoffDetector::default();
+        candles.iter().filter_map(|c| det.update(c)).collect()
+    }
+
+    fn names(events: &[WyckoffEvent]) -> Vec<&str> {
+        events.iter().map(|e| e.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_accumulation_spring_and_markup() {
+        let mut candles = downtrend();
+        // selling climax: wide, heavy bar to a new low
+        candles.push(bar(20, 101.0, 101.0, 95.0, 96.0, 400.0));
+        // automatic rally to 104, then range-bound
+        candles.push(bar(21, 96.0, 104.0, 96.0, 103.0, 150.0));
+        for i in 22..26 {
+            candles.push(bar(i, 100.0, 102.0, 97.0, 99.0, 100.0));
+        }
+        // spring: dips under 95 and closes back inside
+        candles.push(bar(26, 97.0, 98.0, 94.0, 96.0, 120.0));
+        candles.push(bar(27, 96.0, 100.0, 96.0, 99.0, 100.0));
+        // markup through resistance
+        candles.push(bar(28, 99.0, 106.0, 99.0, 105.0, 200.0));
+
+        let events = run(&candles);
+        assert_eq!(
+            names(&events),
+            ["wyckoff_selling_climax", "wyckoff_accumulation", "wyckoff_spring", "wyckoff_markup"]
+        );
+        assert_eq!(events[0].phase, WyckoffPhase::A);
+        assert!((events[0].volume_ratio - 4.0).abs() < 1e-9);
+        let markup = &events[3];
+        assert_eq!(markup.phase, WyckoffPhase::D);
+        assert_eq!((markup.support, markup.resistance), (95.0, 104.0));
+        assert_eq!(markup.polarity, 1.0);
+        assert_eq!((markup.breakout_level(), markup.target()), (104.0, 113.0));
+    }
+
+    #[test]
+    fn test_distribution_upthrust_and_markdown() {
+        // mirror of the accumulation case around 200
+        let flip = |c: &Candle| bar(c.start as usize / 60, 200.0 - c.open, 200.0 - c.low, 200.0 - c.high, 200.0 - c.close, c.volume);
+        let mut candles: Vec<Candle> = downtrend().iter().map(flip).collect();
+        candles.push(bar(20, 99.0, 105.0, 99.0, 104.0, 400.0));
+        candles.push(bar(21, 104.0, 104.0, 96.0, 97.0, 150.0));
+        for i in 22..26 {
+            candles.push(bar(i, 100.0, 103.0, 98.0, 101.0, 100.0));
+        }
+        candles.push(bar(26, 103.0, 106.0, 102.0, 104.0, 120.0));
+        candles.push(bar(27, 104.0, 104.0, 95.0, 95.5, 200.0));
+
+        let events = run(&candles);
+        assert_eq!(
+            names(&events),
+            ["wyckoff_buying_climax", "wyckoff_distribution", "wyckoff_upthrust", "wyckoff_markdown"]
+        );
+        assert!(events.iter().all(|e| e.polarity < 0.0));
+        assert_eq!(events[3].breakout_level(), 96.0);
+        assert_eq!(events[3].target(), 87.0);
+    }
+
+    #[test]
+    fn test_breakdown_abandons_range() {
+        let mut candles = downtrend();
+        candles.push(bar(20, 101.0, 101.0, 95.0, 96.0, 400.0));
+        candles.push(bar(21, 96.0, 104.0, 96.0, 103.0, 150.0));
+        for i in 22..26 {
+            candles.push(bar(i, 100.0, 102.0, 97.0, 99.0, 100.0));
+        }
+        // closes well under support: no spring, the range is gone
+        candles.push(bar(26, 96.0, 96.0, 90.0, 91.0, 120.0));
+        candles.push(bar(27, 91.0, 106.0, 91.0, 105.0, 120.0));
+
+        let mut det = WyckoffDetector::default();
+        let events: Vec<_> = candles.iter().filter_map(|c| det.update(c)).collect();
+        assert_eq!(names(&events), ["wyckoff_selling_climax", "wyckoff_accumulation"]);
+        assert_eq!(det.phase(), None);
+    }
+
+    #[test]
+    fn test_no_climax_on_ordinary_volume() {
+        let mut candles = downtrend();
+        candles.push(bar(20, 101.0, 101.0, 95.0, 96.0, 150.0));
+        assert!(run(&candles).is_empty());
+    }
+}
@@ -0,0 +1,202 @@
+//! ZigZag swing detection.
+//!
+//! Confirms swing highs and lows once price reverses from the running extreme
+//! by at least a configurable fraction. Confirmed pivots are the building
+//! blocks for structural patterns (double tops, head and shoulders, ...).
+
+use super::candlestick::Candle;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// Kind of a confirmed swing point
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PivotKind {
+    High,
+    Low,
+}
+
+/// A confirmed swing high or low
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Pivot {
+    pub kind: PivotKind,
+    pub price: f64,
+    pub timestamp: f64,
+    /// Sequence number of the bar/tick that set the extreme
+    pub index: u64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Extreme {
+    price: f64,
+    timestamp: f64,
+    index: u64,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Trend {
+    /// No pivot confirmed yet: track both extremes
+    Unknown { high: Extreme, low: Extreme },
+    /// Rising leg: candidate swing high
+    Up(Extreme),
+    /// Falling leg: candidate swing low
+    Down(Extreme),
+}
+
+/// Incremental ZigZag pivot detector
+#[derive(Debug, Clone)]
+pub struct ZigZag {
+    threshold: f64,
+    trend: Option<Trend>,
+    count: u64,
+    history: VecDeque<Pivot>,
+    capacity: usize,
+}
+
+impl ZigZag {
+    /// Create a detector with a reversal threshold as a fraction (0.02 = 2%)
+    /// keeping up to `capacity` confirmed pivots.
+    pub fn new(threshold: f64, capacity: usize) -> Self {
+        assert!(threshold > 0.0 && threshold < 1.0, "Threshold must be in (0.0, 1.0)");
+        Self {
+            threshold,
+            trend: None,
+            count: 0,
+            history: VecDeque::with_capacity(capacity),
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Feed a single price
+    pub fn update(&mut self, price: f64, timestamp: f64) -> Option<Pivot> {
+        self.update_bar(price, price, timestamp)
+    }
+
+    /// Feed a closed candle (uses its high and low)
+    pub fn update_candle(&mut self, candle: &Candle) -> Option<Pivot> {
+        self.update_bar(candle.high, candle.low, candle.start as f64)
+    }
+
+    /// Feed a bar high/low; returns a pivot when one is confirmed
+    pub fn update_bar(&mut self, high: f64, low: f64, timestamp: f64) -> Option<Pivot> {
+        let index = self.count;
+        self.count += 1;
+        let hi = Extreme { price: high, timestamp, index };
+        let lo = Extreme { price: low, timestamp, index };
+
+        let (trend, pivot) = match self.trend {
+            None => (Trend::Unknown { high: hi, low: lo }, None),
+            Some(Trend::Unknown { mut high, mut low }) => {
+                if hi.price > high.price {
+                    high = hi;
+                }
+                if lo.price < low.price {
+                    low = lo;
+                }
+                if high.price >= low.price * (1.0 + self.threshold) {
+                    // whichever extreme came first is the confirmed pivot
+                    if low.index <= high.index {
+                        (Trend::Up(high), Some(Self::pivot(PivotKind::Low, low)))
+                    } else {
+                        (Trend::Down(low), Some(Self::pivot(PivotKind::High, high)))
+                    }
+                } else {
+                    (Trend::Unknown { high, low }, None)
+                }
+            }
+            Some(Trend::Up(mut cand)) => {
+                if hi.price > cand.price {
+                    cand = hi;
+                }
+                if lo.price <= cand.price * (1.0 - self.threshold) {
+                    (Trend::Down(lo), Some(Self::pivot(PivotKind::High, cand)))
+                } else {
+                    (Trend::Up(cand), None)
+                }
+            }
+            Some(Trend::Down(mut cand)) => {
+                if lo.price < cand.price {
+                    cand = lo;
+                }
+                if hi.price >= cand.price * (1.0 + self.threshold) {
+                    (Trend::Up(hi), Some(Self::pivot(PivotKind::Low, cand)))
+                } else {
+                    (Trend::Down(cand), None)
+                }
+            }
+        };
+
+        self.trend = Some(trend);
+        if let Some(p) = pivot {
+            if self.history.len() == self.capacity {
+                self.history.pop_front();
+            }
+            self.history.push_back(p);
+        }
+        pivot
+    }
+
+    fn pivot(kind: PivotKind, e: Extreme) -> Pivot {
+        Pivot {
+            kind,
+            price: e.price,
+            timestamp: e.timestamp,
+            index: e.index,
+        }
+    }
+
+    /// Confirmed pivots, oldest first
+    pub fn pivots(&self) -> &VecDeque<Pivot> {
+        &self.history
+    }
+
+    /// The last `n` confirmed pivots, oldest first
+    pub fn last_pivots(&self, n: usize) -> Vec<Pivot> {
+        let skip = self.history.len().saturating_sub(n);
+        self.history.iter().skip(skip).copied().collect()
+    }
+
+    /// Reversal threshold as a fraction
+    pub fn threshold(&self) -> f64 {
+        self.threshold
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zigzag_alternates_pivots() {
+        let mut zz = ZigZag::new(0.05, 16);
+        let prices = [100.0, 102.0, 106.0, 110.0, 108.0, 103.0, 101.0, 99.0, 104.0, 107.0];
+        let pivots: Vec<Pivot> = prices
+            .iter()
+            .enumerate()
+            .filter_map(|(i, p)| zz.update(*p, i as f64))
+            .collect();
+
+        // Low at 100 confirmed on the way up, high at 110, then low at 99
+        assert_eq!(pivots.len(), 3);
+        assert_eq!(pivots[0].kind, PivotKind::Low);
+        assert_eq!(pivots[0].price, 100.0);
+        assert_eq!(pivots[1].kind, PivotKind::High);
+        assert_eq!(pivots[1].price, 110.0);
+        assert_eq!(pivots[1].index, 3);
+        assert_eq!(pivots[2].kind, PivotKind::Low);
+        assert_eq!(pivots[2].price, 99.0);
+        assert_eq!(zz.last_pivots(2), pivots[1..].to_vec());
+    }
+
+    #[test]
+    fn test_zigzag_ignores_noise_and_caps_history() {
+        let mut zz = ZigZag::new(0.05, 2);
+        for (i, p) in [100.0, 101.0, 99.5, 100.5, 99.8].iter().enumerate() {
+            assert!(zz.update(*p, i as f64).is_none());
+        }
+        for (i, p) in [120.0, 100.0, 120.0, 100.0].iter().enumerate() {
+            zz.update(*p, 10.0 + i as f64);
+        }
+        assert_eq!(zz.pivots().len(), 2);
+    }
+}
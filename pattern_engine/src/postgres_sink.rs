@@ -0,0 +1,340 @@
+//! Optional PostgreSQL / TimescaleDB sink.
+//!
+//! Signals and ticks are buffered and inserted in batches, so signal history
+//! can be queried with SQL without a separate ETL job. On connect the sink
+//! creates its tables if missing and, when the TimescaleDB extension is
+//! installed, turns them into hypertables partitioned on `time`; on plain
+//! PostgreSQL they stay ordinary tables.
+//!
+//! ```text
+//! signals(time, id, symbol, pattern, score, status, linked_id, meta, extra)
+//! ticks(time, symbol, price, volume, side, received_at, feed)
+//! ```
+//!
+//! A batch is inserted once `batch_rows` rows are buffered, and everything on
+//! [`PostgresSink::flush`]. A failed insert is counted and its rows dropped.
+//!
+//! Requires the `postgres` feature; without it [`PostgresSink::connect`]
+//! returns an error.
+
+use anyhow::{bail, Result};
+
+#[cfg(feature = "postgres")]
+use anyhow::anyhow;
+#[cfg(feature = "postgres")]
+use async_trait::async_trait;
+#[cfg(feature = "postgres")]
+use sqlx::postgres::{PgPool, PgPoolOptions};
+#[cfg(feature = "postgres")]
+use sqlx::types::Json;
+#[cfg(feature = "postgres")]
+use sqlx::{Postgres, QueryBuilder};
+#[cfg(feature = "postgres")]
+use std::sync::atomic::{AtomicU64, Ordering};
+#[cfg(feature = "postgres")]
+use std::sync::Mutex;
+#[cfg(feature = "postgres")]
+use tracing::{info, warn};
+
+#[cfg(feature = "postgres")]
+use crate::publisher::{Signal, SignalStatus, Tick, TradeSide};
+#[cfg(feature = "postgres")]
+use crate::sink::Sink;
+
+/// Rows per INSERT, well under the 65535 bind parameters Postgres allows
+#[cfg(feature = "postgres")]
+const ROWS_PER_INSERT: usize = 1000;
+
+#[derive(Debug, Clone)]
+pub struct PostgresSinkConfig {
+    pub url: String,
+    /// Table names, optionally schema-qualified (`market.signals`)
+    pub signals_table: String,
+    pub ticks_table: String,
+    /// Rows buffered before a batch is inserted
+    pub batch_rows: usize,
+}
+
+impl PostgresSinkConfig {
+    pub fn new(url: &str) -> Self {
+        Self { url: url.to_string(), signals_table: "signals".to_string(), ticks_table: "ticks".to_string(), batch_rows: 500 }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, serde::Serialize)]
+pub struct PostgresSinkStats {
+    pub rows_written: u64,
+    /// Rows waiting for the next batch
+    pub buffered: usize,
+    pub failed_writes: u64,
+    /// Rows lost to failed inserts
+    pub rows_dropped: u64,
+}
+
+/// Reject table names that would need quoting; they are spliced into SQL
+pub fn check_table_name(name: &str) -> Result<()> {
+    let parts: Vec<&str> = name.split('.').collect();
+    let valid = |part: &&str| {
+        part.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && part.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    };
+    if parts.len() > 2 || !parts.iter().all(valid) {
+        bail!("invalid table name '{}' (expected [schema.]name of letters, digits and underscores)", name);
+    }
+    Ok(())
+}
+
+/// Statements creating the tables and their symbol/time indexes
+pub fn schema_sql(signals_table: &str, ticks_table: &str) -> Vec<String> {
+    let index = |table: &str| format!("{}_symbol_time", table.replace('.', "_"));
+    vec![
+        format!(
+            "CREATE TABLE IF NOT EXISTS {} (time TIMESTAMPTZ NOT NULL, id TEXT NOT NULL, symbol TEXT NOT NULL, \
+             pattern TEXT NOT NULL, score DOUBLE PRECISION NOT NULL, status TEXT, linked_id TEXT, meta JSONB, extra JSONB)",
+            signals_table
+        ),
+        format!("CREATE INDEX IF NOT EXISTS {} ON {} (symbol, time DESC)", index(signals_table), signals_table),
+        format!(
+            "CREATE TABLE IF NOT EXISTS {} (time TIMESTAMPTZ NOT NULL, symbol TEXT NOT NULL, price DOUBLE PRECISION NOT NULL, \
+             volume DOUBLE PRECISION NOT NULL, side TEXT, received_at TIMESTAMPTZ, feed TEXT)",
+            ticks_table
+        ),
+        format!("CREATE INDEX IF NOT EXISTS {} ON {} (symbol, time DESC)", index(ticks_table), ticks_table),
+    ]
+}
+
+#[cfg(feature = "postgres")]
+fn time(timestamp: f64) -> chrono::DateTime<chrono::Utc> {
+    chrono::DateTime::from_timestamp_micros((timestamp * 1e6) as i64).unwrap_or_default()
+}
+
+#[cfg(feature = "postgres")]
+#[derive(Default)]
+struct Buffers {
+    signals: Vec<Signal>,
+    ticks: Vec<Tick>,
+}
+
+#[cfg(feature = "postgres")]
+pub struct PostgresSink {
+    config: PostgresSinkConfig,
+    pool: PgPool,
+    buffers: Mutex<Buffers>,
+    rows_written: AtomicU64,
+    failed_writes: AtomicU64,
+    rows_dropped: AtomicU64,
+}
+
+#[cfg(feature = "postgres")]
+impl PostgresSink {
+    /// Connect and create the tables (and hypertables, with TimescaleDB)
+    pub async fn connect(config: PostgresSinkConfig) -> Result<Self> {
+        check_table_name(&config.signals_table)?;
+        check_table_name(&config.ticks_table)?;
+        let pool = PgPoolOptions::new()
+            .max_connections(4)
+            .acquire_timeout(std::time::Duration::from_secs(10))
+            .connect(&config.url)
+            .await?;
+        for statement in schema_sql(&config.signals_table, &config.ticks_table) {
+            sqlx::query(&statement).execute(&pool).await?;
+        }
+        for table in [&config.signals_table, &config.ticks_table] {
+            let hypertable = format!("SELECT create_hypertable('{}', 'time', if_not_exists => TRUE, migrate_data => TRUE)", table);
+            match sqlx::query(&hypertable).execute(&pool).await {
+                Ok(_) => info!("{} is a TimescaleDB hypertable", table),
+                Err(e) => warn!("Keeping {} as a plain table (TimescaleDB unavailable: {})", table, e),
+            }
+        }
+        Ok(Self {
+            config,
+            pool,
+            buffers: Mutex::new(Buffers::default()),
+            rows_written: AtomicU64::new(0),
+            failed_writes: AtomicU64::new(0),
+            rows_dropped: AtomicU64::new(0),
+        })
+    }
+
+    pub fn tables(&self) -> (&str, &str) {
+        (&self.config.signals_table, &self.config.ticks_table)
+    }
+
+    /// Insert every buffered row
+    pub async fn flush(&self) -> Result<()> {
+        let Buffers { signals, ticks } = std::mem::take(&mut *self.lock()?);
+        let signals = self.write(signals.len(), self.insert_signals(&signals)).await;
+        let ticks = self.write(ticks.len(), self.insert_ticks(&ticks)).await;
+        signals.and(ticks)
+    }
+
+    pub fn stats(&self) -> PostgresSinkStats {
+        let buffered = self.lock().map(|b| b.signals.len() + b.ticks.len()).unwrap_or(0);
+        PostgresSinkStats {
+            rows_written: self.rows_written.load(Ordering::Relaxed),
+            buffered,
+            failed_writes: self.failed_writes.load(Ordering::Relaxed),
+            rows_dropped: self.rows_dropped.load(Ordering::Relaxed),
+        }
+    }
+
+    fn lock(&self) -> Result<std::sync::MutexGuard<'_, Buffers>> {
+        self.buffers.lock().map_err(|_| anyhow!("postgres sink lock poisoned"))
+    }
+
+    async fn write(&self, rows: usize, insert: impl std::future::Future<Output = Result<()>>) -> Result<()> {
+        if rows == 0 {
+            return Ok(());
+        }
+        let result = insert.await;
+        match &result {
+            Ok(()) => self.rows_written.fetch_add(rows as u64, Ordering::Relaxed),
+            Err(_) => {
+                self.failed_writes.fetch_add(1, Ordering::Relaxed);
+                self.rows_dropped.fetch_add(rows as u64, Ordering::Relaxed)
+            }
+        };
+        result
+    }
+
+    async fn insert_signals(&self, rows: &[Signal]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        for chunk in rows.chunks(ROWS_PER_INSERT) {
+            let mut query = QueryBuilder::<Postgres>::new(format!(
+                "INSERT INTO {} (time, id, symbol, pattern, score, status, linked_id, meta, extra) ",
+                self.config.signals_table
+            ));
+            query.push_values(chunk, |mut row, s| {
+                row.push_bind(time(s.timestamp))
+                    .push_bind(&s.id)
+                    .push_bind(&s.symbol)
+                    .push_bind(&s.pattern)
+                    .push_bind(s.score)
+                    .push_bind(s.status.as_ref().map(SignalStatus::as_str))
+                    .push_bind(&s.linked_id)
+                    .push_bind(s.meta.as_ref().map(Json))
+                    .push_bind((!s.extra.is_empty()).then_some(Json(&s.extra)));
+            });
+            query.build().execute(&mut *tx).await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn insert_ticks(&self, rows: &[Tick]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        for chunk in rows.chunks(ROWS_PER_INSERT) {
+            let mut query = QueryBuilder::<Postgres>::new(format!(
+                "INSERT INTO {} (time, symbol, price, volume, side, received_at, feed) ",
+                self.config.ticks_table
+            ));
+            query.push_values(chunk, |mut row, t| {
+                row.push_bind(time(t.timestamp))
+                    .push_bind(&t.symbol)
+                    .push_bind(t.price)
+                    .push_bind(t.volume)
+                    .push_bind(t.side.as_ref().map(TradeSide::as_str))
+                    .push_bind(t.received_at.map(time))
+                    .push_bind(&t.feed);
+            });
+            query.build().execute(&mut *tx).await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "postgres")]
+#[async_trait]
+impl Sink for PostgresSink {
+    async fn publish_signal(&self, signal: &Signal) -> Result<()> {
+        let full = {
+            let mut buffers = self.lock()?;
+            buffers.signals.push(signal.clone());
+            (buffers.signals.len() >= self.config.batch_rows).then(|| std::mem::take(&mut buffers.signals))
+        };
+        match full {
+            Some(rows) => self.write(rows.len(), self.insert_signals(&rows)).await,
+            None => Ok(()),
+        }
+    }
+
+    async fn publish_tick(&self, tick: &Tick) -> Result<()> {
+        let full = {
+            let mut buffers = self.lock()?;
+            buffers.ticks.push(tick.clone());
+            (buffers.ticks.len() >= self.config.batch_rows).then(|| std::mem::take(&mut buffers.ticks))
+        };
+        match full {
+            Some(rows) => self.write(rows.len(), self.insert_ticks(&rows)).await,
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(not(feature = "postgres"))]
+/// Stub when the `postgres` feature is not enabled
+pub struct PostgresSink;
+
+#[cfg(not(feature = "postgres"))]
+impl PostgresSink {
+    pub async fn connect(_config: PostgresSinkConfig) -> Result<Self> {
+        bail!("cannot write to PostgreSQL: built without the postgres feature")
+    }
+
+    pub fn tables(&self) -> (&str, &str) {
+        ("", "")
+    }
+
+    pub async fn flush(&self) -> Result<()> {
+        Ok(())
+    }
+
+    pub fn stats(&self) -> PostgresSinkStats {
+        PostgresSinkStats::default()
+    }
+}
+
+#[cfg(not(feature = "postgres"))]
+#[async_trait::async_trait]
+impl crate::sink::Sink for PostgresSink {
+    async fn publish_signal(&self, _signal: &crate::publisher::Signal) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_table_names() {
+        assert!(check_table_name("signals").is_ok());
+        assert!(check_table_name("market.tick_2024").is_ok());
+        for bad in ["", "1ticks", "a.b.c", "ticks; DROP TABLE signals", "\"ticks\"", "market."] {
+            assert!(check_table_name(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_schema_statements() {
+        let sql = schema_sql("market.signals", "ticks");
+        assert_eq!(sql.len(), 4);
+        assert!(sql[0].starts_with("CREATE TABLE IF NOT EXISTS market.signals (time TIMESTAMPTZ NOT NULL, id TEXT"));
+        assert_eq!(sql[1], "CREATE INDEX IF NOT EXISTS market_signals_symbol_time ON market.signals (symbol, time DESC)");
+        assert!(sql[2].contains("received_at TIMESTAMPTZ"));
+    }
+
+    #[tokio::test]
+    async fn test_refuses_bad_tables_before_connecting() {
+        let bad = PostgresSinkConfig { ticks_table: "ticks;".to_string(), ..PostgresSinkConfig::new("postgres://x") };
+        assert!(PostgresSink::connect(bad).await.is_err());
+    }
+
+    #[cfg(not(feature = "postgres"))]
+    #[tokio::test]
+    async fn test_stub_refuses_to_connect() {
+        let config = PostgresSinkConfig::new("postgres://engine@127.0.0.1:5432/market");
+        assert!(PostgresSink::connect(config).await.is_err());
+    }
+}
@@ -0,0 +1,171 @@
+//! Counters and XADD latency for the Redis publisher.
+//!
+//! Every pipelined round trip counts as one attempt; `entries` counts the
+//! XADDs those attempts carried. Retries are round trips re-sending entries
+//! from the write-ahead log. Latency is recorded per round trip, successful
+//! or not, in a fixed-bucket histogram.
+
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Upper bounds of the latency buckets in milliseconds; one more bucket
+/// catches everything slower
+const BUCKETS_MS: [f64; 12] = [0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 25.0, 50.0, 100.0, 250.0, 500.0, 1000.0];
+
+#[derive(Debug, Default)]
+pub struct LatencyHistogram {
+    counts: [AtomicU64; BUCKETS_MS.len() + 1],
+    sum_ns: AtomicU64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HistogramBucket {
+    /// Upper bound in milliseconds; None for the overflow bucket
+    pub le_ms: Option<f64>,
+    pub count: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct LatencySnapshot {
+    pub count: u64,
+    pub mean_ms: f64,
+    /// Upper bound of the bucket holding the median
+    pub p50_ms: f64,
+    pub p99_ms: f64,
+    pub buckets: Vec<HistogramBucket>,
+}
+
+impl LatencyHistogram {
+    pub fn record(&self, elapsed: Duration) {
+        let ms = elapsed.as_secs_f64() * 1000.0;
+        let bucket = BUCKETS_MS.iter().position(|&le| ms <= le).unwrap_or(BUCKETS_MS.len());
+        self.counts[bucket].fetch_add(1, Ordering::Relaxed);
+        self.sum_ns.fetch_add(elapsed.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> LatencySnapshot {
+        let counts: Vec<u64> = self.counts.iter().map(|c| c.load(Ordering::Relaxed)).collect();
+        let count: u64 = counts.iter().sum();
+        if count == 0 {
+            return LatencySnapshot::default();
+        }
+        // the slowest finite bound stands in for the overflow bucket
+        let quantile = |q: f64| {
+            let rank = (q * count as f64).ceil().max(1.0) as u64;
+            let mut seen = 0;
+            for (i, c) in counts.iter().enumerate() {
+                seen += c;
+                if seen >= rank {
+                    return BUCKETS_MS.get(i).copied().unwrap_or(BUCKETS_MS[BUCKETS_MS.len() - 1]);
+                }
+            }
+            BUCKETS_MS[BUCKETS_MS.len() - 1]
+        };
+        LatencySnapshot {
+            count,
+            mean_ms: self.sum_ns.load(Ordering::Relaxed) as f64 / count as f64 / 1e6,
+            p50_ms: quantile(0.5),
+            p99_ms: quantile(0.99),
+            buckets: counts
+                .iter()
+                .enumerate()
+                .map(|(i, &count)| HistogramBucket { le_ms: BUCKETS_MS.get(i).copied(), count })
+                .collect(),
+        }
+    }
+}
+
+/// Shared by a publisher and its replacements, so counts survive reconnects
+#[derive(Debug, Default)]
+pub struct PublisherMetrics {
+    attempts: AtomicU64,
+    failures: AtomicU64,
+    retries: AtomicU64,
+    entries: AtomicU64,
+    buffered: AtomicU64,
+    latency: LatencyHistogram,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct PublisherStats {
+    /// Round trips to Redis
+    pub attempts: u64,
+    /// Round trips that failed
+    pub failures: u64,
+    /// Round trips re-sending write-ahead log entries
+    pub retries: u64,
+    /// XADDs delivered
+    pub entries: u64,
+    /// Entries written to the write-ahead log instead of Redis
+    pub buffered: u64,
+    pub xadd_latency: LatencySnapshot,
+}
+
+impl PublisherMetrics {
+    /// Record one round trip carrying `entries` XADDs
+    pub fn record_attempt(&self, entries: usize, elapsed: Duration, ok: bool, retry: bool) {
+        self.attempts.fetch_add(1, Ordering::Relaxed);
+        if retry {
+            self.retries.fetch_add(1, Ordering::Relaxed);
+        }
+        if ok {
+            self.entries.fetch_add(entries as u64, Ordering::Relaxed);
+        } else {
+            self.failures.fetch_add(1, Ordering::Relaxed);
+        }
+        self.latency.record(elapsed);
+    }
+
+    pub fn record_buffered(&self, entries: usize) {
+        self.buffered.fetch_add(entries as u64, Ordering::Relaxed);
+    }
+
+    pub fn stats(&self) -> PublisherStats {
+        PublisherStats {
+            attempts: self.attempts.load(Ordering::Relaxed),
+            failures: self.failures.load(Ordering::Relaxed),
+            retries: self.retries.load(Ordering::Relaxed),
+            entries: self.entries.load(Ordering::Relaxed),
+            buffered: self.buffered.load(Ordering::Relaxed),
+            xadd_latency: self.latency.snapshot(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram_buckets_and_quantiles() {
+        let histogram = LatencyHistogram::default();
+        assert_eq!(histogram.snapshot(), LatencySnapshot::default());
+        for _ in 0..98 {
+            histogram.record(Duration::from_micros(800));
+        }
+        histogram.record(Duration::from_millis(20));
+        histogram.record(Duration::from_secs(3));
+        let snapshot = histogram.snapshot();
+        assert_eq!(snapshot.count, 100);
+        assert_eq!((snapshot.p50_ms, snapshot.p99_ms), (1.0, 25.0));
+        assert_eq!(snapshot.buckets[2], HistogramBucket { le_ms: Some(1.0), count: 98 });
+        assert_eq!(snapshot.buckets.last(), Some(&HistogramBucket { le_ms: None, count: 1 }));
+        assert!((snapshot.mean_ms - 30.984).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_counts_attempts_failures_and_retries() {
+        let metrics = PublisherMetrics::default();
+        metrics.record_attempt(3, Duration::from_millis(1), true, false);
+        metrics.record_attempt(2, Duration::from_millis(1), false, false);
+        metrics.record_buffered(2);
+        metrics.record_attempt(2, Duration::from_millis(1), true, true);
+        let stats = metrics.stats();
+        assert_eq!(
+            (stats.attempts, stats.failures, stats.retries, stats.entries, stats.buffered),
+            (3, 1, 1, 5, 2)
+        );
+        assert_eq!(stats.xadd_latency.count, 3);
+    }
+}
@@ -0,0 +1,173 @@
+//! Redis Pub/Sub broadcast of live signals and ticks.
+//!
+//! An alternative to the streams for consumers that only want live fan-out:
+//! nothing is stored, so a subscriber that is not connected misses the
+//! message and there is nothing to replay or trim. Each message is a JSON
+//! object holding the [`Envelope`] fields and the payload under `data`, sent
+//! to a per-symbol channel (`signals:global:AAPL`) so consumers can
+//! SUBSCRIBE to one symbol or PSUBSCRIBE to `signals:global:*`.
+//!
+//! [`RedisMode`] selects streams, Pub/Sub or both for the Redis sink.
+
+use anyhow::{anyhow, bail, Result};
+use async_trait::async_trait;
+use redis::aio::ConnectionManager;
+use redis::Client;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tokio::sync::OnceCell;
+
+use crate::envelope::{self, Envelope};
+use crate::publisher::{PublisherConfig, Signal, Tick};
+use crate::sink::Sink;
+
+/// How the Redis sink delivers signals and ticks
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RedisMode {
+    /// Durable, replayable stream entries
+    #[default]
+    Streams,
+    /// Live Pub/Sub messages only
+    PubSub,
+    Both,
+}
+
+impl RedisMode {
+    pub fn streams(&self) -> bool {
+        matches!(self, RedisMode::Streams | RedisMode::Both)
+    }
+
+    pub fn pubsub(&self) -> bool {
+        matches!(self, RedisMode::PubSub | RedisMode::Both)
+    }
+}
+
+impl std::str::FromStr for RedisMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "streams" | "stream" => Ok(RedisMode::Streams),
+            "pubsub" => Ok(RedisMode::PubSub),
+            "both" => Ok(RedisMode::Both),
+            other => bail!("unknown redis mode '{}' (expected streams, pubsub or both)", other),
+        }
+    }
+}
+
+pub struct PubSubSink {
+    client: Client,
+    conn: OnceCell<ConnectionManager>,
+    signals_channel: String,
+    ticks_channel: String,
+    producer: String,
+}
+
+impl PubSubSink {
+    /// Publish on `{signals_channel}:{symbol}` and `{ticks_channel}:{symbol}`;
+    /// connects on the first message
+    pub fn new(config: &PublisherConfig, signals_channel: &str, ticks_channel: &str, producer: &str) -> Result<Self> {
+        Ok(Self {
+            client: config.client()?,
+            conn: OnceCell::new(),
+            signals_channel: signals_channel.to_string(),
+            ticks_channel: ticks_channel.to_string(),
+            producer: producer.to_string(),
+        })
+    }
+
+    pub fn signal_channel(&self, symbol: &str) -> String {
+        format!("{}:{}", self.signals_channel, symbol)
+    }
+
+    pub fn tick_channel(&self, symbol: &str) -> String {
+        format!("{}:{}", self.ticks_channel, symbol)
+    }
+
+    /// Subscribers that received the message
+    async fn publish(&self, channel: &str, message: String) -> Result<u64> {
+        let connect = || ConnectionManager::new_with_backoff(self.client.clone(), 2, 100, 2);
+        let mut conn = self.conn.get_or_try_init(connect).await?.clone();
+        Ok(redis::cmd("PUBLISH").arg(channel).arg(message).query_async(&mut conn).await?)
+    }
+}
+
+/// A Pub/Sub message: the envelope fields and the payload under `data`
+pub fn encode_message<T: Serialize>(envelope: &Envelope, value: &T) -> Result<String> {
+    let mut message = serde_json::Map::new();
+    message.insert(envelope::SCHEMA_VERSION_FIELD.to_string(), envelope.schema_version.into());
+    if let Some(producer) = &envelope.producer {
+        message.insert(envelope::PRODUCER_FIELD.to_string(), producer.clone().into());
+    }
+    if let Some(emitted_at) = envelope.emitted_at {
+        message.insert(envelope::EMITTED_AT_FIELD.to_string(), emitted_at.into());
+    }
+    message.insert("data".to_string(), serde_json::to_value(value)?);
+    Ok(serde_json::to_string(&message)?)
+}
+
+/// Envelope and payload of a Pub/Sub message; older envelope versions are
+/// handled as for stream entries
+pub fn decode_message<T: DeserializeOwned>(message: &str) -> Result<(Envelope, T)> {
+    let serde_json::Value::Object(map) = serde_json::from_str(message)? else {
+        return Err(anyhow!("pub/sub message is not a JSON object"));
+    };
+    let fields = map
+        .into_iter()
+        .map(|(name, value)| match value {
+            serde_json::Value::String(s) if name != "data" => (name, s),
+            other => (name, other.to_string()),
+        })
+        .collect();
+    envelope::decode_data(&fields)
+}
+
+#[async_trait]
+impl Sink for PubSubSink {
+    async fn publish_signal(&self, signal: &Signal) -> Result<()> {
+        let message = encode_message(&Envelope::new(&self.producer), signal)?;
+        self.publish(&self.signal_channel(&signal.symbol), message).await.map(drop)
+    }
+
+    async fn publish_tick(&self, tick: &Tick) -> Result<()> {
+        let message = encode_message(&Envelope::new(&self.producer), tick)?;
+        self.publish(&self.tick_channel(&tick.symbol), message).await.map(drop)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_modes() {
+        assert_eq!("pubsub".parse::<RedisMode>().unwrap(), RedisMode::PubSub);
+        assert!("BOTH".parse::<RedisMode>().unwrap().streams());
+        assert!(!RedisMode::default().pubsub());
+        assert!("kafka".parse::<RedisMode>().is_err());
+    }
+
+    #[test]
+    fn test_message_round_trip() {
+        let tick = Tick::test("AAPL", 101.5, 1.0);
+        let envelope = Envelope { schema_version: envelope::SCHEMA_VERSION, producer: Some("engine-a".to_string()), emitted_at: Some(2.5) };
+        let message = encode_message(&envelope, &tick).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&message).unwrap();
+        assert_eq!(json["data"]["symbol"], "AAPL");
+        assert_eq!((json["producer"].as_str(), json["schema_version"].as_u64()), (Some("engine-a"), Some(1)));
+
+        let (opened, decoded) = decode_message::<Tick>(&message).unwrap();
+        assert_eq!(opened, envelope);
+        assert_eq!(decoded.price, 101.5);
+        assert!(decode_message::<Tick>("[1]").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_channels_per_symbol() {
+        let sink = PubSubSink::new(&PublisherConfig::new("redis://127.0.0.1:1/"), "signals:global", "ticks:global", "engine").unwrap();
+        assert_eq!(sink.signal_channel("AAPL"), "signals:global:AAPL");
+        assert_eq!(sink.tick_channel("BTC/USD"), "ticks:global:BTC/USD");
+        // nothing listens on port 1
+        assert!(sink.publish("signals:global:AAPL", "{}".to_string()).await.is_err());
+    }
+}
@@ -0,0 +1,347 @@
+//! Per-stream publish rate limits.
+//!
+//! Each limited stream gets a token bucket refilled at `rate` entries per
+//! second, holding at most `burst`. Entries over the limit are held back
+//! rather than sent: ticks are coalesced so only the latest tick per symbol
+//! waits, while signals queue in order and are never dropped. Held entries
+//! are released by [`PublishLimiter::release`] as the buckets refill, symbols
+//! in the order they started waiting so none starves under sustained load.
+//! With [`PublishLimiter::set_track_deferred`] on, a copy of each deferred
+//! signal is kept for [`PublishLimiter::take_deferred`] so it can be reported
+//! as suppressed.
+
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+
+use crate::publisher::{Signal, Tick};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+    /// Entries per second
+    pub rate: f64,
+    /// Entries that may be sent at once after a quiet period
+    pub burst: f64,
+}
+
+impl RateLimit {
+    /// Parse `stream=rate[/burst],...`, e.g. `ticks:global=500/1000,signals:global=50`;
+    /// the burst defaults to one second's worth
+    pub fn parse_list(spec: &str) -> Result<Vec<(String, RateLimit)>> {
+        spec.split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(|item| {
+                let invalid = || anyhow!("invalid rate limit '{}': expected stream=rate[/burst]", item);
+                let (stream, limit) = item.split_once('=').ok_or_else(invalid)?;
+                let (rate, burst) = match limit.split_once('/') {
+                    Some((rate, burst)) => (rate, Some(burst)),
+                    None => (limit, None),
+                };
+                let rate: f64 = rate.trim().parse().map_err(|_| invalid())?;
+                let burst: f64 = match burst {
+                    Some(b) => b.trim().parse().map_err(|_| invalid())?,
+                    None => rate,
+                };
+                if stream.trim().is_empty() || !(rate > 0.0 && burst >= 1.0) {
+                    return Err(invalid());
+                }
+                Ok((stream.trim().to_string(), RateLimit { rate, burst }))
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone)]
+struct TokenBucket {
+    limit: RateLimit,
+    tokens: f64,
+    last: Option<f64>,
+}
+
+impl TokenBucket {
+    fn new(limit: RateLimit) -> Self {
+        Self { limit, tokens: limit.burst, last: None }
+    }
+
+    fn refill(&mut self, now: f64) {
+        if let Some(last) = self.last {
+            let elapsed = (now - last).max(0.0);
+            self.tokens = (self.tokens + elapsed * self.limit.rate).min(self.limit.burst);
+        }
+        self.last = Some(now);
+    }
+
+    fn try_take(&mut self, now: f64) -> bool {
+        self.refill(now);
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct LimiterStats {
+    /// Ticks replaced by a later tick for the same symbol
+    pub coalesced: u64,
+    /// Signals that had to wait for the limit
+    pub deferred: u64,
+    pub held_ticks: usize,
+    pub queued_signals: usize,
+}
+
+/// Entries the limits let through, with the stream each was meant for
+#[derive(Debug, Default)]
+pub struct Released {
+    pub ticks: Vec<(String, Tick)>,
+    pub signals: Vec<(String, Signal)>,
+}
+
+/// Latest waiting tick per symbol, symbols in the order they started waiting
+#[derive(Debug, Default)]
+struct HeldTicks {
+    latest: HashMap<String, Tick>,
+    order: VecDeque<String>,
+}
+
+impl HeldTicks {
+    fn is_empty(&self) -> bool {
+        self.order.is_empty()
+    }
+
+    fn len(&self) -> usize {
+        self.order.len()
+    }
+
+    /// Hold `tick`; true when it replaced a waiting tick, which keeps its place
+    fn insert(&mut self, tick: Tick) -> bool {
+        if let Some(waiting) = self.latest.get_mut(&tick.symbol) {
+            *waiting = tick;
+            return true;
+        }
+        self.order.push_back(tick.symbol.clone());
+        self.latest.insert(tick.symbol.clone(), tick);
+        false
+    }
+
+    /// The tick of the symbol that has waited longest
+    fn pop_oldest(&mut self) -> Option<Tick> {
+        let symbol = self.order.pop_front()?;
+        self.latest.remove(&symbol)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct PublishLimiter {
+    buckets: HashMap<String, TokenBucket>,
+    /// Latest waiting tick per stream and symbol
+    held_ticks: HashMap<String, HeldTicks>,
+    /// Waiting signals per stream, oldest first
+    queued_signals: HashMap<String, VecDeque<Signal>>,
+    coalesced: u64,
+    deferred: u64,
+    track_deferred: bool,
+    /// Signals deferred since the last `take_deferred`, by stream
+    recent_deferred: Vec<(String, Signal)>,
+}
+
+impl PublishLimiter {
+    pub fn new(limits: Vec<(String, RateLimit)>) -> Self {
+        Self {
+            buckets: limits.into_iter().map(|(stream, limit)| (stream, TokenBucket::new(limit))).collect(),
+            ..Default::default()
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buckets.is_empty()
+    }
+
+    pub fn limit(&self, stream: &str) -> Option<RateLimit> {
+        self.buckets.get(stream).map(|b| b.limit)
+    }
+
+    /// The tick when it may be sent now; otherwise it is held, replacing any
+    /// tick still waiting for the same symbol
+    pub fn admit_tick(&mut self, stream: &str, tick: Tick, now: f64) -> Option<Tick> {
+        let Some(bucket) = self.buckets.get_mut(stream) else {
+            return Some(tick);
+        };
+        let held = self.held_ticks.entry(stream.to_string()).or_default();
+        if held.is_empty() && bucket.try_take(now) {
+            return Some(tick);
+        }
+        if held.insert(tick) {
+            self.coalesced += 1;
+        }
+        None
+    }
+
+    /// The signal when it may be sent now; otherwise it is queued behind
+    /// earlier signals
+    pub fn admit_signal(&mut self, stream: &str, signal: Signal, now: f64) -> Option<Signal> {
+        let Some(bucket) = self.buckets.get_mut(stream) else {
+            return Some(signal);
+        };
+        let queue = self.queued_signals.entry(stream.to_string()).or_default();
+        if queue.is_empty() && bucket.try_take(now) {
+            return Some(signal);
+        }
+        if self.track_deferred {
+            self.recent_deferred.push((stream.to_string(), signal.clone()));
+        }
+        queue.push_back(signal);
+        self.deferred += 1;
+        None
+    }
+
+    /// Keep deferred signals for [`Self::take_deferred`]
+    pub fn set_track_deferred(&mut self, track: bool) {
+        self.track_deferred = track;
+        if !track {
+            self.recent_deferred.clear();
+        }
+    }
+
+    /// Signals deferred since the last call, with their stream
+    pub fn take_deferred(&mut self) -> Vec<(String, Signal)> {
+        std::mem::take(&mut self.recent_deferred)
+    }
+
+    /// Held ticks and queued signals the limits allow sending now, by stream
+    pub fn release(&mut self, now: f64) -> Released {
+        let mut ticks = Vec::new();
+        for (stream, held) in self.held_ticks.iter_mut() {
+            let Some(bucket) = self.buckets.get_mut(stream) else { continue };
+            while !held.is_empty() && bucket.try_take(now) {
+                if let Some(tick) = held.pop_oldest() {
+                    ticks.push((stream.clone(), tick));
+                }
+            }
+        }
+        let mut signals = Vec::new();
+        for (stream, queue) in self.queued_signals.iter_mut() {
+            let Some(bucket) = self.buckets.get_mut(stream) else { continue };
+            while !queue.is_empty() && bucket.try_take(now) {
+                if let Some(signal) = queue.pop_front() {
+                    signals.push((stream.clone(), signal));
+                }
+            }
+        }
+        Released { ticks, signals }
+    }
+
+    /// Put signals that could not be delivered back at the head of their
+    /// queues, keeping their order
+    pub fn requeue_signals(&mut self, signals: Vec<(String, Signal)>) {
+        for (stream, signal) in signals.into_iter().rev() {
+            self.queued_signals.entry(stream).or_default().push_front(signal);
+        }
+    }
+
+    pub fn stats(&self) -> LimiterStats {
+        LimiterStats {
+            coalesced: self.coalesced,
+            deferred: self.deferred,
+            held_ticks: self.held_ticks.values().map(HeldTicks::len).sum(),
+            queued_signals: self.queued_signals.values().map(VecDeque::len).sum(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_limits() {
+        let limits = RateLimit::parse_list("ticks:global=500/1000, signals:global=50").unwrap();
+        assert_eq!(limits[0], ("ticks:global".to_string(), RateLimit { rate: 500.0, burst: 1000.0 }));
+        assert_eq!(limits[1].1, RateLimit { rate: 50.0, burst: 50.0 });
+        assert!(RateLimit::parse_list("").unwrap().is_empty());
+        for bad in ["ticks", "ticks=0", "ticks=fast", "=5", "ticks=5/0.5"] {
+            assert!(RateLimit::parse_list(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_ticks_coalesce_latest_wins() {
+        let mut limiter = PublishLimiter::new(vec![("ticks".to_string(), RateLimit { rate: 2.0, burst: 1.0 })]);
+        assert!(limiter.admit_tick("ticks", Tick::test("AAPL", 1.0, 0.0), 0.0).is_some());
+        assert!(limiter.admit_tick("ticks", Tick::test("AAPL", 2.0, 0.0), 0.0).is_none());
+        assert!(limiter.admit_tick("ticks", Tick::test("AAPL", 3.0, 0.0), 0.1).is_none());
+        assert!(limiter.admit_tick("ticks", Tick::test("MSFT", 9.0, 0.0), 0.1).is_none());
+        // unlimited streams pass straight through
+        assert!(limiter.admit_tick("other", Tick::test("AAPL", 1.0, 0.0), 0.1).is_some());
+        assert_eq!(limiter.stats(), LimiterStats { coalesced: 1, deferred: 0, held_ticks: 2, queued_signals: 0 });
+
+        // one token per half second
+        let ticks = limiter.release(0.5).ticks;
+        assert_eq!(ticks.len(), 1);
+        assert_eq!((ticks[0].1.symbol.as_str(), ticks[0].1.price), ("AAPL", 3.0));
+        let ticks = limiter.release(1.0).ticks;
+        assert_eq!(ticks[0].1.symbol, "MSFT");
+        assert_eq!(limiter.stats().held_ticks, 0);
+    }
+
+    #[test]
+    fn test_held_ticks_release_in_arrival_order() {
+        let mut limiter = PublishLimiter::new(vec![("ticks".to_string(), RateLimit { rate: 1.0, burst: 1.0 })]);
+        assert!(limiter.admit_tick("ticks", Tick::test("ZM", 1.0, 0.0), 0.0).is_some());
+        for symbol in ["ZM", "MSFT", "AAPL"] {
+            assert!(limiter.admit_tick("ticks", Tick::test(symbol, 2.0, 0.0), 0.0).is_none());
+        }
+        // AAPL keeps ticking but must not jump the queue
+        let mut released = Vec::new();
+        for t in 1..=3 {
+            assert!(limiter.admit_tick("ticks", Tick::test("AAPL", 3.0 + t as f64, 0.0), t as f64).is_none());
+            released.extend(limiter.release(t as f64).ticks.into_iter().map(|(_, t)| t.symbol));
+        }
+        assert_eq!(released, vec!["ZM", "MSFT", "AAPL"]);
+        // a symbol that was released queues afresh when it waits again
+        assert!(limiter.admit_tick("ticks", Tick::test("ZM", 9.0, 0.0), 3.2).is_none());
+        assert!(limiter.admit_tick("ticks", Tick::test("AAPL", 9.0, 0.0), 3.4).is_none());
+        assert_eq!(limiter.release(4.0).ticks[0].1.symbol, "ZM");
+        assert_eq!(limiter.release(5.0).ticks[0].1.symbol, "AAPL");
+    }
+
+    #[test]
+    fn test_signals_queue_in_order() {
+        let mut limiter = PublishLimiter::new(vec![("signals".to_string(), RateLimit { rate: 1.0, burst: 1.0 })]);
+        assert!(limiter.admit_signal("signals", Signal::test("AAPL", "flag", 0.5, 1.0), 0.0).is_some());
+        for ts in [2.0, 3.0, 4.0] {
+            assert!(limiter.admit_signal("signals", Signal::test("AAPL", "flag", 0.5, ts), 0.0).is_none());
+        }
+        let released = limiter.release(2.0).signals;
+        let ids: Vec<_> = released.iter().map(|(_, s)| s.id.as_str()).collect();
+        assert_eq!(ids, vec!["AAPL_2"]);
+
+        // a failed send goes back to the front
+        limiter.requeue_signals(released);
+        let released = limiter.release(10.0).signals;
+        let ids: Vec<_> = released.iter().map(|(_, s)| s.id.as_str()).collect();
+        assert_eq!(ids, vec!["AAPL_2"]);
+        assert_eq!(limiter.stats().queued_signals, 2);
+        assert_eq!(limiter.stats().deferred, 3);
+    }
+
+    #[test]
+    fn test_track_deferred() {
+        let mut limiter = PublishLimiter::new(vec![("signals".to_string(), RateLimit { rate: 1.0, burst: 1.0 })]);
+        assert!(limiter.admit_signal("signals", Signal::test("AAPL", "flag", 0.5, 1.0), 0.0).is_some());
+        assert!(limiter.admit_signal("signals", Signal::test("AAPL", "flag", 0.5, 2.0), 0.0).is_none());
+        assert!(limiter.take_deferred().is_empty());
+
+        limiter.set_track_deferred(true);
+        assert!(limiter.admit_signal("signals", Signal::test("AAPL", "flag", 0.5, 3.0), 0.0).is_none());
+        let deferred = limiter.take_deferred();
+        assert_eq!(deferred.len(), 1);
+        assert_eq!((deferred[0].0.as_str(), deferred[0].1.id.as_str()), ("signals", "AAPL_3"));
+        assert!(limiter.take_deferred().is_empty());
+        // the copy is only a report; the signal itself still waits
+        assert_eq!(limiter.stats().queued_signals, 2);
+    }
+}
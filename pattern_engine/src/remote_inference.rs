@@ -0,0 +1,401 @@
+//! Scores from a remote inference server.
+//!
+//! [`RemoteClient`] posts feature rows to either
+//!
+//! - a Triton (KServe v2) server: `POST {url}/v2/models/{model}/infer` with a
+//!   single FP32 `[rows, n]` input; the first output holds one score per row
+//!   (the first value of each row when it has several), or
+//! - a plain HTTP JSON endpoint: `POST {url}` with `{"instances": [[...]]}`,
+//!   answered with `{"predictions": [...]}` (numbers, or arrays whose first
+//!   value is the score), as TensorFlow Serving's REST API does.
+//!
+//! Connections are pooled and every request has a timeout. With fallback on,
+//! a failed call is answered by the local stub model instead of failing the
+//! inference; the fallback count is kept for metrics.
+
+use crate::config::{env_duration, env_number};
+use crate::inference_backend::InferenceBackend;
+use crate::inference_metrics::{InferenceMetrics, ModelStats};
+use crate::onnx_client::{default_model_stub, output_stride, row_scores};
+use anyhow::{anyhow, bail, Context, Result};
+use serde::Deserialize;
+use serde_json::json;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RemoteProtocol {
+    /// `{"instances": ...}` in, `{"predictions": ...}` out
+    #[default]
+    Http,
+    /// KServe v2 inference protocol
+    Triton,
+}
+
+impl FromStr for RemoteProtocol {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "http" | "json" => Ok(RemoteProtocol::Http),
+            "triton" | "kserve" => Ok(RemoteProtocol::Triton),
+            other => Err(anyhow!("unknown remote inference protocol: {} (expected http or triton)", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RemoteConfig {
+    /// Endpoint for HTTP, server base URL for Triton
+    pub url: String,
+    pub protocol: RemoteProtocol,
+    /// Triton model name
+    pub model: Option<String>,
+    /// Triton input tensor name
+    pub input: String,
+    /// Per request, connecting included
+    pub timeout: Duration,
+    /// Idle connections kept per host
+    pub pool_size: usize,
+    /// Score with the local stub when the server fails
+    pub fallback: bool,
+}
+
+impl RemoteConfig {
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.trim_end_matches('/').to_string(),
+            protocol: RemoteProtocol::default(),
+            model: None,
+            input: "input".to_string(),
+            timeout: Duration::from_millis(250),
+            pool_size: 8,
+            fallback: true,
+        }
+    }
+
+    /// Read REMOTE_INFERENCE_URL (required), REMOTE_INFERENCE_PROTOCOL,
+    /// REMOTE_INFERENCE_MODEL, REMOTE_INFERENCE_INPUT,
+    /// REMOTE_INFERENCE_TIMEOUT_SECS, REMOTE_INFERENCE_POOL and
+    /// REMOTE_INFERENCE_FALLBACK
+    pub fn from_env() -> Result<Self> {
+        let url = std::env::var("REMOTE_INFERENCE_URL")
+            .map_err(|_| anyhow!("the remote inference backend requires REMOTE_INFERENCE_URL"))?;
+        let defaults = Self::new(&url);
+        let protocol = match std::env::var("REMOTE_INFERENCE_PROTOCOL") {
+            Ok(p) => p.parse()?,
+            Err(_) => defaults.protocol,
+        };
+        let config = Self {
+            protocol,
+            model: std::env::var("REMOTE_INFERENCE_MODEL").ok(),
+            input: std::env::var("REMOTE_INFERENCE_INPUT").unwrap_or(defaults.input.clone()),
+            timeout: env_duration(
+                "REMOTE_INFERENCE_TIMEOUT_SECS",
+                defaults.timeout,
+                Duration::from_millis(1)..=Duration::from_secs(60),
+            )?,
+            pool_size: env_number("REMOTE_INFERENCE_POOL", defaults.pool_size, 0..=1024)?,
+            fallback: std::env::var("REMOTE_INFERENCE_FALLBACK")
+                .map_or(true, |v| !matches!(v.to_ascii_lowercase().as_str(), "0" | "false" | "off")),
+            ..defaults
+        };
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if !(self.url.starts_with("http://") || self.url.starts_with("https://")) {
+            bail!("remote inference URL must be http:// or https://, got '{}'", self.url);
+        }
+        if self.protocol == RemoteProtocol::Triton && self.model.as_deref().is_none_or(str::is_empty) {
+            bail!("the triton protocol requires a model name (REMOTE_INFERENCE_MODEL)");
+        }
+        Ok(())
+    }
+}
+
+#[derive(Deserialize)]
+struct TritonResponse {
+    outputs: Vec<TritonOutput>,
+}
+
+#[derive(Deserialize)]
+struct TritonOutput {
+    data: Vec<f64>,
+}
+
+#[derive(Deserialize)]
+struct HttpResponse {
+    predictions: Vec<Prediction>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Prediction {
+    Score(f64),
+    Row(Vec<f64>),
+}
+
+pub struct RemoteClient {
+    config: RemoteConfig,
+    /// Built on first use: the blocking client cannot be created on an async
+    /// runtime thread, and inference runs on worker threads
+    http: OnceLock<reqwest::blocking::Client>,
+    fallbacks: AtomicU64,
+    /// Last call failed and was answered by the stub
+    degraded: AtomicBool,
+    metrics: InferenceMetrics,
+}
+
+impl RemoteClient {
+    pub fn new(config: RemoteConfig) -> Result<Self> {
+        config.validate()?;
+        Ok(Self {
+            config,
+            http: OnceLock::new(),
+            fallbacks: AtomicU64::new(0),
+            degraded: AtomicBool::new(false),
+            metrics: InferenceMetrics::default(),
+        })
+    }
+
+    pub fn config(&self) -> &RemoteConfig {
+        &self.config
+    }
+
+    /// Triton model name, or the endpoint URL
+    pub fn model_id(&self) -> String {
+        self.config.model.clone().unwrap_or_else(|| self.config.url.clone())
+    }
+
+    fn http(&self) -> Result<&reqwest::blocking::Client> {
+        if let Some(client) = self.http.get() {
+            return Ok(client);
+        }
+        let client = reqwest::blocking::Client::builder()
+            .timeout(self.config.timeout)
+            .connect_timeout(self.config.timeout)
+            .pool_max_idle_per_host(self.config.pool_size)
+            .build()?;
+        Ok(self.http.get_or_init(|| client))
+    }
+
+    /// `{url}/v2/{prefix}/{model}/{action}` on a Triton server
+    fn triton_url(&self, prefix: &str, action: &str) -> String {
+        let model = self.config.model.as_deref().unwrap_or_default();
+        format!("{}/v2/{}/{}/{}", self.config.url, prefix, model, action)
+    }
+
+    /// Scores straight from the server
+    fn call(&self, rows: &[Vec<f64>]) -> Result<Vec<f64>> {
+        let width = rows[0].len();
+        if rows.iter().any(|r| r.len() != width) {
+            bail!("all rows must have the same number of features");
+        }
+        let http = self.http()?;
+        match self.config.protocol {
+            RemoteProtocol::Triton => {
+                let body = json!({
+                    "inputs": [{
+                        "name": self.config.input,
+                        "shape": [rows.len(), width],
+                        "datatype": "FP32",
+                        "data": rows.concat(),
+                    }]
+                });
+                let response: TritonResponse = http
+                    .post(self.triton_url("models", "infer"))
+                    .json(&body)
+                    .send()?
+                    .error_for_status()?
+                    .json()
+                    .context("decoding triton response")?;
+                let output = response.outputs.first().ok_or_else(|| anyhow!("triton response has no outputs"))?;
+                row_scores(&output.data, output_stride(output.data.len(), rows.len())?)
+            }
+            RemoteProtocol::Http => {
+                let response: HttpResponse = http
+                    .post(&self.config.url)
+                    .json(&json!({ "instances": rows }))
+                    .send()?
+                    .error_for_status()?
+                    .json()
+                    .context("decoding predictions")?;
+                if response.predictions.len() != rows.len() {
+                    bail!("server returned {} predictions for {} rows", response.predictions.len(), rows.len());
+                }
+                let values = response
+                    .predictions
+                    .iter()
+                    .map(|p| match p {
+                        Prediction::Score(score) => Ok(*score),
+                        Prediction::Row(row) => row.first().copied().ok_or_else(|| anyhow!("empty prediction")),
+                    })
+                    .collect::<Result<Vec<f64>>>()?;
+                row_scores(&values, 1)
+            }
+        }
+    }
+}
+
+impl InferenceBackend for RemoteClient {
+    fn infer(&self, features: &[f64]) -> Result<f64> {
+        Ok(self.infer_batch(&[features.to_vec()])?[0])
+    }
+
+    fn infer_batch(&self, rows: &[Vec<f64>]) -> Result<Vec<f64>> {
+        if rows.is_empty() {
+            return Ok(Vec::new());
+        }
+        let start = Instant::now();
+        let result = self.call(rows);
+        self.metrics.record(rows.len(), start.elapsed(), result.is_ok());
+        match result {
+            Ok(scores) => {
+                if self.degraded.swap(false, Ordering::Relaxed) {
+                    info!("Remote inference at {} recovered", self.config.url);
+                }
+                Ok(scores)
+            }
+            Err(e) if self.config.fallback => {
+                if !self.degraded.swap(true, Ordering::Relaxed) {
+                    warn!("Remote inference at {} failed, scoring with the local stub: {:#}", self.config.url, e);
+                }
+                self.fallbacks.fetch_add(rows.len() as u64, Ordering::Relaxed);
+                Ok(rows.iter().map(|row| default_model_stub(row)).collect())
+            }
+            Err(e) => Err(e.context(format!("remote inference at {}", self.config.url))),
+        }
+    }
+
+    fn location(&self) -> String {
+        match (&self.config.protocol, &self.config.model) {
+            (RemoteProtocol::Triton, Some(model)) => format!("{} (triton model {})", self.config.url, model),
+            _ => self.config.url.clone(),
+        }
+    }
+
+    fn kind(&self) -> &'static str {
+        "remote"
+    }
+
+    /// Calls answered by the local stub because the server failed
+    fn fallbacks(&self) -> u64 {
+        self.fallbacks.load(Ordering::Relaxed)
+    }
+
+    /// Round trips to the server; failed ones count as errors even when the
+    /// stub answered
+    fn stats(&self) -> Option<ModelStats> {
+        Some(self.metrics.stats())
+    }
+
+    /// Ask Triton to load the model again; plain HTTP endpoints manage their own
+    fn reload(&self) -> Result<()> {
+        if self.config.protocol == RemoteProtocol::Triton {
+            self.http()?
+                .post(self.triton_url("repository/models", "load"))
+                .send()?
+                .error_for_status()
+                .context("triton model reload")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+
+    /// Answer one request with `body`, returning the request line and body
+    fn serve_once(body: &'static str) -> (String, std::thread::JoinHandle<(String, String)>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let handle = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            let mut length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line.trim().is_empty() {
+                    break;
+                }
+                if let Some(v) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                    length = v.trim().parse().unwrap();
+                }
+            }
+            let mut request = vec![0; length];
+            reader.read_exact(&mut request).unwrap();
+            let mut stream = stream;
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            )
+            .unwrap();
+            (request_line.trim().to_string(), String::from_utf8(request).unwrap())
+        });
+        (url, handle)
+    }
+
+    #[test]
+    fn test_http_json_endpoint() {
+        let (url, server) = serve_once(r#"{"predictions": [0.5, [2.0, 0.1]]}"#);
+        let client = RemoteClient::new(RemoteConfig { fallback: false, ..RemoteConfig::new(&format!("{}/score", url)) }).unwrap();
+        assert_eq!(client.infer_batch(&[vec![1.0, 2.0], vec![3.0, 4.0]]).unwrap(), vec![0.5, 1.0]);
+        let (request_line, body) = server.join().unwrap();
+        assert!(request_line.starts_with("POST /score "), "{}", request_line);
+        assert_eq!(serde_json::from_str::<serde_json::Value>(&body).unwrap(), json!({"instances": [[1.0, 2.0], [3.0, 4.0]]}));
+    }
+
+    #[test]
+    fn test_triton_infer_request() {
+        let (url, server) = serve_once(r#"{"model_name": "patterns", "outputs": [{"name": "score", "shape": [1, 2], "datatype": "FP32", "data": [-0.3, 0.7]}]}"#);
+        let config = RemoteConfig {
+            protocol: RemoteProtocol::Triton,
+            model: Some("patterns".into()),
+            input: "features".into(),
+            ..RemoteConfig::new(&url)
+        };
+        let client = RemoteClient::new(config).unwrap();
+        assert_eq!(client.infer(&[0.1, 0.2, 0.3]).unwrap(), -0.3);
+        assert_eq!(client.model_id(), "patterns");
+        let (request_line, body) = server.join().unwrap();
+        assert!(request_line.starts_with("POST /v2/models/patterns/infer "), "{}", request_line);
+        let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(body["inputs"][0]["name"], "features");
+        assert_eq!(body["inputs"][0]["shape"], json!([1, 3]));
+    }
+
+    #[test]
+    fn test_unreachable_server_falls_back_to_stub() {
+        // nothing listens on a port that was just released
+        let url = format!("http://{}", TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap());
+        let client = RemoteClient::new(RemoteConfig::new(&url)).unwrap();
+        assert_eq!(client.infer(&[0.4, 0.2]).unwrap(), default_model_stub(&[0.4, 0.2]));
+        assert_eq!(client.fallbacks(), 1);
+        assert_eq!(client.stats().map(|s| (s.calls, s.errors)), Some((1, 1)));
+
+        let strict = RemoteClient::new(RemoteConfig { fallback: false, ..RemoteConfig::new(&url) }).unwrap();
+        assert!(strict.infer(&[0.4, 0.2]).is_err());
+    }
+
+    #[test]
+    fn test_config_validation() {
+        assert_eq!(RemoteConfig::new("http://triton:8000/").url, "http://triton:8000");
+        assert!(RemoteClient::new(RemoteConfig::new("triton:8000")).is_err());
+        let triton = RemoteConfig { protocol: RemoteProtocol::Triton, ..RemoteConfig::new("http://triton:8000") };
+        assert!(triton.validate().is_err());
+        assert_eq!("KServe".parse::<RemoteProtocol>().unwrap(), RemoteProtocol::Triton);
+    }
+}
@@ -1,34 +1,37 @@
-//! Minimal replay helper
+//! Replay of recorded ticks.
 //!
-//! Provides a small library function that can replay a ticks CSV file. For the
-//! pyo3 binding we provide a simple, safe implementation that reads the file
-//! and returns the number of non-empty lines processed. This is intentionally
-//! small and side-effect free; it can be extended to wire into the full engine.
+//! Tick files are CSV or, for `.jsonl` files, JSON Lines as the capture
+//! service writes them (see [`open_ticks`]). [`run_replay`] only counts rows;
+//! [`run_replay_publish`] republishes the ticks, and [`run_replay_detect`]
+//! runs them through a [`ReplayEngine`] (the service's own per-symbol
+//! detection from [`crate::symbol_state`] plus the pattern library) and
+//! publishes the signals, so history produces the signals live ticks would.
+//! Both publishing replays take a [`ReplaySpeed`], flat out or paced by the
+//! ticks' own timestamps, and stop at the first publish error.
+//!
+//! [`replay_mmap`] parses large CSVs in place for benchmarks and bulk loads;
+//! rows it cannot parse are counted and logged with their line number.
 
 use anyhow::{anyhow, Result};
 use std::fs::File;
-use std::io::{BufRead, BufReader};
-use crate::publisher::{Tick, Publisher};
+use std::io::{BufRead, BufReader, Lines, Read};
+use crate::flags::FeatureFlags;
+use crate::patterns::builder::DEFAULT_MODEL_PATH;
+use crate::patterns::candlestick::Candle;
+use crate::patterns::pipeline::DetectionPipeline;
+use crate::patterns::PatternLibrary;
+use crate::publisher::{Publisher, PublisherConfig, Signal, Tick, TradeSide};
+use crate::rules::Rule;
+use crate::symbol_state::{roll_candles, SymbolState};
+use crate::universe::DetectionThresholds;
+use std::collections::{BTreeMap, HashMap};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
 use tokio::runtime::Runtime;
+use tracing::warn;
 
-/// Internal trait used by replay to publish ticks/signals. This allows tests
-/// to inject a mock publisher without creating a live Redis client.
-#[async_trait::async_trait]
-pub trait PublisherLike: Send + Sync {
-    async fn publish_tick(&self, tick: Tick) -> anyhow::Result<String>;
-    async fn publish_signal(&self, signal: crate::publisher::Signal) -> anyhow::Result<String>;
-}
-
-#[async_trait::async_trait]
-impl PublisherLike for Publisher {
-    async fn publish_tick(&self, tick: Tick) -> anyhow::Result<String> {
-        Publisher::publish_tick(self, tick).await
-    }
-
-    async fn publish_signal(&self, signal: crate::publisher::Signal) -> anyhow::Result<String> {
-        Publisher::publish_signal(self, signal).await
-    }
-}
+/// Skipped rows logged individually per file; the rest are only counted
+const LOGGED_SKIPS: u64 = 10;
 
 /// Run a replay from a CSV of ticks. Returns number of data rows processed.
 /// If `path` is None, an error is returned.
@@ -56,62 +59,775 @@ pub fn run_replay(path: Option<&str>) -> Result<i32> {
     Ok(count)
 }
 
-/// Richer replay: parse CSV rows into `Tick` and optionally publish them.
-/// If `redis_url` is Some, a `Publisher` will be created and used to publish ticks.
-/// Returns the number of ticks processed.
-pub fn run_replay_publish(path: Option<&str>, redis_url: Option<&str>) -> Result<i32> {
-    let path = path.ok_or_else(|| anyhow!("ticks csv path required"))?;
+/// Ticks per pipelined publish in [`run_replay_publish`]
+const PUBLISH_BATCH: usize = 500;
+
+/// A ticks CSV that can't be read
+#[derive(Debug, thiserror::Error)]
+pub enum TickCsvError {
+    #[error("ticks csv header has no '{0}' column")]
+    MissingColumn(&'static str),
+    #[error("line {line}: missing {column}")]
+    MissingField { line: u64, column: &'static str },
+    #[error("line {line}: invalid {column} '{value}'")]
+    InvalidField { line: u64, column: &'static str, value: String },
+    #[error(transparent)]
+    Csv(#[from] csv::Error),
+}
+
+/// Where each tick field sits in a row
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct TickColumns {
+    symbol: usize,
+    price: usize,
+    volume: usize,
+    timestamp: usize,
+    side: Option<usize>,
+}
+
+impl TickColumns {
+    /// Files without a header row: `symbol,price,volume,timestamp[,side]`
+    const POSITIONAL: Self = Self { symbol: 0, price: 1, volume: 2, timestamp: 3, side: Some(4) };
+
+    /// Columns named by `header`, case-insensitively; None when the row is
+    /// data rather than a header
+    fn from_header(header: &csv::StringRecord) -> Result<Option<Self>, TickCsvError> {
+        let find = |name: &str| header.iter().position(|h| h.eq_ignore_ascii_case(name));
+        if !["symbol", "price", "volume", "timestamp"].iter().any(|name| find(name).is_some()) {
+            return Ok(None);
+        }
+        let require = |name: &'static str| find(name).ok_or(TickCsvError::MissingColumn(name));
+        Ok(Some(Self {
+            symbol: require("symbol")?,
+            price: require("price")?,
+            volume: require("volume")?,
+            timestamp: require("timestamp")?,
+            side: find("side"),
+        }))
+    }
+}
+
+/// Ticks from a CSV whose header names the `symbol`, `price`, `volume`,
+/// `timestamp` and optional `side` columns in any order (other columns are
+/// ignored); a file without a header is read positionally. Rows that don't
+/// parse are errors naming the line and column.
+pub struct TickCsvReader<R> {
+    records: csv::StringRecordsIntoIter<R>,
+    columns: TickColumns,
+    /// First row of a file without a header
+    first: Option<csv::StringRecord>,
+}
+
+impl<R: Read> TickCsvReader<R> {
+    pub fn new(reader: R) -> Result<Self, TickCsvError> {
+        let mut records = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .trim(csv::Trim::All)
+            .from_reader(reader)
+            .into_records();
+        let (columns, first) = match records.next().transpose()? {
+            Some(row) => match TickColumns::from_header(&row)? {
+                Some(columns) => (columns, None),
+                None => (TickColumns::POSITIONAL, Some(row)),
+            },
+            None => (TickColumns::POSITIONAL, None),
+        };
+        Ok(Self { records, columns, first })
+    }
+
+    fn tick(&self, row: &csv::StringRecord) -> Result<Tick, TickCsvError> {
+        let line = row.position().map_or(0, |p| p.line());
+        let field = |index: usize, column: &'static str| match row.get(index) {
+            Some(value) if !value.is_empty() => Ok(value),
+            _ => Err(TickCsvError::MissingField { line, column }),
+        };
+        let number = |index: usize, column: &'static str| {
+            let value = field(index, column)?;
+            value.parse::<f64>().map_err(|_| TickCsvError::InvalidField { line, column, value: value.to_string() })
+        };
+        let side = match self.columns.side.and_then(|i| row.get(i)).filter(|v| !v.is_empty()) {
+            Some(value) => Some(
+                value.parse().map_err(|_| TickCsvError::InvalidField { line, column: "side", value: value.to_string() })?,
+            ),
+            None => None,
+        };
+        Ok(Tick {
+            symbol: field(self.columns.symbol, "symbol")?.to_string(),
+            price: number(self.columns.price, "price")?,
+            volume: number(self.columns.volume, "volume")?,
+            timestamp: number(self.columns.timestamp, "timestamp")?,
+            side,
+            received_at: None,
+            feed: Some("replay".to_string()),
+            book: None,
+        })
+    }
+}
+
+impl<R: Read> Iterator for TickCsvReader<R> {
+    type Item = Result<Tick, TickCsvError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let row = match self.first.take() {
+            Some(row) => row,
+            None => match self.records.next()? {
+                Ok(row) => row,
+                Err(e) => return Some(Err(e.into())),
+            },
+        };
+        Some(self.tick(&row))
+    }
+}
+
+/// A JSON Lines tick file line that can't be read
+#[derive(Debug, thiserror::Error)]
+pub enum TickJsonlError {
+    #[error("line {line}: {source}")]
+    Invalid { line: u64, source: serde_json::Error },
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+/// Ticks from JSON Lines, one serialized [`Tick`] per line; blank lines are
+/// skipped. Ticks are replayed as recorded, feed and book included.
+pub struct TickJsonlReader<R> {
+    lines: Lines<R>,
+    line: u64,
+}
+
+impl<R: BufRead> TickJsonlReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self { lines: reader.lines(), line: 0 }
+    }
+}
+
+impl<R: BufRead> Iterator for TickJsonlReader<R> {
+    type Item = Result<Tick, TickJsonlError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.line += 1;
+            let line = match self.lines.next()? {
+                Ok(line) => line,
+                Err(e) => return Some(Err(e.into())),
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            let line_no = self.line;
+            return Some(serde_json::from_str(&line).map_err(|source| TickJsonlError::Invalid { line: line_no, source }));
+        }
+    }
+}
+
+/// Tick file layouts replay reads
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TickFormat {
+    Csv,
+    Jsonl,
+}
+
+impl TickFormat {
+    /// `.jsonl` and `.ndjson` files are JSON Lines, anything else CSV
+    pub fn from_path(path: &str) -> Self {
+        match std::path::Path::new(path).extension().and_then(|e| e.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("jsonl") || ext.eq_ignore_ascii_case("ndjson") => TickFormat::Jsonl,
+            _ => TickFormat::Csv,
+        }
+    }
+}
+
+/// Ticks from `path` in the [`TickFormat`] its extension names; errors are
+/// prefixed with the path
+pub fn open_ticks(path: &str) -> Result<Box<dyn Iterator<Item = Result<Tick>>>> {
     let f = File::open(path).map_err(|e| anyhow!("failed to open {}: {}", path, e))?;
-    let reader = BufReader::new(f);
+    let path = path.to_string();
+    Ok(match TickFormat::from_path(&path) {
+        TickFormat::Csv => {
+            let ticks = TickCsvReader::new(BufReader::new(f)).map_err(|e| anyhow!("{}: {}", path, e))?;
+            Box::new(ticks.map(move |t| t.map_err(|e| anyhow!("{}: {}", path, e))))
+        }
+        TickFormat::Jsonl => {
+            Box::new(TickJsonlReader::new(BufReader::new(f)).map(move |t| t.map_err(|e| anyhow!("{}: {}", path, e))))
+        }
+    })
+}
+
+/// How fast a replay emits ticks
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ReplaySpeed {
+    /// No pacing
+    #[default]
+    AsFastAsPossible,
+    /// Ticks are as far apart as their timestamps
+    RealTime,
+    /// Timestamp gaps are divided by the factor
+    Accelerated(f64),
+}
+
+impl ReplaySpeed {
+    /// Recorded seconds per wall-clock second, None when unpaced
+    fn factor(self) -> Option<f64> {
+        match self {
+            ReplaySpeed::AsFastAsPossible => None,
+            ReplaySpeed::RealTime => Some(1.0),
+            ReplaySpeed::Accelerated(x) if x.is_finite() && x > 0.0 => Some(x),
+            ReplaySpeed::Accelerated(_) => None,
+        }
+    }
+}
+
+impl FromStr for ReplaySpeed {
+    type Err = anyhow::Error;
+
+    /// `max`, `realtime`, or an acceleration like `10x`
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "max" | "fast" | "asap" => Ok(ReplaySpeed::AsFastAsPossible),
+            "realtime" | "real-time" | "1x" => Ok(ReplaySpeed::RealTime),
+            other => match other.strip_suffix('x').and_then(|x| x.parse::<f64>().ok()) {
+                Some(x) if x.is_finite() && x > 0.0 => Ok(ReplaySpeed::Accelerated(x)),
+                _ => Err(anyhow!("unknown replay speed '{}' (max, realtime or e.g. 10x)", s)),
+            },
+        }
+    }
+}
+
+/// Holds ticks back until they are due at a [`ReplaySpeed`], measured from
+/// the first tick. Late ticks (and out-of-order timestamps) go out at once.
+#[derive(Debug)]
+pub struct ReplayPacer {
+    speed: ReplaySpeed,
+    /// First tick's timestamp and when it was replayed
+    origin: Option<(f64, Instant)>,
+}
+
+impl ReplayPacer {
+    pub fn new(speed: ReplaySpeed) -> Self {
+        Self { speed, origin: None }
+    }
+
+    /// How long until the tick at `timestamp` is due
+    pub fn delay(&mut self, timestamp: f64) -> Duration {
+        let Some(factor) = self.speed.factor() else { return Duration::ZERO };
+        let (first, started) = *self.origin.get_or_insert_with(|| (timestamp, Instant::now()));
+        let due = Duration::try_from_secs_f64((timestamp - first) / factor).unwrap_or_default();
+        due.saturating_sub(started.elapsed())
+    }
+}
+
+/// Richer replay: read ticks from a CSV or JSON Lines file (see
+/// [`open_ticks`]) and optionally publish them at `speed`. If `redis_url` is
+/// Some, a `Publisher` will be created and used to publish ticks. Returns the
+/// number of ticks processed; stops at the first row that doesn't parse or
+/// batch that fails to publish.
+pub fn run_replay_publish(path: Option<&str>, redis_url: Option<&str>, speed: ReplaySpeed) -> Result<i32> {
+    let path = path.ok_or_else(|| anyhow!("ticks file path required"))?;
+    let ticks = open_ticks(path)?;
 
     // If redis_url provided, create a Publisher. We need a tokio runtime to run async code.
     let runtime = Runtime::new().map_err(|e| anyhow!("failed to create runtime: {}", e))?;
     let publisher: Option<Publisher> = match redis_url {
-        Some(url) => Some(Publisher::new(url).map_err(|e| anyhow!("failed to create publisher: {}", e))?),
+        Some(url) => Some(Publisher::with_config(&PublisherConfig::from_env(url)).map_err(|e| anyhow!("failed to create publisher: {}", e))?),
         None => None,
     };
 
     let mut processed: i32 = 0;
-    // Simple CSV parsing: symbol,price,volume,timestamp per line (comma separated)
-    for line in reader.lines() {
-        let l = line.map_err(|e| anyhow!("io error: {}", e))?;
-        let s = l.trim();
-        if s.is_empty() {
-            continue;
+    let mut pacer = ReplayPacer::new(speed);
+    // Ticks are published in pipelined batches rather than one round trip each
+    let mut batch: Vec<Tick> = Vec::with_capacity(PUBLISH_BATCH);
+    let flush = |batch: &mut Vec<Tick>| -> Result<()> {
+        if let Some(ref p) = publisher {
+            if !batch.is_empty() {
+                runtime
+                    .block_on(p.publish_ticks(batch))
+                    .map_err(|e| anyhow!("failed to publish {} ticks: {}", batch.len(), e))?;
+            }
         }
-        // Skip optional header
-        if processed == 0 {
-            let h = s.to_lowercase();
-            if h.starts_with("symbol") || h.starts_with("timestamp") || h.starts_with("price") {
-                continue;
+        batch.clear();
+        Ok(())
+    };
+    for tick in ticks {
+        let tick = match tick {
+            Ok(tick) => tick,
+            Err(e) => {
+                // ticks before the bad row still go out
+                flush(&mut batch)?;
+                return Err(e);
+            }
+        };
+        let wait = pacer.delay(tick.timestamp);
+        if !wait.is_zero() {
+            // what is already due goes out before waiting
+            flush(&mut batch)?;
+            std::thread::sleep(wait);
+        }
+        if publisher.is_some() {
+            batch.push(tick);
+            if batch.len() >= PUBLISH_BATCH {
+                flush(&mut batch)?;
             }
         }
 
-        let parts: Vec<&str> = s.split(',').map(|p| p.trim()).collect();
-        if parts.len() < 4 {
-            // ignore malformed lines
-            continue;
+        processed = processed.saturating_add(1);
+    }
+    flush(&mut batch)?;
+
+    Ok(processed)
+}
+
+/// A tick row borrowed straight from the replay buffer
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TickRecord<'a> {
+    pub symbol: &'a str,
+    pub price: f64,
+    pub volume: f64,
+    pub timestamp: f64,
+    pub side: Option<TradeSide>,
+}
+
+impl TickRecord<'_> {
+    pub fn to_tick(&self) -> Tick {
+        Tick {
+            symbol: self.symbol.to_string(),
+            price: self.price,
+            volume: self.volume,
+            timestamp: self.timestamp,
+            side: self.side,
+            received_at: None,
+            feed: Some("replay".to_string()),
+            book: None,
+        }
+    }
+}
+
+fn trim_ascii(mut b: &[u8]) -> &[u8] {
+    while let [first, rest @ ..] = b {
+        if !first.is_ascii_whitespace() {
+            break;
+        }
+        b = rest;
+    }
+    while let [rest @ .., last] = b {
+        if !last.is_ascii_whitespace() {
+            break;
         }
+        b = rest;
+    }
+    b
+}
 
-        let symbol = parts[0].to_string();
-        let price: f64 = parts[1].parse().unwrap_or(0.0);
-        let volume: f64 = parts[2].parse().unwrap_or(0.0);
-        let timestamp: f64 = parts[3].parse().unwrap_or(0.0);
+fn parse_f64(field: &[u8]) -> Option<f64> {
+    std::str::from_utf8(field).ok()?.parse().ok()
+}
 
-        let tick = Tick { symbol, price, volume, timestamp };
+/// Parse one `symbol,price,volume,timestamp[,side]` row without allocating.
+/// Returns None for headers, blank and malformed rows.
+pub fn parse_tick_row(line: &[u8]) -> Option<TickRecord<'_>> {
+    let mut fields = [&line[..0]; 5];
+    let mut n = 0;
+    let mut start = 0;
+    for end in memchr::memchr_iter(b',', line).chain(std::iter::once(line.len())) {
+        if n == fields.len() {
+            break;
+        }
+        fields[n] = trim_ascii(&line[start..end]);
+        n += 1;
+        start = end + 1;
+    }
+    if n < 4 {
+        return None;
+    }
+    Some(TickRecord {
+        symbol: std::str::from_utf8(fields[0]).ok().filter(|s| !s.is_empty())?,
+        price: parse_f64(fields[1])?,
+        volume: parse_f64(fields[2])?,
+        timestamp: parse_f64(fields[3])?,
+        side: if n > 4 { TradeSide::parse_bytes(fields[4]) } else { None },
+    })
+}
 
-        if let Some(ref pubref) = publisher {
-            // run the async publish in the runtime
-            let p = pubref;
-            let t = tick.clone();
-            let res = runtime.block_on(async move { p.publish_tick(t).await });
-            if let Err(e) = res {
-                tracing::error!("failed to publish tick: {}", e);
+/// Row counts from [`parse_tick_batches`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TickParseStats {
+    pub rows: u64,
+    /// Non-blank rows that failed to parse, not counting a leading header
+    pub skipped: u64,
+    /// Line number (1-based) of the first skipped row
+    pub first_skipped: Option<u64>,
+}
+
+/// Parse a whole buffer of CSV ticks, handing rows to `on_batch` in batches
+/// of up to `batch_size`. Blank lines are ignored and a first line that does
+/// not parse is taken as the header; any other row that does not parse is
+/// skipped, counted and logged with its line number.
+pub fn parse_tick_batches<F>(data: &[u8], batch_size: usize, mut on_batch: F) -> TickParseStats
+where
+    F: FnMut(&[TickRecord<'_>]),
+{
+    let batch_size = batch_size.max(1);
+    let mut batch = Vec::with_capacity(batch_size);
+    let mut stats = TickParseStats::default();
+    let mut seen_content = false;
+    let mut start = 0;
+    for (line_no, end) in (1u64..).zip(memchr::memchr_iter(b'\n', data).chain(std::iter::once(data.len()))) {
+        let line = &data[start..end];
+        if let Some(rec) = parse_tick_row(line) {
+            batch.push(rec);
+            if batch.len() == batch_size {
+                stats.rows += batch.len() as u64;
+                on_batch(&batch);
+                batch.clear();
+            }
+        } else if !trim_ascii(line).is_empty() && seen_content {
+            stats.skipped += 1;
+            stats.first_skipped.get_or_insert(line_no);
+            if stats.skipped <= LOGGED_SKIPS {
+                warn!("Skipping malformed tick row at line {}: {}", line_no, String::from_utf8_lossy(trim_ascii(line)));
             }
         }
+        seen_content |= !trim_ascii(line).is_empty();
+        start = end + 1;
+        if start > data.len() {
+            break;
+        }
+    }
+    if !batch.is_empty() {
+        stats.rows += batch.len() as u64;
+        on_batch(&batch);
+    }
+    if stats.skipped > LOGGED_SKIPS {
+        warn!("Skipped {} malformed tick rows ({} not logged)", stats.skipped, stats.skipped - LOGGED_SKIPS);
+    }
+    stats
+}
 
-        processed = processed.saturating_add(1);
+/// Fast replay path for large files: memory-maps `path` and parses rows in
+/// place, feeding `on_batch` in batches. Returns the rows parsed and skipped.
+pub fn replay_mmap<F>(path: &str, batch_size: usize, on_batch: F) -> Result<TickParseStats>
+where
+    F: FnMut(&[TickRecord<'_>]),
+{
+    let f = File::open(path).map_err(|e| anyhow!("failed to open {}: {}", path, e))?;
+    if f.metadata()?.len() == 0 {
+        return Ok(TickParseStats::default());
     }
+    // SAFETY: the map is read-only and only lives for this call; replay files
+    // are not expected to be modified while being replayed.
+    let map = unsafe { memmap2::Mmap::map(&f) }.map_err(|e| anyhow!("failed to map {}: {}", path, e))?;
+    Ok(parse_tick_batches(&map, batch_size, on_batch))
+}
 
-    Ok(processed)
+/// Ticks through the service's detection: candles, tick-level and candle
+/// detectors, rules, composites and anti-patterns per symbol, then the
+/// pattern library's metadata (inferred for unknown patterns). Service-only
+/// stages are not applied: two-phase confirmation, dedup, calibration from
+/// live hit rates, exogenous features and load shedding.
+pub struct ReplayEngine {
+    library: PatternLibrary,
+    rules: Vec<Rule>,
+    flags: FeatureFlags,
+    thresholds: DetectionThresholds,
+    symbols: HashMap<String, SymbolState>,
+    candles: HashMap<String, BTreeMap<u64, Candle>>,
+}
+
+impl ReplayEngine {
+    pub fn new(library: PatternLibrary) -> Self {
+        Self {
+            library,
+            rules: Vec::new(),
+            flags: FeatureFlags::default(),
+            thresholds: DetectionThresholds::default(),
+            symbols: HashMap::new(),
+            candles: HashMap::new(),
+        }
+    }
+
+    /// Rules evaluated on every trade, as in the service's `RULES_FILE`
+    pub fn with_rules(mut self, rules: Vec<Rule>) -> Self {
+        self.rules = rules;
+        self
+    }
+
+    pub fn with_flags(mut self, flags: FeatureFlags) -> Self {
+        self.flags = flags;
+        self
+    }
+
+    /// Thresholds for every symbol's pipeline
+    pub fn with_thresholds(mut self, thresholds: DetectionThresholds) -> Self {
+        self.thresholds = thresholds;
+        self
+    }
+
+    pub fn library(&self) -> &PatternLibrary {
+        &self.library
+    }
+
+    /// Signals raised by `tick`: those of bars it closed first, then its own
+    pub fn on_tick(&mut self, tick: &Tick) -> Vec<Signal> {
+        let timestamp = tick.timestamp;
+        let closed = roll_candles(self.candles.entry(tick.symbol.clone()).or_default(), tick.price, tick.volume, timestamp, false);
+        let library = &self.library;
+        let state = self.symbols.entry(tick.symbol.clone()).or_insert_with(|| {
+            SymbolState::new(DetectionPipeline::new(&tick.symbol, self.thresholds.clone(), library.gates()))
+        });
+        let mut detected = Vec::new();
+        for (interval, bar) in closed {
+            detected.extend(state.on_candle(library, &bar, interval, &self.flags).0);
+        }
+        let signals = state.on_tick(library, &self.rules, tick, timestamp, &self.flags);
+        detected.extend(signals.signal);
+        detected.extend(signals.others);
+        detected.into_iter().map(|(signal, features)| self.enrich(signal, &features)).collect()
+    }
+
+    /// Attach the library's metadata, keeping structural levels from the detector
+    fn enrich(&self, mut signal: Signal, features: &[f64]) -> Signal {
+        match self.library.lookup_or_infer_for(&signal.pattern, Some(&signal.symbol), Some(features)) {
+            Ok(mut meta) => {
+                if let Some(levels) = signal.pattern_meta.take() {
+                    meta.neckline = levels.neckline.or(meta.neckline);
+                    meta.target = levels.target.or(meta.target);
+                    meta.reversal_zone = levels.reversal_zone.or(meta.reversal_zone);
+                }
+                signal.pattern_meta = Some(meta);
+            }
+            Err(e) => tracing::warn!("replayed {} published without pattern metadata: {}", signal.pattern, e),
+        }
+        signal
+    }
+}
+
+/// Every signal a ticks file produces through [`ReplayEngine`], in order
+pub fn replay_signals(path: &str, library: PatternLibrary) -> Result<Vec<Signal>> {
+    let mut engine = ReplayEngine::new(library);
+    let mut signals = Vec::new();
+    for tick in open_ticks(path)? {
+        let tick = tick?;
+        signals.extend(engine.on_tick(&tick));
+    }
+    Ok(signals)
+}
+
+/// Replay a ticks file at `speed` through [`ReplayEngine`] with the built-in
+/// patterns and the model at `MODEL_PATH`; if `redis_url` is Some the
+/// signals are published to the signals stream as they are detected.
+/// Returns the number of signals.
+pub fn run_replay_detect(path: Option<&str>, redis_url: Option<&str>, speed: ReplaySpeed) -> Result<i32> {
+    let path = path.ok_or_else(|| anyhow!("ticks file path required"))?;
+    let model_path = std::env::var("MODEL_PATH").unwrap_or_else(|_| DEFAULT_MODEL_PATH.to_string());
+    let mut engine = ReplayEngine::new(PatternLibrary::builder().model_path(model_path).build()?);
+    let ticks = open_ticks(path)?;
+
+    let runtime = Runtime::new().map_err(|e| anyhow!("failed to create runtime: {}", e))?;
+    let publisher = match redis_url {
+        Some(url) => Some(Publisher::with_config(&PublisherConfig::from_env(url)).map_err(|e| anyhow!("failed to create publisher: {}", e))?),
+        None => None,
+    };
+    let flush = |batch: &mut Vec<Signal>| -> Result<()> {
+        if let Some(ref p) = publisher {
+            if !batch.is_empty() {
+                runtime.block_on(p.publish_signals(batch))?;
+            }
+        }
+        batch.clear();
+        Ok(())
+    };
+
+    let mut pacer = ReplayPacer::new(speed);
+    let mut batch: Vec<Signal> = Vec::new();
+    let mut detected: i32 = 0;
+    for tick in ticks {
+        let tick = tick?;
+        let wait = pacer.delay(tick.timestamp);
+        if !wait.is_zero() {
+            flush(&mut batch)?;
+            std::thread::sleep(wait);
+        }
+        let signals = engine.on_tick(&tick);
+        detected = detected.saturating_add(signals.len() as i32);
+        if publisher.is_some() {
+            batch.extend(signals);
+            if batch.len() >= PUBLISH_BATCH {
+                flush(&mut batch)?;
+            }
+        }
+    }
+    flush(&mut batch)?;
+    Ok(detected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_parse_tick_row() {
+        let rec = parse_tick_row(b" AAPL, 150.5,100,1700000000.25,sell\r").unwrap();
+        assert_eq!(rec.symbol, "AAPL");
+        assert_eq!((rec.price, rec.volume, rec.timestamp), (150.5, 100.0, 1_700_000_000.25));
+        assert_eq!(rec.side, Some(TradeSide::Sell));
+        assert!(parse_tick_row(b"symbol,price,volume,timestamp").is_none());
+        assert!(parse_tick_row(b"AAPL,1,2").is_none());
+        assert!(parse_tick_row(b"").is_none());
+    }
+
+    fn read_ticks(csv: &str) -> Result<Vec<Tick>, TickCsvError> {
+        TickCsvReader::new(csv.as_bytes())?.collect()
+    }
+
+    #[test]
+    fn test_csv_columns_by_header() {
+        let ticks = read_ticks("Timestamp,side,venue,Symbol,volume,price\n1700000000.5,sell,X,AAPL,100,150.25\n\n2,,Y,\"MSFT\",5,300\n").unwrap();
+        assert_eq!(ticks.len(), 2);
+        assert_eq!((ticks[0].symbol.as_str(), ticks[0].price, ticks[0].volume), ("AAPL", 150.25, 100.0));
+        assert_eq!((ticks[0].timestamp, ticks[0].side), (1_700_000_000.5, Some(TradeSide::Sell)));
+        assert_eq!((ticks[1].symbol.as_str(), ticks[1].side), ("MSFT", None));
+        assert_eq!(ticks[1].feed.as_deref(), Some("replay"));
+
+        // no header: symbol,price,volume,timestamp[,side]
+        let ticks = read_ticks(" AAPL , 1.5,2,3,buy\nMSFT,4,5,6\n").unwrap();
+        assert_eq!((ticks[0].symbol.as_str(), ticks[0].price, ticks[0].side), ("AAPL", 1.5, Some(TradeSide::Buy)));
+        assert_eq!(ticks[1].timestamp, 6.0);
+        assert!(read_ticks("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_csv_parse_errors() {
+        let err = read_ticks("symbol,price,timestamp\nAAPL,1,2\n").err().unwrap();
+        assert_eq!(err.to_string(), "ticks csv header has no 'volume' column");
+        let err = read_ticks("symbol,price,volume,timestamp\nAAPL,1,2,3\nAAPL,n/a,2,3\n").err().unwrap();
+        assert_eq!(err.to_string(), "line 3: invalid price 'n/a'");
+        let err = read_ticks("AAPL,1,2\n").err().unwrap();
+        assert_eq!(err.to_string(), "line 1: missing timestamp");
+        let err = read_ticks("AAPL,1,2,3,hold\n").err().unwrap();
+        assert!(matches!(err, TickCsvError::InvalidField { line: 1, column: "side", .. }));
+
+        let mut f = tempfile::NamedTempFile::new().unwrap();
+        write!(f, "symbol,price,volume,timestamp\nAAPL,1,2,3\nAAPL,1,x,3\n").unwrap();
+        let err = run_replay_publish(f.path().to_str(), None, ReplaySpeed::default()).unwrap_err().to_string();
+        assert!(err.ends_with("line 3: invalid volume 'x'"), "{}", err);
+    }
+
+    #[test]
+    fn test_jsonl_ticks_by_extension() {
+        assert_eq!(TickFormat::from_path("capture/2024-01-02.jsonl"), TickFormat::Jsonl);
+        assert_eq!(TickFormat::from_path("ticks.NDJSON"), TickFormat::Jsonl);
+        assert_eq!(TickFormat::from_path("ticks.csv"), TickFormat::Csv);
+        assert_eq!(TickFormat::from_path("ticks"), TickFormat::Csv);
+
+        let mut f = tempfile::Builder::new().suffix(".jsonl").tempfile().unwrap();
+        writeln!(f, r#"{{"symbol":"AAPL","price":150.5,"volume":100,"timestamp":1700000000.25,"side":"sell","feed":"iex"}}"#).unwrap();
+        writeln!(f).unwrap();
+        writeln!(f, r#"{{"symbol":"MSFT","price":300,"volume":5,"timestamp":1700000001}}"#).unwrap();
+        let ticks: Vec<Tick> = open_ticks(f.path().to_str().unwrap()).unwrap().collect::<Result<_>>().unwrap();
+        assert_eq!(ticks.len(), 2);
+        assert_eq!((ticks[0].side, ticks[0].feed.as_deref()), (Some(TradeSide::Sell), Some("iex")));
+        assert_eq!((ticks[1].symbol.as_str(), ticks[1].price, ticks[1].side), ("MSFT", 300.0, None));
+        assert_eq!(run_replay_publish(f.path().to_str(), None, ReplaySpeed::default()).unwrap(), 2);
+
+        writeln!(f, r#"{{"symbol":"MSFT","price":"n/a"}}"#).unwrap();
+        let err = run_replay_publish(f.path().to_str(), None, ReplaySpeed::default()).unwrap_err().to_string();
+        assert!(err.contains("line 4: "), "{}", err);
+    }
+
+    #[test]
+    fn test_replay_mmap_batches() {
+        let mut f = tempfile::NamedTempFile::new().unwrap();
+        writeln!(f, "symbol,price,volume,timestamp,side").unwrap();
+        for i in 0..10 {
+            writeln!(f, "MSFT,{},{},{},buy", 300 + i, 10, i).unwrap();
+        }
+        write!(f, "MSFT,400,1,99").unwrap(); // no trailing newline
+
+        let mut sizes = Vec::new();
+        let mut last = None;
+        let n = replay_mmap(f.path().to_str().unwrap(), 4, |batch| {
+            sizes.push(batch.len());
+            last = batch.last().map(|r| r.to_tick());
+        })
+        .unwrap();
+        assert_eq!(n, TickParseStats { rows: 11, skipped: 0, first_skipped: None });
+        assert_eq!(sizes, vec![4, 4, 3]);
+        assert_eq!(last.unwrap().price, 400.0);
+    }
+
+    #[test]
+    fn test_malformed_rows_are_reported() {
+        let data = b"symbol,price,volume,timestamp\nAAPL,1,2,3\n\nAAPL,x,2,4\nAAPL,1,2\nAAPL,1,2,5\n";
+        let mut rows = 0;
+        let stats = parse_tick_batches(data, 8, |batch| rows += batch.len());
+        assert_eq!(rows, 2);
+        assert_eq!(stats, TickParseStats { rows: 2, skipped: 2, first_skipped: Some(4) });
+        // the first non-blank line is the header; a bad row after data is not
+        assert_eq!(parse_tick_batches(b"\nbad\nAAPL,1,2,3", 8, |_| {}).skipped, 0);
+        assert_eq!(parse_tick_batches(b"AAPL,1,2,3\nbad", 8, |_| {}).first_skipped, Some(2));
+    }
+
+    #[test]
+    fn test_replay_speed_pacing() {
+        assert_eq!("max".parse::<ReplaySpeed>().unwrap(), ReplaySpeed::AsFastAsPossible);
+        assert_eq!("RealTime".parse::<ReplaySpeed>().unwrap(), ReplaySpeed::RealTime);
+        assert_eq!("10x".parse::<ReplaySpeed>().unwrap(), ReplaySpeed::Accelerated(10.0));
+        assert!("0x".parse::<ReplaySpeed>().is_err());
+        assert!("fastest".parse::<ReplaySpeed>().is_err());
+
+        let mut pacer = ReplayPacer::new(ReplaySpeed::AsFastAsPossible);
+        assert!(pacer.delay(0.0).is_zero() && pacer.delay(3600.0).is_zero());
+
+        let mut pacer = ReplayPacer::new(ReplaySpeed::Accelerated(100.0));
+        assert!(pacer.delay(1000.0).is_zero());
+        let wait = pacer.delay(1050.0);
+        assert!(wait > Duration::from_millis(400) && wait <= Duration::from_millis(500), "{:?}", wait);
+        // out of order: due already
+        assert!(pacer.delay(990.0).is_zero());
+
+        let mut pacer = ReplayPacer::new(ReplaySpeed::RealTime);
+        pacer.delay(0.0);
+        std::thread::sleep(Duration::from_millis(20));
+        assert!(pacer.delay(0.01).is_zero());
+        assert!(pacer.delay(2.0) > Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_replay_engine_matches_service_detection() {
+        let mut f = tempfile::NamedTempFile::new().unwrap();
+        writeln!(f, "symbol,price,volume,timestamp").unwrap();
+        let ticks: Vec<Tick> = (0..900)
+            .map(|i| {
+                let price = 100.0 + (i as f64 * 0.05).sin() * 4.0 + i as f64 * 0.01;
+                Tick { volume: 100.0, ..Tick::test("AAPL", price, i as f64 * 2.0) }
+            })
+            .collect();
+        for t in &ticks {
+            writeln!(f, "{},{},{},{}", t.symbol, t.price, t.volume, t.timestamp).unwrap();
+        }
+        let library = || PatternLibrary::new(std::path::Path::new("dummy.onnx")).unwrap();
+        let signals = replay_signals(f.path().to_str().unwrap(), library()).unwrap();
+        assert!(signals.iter().any(|s| s.pattern.ends_with(":60s")), "no candle-level signals");
+        assert!(signals.iter().all(|s| s.pattern_meta.is_some()));
+
+        // the same state machine the service drives, tick by tick
+        let lib = library();
+        let mut state = SymbolState::new(DetectionPipeline::new("AAPL", DetectionThresholds::default(), lib.gates()));
+        let mut candles = BTreeMap::new();
+        let mut expected = Vec::new();
+        for t in &ticks {
+            for (interval, bar) in roll_candles(&mut candles, t.price, t.volume, t.timestamp, false) {
+                expected.extend(state.on_candle(&lib, &bar, interval, &FeatureFlags::default()).0);
+            }
+            let found = state.on_tick(&lib, &[], t, t.timestamp, &FeatureFlags::default());
+            expected.extend(found.signal);
+            expected.extend(found.others);
+        }
+        let patterns = |s: &[Signal]| s.iter().map(|s| (s.pattern.clone(), s.timestamp)).collect::<Vec<_>>();
+        let expected: Vec<Signal> = expected.into_iter().map(|(s, _)| s).collect();
+        assert_eq!(patterns(&signals), patterns(&expected));
+        assert!(run_replay_detect(f.path().to_str(), None, ReplaySpeed::default()).unwrap() > 0);
+        assert!(run_replay_detect(None, None, ReplaySpeed::default()).is_err());
+    }
 }
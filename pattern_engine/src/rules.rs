@@ -0,0 +1,320 @@
+//! Rule DSL for composite signal conditions.
+//!
+//! Rules are small boolean/arithmetic expressions over the per-tick feature
+//! map, e.g. `ema_fast > ema_slow && rsi < 30 && volume_ratio > 2`. They are
+//! compiled once (unknown features and syntax errors fail at load time) and
+//! evaluated per tick. Supported: numbers, feature names, `true`/`false`,
+//! `+ - * /`, comparisons (`< <= > >= == !=`), `&& || !` and parentheses.
+//! Comparisons and logic yield 1.0 / 0.0; a rule fires when its value is
+//! non-zero.
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Num(f64),
+    Ident(String),
+    Op(&'static str),
+    LParen,
+    RParen,
+}
+
+const OPERATORS: [&str; 14] = ["&&", "||", "<=", ">=", "==", "!=", "<", ">", "!", "+", "-", "*", "/", "%"];
+
+fn tokenize(src: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut rest = src.trim_start();
+    while let Some(c) = rest.chars().next() {
+        if c == '(' || c == ')' {
+            tokens.push(if c == '(' { Token::LParen } else { Token::RParen });
+            rest = &rest[1..];
+        } else if c.is_ascii_digit() || c == '.' {
+            let end = rest.find(|ch: char| !(ch.is_ascii_digit() || ch == '.' || ch == 'e')).unwrap_or(rest.len());
+            let num = rest[..end].parse().map_err(|_| anyhow!("invalid number '{}'", &rest[..end]))?;
+            tokens.push(Token::Num(num));
+            rest = &rest[end..];
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let end = rest.find(|ch: char| !(ch.is_ascii_alphanumeric() || ch == '_')).unwrap_or(rest.len());
+            tokens.push(Token::Ident(rest[..end].to_string()));
+            rest = &rest[end..];
+        } else if let Some(op) = OPERATORS.iter().find(|op| rest.starts_with(**op)) {
+            tokens.push(Token::Op(op));
+            rest = &rest[op.len()..];
+        } else {
+            bail!("unexpected character '{}'", c);
+        }
+        rest = rest.trim_start();
+    }
+    Ok(tokens)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Const(f64),
+    Feature(usize),
+    Not(Box<Expr>),
+    Neg(Box<Expr>),
+    Binary(&'static str, Box<Expr>, Box<Expr>),
+}
+
+/// Binding power of binary operators, lowest first
+fn precedence(op: &str) -> Option<u8> {
+    match op {
+        "||" => Some(1),
+        "&&" => Some(2),
+        "==" | "!=" => Some(3),
+        "<" | "<=" | ">" | ">=" => Some(4),
+        "+" | "-" => Some(5),
+        "*" | "/" | "%" => Some(6),
+        _ => None,
+    }
+}
+
+struct Parser<'a> {
+    tokens: Vec<Token>,
+    pos: usize,
+    features: &'a [&'a str],
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let t = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        t
+    }
+
+    /// Precedence climbing over binary operators
+    fn expr(&mut self, min_prec: u8) -> Result<Expr> {
+        let mut lhs = self.unary()?;
+        while let Some(Token::Op(op)) = self.peek() {
+            let op = *op;
+            let Some(prec) = precedence(op).filter(|p| *p >= min_prec) else {
+                break;
+            };
+            self.pos += 1;
+            let rhs = self.expr(prec + 1)?;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn unary(&mut self) -> Result<Expr> {
+        match self.next() {
+            Some(Token::Op("!")) => Ok(Expr::Not(Box::new(self.unary()?))),
+            Some(Token::Op("-")) => Ok(Expr::Neg(Box::new(self.unary()?))),
+            Some(Token::Num(n)) => Ok(Expr::Const(n)),
+            Some(Token::Ident(name)) => match name.as_str() {
+                "true" => Ok(Expr::Const(1.0)),
+                "false" => Ok(Expr::Const(0.0)),
+                _ => self
+                    .features
+                    .iter()
+                    .position(|f| *f == name)
+                    .map(Expr::Feature)
+                    .ok_or_else(|| anyhow!("unknown feature '{}'", name)),
+            },
+            Some(Token::LParen) => {
+                let inner = self.expr(1)?;
+                match self.next() {
+                    Some(Token::RParen) => Ok(inner),
+                    _ => bail!("missing ')'"),
+                }
+            }
+            Some(t) => bail!("unexpected token {:?}", t),
+            None => bail!("unexpected end of expression"),
+        }
+    }
+}
+
+fn truthy(v: f64) -> bool {
+    v != 0.0 && !v.is_nan()
+}
+
+fn flag(b: bool) -> f64 {
+    if b { 1.0 } else { 0.0 }
+}
+
+impl Expr {
+    fn eval(&self, values: &[f64]) -> f64 {
+        match self {
+            Expr::Const(v) => *v,
+            Expr::Feature(i) => values.get(*i).copied().unwrap_or(f64::NAN),
+            Expr::Not(e) => flag(!truthy(e.eval(values))),
+            Expr::Neg(e) => -e.eval(values),
+            // short-circuit logic
+            Expr::Binary("&&", a, b) => flag(truthy(a.eval(values)) && truthy(b.eval(values))),
+            Expr::Binary("||", a, b) => flag(truthy(a.eval(values)) || truthy(b.eval(values))),
+            Expr::Binary(op, a, b) => {
+                let (a, b) = (a.eval(values), b.eval(values));
+                match *op {
+                    "+" => a + b,
+                    "-" => a - b,
+                    "*" => a * b,
+                    "/" => a / b,
+                    "%" => a % b,
+                    "<" => flag(a < b),
+                    "<=" => flag(a <= b),
+                    ">" => flag(a > b),
+                    ">=" => flag(a >= b),
+                    "==" => flag(a == b),
+                    "!=" => flag(a != b),
+                    _ => f64::NAN,
+                }
+            }
+        }
+    }
+}
+
+/// A rule expression compiled against a fixed feature list
+#[derive(Debug, Clone)]
+pub struct CompiledExpr {
+    source: String,
+    expr: Expr,
+}
+
+impl CompiledExpr {
+    /// Compile `source`; identifiers must appear in `features`, and feature
+    /// values are later passed to [`CompiledExpr::eval`] in the same order
+    pub fn compile(source: &str, features: &[&str]) -> Result<Self> {
+        let mut parser = Parser { tokens: tokenize(source)?, pos: 0, features };
+        let expr = parser.expr(1)?;
+        if let Some(t) = parser.peek() {
+            bail!("unexpected token {:?}", t);
+        }
+        Ok(Self { source: source.to_string(), expr })
+    }
+
+    /// Raw value of the expression; NaN features make comparisons false
+    pub fn eval(&self, values: &[f64]) -> f64 {
+        self.expr.eval(values)
+    }
+
+    pub fn matches(&self, values: &[f64]) -> bool {
+        truthy(self.eval(values))
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+}
+
+/// A signal rule as written in config
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleDefinition {
+    /// Pattern name used for signals raised by this rule
+    pub name: String,
+    /// Condition expression
+    pub when: String,
+    /// Signal score (-1..1) when the rule fires
+    pub score: f64,
+}
+
+/// A compiled signal rule
+#[derive(Debug, Clone)]
+pub struct Rule {
+    pub name: String,
+    pub score: f64,
+    pub condition: CompiledExpr,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RulesFile {
+    List(Vec<RuleDefinition>),
+    Wrapped { rules: Vec<RuleDefinition> },
+}
+
+/// Compile rule definitions, reporting every invalid rule together
+pub fn compile_rules(defs: Vec<RuleDefinition>, features: &[&str]) -> Result<Vec<Rule>> {
+    let mut rules = Vec::with_capacity(defs.len());
+    let mut errors = Vec::new();
+    for def in defs {
+        if def.name.is_empty() || def.name.contains(':') {
+            errors.push(format!("{}: name must be non-empty and must not contain ':'", def.name));
+            continue;
+        }
+        if !(-1.0..=1.0).contains(&def.score) {
+            errors.push(format!("{}: score {} outside -1..1", def.name, def.score));
+            continue;
+        }
+        match CompiledExpr::compile(&def.when, features) {
+            Ok(condition) => rules.push(Rule { name: def.name, score: def.score, condition }),
+            Err(e) => errors.push(format!("{}: {}", def.name, e)),
+        }
+    }
+    if !errors.is_empty() {
+        bail!("{} invalid rule(s): {}", errors.len(), errors.join("; "));
+    }
+    Ok(rules)
+}
+
+/// Parse rules from YAML/JSON text (a list or `{rules: [...]}`) and compile them
+pub fn parse_rules(text: &str, features: &[&str]) -> Result<Vec<Rule>> {
+    let defs = match serde_yaml::from_str(text)? {
+        RulesFile::List(defs) => defs,
+        RulesFile::Wrapped { rules } => rules,
+    };
+    compile_rules(defs, features)
+}
+
+/// Load and compile a rules file
+pub fn load_rules(path: &Path, features: &[&str]) -> Result<Vec<Rule>> {
+    let text = std::fs::read_to_string(path).with_context(|| format!("failed to read rules {}", path.display()))?;
+    parse_rules(&text, features).with_context(|| format!("invalid rules in {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FEATURES: [&str; 4] = ["ema_fast", "ema_slow", "rsi", "volume_ratio"];
+
+    #[test]
+    fn test_compile_and_eval() {
+        let rule = CompiledExpr::compile("ema_fast > ema_slow && rsi < 30 && volume_ratio > 2", &FEATURES).unwrap();
+        assert!(rule.matches(&[101.0, 100.0, 25.0, 2.5]));
+        assert!(!rule.matches(&[101.0, 100.0, 35.0, 2.5]));
+
+        let arith = CompiledExpr::compile("(ema_fast - ema_slow) / ema_slow * 100 >= 1 || !(rsi < 70)", &FEATURES).unwrap();
+        assert!(arith.matches(&[101.0, 100.0, 50.0, 1.0]));
+        assert!(!arith.matches(&[100.5, 100.0, 50.0, 1.0]));
+        assert!(arith.matches(&[100.0, 100.0, 80.0, 1.0]));
+        assert_eq!(CompiledExpr::compile("-2 * 3 + 1", &FEATURES).unwrap().eval(&[]), -5.0);
+    }
+
+    #[test]
+    fn test_missing_feature_does_not_fire() {
+        let rule = CompiledExpr::compile("rsi < 30", &FEATURES).unwrap();
+        assert!(!rule.matches(&[0.0, 0.0, f64::NAN, 0.0]));
+    }
+
+    #[test]
+    fn test_compile_errors() {
+        assert!(CompiledExpr::compile("rsi < ", &FEATURES).is_err());
+        assert!(CompiledExpr::compile("macd > 0", &FEATURES).is_err());
+        assert!(CompiledExpr::compile("(rsi < 30", &FEATURES).is_err());
+        assert!(CompiledExpr::compile("rsi < 30 30", &FEATURES).is_err());
+        assert!(CompiledExpr::compile("rsi # 30", &FEATURES).is_err());
+    }
+
+    #[test]
+    fn test_parse_rules_file() {
+        let rules = parse_rules(
+            "rules:\n  - {name: oversold_volume, when: 'rsi < 30 && volume_ratio > 2', score: 0.6}\n",
+            &FEATURES,
+        )
+        .unwrap();
+        assert_eq!(rules[0].name, "oversold_volume");
+
+        let err = parse_rules("- {name: a, when: 'macd > 0', score: 0.5}\n- {name: b, when: 'rsi < 1', score: 2}\n", &FEATURES)
+            .unwrap_err()
+            .to_string();
+        assert!(err.starts_with("2 invalid rule(s)"), "{}", err);
+    }
+}
@@ -0,0 +1,157 @@
+//! Feature normalization shipped with a model.
+//!
+//! Models are trained on scaled features, so the engine has to scale its raw
+//! feature vector the same way before inference. The training pipeline writes
+//! the spec as JSON next to the model, `pattern_model.onnx` →
+//! `pattern_model.scaler.json`, one entry per feature in input order:
+//!
+//! ```json
+//! {"features": [
+//!   {"name": "ema_diff", "mean": 0.02, "std": 1.3},
+//!   {"name": "volume_ratio", "min": 0.0, "max": 12.0}
+//! ]}
+//! ```
+//!
+//! `mean`/`std` standardizes (`(x - mean) / std`, as sklearn's
+//! `StandardScaler`); `min`/`max` rescales to [0, 1] (`MinMaxScaler`).
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Scaling {
+    Standard { mean: f64, std: f64 },
+    MinMax { min: f64, max: f64 },
+}
+
+impl Scaling {
+    pub fn apply(&self, value: f64) -> f64 {
+        match *self {
+            Scaling::Standard { mean, std } => (value - mean) / std,
+            Scaling::MinMax { min, max } => (value - min) / (max - min),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FeatureScale {
+    pub name: String,
+    #[serde(flatten)]
+    pub scaling: Scaling,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FeatureScaler {
+    pub features: Vec<FeatureScale>,
+}
+
+impl FeatureScaler {
+    /// Parse and validate a spec
+    pub fn from_json(json: &str) -> Result<Self> {
+        let scaler: Self = serde_json::from_str(json)?;
+        if scaler.features.is_empty() {
+            bail!("scaler lists no features");
+        }
+        for f in &scaler.features {
+            let ok = match f.scaling {
+                Scaling::Standard { mean, std } => mean.is_finite() && std.is_finite() && std > 0.0,
+                Scaling::MinMax { min, max } => min.is_finite() && max.is_finite() && max > min,
+            };
+            if !ok {
+                bail!("scaler feature '{}': need finite mean and std > 0, or finite min < max", f.name);
+            }
+        }
+        Ok(scaler)
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let json = std::fs::read_to_string(path).with_context(|| format!("reading scaler {}", path.display()))?;
+        Self::from_json(&json).with_context(|| format!("invalid scaler {}", path.display()))
+    }
+
+    /// Where the scaler for `model_path` lives
+    pub fn sidecar_path(model_path: &Path) -> PathBuf {
+        model_path.with_extension("scaler.json")
+    }
+
+    /// The scaler shipped next to `model_path`, if there is one
+    pub fn load_for_model(model_path: &Path) -> Result<Option<Self>> {
+        let path = Self::sidecar_path(model_path);
+        if !path.is_file() {
+            return Ok(None);
+        }
+        Self::load(&path).map(Some)
+    }
+
+    /// Number of features the scaler expects
+    pub fn len(&self) -> usize {
+        self.features.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.features.is_empty()
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.features.iter().map(|f| f.name.as_str()).collect()
+    }
+
+    /// Scale `features`, which must be in the spec's order and length
+    pub fn apply(&self, features: &[f64]) -> Result<Vec<f64>> {
+        if features.len() != self.features.len() {
+            return Err(anyhow!(
+                "scaler expects {} features ({}), got {}",
+                self.features.len(),
+                self.names().join(", "),
+                features.len()
+            ));
+        }
+        Ok(features.iter().zip(&self.features).map(|(&x, f)| f.scaling.apply(x)).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SPEC: &str = r#"{"features": [
+        {"name": "ema_diff", "mean": 1.0, "std": 2.0},
+        {"name": "volume_ratio", "min": 0.0, "max": 4.0}
+    ]}"#;
+
+    #[test]
+    fn test_apply_standard_and_min_max() {
+        let scaler = FeatureScaler::from_json(SPEC).unwrap();
+        assert_eq!(scaler.names(), vec!["ema_diff", "volume_ratio"]);
+        assert_eq!(scaler.features[1].scaling, Scaling::MinMax { min: 0.0, max: 4.0 });
+        assert_eq!(scaler.apply(&[5.0, 1.0]).unwrap(), vec![2.0, 0.25]);
+        let err = scaler.apply(&[5.0]).unwrap_err().to_string();
+        assert!(err.contains("expects 2 features (ema_diff, volume_ratio), got 1"), "{}", err);
+    }
+
+    #[test]
+    fn test_rejects_degenerate_specs() {
+        for bad in [
+            r#"{"features": []}"#,
+            r#"{"features": [{"name": "a", "mean": 0.0, "std": 0.0}]}"#,
+            r#"{"features": [{"name": "a", "min": 1.0, "max": 1.0}]}"#,
+            r#"{"features": [{"name": "a", "scale": 2.0}]}"#,
+        ] {
+            assert!(FeatureScaler::from_json(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_sidecar_next_to_model() {
+        let dir = tempfile::tempdir().unwrap();
+        let model = dir.path().join("pattern_model.onnx");
+        assert_eq!(FeatureScaler::sidecar_path(&model), dir.path().join("pattern_model.scaler.json"));
+        assert!(FeatureScaler::load_for_model(&model).unwrap().is_none());
+        std::fs::write(FeatureScaler::sidecar_path(&model), SPEC).unwrap();
+        assert_eq!(FeatureScaler::load_for_model(&model).unwrap().unwrap().len(), 2);
+        std::fs::write(FeatureScaler::sidecar_path(&model), "{").unwrap();
+        assert!(FeatureScaler::load_for_model(&model).is_err());
+    }
+}
@@ -0,0 +1,102 @@
+//! Signal IDs.
+//!
+//! IDs used to be `{symbol}_{unix_secs}`, which collided whenever two
+//! patterns fired on a symbol within the same second. Every signal now gets
+//! a ULID: 48 bits of wall-clock milliseconds and 80 random bits, written as
+//! 26 Crockford base32 characters. IDs from one process are strictly
+//! increasing, so they also sort in emission order; within a millisecond the
+//! random part is incremented instead of redrawn. Symbol and pattern are not
+//! encoded in the ID — they travel in the signal's own fields.
+
+use std::sync::Mutex;
+
+const ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+const RANDOM_BITS: u32 = 80;
+const RANDOM_MASK: u128 = (1 << RANDOM_BITS) - 1;
+const MAX_MILLIS: u64 = (1 << 48) - 1;
+
+/// Monotonic ULID source
+#[derive(Debug, Default)]
+pub struct SignalIdGenerator {
+    /// Millisecond and random part of the last ID handed out
+    last: Mutex<Option<(u64, u128)>>,
+}
+
+static GENERATOR: SignalIdGenerator = SignalIdGenerator::new();
+
+/// Next ID from the process-wide generator
+pub fn next_id() -> String {
+    GENERATOR.next()
+}
+
+impl SignalIdGenerator {
+    pub const fn new() -> Self {
+        Self { last: Mutex::new(None) }
+    }
+
+    pub fn next(&self) -> String {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        self.next_at(now)
+    }
+
+    /// ID for wall-clock time `millis`; never below the previous one, even if
+    /// the clock steps back
+    pub fn next_at(&self, millis: u64) -> String {
+        let mut last = self.last.lock().unwrap_or_else(|e| e.into_inner());
+        let (millis, random) = match *last {
+            Some((prev, random)) if millis <= prev => match random.checked_add(1).filter(|r| *r <= RANDOM_MASK) {
+                Some(next) => (prev, next),
+                // random part exhausted: borrow the next millisecond
+                None => ((prev + 1).min(MAX_MILLIS), rand::random::<u128>() & (RANDOM_MASK >> 1)),
+            },
+            _ => (millis.min(MAX_MILLIS), rand::random::<u128>() & RANDOM_MASK),
+        };
+        *last = Some((millis, random));
+        encode(((millis as u128) << RANDOM_BITS) | random)
+    }
+}
+
+fn encode(value: u128) -> String {
+    (0..26).rev().map(|i| ALPHABET[((value >> (i * 5)) & 31) as usize] as char).collect()
+}
+
+/// Milliseconds embedded in a ULID, or None if `id` is not one
+pub fn timestamp_ms(id: &str) -> Option<u64> {
+    if id.len() != 26 {
+        return None;
+    }
+    let mut value: u128 = 0;
+    for c in id.bytes() {
+        let digit = ALPHABET.iter().position(|&a| a == c.to_ascii_uppercase())?;
+        value = value.checked_mul(32)? + digit as u128;
+    }
+    Some((value >> RANDOM_BITS) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ids_increase_within_and_across_millis() {
+        let generator = SignalIdGenerator::new();
+        let ids: Vec<String> = [5, 5, 5, 4, 6].iter().map(|&ms| generator.next_at(ms)).collect();
+        assert!(ids.windows(2).all(|w| w[0] < w[1]), "{:?}", ids);
+        assert_eq!(ids[0].len(), 26);
+        // a step back in the clock keeps the previous millisecond
+        assert_eq!(ids.iter().map(|id| timestamp_ms(id).unwrap()).collect::<Vec<_>>(), vec![5, 5, 5, 5, 6]);
+    }
+
+    #[test]
+    fn test_global_ids_are_unique_ulids() {
+        let ids: std::collections::HashSet<String> = (0..1000).map(|_| next_id()).collect();
+        assert_eq!(ids.len(), 1000);
+        let first = ids.iter().next().unwrap();
+        assert!(timestamp_ms(first).unwrap() > 1_600_000_000_000);
+        assert_eq!(timestamp_ms("AAPL_1700000000"), None);
+        assert_eq!(timestamp_ms("0000000000000000000000000U"), None);
+    }
+}
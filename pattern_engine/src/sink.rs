@@ -0,0 +1,315 @@
+//! Destinations for published signals and ticks.
+//!
+//! [`Sink`] is implemented by the Redis [`Publisher`], the gRPC and ZeroMQ
+//! sinks, the WebSocket broadcast and the file sink. [`FanoutSink`] sends
+//! each message to every configured sink in turn; a failing sink is logged
+//! and counted but never stops delivery to the others. Closed candles take
+//! the same path; only the Redis publisher writes them.
+
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tracing::error;
+
+use crate::grpc::GrpcSink;
+use crate::publisher::{Candle, Publisher, Signal, Tick};
+use crate::websocket::SignalBroadcast;
+use crate::zmq_sink::ZmqSink;
+
+#[async_trait]
+pub trait Sink: Send + Sync {
+    async fn publish_signal(&self, signal: &Signal) -> Result<()>;
+
+    /// Sinks that don't carry market data ignore ticks
+    async fn publish_tick(&self, _tick: &Tick) -> Result<()> {
+        Ok(())
+    }
+
+    /// Publish ticks one by one; sinks that can batch override this
+    async fn publish_ticks(&self, ticks: &[Tick]) -> Result<()> {
+        for tick in ticks {
+            self.publish_tick(tick).await?;
+        }
+        Ok(())
+    }
+
+    /// Sinks without a candle stream ignore closed candles
+    async fn publish_candle(&self, _candle: &Candle) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Sink for Publisher {
+    async fn publish_signal(&self, signal: &Signal) -> Result<()> {
+        Publisher::publish_signal(self, signal.clone()).await.map(drop)
+    }
+
+    async fn publish_tick(&self, tick: &Tick) -> Result<()> {
+        Publisher::publish_tick(self, tick.clone()).await.map(drop)
+    }
+
+    async fn publish_ticks(&self, ticks: &[Tick]) -> Result<()> {
+        Publisher::publish_ticks(self, ticks).await.map(drop)
+    }
+
+    async fn publish_candle(&self, candle: &Candle) -> Result<()> {
+        Publisher::publish_candle(self, candle.clone()).await.map(drop)
+    }
+}
+
+/// A sink that can be swapped at runtime, like the supervised publisher
+#[async_trait]
+impl<S: Sink> Sink for Mutex<S> {
+    async fn publish_signal(&self, signal: &Signal) -> Result<()> {
+        self.lock().await.publish_signal(signal).await
+    }
+
+    async fn publish_tick(&self, tick: &Tick) -> Result<()> {
+        self.lock().await.publish_tick(tick).await
+    }
+
+    async fn publish_ticks(&self, ticks: &[Tick]) -> Result<()> {
+        self.lock().await.publish_ticks(ticks).await
+    }
+
+    async fn publish_candle(&self, candle: &Candle) -> Result<()> {
+        self.lock().await.publish_candle(candle).await
+    }
+}
+
+#[async_trait]
+impl Sink for GrpcSink {
+    async fn publish_signal(&self, signal: &Signal) -> Result<()> {
+        if !self.send(signal) {
+            bail!("gRPC queue full, dropped signal {}", signal.id);
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Sink for SignalBroadcast {
+    async fn publish_signal(&self, signal: &Signal) -> Result<()> {
+        self.send(signal);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Sink for ZmqSink {
+    async fn publish_signal(&self, signal: &Signal) -> Result<()> {
+        if !self.send_signal(signal) {
+            bail!("ZeroMQ queue full, dropped signal {}", signal.id);
+        }
+        Ok(())
+    }
+
+    async fn publish_tick(&self, tick: &Tick) -> Result<()> {
+        if !self.send_tick(tick) {
+            bail!("ZeroMQ queue full, dropped {} tick", tick.symbol);
+        }
+        Ok(())
+    }
+}
+
+/// Forwards ticks and candles but drops signals, for a destination that
+/// carries market data while signals go elsewhere
+pub struct TicksOnly(pub Arc<dyn Sink>);
+
+#[async_trait]
+impl Sink for TicksOnly {
+    async fn publish_signal(&self, _signal: &Signal) -> Result<()> {
+        Ok(())
+    }
+
+    async fn publish_tick(&self, tick: &Tick) -> Result<()> {
+        self.0.publish_tick(tick).await
+    }
+
+    async fn publish_ticks(&self, ticks: &[Tick]) -> Result<()> {
+        self.0.publish_ticks(ticks).await
+    }
+
+    async fn publish_candle(&self, candle: &Candle) -> Result<()> {
+        self.0.publish_candle(candle).await
+    }
+}
+
+/// Delivery counters for one sink of a [`FanoutSink`]
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct SinkStats {
+    pub name: String,
+    pub published: u64,
+    pub failed: u64,
+}
+
+struct Route {
+    name: String,
+    sink: Arc<dyn Sink>,
+    published: AtomicU64,
+    failed: AtomicU64,
+}
+
+impl Route {
+    fn record(&self, result: Result<()>, what: &str) -> bool {
+        match result {
+            Ok(()) => {
+                self.published.fetch_add(1, Ordering::Relaxed);
+                true
+            }
+            Err(e) => {
+                self.failed.fetch_add(1, Ordering::Relaxed);
+                error!("Sink {} failed to publish {}: {}", self.name, what, e);
+                false
+            }
+        }
+    }
+}
+
+/// Publishes to several named sinks with per-sink error isolation
+#[derive(Default)]
+pub struct FanoutSink {
+    routes: Vec<Route>,
+}
+
+impl FanoutSink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, name: &str, sink: Arc<dyn Sink>) -> Self {
+        self.routes.push(Route {
+            name: name.to_string(),
+            sink,
+            published: AtomicU64::new(0),
+            failed: AtomicU64::new(0),
+        });
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.routes.is_empty()
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.routes.iter().map(|r| r.name.as_str()).collect()
+    }
+
+    pub fn stats(&self) -> Vec<SinkStats> {
+        self.routes
+            .iter()
+            .map(|r| SinkStats {
+                name: r.name.clone(),
+                published: r.published.load(Ordering::Relaxed),
+                failed: r.failed.load(Ordering::Relaxed),
+            })
+            .collect()
+    }
+}
+
+/// Fails only when every sink failed
+#[async_trait]
+impl Sink for FanoutSink {
+    async fn publish_signal(&self, signal: &Signal) -> Result<()> {
+        let mut delivered = self.routes.is_empty();
+        for route in &self.routes {
+            delivered |= route.record(route.sink.publish_signal(signal).await, "signal");
+        }
+        if !delivered {
+            bail!("no sink accepted signal {}", signal.id);
+        }
+        Ok(())
+    }
+
+    async fn publish_tick(&self, tick: &Tick) -> Result<()> {
+        let mut delivered = self.routes.is_empty();
+        for route in &self.routes {
+            delivered |= route.record(route.sink.publish_tick(tick).await, "tick");
+        }
+        if !delivered {
+            bail!("no sink accepted {} tick", tick.symbol);
+        }
+        Ok(())
+    }
+
+    async fn publish_candle(&self, candle: &Candle) -> Result<()> {
+        let mut delivered = self.routes.is_empty();
+        for route in &self.routes {
+            delivered |= route.record(route.sink.publish_candle(candle).await, "candle");
+        }
+        if !delivered {
+            bail!("no sink accepted {}s {} candle", candle.interval, candle.symbol);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct Recorder {
+        signals: std::sync::Mutex<Vec<String>>,
+        ticks: AtomicU64,
+        fail: bool,
+    }
+
+    #[async_trait]
+    impl Sink for Recorder {
+        async fn publish_signal(&self, signal: &Signal) -> Result<()> {
+            if self.fail {
+                bail!("down");
+            }
+            self.signals.lock().unwrap().push(signal.id.clone());
+            Ok(())
+        }
+
+        async fn publish_tick(&self, _tick: &Tick) -> Result<()> {
+            self.ticks.fetch_add(1, Ordering::Relaxed);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fanout_isolates_failures() {
+        let (ok, down) = (Arc::new(Recorder::default()), Arc::new(Recorder { fail: true, ..Default::default() }));
+        let fanout = FanoutSink::new().with("down", down).with("file", ok.clone());
+        assert_eq!(fanout.names(), vec!["down", "file"]);
+
+        fanout.publish_signal(&Signal::test("AAPL", "flag", 0.5, 1.0)).await.unwrap();
+        fanout.publish_signal(&Signal::test("AAPL", "flag", 0.5, 2.0)).await.unwrap();
+        assert_eq!(*ok.signals.lock().unwrap(), vec!["AAPL_1", "AAPL_2"]);
+        let stats = fanout.stats();
+        assert_eq!((stats[0].published, stats[0].failed), (0, 2));
+        assert_eq!((stats[1].published, stats[1].failed), (2, 0));
+
+        // ticks are ignored by signal-only sinks
+        let tick = Tick::test("AAPL", 1.0, 0.0);
+        fanout.publish_tick(&tick).await.unwrap();
+        assert_eq!(ok.ticks.load(Ordering::Relaxed), 1);
+    }
+
+    #[tokio::test]
+    async fn test_ticks_only_route() {
+        let inner = Arc::new(Recorder::default());
+        let fanout = FanoutSink::new().with("redis", Arc::new(TicksOnly(inner.clone())));
+        fanout.publish_signal(&Signal::test("AAPL", "flag", 0.5, 1.0)).await.unwrap();
+        let tick = Tick::test("AAPL", 1.0, 0.0);
+        fanout.publish_ticks(&[tick]).await.unwrap();
+        assert!(inner.signals.lock().unwrap().is_empty());
+        assert_eq!(inner.ticks.load(Ordering::Relaxed), 1);
+    }
+
+    #[tokio::test]
+    async fn test_fanout_fails_when_every_sink_fails() {
+        let down = Arc::new(Recorder { fail: true, ..Default::default() });
+        let fanout = FanoutSink::new().with("a", down.clone()).with("b", Arc::new(Mutex::new(Recorder { fail: true, ..Default::default() })));
+        assert!(fanout.publish_signal(&Signal::test("AAPL", "flag", 0.5, 1.0)).await.is_err());
+        assert!(FanoutSink::new().publish_signal(&Signal::test("AAPL", "flag", 0.5, 1.0)).await.is_ok());
+    }
+}
@@ -0,0 +1,72 @@
+//! Engine status heartbeats.
+//!
+//! The engine periodically appends an [`EngineStatus`] to the status stream
+//! (`status:pattern_engine` by default). Downstream services treat a missing
+//! heartbeat, or one whose tick rate has dropped to zero while the market is
+//! open, as a stalled engine. Each heartbeat also names the running version
+//! and the model behind inference.
+
+use serde::{Deserialize, Serialize};
+
+use crate::degrade::DegradationLevel;
+
+/// The model behind inference
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelStatus {
+    pub model_id: String,
+    /// `onnx` when built with ONNX Runtime, otherwise `stub`
+    pub backend: String,
+    /// Inference requests waiting for a worker
+    pub queue_depth: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EngineStatus {
+    pub engine: String,
+    /// Distinguishes engine processes sharing the stream
+    pub instance: String,
+    pub version: String,
+    pub timestamp: f64,
+    pub uptime_secs: f64,
+    pub active_symbols: usize,
+    /// Ticks processed since start
+    pub ticks_processed: u64,
+    /// Ticks per second since the previous heartbeat
+    pub tick_rate: f64,
+    pub paused: bool,
+    pub degradation: DegradationLevel,
+    pub model: ModelStatus,
+}
+
+/// Ticks per second between successive readings of a running total
+#[derive(Debug, Default)]
+pub struct RateMeter {
+    last: Option<(u64, f64)>,
+}
+
+impl RateMeter {
+    /// Rate since the previous call; 0 on the first
+    pub fn update(&mut self, total: u64, now: f64) -> f64 {
+        let rate = match self.last {
+            Some((count, at)) if now > at => total.saturating_sub(count) as f64 / (now - at),
+            _ => 0.0,
+        };
+        self.last = Some((total, now));
+        rate
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_between_readings() {
+        let mut meter = RateMeter::default();
+        assert_eq!(meter.update(100, 10.0), 0.0);
+        assert_eq!(meter.update(400, 15.0), 60.0);
+        // no time passed: no rate rather than a division by zero
+        assert_eq!(meter.update(500, 15.0), 0.0);
+        assert_eq!(meter.update(500, 20.0), 0.0);
+    }
+}
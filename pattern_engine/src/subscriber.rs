@@ -0,0 +1,329 @@
+//! Redis Streams subscriber.
+//!
+//! Consumes ticks, candles, signals or order messages published by other
+//! services through a consumer group, so several engine instances can share
+//! one stream. Entries are read with XREADGROUP and stay pending until [`Subscriber::ack`]; entries left
+//! pending by a consumer that died are taken over with
+//! [`Subscriber::claim_pending`] once they have been idle long enough.
+//!
+//! Entries that cannot be decoded are logged and acknowledged so they are not
+//! delivered again.
+
+use anyhow::Result;
+use redis::aio::ConnectionManager;
+use redis::streams::{StreamClaimReply, StreamId, StreamPendingCountReply, StreamReadOptions, StreamReadReply};
+use redis::{AsyncCommands, Client};
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, OnceCell};
+use tracing::{info, warn};
+
+use crate::envelope;
+use crate::orders::{ExecutionReport, OrderIntent};
+use crate::publisher::{Candle, PublisherConfig, Signal, StreamEncoding, Tick};
+
+/// A message type that can be read back from stream entry fields
+pub trait StreamMessage: Sized + Send + 'static {
+    fn decode(fields: &HashMap<String, String>) -> Result<Self>;
+}
+
+impl StreamMessage for Tick {
+    fn decode(fields: &HashMap<String, String>) -> Result<Self> {
+        Ok(envelope::decode_data(fields)?.1)
+    }
+}
+
+impl StreamMessage for Candle {
+    fn decode(fields: &HashMap<String, String>) -> Result<Self> {
+        Ok(envelope::decode_data(fields)?.1)
+    }
+}
+
+impl StreamMessage for OrderIntent {
+    fn decode(fields: &HashMap<String, String>) -> Result<Self> {
+        let (_, order): (_, OrderIntent) = envelope::decode_data(fields)?;
+        order.validate()?;
+        Ok(order)
+    }
+}
+
+impl StreamMessage for ExecutionReport {
+    fn decode(fields: &HashMap<String, String>) -> Result<Self> {
+        Ok(envelope::decode_data(fields)?.1)
+    }
+}
+
+/// Either stream encoding, see [`StreamEncoding`]
+impl StreamMessage for Signal {
+    fn decode(fields: &HashMap<String, String>) -> Result<Self> {
+        StreamEncoding::decode(fields)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SubscriberConfig {
+    pub stream: String,
+    pub group: String,
+    /// Unique per engine instance
+    pub consumer: String,
+    /// Entries per read
+    pub batch: usize,
+    /// How long a read waits for new entries
+    pub block: Duration,
+    /// Pending entries idle this long are claimed from other consumers
+    pub claim_idle: Duration,
+}
+
+impl SubscriberConfig {
+    pub fn new(stream: &str, group: &str, consumer: &str) -> Self {
+        Self {
+            stream: stream.to_string(),
+            group: group.to_string(),
+            consumer: consumer.to_string(),
+            batch: 100,
+            block: Duration::from_secs(1),
+            claim_idle: Duration::from_secs(30),
+        }
+    }
+}
+
+/// A decoded stream entry; ack its `id` once processed
+#[derive(Debug, Clone)]
+pub struct Entry<T> {
+    pub id: String,
+    pub message: T,
+}
+
+/// Consumer-group reader for one stream; clones share the connection
+pub struct Subscriber<T> {
+    client: Client,
+    /// Opened on first use, after creating the group
+    conn: Arc<OnceCell<ConnectionManager>>,
+    config: SubscriberConfig,
+    _message: PhantomData<fn() -> T>,
+}
+
+impl<T> Clone for Subscriber<T> {
+    fn clone(&self) -> Self {
+        Self {
+            client: self.client.clone(),
+            conn: self.conn.clone(),
+            config: self.config.clone(),
+            _message: PhantomData,
+        }
+    }
+}
+
+impl<T: StreamMessage> Subscriber<T> {
+    /// Does not connect; the group is created on the first read
+    pub fn new(redis: &PublisherConfig, config: SubscriberConfig) -> Result<Self> {
+        Ok(Self {
+            client: redis.client()?,
+            conn: Arc::new(OnceCell::new()),
+            config,
+            _message: PhantomData,
+        })
+    }
+
+    pub fn config(&self) -> &SubscriberConfig {
+        &self.config
+    }
+
+    async fn connection(&self) -> Result<ConnectionManager> {
+        let conn = self
+            .conn
+            .get_or_try_init(|| async {
+                let mut conn = ConnectionManager::new_with_backoff(self.client.clone(), 2, 100, 2).await?;
+                self.create_group(&mut conn).await?;
+                anyhow::Ok(conn)
+            })
+            .await?;
+        Ok(conn.clone())
+    }
+
+    /// XGROUP CREATE ... MKSTREAM from the start of the stream; an existing
+    /// group is kept as is
+    async fn create_group(&self, conn: &mut ConnectionManager) -> Result<()> {
+        let created: redis::RedisResult<()> = conn.xgroup_create_mkstream(&self.config.stream, &self.config.group, "0").await;
+        match created {
+            Ok(()) => info!("Created consumer group {} on {}", self.config.group, self.config.stream),
+            Err(e) if e.code() == Some("BUSYGROUP") => {}
+            Err(e) => return Err(e.into()),
+        }
+        Ok(())
+    }
+
+    /// Entries never delivered to any consumer of the group, waiting up to
+    /// `block` for new ones
+    pub async fn read(&self) -> Result<Vec<Entry<T>>> {
+        let mut conn = self.connection().await?;
+        let options = StreamReadOptions::default()
+            .group(&self.config.group, &self.config.consumer)
+            .count(self.config.batch.max(1))
+            .block(self.config.block.as_millis() as usize);
+        let reply: Option<StreamReadReply> = conn.xread_options(&[&self.config.stream], &[">"], &options).await?;
+        let ids = reply.into_iter().flat_map(|r| r.keys).flat_map(|k| k.ids).collect();
+        self.decode(&mut conn, ids).await
+    }
+
+    /// Take over entries pending on other consumers for at least `claim_idle`,
+    /// oldest first, up to `batch` at a time
+    pub async fn claim_pending(&self) -> Result<Vec<Entry<T>>> {
+        let mut conn = self.connection().await?;
+        let pending: StreamPendingCountReply = conn
+            .xpending_count(&self.config.stream, &self.config.group, "-", "+", self.config.batch.max(1))
+            .await?;
+        let ids = claimable(&pending, self.config.claim_idle);
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let min_idle = self.config.claim_idle.as_millis() as usize;
+        let claimed: StreamClaimReply = conn
+            .xclaim(&self.config.stream, &self.config.group, &self.config.consumer, min_idle, &ids)
+            .await?;
+        self.decode(&mut conn, claimed.ids).await
+    }
+
+    /// Acknowledge processed entries; returns how many were still pending
+    pub async fn ack(&self, ids: &[String]) -> Result<usize> {
+        if ids.is_empty() {
+            return Ok(0);
+        }
+        let mut conn = self.connection().await?;
+        Ok(conn.xack(&self.config.stream, &self.config.group, ids).await?)
+    }
+
+    /// Decode entries, acknowledging the ones that are malformed
+    async fn decode(&self, conn: &mut ConnectionManager, ids: Vec<StreamId>) -> Result<Vec<Entry<T>>> {
+        let (entries, malformed) = decode_entries(ids);
+        if !malformed.is_empty() {
+            warn!("Dropping {} malformed entries from {}", malformed.len(), self.config.stream);
+            let _: usize = conn.xack(&self.config.stream, &self.config.group, &malformed).await?;
+        }
+        Ok(entries)
+    }
+
+    /// Feed entries into a channel from a background task: first the entries
+    /// that were pending, then new ones, re-claiming stale entries every
+    /// `claim_idle`. Receivers ack through a clone of this subscriber; the
+    /// task stops when the receiver is dropped.
+    pub fn subscribe(&self, capacity: usize) -> mpsc::Receiver<Entry<T>> {
+        let (tx, rx) = mpsc::channel(capacity.max(1));
+        let subscriber = self.clone();
+        tokio::spawn(async move {
+            let mut last_claim: Option<tokio::time::Instant> = None;
+            while !tx.is_closed() {
+                let claim_due = last_claim.is_none_or(|at| at.elapsed() >= subscriber.config.claim_idle);
+                let batch = if claim_due {
+                    last_claim = Some(tokio::time::Instant::now());
+                    subscriber.claim_pending().await
+                } else {
+                    subscriber.read().await
+                };
+                match batch {
+                    Ok(entries) => {
+                        for entry in entries {
+                            if tx.send(entry).await.is_err() {
+                                return;
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        warn!("Reading {} failed: {}", subscriber.config.stream, e);
+                        tokio::time::sleep(subscriber.config.block).await;
+                    }
+                }
+            }
+        });
+        rx
+    }
+}
+
+/// Pending ids idle for at least `min_idle`
+fn claimable(pending: &StreamPendingCountReply, min_idle: Duration) -> Vec<String> {
+    let min_idle = min_idle.as_millis() as usize;
+    pending
+        .ids
+        .iter()
+        .filter(|p| p.last_delivered_ms >= min_idle)
+        .map(|p| p.id.clone())
+        .collect()
+}
+
+/// Decoded entries and the ids of those that could not be decoded
+fn decode_entries<T: StreamMessage>(ids: Vec<StreamId>) -> (Vec<Entry<T>>, Vec<String>) {
+    let mut entries = Vec::with_capacity(ids.len());
+    let mut malformed = Vec::new();
+    for entry in ids {
+        let fields: HashMap<String, String> = entry
+            .map
+            .iter()
+            .filter_map(|(k, v)| redis::from_redis_value(v).ok().map(|v| (k.clone(), v)))
+            .collect();
+        match T::decode(&fields) {
+            Ok(message) => entries.push(Entry { id: entry.id, message }),
+            Err(e) => {
+                warn!("Malformed stream entry {}: {}", entry.id, e);
+                malformed.push(entry.id);
+            }
+        }
+    }
+    (entries, malformed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use redis::streams::StreamPendingId;
+    use redis::Value;
+
+    fn entry(id: &str, fields: &[(&str, &str)]) -> StreamId {
+        StreamId {
+            id: id.to_string(),
+            map: fields.iter().map(|(k, v)| (k.to_string(), Value::Data(v.as_bytes().to_vec()))).collect(),
+        }
+    }
+
+    #[test]
+    fn test_decodes_ticks_and_flags_malformed() {
+        let tick = r#"{"symbol":"AAPL","price":101.5,"volume":20.0,"timestamp":1.0}"#;
+        let ids = vec![entry("1-0", &[("data", tick)]), entry("2-0", &[("data", "not json")]), entry("3-0", &[])];
+        let (entries, malformed) = decode_entries::<Tick>(ids);
+        assert_eq!(entries.len(), 1);
+        assert_eq!((entries[0].id.as_str(), entries[0].message.symbol.as_str(), entries[0].message.price), ("1-0", "AAPL", 101.5));
+        assert_eq!(malformed, vec!["2-0", "3-0"]);
+    }
+
+    #[test]
+    fn test_decodes_flat_and_json_signals() {
+        let json = r#"{"id":"AAPL_1","symbol":"AAPL","score":0.8,"pattern":"flag","timestamp":2.0}"#;
+        let flat = [("id", "MSFT_1"), ("symbol", "MSFT"), ("score", "-0.4"), ("pattern", "double_top"), ("timestamp", "3.0")];
+        let (entries, malformed) = decode_entries::<Signal>(vec![entry("1-0", &[("data", json)]), entry("2-0", &flat)]);
+        assert!(malformed.is_empty());
+        assert_eq!(entries[0].message.id, "AAPL_1");
+        assert_eq!((entries[1].message.symbol.as_str(), entries[1].message.score), ("MSFT", -0.4));
+    }
+
+    #[test]
+    fn test_claims_only_idle_entries() {
+        let pending = |id: &str, idle: usize| StreamPendingId {
+            id: id.to_string(),
+            consumer: "engine-1".to_string(),
+            last_delivered_ms: idle,
+            times_delivered: 1,
+        };
+        let reply = StreamPendingCountReply { ids: vec![pending("1-0", 45_000), pending("2-0", 500), pending("3-0", 30_000)] };
+        assert_eq!(claimable(&reply, Duration::from_secs(30)), vec!["1-0", "3-0"]);
+    }
+
+    #[tokio::test]
+    async fn test_connection_is_lazy() {
+        let subscriber = Subscriber::<Tick>::new(&PublisherConfig::new("redis://127.0.0.1:1/"), SubscriberConfig::new("ticks:in", "engine", "engine-1")).unwrap();
+        assert!(subscriber.conn.get().is_none());
+        assert_eq!(subscriber.ack(&[]).await.unwrap(), 0);
+        assert!(subscriber.read().await.is_err());
+        assert!(subscriber.clone().conn.get().is_none());
+    }
+}
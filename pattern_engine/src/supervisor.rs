@@ -0,0 +1,190 @@
+//! Supervision of restartable engine subsystems.
+//!
+//! Two kinds of subsystem are registered by name:
+//! - tasks (e.g. market data feeds) are spawned by a factory; a restart aborts
+//!   the running task and spawns a fresh one, and [`Supervisor::revive_stopped`]
+//!   respawns tasks that exited on their own
+//! - resources (e.g. the publisher or pattern library) are rebuilt in place by
+//!   an async reload closure
+//!
+//! Restarting one subsystem leaves the others, and all indicator state, alone.
+
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::pin::Pin;
+use tokio::task::JoinHandle;
+
+/// Future returned by a resource reload
+pub type ReloadFuture = Pin<Box<dyn Future<Output = Result<()>> + Send>>;
+
+type SpawnFn = Box<dyn Fn() -> JoinHandle<()> + Send + Sync>;
+type ReloadFn = Box<dyn Fn() -> ReloadFuture + Send + Sync>;
+
+enum Kind {
+    Task { spawn: SpawnFn, handle: JoinHandle<()> },
+    Resource { reload: ReloadFn },
+}
+
+struct Subsystem {
+    kind: Kind,
+    restarts: u64,
+    last_restart: Option<f64>,
+    last_error: Option<String>,
+}
+
+/// Subsystem state for the admin API
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SubsystemStatus {
+    pub name: String,
+    /// `task` or `resource`
+    pub kind: &'static str,
+    /// Tasks: still running. Resources: the last reload succeeded.
+    pub running: bool,
+    pub restarts: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_restart: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+}
+
+#[derive(Default)]
+pub struct Supervisor {
+    subsystems: BTreeMap<String, Subsystem>,
+}
+
+impl Supervisor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register and start a task subsystem
+    pub fn add_task(&mut self, name: &str, spawn: impl Fn() -> JoinHandle<()> + Send + Sync + 'static) {
+        let handle = spawn();
+        let kind = Kind::Task { spawn: Box::new(spawn), handle };
+        self.insert(name, kind);
+    }
+
+    /// Register a resource subsystem rebuilt by `reload`
+    pub fn add_resource(&mut self, name: &str, reload: impl Fn() -> ReloadFuture + Send + Sync + 'static) {
+        self.insert(name, Kind::Resource { reload: Box::new(reload) });
+    }
+
+    fn insert(&mut self, name: &str, kind: Kind) {
+        let subsystem = Subsystem { kind, restarts: 0, last_restart: None, last_error: None };
+        if let Some(Subsystem { kind: Kind::Task { handle, .. }, .. }) = self.subsystems.insert(name.to_string(), subsystem) {
+            handle.abort();
+        }
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.subsystems.contains_key(name)
+    }
+
+    pub fn status(&self) -> Vec<SubsystemStatus> {
+        self.subsystems.iter().map(|(name, s)| status_of(name, s)).collect()
+    }
+
+    /// Restart one subsystem at time `now`. Reload failures are recorded on
+    /// the subsystem and returned; the previous resource stays in place.
+    pub async fn restart(&mut self, name: &str, now: f64) -> Result<SubsystemStatus> {
+        let subsystem = self
+            .subsystems
+            .get_mut(name)
+            .ok_or_else(|| anyhow!("unknown subsystem '{}'", name))?;
+        subsystem.restarts += 1;
+        subsystem.last_restart = Some(now);
+        let result = match &mut subsystem.kind {
+            Kind::Task { spawn, handle } => {
+                handle.abort();
+                *handle = spawn();
+                Ok(())
+            }
+            Kind::Resource { reload } => reload().await,
+        };
+        subsystem.last_error = result.as_ref().err().map(|e| format!("{:#}", e));
+        result.map(|_| status_of(name, subsystem))
+    }
+
+    /// Respawn task subsystems that have exited; returns their names
+    pub fn revive_stopped(&mut self, now: f64) -> Vec<String> {
+        let mut revived = Vec::new();
+        for (name, subsystem) in self.subsystems.iter_mut() {
+            if let Kind::Task { spawn, handle } = &mut subsystem.kind {
+                if handle.is_finished() {
+                    *handle = spawn();
+                    subsystem.restarts += 1;
+                    subsystem.last_restart = Some(now);
+                    revived.push(name.clone());
+                }
+            }
+        }
+        revived
+    }
+}
+
+fn status_of(name: &str, s: &Subsystem) -> SubsystemStatus {
+    let (kind, running) = match &s.kind {
+        Kind::Task { handle, .. } => ("task", !handle.is_finished()),
+        Kind::Resource { .. } => ("resource", s.last_error.is_none()),
+    };
+    SubsystemStatus {
+        name: name.to_string(),
+        kind,
+        running,
+        restarts: s.restarts,
+        last_restart: s.last_restart,
+        last_error: s.last_error.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_restart_task_and_resource() {
+        let spawned = Arc::new(AtomicU32::new(0));
+        let reloads = Arc::new(AtomicU32::new(0));
+        let mut sup = Supervisor::new();
+        let counter = spawned.clone();
+        sup.add_task("feeds", move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+            tokio::spawn(std::future::pending())
+        });
+        let counter = reloads.clone();
+        sup.add_resource("pattern_lib", move || {
+            let n = counter.fetch_add(1, Ordering::SeqCst);
+            Box::pin(async move { if n == 1 { Err(anyhow!("bad definitions")) } else { Ok(()) } })
+        });
+        assert_eq!(spawned.load(Ordering::SeqCst), 1);
+
+        let status = sup.restart("feeds", 10.0).await.unwrap();
+        assert_eq!((status.kind, status.running, status.restarts), ("task", true, 1));
+        assert_eq!(spawned.load(Ordering::SeqCst), 2);
+
+        assert!(sup.restart("pattern_lib", 11.0).await.unwrap().running);
+        let err = sup.restart("pattern_lib", 12.0).await.unwrap_err();
+        assert_eq!(err.to_string(), "bad definitions");
+        let status = sup.status().into_iter().find(|s| s.name == "pattern_lib").unwrap();
+        assert_eq!((status.running, status.restarts), (false, 2));
+        assert_eq!(status.last_error.as_deref(), Some("bad definitions"));
+
+        assert!(sup.restart("aggregator", 13.0).await.is_err());
+        assert!(!sup.contains("aggregator"));
+    }
+
+    #[tokio::test]
+    async fn test_revives_exited_tasks() {
+        let mut sup = Supervisor::new();
+        sup.add_task("feeds", || tokio::spawn(async {}));
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!sup.status()[0].running);
+        assert_eq!(sup.revive_stopped(1.0), vec!["feeds"]);
+        assert_eq!(sup.status()[0].restarts, 1);
+    }
+}
@@ -0,0 +1,204 @@
+//! Logging of suppressed (rejected) signal candidates.
+//!
+//! Candidates rejected by cooldown, vetoed by an anti-pattern, that narrowly
+//! missed the score threshold, or that were dropped later as duplicates,
+//! held back by the publish rate limit or detected before warm-up finished
+//! are normally invisible. This module decides which of them to record (by
+//! tier, sampling and a rate limit) so thresholds can be tuned from data.
+
+use crate::publisher::Signal;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+/// Why a candidate signal was not emitted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SuppressionReason {
+    /// Score passed the threshold but the pattern was in cooldown
+    Cooldown,
+    /// Score fell short of the threshold (near miss)
+    BelowThreshold,
+    /// Vetoed by an active anti-pattern
+    AntiPattern,
+    /// Dropped as a duplicate of a recently published signal
+    Duplicate,
+    /// Held back by the publish rate limit of its stream
+    RateLimited,
+    /// Detected before the detector finished warming up
+    WarmUp,
+}
+
+impl SuppressionReason {
+    /// Logging tier; lower tiers are closer to being emitted
+    pub fn tier(&self) -> u8 {
+        match self {
+            SuppressionReason::Cooldown
+            | SuppressionReason::AntiPattern
+            | SuppressionReason::Duplicate
+            | SuppressionReason::RateLimited => 1,
+            SuppressionReason::BelowThreshold | SuppressionReason::WarmUp => 2,
+        }
+    }
+}
+
+/// A rejected candidate together with the reason
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SuppressedSignal {
+    pub reason: SuppressionReason,
+    pub tier: u8,
+    /// Human readable detail, e.g. remaining cooldown
+    pub detail: String,
+    pub signal: Signal,
+}
+
+impl SuppressedSignal {
+    pub fn new(reason: SuppressionReason, detail: String, signal: Signal) -> Self {
+        Self {
+            reason,
+            tier: reason.tier(),
+            detail,
+            signal,
+        }
+    }
+}
+
+/// Which suppressed candidates are recorded
+#[derive(Debug, Clone)]
+pub struct SuppressionConfig {
+    /// Highest tier recorded (1 = cooldowns, vetoes, duplicates and rate
+    /// limits, 2 = also near misses and warm-up detections)
+    pub max_tier: u8,
+    /// Fraction of eligible candidates recorded (0..1)
+    pub sample_rate: f64,
+    /// Maximum records per second across all symbols
+    pub max_per_sec: f64,
+}
+
+impl Default for SuppressionConfig {
+    fn default() -> Self {
+        Self {
+            max_tier: 1,
+            sample_rate: 1.0,
+            max_per_sec: 10.0,
+        }
+    }
+}
+
+/// Tier filter, sampler and token-bucket rate limiter for suppressed signals
+#[derive(Debug)]
+pub struct SuppressionLogger {
+    config: SuppressionConfig,
+    tokens: f64,
+    last_refill: Option<f64>,
+    dropped: u64,
+    file: Option<File>,
+}
+
+impl SuppressionLogger {
+    pub fn new(config: SuppressionConfig) -> Self {
+        let tokens = config.max_per_sec;
+        Self {
+            config,
+            tokens,
+            last_refill: None,
+            dropped: 0,
+            file: None,
+        }
+    }
+
+    /// Also append admitted records as JSON lines to `path`
+    pub fn with_file(mut self, path: &Path) -> Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| anyhow!("failed to open {}: {}", path.display(), e))?;
+        self.file = Some(file);
+        Ok(self)
+    }
+
+    /// Decide whether to record `s` at time `now` (seconds). `sample` is a
+    /// uniform random draw in [0, 1).
+    pub fn admit(&mut self, s: &SuppressedSignal, now: f64, sample: f64) -> bool {
+        if s.tier > self.config.max_tier || sample >= self.config.sample_rate {
+            return false;
+        }
+
+        // refill token bucket
+        if let Some(last) = self.last_refill {
+            let elapsed = (now - last).max(0.0);
+            self.tokens = (self.tokens + elapsed * self.config.max_per_sec).min(self.config.max_per_sec);
+        }
+        self.last_refill = Some(now);
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            self.dropped += 1;
+            false
+        }
+    }
+
+    /// Append a record to the file sink, if configured
+    pub fn write(&mut self, s: &SuppressedSignal) -> Result<()> {
+        if let Some(f) = self.file.as_mut() {
+            let line = serde_json::to_string(s)?;
+            writeln!(f, "{}", line)?;
+        }
+        Ok(())
+    }
+
+    /// Number of eligible records dropped by the rate limiter
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn suppressed(reason: SuppressionReason) -> SuppressedSignal {
+        let signal = Signal::test("AAPL", "ema_crossover", 0.5, 1.0);
+        SuppressedSignal::new(reason, "test".to_string(), signal)
+    }
+
+    #[test]
+    fn test_tier_and_sampling() {
+        let mut log = SuppressionLogger::new(SuppressionConfig { max_tier: 1, sample_rate: 0.5, max_per_sec: 100.0 });
+        assert!(log.admit(&suppressed(SuppressionReason::Cooldown), 0.0, 0.1));
+        assert!(!log.admit(&suppressed(SuppressionReason::Cooldown), 0.0, 0.7));
+        assert!(!log.admit(&suppressed(SuppressionReason::BelowThreshold), 0.0, 0.1));
+        assert!(log.admit(&suppressed(SuppressionReason::Duplicate), 0.0, 0.1));
+        assert!(!log.admit(&suppressed(SuppressionReason::WarmUp), 0.0, 0.1));
+    }
+
+    #[test]
+    fn test_rate_limit() {
+        let mut log = SuppressionLogger::new(SuppressionConfig { max_tier: 2, sample_rate: 1.0, max_per_sec: 2.0 });
+        let s = suppressed(SuppressionReason::BelowThreshold);
+        assert!(log.admit(&s, 0.0, 0.0));
+        assert!(log.admit(&s, 0.0, 0.0));
+        assert!(!log.admit(&s, 0.1, 0.0));
+        assert_eq!(log.dropped(), 1);
+        // one second later the bucket is full again
+        assert!(log.admit(&s, 1.1, 0.0));
+    }
+
+    #[test]
+    fn test_file_sink() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("suppressed.jsonl");
+        let mut log = SuppressionLogger::new(SuppressionConfig::default()).with_file(&path).unwrap();
+        log.write(&suppressed(SuppressionReason::Cooldown)).unwrap();
+        let data = std::fs::read_to_string(&path).unwrap();
+        assert!(data.contains("\"reason\":\"cooldown\""));
+        log.write(&suppressed(SuppressionReason::RateLimited)).unwrap();
+        let data = std::fs::read_to_string(&path).unwrap();
+        assert!(data.contains("\"reason\":\"rate_limited\""));
+    }
+}
@@ -0,0 +1,221 @@
+//! Per-symbol detection, shared by the service and replay.
+//!
+//! [`roll_candles`] aggregates a symbol's trades into [`CANDLE_INTERVALS`]
+//! bars. A [`SymbolState`] runs the detection pipeline, composites and
+//! anti-patterns over each closed bar ([`SymbolState::on_candle`]) and each
+//! trade ([`SymbolState::on_tick`]), and returns the signals with the feature
+//! vectors the models score, so replayed history yields the same signals as
+//! live ticks. Enrichment and publishing are left to the caller.
+
+use crate::clock::{wall_now, EventClock};
+use crate::features;
+use crate::flags::{self, FeatureFlags};
+use crate::patterns::candlestick::Candle;
+use crate::patterns::composite::CompositeState;
+use crate::patterns::pipeline::DetectionPipeline;
+use crate::patterns::PatternLibrary;
+use crate::publisher::{Signal, Tick};
+use crate::rules::Rule;
+use crate::suppressed::SuppressedSignal;
+use std::collections::BTreeMap;
+
+/// Candle intervals aggregated for every symbol (60s and 5min)
+pub const CANDLE_INTERVALS: [u64; 2] = [60, 300];
+
+/// Roll a trade at `timestamp` into a symbol's bars under construction, one
+/// per interval. Returns the bars it closed, shortest interval first.
+/// Heartbeats close bars without trading into them.
+pub fn roll_candles(
+    candles: &mut BTreeMap<u64, Candle>,
+    price: f64,
+    volume: f64,
+    timestamp: f64,
+    heartbeat: bool,
+) -> Vec<(u64, Candle)> {
+    let mut closed = Vec::new();
+    for &interval in &CANDLE_INTERVALS {
+        let start = (timestamp as u64 / interval) * interval;
+        let candle = candles.entry(interval).or_insert_with(|| Candle::from_trade(start, price, volume));
+        if candle.start == start {
+            if !heartbeat {
+                candle.high = candle.high.max(price);
+                candle.low = candle.low.min(price);
+                candle.close = price;
+                candle.volume += volume;
+            }
+        } else {
+            closed.push((interval, std::mem::replace(candle, Candle::from_trade(start, price, volume))));
+        }
+    }
+    closed
+}
+
+/// Signals detected on one trade, each with its model features
+#[derive(Debug, Default)]
+pub struct TickSignals {
+    /// The tick-level detectors' signal
+    pub signal: Option<(Signal, Vec<f64>)>,
+    /// Rules, mean reversion, order flow and composites
+    pub others: Vec<(Signal, Vec<f64>)>,
+    /// Signals vetoed by anti-patterns
+    pub vetoed: Vec<SuppressedSignal>,
+}
+
+/// Per-symbol detection pipeline plus ingest bookkeeping
+#[derive(Debug)]
+pub struct SymbolState {
+    pub pipeline: DetectionPipeline,
+    /// Recently emitted patterns for composite evaluation
+    pub composites: CompositeState,
+    /// Event clock of the last real (non-heartbeat) tick
+    pub tick_clock: EventClock,
+    /// Event time of the last heartbeat
+    pub last_heartbeat_time: f64,
+}
+
+impl SymbolState {
+    pub fn new(pipeline: DetectionPipeline) -> Self {
+        Self { pipeline, composites: CompositeState::default(), tick_clock: EventClock::default(), last_heartbeat_time: 0.0 }
+    }
+
+    /// Detect on a bar of `interval` seconds that just closed. Returns the
+    /// signals with their features and those vetoed by anti-patterns.
+    pub fn on_candle(
+        &mut self,
+        library: &PatternLibrary,
+        closed: &Candle,
+        interval: u64,
+        flags: &FeatureFlags,
+    ) -> (Vec<(Signal, Vec<f64>)>, Vec<SuppressedSignal>) {
+        let mut signals = Vec::new();
+        if let Some(mut sig) = self.pipeline.update_and_detect(closed.close, closed.volume, closed.start as f64, None) {
+            // suffix pattern with interval for context
+            sig.pattern = format!("{}:{}s", sig.pattern, interval);
+            signals.push(sig);
+        }
+        signals.extend(self.pipeline.detect_on_candle(closed, interval, flags));
+
+        // Composite windows count bars of the shortest interval
+        if interval == CANDLE_INTERVALS[0] {
+            self.composites.on_bar();
+        }
+        let composites = library.evaluate_composites(&mut self.composites, &signals);
+        signals.extend(composites);
+        let (signals, vetoed) = library.screen_anti_patterns(&self.composites, signals);
+
+        let avg_volume = self.pipeline.indicators().avg_volume;
+        let signals = signals
+            .into_iter()
+            .map(|sig| {
+                let features =
+                    features::signal_features(sig.meta.as_ref(), closed.close, closed.volume, avg_volume, Some(closed.open));
+                (sig, features)
+            })
+            .collect();
+        (signals, vetoed)
+    }
+
+    /// Count a trade at `timestamp` without detecting on it
+    pub fn record_tick(&mut self, volume: f64, timestamp: f64) {
+        self.pipeline.record_arrival(volume, timestamp);
+        self.tick_clock.observe(timestamp, wall_now());
+    }
+
+    /// Event time for a heartbeat when neither a trade nor a heartbeat was
+    /// seen within `cadence` seconds of event time; `wall` is the current
+    /// wall-clock time. None before the first trade.
+    pub fn heartbeat_due(&mut self, wall: f64, cadence: f64) -> Option<f64> {
+        if self.tick_clock == EventClock::default() {
+            return None;
+        }
+        let now = self.tick_clock.now(wall);
+        if now - self.tick_clock.last().max(self.last_heartbeat_time) < cadence {
+            return None;
+        }
+        self.last_heartbeat_time = now;
+        Some(now)
+    }
+
+    /// Record a trade and run tick-level detection, rules, mean reversion,
+    /// order flow (when flagged), composites and anti-patterns on it
+    pub fn on_tick(
+        &mut self,
+        library: &PatternLibrary,
+        rules: &[Rule],
+        tick: &Tick,
+        timestamp: f64,
+        flags: &FeatureFlags,
+    ) -> TickSignals {
+        let (price, volume) = (tick.price, tick.volume);
+        self.record_tick(volume, timestamp);
+        let signal = self.pipeline.update_and_detect(price, volume, timestamp, tick.side);
+        let mut others = self.pipeline.evaluate_rules(rules, price, volume, timestamp);
+        others.extend(self.pipeline.detect_mean_reversion(price, volume, timestamp));
+        if flags.is_enabled(flags::ORDER_FLOW_PATTERNS) {
+            others.extend(self.pipeline.detect_order_flow(tick));
+        }
+        let emitted: Vec<Signal> = signal.iter().chain(&others).cloned().collect();
+        others.extend(library.evaluate_composites(&mut self.composites, &emitted));
+        // Anti-patterns veto or down-weight what was just detected
+        let (signal, mut vetoed) = library.screen_anti_patterns(&self.composites, signal.into_iter().collect());
+        let (others, more) = library.screen_anti_patterns(&self.composites, others);
+        vetoed.extend(more);
+        TickSignals {
+            signal: signal.into_iter().next().map(|sig| self.with_tick_features(sig, price, volume)),
+            others: others.into_iter().map(|sig| self.with_tick_features(sig, price, volume)).collect(),
+            vetoed,
+        }
+    }
+
+    /// Let time pass without a trade; detectors that fire on silence may
+    /// signal
+    pub fn on_heartbeat(&mut self, tick: &Tick, timestamp: f64) -> Option<(Signal, Vec<f64>)> {
+        let signal = self.pipeline.heartbeat(timestamp)?;
+        Some(self.with_tick_features(signal, tick.price, tick.volume))
+    }
+
+    fn with_tick_features(&self, sig: Signal, price: f64, volume: f64) -> (Signal, Vec<f64>) {
+        let avg_volume = self.pipeline.indicators().avg_volume;
+        let features = features::signal_features(sig.meta.as_ref(), price, volume, avg_volume, None);
+        (sig, features)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roll_candles_closes_each_interval() {
+        let mut candles = BTreeMap::new();
+        assert!(roll_candles(&mut candles, 10.0, 1.0, 0.0, false).is_empty());
+        assert!(roll_candles(&mut candles, 12.0, 1.0, 30.0, false).is_empty());
+        // a heartbeat neither trades nor closes inside the bar
+        assert!(roll_candles(&mut candles, 99.0, 5.0, 59.0, true).is_empty());
+        let closed = roll_candles(&mut candles, 11.0, 1.0, 60.0, false);
+        assert_eq!(closed.len(), 1);
+        let (interval, bar) = &closed[0];
+        assert_eq!((*interval, bar.start, bar.open, bar.high, bar.close), (60, 0, 10.0, 12.0, 12.0));
+
+        let closed = roll_candles(&mut candles, 13.0, 1.0, 300.0, true);
+        assert_eq!(closed.iter().map(|(i, _)| *i).collect::<Vec<_>>(), vec![60, 300]);
+        assert_eq!(closed[1].1.high, 12.0);
+    }
+
+    #[test]
+    fn test_heartbeats_follow_the_event_clock() {
+        use crate::universe::DetectionThresholds;
+        let mut state = SymbolState::new(DetectionPipeline::new("AAPL", DetectionThresholds::default(), Default::default()));
+        assert_eq!(state.heartbeat_due(wall_now(), 10.0), None);
+
+        // exchange time far behind the local clock must not read as quiet
+        state.record_tick(1.0, 1_000.0);
+        let wall = wall_now();
+        assert_eq!(state.heartbeat_due(wall + 5.0, 10.0), None);
+        let at = state.heartbeat_due(wall + 11.0, 10.0).unwrap();
+        assert!((1_011.0..1_012.0).contains(&at), "heartbeat stamped {}", at);
+        // the next one is a full cadence later on the same clock
+        assert_eq!(state.heartbeat_due(wall + 15.0, 10.0), None);
+        assert!(state.heartbeat_due(wall + 22.0, 10.0).is_some());
+    }
+}
@@ -0,0 +1,252 @@
+//! Experiment tracking for benchmark and parameter-sweep runs.
+//!
+//! A [`RunRecord`] captures what was run (parameters, dataset hash, git sha)
+//! and what came out (metrics). Records go either to a JSON-lines file or to
+//! an MLflow-compatible tracking server over its REST API, so runs made with
+//! different parameters can be compared and reproduced later.
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// One tracked run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunRecord {
+    pub run_id: String,
+    pub experiment: String,
+    /// Unix timestamps (seconds)
+    pub started_at: f64,
+    pub ended_at: f64,
+    pub params: BTreeMap<String, String>,
+    /// FNV-1a hash of the input data, when the run read a dataset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dataset_hash: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git_sha: Option<String>,
+    pub metrics: BTreeMap<String, f64>,
+}
+
+impl RunRecord {
+    pub fn new(experiment: &str, started_at: f64) -> Self {
+        Self {
+            run_id: format!("{}-{:08x}", started_at as u64, rand::random::<u32>()),
+            experiment: experiment.to_string(),
+            started_at,
+            ended_at: started_at,
+            params: BTreeMap::new(),
+            dataset_hash: None,
+            git_sha: git_sha(),
+            metrics: BTreeMap::new(),
+        }
+    }
+
+    pub fn param(&mut self, key: &str, value: impl ToString) -> &mut Self {
+        self.params.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    pub fn metric(&mut self, key: &str, value: f64) -> &mut Self {
+        self.metrics.insert(key.to_string(), value);
+        self
+    }
+}
+
+/// Commit of the running build: `GIT_SHA` if set, else `git rev-parse HEAD`
+pub fn git_sha() -> Option<String> {
+    if let Ok(sha) = std::env::var("GIT_SHA") {
+        return Some(sha).filter(|s| !s.is_empty());
+    }
+    let out = std::process::Command::new("git").args(["rev-parse", "HEAD"]).output().ok()?;
+    out.status
+        .success()
+        .then(|| String::from_utf8_lossy(&out.stdout).trim().to_string())
+        .filter(|s| !s.is_empty())
+}
+
+/// FNV-1a 64-bit hash of a file's contents, hex encoded
+pub fn dataset_hash(path: &Path) -> Result<String> {
+    let mut file = std::fs::File::open(path).with_context(|| format!("opening {}", path.display()))?;
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        for b in &buf[..n] {
+            hash ^= *b as u64;
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+    Ok(format!("{:016x}", hash))
+}
+
+/// Where run records are sent
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExperimentTracker {
+    /// Append one JSON record per line
+    JsonLines(PathBuf),
+    /// MLflow tracking server base URL and experiment ID
+    Mlflow { base_url: String, experiment_id: String },
+}
+
+impl FromStr for ExperimentTracker {
+    type Err = anyhow::Error;
+
+    /// `jsonl:PATH` or `mlflow:URL[#EXPERIMENT_ID]` (experiment defaults to "0")
+    fn from_str(s: &str) -> Result<Self> {
+        let (kind, target) = s.split_once(':').ok_or_else(|| anyhow!("tracker spec '{}' is not kind:target", s))?;
+        if target.is_empty() {
+            bail!("tracker spec '{}' has no target", s);
+        }
+        match kind {
+            "jsonl" => Ok(Self::JsonLines(PathBuf::from(target))),
+            "mlflow" => {
+                let (url, experiment) = target.split_once('#').unwrap_or((target, "0"));
+                Ok(Self::Mlflow {
+                    base_url: url.trim_end_matches('/').to_string(),
+                    experiment_id: experiment.to_string(),
+                })
+            }
+            other => bail!("unknown tracker kind '{}' (expected jsonl or mlflow)", other),
+        }
+    }
+}
+
+impl ExperimentTracker {
+    pub async fn log_run(&self, run: &RunRecord) -> Result<()> {
+        match self {
+            Self::JsonLines(path) => {
+                let mut file = std::fs::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .with_context(|| format!("opening {}", path.display()))?;
+                writeln!(file, "{}", serde_json::to_string(run)?)?;
+                Ok(())
+            }
+            Self::Mlflow { base_url, experiment_id } => log_mlflow(base_url, experiment_id, run).await,
+        }
+    }
+}
+
+async fn log_mlflow(base_url: &str, experiment_id: &str, run: &RunRecord) -> Result<()> {
+    let client = reqwest::Client::new();
+    let api = |method: &str| format!("{}/api/2.0/mlflow/runs/{}", base_url, method);
+
+    let created: Value = client
+        .post(api("create"))
+        .json(&json!({
+            "experiment_id": experiment_id,
+            "run_name": run.run_id,
+            "start_time": (run.started_at * 1000.0) as i64,
+        }))
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    let run_id = created["run"]["info"]["run_id"]
+        .as_str()
+        .ok_or_else(|| anyhow!("MLflow runs/create response has no run_id"))?;
+
+    client.post(api("log-batch")).json(&mlflow_batch(run_id, run)).send().await?.error_for_status()?;
+    client
+        .post(api("update"))
+        .json(&json!({ "run_id": run_id, "status": "FINISHED", "end_time": (run.ended_at * 1000.0) as i64 }))
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(())
+}
+
+/// `runs/log-batch` body: params and metrics, with provenance as tags
+fn mlflow_batch(run_id: &str, run: &RunRecord) -> Value {
+    let kv = |k: &str, v: &str| json!({ "key": k, "value": v });
+    let timestamp = (run.ended_at * 1000.0) as i64;
+    let mut tags = vec![kv("experiment", &run.experiment)];
+    if let Some(sha) = &run.git_sha {
+        tags.push(kv("mlflow.source.git.commit", sha));
+    }
+    if let Some(hash) = &run.dataset_hash {
+        tags.push(kv("dataset_hash", hash));
+    }
+    json!({
+        "run_id": run_id,
+        "params": run.params.iter().map(|(k, v)| kv(k, v)).collect::<Vec<_>>(),
+        "metrics": run
+            .metrics
+            .iter()
+            .map(|(k, v)| json!({ "key": k, "value": v, "timestamp": timestamp, "step": 0 }))
+            .collect::<Vec<_>>(),
+        "tags": tags,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record() -> RunRecord {
+        let mut run = RunRecord::new("bench", 1_700_000_000.0);
+        run.git_sha = Some("abc123".into());
+        run.param("iterations", 1000).metric("tick_detection.p99_ns", 420.0);
+        run
+    }
+
+    #[test]
+    fn test_parse_tracker_spec() {
+        assert_eq!(
+            "jsonl:/tmp/runs.jsonl".parse::<ExperimentTracker>().unwrap(),
+            ExperimentTracker::JsonLines(PathBuf::from("/tmp/runs.jsonl"))
+        );
+        assert_eq!(
+            "mlflow:http://mlflow:5000/#7".parse::<ExperimentTracker>().unwrap(),
+            ExperimentTracker::Mlflow { base_url: "http://mlflow:5000".into(), experiment_id: "7".into() }
+        );
+        assert!("mlflow:".parse::<ExperimentTracker>().is_err());
+        assert!("wandb:x".parse::<ExperimentTracker>().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_jsonl_appends_records() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("runs.jsonl");
+        let tracker = ExperimentTracker::JsonLines(path.clone());
+        tracker.log_run(&record()).await.unwrap();
+        tracker.log_run(&record()).await.unwrap();
+
+        let data = std::fs::read_to_string(&path).unwrap();
+        let runs: Vec<RunRecord> = data.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+        assert_eq!(runs.len(), 2);
+        assert_eq!(runs[0].params["iterations"], "1000");
+        assert_eq!(runs[1].metrics["tick_detection.p99_ns"], 420.0);
+    }
+
+    #[test]
+    fn test_mlflow_batch_payload() {
+        let mut run = record();
+        run.dataset_hash = Some("deadbeef".into());
+        let body = mlflow_batch("r1", &run);
+        assert_eq!(body["params"][0], json!({ "key": "iterations", "value": "1000" }));
+        assert_eq!(body["metrics"][0]["key"], "tick_detection.p99_ns");
+        assert_eq!(body["tags"][1]["value"], "abc123");
+        assert_eq!(body["tags"][2]["key"], "dataset_hash");
+    }
+
+    #[test]
+    fn test_dataset_hash_is_content_based() {
+        let dir = tempfile::tempdir().unwrap();
+        let (a, b) = (dir.path().join("a.csv"), dir.path().join("b.csv"));
+        std::fs::write(&a, "symbol,price\nAAPL,1\n").unwrap();
+        std::fs::write(&b, "symbol,price\nAAPL,1\n").unwrap();
+        assert_eq!(dataset_hash(&a).unwrap(), dataset_hash(&b).unwrap());
+        std::fs::write(&b, "symbol,price\nAAPL,2\n").unwrap();
+        assert_ne!(dataset_hash(&a).unwrap(), dataset_hash(&b).unwrap());
+        assert_eq!(dataset_hash(&a).unwrap().len(), 16);
+    }
+}
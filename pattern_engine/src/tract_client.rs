@@ -0,0 +1,376 @@
+//! Pure-Rust ONNX inference with tract.
+//!
+//! With the `tract` feature, [`TractClient`] runs the same model files as
+//! [`OnnxClient`](crate::onnx_client::OnnxClient) without linking the ONNX
+//! Runtime shared library, for static musl builds and WASM. Select it with
+//! `INFERENCE_BACKEND=tract`. Models are checked the same way when they load:
+//! a single `[n]` or `[batch, n]` input whose width the engine sends, the
+//! `feature_schema` metadata, a score output, and the scaler and class label
+//! sidecars. Inputs must be float32 or float64; quantized inputs, execution
+//! providers and thread settings are ONNX Runtime only. A dynamic batch
+//! dimension is resolved per call, so [`TractClient::infer_batch`] scores
+//! all rows in one run.
+//!
+//! Without the feature [`TractClient::with_config`] returns an error.
+
+#[cfg(feature = "tract")]
+use crate::labels::{check_label_count, ClassLabels};
+#[cfg(feature = "tract")]
+use crate::onnx_client::{
+    check_feature_schema, check_feature_width, check_output_shape, feature_width, model_rows, output_stride, row_scores,
+    warm_up, warmup_widths, FileStamp,
+};
+#[cfg(feature = "tract")]
+use crate::scaler::FeatureScaler;
+#[cfg(feature = "tract")]
+use anyhow::{anyhow, bail};
+#[cfg(feature = "tract")]
+use std::sync::Arc;
+#[cfg(feature = "tract")]
+use tract_onnx::prelude::*;
+
+use crate::inference_metrics::ModelStats;
+use crate::labels::Classification;
+use crate::onnx_client::{ModelFile, OnnxConfig, WarmupReport};
+use anyhow::Result;
+use std::path::Path;
+#[cfg(feature = "tract")]
+use std::sync::Mutex;
+
+/// An optimized plan and what its input looks like
+#[cfg(feature = "tract")]
+struct Model {
+    plan: TypedRunnableModel<TypedModel>,
+    input_rank: usize,
+    /// Input is `[batch, n]` with a dynamic batch size
+    batched: bool,
+    input_type: DatumType,
+    /// Fixed feature count, if the model declares one
+    width: Option<usize>,
+    scaler: Option<FeatureScaler>,
+    labels: Option<ClassLabels>,
+}
+
+/// Dimensions as ONNX Runtime reports them: -1 when symbolic
+#[cfg(feature = "tract")]
+fn dims(fact: &TypedFact) -> Vec<i64> {
+    fact.shape.iter().map(|d| d.as_i64().unwrap_or(-1)).collect()
+}
+
+#[cfg(feature = "tract")]
+impl Model {
+    /// Load the model at `path` and check its input and outputs
+    fn load(path: &Path, config: &OnnxConfig) -> Result<Self> {
+        if !path.is_file() {
+            bail!("model file {} not found", path.display());
+        }
+        let onnx = tract_onnx::onnx();
+        let proto = onnx
+            .proto_model_for_path(path)
+            .map_err(|e| anyhow!("failed to load model {}: {}", path.display(), e))?;
+        let schema = proto.metadata_props.iter().find(|p| p.key == "feature_schema").map(|p| p.value.as_str());
+        check_feature_schema(schema, config.feature_schema.as_deref())
+            .map_err(|e| anyhow!("model {}: {}", path.display(), e))?;
+        let model = onnx
+            .parse(&proto, path.parent().and_then(Path::to_str))
+            .and_then(|parsed| parsed.model.into_typed())
+            .map_err(|e| anyhow!("failed to load model {}: {:#}", path.display(), e))?;
+        let [input] = model.inputs.as_slice() else {
+            bail!("model {} must take a single feature input, has {}", path.display(), model.inputs.len());
+        };
+        let input_fact = model.outlet_fact(*input)?;
+        let input_type = input_fact.datum_type;
+        if !matches!(input_type, DatumType::F32 | DatumType::F64) {
+            bail!("model {} input must be float32 or float64 for tract, got {:?}", path.display(), input_type);
+        }
+        let shape = dims(input_fact);
+        let width = feature_width(&shape)?;
+        check_feature_width(width, &config.feature_widths).map_err(|e| anyhow!("model {}: {}", path.display(), e))?;
+        let output = model.output_fact(0).map_err(|_| anyhow!("model {} has no outputs", path.display()))?;
+        if !matches!(output.datum_type, DatumType::F32 | DatumType::F64) {
+            bail!("model {} output must be float32 or float64, got {:?}", path.display(), output.datum_type);
+        }
+        let output_shape = dims(output);
+        check_output_shape(&output_shape).map_err(|e| anyhow!("model {}: {}", path.display(), e))?;
+        let labels = ClassLabels::load_for_model(path)?;
+        if let Some(labels) = &labels {
+            check_label_count(&output_shape, labels).map_err(|e| anyhow!("model {}: {}", path.display(), e))?;
+        }
+        let scaler = FeatureScaler::load_for_model(path)?;
+        if let Some(scaler) = &scaler {
+            if width.is_some_and(|w| w != scaler.len()) {
+                bail!("model {} takes {:?} features but its scaler lists {}", path.display(), width, scaler.len());
+            }
+            check_feature_width(Some(scaler.len()), &config.feature_widths)
+                .map_err(|e| anyhow!("scaler for model {}: {}", path.display(), e))?;
+        }
+        let batched = shape.len() == 2 && shape[0] < 0;
+        let plan = model
+            .into_optimized()
+            .and_then(|m| m.into_runnable())
+            .map_err(|e| anyhow!("failed to optimize model {}: {:#}", path.display(), e))?;
+        Ok(Self { plan, input_rank: shape.len(), batched, input_type, width, scaler, labels })
+    }
+
+    fn warm_up(&self, config: &OnnxConfig) -> Result<Option<WarmupReport>> {
+        let fixed = self.scaler.as_ref().map(FeatureScaler::len).or(self.width);
+        warm_up(config.warmup_runs, &warmup_widths(fixed, &config.feature_widths), |row| Ok(self.run(&[row])?[0]))
+    }
+
+    /// One score per row, each clamped to [-1, 1]
+    fn run(&self, rows: &[&[f64]]) -> Result<Vec<f64>> {
+        let (values, stride) = self.outputs(rows)?;
+        row_scores(&values, stride)
+    }
+
+    fn classify(&self, features: &[f64]) -> Result<Option<Classification>> {
+        let Some(labels) = &self.labels else {
+            return Ok(None);
+        };
+        let (values, _) = self.outputs(&[features])?;
+        labels.top(&values).map(Some)
+    }
+
+    /// Flattened first output for `rows` and the number of values per row;
+    /// rows are scaled first
+    fn outputs(&self, rows: &[&[f64]]) -> Result<(Vec<f64>, usize)> {
+        let rows = model_rows(rows, self.scaler.as_ref(), self.width)?;
+        let Some(width) = rows.first().map(Vec::len) else {
+            return Ok((Vec::new(), 0));
+        };
+        let shape = if self.input_rank == 2 { vec![rows.len(), width] } else { vec![width] };
+        let data = rows.concat();
+        let input = match self.input_type {
+            DatumType::F32 => Tensor::from_shape(&shape, &data.iter().map(|&f| f as f32).collect::<Vec<_>>())?,
+            _ => Tensor::from_shape(&shape, &data)?,
+        };
+        let outputs = self.plan.run(tvec!(input.into()))?;
+        let values = outputs[0].cast_to::<f64>()?.as_slice::<f64>()?.to_vec();
+        let stride = output_stride(values.len(), rows.len())?;
+        Ok((values, stride))
+    }
+}
+
+#[cfg(feature = "tract")]
+pub struct TractClient {
+    // plans run through a shared reference; reloads swap the whole model
+    model: Mutex<Arc<Model>>,
+    config: OnnxConfig,
+    file: ModelFile,
+}
+
+#[cfg(feature = "tract")]
+impl TractClient {
+    /// Load the model at `model_path`; only the feature checks and warm-up
+    /// settings of `config` apply
+    pub fn with_config(model_path: &Path, config: &OnnxConfig) -> Result<Self> {
+        let stamp = FileStamp::of(model_path);
+        let model = Model::load(model_path, config)?;
+        let warmup = model.warm_up(config).map_err(|e| anyhow!("model {}: {:#}", model_path.display(), e))?;
+        Ok(Self {
+            model: Mutex::new(Arc::new(model)),
+            config: config.clone(),
+            file: ModelFile::new(model_path, stamp, warmup),
+        })
+    }
+
+    fn model(&self) -> Arc<Model> {
+        self.model.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Load the model file again, warm it up and swap it in; inferences
+    /// already running finish on the old model. On error the old model stays.
+    pub fn reload(&self) -> Result<()> {
+        let path = self.file.path();
+        let stamp = FileStamp::of(path);
+        let model = Model::load(path, &self.config)?;
+        let warmup = model.warm_up(&self.config).map_err(|e| anyhow!("model {}: {:#}", path.display(), e))?;
+        *self.model.lock().unwrap_or_else(|e| e.into_inner()) = Arc::new(model);
+        self.file.loaded(stamp, warmup);
+        Ok(())
+    }
+
+    /// Features per inference the model expects, if fixed
+    pub fn feature_count(&self) -> Option<usize> {
+        let model = self.model();
+        model.scaler.as_ref().map(FeatureScaler::len).or(model.width)
+    }
+
+    /// Score `features`, clamped to [-1, 1]
+    pub fn infer(&self, features: &[f64]) -> Result<f64> {
+        self.file.metrics().measure(1, || Ok(self.model().run(&[features])?[0]))
+    }
+
+    /// Score every row in one run when the model has a dynamic batch
+    /// dimension; other models are run once per row
+    pub fn infer_batch(&self, rows: &[Vec<f64>]) -> Result<Vec<f64>> {
+        let rows: Vec<&[f64]> = rows.iter().map(Vec::as_slice).collect();
+        self.file.metrics().measure(rows.len(), || {
+            let model = self.model();
+            if model.batched || rows.len() <= 1 {
+                return model.run(&rows);
+            }
+            rows.iter().map(|row| Ok(model.run(&[row])?[0])).collect()
+        })
+    }
+
+    /// Top class for `features` from a classification model (None for a
+    /// model without labels)
+    pub fn classify(&self, features: &[f64]) -> Result<Option<Classification>> {
+        let model = self.model();
+        if model.labels.is_none() {
+            return Ok(None);
+        }
+        self.file.metrics().measure(1, || model.classify(features))
+    }
+}
+
+#[cfg(not(feature = "tract"))]
+/// Stub when the `tract` feature is not enabled
+pub struct TractClient {
+    file: ModelFile,
+}
+
+#[cfg(not(feature = "tract"))]
+impl TractClient {
+    pub fn with_config(model_path: &Path, _config: &OnnxConfig) -> Result<Self> {
+        anyhow::bail!("cannot load {} with tract: built without the tract feature", model_path.display())
+    }
+
+    pub fn reload(&self) -> Result<()> {
+        anyhow::bail!("built without the tract feature")
+    }
+
+    pub fn feature_count(&self) -> Option<usize> {
+        None
+    }
+
+    pub fn infer(&self, _features: &[f64]) -> Result<f64> {
+        anyhow::bail!("built without the tract feature")
+    }
+
+    pub fn infer_batch(&self, _rows: &[Vec<f64>]) -> Result<Vec<f64>> {
+        anyhow::bail!("built without the tract feature")
+    }
+
+    pub fn classify(&self, _features: &[f64]) -> Result<Option<Classification>> {
+        Ok(None)
+    }
+}
+
+impl TractClient {
+    pub fn path(&self) -> &Path {
+        self.file.path()
+    }
+
+    /// Calls, errors, throughput and latency since the client was created
+    pub fn stats(&self) -> ModelStats {
+        self.file.stats()
+    }
+
+    /// Warm-up timing from the last (re)load, if one ran
+    pub fn warmup(&self) -> Option<WarmupReport> {
+        self.file.warmup()
+    }
+
+    /// Reload when the model file was replaced since it was last loaded (see
+    /// [`ModelFile::reload_if_changed`])
+    pub fn reload_if_changed(&self) -> Result<bool> {
+        self.file.reload_if_changed(|| self.reload())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(not(feature = "tract"))]
+    #[test]
+    fn test_stub_refuses_to_load() {
+        let err = TractClient::with_config(Path::new("model.onnx"), &OnnxConfig::default()).err().unwrap();
+        assert_eq!(err.to_string(), "cannot load model.onnx with tract: built without the tract feature");
+    }
+
+    #[cfg(feature = "tract")]
+    #[test]
+    fn test_missing_model_file() {
+        let err = TractClient::with_config(Path::new("missing.onnx"), &OnnxConfig::default()).err().unwrap();
+        assert!(err.to_string().contains("not found"));
+    }
+
+    /// `[N, 3] x [3, 1]` MatMul averaging the features, recording `schema`
+    #[cfg(feature = "tract")]
+    fn write_mean_model(path: &Path, schema: &str) {
+        use prost::Message;
+        use tract_onnx::pb::{self, tensor_shape_proto::dimension::Value as Dim, type_proto};
+
+        let value_info = |name: &str, dims: [Dim; 2]| pb::ValueInfoProto {
+            name: name.to_string(),
+            r#type: Some(pb::TypeProto {
+                value: Some(type_proto::Value::TensorType(type_proto::Tensor {
+                    elem_type: pb::tensor_proto::DataType::Float as i32,
+                    shape: Some(pb::TensorShapeProto {
+                        dim: dims
+                            .into_iter()
+                            .map(|d| pb::tensor_shape_proto::Dimension { value: Some(d), ..Default::default() })
+                            .collect(),
+                    }),
+                })),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let model = pb::ModelProto {
+            ir_version: 7,
+            opset_import: vec![pb::OperatorSetIdProto { version: 13, ..Default::default() }],
+            graph: Some(pb::GraphProto {
+                node: vec![pb::NodeProto {
+                    input: vec!["x".to_string(), "w".to_string()],
+                    output: vec!["y".to_string()],
+                    op_type: "MatMul".to_string(),
+                    ..Default::default()
+                }],
+                initializer: vec![pb::TensorProto {
+                    name: "w".to_string(),
+                    dims: vec![3, 1],
+                    data_type: pb::tensor_proto::DataType::Float as i32,
+                    float_data: vec![1.0 / 3.0; 3],
+                    ..Default::default()
+                }],
+                input: vec![value_info("x", [Dim::DimParam("N".to_string()), Dim::DimValue(3)])],
+                output: vec![value_info("y", [Dim::DimParam("N".to_string()), Dim::DimValue(1)])],
+                ..Default::default()
+            }),
+            metadata_props: vec![pb::StringStringEntryProto { key: "feature_schema".to_string(), value: schema.to_string() }],
+            ..Default::default()
+        };
+        std::fs::write(path, model.encode_to_vec()).unwrap();
+    }
+
+    #[cfg(feature = "tract")]
+    #[test]
+    fn test_runs_onnx_model() {
+        use crate::features::FeatureSchema;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("mean.onnx");
+        let schema = FeatureSchema::new(["a", "b", "c"]).unwrap();
+        write_mean_model(&path, &schema.hash());
+        let client = TractClient::with_config(&path, &OnnxConfig::default().with_feature_schema(&schema)).unwrap();
+        assert_eq!(client.feature_count(), Some(3));
+        assert_eq!(client.warmup().map(|w| w.widths), Some(vec![3]));
+        assert!((client.infer(&[0.3, 0.6, 0.0]).unwrap() - 0.3).abs() < 1e-6);
+        let scores = client.infer_batch(&[vec![0.3, 0.6, 0.0], vec![3.0, 3.0, 3.0]]).unwrap();
+        assert!((scores[0] - 0.3).abs() < 1e-6);
+        assert_eq!(scores[1], 1.0);
+        assert!(client.infer(&[0.1, 0.2]).is_err());
+        let stats = client.stats();
+        assert_eq!((stats.calls, stats.rows, stats.errors), (3, 4, 1));
+
+        // refused when the engine sends another layout
+        let other = OnnxConfig::default().with_feature_schema(&FeatureSchema::new(["a", "b", "d"]).unwrap());
+        assert!(TractClient::with_config(&path, &other).is_err());
+        let wider = OnnxConfig::default().with_feature_widths([8]);
+        assert!(TractClient::with_config(&path, &wider).is_err());
+    }
+}
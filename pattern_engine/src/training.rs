@@ -0,0 +1,424 @@
+//! Training-data export.
+//!
+//! Every feature vector the engine sends to a model is logged with the
+//! signal it produced and, once the horizon has passed, the realized forward
+//! return, so models can be retrained on exactly what the engine computes.
+//! A row is held until the first traded price at or after `timestamp +
+//! horizon` and then written with `forward_return = (price - entry) /
+//! entry`, where the entry is the symbol's last price when the signal was
+//! recorded. Once `max_pending` rows are held, each new one pushes out the
+//! oldest row of the busiest symbol, written without a return.
+//!
+//! Holding and resolving rows happens in memory; both return the rows that
+//! are ready, and [`TrainingExport::write`] and [`TrainingExport::flush`] do
+//! the blocking file IO, so async callers run them on the blocking pool.
+//!
+//! As JSON lines, rows are appended to `<dir>/training-<date>.jsonl` per UTC
+//! day. As Parquet (`parquet` feature) they are partitioned like the Parquet
+//! sink, `<dir>/training/date=<date>/symbol=<symbol>/`, with one `f_<name>`
+//! column per feature, written every `batch_rows` rows and on
+//! [`TrainingExport::flush`]. Rows still waiting for their horizon at
+//! shutdown are lost.
+
+use crate::features::FeatureSchema;
+use crate::parquet_sink::partition_dir;
+use crate::patterns::stats::ForwardReturns;
+use crate::patterns::PatternMeta;
+use crate::publisher::Signal;
+use anyhow::{anyhow, bail, Result};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrainingFormat {
+    Jsonl,
+    Parquet,
+}
+
+impl FromStr for TrainingFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "jsonl" | "json" => Ok(TrainingFormat::Jsonl),
+            "parquet" => Ok(TrainingFormat::Parquet),
+            other => Err(anyhow!("unknown training export format: {} (expected jsonl or parquet)", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TrainingExportConfig {
+    pub dir: PathBuf,
+    pub format: TrainingFormat,
+    /// How far ahead the forward return is measured
+    pub horizon: Duration,
+    /// Parquet rows buffered per partition before it is written
+    pub batch_rows: usize,
+    /// Rows held for their horizon before older ones are written without one
+    pub max_pending: usize,
+}
+
+impl TrainingExportConfig {
+    pub fn new(dir: impl Into<PathBuf>, format: TrainingFormat) -> Self {
+        Self { dir: dir.into(), format, horizon: Duration::from_secs(300), batch_rows: 10_000, max_pending: 100_000 }
+    }
+}
+
+/// One model input and what came of it
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TrainingRow {
+    pub signal_id: String,
+    pub timestamp: f64,
+    pub symbol: String,
+    pub pattern: String,
+    pub model_id: Option<String>,
+    /// Model score, the inferred pattern's polarity
+    pub model_score: f64,
+    pub signal_score: f64,
+    pub action: String,
+    pub confidence: f64,
+    /// [`FeatureSchema::hash`] of `feature_names`
+    pub feature_schema: String,
+    pub feature_names: Vec<String>,
+    pub features: Vec<f64>,
+    pub entry_price: Option<f64>,
+    pub horizon_secs: f64,
+    pub forward_return: Option<f64>,
+}
+
+impl TrainingRow {
+    /// Row for `signal`, inferred as `meta` from `features` named `names`
+    pub fn new(signal: &Signal, meta: &PatternMeta, names: &[&str], features: &[f64]) -> Result<Self> {
+        if names.len() != features.len() {
+            bail!("{} feature names for {} features", names.len(), features.len());
+        }
+        Ok(Self {
+            signal_id: signal.id.clone(),
+            timestamp: signal.timestamp,
+            symbol: signal.symbol.clone(),
+            pattern: signal.pattern.clone(),
+            model_id: meta.model_id.clone(),
+            model_score: meta.polarity,
+            signal_score: signal.score,
+            action: meta.action.clone(),
+            confidence: meta.confidence,
+            feature_schema: FeatureSchema::new(names.iter().copied())?.hash(),
+            feature_names: names.iter().map(|n| n.to_string()).collect(),
+            features: features.to_vec(),
+            entry_price: None,
+            horizon_secs: 0.0,
+            forward_return: None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct TrainingExportStats {
+    pub recorded: u64,
+    pub written: u64,
+    /// Rows waiting for their horizon
+    pub pending: usize,
+    pub failed_writes: u64,
+}
+
+#[derive(Default)]
+struct State {
+    /// Rows waiting for their horizon
+    pending: ForwardReturns<TrainingRow>,
+    /// Parquet rows waiting for their partition to fill
+    partitions: HashMap<PathBuf, Vec<TrainingRow>>,
+}
+
+pub struct TrainingExport {
+    config: TrainingExportConfig,
+    state: Mutex<State>,
+    #[cfg(feature = "parquet")]
+    seq: AtomicU64,
+    recorded: AtomicU64,
+    written: AtomicU64,
+    failed_writes: AtomicU64,
+}
+
+impl TrainingExport {
+    pub fn open(config: TrainingExportConfig) -> Result<Self> {
+        if config.format == TrainingFormat::Parquet && !cfg!(feature = "parquet") {
+            bail!("cannot export training data as parquet to {}: built without the parquet feature", config.dir.display());
+        }
+        std::fs::create_dir_all(&config.dir).map_err(|e| anyhow!("failed to create {}: {}", config.dir.display(), e))?;
+        Ok(Self {
+            config,
+            state: Mutex::new(State::default()),
+            #[cfg(feature = "parquet")]
+            seq: AtomicU64::new(0),
+            recorded: AtomicU64::new(0),
+            written: AtomicU64::new(0),
+            failed_writes: AtomicU64::new(0),
+        })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.config.dir
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Hold `row` until its forward return is known. Returns the row pushed
+    /// out when `max_pending` rows are already held.
+    pub fn record(&self, mut row: TrainingRow) -> Vec<TrainingRow> {
+        self.recorded.fetch_add(1, Ordering::Relaxed);
+        let mut state = self.lock();
+        row.entry_price = state.pending.last_price(&row.symbol);
+        row.horizon_secs = self.config.horizon.as_secs_f64();
+        let (full, symbol) = (state.pending.len() >= self.config.max_pending, row.symbol.clone());
+        state.pending.push(&symbol, row.timestamp, row);
+        if full {
+            state.pending.evict_busiest().into_iter().collect()
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Feed a traded price; returns the rows whose horizon has elapsed
+    pub fn on_price(&self, symbol: &str, price: f64, timestamp: f64) -> Vec<TrainingRow> {
+        let horizon = self.config.horizon.as_secs_f64();
+        self.lock().pending.on_price(symbol, price, timestamp, horizon, |row, price, _| {
+            row.forward_return = row.entry_price.filter(|p| *p > 0.0).map(|entry| (price - entry) / entry);
+            true
+        })
+    }
+
+    /// Write buffered Parquet partitions
+    pub fn flush(&self) -> Result<()> {
+        let partitions = std::mem::take(&mut self.lock().partitions);
+        let mut result = Ok(());
+        for (dir, rows) in partitions {
+            if let Err(e) = self.write_parquet(&dir, &rows) {
+                result = Err(e);
+            }
+        }
+        result
+    }
+
+    pub fn stats(&self) -> TrainingExportStats {
+        TrainingExportStats {
+            recorded: self.recorded.load(Ordering::Relaxed),
+            written: self.written.load(Ordering::Relaxed),
+            pending: self.lock().pending.len(),
+            failed_writes: self.failed_writes.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Write rows returned by [`TrainingExport::record`] and
+    /// [`TrainingExport::on_price`]; Parquet rows are buffered per partition
+    pub fn write(&self, rows: Vec<TrainingRow>) -> Result<()> {
+        if rows.is_empty() {
+            return Ok(());
+        }
+        match self.config.format {
+            TrainingFormat::Jsonl => {
+                let mut by_file: HashMap<PathBuf, Vec<TrainingRow>> = HashMap::new();
+                for row in rows {
+                    by_file.entry(jsonl_path(&self.config.dir, row.timestamp)).or_default().push(row);
+                }
+                let mut result = Ok(());
+                for (path, rows) in by_file {
+                    let written = append_jsonl(&path, &rows);
+                    if let Err(e) = self.count(written, rows.len()) {
+                        result = Err(e);
+                    }
+                }
+                result
+            }
+            TrainingFormat::Parquet => {
+                let mut full = Vec::new();
+                {
+                    let mut state = self.lock();
+                    for row in rows {
+                        let dir = partition_dir(&self.config.dir, "training", &row.symbol, row.timestamp);
+                        let buffered = state.partitions.entry(dir.clone()).or_default();
+                        buffered.push(row);
+                        if buffered.len() >= self.config.batch_rows {
+                            full.push((dir, std::mem::take(buffered)));
+                        }
+                    }
+                }
+                let mut result = Ok(());
+                for (dir, rows) in full {
+                    if let Err(e) = self.write_parquet(&dir, &rows) {
+                        result = Err(e);
+                    }
+                }
+                result
+            }
+        }
+    }
+
+    fn count(&self, written: Result<()>, rows: usize) -> Result<()> {
+        match &written {
+            Ok(()) => self.written.fetch_add(rows as u64, Ordering::Relaxed),
+            Err(_) => self.failed_writes.fetch_add(1, Ordering::Relaxed),
+        };
+        written
+    }
+
+    #[cfg(feature = "parquet")]
+    fn write_parquet(&self, dir: &Path, rows: &[TrainingRow]) -> Result<()> {
+        let written = training_batch(rows)
+            .and_then(|batch| crate::parquet_sink::write_batch(dir, self.seq.fetch_add(1, Ordering::Relaxed), &batch));
+        self.count(written, rows.len())
+    }
+
+    #[cfg(not(feature = "parquet"))]
+    fn write_parquet(&self, _dir: &Path, _rows: &[TrainingRow]) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// JSON lines file for rows on the UTC day of `timestamp`
+fn jsonl_path(dir: &Path, timestamp: f64) -> PathBuf {
+    let date = chrono::DateTime::from_timestamp(timestamp.floor() as i64, 0)
+        .map(|t| t.format("%Y-%m-%d").to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+    dir.join(format!("training-{}.jsonl", date))
+}
+
+fn append_jsonl(path: &Path, rows: &[TrainingRow]) -> Result<()> {
+    let mut out = Vec::new();
+    for row in rows {
+        serde_json::to_writer(&mut out, row)?;
+        out.push(b'\n');
+    }
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| anyhow!("failed to open {}: {}", path.display(), e))?;
+    file.write_all(&out)?;
+    Ok(())
+}
+
+#[cfg(feature = "parquet")]
+fn training_batch(rows: &[TrainingRow]) -> Result<arrow_array::RecordBatch> {
+    use crate::parquet_sink::{floats, strings};
+    use arrow_schema::{DataType, Field, Schema};
+
+    let mut names: Vec<&str> = Vec::new();
+    for name in rows.iter().flat_map(|r| &r.feature_names) {
+        if !names.contains(&name.as_str()) {
+            names.push(name);
+        }
+    }
+    let mut fields = vec![
+        Field::new("signal_id", DataType::Utf8, false),
+        Field::new("timestamp", DataType::Float64, false),
+        Field::new("symbol", DataType::Utf8, false),
+        Field::new("pattern", DataType::Utf8, false),
+        Field::new("model_id", DataType::Utf8, true),
+        Field::new("model_score", DataType::Float64, false),
+        Field::new("signal_score", DataType::Float64, false),
+        Field::new("action", DataType::Utf8, false),
+        Field::new("confidence", DataType::Float64, false),
+        Field::new("feature_schema", DataType::Utf8, false),
+        Field::new("entry_price", DataType::Float64, true),
+        Field::new("horizon_secs", DataType::Float64, false),
+        Field::new("forward_return", DataType::Float64, true),
+    ];
+    let mut columns = vec![
+        strings(rows.iter().map(|r| Some(r.signal_id.as_str()))),
+        floats(rows.iter().map(|r| Some(r.timestamp))),
+        strings(rows.iter().map(|r| Some(r.symbol.as_str()))),
+        strings(rows.iter().map(|r| Some(r.pattern.as_str()))),
+        strings(rows.iter().map(|r| r.model_id.as_deref())),
+        floats(rows.iter().map(|r| Some(r.model_score))),
+        floats(rows.iter().map(|r| Some(r.signal_score))),
+        strings(rows.iter().map(|r| Some(r.action.as_str()))),
+        floats(rows.iter().map(|r| Some(r.confidence))),
+        strings(rows.iter().map(|r| Some(r.feature_schema.as_str()))),
+        floats(rows.iter().map(|r| r.entry_price)),
+        floats(rows.iter().map(|r| Some(r.horizon_secs))),
+        floats(rows.iter().map(|r| r.forward_return)),
+    ];
+    for name in names {
+        fields.push(Field::new(format!("f_{}", name), DataType::Float64, true));
+        columns.push(floats(rows.iter().map(|r| {
+            r.feature_names.iter().position(|n| n == name).map(|i| r.features[i])
+        })));
+    }
+    Ok(arrow_array::RecordBatch::try_new(std::sync::Arc::new(Schema::new(fields)), columns)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::patterns::PatternLibrary;
+
+    fn row(symbol: &str, timestamp: f64) -> TrainingRow {
+        let meta = PatternLibrary::stub_meta("mystery", &[0.5, 0.3]);
+        TrainingRow::new(&Signal::test(symbol, "mystery", 0.4, timestamp), &meta, &["ema_diff", "volume_ratio"], &[0.5, 0.3]).unwrap()
+    }
+
+    #[test]
+    fn test_rows_written_with_forward_return() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = TrainingExportConfig { horizon: Duration::from_secs(60), ..TrainingExportConfig::new(dir.path(), TrainingFormat::Jsonl) };
+        let export = TrainingExport::open(config).unwrap();
+        // 2024-03-01T00:00:00Z
+        let t0 = 1_709_251_200.0;
+        assert!(export.on_price("AAPL", 100.0, t0).is_empty());
+        assert!(export.record(row("AAPL", t0)).is_empty());
+        assert!(export.record(row("MSFT", t0)).is_empty());
+        assert!(export.on_price("AAPL", 101.0, t0 + 30.0).is_empty());
+        assert_eq!(export.stats().pending, 2);
+        // nothing reaches disk until the ready rows are written
+        let aapl = export.on_price("AAPL", 102.0, t0 + 60.0);
+        assert_eq!(aapl.len(), 1);
+        assert!(!dir.path().join("training-2024-03-01.jsonl").exists());
+        export.write(aapl).unwrap();
+        export.write(export.on_price("MSFT", 50.0, t0 + 61.0)).unwrap();
+        let stats = export.stats();
+        assert_eq!((stats.recorded, stats.written, stats.pending), (2, 2, 0));
+
+        let text = std::fs::read_to_string(dir.path().join("training-2024-03-01.jsonl")).unwrap();
+        let rows: Vec<serde_json::Value> = text.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+        assert_eq!(rows[0]["symbol"], "AAPL");
+        assert_eq!(rows[0]["entry_price"], 100.0);
+        assert!((rows[0]["forward_return"].as_f64().unwrap() - 0.02).abs() < 1e-12);
+        assert_eq!(rows[0]["feature_names"], serde_json::json!(["ema_diff", "volume_ratio"]));
+        assert_eq!(rows[0]["feature_schema"], FeatureSchema::new(["ema_diff", "volume_ratio"]).unwrap().hash());
+        // no price when it was recorded: written without a return
+        assert!(rows[1]["forward_return"].is_null());
+    }
+
+    #[test]
+    fn test_overflow_writes_oldest_early() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = TrainingExportConfig { max_pending: 2, ..TrainingExportConfig::new(dir.path(), TrainingFormat::Jsonl) };
+        let export = TrainingExport::open(config).unwrap();
+        for i in 0..3 {
+            export.write(export.record(row("AAPL", 1_709_251_200.0 + i as f64))).unwrap();
+        }
+        let stats = export.stats();
+        assert_eq!((stats.written, stats.pending), (1, 2));
+        assert!(TrainingRow::new(&Signal::test("AAPL", "mystery", 0.4, 0.0), &PatternLibrary::stub_meta("x", &[]), &["a"], &[]).is_err());
+        assert_eq!("Parquet".parse::<TrainingFormat>().unwrap(), TrainingFormat::Parquet);
+        assert!("csv".parse::<TrainingFormat>().is_err());
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn test_parquet_columns_per_feature() {
+        let batch = training_batch(&[row("AAPL", 0.0)]).unwrap();
+        let schema = batch.schema();
+        assert_eq!(schema.field(schema.fields().len() - 1).name(), "f_volume_ratio");
+        assert_eq!(batch.num_rows(), 1);
+    }
+}
@@ -0,0 +1,389 @@
+//! Symbol universe configuration.
+//!
+//! Holds per-symbol reference data (exchange, asset class, tier) and optional
+//! detection threshold overrides. The universe can be bulk imported from JSON
+//! or CSV and exported with effective (default + override) thresholds.
+
+use crate::config::{self, check_range};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::ops::RangeInclusive;
+
+/// Detection thresholds used by the per-symbol detector
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DetectionThresholds {
+    /// Relative fast/slow EMA difference for `ema_crossover` (accepts `1%`)
+    #[serde(deserialize_with = "config::deserialize_fraction")]
+    pub ema_diff: f64,
+    /// Relative price deviation from VWAP for `vwap_deviation` (accepts `0.5%`)
+    #[serde(deserialize_with = "config::deserialize_fraction")]
+    pub vwap_deviation: f64,
+    /// Volume multiple of average for `volume_spike`
+    pub volume_ratio: f64,
+    /// Minimum absolute score before a signal is emitted
+    pub min_score: f64,
+    /// Seconds between signals for the same symbol (accepts `30s`, `5m`)
+    #[serde(deserialize_with = "config::deserialize_secs")]
+    pub cooldown_secs: f64,
+}
+
+impl Default for DetectionThresholds {
+    fn default() -> Self {
+        Self {
+            ema_diff: 0.01,
+            vwap_deviation: 0.005,
+            volume_ratio: 2.0,
+            min_score: 0.3,
+            cooldown_secs: 30.0,
+        }
+    }
+}
+
+/// Optional per-symbol overrides of [`DetectionThresholds`]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ThresholdOverrides {
+    #[serde(default, skip_serializing_if = "Option::is_none", deserialize_with = "config::deserialize_opt_fraction")]
+    pub ema_diff: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none", deserialize_with = "config::deserialize_opt_fraction")]
+    pub vwap_deviation: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub volume_ratio: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_score: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none", deserialize_with = "config::deserialize_opt_secs")]
+    pub cooldown_secs: Option<f64>,
+}
+
+impl ThresholdOverrides {
+    /// Apply the overrides on top of `base`
+    pub fn apply(&self, base: &DetectionThresholds) -> DetectionThresholds {
+        DetectionThresholds {
+            ema_diff: self.ema_diff.unwrap_or(base.ema_diff),
+            vwap_deviation: self.vwap_deviation.unwrap_or(base.vwap_deviation),
+            volume_ratio: self.volume_ratio.unwrap_or(base.volume_ratio),
+            min_score: self.min_score.unwrap_or(base.min_score),
+            cooldown_secs: self.cooldown_secs.unwrap_or(base.cooldown_secs),
+        }
+    }
+
+    /// Range-check every configured override
+    pub fn validate(&self) -> Result<()> {
+        let checks: [(&str, Option<f64>, RangeInclusive<f64>); 5] = [
+            ("ema_diff", self.ema_diff, 0.0..=1.0),
+            ("vwap_deviation", self.vwap_deviation, 0.0..=1.0),
+            ("volume_ratio", self.volume_ratio, 0.0..=1000.0),
+            ("min_score", self.min_score, 0.0..=1.0),
+            ("cooldown_secs", self.cooldown_secs, 0.0..=86_400.0),
+        ];
+        for (name, value, range) in checks {
+            if let Some(v) = value {
+                check_range(name, v, &range)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// One symbol entry as imported
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SymbolConfig {
+    pub symbol: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exchange: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub asset_class: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tier: Option<u32>,
+    #[serde(default)]
+    pub thresholds: ThresholdOverrides,
+}
+
+impl SymbolConfig {
+    fn bare(symbol: &str) -> Self {
+        Self {
+            symbol: symbol.to_string(),
+            exchange: None,
+            asset_class: None,
+            tier: None,
+            thresholds: ThresholdOverrides::default(),
+        }
+    }
+}
+
+/// Exported view of a symbol with effective thresholds
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EffectiveSymbol {
+    pub symbol: String,
+    pub exchange: Option<String>,
+    pub asset_class: Option<String>,
+    pub tier: Option<u32>,
+    /// Thresholds in effect (defaults with overrides applied)
+    pub thresholds: DetectionThresholds,
+    /// Raw overrides configured for this symbol
+    pub overrides: ThresholdOverrides,
+}
+
+/// How an import combines with the existing universe
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportMode {
+    /// Upsert imported symbols, keep the rest
+    #[default]
+    Merge,
+    /// Replace the whole universe with the import
+    Replace,
+}
+
+/// CSV column order for import and export
+pub const CSV_HEADER: &str = "symbol,exchange,asset_class,tier,ema_diff,vwap_deviation,volume_ratio,min_score,cooldown_secs";
+
+/// The configured symbol universe
+#[derive(Debug, Clone, Default)]
+pub struct Universe {
+    defaults: DetectionThresholds,
+    symbols: BTreeMap<String, SymbolConfig>,
+}
+
+impl Universe {
+    pub fn new(defaults: DetectionThresholds) -> Self {
+        Self {
+            defaults,
+            symbols: BTreeMap::new(),
+        }
+    }
+
+    /// Default thresholds for symbols without overrides
+    pub fn defaults(&self) -> &DetectionThresholds {
+        &self.defaults
+    }
+
+    /// Configured entry for a symbol, if any
+    pub fn get(&self, symbol: &str) -> Option<&SymbolConfig> {
+        self.symbols.get(symbol)
+    }
+
+    /// Number of configured symbols
+    pub fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+
+    /// Effective thresholds for a symbol
+    pub fn thresholds_for(&self, symbol: &str) -> DetectionThresholds {
+        match self.symbols.get(symbol) {
+            Some(cfg) => cfg.thresholds.apply(&self.defaults),
+            None => self.defaults.clone(),
+        }
+    }
+
+    /// Import entries according to `mode`; returns the number of entries applied
+    pub fn import(&mut self, entries: Vec<SymbolConfig>, mode: ImportMode) -> usize {
+        if mode == ImportMode::Replace {
+            self.symbols.clear();
+        }
+        let n = entries.len();
+        for entry in entries {
+            self.symbols.insert(entry.symbol.clone(), entry);
+        }
+        n
+    }
+
+    /// Export the effective universe. `extra` symbols (e.g. currently active
+    /// ones) that are not configured are included with default thresholds.
+    pub fn export<'a>(&self, extra: impl IntoIterator<Item = &'a str>) -> Vec<EffectiveSymbol> {
+        let mut all: BTreeMap<&str, SymbolConfig> = self
+            .symbols
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.clone()))
+            .collect();
+        for sym in extra {
+            all.entry(sym).or_insert_with(|| SymbolConfig::bare(sym));
+        }
+        all.into_values()
+            .map(|cfg| EffectiveSymbol {
+                thresholds: cfg.thresholds.apply(&self.defaults),
+                symbol: cfg.symbol,
+                exchange: cfg.exchange,
+                asset_class: cfg.asset_class,
+                tier: cfg.tier,
+                overrides: cfg.thresholds,
+            })
+            .collect()
+    }
+}
+
+/// Parse a JSON universe: either an array of entries or `{"symbols": [...]}`
+pub fn parse_json(data: &str) -> Result<Vec<SymbolConfig>> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Doc {
+        List(Vec<SymbolConfig>),
+        Wrapped { symbols: Vec<SymbolConfig> },
+    }
+    let entries = match serde_json::from_str::<Doc>(data)? {
+        Doc::List(v) => v,
+        Doc::Wrapped { symbols } => symbols,
+    };
+    validate(entries)
+}
+
+/// Parse a CSV universe. The header row is required and columns are matched
+/// by name, so any subset/order of [`CSV_HEADER`] columns is accepted.
+pub fn parse_csv(data: &str) -> Result<Vec<SymbolConfig>> {
+    let mut lines = data.lines().map(str::trim).filter(|l| !l.is_empty());
+    let header: Vec<String> = lines
+        .next()
+        .ok_or_else(|| anyhow!("empty csv"))?
+        .split(',')
+        .map(|h| h.trim().to_ascii_lowercase())
+        .collect();
+    if !header.iter().any(|h| h == "symbol") {
+        return Err(anyhow!("csv header must contain a symbol column"));
+    }
+
+    let mut entries = Vec::new();
+    for (i, line) in lines.enumerate() {
+        let mut cfg = SymbolConfig::bare("");
+        for (col, raw) in header.iter().zip(line.split(',')) {
+            let v = raw.trim();
+            if v.is_empty() {
+                continue;
+            }
+            let num = || v.parse::<f64>().map_err(|e| anyhow!("row {}: bad {} '{}': {}", i + 1, col, v, e));
+            let fraction = || config::parse_fraction(v).map_err(|e| anyhow!("row {}: bad {}: {}", i + 1, col, e));
+            let secs = || {
+                config::parse_duration(v)
+                    .map(|d| d.as_secs_f64())
+                    .map_err(|e| anyhow!("row {}: bad {}: {}", i + 1, col, e))
+            };
+            match col.as_str() {
+                "symbol" => cfg.symbol = v.to_string(),
+                "exchange" => cfg.exchange = Some(v.to_string()),
+                "asset_class" => cfg.asset_class = Some(v.to_string()),
+                "tier" => cfg.tier = Some(v.parse().map_err(|e| anyhow!("row {}: bad tier '{}': {}", i + 1, v, e))?),
+                "ema_diff" => cfg.thresholds.ema_diff = Some(fraction()?),
+                "vwap_deviation" => cfg.thresholds.vwap_deviation = Some(fraction()?),
+                "volume_ratio" => cfg.thresholds.volume_ratio = Some(num()?),
+                "min_score" => cfg.thresholds.min_score = Some(num()?),
+                "cooldown_secs" => cfg.thresholds.cooldown_secs = Some(secs()?),
+                _ => {} // unknown columns are ignored
+            }
+        }
+        entries.push(cfg);
+    }
+    validate(entries)
+}
+
+/// Render entries as CSV with [`CSV_HEADER`] columns (overrides only)
+pub fn to_csv(entries: &[EffectiveSymbol]) -> String {
+    fn opt<T: ToString>(v: &Option<T>) -> String {
+        v.as_ref().map(|x| x.to_string()).unwrap_or_default()
+    }
+    let mut out = String::from(CSV_HEADER);
+    out.push('\n');
+    for e in entries {
+        let o = &e.overrides;
+        out.push_str(&format!(
+            "{},{},{},{},{},{},{},{},{}\n",
+            e.symbol,
+            opt(&e.exchange),
+            opt(&e.asset_class),
+            opt(&e.tier),
+            opt(&o.ema_diff),
+            opt(&o.vwap_deviation),
+            opt(&o.volume_ratio),
+            opt(&o.min_score),
+            opt(&o.cooldown_secs),
+        ));
+    }
+    out
+}
+
+fn validate(entries: Vec<SymbolConfig>) -> Result<Vec<SymbolConfig>> {
+    for (i, e) in entries.iter().enumerate() {
+        if e.symbol.trim().is_empty() {
+            return Err(anyhow!("entry {}: symbol is required", i + 1));
+        }
+        e.thresholds
+            .validate()
+            .map_err(|err| anyhow!("entry {} ({}): {}", i + 1, e.symbol, err))?;
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_import_and_effective_thresholds() {
+        let data = r#"[
+            {"symbol": "AAPL", "exchange": "NASDAQ", "asset_class": "equity", "tier": 1,
+             "thresholds": {"min_score": 0.5}},
+            {"symbol": "BTCUSD", "asset_class": "crypto"}
+        ]"#;
+        let mut uni = Universe::default();
+        assert_eq!(uni.import(parse_json(data).unwrap(), ImportMode::Merge), 2);
+
+        let aapl = uni.thresholds_for("AAPL");
+        assert_eq!(aapl.min_score, 0.5);
+        assert_eq!(aapl.cooldown_secs, DetectionThresholds::default().cooldown_secs);
+        assert_eq!(uni.thresholds_for("MSFT"), DetectionThresholds::default());
+
+        // wrapped form and replace mode
+        let wrapped = r#"{"symbols": [{"symbol": "MSFT"}]}"#;
+        uni.import(parse_json(wrapped).unwrap(), ImportMode::Replace);
+        assert_eq!(uni.len(), 1);
+        assert!(uni.get("AAPL").is_none());
+    }
+
+    #[test]
+    fn test_csv_roundtrip() {
+        let data = "symbol,tier,cooldown_secs,exchange\nAAPL,1,10,NASDAQ\nTSLA,,,\n";
+        let entries = parse_csv(data).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].tier, Some(1));
+        assert_eq!(entries[0].thresholds.cooldown_secs, Some(10.0));
+        assert_eq!(entries[1].exchange, None);
+
+        let mut uni = Universe::default();
+        uni.import(entries, ImportMode::Merge);
+        let exported = uni.export(["GOOGL"]);
+        assert_eq!(exported.len(), 3);
+        assert_eq!(exported[0].symbol, "AAPL");
+        assert_eq!(exported[0].thresholds.cooldown_secs, 10.0);
+
+        let csv = to_csv(&exported);
+        let reparsed = parse_csv(&csv).unwrap();
+        assert_eq!(reparsed[0].exchange.as_deref(), Some("NASDAQ"));
+        assert_eq!(reparsed[0].thresholds.cooldown_secs, Some(10.0));
+    }
+
+    #[test]
+    fn test_csv_rejects_bad_rows() {
+        assert!(parse_csv("exchange\nNASDAQ\n").is_err());
+        assert!(parse_csv("symbol,min_score\nAAPL,abc\n").is_err());
+        assert!(parse_csv("symbol,tier\n,1\n").is_err());
+    }
+
+    #[test]
+    fn test_units_and_ranges() {
+        let entries = parse_csv("symbol,ema_diff,cooldown_secs\nAAPL,0.5%,2m\n").unwrap();
+        assert!((entries[0].thresholds.ema_diff.unwrap() - 0.005).abs() < 1e-12);
+        assert_eq!(entries[0].thresholds.cooldown_secs, Some(120.0));
+
+        let json = parse_json(r#"[{"symbol": "AAPL", "thresholds": {"cooldown_secs": "30s", "vwap_deviation": "1%"}}]"#).unwrap();
+        assert_eq!(json[0].thresholds.cooldown_secs, Some(30.0));
+        assert_eq!(json[0].thresholds.vwap_deviation, Some(0.01));
+
+        let err = parse_csv("symbol,cooldown_secs\nAAPL,2d\n").unwrap_err().to_string();
+        assert_eq!(err, "entry 1 (AAPL): cooldown_secs: 172800 is outside 0..=86400");
+        let err = parse_csv("symbol,min_score\nAAPL,1.5\n").unwrap_err().to_string();
+        assert!(err.contains("min_score: 1.5 is outside 0..=1"), "{}", err);
+        assert!(parse_csv("symbol,cooldown_secs\nAAPL,30 parsecs\n").is_err());
+    }
+}
@@ -0,0 +1,192 @@
+//! Local write-ahead log for stream entries that could not reach Redis.
+//!
+//! Entries are appended as JSON lines to `wal.jsonl` in the log directory and
+//! synced to disk before the publish call returns. They are replayed in the
+//! order they were written, so entries for any one stream keep their order.
+//! Replay progress is kept as a byte offset in `wal.offset`; a crash during
+//! replay re-sends the uncommitted batch (at-least-once). Both files are
+//! truncated once everything has been delivered.
+
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+/// One XADD: the fields to append to `stream`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WalRecord {
+    pub stream: String,
+    pub fields: Vec<(String, String)>,
+}
+
+pub struct WriteAheadLog {
+    file: File,
+    offset_path: PathBuf,
+    /// Bytes already delivered
+    offset: u64,
+    /// Bytes written
+    len: u64,
+    /// Records not yet delivered
+    pending: usize,
+    max_bytes: u64,
+}
+
+impl WriteAheadLog {
+    /// Open or create the log in `dir`, holding at most `max_bytes` of
+    /// undelivered entries
+    pub fn open(dir: &Path, max_bytes: u64) -> Result<Self> {
+        std::fs::create_dir_all(dir).map_err(|e| anyhow!("failed to create {}: {}", dir.display(), e))?;
+        let path = dir.join("wal.jsonl");
+        let file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| anyhow!("failed to open {}: {}", path.display(), e))?;
+        let len = file.metadata()?.len();
+        let offset_path = dir.join("wal.offset");
+        let offset = match std::fs::read_to_string(&offset_path) {
+            Ok(s) => s.trim().parse::<u64>().unwrap_or(0).min(len),
+            Err(_) => 0,
+        };
+        let mut wal = Self { file, offset_path, offset, len, pending: 0, max_bytes };
+        let (records, end) = wal.read_from(offset, usize::MAX)?;
+        if end < len {
+            // drop a line torn by a crash mid-write so appends start clean
+            wal.file.set_len(end)?;
+            wal.len = end;
+        }
+        wal.pending = records.len();
+        Ok(wal)
+    }
+
+    pub fn pending(&self) -> usize {
+        self.pending
+    }
+
+    /// Size of the undelivered entries
+    pub fn pending_bytes(&self) -> u64 {
+        self.len - self.offset
+    }
+
+    /// Append `records` durably; fails without writing anything when the log
+    /// would grow past its limit
+    pub fn append<'a>(&mut self, records: impl IntoIterator<Item = &'a WalRecord>) -> Result<()> {
+        let mut buf = Vec::new();
+        let mut count = 0;
+        for record in records {
+            serde_json::to_writer(&mut buf, record)?;
+            buf.push(b'\n');
+            count += 1;
+        }
+        if self.pending_bytes() + buf.len() as u64 > self.max_bytes {
+            bail!("write-ahead log full ({} entries, {} bytes)", self.pending, self.pending_bytes());
+        }
+        self.file.write_all(&buf)?;
+        self.file.sync_data()?;
+        self.len += buf.len() as u64;
+        self.pending += count;
+        Ok(())
+    }
+
+    /// Up to `max` of the oldest undelivered records, and the position to
+    /// [`commit`](Self::commit) once they are delivered
+    pub fn next_batch(&mut self, max: usize) -> Result<(Vec<WalRecord>, u64)> {
+        self.read_from(self.offset, max)
+    }
+
+    /// Mark everything before `position` delivered
+    pub fn commit(&mut self, position: u64, delivered: usize) -> Result<()> {
+        self.offset = position.min(self.len);
+        self.pending = self.pending.saturating_sub(delivered);
+        if self.offset == self.len {
+            // fully drained: start over with empty files
+            self.file.set_len(0)?;
+            self.file.sync_data()?;
+            self.offset = 0;
+            self.len = 0;
+            self.pending = 0;
+        }
+        std::fs::write(&self.offset_path, self.offset.to_string())?;
+        Ok(())
+    }
+
+    fn read_from(&mut self, offset: u64, max: usize) -> Result<(Vec<WalRecord>, u64)> {
+        let mut reader = BufReader::new(&self.file);
+        reader.seek(SeekFrom::Start(offset))?;
+        let mut records = Vec::new();
+        let mut position = offset;
+        let mut line = String::new();
+        while records.len() < max {
+            line.clear();
+            let read = reader.read_line(&mut line)?;
+            // stop at the end, or at a line torn by a crash mid-write
+            if read == 0 || !line.ends_with('\n') {
+                break;
+            }
+            position += read as u64;
+            match serde_json::from_str(&line) {
+                Ok(record) => records.push(record),
+                Err(e) => tracing::warn!("Skipping corrupt write-ahead log entry: {}", e),
+            }
+        }
+        Ok((records, position))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(stream: &str, n: usize) -> WalRecord {
+        WalRecord { stream: stream.to_string(), fields: vec![("data".to_string(), n.to_string())] }
+    }
+
+    #[test]
+    fn test_replays_in_order_across_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut wal = WriteAheadLog::open(dir.path(), 1 << 20).unwrap();
+        let records: Vec<_> = (0..5).map(|n| record(if n % 2 == 0 { "signals" } else { "ticks" }, n)).collect();
+        wal.append(&records).unwrap();
+
+        let (batch, position) = wal.next_batch(2).unwrap();
+        assert_eq!(batch, records[..2]);
+        wal.commit(position, batch.len()).unwrap();
+        drop(wal);
+
+        // progress survives a restart
+        let mut wal = WriteAheadLog::open(dir.path(), 1 << 20).unwrap();
+        assert_eq!(wal.pending(), 3);
+        let (batch, position) = wal.next_batch(10).unwrap();
+        assert_eq!(batch, records[2..]);
+        wal.commit(position, batch.len()).unwrap();
+        assert_eq!((wal.pending(), wal.pending_bytes()), (0, 0));
+        assert_eq!(std::fs::metadata(dir.path().join("wal.jsonl")).unwrap().len(), 0);
+    }
+
+    #[test]
+    fn test_refuses_to_grow_past_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut wal = WriteAheadLog::open(dir.path(), 100).unwrap();
+        wal.append(&[record("signals", 1)]).unwrap();
+        let big: Vec<_> = (0..10).map(|n| record("signals", n)).collect();
+        assert!(wal.append(&big).is_err());
+        assert_eq!(wal.pending(), 1);
+    }
+
+    #[test]
+    fn test_ignores_torn_tail() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut wal = WriteAheadLog::open(dir.path(), 1 << 20).unwrap();
+        wal.append(&[record("signals", 1)]).unwrap();
+        drop(wal);
+        let mut file = OpenOptions::new().append(true).open(dir.path().join("wal.jsonl")).unwrap();
+        file.write_all(br#"{"stream":"sig"#).unwrap();
+
+        let mut wal = WriteAheadLog::open(dir.path(), 1 << 20).unwrap();
+        assert_eq!(wal.pending(), 1);
+        wal.append(&[record("signals", 2)]).unwrap();
+        assert_eq!(wal.next_batch(10).unwrap().0, vec![record("signals", 1), record("signals", 2)]);
+    }
+}
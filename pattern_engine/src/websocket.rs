@@ -0,0 +1,173 @@
+//! Live signal broadcast over WebSocket.
+//!
+//! Every published signal goes to a broadcast channel; each WebSocket
+//! connection forwards the signals for its symbols as JSON text frames.
+//! The initial filter comes from the `symbols` query parameter (empty or
+//! `*` for all) and clients can change it with
+//! `{"action": "subscribe" | "unsubscribe", "symbols": [...]}`.
+//! A connection that falls behind skips the missed signals and gets a
+//! `{"type": "lagged", "skipped": n}` notice instead of slowing publishing.
+
+use axum::extract::ws::{Message, WebSocket};
+use serde::Deserialize;
+use std::collections::BTreeSet;
+use std::sync::Arc;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use crate::publisher::Signal;
+
+/// Fan-out of published signals to WebSocket connections
+#[derive(Clone)]
+pub struct SignalBroadcast {
+    tx: broadcast::Sender<Arc<Signal>>,
+}
+
+impl SignalBroadcast {
+    /// `capacity` signals are buffered per connection before it lags
+    pub fn new(capacity: usize) -> Self {
+        let (tx, _) = broadcast::channel(capacity.max(1));
+        Self { tx }
+    }
+
+    /// Send to every connected client; a no-op with none connected
+    pub fn send(&self, signal: &Signal) {
+        if self.tx.receiver_count() > 0 {
+            let _ = self.tx.send(Arc::new(signal.clone()));
+        }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Arc<Signal>> {
+        self.tx.subscribe()
+    }
+
+    pub fn connections(&self) -> usize {
+        self.tx.receiver_count()
+    }
+}
+
+/// Symbols a connection wants; empty means all
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SymbolFilter {
+    symbols: BTreeSet<String>,
+}
+
+impl SymbolFilter {
+    /// `AAPL,MSFT`; empty or `*` matches everything
+    pub fn parse(spec: &str) -> Self {
+        let symbols = spec
+            .split(',')
+            .map(|s| s.trim().to_ascii_uppercase())
+            .filter(|s| !s.is_empty() && s != "*")
+            .collect();
+        Self { symbols }
+    }
+
+    pub fn matches(&self, symbol: &str) -> bool {
+        self.symbols.is_empty() || self.symbols.contains(&symbol.to_ascii_uppercase())
+    }
+
+    /// Apply a client command; unsubscribing the last symbol matches all again
+    pub fn apply(&mut self, command: &ClientCommand) {
+        match command {
+            ClientCommand::Subscribe { symbols } => {
+                self.symbols.extend(symbols.iter().map(|s| s.trim().to_ascii_uppercase()).filter(|s| !s.is_empty()))
+            }
+            ClientCommand::Unsubscribe { symbols } => {
+                for s in symbols {
+                    self.symbols.remove(&s.trim().to_ascii_uppercase());
+                }
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for SymbolFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.symbols.is_empty() {
+            return f.write_str("*");
+        }
+        let list: Vec<&str> = self.symbols.iter().map(String::as_str).collect();
+        f.write_str(&list.join(","))
+    }
+}
+
+/// Filter change sent by a client
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "action", rename_all = "lowercase")]
+pub enum ClientCommand {
+    Subscribe { symbols: Vec<String> },
+    Unsubscribe { symbols: Vec<String> },
+}
+
+/// Forward matching signals to `socket` until either side closes
+pub async fn serve_connection(mut socket: WebSocket, broadcast: SignalBroadcast, mut filter: SymbolFilter) {
+    let mut rx = broadcast.subscribe();
+    loop {
+        tokio::select! {
+            signal = rx.recv() => {
+                let text = match signal {
+                    Ok(signal) if filter.matches(&signal.symbol) => match serde_json::to_string(&*signal) {
+                        Ok(text) => text,
+                        Err(_) => continue,
+                    },
+                    Ok(_) => continue,
+                    Err(RecvError::Lagged(skipped)) => serde_json::json!({ "type": "lagged", "skipped": skipped }).to_string(),
+                    Err(RecvError::Closed) => return,
+                };
+                if socket.send(Message::Text(text)).await.is_err() {
+                    return;
+                }
+            }
+            msg = socket.recv() => match msg {
+                Some(Ok(Message::Text(text))) => match serde_json::from_str::<ClientCommand>(&text) {
+                    Ok(command) => filter.apply(&command),
+                    Err(e) => {
+                        let error = serde_json::json!({ "type": "error", "message": e.to_string() }).to_string();
+                        if socket.send(Message::Text(error)).await.is_err() {
+                            return;
+                        }
+                    }
+                },
+                Some(Ok(Message::Close(_))) | None | Some(Err(_)) => return,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter_parse_and_commands() {
+        assert!(SymbolFilter::parse("").matches("AAPL"));
+        assert!(SymbolFilter::parse("*").matches("TSLA"));
+        let mut filter = SymbolFilter::parse("aapl, MSFT");
+        assert!(filter.matches("AAPL") && filter.matches("msft") && !filter.matches("TSLA"));
+        assert_eq!(filter.to_string(), "AAPL,MSFT");
+
+        let command: ClientCommand = serde_json::from_str(r#"{"action": "subscribe", "symbols": ["tsla"]}"#).unwrap();
+        filter.apply(&command);
+        assert!(filter.matches("TSLA"));
+        filter.apply(&ClientCommand::Unsubscribe { symbols: vec!["AAPL".into(), "MSFT".into(), "TSLA".into()] });
+        assert!(filter.matches("NVDA"));
+        assert!(serde_json::from_str::<ClientCommand>(r#"{"action": "pause"}"#).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_reaches_subscribers() {
+        let broadcast = SignalBroadcast::new(2);
+        broadcast.send(&Signal::test("AAPL", "flag", 0.5, 0.0));
+        let mut rx = broadcast.subscribe();
+        assert_eq!(broadcast.connections(), 1);
+        broadcast.send(&Signal::test("MSFT", "flag", 0.5, 0.0));
+        assert_eq!(rx.recv().await.unwrap().symbol, "MSFT");
+
+        for symbol in ["A", "B", "C"] {
+            broadcast.send(&Signal::test(symbol, "flag", 0.5, 0.0));
+        }
+        assert!(matches!(rx.recv().await, Err(RecvError::Lagged(1))));
+        assert_eq!(rx.recv().await.unwrap().symbol, "B");
+    }
+}
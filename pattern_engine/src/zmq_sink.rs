@@ -0,0 +1,163 @@
+//! Optional ZeroMQ PUB sink for local low-latency consumers.
+//!
+//! Ticks and signals are published as two-frame messages: a topic and the
+//! JSON payload. Topics start with the symbol (`AAPL.tick`, `AAPL.signal`),
+//! so subscribing to `AAPL.` receives everything for AAPL and `AAPL.signal`
+//! just its signals. Publishing never blocks the tick path: messages go
+//! through a bounded queue to a dedicated socket thread and are dropped (and
+//! counted) when the queue or a subscriber's high-water mark is full.
+//!
+//! Requires the `zmq` feature; without it [`ZmqSink::bind`] returns an error.
+
+#[cfg(feature = "zmq")]
+use std::sync::atomic::{AtomicU64, Ordering};
+#[cfg(feature = "zmq")]
+use std::sync::mpsc::{sync_channel, SyncSender};
+#[cfg(feature = "zmq")]
+use std::sync::Arc;
+
+use crate::publisher::{Signal, Tick};
+
+/// Topic for a tick of `symbol`
+pub fn tick_topic(symbol: &str) -> String {
+    format!("{}.tick", symbol)
+}
+
+/// Topic for a signal on `symbol`
+pub fn signal_topic(symbol: &str) -> String {
+    format!("{}.signal", symbol)
+}
+
+#[cfg(feature = "zmq")]
+pub struct ZmqSink {
+    queue: SyncSender<(String, String)>,
+    dropped: Arc<AtomicU64>,
+    endpoint: String,
+}
+
+#[cfg(feature = "zmq")]
+impl ZmqSink {
+    /// Bind a PUB socket to `endpoint` (e.g. `tcp://127.0.0.1:5556` or
+    /// `ipc:///tmp/pattern_engine.sock`), queueing up to `queue` messages
+    pub fn bind(endpoint: &str, queue: usize) -> anyhow::Result<Self> {
+        let context = zmq::Context::new();
+        let socket = context.socket(zmq::PUB)?;
+        socket.set_sndhwm(queue.min(i32::MAX as usize) as i32)?;
+        socket.bind(endpoint)?;
+        // the resolved endpoint when binding to a wildcard port
+        let endpoint = socket.get_last_endpoint()?.unwrap_or_else(|_| endpoint.to_string());
+
+        let (tx, rx) = sync_channel::<(String, String)>(queue.max(1));
+        let dropped = Arc::new(AtomicU64::new(0));
+        let dropped_by_socket = dropped.clone();
+        std::thread::Builder::new().name("zmq-pub".to_string()).spawn(move || {
+            let _context = context;
+            for (topic, payload) in rx {
+                match socket.send_multipart([topic.as_bytes(), payload.as_bytes()], zmq::DONTWAIT) {
+                    Ok(()) => {}
+                    Err(zmq::Error::EAGAIN) => {
+                        dropped_by_socket.fetch_add(1, Ordering::Relaxed);
+                    }
+                    Err(e) => tracing::warn!("ZeroMQ publish failed: {}", e),
+                }
+            }
+        })?;
+        Ok(Self { queue: tx, dropped, endpoint })
+    }
+
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    /// Queue a tick; false when it was dropped
+    pub fn send_tick(&self, tick: &Tick) -> bool {
+        self.send(tick_topic(&tick.symbol), serde_json::to_string(tick))
+    }
+
+    /// Queue a signal; false when it was dropped
+    pub fn send_signal(&self, signal: &Signal) -> bool {
+        self.send(signal_topic(&signal.symbol), serde_json::to_string(signal))
+    }
+
+    /// Messages dropped because the queue or a subscriber was full
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    fn send(&self, topic: String, payload: serde_json::Result<String>) -> bool {
+        let sent = payload.is_ok_and(|payload| self.queue.try_send((topic, payload)).is_ok());
+        if !sent {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+        sent
+    }
+}
+
+#[cfg(not(feature = "zmq"))]
+/// Stub when the `zmq` feature is not enabled
+pub struct ZmqSink;
+
+#[cfg(not(feature = "zmq"))]
+impl ZmqSink {
+    pub fn bind(endpoint: &str, _queue: usize) -> anyhow::Result<Self> {
+        anyhow::bail!("cannot publish to {}: built without the zmq feature", endpoint)
+    }
+
+    pub fn endpoint(&self) -> &str {
+        ""
+    }
+
+    pub fn send_tick(&self, _tick: &Tick) -> bool {
+        false
+    }
+
+    pub fn send_signal(&self, _signal: &Signal) -> bool {
+        false
+    }
+
+    pub fn dropped(&self) -> u64 {
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_topics_are_symbol_prefixed() {
+        assert_eq!(tick_topic("AAPL"), "AAPL.tick");
+        assert_eq!(signal_topic("AAPL"), "AAPL.signal");
+        assert!(signal_topic("AAPL").starts_with("AAPL."));
+    }
+
+    #[cfg(not(feature = "zmq"))]
+    #[test]
+    fn test_stub_refuses_to_bind() {
+        assert!(ZmqSink::bind("tcp://127.0.0.1:5556", 16).is_err());
+    }
+
+    #[cfg(feature = "zmq")]
+    #[test]
+    fn test_subscriber_receives_symbol_topics() {
+        let sink = ZmqSink::bind("tcp://127.0.0.1:*", 16).unwrap();
+        let context = zmq::Context::new();
+        let sub = context.socket(zmq::SUB).unwrap();
+        sub.connect(sink.endpoint()).unwrap();
+        sub.set_subscribe(b"AAPL.").unwrap();
+        sub.set_rcvtimeo(100).unwrap();
+
+        let tick = |symbol: &str| Tick::test(symbol, 100.0, 0.0);
+        // keep publishing until the subscription has propagated
+        let frames = (0..50)
+            .find_map(|_| {
+                sink.send_tick(&tick("MSFT"));
+                sink.send_tick(&tick("AAPL"));
+                sub.recv_multipart(0).ok()
+            })
+            .expect("no message received");
+        assert_eq!(frames[0], b"AAPL.tick");
+        let received: Tick = serde_json::from_slice(&frames[1]).unwrap();
+        assert_eq!(received.symbol, "AAPL");
+    }
+}
@@ -54,7 +54,7 @@ fn test_replay_publish_counts_and_publish() {
         }
         let parts: Vec<&str> = l.split(',').map(|s| s.trim()).collect();
         if parts.len() < 4 { continue; }
-        let tick = Tick { symbol: parts[0].to_string(), price: parts[1].parse().unwrap_or(0.0), volume: parts[2].parse().unwrap_or(0.0), timestamp: parts[3].parse().unwrap_or(0.0) };
+        let tick = Tick { symbol: parts[0].to_string(), price: parts[1].parse().unwrap_or(0.0), volume: parts[2].parse().unwrap_or(0.0), timestamp: parts[3].parse().unwrap_or(0.0), side: None, received_at: None, feed: None, book: None };
         let mpc = mp.clone();
         rt.block_on(async { let _ = mpc.publish_tick(tick).await; });
         processed += 1;